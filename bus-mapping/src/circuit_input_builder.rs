@@ -9,25 +9,33 @@ mod input_state_ref;
 #[cfg(test)]
 mod tracer_tests;
 mod transaction;
+mod witness_bundle;
 
 use self::access::gen_state_access_trace;
 use crate::error::Error;
 use crate::evm::opcodes::{gen_associated_ops, gen_begin_tx_ops, gen_end_tx_ops};
-use crate::operation::{CallContextField, RW};
-use crate::rpc::GethClient;
+use crate::operation::{CallContextField, CallContextOp, Operation, RW};
+use crate::rpc::{BatchJsonRpcClient, BlockNumber, GethClient};
 use crate::state_db::{self, CodeDB, StateDB};
 pub use access::{Access, AccessSet, AccessValue, CodeSource};
-pub use block::{Block, BlockContext};
+pub use block::{Block, BlockContext, BlockHead};
 pub use call::{Call, CallContext, CallKind};
 use core::fmt::Debug;
 use eth_types::sign_types::{pk_bytes_le, pk_bytes_swap_endianness, SignData};
-use eth_types::{self, geth_types, Address, GethExecStep, GethExecTrace, Word};
-use ethers_providers::JsonRpcClient;
-pub use execution::{CopyDataType, CopyEvent, CopyStep, ExecState, ExecStep, NumberOrHash};
+use eth_types::{self, geth_types, Address, GethExecStep, GethExecTrace, ToWord, Word, H256};
+use ethers_core::utils::rlp::Rlp;
+pub use execution::{
+    CopyDataType, CopyEvent, CopyStep, ExecState, ExecStep, ExpEvent, ExpStep, NumberOrHash,
+};
+use futures::{stream, Future, StreamExt, TryStreamExt};
 pub use input_state_ref::CircuitInputStateRef;
 use itertools::Itertools;
-use std::collections::HashMap;
+use keccak256::EMPTY_HASH;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::ops::Range;
+use std::sync::Mutex;
 pub use transaction::{Transaction, TransactionContext};
+pub use witness_bundle::WitnessBundle;
 
 /// Builder to generate a complete circuit input from data gathered from a geth
 /// instance. This structure is the centre of the crate and is intended to be
@@ -97,18 +105,18 @@ impl<'a> CircuitInputBuilder {
     ) -> Result<Transaction, Error> {
         let call_id = self.block_ctx.rwc.0;
 
-        self.block_ctx.call_map.insert(
-            call_id,
-            (
-                eth_tx
-                    .transaction_index
-                    .ok_or(Error::EthTypeError(eth_types::Error::IncompleteBlock))?
-                    .as_u64() as usize,
-                0,
-            ),
-        );
+        self.block_ctx
+            .call_map
+            .insert(call_id, (self.block.txs.len(), 0));
 
-        Transaction::new(call_id, &self.sdb, &mut self.code_db, eth_tx, is_success)
+        Transaction::new(
+            call_id,
+            self.block.last_header().number.as_u64(),
+            &self.sdb,
+            &mut self.code_db,
+            eth_tx,
+            is_success,
+        )
     }
 
     /// Iterate over all generated CallContext RwCounterEndOfReversion
@@ -132,13 +140,80 @@ impl<'a> CircuitInputBuilder {
         }
     }
 
+    /// Add the header of `eth_block`, which must be the block following the
+    /// last one of the builder, so that its transactions are handled by the
+    /// next call to [`CircuitInputBuilder::handle_block`].  The history hashes
+    /// of the block are advanced from the ones of the previous block.
+    pub fn begin_block<TX>(&mut self, eth_block: &eth_types::Block<TX>) -> Result<(), Error> {
+        let last_header = self.block.last_header();
+        let block_num = eth_block
+            .number
+            .ok_or(Error::EthTypeError(eth_types::Error::IncompleteBlock))?
+            .as_u64();
+        if block_num != last_header.number.as_u64() + 1 {
+            return Err(Error::NonConsecutiveBlock(block_num));
+        }
+
+        let mut history_hashes = last_header.history_hashes.clone();
+        history_hashes.push(eth_block.parent_hash.to_word());
+        if history_hashes.len() > NUM_HISTORY_HASHES as usize {
+            history_hashes.remove(0);
+        }
+        self.block
+            .headers
+            .insert(block_num, BlockHead::new(history_hashes, eth_block)?);
+        self.block_ctx.cumulative_gas_used = 0;
+        Ok(())
+    }
+
+    /// Handle a sequence of consecutive blocks, the first one being the last
+    /// block of the builder, so that the StateDB, the CodeDB and the block
+    /// context are carried over from one block to the following one.
+    pub fn handle_blocks(
+        &mut self,
+        blocks: &[(EthBlock, Vec<eth_types::GethExecTrace>)],
+    ) -> Result<(), Error> {
+        for (index, (eth_block, geth_traces)) in blocks.iter().enumerate() {
+            if index > 0 {
+                self.begin_block(eth_block)?;
+            }
+            self.handle_block(eth_block, geth_traces)?;
+        }
+        Ok(())
+    }
+
     /// Handle a block by handling each transaction to generate all the
-    /// associated operations.
+    /// associated operations.  The block must be the last block of the
+    /// builder.
     pub fn handle_block(
         &mut self,
         eth_block: &EthBlock,
         geth_traces: &[eth_types::GethExecTrace],
     ) -> Result<(), Error> {
+        let block_num = eth_block
+            .number
+            .ok_or(Error::EthTypeError(eth_types::Error::IncompleteBlock))?
+            .as_u64();
+        if block_num != self.block.last_header().number.as_u64() {
+            return Err(Error::NonConsecutiveBlock(block_num));
+        }
+
+        // The EndInnerBlock step preceding the first tx of a block, except the
+        // first block, reads the id of the tx
+        let is_first_block = self.block.headers.keys().next() == Some(&block_num);
+        if !is_first_block && !eth_block.transactions.is_empty() {
+            let call_id = self.block_ctx.rwc.0 + 1;
+            self.block.container.insert(Operation::new(
+                self.block_ctx.rwc.inc_pre(),
+                RW::READ,
+                CallContextOp {
+                    call_id,
+                    field: CallContextField::TxId,
+                    value: (self.block.txs.len() + 1).into(),
+                },
+            ));
+        }
+
         // accumulates gas across all txs in the block
         for (tx_index, tx) in eth_block.transactions.iter().enumerate() {
             let geth_trace = &geth_traces[tx_index];
@@ -160,7 +235,8 @@ impl<'a> CircuitInputBuilder {
         is_last_tx: bool,
     ) -> Result<(), Error> {
         let mut tx = self.new_tx(eth_tx, !geth_trace.failed)?;
-        let mut tx_ctx = TransactionContext::new(eth_tx, geth_trace, is_last_tx)?;
+        let mut tx_ctx =
+            TransactionContext::new(self.block.txs.len() + 1, eth_tx, geth_trace, is_last_tx)?;
 
         // TODO: Move into gen_associated_steps with
         // - execution_state: BeginTx
@@ -270,16 +346,145 @@ pub fn get_call_memory_offset_length(step: &GethExecStep, nth: usize) -> Result<
 
 type EthBlock = eth_types::Block<eth_types::Transaction>;
 
+/// Number of previous block hashes accessible by the BLOCKHASH opcode
+const NUM_HISTORY_HASHES: u64 = 256;
+
+/// Default maximum number of JSON-RPC requests in flight when querying the
+/// state of a block
+pub const DEFAULT_MAX_CONCURRENT_REQUESTS: usize = 16;
+
+/// Default maximum number of requests in a JSON-RPC batch request when
+/// querying the state of a block
+pub const DEFAULT_MAX_BATCH_SIZE: usize = 100;
+
+/// Build a partial StateDB from the account proofs, and a CodeDB from the
+/// codes of the accounts.
+fn build_state_code_db(
+    proofs: Vec<eth_types::EIP1186ProofResponse>,
+    codes: HashMap<Address, Vec<u8>>,
+) -> (StateDB, CodeDB) {
+    let mut sdb = StateDB::new();
+    for proof in proofs {
+        let mut storage = HashMap::new();
+        for storage_proof in proof.storage_proof {
+            storage.insert(storage_proof.key, storage_proof.value);
+        }
+        sdb.set_account(
+            &proof.address,
+            state_db::Account {
+                nonce: proof.nonce,
+                balance: proof.balance,
+                storage,
+                code_hash: proof.code_hash,
+            },
+        )
+    }
+
+    let mut code_db = CodeDB::new();
+    for (_address, code) in codes {
+        code_db.insert(code.clone());
+    }
+    (sdb, code_db)
+}
+
+/// Return the hashes of the children of the branches with two children in
+/// the proofs, which are not nodes of the proofs themselves.  When a key of
+/// such a branch is deleted, the branch is collapsed into its other child,
+/// which must then be known to update the trie.
+fn collapse_siblings(proofs: &[eth_types::EIP1186ProofResponse]) -> Vec<H256> {
+    let nodes: Vec<&[u8]> = proofs
+        .iter()
+        .flat_map(|proof| {
+            proof.account_proof.iter().chain(
+                proof
+                    .storage_proof
+                    .iter()
+                    .flat_map(|storage_proof| storage_proof.proof.iter()),
+            )
+        })
+        .map(|node| node.as_ref())
+        .collect();
+    let known: HashSet<H256> = nodes
+        .iter()
+        .map(|node| H256(ethers_core::utils::keccak256(node)))
+        .collect();
+
+    let mut siblings = BTreeSet::new();
+    for node in nodes {
+        let node = Rlp::new(node);
+        if !matches!(node.item_count(), Ok(17)) {
+            continue;
+        }
+        let children: Vec<Rlp> = node
+            .iter()
+            .take(16)
+            .filter(|child| !child.is_empty())
+            .collect();
+        if children.len() != 2 {
+            continue;
+        }
+        // Inline children are part of the branch.
+        siblings.extend(
+            children
+                .iter()
+                .filter_map(|child| child.data().ok())
+                .filter(|data| data.len() == 32)
+                .map(H256::from_slice)
+                .filter(|hash| !known.contains(hash)),
+        );
+    }
+    siblings.into_iter().collect()
+}
+
+/// For each step in TxExecTraces, gen the associated ops and state circuit
+/// inputs, starting from the partial StateDB and CodeDB of the block.
+fn gen_inputs_from_state(
+    chain_id: Word,
+    history_hashes: Vec<Word>,
+    sdb: StateDB,
+    code_db: CodeDB,
+    eth_block: &EthBlock,
+    geth_traces: &[eth_types::GethExecTrace],
+) -> Result<CircuitInputBuilder, Error> {
+    let block = Block::new(chain_id, history_hashes, eth_block)?;
+    let mut builder = CircuitInputBuilder::new(sdb, code_db, block);
+    builder.handle_block(eth_block, geth_traces)?;
+    Ok(builder)
+}
+
+/// Hashes of the previous blocks by their number, which are cached to be
+/// reused by the following blocks.  The hashes are kept as long as they are
+/// accessible from a block which is still being built, that is, a block
+/// fetched by `get_block` whose history hashes haven't been taken yet, or
+/// otherwise from the latest fetched block.
+#[derive(Debug, Default)]
+struct HistoryHashes {
+    hashes: HashMap<u64, Word>,
+    /// Number of times each block still being built has been fetched
+    building: BTreeMap<u64, usize>,
+    latest: u64,
+}
+
+impl HistoryHashes {
+    fn evict(&mut self) {
+        let oldest = self.building.keys().next().copied().unwrap_or(self.latest);
+        let first = oldest.saturating_sub(NUM_HISTORY_HASHES);
+        self.hashes.retain(|number, _| *number >= first);
+    }
+}
+
 /// Struct that wraps a GethClient and contains methods to perform all the steps
 /// necessary to generate the circuit inputs for a block by querying geth for
 /// the necessary information and using the CircuitInputBuilder.
-pub struct BuilderClient<P: JsonRpcClient> {
+pub struct BuilderClient<P: BatchJsonRpcClient> {
     cli: GethClient<P>,
     chain_id: Word,
-    history_hashes: Vec<Word>,
+    history_hashes: Mutex<HistoryHashes>,
+    max_concurrent_requests: usize,
+    max_batch_size: usize,
 }
 
-impl<P: JsonRpcClient> BuilderClient<P> {
+impl<P: BatchJsonRpcClient> BuilderClient<P> {
     /// Create a new BuilderClient
     pub async fn new(client: GethClient<P>) -> Result<Self, Error> {
         let chain_id = client.get_chain_id().await?;
@@ -287,22 +492,138 @@ impl<P: JsonRpcClient> BuilderClient<P> {
         Ok(Self {
             cli: client,
             chain_id: chain_id.into(),
-            // TODO: Get history hashes
-            history_hashes: Vec::new(),
+            history_hashes: Mutex::new(HistoryHashes::default()),
+            max_concurrent_requests: DEFAULT_MAX_CONCURRENT_REQUESTS,
+            max_batch_size: DEFAULT_MAX_BATCH_SIZE,
         })
     }
 
-    /// Step 1. Query geth for Block, Txs and TxExecTraces
+    /// Return the client queried by the BuilderClient.
+    pub fn geth_client(&self) -> &GethClient<P> {
+        &self.cli
+    }
+
+    /// Set the maximum number of JSON-RPC requests in flight when querying
+    /// the state of a block, which must be positive.
+    pub fn with_max_concurrent_requests(
+        mut self,
+        max_concurrent_requests: usize,
+    ) -> Result<Self, Error> {
+        if max_concurrent_requests == 0 {
+            return Err(Error::InvalidArgument(
+                "the maximum number of concurrent requests must be positive",
+            ));
+        }
+        self.max_concurrent_requests = max_concurrent_requests;
+        Ok(self)
+    }
+
+    /// Set the maximum number of requests in a JSON-RPC batch request when
+    /// querying the state of a block, which must be positive.
+    pub fn with_max_batch_size(mut self, max_batch_size: usize) -> Result<Self, Error> {
+        if max_batch_size == 0 {
+            return Err(Error::InvalidArgument(
+                "the maximum batch size must be positive",
+            ));
+        }
+        self.max_batch_size = max_batch_size;
+        Ok(self)
+    }
+
+    /// Send the requests in JSON-RPC batch requests of up to the maximum
+    /// batch size, up to the maximum number of concurrent requests, and
+    /// return their responses in the same order.
+    async fn request_batches<T, R, F, Fut>(
+        &self,
+        requests: Vec<T>,
+        request_batch: F,
+    ) -> Result<Vec<R>, Error>
+    where
+        T: Clone,
+        F: Fn(Vec<T>) -> Fut,
+        Fut: Future<Output = Result<Vec<R>, Error>>,
+    {
+        let responses: Vec<Vec<R>> = stream::iter(
+            requests
+                .chunks(self.max_batch_size)
+                .map(|batch| request_batch(batch.to_vec())),
+        )
+        .buffered(self.max_concurrent_requests)
+        .try_collect()
+        .await?;
+        Ok(responses.into_iter().flatten().collect())
+    }
+
+    /// Step 1. Query geth for Block, Txs and TxExecTraces, and for the hashes
+    /// of the previous blocks accessible by the BLOCKHASH opcode.
     pub async fn get_block(
         &self,
         block_num: u64,
     ) -> Result<(EthBlock, Vec<eth_types::GethExecTrace>), Error> {
         let eth_block = self.cli.get_block_by_number(block_num.into()).await?;
         let geth_traces = self.cli.trace_block_by_number(block_num.into()).await?;
+        self.fetch_history_hashes(block_num).await?;
 
         Ok((eth_block, geth_traces))
     }
 
+    /// Query geth for the hashes of the (up to 256) blocks before `block_num`
+    /// which aren't cached yet, concurrently like in `get_state`.  The block
+    /// is then being built until its history hashes are taken, and the hashes
+    /// which aren't accessible from the blocks being built are dropped from
+    /// the cache.
+    async fn fetch_history_hashes(&self, block_num: u64) -> Result<(), Error> {
+        let first = block_num.saturating_sub(NUM_HISTORY_HASHES);
+        let missing: Vec<u64> = {
+            let history_hashes = self.history_hashes.lock().unwrap();
+            (first..block_num)
+                .filter(|number| !history_hashes.hashes.contains_key(number))
+                .collect()
+        };
+        let fetched: Vec<(u64, Word)> = stream::iter(missing)
+            .map(|number| async move {
+                let header = self.cli.get_block_header_by_number(number.into()).await?;
+                let hash = header
+                    .hash
+                    .ok_or(Error::EthTypeError(eth_types::Error::IncompleteBlock))?;
+                Ok::<_, Error>((number, hash.to_word()))
+            })
+            .buffered(self.max_concurrent_requests)
+            .try_collect()
+            .await?;
+        let mut history_hashes = self.history_hashes.lock().unwrap();
+        history_hashes.hashes.extend(fetched);
+        *history_hashes.building.entry(block_num).or_default() += 1;
+        history_hashes.latest = history_hashes.latest.max(block_num);
+        history_hashes.evict();
+        Ok(())
+    }
+
+    /// Take the hashes of the blocks before `block_num` accessible by the
+    /// BLOCKHASH opcode, from the oldest to the latest one, which must have
+    /// been fetched by `get_block`.  The block is no longer being built, so
+    /// its hashes may be dropped from the cache afterwards.
+    fn history_hashes(&self, block_num: u64) -> Result<Vec<Word>, Error> {
+        let mut history_hashes = self.history_hashes.lock().unwrap();
+        let hashes = (block_num.saturating_sub(NUM_HISTORY_HASHES)..block_num)
+            .map(|number| {
+                history_hashes
+                    .hashes
+                    .get(&number)
+                    .copied()
+                    .ok_or(Error::InternalError("history hash not fetched"))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if let Some(count) = history_hashes.building.get_mut(&block_num) {
+            *count -= 1;
+            if *count == 0 {
+                history_hashes.building.remove(&block_num);
+            }
+        }
+        history_hashes.evict();
+        Ok(hashes)
+    }
+
     /// Step 2. Get State Accesses from TxExecTraces
     pub fn get_state_accesses(
         &self,
@@ -328,7 +649,11 @@ impl<P: JsonRpcClient> BuilderClient<P> {
     }
 
     /// Step 3. Query geth for all accounts, storage keys, and codes from
-    /// Accesses
+    /// Accesses, at the state before `block_num`, together with the trie
+    /// nodes off the proofs which are needed to delete their keys (see
+    /// [`Block::nodes`]).  The requests of each kind are sent in JSON-RPC
+    /// batch requests of up to the maximum batch size of the client, and up
+    /// to its maximum number of concurrent requests are in flight.
     pub async fn get_state(
         &self,
         block_num: u64,
@@ -336,31 +661,72 @@ impl<P: JsonRpcClient> BuilderClient<P> {
     ) -> Result<
         (
             Vec<eth_types::EIP1186ProofResponse>,
+            Vec<eth_types::Bytes>,
             HashMap<Address, Vec<u8>>,
         ),
         Error,
     > {
-        let mut proofs = Vec::new();
-        for (address, key_set) in access_set.state {
-            let mut keys: Vec<Word> = key_set.iter().cloned().collect();
-            keys.sort();
-            let proof = self
-                .cli
-                .get_proof(address, keys, (block_num - 1).into())
-                .await
-                .unwrap();
-            proofs.push(proof);
-        }
-        let mut codes: HashMap<Address, Vec<u8>> = HashMap::new();
+        let prev_block_num = BlockNumber::from(block_num.checked_sub(1).ok_or(
+            Error::InvalidArgument("there is no state before the genesis block"),
+        )?);
+        let accounts: Vec<(Address, Vec<Word>)> = access_set
+            .state
+            .into_iter()
+            .map(|(address, key_set)| {
+                let mut keys: Vec<Word> = key_set.into_iter().collect();
+                keys.sort();
+                (address, keys)
+            })
+            .collect();
+        let proofs = self
+            .request_batches(accounts, |batch| self.cli.get_proofs(batch, prev_block_num))
+            .await?;
+        let nodes = self
+            .request_batches(collapse_siblings(&proofs), |batch| {
+                self.cli.get_nodes(batch)
+            })
+            .await?;
+
+        // The code of the contracts sharing the same code hash is queried once.
+        let code_hashes: HashMap<Address, H256> = proofs
+            .iter()
+            .map(|proof| (proof.address, proof.code_hash))
+            .collect();
+        let mut addresses_by_code_hash: HashMap<H256, Vec<Address>> = HashMap::new();
         for address in access_set.code {
-            let code = self
-                .cli
-                .get_code(address, (block_num - 1).into())
-                .await
-                .unwrap();
-            codes.insert(address, code);
+            let code_hash = code_hashes
+                .get(&address)
+                .ok_or(Error::AccountNotFound(address))?;
+            addresses_by_code_hash
+                .entry(*code_hash)
+                .or_default()
+                .push(address);
         }
-        Ok((proofs, codes))
+        let (with_code, without_code): (Vec<_>, Vec<_>) = addresses_by_code_hash
+            .into_iter()
+            .partition(|(code_hash, _)| {
+                !code_hash.is_zero() && code_hash.to_fixed_bytes() != *EMPTY_HASH
+            });
+        let fetched_codes = self
+            .request_batches(
+                with_code
+                    .iter()
+                    .map(|(_, addresses)| addresses[0])
+                    .collect(),
+                |batch| self.cli.get_codes(batch, prev_block_num),
+            )
+            .await?;
+        let codes = with_code
+            .into_iter()
+            .zip(fetched_codes)
+            .chain(without_code.into_iter().map(|entry| (entry, Vec::new())))
+            .flat_map(|((_, addresses), code)| {
+                addresses
+                    .into_iter()
+                    .map(move |address| (address, code.clone()))
+            })
+            .collect();
+        Ok((proofs, nodes, codes))
     }
 
     /// Step 4. Build a partial StateDB from step 3
@@ -369,28 +735,7 @@ impl<P: JsonRpcClient> BuilderClient<P> {
         proofs: Vec<eth_types::EIP1186ProofResponse>,
         codes: HashMap<Address, Vec<u8>>,
     ) -> (StateDB, CodeDB) {
-        let mut sdb = StateDB::new();
-        for proof in proofs {
-            let mut storage = HashMap::new();
-            for storage_proof in proof.storage_proof {
-                storage.insert(storage_proof.key, storage_proof.value);
-            }
-            sdb.set_account(
-                &proof.address,
-                state_db::Account {
-                    nonce: proof.nonce,
-                    balance: proof.balance,
-                    storage,
-                    code_hash: proof.code_hash,
-                },
-            )
-        }
-
-        let mut code_db = CodeDB::new();
-        for (_address, code) in codes {
-            code_db.insert(code.clone());
-        }
-        (sdb, code_db)
+        build_state_code_db(proofs, codes)
     }
 
     /// Step 5. For each step in TxExecTraces, gen the associated ops and state
@@ -402,10 +747,40 @@ impl<P: JsonRpcClient> BuilderClient<P> {
         eth_block: &EthBlock,
         geth_traces: &[eth_types::GethExecTrace],
     ) -> Result<CircuitInputBuilder, Error> {
-        let block = Block::new(self.chain_id, self.history_hashes.clone(), eth_block)?;
-        let mut builder = CircuitInputBuilder::new(sdb, code_db, block);
-        builder.handle_block(eth_block, geth_traces)?;
-        Ok(builder)
+        let block_num = eth_block
+            .number
+            .ok_or(Error::EthTypeError(eth_types::Error::IncompleteBlock))?
+            .as_u64();
+        gen_inputs_from_state(
+            self.chain_id,
+            self.history_hashes(block_num)?,
+            sdb,
+            code_db,
+            eth_block,
+            geth_traces,
+        )
+    }
+
+    /// Perform the steps 1 to 3 to gather everything required to generate the
+    /// circuit inputs of a block in a [`WitnessBundle`], which can be saved to
+    /// generate the circuit inputs offline.
+    pub async fn get_witness_bundle(&self, block_num: u64) -> Result<WitnessBundle, Error> {
+        let (eth_block, geth_traces) = self.get_block(block_num).await?;
+        let access_set = self.get_state_accesses(&eth_block, &geth_traces)?;
+        let (proofs, nodes, codes) = self.get_state(block_num, access_set).await?;
+        let history_hashes = self.history_hashes(block_num)?;
+        Ok(WitnessBundle {
+            chain_id: self.chain_id,
+            history_hashes,
+            eth_block,
+            geth_traces,
+            proofs,
+            nodes,
+            codes: codes
+                .into_iter()
+                .map(|(address, code)| (address, code.into()))
+                .collect(),
+        })
     }
 
     /// Perform all the steps to generate the circuit inputs
@@ -419,11 +794,464 @@ impl<P: JsonRpcClient> BuilderClient<P> {
         ),
         Error,
     > {
-        let (eth_block, geth_traces) = self.get_block(block_num).await?;
-        let access_set = self.get_state_accesses(&eth_block, &geth_traces)?;
-        let (proofs, codes) = self.get_state(block_num, access_set).await?;
-        let (state_db, code_db) = self.build_state_code_db(proofs, codes);
-        let builder = self.gen_inputs_from_state(state_db, code_db, &eth_block, &geth_traces)?;
-        Ok((builder, eth_block))
+        let bundle = self.get_witness_bundle(block_num).await?;
+        let builder = bundle.gen_inputs()?;
+        Ok((builder, bundle.eth_block))
+    }
+
+    /// Perform all the steps to generate the circuit inputs of the
+    /// consecutive blocks in `block_nums` in a single
+    /// [`CircuitInputBuilder`].  The state accessed by all the blocks is
+    /// queried at the block before the first one, and then carried over from
+    /// one block to the following one.
+    pub async fn gen_inputs_multi_blocks(
+        &self,
+        block_nums: Range<u64>,
+    ) -> Result<(CircuitInputBuilder, Vec<EthBlock>), Error> {
+        if block_nums.is_empty() {
+            return Err(Error::InternalError("empty block range"));
+        }
+        let mut blocks = Vec::new();
+        let mut access_set = AccessSet::from(Vec::new());
+        let mut history_hashes = Vec::new();
+        for block_num in block_nums.clone() {
+            let (eth_block, geth_traces) = self.get_block(block_num).await?;
+            // The history hashes of the following blocks are carried over from
+            // the first one, but they are taken to release them from the cache.
+            let hashes = self.history_hashes(block_num)?;
+            if block_num == block_nums.start {
+                history_hashes = hashes;
+            }
+            access_set.extend(self.get_state_accesses(&eth_block, &geth_traces)?);
+            blocks.push((eth_block, geth_traces));
+        }
+        let (proofs, nodes, codes) = self.get_state(block_nums.start, access_set).await?;
+        let (sdb, code_db) = build_state_code_db(proofs.clone(), codes);
+
+        let mut block = Block::new(self.chain_id, history_hashes, &blocks[0].0)?;
+        block.proofs = proofs;
+        block.nodes = nodes;
+        let mut builder = CircuitInputBuilder::new(sdb, code_db, block);
+        builder.handle_blocks(&blocks)?;
+        Ok((
+            builder,
+            blocks.into_iter().map(|(eth_block, _)| eth_block).collect(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod builder_client_tests {
+    use super::*;
+    use crate::rpc::{serialize, BlockNumber};
+    use async_trait::async_trait;
+    use eth_types::U64;
+    use ethers_core::utils::{keccak256, rlp::RlpStream};
+    use ethers_providers::{JsonRpcClient, MockError, MockProvider};
+    use serde::{de::DeserializeOwned, Serialize};
+    use serde_json::json;
+
+    // Mock provider which records the size of the batches sent to it.
+    #[derive(Debug)]
+    struct BatchSizes(MockProvider, Mutex<Vec<usize>>);
+
+    #[async_trait]
+    impl JsonRpcClient for BatchSizes {
+        type Error = MockError;
+
+        async fn request<T, R>(&self, method: &str, params: T) -> Result<R, Self::Error>
+        where
+            T: Debug + Serialize + Send + Sync,
+            R: DeserializeOwned,
+        {
+            self.0.request(method, params).await
+        }
+    }
+
+    #[async_trait]
+    impl BatchJsonRpcClient for BatchSizes {
+        async fn request_batch<T, R>(
+            &self,
+            method: &str,
+            params: Vec<T>,
+        ) -> Result<Vec<R>, Self::Error>
+        where
+            T: Debug + Serialize + Send + Sync,
+            R: DeserializeOwned + Send,
+        {
+            self.1.lock().unwrap().push(params.len());
+            self.0.request_batch(method, params).await
+        }
+    }
+
+    fn block_hash(number: u64) -> H256 {
+        H256::from_low_u64_be(0x1000 + number)
+    }
+
+    // Push the responses to `eth_getBlockByNumber` for the headers of the
+    // blocks, which are requested in order.
+    fn push_headers(mock: &MockProvider, numbers: impl DoubleEndedIterator<Item = u64>) {
+        // The responses of the mock provider are returned in reverse order.
+        for number in numbers.rev() {
+            let header = eth_types::Block::<H256> {
+                hash: Some(block_hash(number)),
+                number: Some(U64::from(number)),
+                ..Default::default()
+            };
+            mock.push(header).unwrap();
+        }
+    }
+
+    fn assert_header_requests(mock: &MockProvider, numbers: impl Iterator<Item = u64>) {
+        for number in numbers {
+            mock.assert_request(
+                "eth_getBlockByNumber",
+                [serialize(&BlockNumber::from(number)), serialize(&false)],
+            )
+            .unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn history_hashes_are_cached() {
+        let mock = MockProvider::new();
+        mock.push(U64::from(1)).unwrap();
+        let cli = BuilderClient::new(GethClient::new(mock.clone()))
+            .await
+            .unwrap();
+        mock.assert_request("eth_chainId", ()).unwrap();
+
+        // Block 300 accesses the hashes of blocks 44 to 299.
+        push_headers(&mock, 44..300);
+        cli.fetch_history_hashes(300).await.unwrap();
+        assert_header_requests(&mock, 44..300);
+        let history_hashes = cli.history_hashes(300).unwrap();
+        assert_eq!(
+            history_hashes,
+            (44..300)
+                .map(|number| block_hash(number).to_word())
+                .collect::<Vec<_>>()
+        );
+
+        // Only the hash of block 300 is fetched for block 301.
+        push_headers(&mock, 300..301);
+        cli.fetch_history_hashes(301).await.unwrap();
+        assert_header_requests(&mock, 300..301);
+        assert!(mock.assert_request("eth_getBlockByNumber", ()).is_err());
+        assert_eq!(cli.history_hashes(301).unwrap()[..255], history_hashes[1..]);
+        assert_eq!(
+            cli.history_hashes(301).unwrap()[255],
+            block_hash(300).to_word()
+        );
+    }
+
+    #[tokio::test]
+    async fn history_hashes_of_first_blocks() {
+        let mock = MockProvider::new();
+        mock.push(U64::from(1)).unwrap();
+        let cli = BuilderClient::new(GethClient::new(mock.clone()))
+            .await
+            .unwrap();
+
+        cli.fetch_history_hashes(0).await.unwrap();
+        assert!(cli.history_hashes(0).unwrap().is_empty());
+
+        push_headers(&mock, 0..2);
+        cli.fetch_history_hashes(2).await.unwrap();
+        assert_eq!(
+            cli.history_hashes(2).unwrap(),
+            vec![block_hash(0).to_word(), block_hash(1).to_word()]
+        );
+        assert!(cli.history_hashes(3).is_err());
+    }
+
+    #[tokio::test]
+    async fn history_hashes_of_earlier_block() {
+        let mock = MockProvider::new();
+        mock.push(U64::from(1)).unwrap();
+        let cli = BuilderClient::new(GethClient::new(mock.clone()))
+            .await
+            .unwrap();
+        mock.assert_request("eth_chainId", ()).unwrap();
+
+        // Block 300 is built after block 600, whose history hashes don't
+        // overlap with its own.
+        push_headers(&mock, 44..300);
+        cli.fetch_history_hashes(300).await.unwrap();
+        push_headers(&mock, 344..600);
+        cli.fetch_history_hashes(600).await.unwrap();
+        assert_header_requests(&mock, (44..300).chain(344..600));
+
+        assert_eq!(
+            cli.history_hashes(600).unwrap(),
+            (344..600)
+                .map(|number| block_hash(number).to_word())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            cli.history_hashes(300).unwrap(),
+            (44..300)
+                .map(|number| block_hash(number).to_word())
+                .collect::<Vec<_>>()
+        );
+        assert!(mock.assert_request("eth_getBlockByNumber", ()).is_err());
+
+        // Once both blocks are built, only the hashes of the latest one are
+        // kept.
+        assert_eq!(cli.history_hashes.lock().unwrap().hashes.len(), 256);
+        assert!(cli.history_hashes(300).is_err());
+    }
+
+    fn proof_response(address: Address, code_hash: H256) -> serde_json::Value {
+        json!({
+            "address": address,
+            "balance": "0x0",
+            "codeHash": code_hash,
+            "nonce": "0x1",
+            "storageHash": H256::zero(),
+            "accountProof": [],
+            "storageProof": [],
+        })
+    }
+
+    #[tokio::test]
+    async fn get_state_dedups_codes() {
+        let mock = MockProvider::new();
+        mock.push(U64::from(1)).unwrap();
+        let cli = BuilderClient::new(GethClient::new(mock.clone()))
+            .await
+            .unwrap()
+            .with_max_concurrent_requests(2)
+            .unwrap();
+
+        // Two contracts with the same code, and an account without code.
+        let (a, b, c) = (
+            Address::repeat_byte(0xa),
+            Address::repeat_byte(0xb),
+            Address::repeat_byte(0xc),
+        );
+        let code_hash = H256::repeat_byte(0xcc);
+        let code = vec![0x60, 0x00];
+        let access_set = AccessSet::from(vec![
+            Access::new(None, RW::READ, AccessValue::Code { address: a }),
+            Access::new(None, RW::READ, AccessValue::Code { address: b }),
+            Access::new(None, RW::READ, AccessValue::Code { address: c }),
+        ]);
+
+        // A single `eth_getCode` request follows the `eth_getProof` ones, whose
+        // responses are consumed in reverse order.
+        mock.push(eth_types::Bytes::from(code.clone())).unwrap();
+        mock.push(proof_response(c, H256::from(*EMPTY_HASH)))
+            .unwrap();
+        mock.push(proof_response(b, code_hash)).unwrap();
+        mock.push(proof_response(a, code_hash)).unwrap();
+
+        let (proofs, nodes, codes) = cli.get_state(2, access_set).await.unwrap();
+        assert_eq!(proofs.len(), 3);
+        assert!(nodes.is_empty());
+        assert_eq!(
+            codes,
+            HashMap::from([(a, code.clone()), (b, code), (c, vec![])])
+        );
+    }
+
+    #[tokio::test]
+    async fn get_state_fetches_collapse_siblings() {
+        let mock = MockProvider::new();
+        mock.push(U64::from(1)).unwrap();
+        let cli = BuilderClient::new(GethClient::new(mock.clone()))
+            .await
+            .unwrap();
+        mock.assert_request("eth_chainId", ()).unwrap();
+
+        // The account is in a branch with a single other child, which isn't
+        // in the proof.
+        let address = Address::repeat_byte(0xa);
+        let leaf = vec![0xc2, 0x20, 0x01];
+        let sibling = vec![0xc2, 0x30, 0x02];
+        let mut branch = RlpStream::new_list(17);
+        branch.append(&keccak256(&leaf).to_vec());
+        branch.append(&keccak256(&sibling).to_vec());
+        for _ in 2..17 {
+            branch.append_empty_data();
+        }
+        let mut proof = proof_response(address, H256::from(*EMPTY_HASH));
+        proof["accountProof"] = json!([
+            eth_types::Bytes::from(branch.out().to_vec()),
+            eth_types::Bytes::from(leaf),
+        ]);
+        mock.push(eth_types::Bytes::from(sibling.clone())).unwrap();
+        mock.push(proof).unwrap();
+
+        let access_set = AccessSet::from(vec![Access::new(
+            None,
+            RW::READ,
+            AccessValue::Account { address },
+        )]);
+        let (_, nodes, _) = cli.get_state(2, access_set).await.unwrap();
+        assert_eq!(nodes, vec![eth_types::Bytes::from(sibling.clone())]);
+        mock.assert_request(
+            "eth_getProof",
+            [
+                serialize(&address),
+                serialize(&Vec::<Word>::new()),
+                serialize(&BlockNumber::from(1u64)),
+            ],
+        )
+        .unwrap();
+        mock.assert_request("debug_dbGet", [serialize(&H256(keccak256(&sibling)))])
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn get_state_sends_batches() {
+        let mock = MockProvider::new();
+        mock.push(U64::from(1)).unwrap();
+        let cli = BuilderClient::new(GethClient::new(BatchSizes(mock.clone(), Mutex::default())))
+            .await
+            .unwrap()
+            .with_max_concurrent_requests(1)
+            .unwrap()
+            .with_max_batch_size(2)
+            .unwrap();
+
+        let addresses = [0xa, 0xb, 0xc].map(Address::repeat_byte);
+        for address in addresses {
+            mock.push(proof_response(address, H256::from(*EMPTY_HASH)))
+                .unwrap();
+        }
+        let access_set = AccessSet::from(
+            addresses
+                .map(|address| Access::new(None, RW::READ, AccessValue::Account { address }))
+                .to_vec(),
+        );
+        let (proofs, nodes, codes) = cli.get_state(2, access_set).await.unwrap();
+        assert_eq!(proofs.len(), 3);
+        assert!(nodes.is_empty() && codes.is_empty());
+        // The three proofs are queried in a batch of two and a batch of one.
+        assert_eq!(*cli.cli.0 .1.lock().unwrap(), vec![2, 1]);
+    }
+
+    #[tokio::test]
+    async fn get_state_propagates_errors() {
+        let mock = MockProvider::new();
+        mock.push(U64::from(1)).unwrap();
+        let cli = BuilderClient::new(GethClient::new(mock.clone()))
+            .await
+            .unwrap();
+
+        let access_set = AccessSet::from(vec![Access::new(
+            None,
+            RW::READ,
+            AccessValue::Account {
+                address: Address::repeat_byte(0xa),
+            },
+        )]);
+        assert!(matches!(
+            cli.get_state(2, access_set).await,
+            Err(Error::JSONRpcError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn builder_client_rejects_invalid_arguments() {
+        let mock = MockProvider::new();
+        mock.push(U64::from(1)).unwrap();
+        let cli = BuilderClient::new(GethClient::new(mock.clone()))
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            cli.get_state(0, AccessSet::from(vec![])).await,
+            Err(Error::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            cli.with_max_concurrent_requests(0),
+            Err(Error::InvalidArgument(_))
+        ));
+
+        mock.push(U64::from(1)).unwrap();
+        let cli = BuilderClient::new(GethClient::new(mock.clone()))
+            .await
+            .unwrap();
+        assert!(matches!(
+            cli.with_max_batch_size(0),
+            Err(Error::InvalidArgument(_))
+        ));
+    }
+}
+
+#[cfg(test)]
+mod multi_block_tests {
+    use super::*;
+    use crate::mock::BlockData;
+    use eth_types::geth_types::GethData;
+    use mock::test_ctx::{helpers::*, TestContext};
+
+    fn transfer_block(number: u64, parent_hash: H256) -> GethData {
+        TestContext::<2, 1>::new(
+            None,
+            account_0_code_account_1_no_code(eth_types::Bytecode::default()),
+            tx_from_1_to_0,
+            |block, _tx| block.number(number).parent_hash(parent_hash),
+        )
+        .unwrap()
+        .into()
+    }
+
+    #[test]
+    fn handle_consecutive_blocks() {
+        let block_0 = transfer_block(0xcafe, H256::from_low_u64_be(0xcafd));
+        let block_1 = transfer_block(0xcaff, H256::from_low_u64_be(0xcafe));
+
+        let mut builder =
+            BlockData::new_from_geth_data(block_0.clone()).new_circuit_input_builder();
+        let sender = block_0.eth_block.transactions[0].from;
+        let sender_nonce = builder.sdb.get_account(&sender).1.nonce;
+        builder
+            .handle_blocks(&[
+                (block_0.eth_block.clone(), block_0.geth_traces.clone()),
+                (block_1.eth_block.clone(), block_1.geth_traces.clone()),
+            ])
+            .unwrap();
+
+        // The transactions of both blocks are handled, and the state is carried
+        // from the first block to the second one.
+        let txs = builder.block.txs();
+        assert_eq!(txs.len(), 2);
+        assert_eq!((txs[0].block_num, txs[1].block_num), (0xcafe, 0xcaff));
+        assert_eq!(
+            builder.sdb.get_account(&sender).1.nonce,
+            sender_nonce + Word::from(2)
+        );
+
+        // The history hashes of the second block end with the hash of the
+        // first one.
+        let header = builder.block.header(0xcaff).unwrap();
+        assert_eq!(
+            header.history_hashes.last(),
+            Some(&H256::from_low_u64_be(0xcafe).to_word())
+        );
+        assert_eq!(
+            header.history_hashes.len(),
+            builder.block.header(0xcafe).unwrap().history_hashes.len() + 1
+        );
+        assert!(builder.block.header(0xcb00).is_err());
+    }
+
+    #[test]
+    fn handle_non_consecutive_blocks() {
+        let block_0 = transfer_block(0xcafe, H256::zero());
+        let block_2 = transfer_block(0xcb00, H256::zero());
+
+        let mut builder =
+            BlockData::new_from_geth_data(block_0.clone()).new_circuit_input_builder();
+        assert!(matches!(
+            builder.handle_blocks(&[
+                (block_0.eth_block.clone(), block_0.geth_traces.clone()),
+                (block_2.eth_block.clone(), block_2.geth_traces.clone()),
+            ]),
+            Err(Error::NonConsecutiveBlock(0xcb00))
+        ));
     }
 }
@@ -91,6 +91,16 @@ impl From<Vec<Access>> for AccessSet {
     }
 }
 
+impl AccessSet {
+    /// Add the accesses of `other` to the set.
+    pub fn extend(&mut self, other: AccessSet) {
+        for (address, keys) in other.state {
+            self.state.entry(address).or_default().extend(keys);
+        }
+        self.code.extend(other.code);
+    }
+}
+
 /// Source of the code in the EVM execution.
 #[derive(Debug, Clone, Copy)]
 pub enum CodeSource {
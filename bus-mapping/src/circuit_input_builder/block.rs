@@ -1,14 +1,15 @@
 //! Block-related utility module
 
-use super::{transaction::Transaction, CopyEvent};
+use super::{transaction::Transaction, CopyEvent, ExpEvent};
 use crate::{
     operation::{OperationContainer, RWCounter},
     Error,
 };
-use eth_types::{Address, Hash, Word};
-use std::collections::HashMap;
+use eth_types::{Address, Bytes, EIP1186ProofResponse, Hash, Word};
+use std::collections::{BTreeMap, HashMap};
 
-/// Context of a [`Block`] which can mutate in a [`Transaction`].
+/// Context of a [`Block`] which can mutate in a [`Transaction`].  The context
+/// is carried over from one block to the following one.
 #[derive(Debug)]
 pub struct BlockContext {
     /// Used to track the global counter in every operation in the block.
@@ -18,7 +19,8 @@ pub struct BlockContext {
     /// in Block.txs and call_index is the index used in Transaction.
     /// calls).
     pub(crate) call_map: HashMap<usize, (usize, usize)>,
-    /// Total gas used by previous transactions in this block.
+    /// Total gas used by previous transactions in the block, which is reset
+    /// at the start of each block.
     pub(crate) cumulative_gas_used: u64,
 }
 
@@ -39,41 +41,30 @@ impl BlockContext {
     }
 }
 
-/// Circuit Input related to a block.
-#[derive(Debug)]
-pub struct Block {
-    /// chain id
-    pub chain_id: Word,
+/// Header fields of a block handled by the
+/// [`CircuitInputBuilder`](super::CircuitInputBuilder).
+#[derive(Debug, Clone)]
+pub struct BlockHead {
     /// history hashes contains most recent 256 block hashes in history, where
     /// the lastest one is at history_hashes[history_hashes.len() - 1].
     pub history_hashes: Vec<Word>,
     /// coinbase
     pub coinbase: Address,
-    /// time
+    /// gas limit
     pub gas_limit: u64,
     /// number
     pub number: Word,
-    /// difficulty
+    /// time
     pub timestamp: Word,
-    /// gas limit
+    /// difficulty
     pub difficulty: Word,
     /// base fee
     pub base_fee: Word,
-    /// Container of operations done in this block.
-    pub container: OperationContainer,
-    /// Transactions contained in the block
-    pub txs: Vec<Transaction>,
-    /// Copy events in this block.
-    pub copy_events: Vec<CopyEvent>,
-    /// Inputs to the SHA3 opcode
-    pub sha3_inputs: Vec<Vec<u8>>,
-    code: HashMap<Hash, Vec<u8>>,
 }
 
-impl Block {
-    /// Create a new block.
+impl BlockHead {
+    /// Create a new block header.
     pub fn new<TX>(
-        chain_id: Word,
         history_hashes: Vec<Word>,
         eth_block: &eth_types::Block<TX>,
     ) -> Result<Self, Error> {
@@ -85,7 +76,6 @@ impl Block {
         }
 
         Ok(Self {
-            chain_id,
             history_hashes,
             coinbase: eth_block
                 .author
@@ -99,14 +89,78 @@ impl Block {
             timestamp: eth_block.timestamp,
             difficulty: eth_block.difficulty,
             base_fee: eth_block.base_fee_per_gas.unwrap_or_default(),
+        })
+    }
+}
+
+/// Circuit Input related to a sequence of consecutive blocks, which are proven
+/// together.
+#[derive(Debug)]
+pub struct Block {
+    /// chain id
+    pub chain_id: Word,
+    /// Headers of the blocks by block number
+    pub headers: BTreeMap<u64, BlockHead>,
+    /// Container of operations done in the blocks.
+    pub container: OperationContainer,
+    /// Transactions contained in the blocks
+    pub txs: Vec<Transaction>,
+    /// Copy events in the blocks.
+    pub copy_events: Vec<CopyEvent>,
+    /// Exponentiation events in the blocks.
+    pub exp_events: Vec<ExpEvent>,
+    /// Inputs to the SHA3 opcode
+    pub sha3_inputs: Vec<Vec<u8>>,
+    /// `eth_getProof` responses of the state accessed by the blocks, before
+    /// the first one, when it's queried from geth.  Empty for a mock state.
+    pub proofs: Vec<EIP1186ProofResponse>,
+    /// Trie nodes which are not in the proofs, but which are needed to delete
+    /// their keys: the siblings of the keys in the branches with two
+    /// children, which are collapsed into the sibling.
+    pub nodes: Vec<Bytes>,
+    code: HashMap<Hash, Vec<u8>>,
+}
+
+impl Block {
+    /// Create a new block, to which the following blocks can be added with
+    /// [`CircuitInputBuilder::begin_block`](super::CircuitInputBuilder::begin_block).
+    pub fn new<TX>(
+        chain_id: Word,
+        history_hashes: Vec<Word>,
+        eth_block: &eth_types::Block<TX>,
+    ) -> Result<Self, Error> {
+        let head = BlockHead::new(history_hashes, eth_block)?;
+
+        Ok(Self {
+            chain_id,
+            headers: BTreeMap::from([(head.number.as_u64(), head)]),
             container: OperationContainer::new(),
             txs: Vec::new(),
             copy_events: Vec::new(),
+            exp_events: Vec::new(),
             code: HashMap::new(),
             sha3_inputs: Vec::new(),
+            proofs: Vec::new(),
+            nodes: Vec::new(),
         })
     }
 
+    /// Return the header of the block with number `block_num`.
+    pub fn header(&self, block_num: u64) -> Result<&BlockHead, Error> {
+        self.headers
+            .get(&block_num)
+            .ok_or(Error::InternalError("block header not found"))
+    }
+
+    /// Return the header of the last block, whose transactions are handled
+    /// next.
+    pub fn last_header(&self) -> &BlockHead {
+        self.headers
+            .values()
+            .next_back()
+            .expect("block has at least one header")
+    }
+
     /// Return the list of transactions of this block.
     pub fn txs(&self) -> &[Transaction] {
         &self.txs
@@ -123,4 +177,8 @@ impl Block {
     pub fn add_copy_event(&mut self, copy: CopyEvent) {
         self.copy_events.push(copy);
     }
+    /// Push an exponentiation event to the block.
+    pub fn add_exp_event(&mut self, event: ExpEvent) {
+        self.exp_events.push(event);
+    }
 }
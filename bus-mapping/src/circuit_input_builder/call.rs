@@ -113,6 +113,28 @@ pub struct CallContext {
     pub memory: Memory,
     /// return data buffer
     pub return_data: Vec<u8>,
+    /// Id of the last callee
+    pub last_callee_id: usize,
+    /// Offset of the return data in the memory of the last callee
+    pub last_callee_return_data_offset: u64,
+    /// Length of the return data in the memory of the last callee
+    pub last_callee_return_data_length: u64,
+}
+
+impl CallContext {
+    /// Update the information of the last callee and its return data, which
+    /// are accessed by RETURNDATASIZE and RETURNDATACOPY.
+    pub fn update_last_callee(
+        &mut self,
+        callee_id: usize,
+        return_data_offset: u64,
+        return_data: Vec<u8>,
+    ) {
+        self.last_callee_id = callee_id;
+        self.last_callee_return_data_offset = return_data_offset;
+        self.last_callee_return_data_length = return_data.len() as u64;
+        self.return_data = return_data;
+    }
 }
 
 /// A reversion group is the collection of calls and the operations which are
@@ -2,11 +2,11 @@
 
 use crate::{
     circuit_input_builder::CallContext, error::ExecError, exec_trace::OperationRef,
-    operation::RWCounter,
+    operation::RWCounter, precompile::PrecompileCalls,
 };
 use eth_types::{
     evm_types::{Gas, GasCost, OpcodeId, ProgramCounter},
-    GethExecStep, H256,
+    GethExecStep, Word, H256,
 };
 use gadgets::impl_expr;
 use halo2_proofs::plonk::Expression;
@@ -110,6 +110,8 @@ pub enum ExecState {
     BeginTx,
     /// Virtual step End Tx
     EndTx,
+    /// Virtual step Precompile, which executes a precompiled contract
+    Precompile(PrecompileCalls),
 }
 
 impl ExecState {
@@ -163,7 +165,9 @@ pub enum CopyDataType {
     TxLog,
     /// When the destination rows are not directly for copying but for a special
     /// scenario where we wish to accumulate the value (RLC) over all rows.
-    /// This is used for Copy Lookup from SHA3 opcode verification.
+    /// This is used for Copy Lookup from SHA3 opcode verification.  It's also
+    /// used as the source for the output of a precompiled contract, which is
+    /// written to the callee's memory.
     RlcAcc,
 }
 
@@ -243,14 +247,14 @@ impl CopyEvent {
     // increase in rw counter from the start of the copy event to step index
     fn rw_counter_increase(&self, step_index: usize) -> u64 {
         let source_rw_increase = match self.src_type {
-            CopyDataType::Bytecode | CopyDataType::TxCalldata => 0,
+            CopyDataType::Bytecode | CopyDataType::TxCalldata | CopyDataType::RlcAcc => 0,
             CopyDataType::Memory => std::cmp::min(
                 u64::try_from(step_index + 1).unwrap() / 2,
                 self.src_addr_end
                     .checked_sub(self.src_addr)
                     .unwrap_or_default(),
             ),
-            CopyDataType::RlcAcc | CopyDataType::TxLog => unreachable!(),
+            CopyDataType::TxLog => unreachable!(),
         };
         let destination_rw_increase = match self.dst_type {
             CopyDataType::RlcAcc => 0,
@@ -260,3 +264,79 @@ impl CopyEvent {
         source_rw_increase + destination_rw_increase
     }
 }
+
+/// Defines a single multiplication step of an exponentiation by squaring,
+/// which verifies that `a * b == d (mod 2^256)`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExpStep {
+    /// Multiplier.
+    pub a: Word,
+    /// Multiplicand.
+    pub b: Word,
+    /// Product `a * b (mod 2^256)`.
+    pub d: Word,
+}
+
+impl From<(Word, Word, Word)> for ExpStep {
+    fn from(values: (Word, Word, Word)) -> Self {
+        Self {
+            a: values.0,
+            b: values.1,
+            d: values.2,
+        }
+    }
+}
+
+/// Defines an exponentiation event associated with the EXP opcode, holding
+/// the intermediate multiplications of an exponentiation by squaring.
+#[derive(Clone, Debug)]
+pub struct ExpEvent {
+    /// Base of the exponentiation.
+    pub base: Word,
+    /// Exponent of the exponentiation.
+    pub exponent: Word,
+    /// Result of the exponentiation `base ^ exponent (mod 2^256)`.
+    pub exponentiation: Word,
+    /// Intermediate multiplication steps, ordered from the one that computes
+    /// the final result down to the first squaring of the base.
+    pub steps: Vec<ExpStep>,
+}
+
+impl ExpEvent {
+    /// Build the exponentiation event for `base ^ exponent`. Exponents smaller
+    /// than 2 don't need any multiplication, so the resulting event has no
+    /// steps.
+    pub fn new(base: Word, exponent: Word) -> Self {
+        let mut steps = Vec::new();
+        let exponentiation = exp_by_squaring(base, exponent, &mut steps);
+        steps.reverse();
+        Self {
+            base,
+            exponent,
+            exponentiation,
+            steps,
+        }
+    }
+}
+
+fn exp_by_squaring(base: Word, exponent: Word, steps: &mut Vec<ExpStep>) -> Word {
+    if exponent.is_zero() {
+        return Word::one();
+    }
+    if exponent == Word::one() {
+        return base;
+    }
+
+    let (exponent_div2, odd) = exponent.div_mod(Word::from(2));
+    let exp1 = exp_by_squaring(base, exponent_div2, steps);
+    let (exp2, _) = exp1.overflowing_mul(exp1);
+    steps.push((exp1, exp1, exp2).into());
+
+    if odd.is_zero() {
+        exp2
+    } else {
+        let (exp3, _) = exp2.overflowing_mul(base);
+        steps.push((exp2, base, exp3).into());
+        exp3
+    }
+}
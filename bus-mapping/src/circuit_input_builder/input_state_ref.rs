@@ -2,7 +2,8 @@
 
 use super::{
     get_call_memory_offset_length, get_create_init_code, Block, BlockContext, Call, CallContext,
-    CallKind, CodeSource, CopyEvent, ExecState, ExecStep, Transaction, TransactionContext,
+    CallKind, CodeSource, CopyEvent, ExecState, ExecStep, ExpEvent, Transaction,
+    TransactionContext,
 };
 use crate::{
     error::{get_step_reported_error, ExecError},
@@ -16,7 +17,10 @@ use crate::{
     Error,
 };
 use eth_types::{
-    evm_types::{gas_utils::memory_expansion_gas_cost, Gas, MemoryAddress, OpcodeId, StackAddress},
+    evm_types::{
+        gas_utils::memory_expansion_gas_cost, Gas, GasCost, MemoryAddress, OpcodeId, StackAddress,
+        MAX_CODE_SIZE,
+    },
     Address, GethExecStep, ToAddress, ToBigEndian, ToWord, Word, H256,
 };
 use ethers_core::utils::{get_contract_address, get_create2_address};
@@ -588,7 +592,7 @@ impl<'a> CircuitInputStateRef<'a> {
                 step.stack.nth_last(2)?,
             ),
             CallKind::CallCode => (caller.address, caller.address, step.stack.nth_last(2)?),
-            CallKind::DelegateCall => (caller.caller_address, caller.address, Word::zero()),
+            CallKind::DelegateCall => (caller.caller_address, caller.address, caller.value),
             CallKind::StaticCall => (
                 caller.address,
                 step.stack.nth_last(1)?.to_address(),
@@ -756,13 +760,22 @@ impl<'a> CircuitInputStateRef<'a> {
             OpEnum::TxRefund(op) => {
                 self.sdb.set_refund(op.value);
             }
-            OpEnum::AccountDestructed(_) => unimplemented!(),
+            OpEnum::AccountDestructed(op) => {
+                if op.is_destructed && !op.is_destructed_prev {
+                    self.sdb.destruct_account(op.address);
+                }
+                if op.is_destructed_prev && !op.is_destructed {
+                    self.sdb.remove_destructed_account(&op.address);
+                }
+            }
             _ => unreachable!(),
         };
     }
 
-    /// Handle a reversion group
-    fn handle_reversion(&mut self) {
+    /// Handle a reversion group, where the steps of the current opcode which
+    /// aren't pushed into the transaction yet are given in
+    /// `current_exec_steps`.
+    fn handle_reversion(&mut self, current_exec_steps: &mut [&mut ExecStep]) {
         let reversion_group = self
             .tx_ctx
             .reversion_groups
@@ -779,9 +792,15 @@ impl<'a> CircuitInputStateRef<'a> {
                     false,
                     op,
                 );
-                self.tx.steps_mut()[step_index]
-                    .bus_mapping_instance
-                    .push(rev_op_ref);
+                // The current steps will be pushed right after the steps of
+                // the transaction, so their indices are shifted.
+                let steps_len = self.tx.steps().len();
+                let step = if step_index >= steps_len {
+                    &mut *current_exec_steps[step_index - steps_len]
+                } else {
+                    &mut self.tx.steps_mut()[step_index]
+                };
+                step.bus_mapping_instance.push(rev_op_ref);
             }
         }
 
@@ -796,6 +815,18 @@ impl<'a> CircuitInputStateRef<'a> {
     /// Handle a return step caused by any opcode that causes a return to the
     /// previous call context.
     pub fn handle_return(&mut self, step: &GethExecStep) -> Result<(), Error> {
+        self.handle_return_with_steps(&mut [], step)
+    }
+
+    /// Same as [`Self::handle_return`], but the steps generated for the
+    /// current opcode which aren't pushed into the transaction yet are given
+    /// in `current_exec_steps`, in the order they will be pushed, so that the
+    /// reversion of their operations can be recorded.
+    pub fn handle_return_with_steps(
+        &mut self,
+        current_exec_steps: &mut [&mut ExecStep],
+        step: &GethExecStep,
+    ) -> Result<(), Error> {
         let call = self.call()?.clone();
         let call_ctx = self.call_ctx()?;
 
@@ -816,7 +847,7 @@ impl<'a> CircuitInputStateRef<'a> {
 
         // Handle reversion if this call doesn't end successfully
         if !call.is_success {
-            self.handle_reversion();
+            self.handle_reversion(current_exec_steps);
         }
 
         self.tx_ctx.pop_call_ctx();
@@ -844,8 +875,10 @@ impl<'a> CircuitInputStateRef<'a> {
         let geth_step = &steps[0];
         let geth_step_next = &steps[1];
 
-        let [last_callee_return_data_offset, last_callee_return_data_length] = match geth_step.op {
-            OpcodeId::STOP => [Word::zero(); 2],
+        let [memory_offset, memory_length] = match geth_step.op {
+            // A call halting in exception doesn't touch the memory.
+            _ if exec_step.error.is_some() => [Word::zero(); 2],
+            OpcodeId::STOP | OpcodeId::SELFDESTRUCT => [Word::zero(); 2],
             OpcodeId::REVERT | OpcodeId::RETURN => {
                 let offset = geth_step.stack.nth_last(0)?;
                 let length = geth_step.stack.nth_last(1)?;
@@ -860,11 +893,19 @@ impl<'a> CircuitInputStateRef<'a> {
             _ => unreachable!(),
         };
 
+        // A successful creation returns the code to be deployed instead of return
+        // data, so its caller gets an empty return data buffer.
+        let is_code_deposit = call.is_create() && call.is_success;
+        let [last_callee_return_data_offset, last_callee_return_data_length] = if is_code_deposit {
+            [Word::zero(); 2]
+        } else {
+            [memory_offset, memory_length]
+        };
+
         let curr_memory_word_size = (exec_step.memory_size as u64) / 32;
-        let next_memory_word_size = if !last_callee_return_data_length.is_zero() {
+        let next_memory_word_size = if !memory_length.is_zero() {
             std::cmp::max(
-                (last_callee_return_data_offset + last_callee_return_data_length + 31).as_u64()
-                    / 32,
+                (memory_offset + memory_length + 31).as_u64() / 32,
                 curr_memory_word_size,
             )
         } else {
@@ -873,7 +914,23 @@ impl<'a> CircuitInputStateRef<'a> {
 
         let memory_expansion_gas_cost =
             memory_expansion_gas_cost(curr_memory_word_size, next_memory_word_size);
-        let gas_refund = geth_step.gas.0 - memory_expansion_gas_cost;
+        let code_deposit_cost = if is_code_deposit {
+            GasCost::CODE_DEPOSIT_BYTE_COST.as_u64() * memory_length.as_u64()
+        } else {
+            0
+        };
+        // SELFDESTRUCT has dynamic gas cost which is paid before returning.
+        let selfdestruct_gas_cost = if geth_step.op == OpcodeId::SELFDESTRUCT {
+            geth_step.gas_cost.0
+        } else {
+            0
+        };
+        // No gas is refunded to the caller when the call halts in exception.
+        let gas_refund = if exec_step.error.is_some() {
+            0
+        } else {
+            geth_step.gas.0 - memory_expansion_gas_cost - code_deposit_cost - selfdestruct_gas_cost
+        };
         let caller_gas_left = geth_step_next.gas.0 - gas_refund;
 
         for (field, value) in [
@@ -915,6 +972,48 @@ impl<'a> CircuitInputStateRef<'a> {
             self.call_context_write(exec_step, caller.call_id, field, value);
         }
 
+        // Keep the return data of callee in caller's return data buffer.
+        let return_data = self.call_ctx()?.memory.read_chunk(
+            last_callee_return_data_offset.low_u64().into(),
+            last_callee_return_data_length.low_u64().into(),
+        );
+        self.caller_ctx_mut()?.update_last_callee(
+            call.call_id,
+            last_callee_return_data_offset.low_u64(),
+            return_data,
+        );
+
+        Ok(())
+    }
+
+    /// Bus mapping for the CommonErrorGadget, which is used by every execution
+    /// step that halts the current call in exception.  The current call is
+    /// failed, so its `IsSuccess` and `RwCounterEndOfReversion` are read,
+    /// and the caller's context is restored if the call is not the root one.
+    pub fn gen_restore_context_ops(
+        &mut self,
+        exec_step: &mut ExecStep,
+        steps: &[GethExecStep],
+    ) -> Result<(), Error> {
+        let call = self.call()?.clone();
+        debug_assert!(!call.is_success, "call halting in exception must fail");
+
+        // NOTE: `rw_counter_end_of_reversion` is not known yet, the proper value is
+        // set later in `CircuitInputBuilder::set_value_ops_call_context_rwc_eor`
+        for (field, value) in [
+            (CallContextField::IsSuccess, Word::zero()),
+            (
+                CallContextField::RwCounterEndOfReversion,
+                call.rw_counter_end_of_reversion.into(),
+            ),
+        ] {
+            self.call_context_read(exec_step, call.call_id, field, value);
+        }
+
+        if !call.is_root {
+            self.handle_restore_context(steps, exec_step)?;
+        }
+
         Ok(())
     }
 
@@ -923,6 +1022,11 @@ impl<'a> CircuitInputStateRef<'a> {
         self.block.add_copy_event(copy);
     }
 
+    /// Push an exponentiation event to the state.
+    pub fn push_exponentiation(&mut self, event: ExpEvent) {
+        self.block.add_exp_event(event);
+    }
+
     pub(crate) fn get_step_err(
         &self,
         step: &GethExecStep,
@@ -961,7 +1065,10 @@ impl<'a> CircuitInputStateRef<'a> {
                 return Ok(match step.op {
                     OpcodeId::JUMP | OpcodeId::JUMPI => Some(ExecError::InvalidJump),
                     OpcodeId::RETURNDATACOPY => Some(ExecError::ReturnDataOutOfBounds),
-                    // Break write protection (CALL with value will be handled below)
+                    // Break write protection
+                    OpcodeId::CALL if call.is_static && !step.stack.nth_last(2)?.is_zero() => {
+                        Some(ExecError::WriteProtection)
+                    }
                     OpcodeId::SSTORE
                     | OpcodeId::CREATE
                     | OpcodeId::CREATE2
@@ -988,14 +1095,31 @@ impl<'a> CircuitInputStateRef<'a> {
                 if !call.is_root && call.is_create() {
                     let offset = step.stack.nth_last(0)?;
                     let length = step.stack.nth_last(1)?;
-                    if length > Word::from(0x6000u64) {
+                    if length > Word::from(MAX_CODE_SIZE) {
                         return Ok(Some(ExecError::MaxCodeSizeExceeded));
                     } else if length > Word::zero()
                         && !call_ctx.memory.is_empty()
                         && call_ctx.memory.0.get(offset.low_u64() as usize) == Some(&0xef)
                     {
                         return Ok(Some(ExecError::InvalidCreationCode));
-                    } else if Word::from(200u64) * length > Word::from(step.gas.0) {
+                    }
+
+                    // The code deposit is charged after the memory expansion of RETURN.
+                    let curr_memory_word_size = call_ctx.memory.word_size() as u64;
+                    let next_memory_word_size = if length.is_zero() {
+                        curr_memory_word_size
+                    } else {
+                        max(
+                            curr_memory_word_size,
+                            (offset.low_u64() + length.low_u64() + 31) / 32,
+                        )
+                    };
+                    let memory_expansion_gas_cost =
+                        memory_expansion_gas_cost(curr_memory_word_size, next_memory_word_size);
+                    if Word::from(GasCost::CODE_DEPOSIT_BYTE_COST.as_u64()) * length
+                        + Word::from(memory_expansion_gas_cost)
+                        > Word::from(step.gas.0)
+                    {
                         return Ok(Some(ExecError::CodeStoreOutOfGas));
                     } else {
                         return Err(Error::UnexpectedExecStepError(
@@ -1054,11 +1178,6 @@ impl<'a> CircuitInputStateRef<'a> {
                 _ => Word::zero(),
             };
 
-            // CALL with value
-            if matches!(step.op, OpcodeId::CALL) && !value.is_zero() && self.call()?.is_static {
-                return Ok(Some(ExecError::WriteProtection));
-            }
-
             let sender = self.call()?.address;
             let (found, account) = self.sdb.get_account(&sender);
             if !found {
@@ -36,6 +36,7 @@ impl CircuitInputBuilderTx {
             .new_tx(&block.eth_block.transactions[0], true)
             .unwrap();
         let tx_ctx = TransactionContext::new(
+            1,
             &block.eth_block.transactions[0],
             &GethExecTrace {
                 gas: Gas(0),
@@ -17,7 +17,8 @@ use super::{call::ReversionGroup, Call, CallContext, CallKind, CodeSource, ExecS
 #[derive(Debug)]
 /// Context of a [`Transaction`] which can mutate in an [`ExecStep`].
 pub struct TransactionContext {
-    /// Unique identifier of transaction of the block. The value is `index + 1`.
+    /// Unique identifier of transaction of the blocks. The value is `index +
+    /// 1`, where `index` is the index of the transaction in all the blocks.
     id: usize,
     /// The index of logs made in the transaction.
     pub(crate) log_id: usize,
@@ -38,6 +39,7 @@ pub struct TransactionContext {
 impl TransactionContext {
     /// Create a new Self.
     pub fn new(
+        id: usize,
         eth_tx: &eth_types::Transaction,
         geth_trace: &GethExecTrace,
         is_last_tx: bool,
@@ -70,11 +72,7 @@ impl TransactionContext {
         };
 
         let mut tx_ctx = Self {
-            id: eth_tx
-                .transaction_index
-                .ok_or(Error::EthTypeError(eth_types::Error::IncompleteBlock))?
-                .as_u64() as usize
-                + 1,
+            id,
             log_id: 0,
             is_last_tx,
             call_is_success,
@@ -162,7 +160,7 @@ impl TransactionContext {
             reversible_write_counter: 0,
             call_data,
             memory: Memory::default(),
-            return_data: vec![],
+            ..Default::default()
         });
     }
 
@@ -181,6 +179,8 @@ impl TransactionContext {
 #[derive(Debug, Clone)]
 /// Result of the parsing of an Ethereum Transaction.
 pub struct Transaction {
+    /// Number of the block of the transaction
+    pub block_num: u64,
     /// Nonce
     pub nonce: u64,
     /// Gas
@@ -225,6 +225,7 @@ impl Transaction {
     /// Create a new Self.
     pub fn new(
         call_id: usize,
+        block_num: u64,
         sdb: &StateDB,
         code_db: &mut CodeDB,
         eth_tx: &eth_types::Transaction,
@@ -277,6 +278,7 @@ impl Transaction {
         };
 
         Ok(Self {
+            block_num,
             nonce: eth_tx.nonce.as_u64(),
             gas: eth_tx.gas.as_u64(),
             gas_price: eth_tx.gas_price.unwrap_or_default(),
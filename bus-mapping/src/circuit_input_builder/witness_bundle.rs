@@ -0,0 +1,201 @@
+//! Witness bundle of a block, which contains everything that is queried to
+//! geth to generate its circuit inputs.
+
+use super::{build_state_code_db, gen_inputs_from_state, CircuitInputBuilder, EthBlock};
+use crate::Error;
+use eth_types::{Address, Bytes, EIP1186ProofResponse, GethExecTrace, Word};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+
+/// All the data queried to geth by the
+/// [`BuilderClient`](super::BuilderClient) to generate the circuit inputs of a
+/// block.  A `WitnessBundle` can be saved to a file and loaded later, so that
+/// the circuit inputs can be generated without access to a geth node.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WitnessBundle {
+    /// chain id
+    pub chain_id: Word,
+    /// history hashes contains most recent 256 block hashes in history, where
+    /// the lastest one is at history_hashes[history_hashes.len() - 1].
+    pub history_hashes: Vec<Word>,
+    /// Block from geth
+    pub eth_block: EthBlock,
+    /// Execution Trace from geth
+    pub geth_traces: Vec<GethExecTrace>,
+    /// Proofs of the accounts and storage keys accessed in the block, at the
+    /// state of the previous block.
+    pub proofs: Vec<EIP1186ProofResponse>,
+    /// Trie nodes off the proofs which are needed to delete their keys.
+    #[serde(default)]
+    pub nodes: Vec<Bytes>,
+    /// Codes of the accounts accessed in the block, at the state of the
+    /// previous block.
+    pub codes: HashMap<Address, Bytes>,
+}
+
+impl WitnessBundle {
+    /// Save the bundle as JSON to the file at `path`.
+    pub fn save<T: AsRef<Path>>(&self, path: T) -> Result<(), Error> {
+        let writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer(writer, self).map_err(Error::SerdeError)
+    }
+
+    /// Load a bundle saved with [`WitnessBundle::save`] from the file at
+    /// `path`.
+    pub fn load<T: AsRef<Path>>(path: T) -> Result<Self, Error> {
+        let reader = BufReader::new(File::open(path)?);
+        serde_json::from_reader(reader).map_err(Error::SerdeError)
+    }
+
+    /// Generate the circuit inputs of the block from the bundle.
+    pub fn gen_inputs(&self) -> Result<CircuitInputBuilder, Error> {
+        let codes = self
+            .codes
+            .iter()
+            .map(|(address, code)| (*address, code.to_vec()))
+            .collect();
+        let (sdb, code_db) = build_state_code_db(self.proofs.clone(), codes);
+        let mut builder = gen_inputs_from_state(
+            self.chain_id,
+            self.history_hashes.clone(),
+            sdb,
+            code_db,
+            &self.eth_block,
+            &self.geth_traces,
+        )?;
+        builder.block.proofs = self.proofs.clone();
+        builder.block.nodes = self.nodes.clone();
+        Ok(builder)
+    }
+}
+
+#[cfg(test)]
+mod witness_bundle_tests {
+    use super::*;
+    use crate::mock::BlockData;
+    use eth_types::{bytecode, geth_types::GethData, StorageProof, H256};
+    use ethers_core::utils::keccak256;
+    use mock::test_ctx::{helpers::*, TestContext};
+    use pretty_assertions::assert_eq;
+
+    // Build the bundle that a BuilderClient would fetch for the mock block,
+    // with an empty proof for every account.
+    fn witness_bundle(geth_data: GethData) -> WitnessBundle {
+        let mut proofs = HashMap::new();
+        let mut codes = HashMap::new();
+        let touched = geth_data
+            .eth_block
+            .transactions
+            .iter()
+            .flat_map(|tx| [Some(tx.from), tx.to])
+            .chain([geth_data.eth_block.author])
+            .flatten();
+        for address in touched {
+            proofs.insert(
+                address,
+                EIP1186ProofResponse {
+                    address,
+                    code_hash: H256(keccak256([])),
+                    ..Default::default()
+                },
+            );
+        }
+        for account in geth_data.accounts {
+            proofs.insert(
+                account.address,
+                EIP1186ProofResponse {
+                    address: account.address,
+                    balance: account.balance,
+                    nonce: account.nonce,
+                    code_hash: H256(keccak256(&account.code)),
+                    storage_proof: account
+                        .storage
+                        .into_iter()
+                        .map(|(key, value)| StorageProof {
+                            key,
+                            value,
+                            proof: vec![],
+                        })
+                        .collect(),
+                    ..Default::default()
+                },
+            );
+            codes.insert(account.address, account.code);
+        }
+
+        WitnessBundle {
+            chain_id: geth_data.chain_id,
+            history_hashes: geth_data.history_hashes,
+            eth_block: geth_data.eth_block,
+            geth_traces: geth_data.geth_traces,
+            proofs: proofs.into_values().collect(),
+            nodes: vec![],
+            codes,
+        }
+    }
+
+    #[test]
+    fn witness_bundle_save_load_gen_inputs() {
+        let code = bytecode! {
+            PUSH1(0x01)
+            SLOAD
+            PUSH1(0x02)
+            SSTORE
+            PUSH1(0x20)
+            PUSH1(0x00)
+            SHA3
+            POP
+            STOP
+        };
+        let block: GethData = TestContext::<2, 1>::new(
+            None,
+            |accs| {
+                accs[0]
+                    .address(mock::MOCK_ACCOUNTS[0])
+                    .balance(mock::eth(10))
+                    .storage(vec![(Word::from(1), Word::from(0xcafe))].into_iter())
+                    .code(code);
+                accs[1]
+                    .address(mock::MOCK_ACCOUNTS[1])
+                    .balance(mock::eth(10));
+            },
+            tx_from_1_to_0,
+            |block, _tx| block.number(0xcafeu64),
+        )
+        .unwrap()
+        .into();
+
+        let bundle = witness_bundle(block.clone());
+        let path = std::env::temp_dir().join(format!(
+            "witness_bundle_save_load_{}.json",
+            std::process::id()
+        ));
+        bundle.save(&path).unwrap();
+        let loaded = WitnessBundle::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        let loaded = loaded.unwrap();
+        assert_eq!(loaded, bundle);
+
+        let builder = loaded.gen_inputs().unwrap();
+
+        let mut expected = BlockData::new_from_geth_data(block.clone()).new_circuit_input_builder();
+        expected
+            .handle_block(&block.eth_block, &block.geth_traces)
+            .unwrap();
+        assert_eq!(builder.block.container, expected.block.container);
+        assert_eq!(builder.block.txs().len(), expected.block.txs().len());
+    }
+
+    #[test]
+    fn witness_bundle_load_missing_file() {
+        let path = std::env::temp_dir().join(format!(
+            "witness_bundle_missing_{}.json",
+            std::process::id()
+        ));
+        assert!(matches!(WitnessBundle::load(&path), Err(Error::IoError(_))));
+    }
+}
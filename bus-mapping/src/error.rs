@@ -15,6 +15,8 @@ use crate::geth_errors::{
 pub enum Error {
     /// Serde de/serialization error.
     SerdeError(serde_json::error::Error),
+    /// I/O error.
+    IoError(std::io::Error),
     /// JSON-RPC related error.
     JSONRpcError(ProviderError),
     /// OpcodeId is not a call type.
@@ -38,6 +40,11 @@ pub enum Error {
     EthTypeError(eth_types::Error),
     /// EVM Execution error
     ExecutionError(ExecError),
+    /// Block doesn't follow the last block of the
+    /// [`CircuitInputBuilder`](crate::circuit_input_builder::CircuitInputBuilder).
+    NonConsecutiveBlock(u64),
+    /// Invalid argument of a method.
+    InvalidArgument(&'static str),
     /// Internal Code error
     InternalError(&'static str),
 }
@@ -54,6 +61,12 @@ impl From<ProviderError> for Error {
     }
 }
 
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Error::IoError(err)
+    }
+}
+
 impl Display for Error {
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
         write!(f, "{:?}", self)
@@ -1,36 +1,51 @@
 //! Definition of each opcode of the EVM.
 use crate::{
     circuit_input_builder::{CircuitInputStateRef, ExecStep},
+    error::{ExecError, OogError},
     evm::OpcodeId,
-    operation::{
-        AccountField, CallContextField, TxAccessListAccountOp, TxReceiptField, TxRefundOp, RW,
-    },
+    operation::{AccountField, CallContextField, TxReceiptField, TxRefundOp, RW},
+    precompile::PrecompileCalls,
     Error,
 };
 use core::fmt::Debug;
 use eth_types::{
     evm_types::{GasCost, MAX_REFUND_QUOTIENT_OF_GAS_USED},
-    GethExecStep, ToAddress, ToWord, Word,
+    GethExecStep, ToWord, Word,
 };
 use keccak256::EMPTY_HASH;
 use log::warn;
+use strum::IntoEnumIterator;
 
 #[cfg(any(feature = "test", test))]
 pub use self::sha3::sha3_tests::{gen_sha3_code, MemoryKind};
 
 mod address;
 mod balance;
-mod call;
 mod calldatacopy;
 mod calldataload;
 mod calldatasize;
 mod caller;
+mod callop;
 mod callvalue;
 mod chainid;
 mod codecopy;
 mod codesize;
 mod create;
 mod dup;
+mod error_code_store;
+mod error_invalid_creation_code;
+mod error_invalid_jump;
+mod error_invalid_opcode;
+mod error_oog_call;
+mod error_oog_ext_codecopy;
+mod error_oog_memory;
+mod error_oog_self_destruct;
+mod error_oog_sload_sstore;
+mod error_precheck_failed;
+mod error_return_data_oob;
+mod error_stack;
+mod error_write_protection;
+mod exp;
 mod extcodecopy;
 mod extcodehash;
 mod extcodesize;
@@ -42,7 +57,9 @@ mod number;
 mod origin;
 mod r#return;
 mod returndatacopy;
+mod returndatasize;
 mod selfbalance;
+mod selfdestruct;
 mod sha3;
 mod sload;
 mod sstore;
@@ -56,16 +73,30 @@ mod memory_expansion_test;
 use self::sha3::Sha3;
 use address::Address;
 use balance::Balance;
-use call::Call;
 use calldatacopy::Calldatacopy;
 use calldataload::Calldataload;
 use calldatasize::Calldatasize;
 use caller::Caller;
+use callop::CallOpcode;
 use callvalue::Callvalue;
 use codecopy::Codecopy;
 use codesize::Codesize;
-use create::DummyCreate;
+use create::Create;
 use dup::Dup;
+use error_code_store::ErrorCodeStore;
+use error_invalid_creation_code::ErrorCreationCode;
+use error_invalid_jump::ErrorInvalidJump;
+use error_invalid_opcode::ErrorInvalidOpcode;
+use error_oog_call::ErrorOOGCall;
+use error_oog_ext_codecopy::ErrorOOGExtCodeCopy;
+use error_oog_memory::ErrorOOGMemory;
+use error_oog_self_destruct::ErrorOOGSelfDestruct;
+use error_oog_sload_sstore::ErrorOOGSloadSstore;
+use error_precheck_failed::ErrorPrecheckFailed;
+use error_return_data_oob::ErrorReturnDataOutOfBound;
+use error_stack::ErrorStack;
+use error_write_protection::ErrorWriteProtection;
+use exp::Exponentiation;
 use extcodecopy::Extcodecopy;
 use extcodehash::Extcodehash;
 use extcodesize::Extcodesize;
@@ -76,7 +107,9 @@ use mstore::Mstore;
 use origin::Origin;
 use r#return::Return;
 use returndatacopy::Returndatacopy;
+use returndatasize::Returndatasize;
 use selfbalance::Selfbalance;
+use selfdestruct::Selfdestruct;
 use sload::Sload;
 use sstore::Sstore;
 use stackonlyop::StackOnlyOpcode;
@@ -131,7 +164,7 @@ fn fn_gen_associated_ops(opcode_id: &OpcodeId) -> FnGenAssociatedOps {
         OpcodeId::SMOD => StackOnlyOpcode::<2, 1>::gen_associated_ops,
         OpcodeId::ADDMOD => StackOnlyOpcode::<3, 1>::gen_associated_ops,
         OpcodeId::MULMOD => StackOnlyOpcode::<3, 1>::gen_associated_ops,
-        OpcodeId::EXP => StackOnlyOpcode::<2, 1>::gen_associated_ops,
+        OpcodeId::EXP => Exponentiation::gen_associated_ops,
         OpcodeId::SIGNEXTEND => StackOnlyOpcode::<2, 1>::gen_associated_ops,
         OpcodeId::LT => StackOnlyOpcode::<2, 1>::gen_associated_ops,
         OpcodeId::GT => StackOnlyOpcode::<2, 1>::gen_associated_ops,
@@ -161,7 +194,7 @@ fn fn_gen_associated_ops(opcode_id: &OpcodeId) -> FnGenAssociatedOps {
         OpcodeId::CODESIZE => Codesize::gen_associated_ops,
         OpcodeId::EXTCODESIZE => Extcodesize::gen_associated_ops,
         OpcodeId::EXTCODECOPY => Extcodecopy::gen_associated_ops,
-        OpcodeId::RETURNDATASIZE => StackOnlyOpcode::<0, 1>::gen_associated_ops,
+        OpcodeId::RETURNDATASIZE => Returndatasize::gen_associated_ops,
         OpcodeId::RETURNDATACOPY => Returndatacopy::gen_associated_ops,
         OpcodeId::EXTCODEHASH => Extcodehash::gen_associated_ops,
         OpcodeId::BLOCKHASH => StackOnlyOpcode::<1, 1>::gen_associated_ops,
@@ -222,30 +255,60 @@ fn fn_gen_associated_ops(opcode_id: &OpcodeId) -> FnGenAssociatedOps {
         OpcodeId::LOG2 => Log::gen_associated_ops,
         OpcodeId::LOG3 => Log::gen_associated_ops,
         OpcodeId::LOG4 => Log::gen_associated_ops,
-        OpcodeId::CALL => Call::gen_associated_ops,
+        OpcodeId::CALL | OpcodeId::CALLCODE => CallOpcode::<7>::gen_associated_ops,
+        OpcodeId::DELEGATECALL | OpcodeId::STATICCALL => CallOpcode::<6>::gen_associated_ops,
         OpcodeId::RETURN => Return::gen_associated_ops,
         // REVERT is almost the same as RETURN
         OpcodeId::REVERT => Return::gen_associated_ops,
-        OpcodeId::SELFDESTRUCT => {
-            warn!("Using dummy gen_selfdestruct_ops for opcode SELFDESTRUCT");
-            DummySelfDestruct::gen_associated_ops
+        OpcodeId::SELFDESTRUCT => Selfdestruct::gen_associated_ops,
+        OpcodeId::CREATE => Create::<false>::gen_associated_ops,
+        OpcodeId::CREATE2 => Create::<true>::gen_associated_ops,
+        _ => {
+            warn!("Using dummy gen_associated_ops for opcode {:?}", opcode_id);
+            Dummy::gen_associated_ops
         }
-        OpcodeId::CALLCODE | OpcodeId::DELEGATECALL | OpcodeId::STATICCALL => {
-            warn!("Using dummy gen_call_ops for opcode {:?}", opcode_id);
-            DummyCall::gen_associated_ops
+    }
+}
+
+fn fn_gen_error_state_associated_ops(
+    opcode_id: &OpcodeId,
+    error: &ExecError,
+) -> Option<FnGenAssociatedOps> {
+    match error {
+        // CREATE and CREATE2 handle the address collision themselves, since
+        // the caller's nonce is still increased.
+        ExecError::ContractAddressCollision => Some(fn_gen_associated_ops(opcode_id)),
+        ExecError::CodeStoreOutOfGas | ExecError::MaxCodeSizeExceeded => {
+            Some(ErrorCodeStore::gen_associated_ops)
         }
-        OpcodeId::CREATE => {
-            warn!("Using dummy gen_create_ops for opcode {:?}", opcode_id);
-            DummyCreate::<false>::gen_associated_ops
+        ExecError::InvalidCreationCode => Some(ErrorCreationCode::gen_associated_ops),
+        ExecError::InvalidJump => Some(ErrorInvalidJump::gen_associated_ops),
+        ExecError::InvalidOpcode => Some(ErrorInvalidOpcode::gen_associated_ops),
+        ExecError::OutOfGas(
+            OogError::Call | OogError::CallCode | OogError::DelegateCall | OogError::StaticCall,
+        ) => Some(ErrorOOGCall::gen_associated_ops),
+        ExecError::OutOfGas(OogError::ExtCodeCopy) => Some(ErrorOOGExtCodeCopy::gen_associated_ops),
+        ExecError::OutOfGas(
+            OogError::DynamicMemoryExpansion
+            | OogError::MemoryCopy
+            | OogError::Log
+            | OogError::Sha3,
+        ) => Some(ErrorOOGMemory::gen_associated_ops),
+        ExecError::OutOfGas(OogError::SelfDestruct) => {
+            Some(ErrorOOGSelfDestruct::gen_associated_ops)
         }
-        OpcodeId::CREATE2 => {
-            warn!("Using dummy gen_create_ops for opcode {:?}", opcode_id);
-            DummyCreate::<true>::gen_associated_ops
+        ExecError::OutOfGas(OogError::Sload | OogError::Sstore) => {
+            Some(ErrorOOGSloadSstore::gen_associated_ops)
         }
-        _ => {
-            warn!("Using dummy gen_associated_ops for opcode {:?}", opcode_id);
-            Dummy::gen_associated_ops
+        ExecError::ReturnDataOutOfBounds => Some(ErrorReturnDataOutOfBound::gen_associated_ops),
+        ExecError::StackOverflow | ExecError::StackUnderflow => {
+            Some(ErrorStack::gen_associated_ops)
         }
+        ExecError::WriteProtection => Some(ErrorWriteProtection::gen_associated_ops),
+        ExecError::Depth | ExecError::InsufficientBalance => {
+            Some(ErrorPrecheckFailed::gen_associated_ops)
+        }
+        _ => None,
     }
 }
 
@@ -284,6 +347,10 @@ pub fn gen_associated_ops(
             geth_step.op
         );
 
+        if let Some(fn_gen_error_ops) = fn_gen_error_state_associated_ops(opcode_id, &exec_error) {
+            return fn_gen_error_ops(state, geth_steps);
+        }
+
         exec_step.error = Some(exec_error);
         // for `oog_or_stack_error` error message will be returned by geth_step error
         // field, when this kind of error happens, no more proceeding
@@ -330,8 +397,11 @@ pub fn gen_begin_tx_ops(state: &mut CircuitInputStateRef) -> Result<ExecStep, Er
         nonce_prev.into(),
     )?;
 
-    // Add caller and callee into access list
-    for address in [call.caller_address, call.address] {
+    // Add caller, callee and precompiled contracts into access list
+    for address in [call.caller_address, call.address]
+        .into_iter()
+        .chain(PrecompileCalls::iter().map(|precompile| precompile.address()))
+    {
         state.sdb.add_account_to_access_list(address);
         state.tx_accesslist_account_write(
             &mut exec_step,
@@ -479,10 +549,12 @@ pub fn gen_end_tx_ops(state: &mut CircuitInputStateRef) -> Result<ExecStep, Erro
         caller_balance_prev,
     )?;
 
-    let effective_tip = state.tx.gas_price - state.block.base_fee;
-    let (found, coinbase_account) = state.sdb.get_account_mut(&state.block.coinbase);
+    let header = state.block.header(state.tx.block_num)?;
+    let (coinbase, base_fee) = (header.coinbase, header.base_fee);
+    let effective_tip = state.tx.gas_price - base_fee;
+    let (found, coinbase_account) = state.sdb.get_account_mut(&coinbase);
     if !found {
-        return Err(Error::AccountNotFound(state.block.coinbase));
+        return Err(Error::AccountNotFound(coinbase));
     }
     let coinbase_balance_prev = coinbase_account.balance;
     let coinbase_balance =
@@ -490,7 +562,7 @@ pub fn gen_end_tx_ops(state: &mut CircuitInputStateRef) -> Result<ExecStep, Erro
     coinbase_account.balance = coinbase_balance;
     state.account_write(
         &mut exec_step,
-        state.block.coinbase,
+        coinbase,
         AccountField::Balance,
         coinbase_balance,
         coinbase_balance_prev,
@@ -512,7 +584,12 @@ pub fn gen_end_tx_ops(state: &mut CircuitInputStateRef) -> Result<ExecStep, Erro
         log_id as u64,
     )?;
 
-    if state.tx_ctx.id() > 1 {
+    let is_first_tx_in_block = state
+        .block
+        .txs
+        .last()
+        .map_or(true, |prev_tx| prev_tx.block_num != state.tx.block_num);
+    if !is_first_tx_in_block {
         // query pre tx cumulative gas
         state.tx_receipt_read(
             &mut exec_step,
@@ -541,127 +618,3 @@ pub fn gen_end_tx_ops(state: &mut CircuitInputStateRef) -> Result<ExecStep, Erro
 
     Ok(exec_step)
 }
-
-#[derive(Debug, Copy, Clone)]
-struct DummyCall;
-
-impl Opcode for DummyCall {
-    fn gen_associated_ops(
-        state: &mut CircuitInputStateRef,
-        geth_steps: &[GethExecStep],
-    ) -> Result<Vec<ExecStep>, Error> {
-        dummy_gen_call_ops(state, geth_steps)
-    }
-}
-
-fn dummy_gen_call_ops(
-    state: &mut CircuitInputStateRef,
-    geth_steps: &[GethExecStep],
-) -> Result<Vec<ExecStep>, Error> {
-    let geth_step = &geth_steps[0];
-    let mut exec_step = state.new_step(geth_step)?;
-
-    let (args_offset, args_length, ret_offset, ret_length) = {
-        // CALLCODE    (gas, addr, value, argsOffset, argsLength, retOffset, retLength)
-        // DELEGATECALL(gas, addr,        argsOffset, argsLength, retOffset, retLength)
-        // STATICCALL  (gas, addr,        argsOffset, argsLength, retOffset, retLength)
-        let pos = match geth_step.op {
-            OpcodeId::CALLCODE => (3, 4, 5, 6),
-            OpcodeId::DELEGATECALL | OpcodeId::STATICCALL => (2, 3, 4, 5),
-            _ => unreachable!("opcode is not of call type"),
-        };
-        (
-            geth_step.stack.nth_last(pos.0)?.as_usize(),
-            geth_step.stack.nth_last(pos.1)?.as_usize(),
-            geth_step.stack.nth_last(pos.2)?.as_usize(),
-            geth_step.stack.nth_last(pos.3)?.as_usize(),
-        )
-    };
-    state.call_expand_memory(args_offset, args_length, ret_offset, ret_length)?;
-
-    let tx_id = state.tx_ctx.id();
-    let call = state.parse_call(geth_step)?;
-
-    let (_, account) = state.sdb.get_account(&call.address);
-    let callee_code_hash = account.code_hash;
-
-    let is_warm = state.sdb.check_account_in_access_list(&call.address);
-    state.push_op_reversible(
-        &mut exec_step,
-        RW::WRITE,
-        TxAccessListAccountOp {
-            tx_id,
-            address: call.address,
-            is_warm: true,
-            is_warm_prev: is_warm,
-        },
-    )?;
-
-    state.push_call(call.clone());
-
-    match (
-        state.is_precompiled(&call.address),
-        callee_code_hash.to_fixed_bytes() == *EMPTY_HASH,
-    ) {
-        // 1. Call to precompiled.
-        (true, _) => Ok(vec![exec_step]),
-        // 2. Call to account with empty code.
-        (_, true) => {
-            state.handle_return(geth_step)?;
-            Ok(vec![exec_step])
-        }
-        // 3. Call to account with non-empty code.
-        (_, false) => Ok(vec![exec_step]),
-    }
-}
-
-#[derive(Debug, Copy, Clone)]
-struct DummySelfDestruct;
-
-impl Opcode for DummySelfDestruct {
-    fn gen_associated_ops(
-        state: &mut CircuitInputStateRef,
-        geth_steps: &[GethExecStep],
-    ) -> Result<Vec<ExecStep>, Error> {
-        dummy_gen_selfdestruct_ops(state, geth_steps)
-    }
-}
-fn dummy_gen_selfdestruct_ops(
-    state: &mut CircuitInputStateRef,
-    geth_steps: &[GethExecStep],
-) -> Result<Vec<ExecStep>, Error> {
-    let geth_step = &geth_steps[0];
-    let mut exec_step = state.new_step(geth_step)?;
-    let sender = state.call()?.address;
-    let receiver = geth_step.stack.last()?.to_address();
-
-    let is_warm = state.sdb.check_account_in_access_list(&receiver);
-    state.push_op_reversible(
-        &mut exec_step,
-        RW::WRITE,
-        TxAccessListAccountOp {
-            tx_id: state.tx_ctx.id(),
-            address: receiver,
-            is_warm: true,
-            is_warm_prev: is_warm,
-        },
-    )?;
-
-    let (found, _) = state.sdb.get_account(&receiver);
-    if !found {
-        return Err(Error::AccountNotFound(receiver));
-    }
-    let (found, sender_account) = state.sdb.get_account(&sender);
-    if !found {
-        return Err(Error::AccountNotFound(sender));
-    }
-    let value = sender_account.balance;
-    state.transfer(&mut exec_step, sender, receiver, value)?;
-
-    if state.call()?.is_persistent {
-        state.sdb.destruct_account(sender);
-    }
-
-    state.handle_return(geth_step)?;
-    Ok(vec![exec_step])
-}
@@ -0,0 +1,663 @@
+use super::Opcode;
+use crate::{
+    circuit_input_builder::{
+        CallKind, CircuitInputStateRef, CopyDataType, CopyEvent, ExecState, ExecStep, NumberOrHash,
+    },
+    operation::{AccountField, CallContextField, MemoryOp, TxAccessListAccountOp, RW},
+    precompile::{execute_precompiled, PrecompileCalls},
+    Error,
+};
+use eth_types::{
+    evm_types::{
+        gas_utils::{eip150_gas, memory_expansion_gas_cost},
+        Gas, GasCost, Memory, ProgramCounter, GAS_STIPEND_CALL_WITH_VALUE,
+    },
+    GethExecStep, ToWord, Word,
+};
+use keccak256::EMPTY_HASH;
+
+/// Placeholder structure used to implement [`Opcode`] trait over it
+/// corresponding to the `OpcodeId::CALL`, `OpcodeId::CALLCODE`,
+/// `OpcodeId::DELEGATECALL` and `OpcodeId::STATICCALL`.
+/// - CALL and CALLCODE: N_ARGS = 7
+/// - DELEGATECALL and STATICCALL: N_ARGS = 6
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct CallOpcode<const N_ARGS: usize>;
+
+impl<const N_ARGS: usize> Opcode for CallOpcode<N_ARGS> {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        geth_steps: &[GethExecStep],
+    ) -> Result<Vec<ExecStep>, Error> {
+        let geth_step = &geth_steps[0];
+        let mut exec_step = state.new_step(geth_step)?;
+
+        let args_offset = geth_step.stack.nth_last(N_ARGS - 4)?.as_usize();
+        let args_length = geth_step.stack.nth_last(N_ARGS - 3)?.as_usize();
+        let ret_offset = geth_step.stack.nth_last(N_ARGS - 2)?.as_usize();
+        let ret_length = geth_step.stack.nth_last(N_ARGS - 1)?.as_usize();
+
+        // we need to keep the memory until parse_call complete
+        state.call_expand_memory(args_offset, args_length, ret_offset, ret_length)?;
+
+        let tx_id = state.tx_ctx.id();
+        let call = state.parse_call(geth_step)?;
+        let current_call = state.call()?.clone();
+
+        // NOTE: For `RwCounterEndOfReversion` we use the `0` value as a placeholder,
+        // and later set the proper value in
+        // `CircuitInputBuilder::set_value_ops_call_context_rwc_eor`
+        for (field, value) in [
+            (CallContextField::TxId, tx_id.into()),
+            (CallContextField::RwCounterEndOfReversion, 0.into()),
+            (
+                CallContextField::IsPersistent,
+                (current_call.is_persistent as u64).into(),
+            ),
+            (
+                CallContextField::CalleeAddress,
+                current_call.address.to_word(),
+            ),
+            (
+                CallContextField::IsStatic,
+                (current_call.is_static as u64).into(),
+            ),
+            (CallContextField::Depth, current_call.depth.into()),
+        ] {
+            state.call_context_read(&mut exec_step, current_call.call_id, field, value);
+        }
+
+        // DELEGATECALL propagates the caller address and value of the current
+        // call to the callee.
+        if call.kind == CallKind::DelegateCall {
+            for (field, value) in [
+                (
+                    CallContextField::CallerAddress,
+                    current_call.caller_address.to_word(),
+                ),
+                (CallContextField::Value, current_call.value),
+            ] {
+                state.call_context_read(&mut exec_step, current_call.call_id, field, value);
+            }
+        }
+
+        for i in 0..N_ARGS {
+            state.stack_read(
+                &mut exec_step,
+                geth_step.stack.nth_last_filled(i),
+                geth_step.stack.nth_last(i)?,
+            )?;
+        }
+
+        state.stack_write(
+            &mut exec_step,
+            geth_step.stack.nth_last_filled(N_ARGS - 1),
+            (call.is_success as u64).into(),
+        )?;
+
+        // The address on the stack is the callee address for CALL and
+        // STATICCALL, and only the code address for CALLCODE and DELEGATECALL.
+        let code_address = geth_step.stack.nth_last(1)?.to_address();
+        let is_warm = state.sdb.check_account_in_access_list(&code_address);
+        state.push_op_reversible(
+            &mut exec_step,
+            RW::WRITE,
+            TxAccessListAccountOp {
+                tx_id,
+                address: code_address,
+                is_warm: true,
+                is_warm_prev: is_warm,
+            },
+        )?;
+
+        // Switch to callee's call context
+        state.push_call(call.clone());
+
+        for (field, value) in [
+            (CallContextField::RwCounterEndOfReversion, 0.into()),
+            (
+                CallContextField::IsPersistent,
+                (call.is_persistent as u64).into(),
+            ),
+        ] {
+            state.call_context_write(&mut exec_step, call.call_id, field, value);
+        }
+
+        // Only CALL and CALLCODE transfer value, DELEGATECALL just propagates
+        // the value of the current call.
+        let value = match call.kind {
+            CallKind::Call | CallKind::CallCode => call.value,
+            _ => Word::zero(),
+        };
+        state.transfer(&mut exec_step, current_call.address, call.address, value)?;
+
+        let (_, callee_account) = state.sdb.get_account(&code_address);
+        let is_empty_account = callee_account.is_empty();
+        let callee_nonce = callee_account.nonce;
+        let callee_code_hash = callee_account.code_hash;
+        for (field, value) in [
+            (AccountField::Nonce, callee_nonce),
+            (AccountField::CodeHash, callee_code_hash.to_word()),
+        ] {
+            state.account_read(&mut exec_step, code_address, field, value, value)?;
+        }
+
+        // Calculate next_memory_word_size and callee_gas_left manually in case
+        // there isn't next geth_step (e.g. callee doesn't have code).
+        debug_assert_eq!(exec_step.memory_size % 32, 0);
+        let curr_memory_word_size = (exec_step.memory_size as u64) / 32;
+        let next_memory_word_size = [
+            curr_memory_word_size,
+            (call.call_data_offset + call.call_data_length + 31) / 32,
+            (call.return_data_offset + call.return_data_length + 31) / 32,
+        ]
+        .into_iter()
+        .max()
+        .unwrap();
+
+        let has_value = !value.is_zero();
+        let memory_expansion_gas_cost =
+            memory_expansion_gas_cost(curr_memory_word_size, next_memory_word_size);
+        let gas_cost = if is_warm {
+            GasCost::WARM_ACCESS.as_u64()
+        } else {
+            GasCost::COLD_ACCOUNT_ACCESS.as_u64()
+        } + if has_value {
+            GasCost::CALL_WITH_VALUE.as_u64()
+                + if call.kind == CallKind::Call && is_empty_account {
+                    GasCost::NEW_ACCOUNT.as_u64()
+                } else {
+                    0
+                }
+        } else {
+            0
+        } + memory_expansion_gas_cost;
+        let gas_specified = geth_step.stack.last()?;
+        let callee_gas_left = eip150_gas(geth_step.gas.0 - gas_cost, gas_specified);
+
+        // There are 3 branches from here.
+        match (
+            state.is_precompiled(&code_address),
+            callee_code_hash.to_fixed_bytes() == *EMPTY_HASH,
+        ) {
+            // 1. Call to account with empty code.
+            (false, true) => {
+                for (field, value) in [
+                    (CallContextField::LastCalleeId, 0.into()),
+                    (CallContextField::LastCalleeReturnDataOffset, 0.into()),
+                    (CallContextField::LastCalleeReturnDataLength, 0.into()),
+                ] {
+                    state.call_context_write(&mut exec_step, current_call.call_id, field, value);
+                }
+                state.caller_ctx_mut()?.update_last_callee(0, 0, vec![]);
+                state.handle_return(geth_step)?;
+                Ok(vec![exec_step])
+            }
+            // 2. Call to precompiled or account with non-empty code, which
+            // switches to the callee's context.
+            (is_precompile, _) => {
+                for (field, value) in [
+                    (
+                        CallContextField::ProgramCounter,
+                        (geth_step.pc.0 + 1).into(),
+                    ),
+                    (
+                        CallContextField::StackPointer,
+                        (geth_step.stack.stack_pointer().0 + N_ARGS - 1).into(),
+                    ),
+                    (
+                        CallContextField::GasLeft,
+                        (geth_step.gas.0 - gas_cost - callee_gas_left).into(),
+                    ),
+                    (CallContextField::MemorySize, next_memory_word_size.into()),
+                    (
+                        CallContextField::ReversibleWriteCounter,
+                        (exec_step.reversible_write_counter + 1).into(),
+                    ),
+                ] {
+                    state.call_context_write(&mut exec_step, current_call.call_id, field, value);
+                }
+
+                for (field, value) in [
+                    (CallContextField::CallerId, current_call.call_id.into()),
+                    (CallContextField::TxId, tx_id.into()),
+                    (CallContextField::Depth, call.depth.into()),
+                    (
+                        CallContextField::CallerAddress,
+                        call.caller_address.to_word(),
+                    ),
+                    (CallContextField::CalleeAddress, call.address.to_word()),
+                    (
+                        CallContextField::CallDataOffset,
+                        call.call_data_offset.into(),
+                    ),
+                    (
+                        CallContextField::CallDataLength,
+                        call.call_data_length.into(),
+                    ),
+                    (
+                        CallContextField::ReturnDataOffset,
+                        call.return_data_offset.into(),
+                    ),
+                    (
+                        CallContextField::ReturnDataLength,
+                        call.return_data_length.into(),
+                    ),
+                    (CallContextField::Value, call.value),
+                    (CallContextField::IsSuccess, (call.is_success as u64).into()),
+                    (CallContextField::IsStatic, (call.is_static as u64).into()),
+                    (CallContextField::LastCalleeId, 0.into()),
+                    (CallContextField::LastCalleeReturnDataOffset, 0.into()),
+                    (CallContextField::LastCalleeReturnDataLength, 0.into()),
+                    (CallContextField::IsRoot, 0.into()),
+                    (CallContextField::IsCreate, 0.into()),
+                    (CallContextField::CodeHash, call.code_hash.to_word()),
+                ] {
+                    state.call_context_write(&mut exec_step, call.call_id, field, value);
+                }
+
+                // 3. Call to precompiled, which is executed in a virtual step
+                // right after the call, and then returns to the caller.
+                if is_precompile {
+                    let precompile_gas_left = callee_gas_left
+                        + if has_value {
+                            GAS_STIPEND_CALL_WITH_VALUE
+                        } else {
+                            0
+                        };
+                    let precompile_step = gen_precompile_step(
+                        state,
+                        &mut exec_step,
+                        geth_steps,
+                        PrecompileCalls::from(code_address.0[19]),
+                        precompile_gas_left,
+                    )?;
+                    return Ok(vec![exec_step, precompile_step]);
+                }
+
+                Ok(vec![exec_step])
+            }
+        }
+    }
+}
+
+/// Generate the virtual step which executes the precompiled contract of the
+/// current call with the given gas, and then returns to the caller.  The
+/// reversion of the operations of `call_step` is recorded in it in case the
+/// precompile fails.
+fn gen_precompile_step(
+    state: &mut CircuitInputStateRef,
+    call_step: &mut ExecStep,
+    geth_steps: &[GethExecStep],
+    precompile: PrecompileCalls,
+    gas_left: u64,
+) -> Result<ExecStep, Error> {
+    let call = state.call()?.clone();
+    let caller = state.caller()?.clone();
+    let input = state.call_ctx()?.call_data.clone();
+
+    // A failed precompile returns nothing and consumes all the gas.
+    let (output, gas_cost) = if call.is_success {
+        execute_precompiled(precompile, &input, gas_left)
+    } else {
+        (vec![], gas_left)
+    };
+
+    let mut exec_step = ExecStep {
+        exec_state: ExecState::Precompile(precompile),
+        pc: ProgramCounter(0),
+        stack_size: 0,
+        memory_size: 0,
+        gas_left: Gas(gas_left),
+        gas_cost: GasCost(gas_cost),
+        ..state.new_step(&geth_steps[0])?
+    };
+
+    for (field, value) in [
+        (CallContextField::CallerId, caller.call_id.into()),
+        (
+            CallContextField::CallDataOffset,
+            call.call_data_offset.into(),
+        ),
+        (
+            CallContextField::CallDataLength,
+            call.call_data_length.into(),
+        ),
+        (
+            CallContextField::ReturnDataOffset,
+            call.return_data_offset.into(),
+        ),
+        (
+            CallContextField::ReturnDataLength,
+            call.return_data_length.into(),
+        ),
+        (CallContextField::IsSuccess, (call.is_success as u64).into()),
+    ] {
+        state.call_context_read(&mut exec_step, call.call_id, field, value);
+    }
+
+    // Copy the input from the caller's memory.
+    if !input.is_empty() {
+        let rw_counter_start = state.block_ctx.rwc;
+        for (i, byte) in input.iter().enumerate() {
+            state.push_op(
+                &mut exec_step,
+                RW::READ,
+                MemoryOp::new(
+                    caller.call_id,
+                    (call.call_data_offset as usize + i).into(),
+                    *byte,
+                ),
+            );
+        }
+        state.push_copy(CopyEvent {
+            rw_counter_start,
+            src_type: CopyDataType::Memory,
+            src_id: NumberOrHash::Number(caller.call_id),
+            src_addr: call.call_data_offset,
+            src_addr_end: call.call_data_offset + call.call_data_length,
+            dst_type: CopyDataType::RlcAcc,
+            dst_id: NumberOrHash::Number(call.call_id),
+            dst_addr: 0,
+            log_id: None,
+            bytes: input.iter().map(|byte| (*byte, false)).collect(),
+        });
+    }
+
+    // Write the output to the callee's memory, where it's kept as the return
+    // data of the call.
+    if !output.is_empty() {
+        let rw_counter_start = state.block_ctx.rwc;
+        for (i, byte) in output.iter().enumerate() {
+            state.memory_write(&mut exec_step, i.into(), *byte)?;
+        }
+        state.push_copy(CopyEvent {
+            rw_counter_start,
+            src_type: CopyDataType::RlcAcc,
+            src_id: NumberOrHash::Number(call.call_id),
+            src_addr: 0,
+            src_addr_end: output.len() as u64,
+            dst_type: CopyDataType::Memory,
+            dst_id: NumberOrHash::Number(call.call_id),
+            dst_addr: 0,
+            log_id: None,
+            bytes: output.iter().map(|byte| (*byte, false)).collect(),
+        });
+        state.call_ctx_mut()?.memory = Memory::from(output.clone());
+    }
+
+    // Copy the output to the caller's memory, up to the requested length.
+    let copy_length = std::cmp::min(call.return_data_length as usize, output.len());
+    if copy_length > 0 {
+        let return_offset = call.return_data_offset as usize;
+        state.caller_ctx_mut()?.memory.0[return_offset..return_offset + copy_length]
+            .copy_from_slice(&output[..copy_length]);
+
+        let rw_counter_start = state.block_ctx.rwc;
+        for (i, byte) in output[..copy_length].iter().enumerate() {
+            state.push_op(
+                &mut exec_step,
+                RW::READ,
+                MemoryOp::new(call.call_id, i.into(), *byte),
+            );
+            state.push_op(
+                &mut exec_step,
+                RW::WRITE,
+                MemoryOp::new(caller.call_id, (return_offset + i).into(), *byte),
+            );
+        }
+        state.push_copy(CopyEvent {
+            rw_counter_start,
+            src_type: CopyDataType::Memory,
+            src_id: NumberOrHash::Number(call.call_id),
+            src_addr: 0,
+            src_addr_end: output.len() as u64,
+            dst_type: CopyDataType::Memory,
+            dst_id: NumberOrHash::Number(caller.call_id),
+            dst_addr: call.return_data_offset,
+            log_id: None,
+            bytes: output[..copy_length]
+                .iter()
+                .map(|byte| (*byte, false))
+                .collect(),
+        });
+    }
+
+    // Restore the caller's context, which gets back the gas not consumed by
+    // the precompile.
+    state.call_context_read(
+        &mut exec_step,
+        call.call_id,
+        CallContextField::CallerId,
+        caller.call_id.into(),
+    );
+    let geth_step_next = &geth_steps[1];
+    let caller_gas_left = geth_step_next.gas.0 - (gas_left - gas_cost);
+    for (field, value) in [
+        (CallContextField::IsRoot, (caller.is_root as u64).into()),
+        (
+            CallContextField::IsCreate,
+            (caller.is_create() as u64).into(),
+        ),
+        (CallContextField::CodeHash, caller.code_hash.to_word()),
+        (CallContextField::ProgramCounter, geth_step_next.pc.0.into()),
+        (
+            CallContextField::StackPointer,
+            geth_step_next.stack.stack_pointer().0.into(),
+        ),
+        (CallContextField::GasLeft, caller_gas_left.into()),
+        (
+            CallContextField::MemorySize,
+            state.caller_ctx()?.memory.word_size().into(),
+        ),
+        (
+            CallContextField::ReversibleWriteCounter,
+            state.caller_ctx()?.reversible_write_counter.into(),
+        ),
+    ] {
+        state.call_context_read(&mut exec_step, caller.call_id, field, value);
+    }
+
+    for (field, value) in [
+        (CallContextField::LastCalleeId, call.call_id.into()),
+        (CallContextField::LastCalleeReturnDataOffset, 0.into()),
+        (
+            CallContextField::LastCalleeReturnDataLength,
+            output.len().into(),
+        ),
+    ] {
+        state.call_context_write(&mut exec_step, caller.call_id, field, value);
+    }
+    state
+        .caller_ctx_mut()?
+        .update_last_callee(call.call_id, 0, output);
+
+    state.handle_return_with_steps(&mut [call_step, &mut exec_step], &geth_steps[0])?;
+    Ok(exec_step)
+}
+
+#[cfg(test)]
+mod callop_tests {
+    use crate::{
+        circuit_input_builder::{CircuitInputBuilder, CopyDataType, ExecState},
+        mock::BlockData,
+        operation::{AccountField, AccountOp, RW},
+        precompile::PrecompileCalls,
+    };
+    use eth_types::{
+        address, bytecode, evm_types::OpcodeId, geth_types::GethData, word, Bytecode, Word,
+    };
+    use mock::TestContext;
+    use pretty_assertions::assert_eq;
+
+    fn build_block(code: Bytecode) -> CircuitInputBuilder {
+        let block: GethData = TestContext::<2, 1>::new(
+            None,
+            |accs| {
+                accs[0]
+                    .address(address!("0x0000000000000000000000000000000000000010"))
+                    .balance(Word::from(1u64 << 20))
+                    .code(code);
+                accs[1]
+                    .address(address!("0x0000000000000000000000000000000000cafe01"))
+                    .balance(Word::from(1u64 << 20));
+            },
+            |mut txs, accs| {
+                txs[0].to(accs[0].address).from(accs[1].address);
+            },
+            |block, _tx| block.number(0xcafeu64),
+        )
+        .unwrap()
+        .into();
+
+        let mut builder = BlockData::new_from_geth_data(block.clone()).new_circuit_input_builder();
+        builder
+            .handle_block(&block.eth_block, &block.geth_traces)
+            .unwrap();
+        builder
+    }
+
+    #[test]
+    fn call_precompile_identity() {
+        let code = bytecode! {
+            PUSH32(word!("0x0102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f20"))
+            PUSH1(0x00)
+            MSTORE
+            PUSH1(0x10) // retLength
+            PUSH1(0x20) // retOffset
+            PUSH1(0x20) // argsLength
+            PUSH1(0x00) // argsOffset
+            PUSH1(0x04) // address
+            PUSH2(0xffff) // gas
+            STATICCALL
+            STOP
+        };
+        let builder = build_block(code);
+
+        let step = builder.block.txs()[0]
+            .steps()
+            .iter()
+            .find(|step| step.exec_state == ExecState::Precompile(PrecompileCalls::Identity))
+            .unwrap();
+        // Base cost plus 3 gas per word of input.
+        assert_eq!(step.gas_cost.as_u64(), 15 + 3);
+
+        let input: Vec<_> = (1..=0x20u8).collect();
+        let copy_events = &builder.block.copy_events;
+        // The input is read from the caller's memory, the output is written to
+        // the callee's memory as the return data, and then the requested part
+        // of it is copied back to the caller.
+        assert_eq!(copy_events.len(), 3);
+        for (copy_event, (src_type, dst_type, bytes)) in copy_events.iter().zip([
+            (CopyDataType::Memory, CopyDataType::RlcAcc, &input[..]),
+            (CopyDataType::RlcAcc, CopyDataType::Memory, &input[..]),
+            (CopyDataType::Memory, CopyDataType::Memory, &input[..0x10]),
+        ]) {
+            assert_eq!(copy_event.src_type, src_type);
+            assert_eq!(copy_event.dst_type, dst_type);
+            assert_eq!(
+                copy_event
+                    .bytes
+                    .iter()
+                    .map(|(byte, _)| *byte)
+                    .collect::<Vec<_>>(),
+                bytes
+            );
+        }
+    }
+
+    #[test]
+    fn call_precompile_sha256() {
+        let code = bytecode! {
+            PUSH1(0x20) // retLength
+            PUSH1(0x00) // retOffset
+            PUSH1(0x00) // argsLength
+            PUSH1(0x00) // argsOffset
+            PUSH1(0x02) // address
+            PUSH2(0xffff) // gas
+            STATICCALL
+            STOP
+        };
+        let builder = build_block(code);
+
+        // The precompile step follows the CALL step which it returns from.
+        let steps = builder.block.txs()[0].steps();
+        let idx = steps
+            .iter()
+            .position(|step| step.exec_state == ExecState::Precompile(PrecompileCalls::Sha256))
+            .unwrap();
+        assert_eq!(
+            steps[idx - 1].exec_state,
+            ExecState::Op(OpcodeId::STATICCALL)
+        );
+        assert_eq!(steps[idx].gas_cost.as_u64(), 60);
+
+        // The hash of the empty input is written to the callee's memory and
+        // copied back to the caller.
+        let output = word!("0xe3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855");
+        let mut output_bytes = [0u8; 32];
+        output.to_big_endian(&mut output_bytes);
+        let copy_events = &builder.block.copy_events;
+        assert_eq!(copy_events.len(), 2);
+        for copy_event in copy_events {
+            assert_eq!(
+                copy_event
+                    .bytes
+                    .iter()
+                    .map(|(byte, _)| *byte)
+                    .collect::<Vec<_>>(),
+                output_bytes
+            );
+        }
+    }
+
+    #[test]
+    fn call_precompile_failure() {
+        // Add the invalid point (1, 1) on the bn256 curve with value, so the
+        // transfer is reverted when the precompile fails.
+        let code = bytecode! {
+            PUSH1(0x01)
+            PUSH1(0x1f)
+            MSTORE8
+            PUSH1(0x01)
+            PUSH1(0x3f)
+            MSTORE8
+            PUSH1(0x00) // retLength
+            PUSH1(0x00) // retOffset
+            PUSH1(0x80) // argsLength
+            PUSH1(0x00) // argsOffset
+            PUSH1(0x01) // value
+            PUSH1(0x06) // address
+            PUSH2(0xffff) // gas
+            CALL
+            STOP
+        };
+        let builder = build_block(code);
+
+        let transaction = &builder.block.txs()[0];
+        assert!(!transaction.calls()[1].is_success);
+        let steps = transaction.steps();
+        let idx = steps
+            .iter()
+            .position(|step| step.exec_state == ExecState::Precompile(PrecompileCalls::Bn256Add))
+            .unwrap();
+        assert_eq!(steps[idx].gas_cost.as_u64(), steps[idx].gas_left.0);
+
+        // The reversion of the transfer is recorded in the CALL step.
+        let call_step = &steps[idx - 1];
+        assert_eq!(call_step.exec_state, ExecState::Op(OpcodeId::CALL));
+        let container = &builder.block.container;
+        let operation =
+            &container.account[call_step.bus_mapping_instance.last().unwrap().as_usize()];
+        assert_eq!(operation.rw(), RW::WRITE);
+        assert_eq!(
+            operation.op(),
+            &AccountOp {
+                address: address!("0x0000000000000000000000000000000000000010"),
+                field: AccountField::Balance,
+                value: Word::from(1u64 << 20),
+                value_prev: Word::from((1u64 << 20) - 1),
+            }
+        );
+    }
+}
@@ -1,24 +1,36 @@
-use crate::circuit_input_builder::{CircuitInputStateRef, ExecStep};
+use crate::circuit_input_builder::{
+    CircuitInputStateRef, CopyDataType, CopyEvent, ExecStep, NumberOrHash,
+};
+use crate::error::ExecError;
 use crate::evm::Opcode;
-use crate::operation::{AccountField, AccountOp, TxAccessListAccountOp, RW};
+use crate::operation::{AccountField, AccountOp, CallContextField, TxAccessListAccountOp, RW};
 use crate::Error;
-use eth_types::GethExecStep;
+use eth_types::{
+    evm_types::{gas_utils::memory_expansion_gas_cost, GasCost},
+    GethExecStep, ToBigEndian, ToWord, Word,
+};
+use ethers_core::utils::{keccak256, rlp};
 use keccak256::EMPTY_HASH;
 
+/// Placeholder structure used to implement [`Opcode`] trait over it
+/// corresponding to the `OpcodeId::CREATE` and `OpcodeId::CREATE2`
+/// `OpcodeId`s.
 #[derive(Debug, Copy, Clone)]
-pub struct DummyCreate<const IS_CREATE2: bool>;
+pub(crate) struct Create<const IS_CREATE2: bool>;
 
-impl<const IS_CREATE2: bool> Opcode for DummyCreate<IS_CREATE2> {
+impl<const IS_CREATE2: bool> Opcode for Create<IS_CREATE2> {
     fn gen_associated_ops(
         state: &mut CircuitInputStateRef,
         geth_steps: &[GethExecStep],
     ) -> Result<Vec<ExecStep>, Error> {
-        // TODO: replace dummy create here
         let geth_step = &geth_steps[0];
+        let mut exec_step = state.new_step(geth_step)?;
 
+        let n_pop = if IS_CREATE2 { 4 } else { 3 };
         let offset = geth_step.stack.nth_last(1)?.as_usize();
         let length = geth_step.stack.nth_last(2)?.as_usize();
 
+        // we need to keep the memory until parse_call complete
         if length != 0 {
             state
                 .call_ctx_mut()?
@@ -26,10 +38,75 @@ impl<const IS_CREATE2: bool> Opcode for DummyCreate<IS_CREATE2> {
                 .extend_at_least(offset + length);
         }
 
-        let mut exec_step = state.new_step(geth_step)?;
-
         let tx_id = state.tx_ctx.id();
+        // The address of the created account depends on the caller's nonce
+        // before it is increased, so the call must be parsed first.
         let call = state.parse_call(geth_step)?;
+        let current_call = state.call()?.clone();
+
+        // NOTE: For `RwCounterEndOfReversion` we use the `0` value as a placeholder,
+        // and later set the proper value in
+        // `CircuitInputBuilder::set_value_ops_call_context_rwc_eor`
+        for (field, value) in [
+            (CallContextField::TxId, tx_id.into()),
+            (CallContextField::RwCounterEndOfReversion, 0.into()),
+            (
+                CallContextField::IsPersistent,
+                (current_call.is_persistent as u64).into(),
+            ),
+            (
+                CallContextField::CalleeAddress,
+                current_call.address.to_word(),
+            ),
+            (
+                CallContextField::IsStatic,
+                (current_call.is_static as u64).into(),
+            ),
+            (CallContextField::Depth, current_call.depth.into()),
+        ] {
+            state.call_context_read(&mut exec_step, current_call.call_id, field, value);
+        }
+
+        for i in 0..n_pop {
+            state.stack_read(
+                &mut exec_step,
+                geth_step.stack.nth_last_filled(i),
+                geth_step.stack.nth_last(i)?,
+            )?;
+        }
+
+        let address = if call.is_success {
+            call.address.to_word()
+        } else {
+            Word::zero()
+        };
+        state.stack_write(
+            &mut exec_step,
+            geth_step.stack.nth_last_filled(n_pop - 1),
+            address,
+        )?;
+
+        // Read the init code from memory, its keccak hash is the code hash of
+        // the new call.
+        let init_code = state.call_ctx()?.memory.0[offset..offset + length].to_vec();
+        if length != 0 {
+            let rw_counter_start = state.block_ctx.rwc;
+            for (i, byte) in init_code.iter().enumerate() {
+                state.memory_read(&mut exec_step, (offset + i).into(), *byte)?;
+            }
+            state.push_copy(CopyEvent {
+                src_addr: offset as u64,
+                src_addr_end: (offset + length) as u64,
+                src_type: CopyDataType::Memory,
+                src_id: NumberOrHash::Number(current_call.call_id),
+                dst_addr: 0,
+                dst_type: CopyDataType::RlcAcc,
+                dst_id: NumberOrHash::Number(current_call.call_id),
+                log_id: None,
+                rw_counter_start,
+                bytes: init_code.iter().map(|byte| (*byte, false)).collect(),
+            });
+        }
 
         // Quote from [EIP-2929](https://eips.ethereum.org/EIPS/eip-2929)
         // > When a CREATE or CREATE2 opcode is called,
@@ -37,54 +114,96 @@ impl<const IS_CREATE2: bool> Opcode for DummyCreate<IS_CREATE2> {
         // > whether or not the address is unclaimed)
         // > add the address being created to accessed_addresses,
         // > but gas costs of CREATE and CREATE2 are unchanged
-        let address = if IS_CREATE2 {
-            state.create2_address(&geth_steps[0])?
-        } else {
-            state.create_address()?
-        };
-        let is_warm = state.sdb.check_account_in_access_list(&address);
+        let is_warm = state.sdb.check_account_in_access_list(&call.address);
         state.push_op_reversible(
             &mut exec_step,
             RW::WRITE,
             TxAccessListAccountOp {
-                tx_id: state.tx_ctx.id(),
-                address,
+                tx_id,
+                address: call.address,
                 is_warm: true,
                 is_warm_prev: is_warm,
             },
         )?;
 
         // Increase caller's nonce
-        let nonce_prev = state.sdb.get_nonce(&call.caller_address);
+        let caller_nonce = state.sdb.get_nonce(&call.caller_address);
         state.push_op_reversible(
             &mut exec_step,
             RW::WRITE,
             AccountOp {
                 address: call.caller_address,
                 field: AccountField::Nonce,
-                value: (nonce_prev + 1).into(),
-                value_prev: nonce_prev.into(),
+                value: (caller_nonce + 1).into(),
+                value_prev: caller_nonce.into(),
             },
         )?;
 
-        // Add callee into access list
-        let is_warm = state.sdb.check_account_in_access_list(&call.address);
-        state.push_op_reversible(
-            &mut exec_step,
-            RW::WRITE,
-            TxAccessListAccountOp {
-                tx_id,
-                address: call.address,
-                is_warm: true,
-                is_warm_prev: is_warm,
-            },
-        )?;
+        // Record the preimages of the init code hash and of the new address,
+        // which are looked up in the keccak table.
+        let address_preimage = if IS_CREATE2 {
+            let salt = geth_step.stack.nth_last(3)?;
+            [
+                &[0xff],
+                call.caller_address.as_bytes(),
+                &salt.to_be_bytes(),
+                &keccak256(&init_code),
+            ]
+            .concat()
+        } else {
+            let mut stream = rlp::RlpStream::new();
+            stream.begin_list(2);
+            stream.append(&call.caller_address);
+            stream.append(&caller_nonce);
+            stream.out().to_vec()
+        };
+        state.block.sha3_inputs.push(init_code);
+        state.block.sha3_inputs.push(address_preimage);
+
+        // The creation fails when the new address already has a nonce or
+        // code (EIP-684). The caller's nonce is still increased, but the gas
+        // passed to the callee is consumed and the callee is never executed.
+        let (_, callee_account) = state.sdb.get_account(&call.address);
+        let callee_nonce = callee_account.nonce;
+        let callee_code_hash = callee_account.code_hash;
+        if !callee_nonce.is_zero()
+            || !(callee_code_hash.is_zero() || callee_code_hash.to_fixed_bytes() == *EMPTY_HASH)
+        {
+            exec_step.error = Some(ExecError::ContractAddressCollision);
+            for (field, value) in [
+                (AccountField::Nonce, callee_nonce),
+                (AccountField::CodeHash, callee_code_hash.to_word()),
+            ] {
+                state.account_read(&mut exec_step, call.address, field, value, value)?;
+            }
+            for field in [
+                CallContextField::LastCalleeId,
+                CallContextField::LastCalleeReturnDataOffset,
+                CallContextField::LastCalleeReturnDataLength,
+            ] {
+                state.call_context_write(&mut exec_step, current_call.call_id, field, 0.into());
+            }
 
+            state.push_call(call);
+            state.caller_ctx_mut()?.update_last_callee(0, 0, vec![]);
+            state.handle_return(geth_step)?;
+            return Ok(vec![exec_step]);
+        }
+
+        // Switch to callee's call context
         state.push_call(call.clone());
 
+        for (field, value) in [
+            (CallContextField::RwCounterEndOfReversion, 0.into()),
+            (
+                CallContextField::IsPersistent,
+                (call.is_persistent as u64).into(),
+            ),
+        ] {
+            state.call_context_write(&mut exec_step, call.call_id, field, value);
+        }
+
         // Increase callee's nonce
-        let nonce_prev = state.sdb.get_nonce(&call.address);
-        debug_assert!(nonce_prev == 0);
         state.push_op_reversible(
             &mut exec_step,
             RW::WRITE,
@@ -103,13 +222,408 @@ impl<const IS_CREATE2: bool> Opcode for DummyCreate<IS_CREATE2> {
             call.value,
         )?;
 
+        // Calculate next_memory_word_size and callee_gas_left manually in case
+        // there isn't next geth_step (e.g. init code is empty).
+        debug_assert_eq!(exec_step.memory_size % 32, 0);
+        let curr_memory_word_size = (exec_step.memory_size as u64) / 32;
+        let next_memory_word_size = if length == 0 {
+            curr_memory_word_size
+        } else {
+            std::cmp::max(curr_memory_word_size, (offset + length + 31) as u64 / 32)
+        };
+        let init_code_word_size = (length as u64 + 31) / 32;
+        let gas_cost = GasCost::CREATE.as_u64()
+            + memory_expansion_gas_cost(curr_memory_word_size, next_memory_word_size)
+            + if IS_CREATE2 {
+                GasCost::COPY_SHA3.as_u64() * init_code_word_size
+            } else {
+                0
+            };
+        // Apply EIP 150, the callee gets all but one 64th of the gas left.
+        let gas_available = geth_step.gas.0 - gas_cost;
+        let callee_gas_left = gas_available - gas_available / 64;
+
+        // There are 2 branches from here.
         if call.code_hash.to_fixed_bytes() == *EMPTY_HASH {
-            // 1. Create with empty initcode.
+            // 1. Create with empty init code.
+            for (field, value) in [
+                (CallContextField::LastCalleeId, 0.into()),
+                (CallContextField::LastCalleeReturnDataOffset, 0.into()),
+                (CallContextField::LastCalleeReturnDataLength, 0.into()),
+            ] {
+                state.call_context_write(&mut exec_step, current_call.call_id, field, value);
+            }
+            state.caller_ctx_mut()?.update_last_callee(0, 0, vec![]);
             state.handle_return(geth_step)?;
-            Ok(vec![exec_step])
         } else {
-            // 2. Create with non-empty initcode.
-            Ok(vec![exec_step])
+            // 2. Create with non-empty init code.
+            for (field, value) in [
+                (
+                    CallContextField::ProgramCounter,
+                    (geth_step.pc.0 + 1).into(),
+                ),
+                (
+                    CallContextField::StackPointer,
+                    (geth_step.stack.stack_pointer().0 + n_pop - 1).into(),
+                ),
+                (
+                    CallContextField::GasLeft,
+                    (geth_step.gas.0 - gas_cost - callee_gas_left).into(),
+                ),
+                (CallContextField::MemorySize, next_memory_word_size.into()),
+                (
+                    CallContextField::ReversibleWriteCounter,
+                    (exec_step.reversible_write_counter + 2).into(),
+                ),
+            ] {
+                state.call_context_write(&mut exec_step, current_call.call_id, field, value);
+            }
+
+            for (field, value) in [
+                (CallContextField::CallerId, current_call.call_id.into()),
+                (CallContextField::TxId, tx_id.into()),
+                (CallContextField::Depth, call.depth.into()),
+                (
+                    CallContextField::CallerAddress,
+                    call.caller_address.to_word(),
+                ),
+                (CallContextField::CalleeAddress, call.address.to_word()),
+                (CallContextField::CallDataOffset, 0.into()),
+                (CallContextField::CallDataLength, 0.into()),
+                (CallContextField::ReturnDataOffset, 0.into()),
+                (CallContextField::ReturnDataLength, 0.into()),
+                (CallContextField::Value, call.value),
+                (CallContextField::IsSuccess, (call.is_success as u64).into()),
+                (CallContextField::IsStatic, (call.is_static as u64).into()),
+                (CallContextField::LastCalleeId, 0.into()),
+                (CallContextField::LastCalleeReturnDataOffset, 0.into()),
+                (CallContextField::LastCalleeReturnDataLength, 0.into()),
+                (CallContextField::IsRoot, 0.into()),
+                (CallContextField::IsCreate, 1.into()),
+                (CallContextField::CodeHash, call.code_hash.to_word()),
+            ] {
+                state.call_context_write(&mut exec_step, call.call_id, field, value);
+            }
         }
+
+        Ok(vec![exec_step])
+    }
+}
+
+#[cfg(test)]
+mod create_tests {
+    use super::*;
+    use crate::{
+        circuit_input_builder::ExecState,
+        mock::BlockData,
+        operation::{CallContextOp, MemoryOp, StackOp},
+    };
+    use eth_types::{
+        address, bytecode,
+        evm_types::{OpcodeId, StackAddress},
+        geth_types::GethData,
+    };
+    use ethers_core::utils::{get_contract_address, get_create2_address};
+    use mock::{eth, TestContext};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn create_opcode_impl() {
+        test_ok(None);
+    }
+
+    #[test]
+    fn create2_opcode_impl() {
+        test_ok(Some(Word::from(0xcafe)));
+    }
+
+    #[test]
+    fn create_address_collision() {
+        let creator_address = address!("0x0000000000000000000000000000000000000010");
+        // The address of the first contract created by the creator is
+        // already used by an account with a nonce.
+        let collided_address = get_contract_address(creator_address, Word::one());
+        let code = bytecode! {
+            PUSH1(0x00) // length
+            PUSH1(0x00) // offset
+            PUSH1(0x00) // value
+            CREATE
+            STOP
+        };
+
+        let block: GethData = TestContext::<3, 1>::new(
+            None,
+            |accs| {
+                accs[0]
+                    .address(creator_address)
+                    .balance(eth(10))
+                    .nonce(Word::one())
+                    .code(code);
+                accs[1]
+                    .address(address!("0x0000000000000000000000000000000000cafe01"))
+                    .balance(eth(10));
+                accs[2].address(collided_address).nonce(Word::one());
+            },
+            |mut txs, accs| {
+                txs[0].to(accs[0].address).from(accs[1].address);
+            },
+            |block, _tx| block.number(0xcafeu64),
+        )
+        .unwrap()
+        .into();
+
+        let mut builder = BlockData::new_from_geth_data(block.clone()).new_circuit_input_builder();
+        builder
+            .handle_block(&block.eth_block, &block.geth_traces)
+            .unwrap();
+
+        let tx_id = 1;
+        let transaction = &builder.block.txs()[tx_id - 1];
+        let call_id = transaction.calls()[0].call_id;
+        let callee = &transaction.calls()[1];
+        assert_eq!(callee.address, collided_address);
+        assert!(callee.is_create() && !callee.is_success);
+
+        let step = transaction
+            .steps()
+            .iter()
+            .find(|step| step.exec_state == ExecState::Op(OpcodeId::CREATE))
+            .unwrap();
+        assert_eq!(step.error, Some(ExecError::ContractAddressCollision));
+        let indices = &step.bus_mapping_instance;
+        let container = &builder.block.container;
+
+        // The result of the creation is 0.
+        assert_eq!(
+            {
+                let operation = &container.stack[indices[9].as_usize()];
+                (operation.rw(), operation.op())
+            },
+            (
+                RW::WRITE,
+                &StackOp {
+                    call_id,
+                    address: StackAddress::from(1023u32),
+                    value: Word::zero()
+                }
+            )
+        );
+        // The caller's nonce is still increased, and the nonce of the
+        // collided account is read.
+        for (idx, rw, address, value, value_prev) in [
+            (11, RW::WRITE, creator_address, 2, 1),
+            (12, RW::READ, collided_address, 1, 1),
+        ] {
+            assert_eq!(
+                {
+                    let operation = &container.account[indices[idx].as_usize()];
+                    (operation.rw(), operation.op())
+                },
+                (
+                    rw,
+                    &AccountOp {
+                        address,
+                        field: AccountField::Nonce,
+                        value: value.into(),
+                        value_prev: value_prev.into(),
+                    }
+                )
+            );
+        }
+        // The return data of the caller is cleared.
+        assert_eq!(
+            {
+                let operation = &container.call_context[indices.last().unwrap().as_usize()];
+                (operation.rw(), operation.op())
+            },
+            (
+                RW::WRITE,
+                &CallContextOp {
+                    call_id,
+                    field: CallContextField::LastCalleeReturnDataLength,
+                    value: Word::zero(),
+                }
+            )
+        );
+    }
+
+    fn test_ok(salt: Option<Word>) {
+        // Init code which deploys a contract with empty code.
+        let init_code = bytecode! {
+            PUSH1(0x00) // length
+            PUSH1(0x00) // offset
+            RETURN
+        }
+        .to_vec();
+        let mut init_code_word = [0u8; 32];
+        init_code_word[..init_code.len()].copy_from_slice(&init_code);
+
+        let creator_address = address!("0x0000000000000000000000000000000000000010");
+        let mut code = bytecode! {
+            PUSH32(Word::from_big_endian(&init_code_word))
+            PUSH1(0x00)
+            MSTORE
+        };
+        if let Some(salt) = salt {
+            code.push(32, salt);
+        }
+        code.append(&bytecode! {
+            PUSH1(init_code.len()) // length
+            PUSH1(0x00) // offset
+            PUSH1(0x00) // value
+        });
+        code.write_op(if salt.is_some() {
+            OpcodeId::CREATE2
+        } else {
+            OpcodeId::CREATE
+        });
+        code.append(&bytecode! { STOP });
+
+        let block: GethData = TestContext::<2, 1>::new(
+            None,
+            |accs| {
+                accs[0]
+                    .address(creator_address)
+                    .balance(eth(10))
+                    .nonce(Word::one())
+                    .code(code);
+                accs[1]
+                    .address(address!("0x0000000000000000000000000000000000cafe01"))
+                    .balance(eth(10));
+            },
+            |mut txs, accs| {
+                txs[0].to(accs[0].address).from(accs[1].address);
+            },
+            |block, _tx| block.number(0xcafeu64),
+        )
+        .unwrap()
+        .into();
+
+        let mut builder = BlockData::new_from_geth_data(block.clone()).new_circuit_input_builder();
+        builder
+            .handle_block(&block.eth_block, &block.geth_traces)
+            .unwrap();
+
+        let expected_address = match salt {
+            Some(salt) => get_create2_address(
+                creator_address,
+                salt.to_be_bytes().to_vec(),
+                init_code.clone(),
+            ),
+            None => get_contract_address(creator_address, Word::one()),
+        };
+
+        let tx_id = 1;
+        let transaction = &builder.block.txs()[tx_id - 1];
+        let call_id = transaction.calls()[0].call_id;
+        let callee = &transaction.calls()[1];
+        assert_eq!(callee.address, expected_address);
+        assert!(callee.is_create() && callee.is_success);
+
+        let opcode = if salt.is_some() {
+            OpcodeId::CREATE2
+        } else {
+            OpcodeId::CREATE
+        };
+        let indices = transaction
+            .steps()
+            .iter()
+            .find(|step| step.exec_state == ExecState::Op(opcode))
+            .unwrap()
+            .bus_mapping_instance
+            .clone();
+        let container = &builder.block.container;
+
+        let n_pop = if salt.is_some() { 4 } else { 3 };
+        assert_eq!(
+            {
+                let operation = &container.call_context[indices[0].as_usize()];
+                (operation.rw(), operation.op())
+            },
+            (
+                RW::READ,
+                &CallContextOp {
+                    call_id,
+                    field: CallContextField::TxId,
+                    value: tx_id.into()
+                }
+            )
+        );
+        assert_eq!(
+            {
+                let operation = &container.stack[indices[6 + n_pop].as_usize()];
+                (operation.rw(), operation.op())
+            },
+            (
+                RW::WRITE,
+                &StackOp {
+                    call_id,
+                    address: StackAddress::from(1023u32),
+                    value: expected_address.to_word()
+                }
+            )
+        );
+        for (idx, byte) in init_code.iter().enumerate() {
+            assert_eq!(
+                {
+                    let operation = &container.memory[indices[7 + n_pop + idx].as_usize()];
+                    (operation.rw(), operation.op())
+                },
+                (RW::READ, &MemoryOp::new(call_id, idx.into(), *byte))
+            );
+        }
+
+        let offset = 7 + n_pop + init_code.len();
+        assert_eq!(
+            {
+                let operation = &container.tx_access_list_account[indices[offset].as_usize()];
+                (operation.rw(), operation.op())
+            },
+            (
+                RW::WRITE,
+                &TxAccessListAccountOp {
+                    tx_id,
+                    address: expected_address,
+                    is_warm: true,
+                    is_warm_prev: false
+                }
+            )
+        );
+        for (idx, address, value, value_prev) in [
+            (offset + 1, creator_address, 2, 1),
+            (offset + 4, expected_address, 1, 0),
+        ] {
+            assert_eq!(
+                {
+                    let operation = &container.account[indices[idx].as_usize()];
+                    (operation.rw(), operation.op())
+                },
+                (
+                    RW::WRITE,
+                    &AccountOp {
+                        address,
+                        field: AccountField::Nonce,
+                        value: value.into(),
+                        value_prev: value_prev.into(),
+                    }
+                )
+            );
+        }
+        assert_eq!(
+            {
+                let operation = &container.call_context[indices.last().unwrap().as_usize()];
+                (operation.rw(), operation.op())
+            },
+            (
+                RW::WRITE,
+                &CallContextOp {
+                    call_id: callee.call_id,
+                    field: CallContextField::CodeHash,
+                    value: Word::from(keccak256(&init_code)),
+                }
+            )
+        );
+
+        assert!(builder.block.sha3_inputs.contains(&init_code));
     }
 }
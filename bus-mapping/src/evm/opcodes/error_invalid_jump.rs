@@ -0,0 +1,46 @@
+use super::Opcode;
+use crate::{
+    circuit_input_builder::{CircuitInputStateRef, ExecStep},
+    error::ExecError,
+    evm::OpcodeId,
+    Error,
+};
+use eth_types::GethExecStep;
+
+/// Placeholder structure used to implement [`Opcode`] trait over it
+/// corresponding to the JUMP and JUMPI which fail because of
+/// [`ExecError::InvalidJump`], i.e. the destination is out of the code or is
+/// not a JUMPDEST.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct ErrorInvalidJump;
+
+impl Opcode for ErrorInvalidJump {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        geth_steps: &[GethExecStep],
+    ) -> Result<Vec<ExecStep>, Error> {
+        let geth_step = &geth_steps[0];
+        let mut exec_step = state.new_step(geth_step)?;
+        let next_step = geth_steps.get(1);
+        exec_step.error = state.get_step_err(geth_step, next_step)?;
+        debug_assert_eq!(exec_step.error, Some(ExecError::InvalidJump));
+
+        // Read the destination, and the condition for JUMPI.
+        let n_pops = if geth_step.op == OpcodeId::JUMPI {
+            2
+        } else {
+            1
+        };
+        for idx in 0..n_pops {
+            state.stack_read(
+                &mut exec_step,
+                geth_step.stack.nth_last_filled(idx),
+                geth_step.stack.nth_last(idx)?,
+            )?;
+        }
+
+        state.gen_restore_context_ops(&mut exec_step, geth_steps)?;
+        state.handle_return(geth_step)?;
+        Ok(vec![exec_step])
+    }
+}
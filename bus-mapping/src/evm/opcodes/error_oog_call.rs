@@ -0,0 +1,92 @@
+use super::Opcode;
+use crate::{
+    circuit_input_builder::{CircuitInputStateRef, ExecStep},
+    error::{ExecError, OogError},
+    evm::OpcodeId,
+    operation::{AccountField, CallContextField, TxAccessListAccountOp, RW},
+    Error,
+};
+use eth_types::{GethExecStep, ToAddress, ToWord};
+
+/// Placeholder structure used to implement [`Opcode`] trait over it
+/// corresponding to the CALL, CALLCODE, DELEGATECALL and STATICCALL which fail
+/// because of [`ExecError::OutOfGas`] with [`OogError::Call`],
+/// [`OogError::CallCode`], [`OogError::DelegateCall`] or
+/// [`OogError::StaticCall`].
+///
+/// All the call arguments are read, along with the warmth of the code address
+/// which is not added into the access list.  CALL also reads the nonce, the
+/// balance and the code hash of the callee to know if a value transfer
+/// creates a new account.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct ErrorOOGCall;
+
+impl Opcode for ErrorOOGCall {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        geth_steps: &[GethExecStep],
+    ) -> Result<Vec<ExecStep>, Error> {
+        let geth_step = &geth_steps[0];
+        let mut exec_step = state.new_step(geth_step)?;
+        let next_step = geth_steps.get(1);
+        exec_step.error = state.get_step_err(geth_step, next_step)?;
+        debug_assert!(matches!(
+            exec_step.error,
+            Some(ExecError::OutOfGas(
+                OogError::Call | OogError::CallCode | OogError::DelegateCall | OogError::StaticCall
+            ))
+        ));
+
+        let tx_id = state.tx_ctx.id();
+        let call_id = state.call()?.call_id;
+        state.call_context_read(
+            &mut exec_step,
+            call_id,
+            CallContextField::TxId,
+            tx_id.into(),
+        );
+
+        let n_args = match geth_step.op {
+            OpcodeId::CALL | OpcodeId::CALLCODE => 7,
+            _ => 6,
+        };
+        for idx in 0..n_args {
+            state.stack_read(
+                &mut exec_step,
+                geth_step.stack.nth_last_filled(idx),
+                geth_step.stack.nth_last(idx)?,
+            )?;
+        }
+
+        let code_address = geth_step.stack.nth_last(1)?.to_address();
+        let is_warm = state.sdb.check_account_in_access_list(&code_address);
+        state.push_op(
+            &mut exec_step,
+            RW::READ,
+            TxAccessListAccountOp {
+                tx_id,
+                address: code_address,
+                is_warm,
+                is_warm_prev: is_warm,
+            },
+        );
+
+        if geth_step.op == OpcodeId::CALL {
+            let (_, callee_account) = state.sdb.get_account(&code_address);
+            let callee_nonce = callee_account.nonce;
+            let callee_balance = callee_account.balance;
+            let callee_code_hash = callee_account.code_hash.to_word();
+            for (field, value) in [
+                (AccountField::Nonce, callee_nonce),
+                (AccountField::Balance, callee_balance),
+                (AccountField::CodeHash, callee_code_hash),
+            ] {
+                state.account_read(&mut exec_step, code_address, field, value, value)?;
+            }
+        }
+
+        state.gen_restore_context_ops(&mut exec_step, geth_steps)?;
+        state.handle_return(geth_step)?;
+        Ok(vec![exec_step])
+    }
+}
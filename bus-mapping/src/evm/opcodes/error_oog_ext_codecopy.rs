@@ -0,0 +1,66 @@
+use super::Opcode;
+use crate::{
+    circuit_input_builder::{CircuitInputStateRef, ExecStep},
+    error::{ExecError, OogError},
+    operation::{CallContextField, TxAccessListAccountOp, RW},
+    Error,
+};
+use eth_types::{GethExecStep, ToAddress};
+
+/// Placeholder structure used to implement [`Opcode`] trait over it
+/// corresponding to the EXTCODECOPY which fails because of
+/// [`ExecError::OutOfGas`] with [`OogError::ExtCodeCopy`].
+///
+/// The warmth of the external account is read to compute the account access
+/// cost, and the external account is not added into the access list.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct ErrorOOGExtCodeCopy;
+
+impl Opcode for ErrorOOGExtCodeCopy {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        geth_steps: &[GethExecStep],
+    ) -> Result<Vec<ExecStep>, Error> {
+        let geth_step = &geth_steps[0];
+        let mut exec_step = state.new_step(geth_step)?;
+        let next_step = geth_steps.get(1);
+        exec_step.error = state.get_step_err(geth_step, next_step)?;
+        debug_assert_eq!(
+            exec_step.error,
+            Some(ExecError::OutOfGas(OogError::ExtCodeCopy))
+        );
+
+        let external_address = geth_step.stack.last()?.to_address();
+        for idx in 0..4 {
+            state.stack_read(
+                &mut exec_step,
+                geth_step.stack.nth_last_filled(idx),
+                geth_step.stack.nth_last(idx)?,
+            )?;
+        }
+
+        let tx_id = state.tx_ctx.id();
+        state.call_context_read(
+            &mut exec_step,
+            state.call()?.call_id,
+            CallContextField::TxId,
+            tx_id.into(),
+        );
+
+        let is_warm = state.sdb.check_account_in_access_list(&external_address);
+        state.push_op(
+            &mut exec_step,
+            RW::READ,
+            TxAccessListAccountOp {
+                tx_id,
+                address: external_address,
+                is_warm,
+                is_warm_prev: is_warm,
+            },
+        );
+
+        state.gen_restore_context_ops(&mut exec_step, geth_steps)?;
+        state.handle_return(geth_step)?;
+        Ok(vec![exec_step])
+    }
+}
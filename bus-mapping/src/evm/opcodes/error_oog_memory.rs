@@ -0,0 +1,57 @@
+use super::Opcode;
+use crate::{
+    circuit_input_builder::{CircuitInputStateRef, ExecStep},
+    error::{ExecError, OogError},
+    evm::OpcodeId,
+    Error,
+};
+use eth_types::GethExecStep;
+
+/// Placeholder structure used to implement [`Opcode`] trait over it
+/// corresponding to the opcodes with dynamic memory expansion which fail
+/// because of [`ExecError::OutOfGas`] with
+/// [`OogError::DynamicMemoryExpansion`], [`OogError::MemoryCopy`],
+/// [`OogError::Log`] or [`OogError::Sha3`].
+///
+/// Only the stack items which affect the gas cost are read, which are the
+/// memory offset and length, and the data offset in between for
+/// CALLDATACOPY, CODECOPY and RETURNDATACOPY.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct ErrorOOGMemory;
+
+impl Opcode for ErrorOOGMemory {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        geth_steps: &[GethExecStep],
+    ) -> Result<Vec<ExecStep>, Error> {
+        let geth_step = &geth_steps[0];
+        let mut exec_step = state.new_step(geth_step)?;
+        let next_step = geth_steps.get(1);
+        exec_step.error = state.get_step_err(geth_step, next_step)?;
+        debug_assert!(matches!(
+            exec_step.error,
+            Some(ExecError::OutOfGas(
+                OogError::DynamicMemoryExpansion
+                    | OogError::MemoryCopy
+                    | OogError::Log
+                    | OogError::Sha3
+            ))
+        ));
+
+        let n_pops = match geth_step.op {
+            OpcodeId::CALLDATACOPY | OpcodeId::CODECOPY | OpcodeId::RETURNDATACOPY => 3,
+            _ => 2,
+        };
+        for idx in 0..n_pops {
+            state.stack_read(
+                &mut exec_step,
+                geth_step.stack.nth_last_filled(idx),
+                geth_step.stack.nth_last(idx)?,
+            )?;
+        }
+
+        state.gen_restore_context_ops(&mut exec_step, geth_steps)?;
+        state.handle_return(geth_step)?;
+        Ok(vec![exec_step])
+    }
+}
@@ -0,0 +1,90 @@
+use super::Opcode;
+use crate::{
+    circuit_input_builder::{CircuitInputStateRef, ExecStep},
+    error::{ExecError, OogError},
+    operation::{AccountField, CallContextField, TxAccessListAccountOp, RW},
+    Error,
+};
+use eth_types::{GethExecStep, ToAddress, ToWord};
+
+/// Placeholder structure used to implement [`Opcode`] trait over it
+/// corresponding to the SELFDESTRUCT which fails because of
+/// [`ExecError::OutOfGas`] with [`OogError::SelfDestruct`].
+///
+/// Everything needed to compute the dynamic gas cost is read, i.e. the warmth
+/// of the beneficiary, the balance of the current account and whether the
+/// beneficiary is empty.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct ErrorOOGSelfDestruct;
+
+impl Opcode for ErrorOOGSelfDestruct {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        geth_steps: &[GethExecStep],
+    ) -> Result<Vec<ExecStep>, Error> {
+        let geth_step = &geth_steps[0];
+        let mut exec_step = state.new_step(geth_step)?;
+        let next_step = geth_steps.get(1);
+        exec_step.error = state.get_step_err(geth_step, next_step)?;
+        debug_assert_eq!(
+            exec_step.error,
+            Some(ExecError::OutOfGas(OogError::SelfDestruct))
+        );
+
+        let tx_id = state.tx_ctx.id();
+        let call = state.call()?.clone();
+
+        let beneficiary = geth_step.stack.last()?.to_address();
+        state.stack_read(
+            &mut exec_step,
+            geth_step.stack.last_filled(),
+            geth_step.stack.last()?,
+        )?;
+
+        for (field, value) in [
+            (CallContextField::TxId, tx_id.into()),
+            (CallContextField::CalleeAddress, call.address.to_word()),
+        ] {
+            state.call_context_read(&mut exec_step, call.call_id, field, value);
+        }
+
+        // The beneficiary is not added into access list since the step fails.
+        let is_warm = state.sdb.check_account_in_access_list(&beneficiary);
+        state.push_op(
+            &mut exec_step,
+            RW::READ,
+            TxAccessListAccountOp {
+                tx_id,
+                address: beneficiary,
+                is_warm,
+                is_warm_prev: is_warm,
+            },
+        );
+
+        let (_, account) = state.sdb.get_account(&call.address);
+        let balance = account.balance;
+        state.account_read(
+            &mut exec_step,
+            call.address,
+            AccountField::Balance,
+            balance,
+            balance,
+        )?;
+
+        let (_, beneficiary_account) = state.sdb.get_account(&beneficiary);
+        for (field, value) in [
+            (AccountField::Nonce, beneficiary_account.nonce),
+            (AccountField::Balance, beneficiary_account.balance),
+            (
+                AccountField::CodeHash,
+                beneficiary_account.code_hash.to_word(),
+            ),
+        ] {
+            state.account_read(&mut exec_step, beneficiary, field, value, value)?;
+        }
+
+        state.gen_restore_context_ops(&mut exec_step, geth_steps)?;
+        state.handle_return(geth_step)?;
+        Ok(vec![exec_step])
+    }
+}
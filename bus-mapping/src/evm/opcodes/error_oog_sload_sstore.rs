@@ -0,0 +1,95 @@
+use super::Opcode;
+use crate::{
+    circuit_input_builder::{CircuitInputStateRef, ExecStep},
+    error::{ExecError, OogError},
+    evm::OpcodeId,
+    operation::{CallContextField, StorageOp, TxAccessListAccountStorageOp, RW},
+    Error,
+};
+use eth_types::{GethExecStep, ToWord};
+
+/// Placeholder structure used to implement [`Opcode`] trait over it
+/// corresponding to the SLOAD and SSTORE which fail because of
+/// [`ExecError::OutOfGas`] with [`OogError::Sload`] or [`OogError::Sstore`].
+///
+/// The current value, the committed value and the warmth of the storage slot
+/// are read to compute the gas cost, and the storage slot is not added into
+/// the access list.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct ErrorOOGSloadSstore;
+
+impl Opcode for ErrorOOGSloadSstore {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        geth_steps: &[GethExecStep],
+    ) -> Result<Vec<ExecStep>, Error> {
+        let geth_step = &geth_steps[0];
+        let mut exec_step = state.new_step(geth_step)?;
+        let next_step = geth_steps.get(1);
+        exec_step.error = state.get_step_err(geth_step, next_step)?;
+        debug_assert!(matches!(
+            exec_step.error,
+            Some(ExecError::OutOfGas(OogError::Sload | OogError::Sstore))
+        ));
+
+        let call_id = state.call()?.call_id;
+        let callee_address = state.call()?.address;
+        let tx_id = state.tx_ctx.id();
+        state.call_context_read(
+            &mut exec_step,
+            call_id,
+            CallContextField::TxId,
+            tx_id.into(),
+        );
+        state.call_context_read(
+            &mut exec_step,
+            call_id,
+            CallContextField::CalleeAddress,
+            callee_address.to_word(),
+        );
+
+        // Read the key, and the value for SSTORE.
+        let key = geth_step.stack.last()?;
+        let n_pops = if geth_step.op == OpcodeId::SSTORE {
+            2
+        } else {
+            1
+        };
+        for idx in 0..n_pops {
+            state.stack_read(
+                &mut exec_step,
+                geth_step.stack.nth_last_filled(idx),
+                geth_step.stack.nth_last(idx)?,
+            )?;
+        }
+
+        let (_, value) = state.sdb.get_storage(&callee_address, &key);
+        let value = *value;
+        let (_, committed_value) = state.sdb.get_committed_storage(&callee_address, &key);
+        let committed_value = *committed_value;
+        state.push_op(
+            &mut exec_step,
+            RW::READ,
+            StorageOp::new(callee_address, key, value, value, tx_id, committed_value),
+        );
+
+        let is_warm = state
+            .sdb
+            .check_account_storage_in_access_list(&(callee_address, key));
+        state.push_op(
+            &mut exec_step,
+            RW::READ,
+            TxAccessListAccountStorageOp {
+                tx_id,
+                address: callee_address,
+                key,
+                is_warm,
+                is_warm_prev: is_warm,
+            },
+        );
+
+        state.gen_restore_context_ops(&mut exec_step, geth_steps)?;
+        state.handle_return(geth_step)?;
+        Ok(vec![exec_step])
+    }
+}
@@ -0,0 +1,168 @@
+use super::Opcode;
+use crate::{
+    circuit_input_builder::{CircuitInputStateRef, ExecStep},
+    error::ExecError,
+    evm::OpcodeId,
+    operation::{AccountField, CallContextField, TxAccessListAccountOp, RW},
+    Error,
+};
+use eth_types::{GethExecStep, ToAddress, ToWord};
+
+/// Placeholder structure used to implement [`Opcode`] trait over it
+/// corresponding to the call and create related opcodes which fail the
+/// precheck of the callee, because of [`ExecError::Depth`] or
+/// [`ExecError::InsufficientBalance`].
+///
+/// Unlike other errors, the current call doesn't halt.  The gas cost of the
+/// call is charged and the code address is added into the access list, but
+/// the gas passed to the callee is returned, 0 is pushed onto the stack as
+/// the result and the return data of the current call is cleared.  CREATE and
+/// CREATE2 fail before the nonce of the current account is increased and the
+/// new address is added into the access list, so only their gas cost is
+/// charged.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct ErrorPrecheckFailed;
+
+impl Opcode for ErrorPrecheckFailed {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        geth_steps: &[GethExecStep],
+    ) -> Result<Vec<ExecStep>, Error> {
+        let geth_step = &geth_steps[0];
+        let mut exec_step = state.new_step(geth_step)?;
+        let next_step = geth_steps.get(1);
+        exec_step.error = state.get_step_err(geth_step, next_step)?;
+        debug_assert!(matches!(
+            exec_step.error,
+            Some(ExecError::Depth | ExecError::InsufficientBalance)
+        ));
+
+        let is_create = matches!(geth_step.op, OpcodeId::CREATE | OpcodeId::CREATE2);
+        let n_args = match geth_step.op {
+            OpcodeId::CALL | OpcodeId::CALLCODE => 7,
+            OpcodeId::DELEGATECALL | OpcodeId::STATICCALL => 6,
+            OpcodeId::CREATE => 3,
+            OpcodeId::CREATE2 => 4,
+            _ => unreachable!("precheck only fails for call and create related opcodes"),
+        };
+        if is_create {
+            // we need to keep the memory until parse_call complete
+            let offset = geth_step.stack.nth_last(1)?.as_usize();
+            let length = geth_step.stack.nth_last(2)?.as_usize();
+            if length != 0 {
+                state
+                    .call_ctx_mut()?
+                    .memory
+                    .extend_at_least(offset + length);
+            }
+        } else {
+            let args_offset = geth_step.stack.nth_last(n_args - 4)?.as_usize();
+            let args_length = geth_step.stack.nth_last(n_args - 3)?.as_usize();
+            let ret_offset = geth_step.stack.nth_last(n_args - 2)?.as_usize();
+            let ret_length = geth_step.stack.nth_last(n_args - 1)?.as_usize();
+            state.call_expand_memory(args_offset, args_length, ret_offset, ret_length)?;
+        }
+
+        let tx_id = state.tx_ctx.id();
+        let call = state.parse_call(geth_step)?;
+        let current_call = state.call()?.clone();
+
+        // NOTE: For `RwCounterEndOfReversion` we use the `0` value as a placeholder,
+        // and later set the proper value in
+        // `CircuitInputBuilder::set_value_ops_call_context_rwc_eor`
+        for (field, value) in [
+            (CallContextField::TxId, tx_id.into()),
+            (CallContextField::RwCounterEndOfReversion, 0.into()),
+            (
+                CallContextField::IsPersistent,
+                (current_call.is_persistent as u64).into(),
+            ),
+            (
+                CallContextField::CalleeAddress,
+                current_call.address.to_word(),
+            ),
+            (
+                CallContextField::IsStatic,
+                (current_call.is_static as u64).into(),
+            ),
+            (CallContextField::Depth, current_call.depth.into()),
+        ] {
+            state.call_context_read(&mut exec_step, current_call.call_id, field, value);
+        }
+
+        for idx in 0..n_args {
+            state.stack_read(
+                &mut exec_step,
+                geth_step.stack.nth_last_filled(idx),
+                geth_step.stack.nth_last(idx)?,
+            )?;
+        }
+        state.stack_write(
+            &mut exec_step,
+            geth_step.stack.nth_last_filled(n_args - 1),
+            0.into(),
+        )?;
+
+        if !is_create {
+            // The code address is added into the access list when the gas cost is
+            // charged, before the precheck.
+            let code_address = geth_step.stack.nth_last(1)?.to_address();
+            let is_warm = state.sdb.check_account_in_access_list(&code_address);
+            state.push_op_reversible(
+                &mut exec_step,
+                RW::WRITE,
+                TxAccessListAccountOp {
+                    tx_id,
+                    address: code_address,
+                    is_warm: true,
+                    is_warm_prev: is_warm,
+                },
+            )?;
+
+            // CALL reads the callee account to know if a value transfer creates a
+            // new account.
+            if geth_step.op == OpcodeId::CALL {
+                let (_, callee_account) = state.sdb.get_account(&code_address);
+                let callee_nonce = callee_account.nonce;
+                let callee_balance = callee_account.balance;
+                let callee_code_hash = callee_account.code_hash.to_word();
+                for (field, value) in [
+                    (AccountField::Nonce, callee_nonce),
+                    (AccountField::Balance, callee_balance),
+                    (AccountField::CodeHash, callee_code_hash),
+                ] {
+                    state.account_read(&mut exec_step, code_address, field, value, value)?;
+                }
+            }
+        }
+
+        // The balance of the current account is read to prove it's less than
+        // the value to transfer.
+        if exec_step.error == Some(ExecError::InsufficientBalance) {
+            let (_, caller_account) = state.sdb.get_account(&current_call.address);
+            let caller_balance = caller_account.balance;
+            state.account_read(
+                &mut exec_step,
+                current_call.address,
+                AccountField::Balance,
+                caller_balance,
+                caller_balance,
+            )?;
+        }
+
+        for field in [
+            CallContextField::LastCalleeId,
+            CallContextField::LastCalleeReturnDataOffset,
+            CallContextField::LastCalleeReturnDataLength,
+        ] {
+            state.call_context_write(&mut exec_step, current_call.call_id, field, 0.into());
+        }
+
+        // Switch to the callee's call context and return from it immediately,
+        // since the callee is never executed.
+        state.push_call(call);
+        state.caller_ctx_mut()?.update_last_callee(0, 0, vec![]);
+        state.handle_return(geth_step)?;
+        Ok(vec![exec_step])
+    }
+}
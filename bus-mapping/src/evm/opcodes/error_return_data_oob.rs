@@ -0,0 +1,49 @@
+use super::Opcode;
+use crate::{
+    circuit_input_builder::{CircuitInputStateRef, ExecStep},
+    error::ExecError,
+    operation::CallContextField,
+    Error,
+};
+use eth_types::GethExecStep;
+
+/// Placeholder structure used to implement [`Opcode`] trait over it
+/// corresponding to the RETURNDATACOPY which fails because of
+/// [`ExecError::ReturnDataOutOfBounds`], i.e. it tries to copy more than the
+/// return data of the last callee.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct ErrorReturnDataOutOfBound;
+
+impl Opcode for ErrorReturnDataOutOfBound {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        geth_steps: &[GethExecStep],
+    ) -> Result<Vec<ExecStep>, Error> {
+        let geth_step = &geth_steps[0];
+        let mut exec_step = state.new_step(geth_step)?;
+        let next_step = geth_steps.get(1);
+        exec_step.error = state.get_step_err(geth_step, next_step)?;
+        debug_assert_eq!(exec_step.error, Some(ExecError::ReturnDataOutOfBounds));
+
+        for idx in 0..3 {
+            state.stack_read(
+                &mut exec_step,
+                geth_step.stack.nth_last_filled(idx),
+                geth_step.stack.nth_last(idx)?,
+            )?;
+        }
+
+        let call_id = state.call()?.call_id;
+        let return_data_length = state.call_ctx()?.last_callee_return_data_length;
+        state.call_context_read(
+            &mut exec_step,
+            call_id,
+            CallContextField::LastCalleeReturnDataLength,
+            return_data_length.into(),
+        );
+
+        state.gen_restore_context_ops(&mut exec_step, geth_steps)?;
+        state.handle_return(geth_step)?;
+        Ok(vec![exec_step])
+    }
+}
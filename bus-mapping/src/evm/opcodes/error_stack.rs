@@ -0,0 +1,36 @@
+use super::Opcode;
+use crate::{
+    circuit_input_builder::{CircuitInputStateRef, ExecStep},
+    error::ExecError,
+    Error,
+};
+use eth_types::GethExecStep;
+
+/// Placeholder structure used to implement [`Opcode`] trait over it
+/// corresponding to any opcode which fails because of
+/// [`ExecError::StackOverflow`] or [`ExecError::StackUnderflow`].
+///
+/// Nothing is read from the stack, since the error only depends on the stack
+/// pointer.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct ErrorStack;
+
+impl Opcode for ErrorStack {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        geth_steps: &[GethExecStep],
+    ) -> Result<Vec<ExecStep>, Error> {
+        let geth_step = &geth_steps[0];
+        let mut exec_step = state.new_step(geth_step)?;
+        let next_step = geth_steps.get(1);
+        exec_step.error = state.get_step_err(geth_step, next_step)?;
+        debug_assert!(matches!(
+            exec_step.error,
+            Some(ExecError::StackOverflow | ExecError::StackUnderflow)
+        ));
+
+        state.gen_restore_context_ops(&mut exec_step, geth_steps)?;
+        state.handle_return(geth_step)?;
+        Ok(vec![exec_step])
+    }
+}
@@ -0,0 +1,53 @@
+use super::Opcode;
+use crate::{
+    circuit_input_builder::{CircuitInputStateRef, ExecStep},
+    error::ExecError,
+    evm::OpcodeId,
+    operation::CallContextField,
+    Error,
+};
+use eth_types::GethExecStep;
+
+/// Placeholder structure used to implement [`Opcode`] trait over it
+/// corresponding to the state modifying opcodes which fail because of
+/// [`ExecError::WriteProtection`] in a static call.
+///
+/// CALL only breaks the write protection when it transfers value, so the gas,
+/// address and value arguments are read for it.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct ErrorWriteProtection;
+
+impl Opcode for ErrorWriteProtection {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        geth_steps: &[GethExecStep],
+    ) -> Result<Vec<ExecStep>, Error> {
+        let geth_step = &geth_steps[0];
+        let mut exec_step = state.new_step(geth_step)?;
+        let next_step = geth_steps.get(1);
+        exec_step.error = state.get_step_err(geth_step, next_step)?;
+        debug_assert_eq!(exec_step.error, Some(ExecError::WriteProtection));
+
+        let call = state.call()?.clone();
+        state.call_context_read(
+            &mut exec_step,
+            call.call_id,
+            CallContextField::IsStatic,
+            (call.is_static as u64).into(),
+        );
+
+        if geth_step.op == OpcodeId::CALL {
+            for idx in 0..3 {
+                state.stack_read(
+                    &mut exec_step,
+                    geth_step.stack.nth_last_filled(idx),
+                    geth_step.stack.nth_last(idx)?,
+                )?;
+            }
+        }
+
+        state.gen_restore_context_ops(&mut exec_step, geth_steps)?;
+        state.handle_return(geth_step)?;
+        Ok(vec![exec_step])
+    }
+}
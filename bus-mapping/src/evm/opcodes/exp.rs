@@ -0,0 +1,120 @@
+use super::Opcode;
+use crate::circuit_input_builder::{CircuitInputStateRef, ExecStep, ExpEvent};
+use crate::Error;
+use eth_types::{GethExecStep, Word};
+
+/// Placeholder structure used to implement [`Opcode`] trait over it
+/// corresponding to the [`OpcodeId::EXP`](crate::evm::OpcodeId::EXP) opcode.
+/// Besides the stack operations, it records an [`ExpEvent`] for the
+/// exponentiation table whenever the exponent is greater than 1.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Exponentiation;
+
+impl Opcode for Exponentiation {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        geth_steps: &[GethExecStep],
+    ) -> Result<Vec<ExecStep>, Error> {
+        let geth_step = &geth_steps[0];
+        let mut exec_step = state.new_step(geth_step)?;
+
+        let base = geth_step.stack.nth_last(0)?;
+        state.stack_read(&mut exec_step, geth_step.stack.nth_last_filled(0), base)?;
+        let exponent = geth_step.stack.nth_last(1)?;
+        state.stack_read(&mut exec_step, geth_step.stack.nth_last_filled(1), exponent)?;
+
+        let exponentiation = geth_steps[1].stack.nth_last(0)?;
+        state.stack_write(
+            &mut exec_step,
+            geth_steps[1].stack.nth_last_filled(0),
+            exponentiation,
+        )?;
+
+        // Exponents 0 and 1 are handled directly in the EVM circuit.
+        if exponent > Word::one() {
+            let event = ExpEvent::new(base, exponent);
+            debug_assert_eq!(event.exponentiation, exponentiation);
+            state.push_exponentiation(event);
+        }
+
+        Ok(vec![exec_step])
+    }
+}
+
+#[cfg(test)]
+mod exp_tests {
+    use crate::{
+        circuit_input_builder::{ExecState, ExpEvent, ExpStep},
+        mock::BlockData,
+    };
+    use eth_types::{bytecode, evm_types::OpcodeId, geth_types::GethData, Word};
+    use mock::test_ctx::{helpers::*, TestContext};
+    use pretty_assertions::assert_eq;
+
+    fn exp_events(base: Word, exponent: Word) -> Vec<ExpEvent> {
+        let code = bytecode! {
+            PUSH32(exponent)
+            PUSH32(base)
+            EXP
+            STOP
+        };
+
+        // Get the execution steps from the external tracer
+        let block: GethData = TestContext::<2, 1>::new(
+            None,
+            account_0_code_account_1_no_code(code),
+            tx_from_1_to_0,
+            |block, _tx| block.number(0xcafeu64),
+        )
+        .unwrap()
+        .into();
+
+        let mut builder = BlockData::new_from_geth_data(block.clone()).new_circuit_input_builder();
+        builder
+            .handle_block(&block.eth_block, &block.geth_traces)
+            .unwrap();
+
+        let step = builder.block.txs()[0]
+            .steps()
+            .iter()
+            .find(|step| step.exec_state == ExecState::Op(OpcodeId::EXP))
+            .unwrap();
+        assert_eq!(step.bus_mapping_instance.len(), 3);
+
+        builder.block.exp_events
+    }
+
+    #[test]
+    fn exp_opcode_impl() {
+        let events = exp_events(3.into(), 13.into());
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].exponentiation, Word::from(1594323u64));
+        // Steps go from the final multiplication down to the first squaring.
+        assert_eq!(
+            events[0].steps,
+            vec![
+                ExpStep::from((531441.into(), 3.into(), 1594323.into())),
+                ExpStep::from((729.into(), 729.into(), 531441.into())),
+                ExpStep::from((27.into(), 27.into(), 729.into())),
+                ExpStep::from((9.into(), 3.into(), 27.into())),
+                ExpStep::from((3.into(), 3.into(), 9.into())),
+            ]
+        );
+    }
+
+    #[test]
+    fn exp_opcode_small_exponent() {
+        assert!(exp_events(7.into(), 0.into()).is_empty());
+        assert!(exp_events(7.into(), 1.into()).is_empty());
+    }
+
+    #[test]
+    fn exp_opcode_overflow() {
+        let events = exp_events(Word::MAX, Word::MAX);
+        assert_eq!(events.len(), 1);
+        // (-1) ^ odd == -1 (mod 2^256)
+        assert_eq!(events[0].exponentiation, Word::MAX);
+        // one squaring and one multiplication for each of the 255 lower bits
+        assert_eq!(events[0].steps.len(), 255 * 2);
+    }
+}
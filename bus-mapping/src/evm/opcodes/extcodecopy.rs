@@ -1,8 +1,10 @@
 use super::Opcode;
-use crate::circuit_input_builder::{CircuitInputStateRef, ExecStep};
-use crate::operation::{TxAccessListAccountOp, RW};
+use crate::circuit_input_builder::{
+    CircuitInputStateRef, CopyDataType, CopyEvent, ExecStep, NumberOrHash,
+};
+use crate::operation::{AccountField, CallContextField, TxAccessListAccountOp, RW};
 use crate::Error;
-use eth_types::{GethExecStep, ToAddress};
+use eth_types::{Bytecode, GethExecStep, ToAddress, ToWord, H256, U256};
 
 #[derive(Clone, Copy, Debug)]
 pub(crate) struct Extcodecopy;
@@ -13,7 +15,7 @@ impl Opcode for Extcodecopy {
         geth_steps: &[GethExecStep],
     ) -> Result<Vec<ExecStep>, Error> {
         let geth_step = &geth_steps[0];
-        let exec_steps = vec![gen_extcodecopy_step(state, geth_step)?];
+        let mut exec_step = gen_extcodecopy_step(state, geth_step)?;
 
         // reconstruction
         let address = geth_steps[0].stack.nth_last(0)?.to_address();
@@ -21,9 +23,9 @@ impl Opcode for Extcodecopy {
         let code_offset = geth_steps[0].stack.nth_last(2)?.as_u64();
         let length = geth_steps[0].stack.nth_last(3)?.as_u64();
 
-        let (exist, account) = state.sdb.get_account(&address);
-        assert!(exist, "target account does not exist");
-        let code = state.code(account.code_hash)?;
+        let (_, account) = state.sdb.get_account(&address);
+        let code_hash = account.code_hash;
+        let code = state.code(code_hash).unwrap_or_default();
 
         let call_ctx = state.call_ctx_mut()?;
         let memory = &mut call_ctx.memory;
@@ -45,8 +47,9 @@ impl Opcode for Extcodecopy {
             }
         }
 
-        // TODO: implement copy event
-        Ok(exec_steps)
+        let copy_event = gen_copy_event(state, geth_step, code_hash, code.into(), &mut exec_step)?;
+        state.push_copy(copy_event);
+        Ok(vec![exec_step])
     }
 }
 
@@ -56,44 +59,284 @@ fn gen_extcodecopy_step(
 ) -> Result<ExecStep, Error> {
     let mut exec_step = state.new_step(geth_step)?;
 
-    let address = geth_step.stack.nth_last(0)?;
+    let external_address = geth_step.stack.nth_last(0)?.to_address();
     let dest_offset = geth_step.stack.nth_last(1)?;
     let offset = geth_step.stack.nth_last(2)?;
     let length = geth_step.stack.nth_last(3)?;
 
-    let is_warm = state
-        .sdb
-        .check_account_in_access_list(&address.to_address());
+    // stack reads
+    state.stack_read(
+        &mut exec_step,
+        geth_step.stack.nth_last_filled(0),
+        external_address.to_word(),
+    )?;
+    state.stack_read(
+        &mut exec_step,
+        geth_step.stack.nth_last_filled(1),
+        dest_offset,
+    )?;
+    state.stack_read(&mut exec_step, geth_step.stack.nth_last_filled(2), offset)?;
+    state.stack_read(&mut exec_step, geth_step.stack.nth_last_filled(3), length)?;
+
+    for (field, value) in [
+        (CallContextField::TxId, U256::from(state.tx_ctx.id())),
+        (
+            CallContextField::RwCounterEndOfReversion,
+            U256::from(state.call()?.rw_counter_end_of_reversion as u64),
+        ),
+        (
+            CallContextField::IsPersistent,
+            U256::from(state.call()?.is_persistent as u64),
+        ),
+    ] {
+        state.call_context_read(&mut exec_step, state.call()?.call_id, field, value);
+    }
+
+    let is_warm = state.sdb.check_account_in_access_list(&external_address);
     state.push_op_reversible(
         &mut exec_step,
         RW::WRITE,
         TxAccessListAccountOp {
             tx_id: state.tx_ctx.id(),
-            address: address.to_address(),
+            address: external_address,
             is_warm: true,
             is_warm_prev: is_warm,
         },
     )?;
 
-    // stack reads
-    state.stack_read(&mut exec_step, geth_step.stack.nth_last_filled(0), address)?;
-    state.stack_read(
+    let code_hash = state.sdb.get_account(&external_address).1.code_hash;
+    state.account_read(
         &mut exec_step,
-        geth_step.stack.nth_last_filled(1),
-        dest_offset,
+        external_address,
+        AccountField::CodeHash,
+        code_hash.to_word(),
+        code_hash.to_word(),
     )?;
-    state.stack_read(&mut exec_step, geth_step.stack.nth_last_filled(2), offset)?;
-    state.stack_read(&mut exec_step, geth_step.stack.nth_last_filled(3), length)?;
     Ok(exec_step)
 }
 
+fn gen_copy_steps(
+    state: &mut CircuitInputStateRef,
+    exec_step: &mut ExecStep,
+    src_addr: u64,
+    dst_addr: u64,
+    bytes_left: u64,
+    bytecode: &Bytecode,
+) -> Result<Vec<(u8, bool)>, Error> {
+    let mut steps = Vec::with_capacity(bytes_left as usize);
+    for idx in 0..bytes_left {
+        let addr = src_addr + idx;
+        let bytecode_element = bytecode.get(addr as usize).unwrap_or_default();
+        steps.push((bytecode_element.value, bytecode_element.is_code));
+        state.memory_write(exec_step, (dst_addr + idx).into(), bytecode_element.value)?;
+    }
+    Ok(steps)
+}
+
+fn gen_copy_event(
+    state: &mut CircuitInputStateRef,
+    geth_step: &GethExecStep,
+    code_hash: H256,
+    bytecode: Bytecode,
+    exec_step: &mut ExecStep,
+) -> Result<CopyEvent, Error> {
+    let rw_counter_start = state.block_ctx.rwc;
+
+    let dst_offset = geth_step.stack.nth_last(1)?.as_u64();
+    let code_offset = geth_step.stack.nth_last(2)?.as_u64();
+    let length = geth_step.stack.nth_last(3)?.as_u64();
+
+    // Out of bound bytes are padded with 0s.
+    let src_addr_end = bytecode.to_vec().len() as u64;
+    let copy_steps = gen_copy_steps(state, exec_step, code_offset, dst_offset, length, &bytecode)?;
+
+    Ok(CopyEvent {
+        src_type: CopyDataType::Bytecode,
+        src_id: NumberOrHash::Hash(code_hash),
+        src_addr: code_offset,
+        src_addr_end,
+        dst_type: CopyDataType::Memory,
+        dst_id: NumberOrHash::Number(state.call()?.call_id),
+        dst_addr: dst_offset,
+        log_id: None,
+        rw_counter_start,
+        bytes: copy_steps,
+    })
+}
+
 #[cfg(test)]
 mod extcodecopy_tests {
-    use crate::mock::BlockData;
-    use eth_types::geth_types::GethData;
-    use eth_types::{bytecode, word};
+    use crate::{
+        circuit_input_builder::{CopyDataType, ExecState, NumberOrHash},
+        mock::BlockData,
+        operation::{AccountField, AccountOp, MemoryOp, StackOp, TxAccessListAccountOp, RW},
+    };
+    use eth_types::{
+        address, bytecode,
+        evm_types::{MemoryAddress, OpcodeId, StackAddress},
+        geth_types::GethData,
+        word, Bytecode, Bytes, ToWord, Word, H256,
+    };
+    use ethers_core::utils::keccak256;
     use mock::test_ctx::helpers::{account_0_code_account_1_no_code, tx_from_1_to_0};
     use mock::TestContext;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn extcodecopy_opcode_impl() {
+        test_copy(false, 0x00, 0x00, 0x00);
+        test_copy(false, 0x00, 0x00, 0x04);
+        test_copy(true, 0x00, 0x00, 0x04);
+        test_copy(true, 0x20, 0x02, 0x10);
+    }
+
+    fn test_copy(is_warm: bool, dst_offset: usize, code_offset: usize, size: usize) {
+        let external_address = address!("0xaabbccddee000000000000000000000000000000");
+        let code_ext = Bytes::from([0x60, 0x01, 0x60, 0x02, 0x01]);
+
+        let mut code = Bytecode::default();
+        if is_warm {
+            code.append(&bytecode! {
+                PUSH20(external_address.to_word())
+                EXTCODEHASH
+                POP
+            });
+        }
+        code.append(&bytecode! {
+            PUSH32(size)
+            PUSH32(code_offset)
+            PUSH32(dst_offset)
+            PUSH20(external_address.to_word())
+            EXTCODECOPY
+            STOP
+        });
+
+        let block: GethData = TestContext::<3, 1>::new(
+            None,
+            |accs| {
+                accs[0]
+                    .address(address!("0x0000000000000000000000000000000000000010"))
+                    .balance(Word::from(1u64 << 20))
+                    .code(code.clone());
+                accs[1].address(external_address).code(code_ext.clone());
+                accs[2]
+                    .address(address!("0x0000000000000000000000000000000000cafe01"))
+                    .balance(Word::from(1u64 << 20));
+            },
+            |mut txs, accs| {
+                txs[0].to(accs[0].address).from(accs[2].address);
+            },
+            |block, _tx| block.number(0xcafeu64),
+        )
+        .unwrap()
+        .into();
+
+        let mut builder = BlockData::new_from_geth_data(block.clone()).new_circuit_input_builder();
+        builder
+            .handle_block(&block.eth_block, &block.geth_traces)
+            .unwrap();
+
+        let tx_id = 1;
+        let transaction = &builder.block.txs()[tx_id - 1];
+        let call_id = transaction.calls()[0].call_id;
+        let step = transaction
+            .steps()
+            .iter()
+            .find(|step| step.exec_state == ExecState::Op(OpcodeId::EXTCODECOPY))
+            .unwrap();
+        let container = &builder.block.container;
+
+        assert_eq!(
+            [0, 1, 2, 3]
+                .map(|idx| &container.stack[step.bus_mapping_instance[idx].as_usize()])
+                .map(|op| (op.rw(), op.op())),
+            [
+                (
+                    RW::READ,
+                    &StackOp::new(
+                        call_id,
+                        StackAddress::from(1020),
+                        external_address.to_word()
+                    ),
+                ),
+                (
+                    RW::READ,
+                    &StackOp::new(call_id, StackAddress::from(1021), Word::from(dst_offset)),
+                ),
+                (
+                    RW::READ,
+                    &StackOp::new(call_id, StackAddress::from(1022), Word::from(code_offset)),
+                ),
+                (
+                    RW::READ,
+                    &StackOp::new(call_id, StackAddress::from(1023), Word::from(size)),
+                ),
+            ]
+        );
+        assert_eq!(
+            {
+                let operation =
+                    &container.tx_access_list_account[step.bus_mapping_instance[7].as_usize()];
+                (operation.rw(), operation.op())
+            },
+            (
+                RW::WRITE,
+                &TxAccessListAccountOp {
+                    tx_id,
+                    address: external_address,
+                    is_warm: true,
+                    is_warm_prev: is_warm
+                }
+            )
+        );
+        let code_hash = H256(keccak256(&code_ext));
+        assert_eq!(
+            {
+                let operation = &container.account[step.bus_mapping_instance[8].as_usize()];
+                (operation.rw(), operation.op())
+            },
+            (
+                RW::READ,
+                &AccountOp {
+                    address: external_address,
+                    field: AccountField::CodeHash,
+                    value: code_hash.to_word(),
+                    value_prev: code_hash.to_word(),
+                }
+            )
+        );
+
+        // RW table memory writes, padded with 0s for out of bound bytes.
+        assert_eq!(
+            (0..size)
+                .map(|idx| &container.memory[step.bus_mapping_instance[9 + idx].as_usize()])
+                .map(|op| (op.rw(), op.op().clone()))
+                .collect::<Vec<(RW, MemoryOp)>>(),
+            (0..size)
+                .map(|idx| {
+                    (
+                        RW::WRITE,
+                        MemoryOp::new(
+                            call_id,
+                            MemoryAddress::from(dst_offset + idx),
+                            code_ext.get(code_offset + idx).cloned().unwrap_or(0),
+                        ),
+                    )
+                })
+                .collect::<Vec<(RW, MemoryOp)>>(),
+        );
+
+        let copy_events = &builder.block.copy_events;
+        assert_eq!(copy_events.len(), 1);
+        assert_eq!(copy_events[0].bytes.len(), size);
+        assert_eq!(copy_events[0].src_id, NumberOrHash::Hash(code_hash));
+        assert_eq!(copy_events[0].src_addr as usize, code_offset);
+        assert_eq!(copy_events[0].src_addr_end as usize, code_ext.len());
+        assert_eq!(copy_events[0].src_type, CopyDataType::Bytecode);
+        assert_eq!(copy_events[0].dst_id, NumberOrHash::Number(call_id));
+        assert_eq!(copy_events[0].dst_addr as usize, dst_offset);
+        assert_eq!(copy_events[0].dst_type, CopyDataType::Memory);
+    }
 
     #[test]
     fn test_ok() {
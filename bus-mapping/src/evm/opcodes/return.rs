@@ -1,6 +1,6 @@
 use super::Opcode;
 use crate::circuit_input_builder::{CopyDataType, CopyEvent, NumberOrHash};
-use crate::operation::MemoryOp;
+use crate::operation::{AccountField, AccountOp, MemoryOp};
 use crate::{
     circuit_input_builder::CircuitInputStateRef,
     evm::opcodes::ExecStep,
@@ -48,8 +48,8 @@ impl Opcode for Return {
         // Case A in the spec.
         if call.is_create() && call.is_success && length > 0 {
             // Note: handle_return updates state.code_db. All we need to do here is push the
-            // copy event.
-            handle_create(
+            // copy event and set the code hash of the created account.
+            let code_hash = handle_create(
                 state,
                 &mut exec_step,
                 Source {
@@ -58,6 +58,30 @@ impl Opcode for Return {
                     length,
                 },
             )?;
+
+            for (field, value) in [
+                (CallContextField::CalleeAddress, call.address.to_word()),
+                (
+                    CallContextField::RwCounterEndOfReversion,
+                    call.rw_counter_end_of_reversion.into(),
+                ),
+                (CallContextField::IsPersistent, call.is_persistent.to_word()),
+            ] {
+                state.call_context_read(&mut exec_step, call.call_id, field, value);
+            }
+
+            let (_, callee_account) = state.sdb.get_account(&call.address);
+            let code_hash_prev = callee_account.code_hash;
+            state.push_op_reversible(
+                &mut exec_step,
+                RW::WRITE,
+                AccountOp {
+                    address: call.address,
+                    field: AccountField::CodeHash,
+                    value: code_hash.to_word(),
+                    value_prev: code_hash_prev.to_word(),
+                },
+            )?;
         }
 
         // Case B in the specs.
@@ -94,9 +118,6 @@ impl Opcode for Return {
 
                 caller_ctx.memory.0[return_offset..return_offset + copy_length]
                     .copy_from_slice(&callee_memory.0[offset..offset + copy_length]);
-                caller_ctx.return_data.resize(length, 0);
-                caller_ctx.return_data[0..copy_length]
-                    .copy_from_slice(&callee_memory.0[offset..offset + copy_length]);
 
                 handle_copy(
                     state,
@@ -174,13 +195,16 @@ fn handle_copy(
     Ok(())
 }
 
+/// Push the copy event of the code deployed by a creation, returning its code
+/// hash.
 fn handle_create(
     state: &mut CircuitInputStateRef,
     step: &mut ExecStep,
     source: Source,
-) -> Result<(), Error> {
+) -> Result<H256, Error> {
     let values = state.call_ctx()?.memory.0[source.offset..source.offset + source.length].to_vec();
-    let dst_id = NumberOrHash::Hash(H256(keccak256(&values)));
+    let code_hash = H256(keccak256(&values));
+    let dst_id = NumberOrHash::Hash(code_hash);
     let bytes: Vec<_> = Bytecode::from(values)
         .code
         .iter()
@@ -209,5 +233,5 @@ fn handle_create(
         bytes,
     });
 
-    Ok(())
+    Ok(code_hash)
 }
@@ -1,6 +1,11 @@
-use crate::circuit_input_builder::{CircuitInputStateRef, ExecStep};
-use crate::evm::Opcode;
-use crate::Error;
+use super::Opcode;
+use crate::{
+    circuit_input_builder::{
+        CircuitInputStateRef, CopyDataType, CopyEvent, ExecStep, NumberOrHash,
+    },
+    operation::{CallContextField, MemoryOp, RW},
+    Error,
+};
 use eth_types::GethExecStep;
 
 #[derive(Clone, Copy, Debug)]
@@ -11,40 +16,117 @@ impl Opcode for Returndatacopy {
         state: &mut CircuitInputStateRef,
         geth_steps: &[GethExecStep],
     ) -> Result<Vec<ExecStep>, Error> {
-        // TODO: complete `ExecStep` and circuit implementation
-        let exec_step = state.new_step(&geth_steps[0])?;
-
-        // reconstruction
         let geth_step = &geth_steps[0];
-        let dest_offset = geth_step.stack.nth_last(0)?;
-        let offset = geth_step.stack.nth_last(1)?;
-        let size = geth_step.stack.nth_last(2)?;
-
-        // can we reduce this clone?
-        let return_data = state.call_ctx()?.return_data.clone();
+        let mut exec_step = gen_returndatacopy_step(state, geth_step)?;
 
+        // reconstruction
+        let dest_offset = geth_step.stack.nth_last(0)?.as_usize();
+        let offset = geth_step.stack.nth_last(1)?.as_usize();
+        let length = geth_step.stack.nth_last(2)?.as_usize();
         let call_ctx = state.call_ctx_mut()?;
-        let memory = &mut call_ctx.memory;
-        let length = size.as_usize();
         if length != 0 {
-            let mem_starts = dest_offset.as_usize();
-            let mem_ends = mem_starts + length;
-            let data_starts = offset.as_usize();
-            let data_ends = data_starts + length;
-            let minimal_length = dest_offset.as_usize() + length;
-            if data_ends <= return_data.len() {
-                memory.extend_at_least(minimal_length);
-                memory[mem_starts..mem_ends].copy_from_slice(&return_data[data_starts..data_ends]);
-            } else {
-                assert_eq!(geth_steps.len(), 1);
-                // if overflows this opcode would fails current context, so
-                // there is no more steps.
-            }
+            let memory = &mut call_ctx.memory;
+            memory.extend_at_least(dest_offset + length);
+            memory.0[dest_offset..dest_offset + length]
+                .copy_from_slice(&call_ctx.return_data[offset..offset + length]);
         }
+
+        let copy_event = gen_copy_event(state, geth_step, &mut exec_step)?;
+        state.push_copy(copy_event);
         Ok(vec![exec_step])
     }
 }
 
+fn gen_returndatacopy_step(
+    state: &mut CircuitInputStateRef,
+    geth_step: &GethExecStep,
+) -> Result<ExecStep, Error> {
+    let mut exec_step = state.new_step(geth_step)?;
+    let dest_offset = geth_step.stack.nth_last(0)?;
+    let offset = geth_step.stack.nth_last(1)?;
+    let length = geth_step.stack.nth_last(2)?;
+
+    state.stack_read(
+        &mut exec_step,
+        geth_step.stack.nth_last_filled(0),
+        dest_offset,
+    )?;
+    state.stack_read(&mut exec_step, geth_step.stack.nth_last_filled(1), offset)?;
+    state.stack_read(&mut exec_step, geth_step.stack.nth_last_filled(2), length)?;
+
+    let call_id = state.call()?.call_id;
+    let call_ctx = state.call_ctx()?;
+    for (field, value) in [
+        (
+            CallContextField::LastCalleeId,
+            call_ctx.last_callee_id.into(),
+        ),
+        (
+            CallContextField::LastCalleeReturnDataOffset,
+            call_ctx.last_callee_return_data_offset.into(),
+        ),
+        (
+            CallContextField::LastCalleeReturnDataLength,
+            call_ctx.last_callee_return_data_length.into(),
+        ),
+    ] {
+        state.call_context_read(&mut exec_step, call_id, field, value);
+    }
+
+    Ok(exec_step)
+}
+
+fn gen_copy_event(
+    state: &mut CircuitInputStateRef,
+    geth_step: &GethExecStep,
+    exec_step: &mut ExecStep,
+) -> Result<CopyEvent, Error> {
+    let rw_counter_start = state.block_ctx.rwc;
+    let dest_offset = geth_step.stack.nth_last(0)?.as_u64();
+    let offset = geth_step.stack.nth_last(1)?.as_u64();
+    let length = geth_step.stack.nth_last(2)?.as_u64();
+
+    let call_id = state.call()?.call_id;
+    let call_ctx = state.call_ctx()?;
+    let last_callee_id = call_ctx.last_callee_id;
+    let return_data_offset = call_ctx.last_callee_return_data_offset;
+    let return_data_length = call_ctx.last_callee_return_data_length;
+    // The return data is copied from the memory of last callee, and it's
+    // guaranteed to be in bound, otherwise it fails with
+    // `ExecError::ReturnDataOutOfBounds`.
+    let bytes = call_ctx.return_data[offset as usize..(offset + length) as usize].to_vec();
+
+    let (src_addr, src_addr_end) = (
+        return_data_offset + offset,
+        return_data_offset + return_data_length,
+    );
+
+    let mut copy_steps = Vec::with_capacity(length as usize);
+    for (idx, byte) in bytes.into_iter().enumerate() {
+        let idx = idx as u64;
+        state.push_op(
+            exec_step,
+            RW::READ,
+            MemoryOp::new(last_callee_id, (src_addr + idx).into(), byte),
+        );
+        state.memory_write(exec_step, (dest_offset + idx).into(), byte)?;
+        copy_steps.push((byte, false));
+    }
+
+    Ok(CopyEvent {
+        src_type: CopyDataType::Memory,
+        src_id: NumberOrHash::Number(last_callee_id),
+        src_addr,
+        src_addr_end,
+        dst_type: CopyDataType::Memory,
+        dst_id: NumberOrHash::Number(call_id),
+        dst_addr: dest_offset,
+        log_id: None,
+        rw_counter_start,
+        bytes: copy_steps,
+    })
+}
+
 #[cfg(test)]
 mod return_tests {
     use crate::mock::BlockData;
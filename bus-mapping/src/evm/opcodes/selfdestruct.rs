@@ -0,0 +1,236 @@
+use super::Opcode;
+use crate::{
+    circuit_input_builder::{CircuitInputStateRef, ExecStep},
+    operation::{
+        AccountDestructedOp, AccountField, AccountOp, CallContextField, TxAccessListAccountOp, RW,
+    },
+    Error,
+};
+use eth_types::{GethExecStep, ToAddress, ToWord, Word};
+
+/// Placeholder structure used to implement [`Opcode`] trait over it
+/// corresponding to the `OpcodeId::SELFDESTRUCT` `OpcodeId`.
+///
+/// The whole balance of the current account is moved to the beneficiary and
+/// the current account is marked as destructed, it's then cleared at the end
+/// of the transaction. Since
+/// [EIP-3529](https://eips.ethereum.org/EIPS/eip-3529) there is no refund for
+/// SELFDESTRUCT anymore.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Selfdestruct;
+
+impl Opcode for Selfdestruct {
+    fn gen_associated_ops(
+        state: &mut CircuitInputStateRef,
+        geth_steps: &[GethExecStep],
+    ) -> Result<Vec<ExecStep>, Error> {
+        let geth_step = &geth_steps[0];
+        let mut exec_step = state.new_step(geth_step)?;
+
+        let tx_id = state.tx_ctx.id();
+        let call = state.call()?.clone();
+
+        let beneficiary = geth_step.stack.last()?.to_address();
+        state.stack_read(
+            &mut exec_step,
+            geth_step.stack.last_filled(),
+            geth_step.stack.last()?,
+        )?;
+
+        // NOTE: `rw_counter_end_of_reversion` is not known yet, the proper value is
+        // set later in `CircuitInputBuilder::set_value_ops_call_context_rwc_eor`
+        for (field, value) in [
+            (CallContextField::TxId, tx_id.into()),
+            (
+                CallContextField::RwCounterEndOfReversion,
+                call.rw_counter_end_of_reversion.into(),
+            ),
+            (
+                CallContextField::IsPersistent,
+                (call.is_persistent as u64).into(),
+            ),
+            (CallContextField::CalleeAddress, call.address.to_word()),
+            (CallContextField::IsStatic, (call.is_static as u64).into()),
+        ] {
+            state.call_context_read(&mut exec_step, call.call_id, field, value);
+        }
+
+        let is_warm = state.sdb.check_account_in_access_list(&beneficiary);
+        state.push_op_reversible(
+            &mut exec_step,
+            RW::WRITE,
+            TxAccessListAccountOp {
+                tx_id,
+                address: beneficiary,
+                is_warm: true,
+                is_warm_prev: is_warm,
+            },
+        )?;
+
+        // Nonce and code hash of beneficiary are read to check if it's empty.
+        let (_, beneficiary_account) = state.sdb.get_account(&beneficiary);
+        let beneficiary_balance_prev = beneficiary_account.balance;
+        for (field, value) in [
+            (AccountField::Nonce, beneficiary_account.nonce),
+            (
+                AccountField::CodeHash,
+                beneficiary_account.code_hash.to_word(),
+            ),
+        ] {
+            state.account_read(&mut exec_step, beneficiary, field, value, value)?;
+        }
+
+        // The balance is first added to beneficiary, and then the balance of
+        // current account is set to 0, so it's burnt if the beneficiary is the
+        // current account itself.
+        let (found, account) = state.sdb.get_account(&call.address);
+        if !found {
+            return Err(Error::AccountNotFound(call.address));
+        }
+        let value = account.balance;
+        state.push_op_reversible(
+            &mut exec_step,
+            RW::WRITE,
+            AccountOp {
+                address: beneficiary,
+                field: AccountField::Balance,
+                value: beneficiary_balance_prev + value,
+                value_prev: beneficiary_balance_prev,
+            },
+        )?;
+        let (_, account) = state.sdb.get_account(&call.address);
+        let balance_prev = account.balance;
+        state.push_op_reversible(
+            &mut exec_step,
+            RW::WRITE,
+            AccountOp {
+                address: call.address,
+                field: AccountField::Balance,
+                value: Word::zero(),
+                value_prev: balance_prev,
+            },
+        )?;
+
+        let is_destructed_prev = state.sdb.is_account_destructed(&call.address);
+        state.push_op_reversible(
+            &mut exec_step,
+            RW::WRITE,
+            AccountDestructedOp {
+                tx_id,
+                address: call.address,
+                is_destructed: true,
+                is_destructed_prev,
+            },
+        )?;
+
+        state.call_context_read(
+            &mut exec_step,
+            call.call_id,
+            CallContextField::IsSuccess,
+            1.into(),
+        );
+        if !call.is_root {
+            state.handle_restore_context(geth_steps, &mut exec_step)?;
+        }
+
+        state.handle_return(geth_step)?;
+        Ok(vec![exec_step])
+    }
+}
+
+#[cfg(test)]
+mod selfdestruct_tests {
+    use super::*;
+    use crate::{circuit_input_builder::ExecState, mock::BlockData};
+    use eth_types::{
+        address, bytecode,
+        evm_types::{GasCost, OpcodeId},
+        geth_types::GethData,
+    };
+    use mock::{
+        eth,
+        test_ctx::{helpers::*, TestContext},
+        MOCK_ACCOUNTS,
+    };
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn selfdestruct_opcode_impl() {
+        let beneficiary = address!("0x0000000000000000000000000000000000000bee");
+        let code = bytecode! {
+            PUSH20(beneficiary.to_word())
+            SELFDESTRUCT
+        };
+
+        // Get the execution steps from the external tracer
+        let block: GethData = TestContext::<2, 1>::new(
+            None,
+            account_0_code_account_1_no_code(code),
+            tx_from_1_to_0,
+            |block, _tx| block.number(0xcafeu64),
+        )
+        .unwrap()
+        .into();
+
+        let mut builder = BlockData::new_from_geth_data(block.clone()).new_circuit_input_builder();
+        builder
+            .handle_block(&block.eth_block, &block.geth_traces)
+            .unwrap();
+
+        let step = builder.block.txs()[0]
+            .steps()
+            .iter()
+            .find(|step| step.exec_state == ExecState::Op(OpcodeId::SELFDESTRUCT))
+            .unwrap();
+
+        // Cold access to an empty beneficiary with value transfer
+        assert_eq!(
+            step.gas_cost.as_u64(),
+            GasCost::SELFDESTRUCT.as_u64()
+                + GasCost::COLD_ACCOUNT_ACCESS.as_u64()
+                + GasCost::NEW_ACCOUNT.as_u64()
+        );
+
+        assert_eq!(
+            [9, 10]
+                .map(|idx| &builder.block.container.account
+                    [step.bus_mapping_instance[idx].as_usize()])
+                .map(|operation| (operation.rw(), operation.op())),
+            [
+                (
+                    RW::WRITE,
+                    &AccountOp {
+                        address: beneficiary,
+                        field: AccountField::Balance,
+                        value: eth(10),
+                        value_prev: Word::zero(),
+                    }
+                ),
+                (
+                    RW::WRITE,
+                    &AccountOp {
+                        address: MOCK_ACCOUNTS[0],
+                        field: AccountField::Balance,
+                        value: Word::zero(),
+                        value_prev: eth(10),
+                    }
+                ),
+            ]
+        );
+
+        let account_destructed_op =
+            &builder.block.container.account_destructed[step.bus_mapping_instance[11].as_usize()];
+        assert_eq!(
+            (account_destructed_op.rw(), account_destructed_op.op()),
+            (
+                RW::WRITE,
+                &AccountDestructedOp {
+                    tx_id: 1,
+                    address: MOCK_ACCOUNTS[0],
+                    is_destructed: true,
+                    is_destructed_prev: false,
+                }
+            )
+        );
+    }
+}
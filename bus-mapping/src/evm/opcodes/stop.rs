@@ -87,6 +87,9 @@ impl Opcode for Stop {
             ] {
                 state.call_context_write(&mut exec_step, caller.call_id, field, value);
             }
+            state
+                .caller_ctx_mut()?
+                .update_last_callee(call.call_id, 0, vec![]);
         }
 
         state.handle_return(geth_step)?;
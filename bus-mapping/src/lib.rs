@@ -234,6 +234,7 @@ pub mod exec_trace;
 pub(crate) mod geth_errors;
 pub mod mock;
 pub mod operation;
+pub mod precompile;
 pub mod rpc;
 pub mod state_db;
 pub use error::Error;
@@ -0,0 +1,168 @@
+//! Precompiled contracts of the EVM, which are called by *CALL* opcodes like
+//! any other account, but are executed natively instead of running bytecode.
+
+use eth_types::{evm_types::GasCost, Address};
+use revm_precompile::{Precompile, Precompiles};
+use strum_macros::EnumIter;
+
+/// Addresses of the precompiled contracts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, EnumIter)]
+pub enum PrecompileCalls {
+    /// Elliptic Curve Recovery
+    ECRecover = 0x01,
+    /// SHA2-256 hash function
+    Sha256 = 0x02,
+    /// Ripemd-160 hash function
+    Ripemd160 = 0x03,
+    /// Identity function
+    Identity = 0x04,
+    /// Modular exponentiation
+    Modexp = 0x05,
+    /// Point addition on the bn256 curve
+    Bn256Add = 0x06,
+    /// Scalar multiplication on the bn256 curve
+    Bn256ScalarMul = 0x07,
+    /// Bilinear function on groups on the bn256 curve
+    Bn256Pairing = 0x08,
+    /// Compression function F used in the BLAKE2 cryptographic hashing
+    /// algorithm
+    Blake2F = 0x09,
+}
+
+impl From<PrecompileCalls> for Address {
+    fn from(value: PrecompileCalls) -> Self {
+        let mut addr = [0u8; 20];
+        addr[19] = value as u8;
+        Self(addr)
+    }
+}
+
+impl From<PrecompileCalls> for u64 {
+    fn from(value: PrecompileCalls) -> Self {
+        value as u64
+    }
+}
+
+impl From<u8> for PrecompileCalls {
+    fn from(value: u8) -> Self {
+        match value {
+            0x01 => Self::ECRecover,
+            0x02 => Self::Sha256,
+            0x03 => Self::Ripemd160,
+            0x04 => Self::Identity,
+            0x05 => Self::Modexp,
+            0x06 => Self::Bn256Add,
+            0x07 => Self::Bn256ScalarMul,
+            0x08 => Self::Bn256Pairing,
+            0x09 => Self::Blake2F,
+            _ => unreachable!("precompile contracts only from 0x01 to 0x09"),
+        }
+    }
+}
+
+impl PrecompileCalls {
+    /// Get the address of the precompiled contract.
+    pub fn address(&self) -> Address {
+        (*self).into()
+    }
+
+    /// Get the base gas cost of the precompiled contract, which is paid
+    /// regardless of its input.
+    pub fn base_gas_cost(&self) -> GasCost {
+        match self {
+            Self::ECRecover => GasCost(3_000),
+            Self::Sha256 => GasCost(60),
+            Self::Ripemd160 => GasCost(600),
+            Self::Identity => GasCost::PRECOMPILE_IDENTITY_BASE,
+            Self::Modexp => GasCost(200),
+            Self::Bn256Add => GasCost(150),
+            Self::Bn256ScalarMul => GasCost(6_000),
+            Self::Bn256Pairing => GasCost(45_000),
+            Self::Blake2F => GasCost(0),
+        }
+    }
+}
+
+/// Execute the precompiled contract with the given input and gas limit,
+/// returning its output and the gas consumed.  A failed execution returns no
+/// output and consumes all the gas.
+pub(crate) fn execute_precompiled(
+    precompile: PrecompileCalls,
+    input: &[u8],
+    gas: u64,
+) -> (Vec<u8>, u64) {
+    let precompile_fn = match Precompiles::berlin().get(precompile.address().as_fixed_bytes()) {
+        Some(Precompile::Standard(precompile_fn)) => precompile_fn,
+        _ => unreachable!("precompiled contracts of berlin are standard"),
+    };
+
+    match precompile_fn(input, gas) {
+        Ok((gas_cost, output)) => (output, gas_cost),
+        Err(_) => (vec![], gas),
+    }
+}
+
+#[cfg(test)]
+mod precompile_tests {
+    use super::*;
+    use strum::IntoEnumIterator;
+
+    #[test]
+    fn precompile_addresses() {
+        for (i, precompile) in PrecompileCalls::iter().enumerate() {
+            let address = precompile.address();
+            assert_eq!(address, Address::from_low_u64_be(i as u64 + 1));
+            assert_eq!(PrecompileCalls::from(address.0[19]), precompile);
+            assert_eq!(u64::from(precompile), i as u64 + 1);
+        }
+    }
+
+    #[test]
+    fn execute_identity() {
+        let input = [1u8, 2, 3, 4, 5];
+        let (output, gas_cost) = execute_precompiled(PrecompileCalls::Identity, &input, 100);
+        assert_eq!(output, input);
+        assert_eq!(gas_cost, 15 + 3);
+
+        // Not enough gas to pay the base cost.
+        let (output, gas_cost) = execute_precompiled(PrecompileCalls::Identity, &input, 10);
+        assert!(output.is_empty());
+        assert_eq!(gas_cost, 10);
+    }
+
+    #[test]
+    fn execute_sha256() {
+        let (output, gas_cost) = execute_precompiled(PrecompileCalls::Sha256, &[], 100);
+        assert_eq!(
+            hex::encode(output),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_eq!(gas_cost, PrecompileCalls::Sha256.base_gas_cost().as_u64());
+    }
+
+    #[test]
+    fn execute_modexp() {
+        // 3 ** 2 % 5, with the lengths of base, exponent and modulus as 32
+        // bytes words.
+        let mut input = vec![0u8; 96];
+        for idx in 0..3 {
+            input[32 * idx + 31] = 1;
+        }
+        input.extend([3, 2, 5]);
+        let (output, gas_cost) = execute_precompiled(PrecompileCalls::Modexp, &input, 1000);
+        assert_eq!(output, [4]);
+        assert_eq!(gas_cost, PrecompileCalls::Modexp.base_gas_cost().as_u64());
+    }
+
+    #[test]
+    fn execute_bn256_add_invalid_point() {
+        // (1, 1) isn't on the curve, so the call fails and consumes all the
+        // gas.
+        let mut input = vec![0u8; 128];
+        input[31] = 1;
+        input[63] = 1;
+        let (output, gas_cost) = execute_precompiled(PrecompileCalls::Bn256Add, &input, 1000);
+        assert!(output.is_empty());
+        assert_eq!(gas_cost, 1000);
+    }
+}
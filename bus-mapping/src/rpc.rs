@@ -1,14 +1,21 @@
 //! Module which contains all the RPC calls that are needed at any point to
 //! query a Geth node in order to get a Block, Tx or Trace info.
 
+mod http;
+mod replay;
+
 use crate::Error;
+use async_trait::async_trait;
 use eth_types::{
     Address, Block, Bytes, EIP1186ProofResponse, GethExecTrace, Hash, ResultGethExecTraces,
     Transaction, Word, U64,
 };
 pub use ethers_core::types::BlockNumber;
 use ethers_providers::JsonRpcClient;
-use serde::Serialize;
+pub use http::BatchHttp;
+pub use replay::{RecordingProvider, ReplayError, ReplayProvider, RpcRecord};
+use serde::{de::DeserializeOwned, Serialize};
+use std::fmt::Debug;
 
 /// Serialize a type.
 ///
@@ -47,6 +54,39 @@ impl Default for GethLoggerConfig {
     }
 }
 
+/// JSON-RPC transport which can send several requests of the same method in a
+/// single batch request.
+#[async_trait]
+pub trait BatchJsonRpcClient: JsonRpcClient {
+    /// Send a batch of requests of `method`, one for each of `params`, and
+    /// return their responses in the same order.
+    async fn request_batch<T, R>(
+        &self,
+        method: &str,
+        params: Vec<T>,
+    ) -> Result<Vec<R>, Self::Error>
+    where
+        T: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send;
+}
+
+// The mock provider serves the requests of a batch one by one.
+#[cfg(test)]
+#[async_trait]
+impl BatchJsonRpcClient for ethers_providers::MockProvider {
+    async fn request_batch<T, R>(&self, method: &str, params: Vec<T>) -> Result<Vec<R>, Self::Error>
+    where
+        T: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        let mut responses = Vec::with_capacity(params.len());
+        for params in params {
+            responses.push(self.request(method, params).await?);
+        }
+        Ok(responses)
+    }
+}
+
 /// Placeholder structure designed to contain the methods that the BusMapping
 /// needs in order to enable Geth queries.
 pub struct GethClient<P: JsonRpcClient>(pub P);
@@ -101,6 +141,20 @@ impl<P: JsonRpcClient> GethClient<P> {
             .map_err(|e| Error::JSONRpcError(e.into()))
     }
 
+    /// Calls `eth_getBlockByNumber` via JSON-RPC returning a [`Block`] with
+    /// only the hashes of its transactions.
+    pub async fn get_block_header_by_number(
+        &self,
+        block_num: BlockNumber,
+    ) -> Result<Block<Hash>, Error> {
+        let num = serialize(&block_num);
+        let flag = serialize(&false);
+        self.0
+            .request("eth_getBlockByNumber", [num, flag])
+            .await
+            .map_err(|e| Error::JSONRpcError(e.into()))
+    }
+
     /// Calls `debug_traceBlockByHash` via JSON-RPC returning a
     /// [`Vec<GethExecTrace>`] with each GethTrace corresponding to 1
     /// transaction of the block.
@@ -185,4 +239,56 @@ impl<P: JsonRpcClient> GethClient<P> {
     }
 }
 
+impl<P: BatchJsonRpcClient> GethClient<P> {
+    /// Calls `eth_getProof` via a JSON-RPC batch request for each account and
+    /// its storage keys, returning their [`EIP1186ProofResponse`] in the same
+    /// order.
+    pub async fn get_proofs(
+        &self,
+        accounts: Vec<(Address, Vec<Word>)>,
+        block_num: BlockNumber,
+    ) -> Result<Vec<EIP1186ProofResponse>, Error> {
+        let num = serialize(&block_num);
+        let params = accounts
+            .into_iter()
+            .map(|(account, keys)| [serialize(&account), serialize(&keys), num.clone()])
+            .collect();
+        self.0
+            .request_batch("eth_getProof", params)
+            .await
+            .map_err(|e| Error::JSONRpcError(e.into()))
+    }
+
+    /// Calls `eth_getCode` via a JSON-RPC batch request for each contract,
+    /// returning their codes in the same order.
+    pub async fn get_codes(
+        &self,
+        contract_addresses: Vec<Address>,
+        block_num: BlockNumber,
+    ) -> Result<Vec<Vec<u8>>, Error> {
+        let num = serialize(&block_num);
+        let params = contract_addresses
+            .into_iter()
+            .map(|address| [serialize(&address), num.clone()])
+            .collect();
+        let resp: Vec<Bytes> = self
+            .0
+            .request_batch("eth_getCode", params)
+            .await
+            .map_err(|e| Error::JSONRpcError(e.into()))?;
+        Ok(resp.into_iter().map(|code| code.to_vec()).collect())
+    }
+
+    /// Calls `debug_dbGet` via a JSON-RPC batch request for each hash,
+    /// returning the rlp-encoded trie nodes of the hashes in the same order.
+    /// The hash of a node is its key in the database of an archive node.
+    pub async fn get_nodes(&self, hashes: Vec<Hash>) -> Result<Vec<Bytes>, Error> {
+        let params = hashes.iter().map(|hash| [serialize(hash)]).collect();
+        self.0
+            .request_batch("debug_dbGet", params)
+            .await
+            .map_err(|e| Error::JSONRpcError(e.into()))
+    }
+}
+
 // Integration tests found in `integration-tests/tests/rpc.rs`.
@@ -0,0 +1,117 @@
+//! HTTP JSON-RPC transport which supports batch requests.
+
+use super::BatchJsonRpcClient;
+use async_trait::async_trait;
+use ethers_providers::{Http, HttpClientError, JsonRpcClient, JsonRpcError};
+use reqwest::{Client, Url};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::fmt::Debug;
+
+#[derive(Serialize)]
+struct Request<'a, T> {
+    id: usize,
+    jsonrpc: &'a str,
+    method: &'a str,
+    params: T,
+}
+
+#[derive(Deserialize)]
+struct Response {
+    id: usize,
+    #[serde(default)]
+    result: serde_json::Value,
+    error: Option<JsonRpcError>,
+}
+
+/// HTTP transport which sends the requests of a batch in a single JSON-RPC
+/// batch request.  Single requests are sent by an [`Http`] transport.
+#[derive(Debug, Clone)]
+pub struct BatchHttp {
+    http: Http,
+    client: Client,
+    url: Url,
+}
+
+impl BatchHttp {
+    /// Create a new `BatchHttp` transport to the node at `url`.
+    pub fn new(url: Url) -> Self {
+        Self {
+            http: Http::new(url.clone()),
+            client: Client::new(),
+            url,
+        }
+    }
+}
+
+#[async_trait]
+impl JsonRpcClient for BatchHttp {
+    type Error = HttpClientError;
+
+    async fn request<T, R>(&self, method: &str, params: T) -> Result<R, Self::Error>
+    where
+        T: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned,
+    {
+        self.http.request(method, params).await
+    }
+}
+
+#[async_trait]
+impl BatchJsonRpcClient for BatchHttp {
+    async fn request_batch<T, R>(&self, method: &str, params: Vec<T>) -> Result<Vec<R>, Self::Error>
+    where
+        T: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        if params.is_empty() {
+            return Ok(Vec::new());
+        }
+        let requests: Vec<Request<T>> = params
+            .into_iter()
+            .enumerate()
+            .map(|(id, params)| Request {
+                id,
+                jsonrpc: "2.0",
+                method,
+                params,
+            })
+            .collect();
+        let text = self
+            .client
+            .post(self.url.clone())
+            .json(&requests)
+            .send()
+            .await?
+            .text()
+            .await?;
+        let invalid = |err| HttpClientError::SerdeJson {
+            err,
+            text: text.clone(),
+        };
+
+        // The responses of a batch can be in any order.
+        let responses: Vec<Response> = serde_json::from_str(&text).map_err(invalid)?;
+        let mut results = vec![None; requests.len()];
+        for response in responses {
+            if let Some(err) = response.error {
+                return Err(HttpClientError::JsonRpcError(err));
+            }
+            if let Some(result) = results.get_mut(response.id) {
+                *result = Some(response.result);
+            }
+        }
+        results
+            .into_iter()
+            .enumerate()
+            .map(|(id, result)| {
+                let result = result.ok_or_else(|| {
+                    invalid(serde::de::Error::custom(format!(
+                        "missing response to the request {} of the batch",
+                        id
+                    )))
+                })?;
+                serde_json::from_value(result).map_err(invalid)
+            })
+            .collect()
+    }
+}
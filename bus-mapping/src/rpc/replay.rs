@@ -0,0 +1,325 @@
+//! JSON-RPC providers to record the responses of a node and to replay them
+//! later, so that a [`GethClient`](super::GethClient) can be used without
+//! access to the node.
+
+use super::BatchJsonRpcClient;
+use crate::Error;
+use async_trait::async_trait;
+use ethers_providers::{JsonRpcClient, ProviderError};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::error::Error as StdError;
+use std::fmt::{self, Debug, Display, Formatter};
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+use std::sync::Mutex;
+
+/// JSON-RPC request along with the response returned by the node.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RpcRecord {
+    /// Method of the request
+    pub method: String,
+    /// Parameters of the request
+    pub params: serde_json::Value,
+    /// Response of the node
+    pub response: serde_json::Value,
+}
+
+/// Error type of [`RecordingProvider`] and [`ReplayProvider`].
+#[derive(Debug)]
+pub enum ReplayError {
+    /// Error of the provider being recorded.
+    ProviderError(ProviderError),
+    /// Serde de/serialization error.
+    SerdeError(serde_json::Error),
+    /// No response was recorded for the request.
+    MissingResponse {
+        /// Method of the request
+        method: String,
+        /// Parameters of the request
+        params: serde_json::Value,
+    },
+}
+
+impl Display for ReplayError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl StdError for ReplayError {}
+
+impl From<ReplayError> for ProviderError {
+    fn from(err: ReplayError) -> Self {
+        match err {
+            ReplayError::ProviderError(err) => err,
+            ReplayError::SerdeError(err) => ProviderError::SerdeJson(err),
+            err => ProviderError::JsonRpcClientError(Box::new(err)),
+        }
+    }
+}
+
+/// Provider that forwards the requests to another provider, and records each
+/// request along with its response so that they can be saved and served by a
+/// [`ReplayProvider`].
+#[derive(Debug)]
+pub struct RecordingProvider<P> {
+    inner: P,
+    records: Mutex<Vec<RpcRecord>>,
+}
+
+impl<P> RecordingProvider<P> {
+    /// Create a new `RecordingProvider` which records the requests to `inner`.
+    pub fn new(inner: P) -> Self {
+        Self {
+            inner,
+            records: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Return the requests recorded so far, in the order the responses were
+    /// received.
+    pub fn records(&self) -> Vec<RpcRecord> {
+        self.records.lock().unwrap().clone()
+    }
+
+    /// Save the requests recorded so far as JSON to the file at `path`.
+    pub fn save<T: AsRef<Path>>(&self, path: T) -> Result<(), Error> {
+        let writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer(writer, &*self.records.lock().unwrap()).map_err(Error::SerdeError)
+    }
+}
+
+#[async_trait]
+impl<P: JsonRpcClient> JsonRpcClient for RecordingProvider<P> {
+    type Error = ReplayError;
+
+    async fn request<T, R>(&self, method: &str, params: T) -> Result<R, Self::Error>
+    where
+        T: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned,
+    {
+        let params_value = serde_json::to_value(&params).map_err(ReplayError::SerdeError)?;
+        let response: serde_json::Value = self
+            .inner
+            .request(method, params)
+            .await
+            .map_err(|e| ReplayError::ProviderError(e.into()))?;
+        self.records.lock().unwrap().push(RpcRecord {
+            method: method.to_string(),
+            params: params_value,
+            response: response.clone(),
+        });
+        serde_json::from_value(response).map_err(ReplayError::SerdeError)
+    }
+}
+
+// The requests of a batch are recorded one by one, so that they can be
+// replayed in batches of any size.
+#[async_trait]
+impl<P: BatchJsonRpcClient> BatchJsonRpcClient for RecordingProvider<P> {
+    async fn request_batch<T, R>(&self, method: &str, params: Vec<T>) -> Result<Vec<R>, Self::Error>
+    where
+        T: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        let params_values = params
+            .iter()
+            .map(serde_json::to_value)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(ReplayError::SerdeError)?;
+        let responses: Vec<serde_json::Value> = self
+            .inner
+            .request_batch(method, params)
+            .await
+            .map_err(|e| ReplayError::ProviderError(e.into()))?;
+        self.records
+            .lock()
+            .unwrap()
+            .extend(
+                params_values
+                    .into_iter()
+                    .zip(responses.iter())
+                    .map(|(params, response)| RpcRecord {
+                        method: method.to_string(),
+                        params,
+                        response: response.clone(),
+                    }),
+            );
+        responses
+            .into_iter()
+            .map(|response| serde_json::from_value(response).map_err(ReplayError::SerdeError))
+            .collect()
+    }
+}
+
+/// Provider that serves the responses recorded by a [`RecordingProvider`].
+/// The responses are looked up by method and parameters, so that they don't
+/// depend on the order of the requests.  When the same request was recorded
+/// several times, its responses are served in the recorded order, and the
+/// last one is served again once they are exhausted.
+#[derive(Debug)]
+pub struct ReplayProvider {
+    responses: Mutex<HashMap<(String, String), VecDeque<serde_json::Value>>>,
+}
+
+fn request_key(method: &str, params: &serde_json::Value) -> (String, String) {
+    (method.to_string(), params.to_string())
+}
+
+impl ReplayProvider {
+    /// Create a new `ReplayProvider` which serves the responses of `records`.
+    pub fn new<I: IntoIterator<Item = RpcRecord>>(records: I) -> Self {
+        let mut responses: HashMap<_, VecDeque<_>> = HashMap::new();
+        for record in records {
+            responses
+                .entry(request_key(&record.method, &record.params))
+                .or_default()
+                .push_back(record.response);
+        }
+        Self {
+            responses: Mutex::new(responses),
+        }
+    }
+
+    /// Load the records saved with [`RecordingProvider::save`] from the file
+    /// at `path`.
+    pub fn load<T: AsRef<Path>>(path: T) -> Result<Self, Error> {
+        let reader = BufReader::new(File::open(path)?);
+        let records: Vec<RpcRecord> = serde_json::from_reader(reader).map_err(Error::SerdeError)?;
+        Ok(Self::new(records))
+    }
+}
+
+#[async_trait]
+impl JsonRpcClient for ReplayProvider {
+    type Error = ReplayError;
+
+    async fn request<T, R>(&self, method: &str, params: T) -> Result<R, Self::Error>
+    where
+        T: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned,
+    {
+        let params = serde_json::to_value(&params).map_err(ReplayError::SerdeError)?;
+        let response = {
+            let mut responses = self.responses.lock().unwrap();
+            let queue = responses
+                .get_mut(&request_key(method, &params))
+                .filter(|queue| !queue.is_empty())
+                .ok_or_else(|| ReplayError::MissingResponse {
+                    method: method.to_string(),
+                    params,
+                })?;
+            if queue.len() > 1 {
+                queue.pop_front().unwrap()
+            } else {
+                queue[0].clone()
+            }
+        };
+        serde_json::from_value(response).map_err(ReplayError::SerdeError)
+    }
+}
+
+#[async_trait]
+impl BatchJsonRpcClient for ReplayProvider {
+    async fn request_batch<T, R>(&self, method: &str, params: Vec<T>) -> Result<Vec<R>, Self::Error>
+    where
+        T: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        let mut responses = Vec::with_capacity(params.len());
+        for params in params {
+            responses.push(self.request(method, params).await?);
+        }
+        Ok(responses)
+    }
+}
+
+#[cfg(test)]
+mod replay_tests {
+    use super::*;
+    use crate::rpc::{BlockNumber, GethClient};
+    use eth_types::{Block, Bytes, Hash, H256, U64};
+    use ethers_providers::MockProvider;
+
+    fn header(number: u64) -> Block<Hash> {
+        Block {
+            number: Some(U64::from(number)),
+            hash: Some(H256::from_low_u64_be(0x1000 + number)),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn record_and_replay() {
+        // The responses of the MockProvider are served in reverse order.
+        let mock = MockProvider::new();
+        mock.push(header(2)).unwrap();
+        mock.push(U64::from(1338)).unwrap();
+        mock.push(U64::from(1337)).unwrap();
+
+        let cli = GethClient::new(RecordingProvider::new(mock));
+        assert_eq!(cli.get_chain_id().await.unwrap(), 1337);
+        assert_eq!(cli.get_chain_id().await.unwrap(), 1338);
+        assert_eq!(
+            cli.get_block_header_by_number(BlockNumber::from(2))
+                .await
+                .unwrap(),
+            header(2)
+        );
+        assert_eq!(cli.0.records().len(), 3);
+
+        let path = std::env::temp_dir().join(format!("rpc_records_{}.json", std::process::id()));
+        cli.0.save(&path).unwrap();
+        let replay = ReplayProvider::load(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        // The requests are replayed in a different order, and the last
+        // response of a repeated request is served again.
+        let cli = GethClient::new(replay.unwrap());
+        assert_eq!(
+            cli.get_block_header_by_number(BlockNumber::from(2))
+                .await
+                .unwrap(),
+            header(2)
+        );
+        assert_eq!(cli.get_chain_id().await.unwrap(), 1337);
+        assert_eq!(cli.get_chain_id().await.unwrap(), 1338);
+        assert_eq!(cli.get_chain_id().await.unwrap(), 1338);
+    }
+
+    #[tokio::test]
+    async fn record_and_replay_batch() {
+        let nodes = [Bytes::from(vec![0xc1, 0x01]), Bytes::from(vec![0xc1, 0x02])];
+        let hashes = [H256::repeat_byte(1), H256::repeat_byte(2)];
+        let mock = MockProvider::new();
+        mock.push(nodes[1].clone()).unwrap();
+        mock.push(nodes[0].clone()).unwrap();
+
+        let cli = GethClient::new(RecordingProvider::new(mock));
+        assert_eq!(cli.get_nodes(hashes.to_vec()).await.unwrap(), nodes);
+        assert_eq!(cli.0.records().len(), 2);
+
+        // The requests of the batch are replayed in another batch.
+        let cli = GethClient::new(ReplayProvider::new(cli.0.records()));
+        assert_eq!(
+            cli.get_nodes(vec![hashes[1]]).await.unwrap(),
+            vec![nodes[1].clone()]
+        );
+    }
+
+    #[tokio::test]
+    async fn replay_missing_response() {
+        let cli = GethClient::new(ReplayProvider::new(vec![RpcRecord {
+            method: "eth_chainId".to_string(),
+            params: serde_json::Value::Null,
+            response: serde_json::json!("0x539"),
+        }]));
+        assert_eq!(cli.get_chain_id().await.unwrap(), 1337);
+        assert!(matches!(
+            cli.get_block_header_by_number(BlockNumber::from(2)).await,
+            Err(Error::JSONRpcError(ProviderError::JsonRpcClientError(_)))
+        ));
+    }
+}
@@ -66,6 +66,9 @@ impl fmt::Debug for Gas {
 pub const MAX_REFUND_QUOTIENT_OF_GAS_USED: usize = 5;
 /// Gas stipend when CALL or CALLCODE is attached with value.
 pub const GAS_STIPEND_CALL_WITH_VALUE: u64 = 2300;
+/// Maximum size in bytes of the code of a contract, defined in
+/// [EIP-170](https://eips.ethereum.org/EIPS/eip-170).
+pub const MAX_CODE_SIZE: u64 = 0x6000;
 
 /// Defines the gas consumption.
 #[derive(Clone, Copy, Eq, PartialEq, PartialOrd, Ord, Serialize, Deserialize)]
@@ -94,12 +97,17 @@ impl GasCost {
     pub const SLOW: Self = Self(10);
     /// Constant cost for ext step
     pub const EXT: Self = Self(20);
+    /// Constant cost for every byte in the exponent of EXP
+    pub const EXP_BYTE_TIMES: Self = Self(50);
     /// Constant cost for SHA3
     pub const SHA3: Self = Self(30);
     /// Constant cost for SELFDESTRUCT
     pub const SELFDESTRUCT: Self = Self(5000);
     /// Constant cost for CREATE
     pub const CREATE: Self = Self(32000);
+    /// Constant cost for every byte of the code deposited by a contract
+    /// creation
+    pub const CODE_DEPOSIT_BYTE_COST: Self = Self(200);
     /// Constant cost for copying every word
     pub const COPY: Self = Self(3);
     /// Constant cost for copying every word, specifically in the case of SHA3
@@ -118,6 +126,9 @@ impl GasCost {
     /// Constant cost for a storage clear. EIP-3529 changed it to 4800 from
     /// 15000.
     pub const SSTORE_CLEARS_SCHEDULE: Self = Self(4800);
+    /// Minimum gas left required by SSTORE, which fails if the gas left is not
+    /// greater than it. It's introduced by EIP-2200 to prevent reentrancy.
+    pub const SSTORE_SENTRY: Self = Self(2300);
     /// Constant cost for a non-creation transaction
     pub const TX: Self = Self(21000);
     /// Constant cost for a creation transaction
@@ -132,6 +143,10 @@ impl GasCost {
     pub const MEMORY_EXPANSION_LINEAR_COEFF: Self = Self(3);
     /// constant gas for logs op codes
     pub const LOG: Self = Self(375);
+    /// Constant cost of the identity precompile
+    pub const PRECOMPILE_IDENTITY_BASE: Self = Self(15);
+    /// Constant cost of the identity precompile for copying every word
+    pub const PRECOMPILE_IDENTITY_PER_WORD: Self = Self(3);
 }
 
 impl GasCost {
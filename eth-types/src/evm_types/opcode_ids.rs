@@ -662,6 +662,156 @@ impl OpcodeId {
         }
     }
 
+    /// Returns the number of stack items popped and pushed by the `OpcodeId`
+    pub const fn stack_io(&self) -> (u32, u32) {
+        match self {
+            OpcodeId::STOP | OpcodeId::JUMPDEST | OpcodeId::INVALID(_) => (0, 0),
+            OpcodeId::ADD
+            | OpcodeId::MUL
+            | OpcodeId::SUB
+            | OpcodeId::DIV
+            | OpcodeId::SDIV
+            | OpcodeId::MOD
+            | OpcodeId::SMOD
+            | OpcodeId::EXP
+            | OpcodeId::SIGNEXTEND
+            | OpcodeId::LT
+            | OpcodeId::GT
+            | OpcodeId::SLT
+            | OpcodeId::SGT
+            | OpcodeId::EQ
+            | OpcodeId::AND
+            | OpcodeId::OR
+            | OpcodeId::XOR
+            | OpcodeId::BYTE
+            | OpcodeId::SHL
+            | OpcodeId::SHR
+            | OpcodeId::SAR
+            | OpcodeId::SHA3 => (2, 1),
+            OpcodeId::ADDMOD | OpcodeId::MULMOD => (3, 1),
+            OpcodeId::ISZERO
+            | OpcodeId::NOT
+            | OpcodeId::BALANCE
+            | OpcodeId::CALLDATALOAD
+            | OpcodeId::EXTCODESIZE
+            | OpcodeId::EXTCODEHASH
+            | OpcodeId::BLOCKHASH
+            | OpcodeId::MLOAD
+            | OpcodeId::SLOAD => (1, 1),
+            OpcodeId::ADDRESS
+            | OpcodeId::ORIGIN
+            | OpcodeId::CALLER
+            | OpcodeId::CALLVALUE
+            | OpcodeId::CALLDATASIZE
+            | OpcodeId::CODESIZE
+            | OpcodeId::GASPRICE
+            | OpcodeId::RETURNDATASIZE
+            | OpcodeId::COINBASE
+            | OpcodeId::TIMESTAMP
+            | OpcodeId::NUMBER
+            | OpcodeId::DIFFICULTY
+            | OpcodeId::GASLIMIT
+            | OpcodeId::CHAINID
+            | OpcodeId::SELFBALANCE
+            | OpcodeId::BASEFEE
+            | OpcodeId::PC
+            | OpcodeId::MSIZE
+            | OpcodeId::GAS
+            | OpcodeId::PUSH1
+            | OpcodeId::PUSH2
+            | OpcodeId::PUSH3
+            | OpcodeId::PUSH4
+            | OpcodeId::PUSH5
+            | OpcodeId::PUSH6
+            | OpcodeId::PUSH7
+            | OpcodeId::PUSH8
+            | OpcodeId::PUSH9
+            | OpcodeId::PUSH10
+            | OpcodeId::PUSH11
+            | OpcodeId::PUSH12
+            | OpcodeId::PUSH13
+            | OpcodeId::PUSH14
+            | OpcodeId::PUSH15
+            | OpcodeId::PUSH16
+            | OpcodeId::PUSH17
+            | OpcodeId::PUSH18
+            | OpcodeId::PUSH19
+            | OpcodeId::PUSH20
+            | OpcodeId::PUSH21
+            | OpcodeId::PUSH22
+            | OpcodeId::PUSH23
+            | OpcodeId::PUSH24
+            | OpcodeId::PUSH25
+            | OpcodeId::PUSH26
+            | OpcodeId::PUSH27
+            | OpcodeId::PUSH28
+            | OpcodeId::PUSH29
+            | OpcodeId::PUSH30
+            | OpcodeId::PUSH31
+            | OpcodeId::PUSH32 => (0, 1),
+            OpcodeId::CALLDATACOPY | OpcodeId::CODECOPY | OpcodeId::RETURNDATACOPY => (3, 0),
+            OpcodeId::EXTCODECOPY => (4, 0),
+            OpcodeId::POP | OpcodeId::JUMP | OpcodeId::SELFDESTRUCT => (1, 0),
+            OpcodeId::MSTORE
+            | OpcodeId::MSTORE8
+            | OpcodeId::SSTORE
+            | OpcodeId::JUMPI
+            | OpcodeId::RETURN
+            | OpcodeId::REVERT => (2, 0),
+            OpcodeId::DUP1 => (1, 2),
+            OpcodeId::DUP2 => (2, 3),
+            OpcodeId::DUP3 => (3, 4),
+            OpcodeId::DUP4 => (4, 5),
+            OpcodeId::DUP5 => (5, 6),
+            OpcodeId::DUP6 => (6, 7),
+            OpcodeId::DUP7 => (7, 8),
+            OpcodeId::DUP8 => (8, 9),
+            OpcodeId::DUP9 => (9, 10),
+            OpcodeId::DUP10 => (10, 11),
+            OpcodeId::DUP11 => (11, 12),
+            OpcodeId::DUP12 => (12, 13),
+            OpcodeId::DUP13 => (13, 14),
+            OpcodeId::DUP14 => (14, 15),
+            OpcodeId::DUP15 => (15, 16),
+            OpcodeId::DUP16 => (16, 17),
+            OpcodeId::SWAP1 => (2, 2),
+            OpcodeId::SWAP2 => (3, 3),
+            OpcodeId::SWAP3 => (4, 4),
+            OpcodeId::SWAP4 => (5, 5),
+            OpcodeId::SWAP5 => (6, 6),
+            OpcodeId::SWAP6 => (7, 7),
+            OpcodeId::SWAP7 => (8, 8),
+            OpcodeId::SWAP8 => (9, 9),
+            OpcodeId::SWAP9 => (10, 10),
+            OpcodeId::SWAP10 => (11, 11),
+            OpcodeId::SWAP11 => (12, 12),
+            OpcodeId::SWAP12 => (13, 13),
+            OpcodeId::SWAP13 => (14, 14),
+            OpcodeId::SWAP14 => (15, 15),
+            OpcodeId::SWAP15 => (16, 16),
+            OpcodeId::SWAP16 => (17, 17),
+            OpcodeId::LOG0 => (2, 0),
+            OpcodeId::LOG1 => (3, 0),
+            OpcodeId::LOG2 => (4, 0),
+            OpcodeId::LOG3 => (5, 0),
+            OpcodeId::LOG4 => (6, 0),
+            OpcodeId::CREATE => (3, 1),
+            OpcodeId::CREATE2 => (4, 1),
+            OpcodeId::CALL | OpcodeId::CALLCODE => (7, 1),
+            OpcodeId::DELEGATECALL | OpcodeId::STATICCALL => (6, 1),
+        }
+    }
+
+    /// Returns the valid range `(min, max)` of the stack pointer before
+    /// executing the `OpcodeId`.  A stack pointer less than `min` results in
+    /// stack overflow, and one greater than `max` results in stack underflow.
+    pub const fn valid_stack_ptr_range(&self) -> (u32, u32) {
+        let (pops, pushes) = self.stack_io();
+        let min = if pushes > pops { pushes - pops } else { 0 };
+        // Stack has 1024 slots.
+        (min, 1024 - pops)
+    }
+
     /// Returns `true` if the `OpcodeId` has memory access
     pub const fn has_memory_access(&self) -> bool {
         matches!(
@@ -41,7 +41,7 @@ pub use ethers_core::types::{
     Address, Block, Bytes, Signature, H160, H256, H64, U256, U64,
 };
 
-use serde::{de, Deserialize, Serialize};
+use serde::{de, ser::SerializeStruct, Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fmt;
 use std::str::FromStr;
@@ -211,7 +211,7 @@ impl<F: Field> ToScalar<F> for bool {
 }
 
 /// Struct used to define the storage proof
-#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub struct StorageProof {
     /// Storage key
     pub key: U256,
@@ -222,7 +222,7 @@ pub struct StorageProof {
 }
 
 /// Struct used to define the result of `eth_getProof` call
-#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct EIP1186ProofResponse {
     /// Account address
@@ -265,7 +265,7 @@ struct GethExecStepInternal {
 
 /// The execution step type returned by geth RPC debug_trace* methods.
 /// Corresponds to `StructLogRes` in `go-ethereum/internal/ethapi/api.go`.
-#[derive(Clone, Eq, PartialEq, Serialize)]
+#[derive(Clone, Eq, PartialEq)]
 #[doc(hidden)]
 pub struct GethExecStep {
     pub pc: ProgramCounter,
@@ -349,6 +349,51 @@ impl<'de> Deserialize<'de> for GethExecStep {
     }
 }
 
+// The step is serialized in the format returned by geth, so that it can be
+// deserialized back.
+impl Serialize for GethExecStep {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let op = match self.op {
+            OpcodeId::INVALID(byte) => format!("opcode 0x{:x} not defined", byte),
+            op => format!("{:?}", op),
+        };
+        let stack: Vec<String> = self
+            .stack
+            .0
+            .iter()
+            .map(|word| format!("0x{:x}", word))
+            .collect();
+        let memory: Vec<String> = self.memory.0.chunks(32).map(hex::encode).collect();
+        let storage: HashMap<String, String> = self
+            .storage
+            .0
+            .iter()
+            .map(|(key, value)| {
+                (
+                    hex::encode(key.to_be_bytes()),
+                    hex::encode(value.to_be_bytes()),
+                )
+            })
+            .collect();
+
+        let mut step = serializer.serialize_struct("GethExecStep", 10)?;
+        step.serialize_field("pc", &self.pc)?;
+        step.serialize_field("op", &op)?;
+        step.serialize_field("gas", &self.gas)?;
+        step.serialize_field("gasCost", &self.gas_cost)?;
+        step.serialize_field("refund", &self.refund)?;
+        step.serialize_field("depth", &self.depth)?;
+        step.serialize_field("error", &self.error)?;
+        step.serialize_field("stack", &stack)?;
+        step.serialize_field("memory", &memory)?;
+        step.serialize_field("storage", &storage)?;
+        step.end()
+    }
+}
+
 /// Helper type built to deal with the weird `result` field added between
 /// `GethExecutionTrace`s in `debug_traceBlockByHash` and
 /// `debug_traceBlockByNumber` Geth JSON-RPC calls.
@@ -557,6 +602,56 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn serialize_geth_exec_trace() {
+        let trace_json = r#"
+  {
+    "gas": 26809,
+    "failed": true,
+    "returnValue": "",
+    "structLogs": [
+      {
+        "pc": 163,
+        "op": "SSTORE",
+        "gas": 5217,
+        "gasCost": 2100,
+        "refund": 4800,
+        "depth": 1,
+        "stack": [
+          "0x1003e2d2",
+          "0x2a"
+        ],
+        "storage": {
+          "0000000000000000000000000000000000000000000000000000000000000000": "000000000000000000000000000000000000000000000000000000000000006f"
+        },
+        "memory": [
+          "0000000000000000000000000000000000000000000000000000000000000000",
+          "0000000000000000000000000000000000000000000000000000000000000080"
+        ]
+      },
+      {
+        "pc": 164,
+        "op": "opcode 0xfe not defined",
+        "gas": 3117,
+        "gasCost": 0,
+        "refund": 4800,
+        "depth": 1,
+        "error": "invalid opcode: INVALID",
+        "stack": []
+      }
+    ]
+  }
+        "#;
+        let trace: GethExecTrace =
+            serde_json::from_str(trace_json).expect("json-deserialize GethExecTrace");
+        assert_eq!(trace.struct_logs[1].op, OpcodeId::INVALID(0xfe));
+
+        let serialized = serde_json::to_string(&trace).expect("json-serialize GethExecTrace");
+        let deserialized: GethExecTrace =
+            serde_json::from_str(&serialized).expect("json-deserialize GethExecTrace");
+        assert_eq!(deserialized, trace);
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,15 @@
+#!/bin/sh
+set -e
+
+# Record the JSON-RPC responses used by the `circuit_input_builder` and
+# `circuits` test groups into `rpc_records/`, so that they can later run with
+# `RPC_MODE=replay` without geth, like in the CI once they are committed.  All
+# the arguments are passed to `run.sh`, like `--sudo`.
+
+cd "$(dirname "$0")"
+rm -rf rpc_records
+RPC_MODE=record ./run.sh \
+    --steps "setup gendata tests cleanup" \
+    --tests "circuit_input_builder circuits" \
+    "$@"
+echo "+ JSON-RPC responses recorded in rpc_records/"
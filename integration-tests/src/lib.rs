@@ -3,21 +3,26 @@
 #![deny(rustdoc::broken_intra_doc_links)]
 #![deny(missing_docs)]
 
-use bus_mapping::rpc::GethClient;
+use async_trait::async_trait;
+use bus_mapping::rpc::{
+    BatchHttp, BatchJsonRpcClient, GethClient, RecordingProvider, ReplayProvider,
+};
 use env_logger::Env;
 use eth_types::Address;
 use ethers::{
     abi,
     core::k256::ecdsa::SigningKey,
     core::types::Bytes,
-    providers::{Http, Provider},
+    providers::{Http, JsonRpcClient, Provider, ProviderError},
     signers::{coins_bip39::English, MnemonicBuilder, Signer, Wallet},
 };
 use lazy_static::lazy_static;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use std::collections::HashMap;
 use std::env::{self, VarError};
-use std::fs::File;
+use std::fmt::Debug;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
 use std::sync::Once;
 use std::time::Duration;
 use url::Url;
@@ -36,6 +41,8 @@ pub const CONTRACTS: &[(&str, &str)] = &[
 ];
 /// Path to gen_blockchain_data output file
 pub const GENDATA_OUTPUT_PATH: &str = "gendata_output.json";
+/// Path to the directory of the JSON-RPC responses recorded from geth0
+pub const RPC_RECORDS_PATH: &str = "rpc_records";
 
 const GETH0_URL_DEFAULT: &str = "http://localhost:8545";
 
@@ -47,6 +54,29 @@ lazy_static! {
         Err(VarError::NotPresent) => GETH0_URL_DEFAULT.to_string(),
         Err(e) => panic!("Error in GETH0_URL env var: {:?}", e),
     };
+    /// How the tests query geth0, set with the `RPC_MODE` env var to `live`
+    /// (default), `record` or `replay`.
+    pub static ref RPC_MODE: RpcMode = match env::var("RPC_MODE") {
+        Ok(val) => match val.as_str() {
+            "live" => RpcMode::Live,
+            "record" => RpcMode::Record,
+            "replay" => RpcMode::Replay,
+            _ => panic!("Invalid RPC_MODE env var: {}", val),
+        },
+        Err(VarError::NotPresent) => RpcMode::Live,
+        Err(e) => panic!("Error in RPC_MODE env var: {:?}", e),
+    };
+}
+
+/// How the tests query geth0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RpcMode {
+    /// Query geth0.
+    Live,
+    /// Query geth0 and record its responses in [`RPC_RECORDS_PATH`].
+    Record,
+    /// Serve the responses recorded in [`RPC_RECORDS_PATH`], without geth0.
+    Replay,
 }
 
 static LOG_INIT: Once = Once::new();
@@ -64,6 +94,99 @@ pub fn get_client() -> GethClient<Http> {
     GethClient::new(transport)
 }
 
+/// Get the transport to geth0 which supports batch requests
+fn get_batch_transport() -> BatchHttp {
+    BatchHttp::new(Url::parse(&GETH0_URL).expect("invalid url"))
+}
+
+/// JSON-RPC provider of the tests, selected by [`RPC_MODE`].  When recording,
+/// the responses are saved by [`TestProvider::save_records`] at the end of
+/// each test.
+#[derive(Debug)]
+pub enum TestProvider {
+    /// Provider querying geth0.
+    Live(BatchHttp),
+    /// Provider querying geth0 and recording its responses to a file.
+    Record(RecordingProvider<BatchHttp>, PathBuf),
+    /// Provider serving the responses recorded in a file.
+    Replay(ReplayProvider),
+}
+
+#[async_trait]
+impl JsonRpcClient for TestProvider {
+    type Error = ProviderError;
+
+    async fn request<T, R>(&self, method: &str, params: T) -> Result<R, Self::Error>
+    where
+        T: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned,
+    {
+        match self {
+            TestProvider::Live(provider) => {
+                provider.request(method, params).await.map_err(Into::into)
+            }
+            TestProvider::Record(provider, _) => {
+                provider.request(method, params).await.map_err(Into::into)
+            }
+            TestProvider::Replay(provider) => {
+                provider.request(method, params).await.map_err(Into::into)
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl BatchJsonRpcClient for TestProvider {
+    async fn request_batch<T, R>(&self, method: &str, params: Vec<T>) -> Result<Vec<R>, Self::Error>
+    where
+        T: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        match self {
+            TestProvider::Live(provider) => provider
+                .request_batch(method, params)
+                .await
+                .map_err(Into::into),
+            TestProvider::Record(provider, _) => provider
+                .request_batch(method, params)
+                .await
+                .map_err(Into::into),
+            TestProvider::Replay(provider) => provider
+                .request_batch(method, params)
+                .await
+                .map_err(Into::into),
+        }
+    }
+}
+
+impl TestProvider {
+    /// Save the responses recorded by the test, which must be called once it
+    /// has passed.  Does nothing when not recording.
+    pub fn save_records(&self) {
+        if let TestProvider::Record(provider, path) = self {
+            provider.save(path).expect("cannot save rpc records");
+        }
+    }
+}
+
+/// Get the integration test [`GethClient`] of the test `name`, which queries
+/// geth0, records its responses or replays them depending on [`RPC_MODE`].
+pub fn get_test_client(name: &str) -> GethClient<TestProvider> {
+    let path = Path::new(RPC_RECORDS_PATH).join(format!("{}.json", name));
+    let provider = match *RPC_MODE {
+        RpcMode::Live => TestProvider::Live(get_batch_transport()),
+        RpcMode::Record => {
+            fs::create_dir_all(RPC_RECORDS_PATH).expect("cannot create rpc records dir");
+            TestProvider::Record(RecordingProvider::new(get_batch_transport()), path)
+        }
+        RpcMode::Replay => TestProvider::Replay(
+            ReplayProvider::load(path)
+                .expect("cannot load rpc records, which are generated by gen_rpc_records.sh"),
+        ),
+    };
+    GethClient::new(provider)
+}
+
 /// Get the integration test [`Provider`]
 pub fn get_provider() -> Provider<Http> {
     let transport = Http::new(Url::parse(&GETH0_URL).expect("invalid url"));
@@ -107,10 +230,22 @@ pub struct GenDataOutput {
 }
 
 impl GenDataOutput {
-    /// Load [`GenDataOutput`] from the json file.
+    /// Load [`GenDataOutput`] from the json file.  When replaying the
+    /// JSON-RPC responses, the file is read from [`RPC_RECORDS_PATH`], where
+    /// it's copied when recording them.
     pub fn load() -> Self {
-        serde_json::from_reader(File::open(GENDATA_OUTPUT_PATH).expect("cannot read file"))
-            .expect("cannot deserialize json from file")
+        let records_path = Path::new(RPC_RECORDS_PATH).join(GENDATA_OUTPUT_PATH);
+        let path = match *RPC_MODE {
+            RpcMode::Replay => records_path.as_path(),
+            _ => Path::new(GENDATA_OUTPUT_PATH),
+        };
+        let gen_data = serde_json::from_reader(File::open(path).expect("cannot read file"))
+            .expect("cannot deserialize json from file");
+        if *RPC_MODE == RpcMode::Record {
+            fs::create_dir_all(RPC_RECORDS_PATH).expect("cannot create rpc records dir");
+            fs::copy(GENDATA_OUTPUT_PATH, &records_path).expect("cannot copy file");
+        }
+        gen_data
     }
 
     /// Store [`GenDataOutput`] into the json file.
@@ -1,7 +1,7 @@
 #![cfg(feature = "circuit_input_builder")]
 
 use bus_mapping::circuit_input_builder::BuilderClient;
-use integration_tests::{get_client, log_init, GenDataOutput};
+use integration_tests::{get_test_client, log_init, GenDataOutput};
 use lazy_static::lazy_static;
 use log::trace;
 
@@ -10,7 +10,7 @@ lazy_static! {
 }
 
 async fn test_circuit_input_builder_block(block_num: u64) {
-    let cli = get_client();
+    let cli = get_test_client(&format!("circuit_input_builder_block_{}", block_num));
     let cli = BuilderClient::new(cli).await.unwrap();
 
     // 1. Query geth for Block, Txs and TxExecTraces
@@ -21,7 +21,7 @@ async fn test_circuit_input_builder_block(block_num: u64) {
     trace!("AccessSet: {:#?}", access_set);
 
     // 3. Query geth for all accounts, storage keys, and codes from Accesses
-    let (proofs, codes) = cli.get_state(block_num, access_set).await.unwrap();
+    let (proofs, _, codes) = cli.get_state(block_num, access_set).await.unwrap();
 
     // 4. Build a partial StateDB from step 3
     let (state_db, code_db) = cli.build_state_code_db(proofs, codes);
@@ -34,6 +34,7 @@ async fn test_circuit_input_builder_block(block_num: u64) {
         .unwrap();
 
     trace!("CircuitInputBuilder: {:#?}", builder);
+    cli.geth_client().0.save_records();
 }
 
 macro_rules! declare_tests {
@@ -11,7 +11,7 @@ use halo2_proofs::{
         group::{Curve, Group},
     },
 };
-use integration_tests::{get_client, log_init, GenDataOutput, CHAIN_ID};
+use integration_tests::{get_test_client, log_init, GenDataOutput, CHAIN_ID};
 use lazy_static::lazy_static;
 use log::trace;
 use paste::paste;
@@ -24,6 +24,7 @@ use zkevm_circuits::evm_circuit::witness::RwMap;
 use zkevm_circuits::evm_circuit::{test::run_test_circuit, witness::block_convert};
 use zkevm_circuits::state_circuit::StateCircuit;
 use zkevm_circuits::tx_circuit::{sign_verify::SignVerifyChip, Secp256k1Affine, TxCircuit};
+use zkevm_circuits::witness::MptUpdates;
 
 lazy_static! {
     pub static ref GEN_DATA: GenDataOutput = GenDataOutput::load();
@@ -31,19 +32,26 @@ lazy_static! {
 
 async fn test_evm_circuit_block(block_num: u64) {
     log::info!("test evm circuit, block number: {}", block_num);
-    let cli = get_client();
+    let cli = get_test_client(&format!("evm_circuit_block_{}", block_num));
     let cli = BuilderClient::new(cli).await.unwrap();
     let (builder, _) = cli.gen_inputs(block_num).await.unwrap();
 
-    let block = block_convert(&builder.block, &builder.code_db);
+    let block = block_convert(&builder.block, &builder.code_db).unwrap();
     run_test_circuit(block).expect("evm_circuit verification failed");
+    cli.geth_client().0.save_records();
 }
 
 async fn test_state_circuit_block(block_num: u64) {
     log::info!("test state circuit, block number: {}", block_num);
-    let cli = get_client();
+    let cli = get_test_client(&format!("state_circuit_block_{}", block_num));
     let cli = BuilderClient::new(cli).await.unwrap();
-    let (builder, _) = cli.gen_inputs(block_num).await.unwrap();
+    let (eth_block, geth_traces) = cli.get_block(block_num).await.unwrap();
+    let access_set = cli.get_state_accesses(&eth_block, &geth_traces).unwrap();
+    let (proofs, nodes, codes) = cli.get_state(block_num, access_set).await.unwrap();
+    let (state_db, code_db) = cli.build_state_code_db(proofs.clone(), codes);
+    let builder = cli
+        .gen_inputs_from_state(state_db, code_db, &eth_block, &geth_traces)
+        .unwrap();
 
     // Generate state proof
     let stack_ops = builder.block.container.sorted_stack();
@@ -62,19 +70,24 @@ async fn test_state_circuit_block(block_num: u64) {
         ..Default::default()
     });
 
+    // The state roots of the MPT updates are computed from the proofs of the
+    // state before the block.
+    let updates = MptUpdates::from_proofs(&rw_map.table_assignments(), &proofs, &nodes).unwrap();
+
     let randomness = Fr::from(0xcafeu64);
-    let circuit = StateCircuit::<Fr>::new(randomness, rw_map, 1 << 16);
+    let circuit = StateCircuit::<Fr>::new_with_mpt_updates(randomness, rw_map, updates, 1 << 16);
     let power_of_randomness = circuit.instance();
 
     let prover = MockProver::<Fr>::run(DEGREE as u32, &circuit, power_of_randomness).unwrap();
     prover.verify().expect("state_circuit verification failed");
+    cli.geth_client().0.save_records();
 }
 
 async fn test_tx_circuit_block(block_num: u64) {
     const DEGREE: u32 = 20;
 
     log::info!("test tx circuit, block number: {}", block_num);
-    let cli = get_client();
+    let cli = get_test_client(&format!("tx_circuit_block_{}", block_num));
     let cli = BuilderClient::new(cli).await.unwrap();
 
     let (_, eth_block) = cli.gen_inputs(block_num).await.unwrap();
@@ -100,30 +113,33 @@ async fn test_tx_circuit_block(block_num: u64) {
     let prover = MockProver::run(DEGREE, &circuit, vec![vec![]]).unwrap();
 
     prover.verify().expect("tx_circuit verification failed");
+    cli.geth_client().0.save_records();
 }
 
 pub async fn test_bytecode_circuit_block(block_num: u64) {
     const DEGREE: u32 = 16;
 
     log::info!("test bytecode circuit, block number: {}", block_num);
-    let cli = get_client();
+    let cli = get_test_client(&format!("bytecode_circuit_block_{}", block_num));
     let cli = BuilderClient::new(cli).await.unwrap();
     let (builder, _) = cli.gen_inputs(block_num).await.unwrap();
     let bytecodes: Vec<Vec<u8>> = builder.code_db.0.values().cloned().collect();
 
     test_bytecode_circuit::<Fr>(DEGREE, bytecodes);
+    cli.geth_client().0.save_records();
 }
 
 pub async fn test_copy_circuit_block(block_num: u64) {
     const DEGREE: u32 = 16;
 
     log::info!("test copy circuit, block number: {}", block_num);
-    let cli = get_client();
+    let cli = get_test_client(&format!("copy_circuit_block_{}", block_num));
     let cli = BuilderClient::new(cli).await.unwrap();
     let (builder, _) = cli.gen_inputs(block_num).await.unwrap();
-    let block = block_convert(&builder.block, &builder.code_db);
+    let block = block_convert(&builder.block, &builder.code_db).unwrap();
 
     assert!(test_copy_circuit(DEGREE, block).is_ok());
+    cli.geth_client().0.save_records();
 }
 
 macro_rules! declare_tests {
@@ -260,6 +260,7 @@ fn gen_geth_traces<const NACC: usize, const NTX: usize>(
 pub mod helpers {
     use super::*;
     use crate::MOCK_ACCOUNTS;
+    use eth_types::{address, bytecode, evm_types::OpcodeId, ToWord};
 
     /// Generate a simple setup which adds balance to two default accounts from
     /// [`static@MOCK_ACCOUNTS`]:
@@ -281,4 +282,82 @@ pub mod helpers {
     pub fn tx_from_1_to_0(mut txs: Vec<&mut MockTransaction>, accs: [MockAccount; 2]) {
         txs[0].from(accs[1].address).to(accs[0].address);
     }
+
+    /// Generate a test context in which the code of `contract` runs with `gas`,
+    /// either in the root call of the transaction when `is_root`, or else in an
+    /// internal `CALL` from the caller account
+    /// 0x00000000000000000000000000000000000cafe1.
+    pub fn root_or_internal_call_ctx(
+        contract: Account,
+        gas: u64,
+        is_root: bool,
+    ) -> Result<TestContext<3, 1>, Error> {
+        contract_call_ctx(contract, vec![], OpcodeId::CALL, gas, is_root)
+    }
+
+    /// Generate a test context in which the code of `contract` runs with `gas`,
+    /// either in the root call of the transaction when `is_root`, or else in an
+    /// internal `call_op` from the caller account
+    /// 0x00000000000000000000000000000000000cafe1.  The accounts are, in order,
+    /// the caller, `contract`, the sender of the transaction
+    /// 0x000000000000000000000000000000000000cafe and the `extra_accounts`.
+    pub fn contract_call_ctx<const NACC: usize>(
+        contract: Account,
+        extra_accounts: Vec<Account>,
+        call_op: OpcodeId,
+        gas: u64,
+        is_root: bool,
+    ) -> Result<TestContext<NACC, 1>, Error> {
+        assert_eq!(
+            NACC,
+            3 + extra_accounts.len(),
+            "unexpected number of accounts"
+        );
+        let mut caller_code = bytecode! {
+            PUSH1(0) // retLength
+            PUSH1(0) // retOffset
+            PUSH1(0) // argsLength
+            PUSH1(0) // argsOffset
+        };
+        if matches!(call_op, OpcodeId::CALL | OpcodeId::CALLCODE) {
+            caller_code.push(1, Word::zero()); // value
+        }
+        caller_code.push(20, contract.address.to_word());
+        caller_code.push(32, Word::from(gas));
+        caller_code.write_op(call_op);
+        caller_code.write_op(OpcodeId::STOP);
+        let caller = Account {
+            address: address!("0x00000000000000000000000000000000000cafe1"),
+            balance: eth(10),
+            code: caller_code.into(),
+            ..Default::default()
+        };
+
+        TestContext::new(
+            None,
+            |accs| {
+                let mut accs = accs.into_iter();
+                accs.next().unwrap().account(&caller);
+                accs.next().unwrap().account(&contract);
+                accs.next()
+                    .unwrap()
+                    .address(address!("0x000000000000000000000000000000000000cafe"))
+                    .balance(eth(10));
+                for (acc, extra_account) in accs.zip(extra_accounts.iter()) {
+                    acc.account(extra_account);
+                }
+            },
+            |mut txs, accs| {
+                if is_root {
+                    txs[0]
+                        .from(accs[2].address)
+                        .to(accs[1].address)
+                        .gas(Word::from(21_000 + gas));
+                } else {
+                    txs[0].from(accs[2].address).to(accs[0].address);
+                }
+            },
+            |block, _| block,
+        )
+    }
 }
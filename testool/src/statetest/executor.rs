@@ -338,7 +338,8 @@ impl StateTest {
     ) {
         // build a witness block from trace result
         let block =
-            zkevm_circuits::evm_circuit::witness::block_convert(&builder.block, &builder.code_db);
+            zkevm_circuits::evm_circuit::witness::block_convert(&builder.block, &builder.code_db)
+                .expect("could not convert block");
 
         // finish requiered tests according to config using this witness block
         zkevm_circuits::test_util::test_circuits_using_witness_block(block, bytecode_test_config)
@@ -58,6 +58,9 @@ pub struct CopyCircuit<F> {
     /// In case of a bytecode tag, this denotes whether or not the copied byte
     /// is an opcode or push data byte.
     pub is_code: Column<Advice>,
+    /// In case of a RlcAcc source, the RLC of the bytes read so far, which
+    /// is equal to `rlc_acc` at the last read.
+    pub read_value_acc: Column<Advice>,
     /// Whether the row is enabled or not.
     pub q_enable: Column<Fixed>,
     /// The Copy Table contains the columns that are exposed via the lookup
@@ -86,6 +89,7 @@ impl<F: Field> CopyCircuit<F> {
         let value = meta.advice_column();
         let is_code = meta.advice_column();
         let is_pad = meta.advice_column();
+        let read_value_acc = meta.advice_column();
         let is_first = copy_table.is_first;
         let id = copy_table.id;
         let addr = copy_table.addr;
@@ -265,6 +269,41 @@ impl<F: Field> CopyCircuit<F> {
             cb.gate(meta.query_selector(q_step))
         });
 
+        meta.create_gate("verify read from rlc acc (q_step == 1)", |meta| {
+            let mut cb = BaseConstraintBuilder::default();
+
+            cb.condition(meta.query_advice(is_first, Rotation::cur()), |cb| {
+                cb.require_equal(
+                    "read_value_acc == value for the first read",
+                    meta.query_advice(read_value_acc, Rotation::cur()),
+                    meta.query_advice(value, Rotation::cur()),
+                );
+            });
+            cb.condition(
+                not::expr(meta.query_advice(is_last, Rotation::next())),
+                |cb| {
+                    cb.require_equal(
+                        "rows[2].read_value_acc == rows[0].read_value_acc * r + rows[2].value",
+                        meta.query_advice(read_value_acc, Rotation(2)),
+                        meta.query_advice(read_value_acc, Rotation::cur()) * randomness.clone()
+                            + meta.query_advice(value, Rotation(2)),
+                    );
+                },
+            );
+            cb.condition(meta.query_advice(is_last, Rotation::next()), |cb| {
+                cb.require_equal(
+                    "read_value_acc == rlc_acc for the last read",
+                    meta.query_advice(read_value_acc, Rotation::cur()),
+                    meta.query_advice(rlc_acc, Rotation::cur()),
+                );
+            });
+
+            cb.gate(and::expr([
+                meta.query_selector(q_step),
+                tag.value_equals(CopyDataType::RlcAcc, Rotation::cur())(meta),
+            ]))
+        });
+
         meta.create_gate("verify_step (q_step == 0)", |meta| {
             let mut cb = BaseConstraintBuilder::default();
 
@@ -368,6 +407,7 @@ impl<F: Field> CopyCircuit<F> {
             value,
             is_pad,
             is_code,
+            read_value_acc,
             q_enable,
             addr_lt_addr_end,
             copy_table,
@@ -423,11 +463,16 @@ impl<F: Field> CopyCircuit<F> {
                             || Value::known(F::one()),
                         )?;
 
-                        // is_last, value, is_pad, is_code
-                        for (column, &(value, label)) in
-                            [self.is_last, self.value, self.is_pad, self.is_code]
-                                .iter()
-                                .zip_eq(circuit_row)
+                        // is_last, value, is_pad, is_code, read_value_acc
+                        for (column, &(value, label)) in [
+                            self.is_last,
+                            self.value,
+                            self.is_pad,
+                            self.is_code,
+                            self.read_value_acc,
+                        ]
+                        .iter()
+                        .zip_eq(circuit_row)
                         {
                             region.assign_advice(
                                 || format!("{} at row: {}", label, offset),
@@ -548,6 +593,13 @@ impl<F: Field> CopyCircuit<F> {
             offset,
             || Value::known(F::zero()),
         )?;
+        // read_value_acc
+        region.assign_advice(
+            || format!("assign read_value_acc {}", offset),
+            self.read_value_acc,
+            offset,
+            || Value::known(F::zero()),
+        )?;
         // rw_counter
         region.assign_advice(
             || format!("assign rw_counter {}", offset),
@@ -778,28 +830,28 @@ mod tests {
     #[test]
     fn copy_circuit_valid_calldatacopy() {
         let builder = gen_calldatacopy_data();
-        let block = block_convert(&builder.block, &builder.code_db);
+        let block = block_convert(&builder.block, &builder.code_db).unwrap();
         assert_eq!(test_copy_circuit(14, block), Ok(()));
     }
 
     #[test]
     fn copy_circuit_valid_codecopy() {
         let builder = gen_codecopy_data();
-        let block = block_convert(&builder.block, &builder.code_db);
+        let block = block_convert(&builder.block, &builder.code_db).unwrap();
         assert_eq!(test_copy_circuit(10, block), Ok(()));
     }
 
     #[test]
     fn copy_circuit_valid_sha3() {
         let builder = gen_sha3_data();
-        let block = block_convert(&builder.block, &builder.code_db);
+        let block = block_convert(&builder.block, &builder.code_db).unwrap();
         assert_eq!(test_copy_circuit(20, block), Ok(()));
     }
 
     #[test]
     fn copy_circuit_tx_log() {
         let builder = gen_tx_log_data();
-        let block = block_convert(&builder.block, &builder.code_db);
+        let block = block_convert(&builder.block, &builder.code_db).unwrap();
         assert_eq!(test_copy_circuit(10, block), Ok(()));
     }
 
@@ -41,6 +41,7 @@ impl<F: Field> EvmCircuit<F> {
         block_table: &dyn LookupTable<F>,
         copy_table: &dyn LookupTable<F>,
         keccak_table: &dyn LookupTable<F>,
+        exp_table: &dyn LookupTable<F>,
     ) -> Self {
         let fixed_table = [(); 4].map(|_| meta.fixed_column());
         let byte_table = [(); 1].map(|_| meta.fixed_column());
@@ -55,6 +56,7 @@ impl<F: Field> EvmCircuit<F> {
             block_table,
             copy_table,
             keccak_table,
+            exp_table,
         ));
 
         Self {
@@ -153,7 +155,7 @@ impl<F: Field> EvmCircuit<F> {
 pub mod test {
     use crate::{
         evm_circuit::{table::FixedTableTag, witness::Block, EvmCircuit},
-        table::{BlockTable, BytecodeTable, CopyTable, KeccakTable, RwTable, TxTable},
+        table::{BlockTable, BytecodeTable, CopyTable, ExpTable, KeccakTable, RwTable, TxTable},
         util::{power_of_randomness_from_instance, Challenges},
     };
     use bus_mapping::evm::OpcodeId;
@@ -222,6 +224,7 @@ pub mod test {
         block_table: BlockTable,
         copy_table: CopyTable,
         keccak_table: KeccakTable,
+        exp_table: ExpTable,
         pub evm_circuit: EvmCircuit<F>,
     }
 
@@ -256,6 +259,7 @@ pub mod test {
             let q_copy_table = meta.fixed_column();
             let copy_table = CopyTable::construct(meta, q_copy_table);
             let keccak_table = KeccakTable::construct(meta);
+            let exp_table = ExpTable::construct(meta);
 
             let power_of_randomness = power_of_randomness_from_instance(meta);
             let evm_circuit = EvmCircuit::configure(
@@ -267,6 +271,7 @@ pub mod test {
                 &block_table,
                 &copy_table,
                 &keccak_table,
+                &exp_table,
             );
 
             Self::Config {
@@ -276,6 +281,7 @@ pub mod test {
                 block_table,
                 copy_table,
                 keccak_table,
+                exp_table,
                 evm_circuit,
             }
         }
@@ -317,6 +323,8 @@ pub mod test {
                 .keccak_table
                 .dev_load(&mut layouter, &self.block.sha3_inputs, &challenges)?;
 
+            config.exp_table.dev_load(&mut layouter, &self.block)?;
+
             config
                 .evm_circuit
                 .assign_block_exact(&mut layouter, &self.block)
@@ -356,6 +364,13 @@ pub mod test {
                 .map(|bytecode| bytecode.bytes.len())
                 .sum::<usize>(),
         ));
+        let k = k.max(log2_ceil(
+            64 + block
+                .exp_events
+                .iter()
+                .map(|exp_event| exp_event.steps.len())
+                .sum::<usize>(),
+        ));
         let k = k.max(log2_ceil(64 + num_rows_required_for_steps));
         log::debug!("evm circuit uses k = {}", k);
 
@@ -26,28 +26,52 @@ use strum::IntoEnumIterator;
 mod add_sub;
 mod addmod;
 mod address;
+mod balance;
 mod begin_tx;
 mod bitwise;
 mod block_ctx;
 mod blockhash;
 mod byte;
-mod call;
 mod calldatacopy;
 mod calldataload;
 mod calldatasize;
 mod caller;
+mod callop;
 mod callvalue;
 mod chainid;
 mod codecopy;
 mod codesize;
 mod comparator;
+mod create;
 mod dummy;
 mod dup;
 mod end_block;
+mod end_inner_block;
 mod end_tx;
+mod error_invalid_creation_code;
+mod error_invalid_jump;
+mod error_invalid_opcode;
+mod error_max_code_size_exceeded;
+mod error_oog_call;
+mod error_oog_code_store;
 mod error_oog_constant;
+mod error_oog_dynamic_memory;
+mod error_oog_ext_codecopy;
+mod error_oog_log;
+mod error_oog_memory_copy;
+mod error_oog_self_destruct;
+mod error_oog_sha3;
+mod error_oog_sload;
+mod error_oog_sstore;
 mod error_oog_static_memory;
+mod error_precheck_failed;
+mod error_return_data_oob;
+mod error_stack;
+mod error_write_protection;
+mod exp;
+mod extcodecopy;
 mod extcodehash;
+mod extcodesize;
 mod gas;
 mod gasprice;
 mod is_zero;
@@ -63,10 +87,15 @@ mod not;
 mod origin;
 mod pc;
 mod pop;
+mod precompiles;
 mod push;
 mod r#return;
+mod returndatacopy;
+mod returndatasize;
+mod sar;
 mod sdiv_smod;
 mod selfbalance;
+mod selfdestruct;
 mod sha3;
 mod shl_shr;
 mod signed_comparator;
@@ -80,27 +109,51 @@ use self::sha3::Sha3Gadget;
 use add_sub::AddSubGadget;
 use addmod::AddModGadget;
 use address::AddressGadget;
+use balance::BalanceGadget;
 use begin_tx::BeginTxGadget;
 use bitwise::BitwiseGadget;
 use block_ctx::{BlockCtxU160Gadget, BlockCtxU256Gadget, BlockCtxU64Gadget};
 use blockhash::BlockHashGadget;
 use byte::ByteGadget;
-use call::CallGadget;
 use calldatacopy::CallDataCopyGadget;
 use calldataload::CallDataLoadGadget;
 use calldatasize::CallDataSizeGadget;
 use caller::CallerGadget;
+use callop::CallOpGadget;
 use callvalue::CallValueGadget;
 use chainid::ChainIdGadget;
 use codecopy::CodeCopyGadget;
 use codesize::CodesizeGadget;
 use comparator::ComparatorGadget;
+use create::CreateGadget;
 use dummy::DummyGadget;
 use dup::DupGadget;
 use end_block::EndBlockGadget;
+use end_inner_block::EndInnerBlockGadget;
 use end_tx::EndTxGadget;
+use error_invalid_creation_code::ErrorInvalidCreationCodeGadget;
+use error_invalid_jump::ErrorInvalidJumpGadget;
+use error_invalid_opcode::ErrorInvalidOpcodeGadget;
+use error_max_code_size_exceeded::ErrorMaxCodeSizeExceededGadget;
+use error_oog_call::ErrorOOGCallGadget;
+use error_oog_code_store::ErrorOOGCodeStoreGadget;
 use error_oog_constant::ErrorOOGConstantGadget;
+use error_oog_dynamic_memory::ErrorOOGDynamicMemoryGadget;
+use error_oog_ext_codecopy::ErrorOOGExtCodeCopyGadget;
+use error_oog_log::ErrorOOGLogGadget;
+use error_oog_memory_copy::ErrorOOGMemoryCopyGadget;
+use error_oog_self_destruct::ErrorOOGSelfDestructGadget;
+use error_oog_sha3::ErrorOOGSha3Gadget;
+use error_oog_sload::ErrorOOGSloadGadget;
+use error_oog_sstore::ErrorOOGSstoreGadget;
+use error_precheck_failed::ErrorPrecheckFailedGadget;
+use error_return_data_oob::ErrorReturnDataOutOfBoundGadget;
+use error_stack::ErrorStackGadget;
+use error_write_protection::ErrorWriteProtectionGadget;
+use exp::ExpGadget;
+use extcodecopy::ExtcodecopyGadget;
 use extcodehash::ExtcodehashGadget;
+use extcodesize::ExtcodesizeGadget;
 use gas::GasGadget;
 use gasprice::GasPriceGadget;
 use is_zero::IsZeroGadget;
@@ -116,10 +169,15 @@ use not::NotGadget;
 use origin::OriginGadget;
 use pc::PcGadget;
 use pop::PopGadget;
+use precompiles::{DummyPrecompile, IdentityCall, PrecompileGadget};
 use push::PushGadget;
 use r#return::ReturnGadget;
+use returndatacopy::ReturnDataCopyGadget;
+use returndatasize::ReturnDataSizeGadget;
+use sar::SarGadget;
 use sdiv_smod::SignedDivModGadget;
 use selfbalance::SelfbalanceGadget;
+use selfdestruct::SelfdestructGadget;
 use shl_shr::ShlShrGadget;
 use signed_comparator::SignedComparatorGadget;
 use signextend::SignextendGadget;
@@ -168,6 +226,7 @@ pub(crate) struct ExecutionConfig<F> {
     // internal state gadgets
     begin_tx_gadget: BeginTxGadget<F>,
     end_block_gadget: EndBlockGadget<F>,
+    end_inner_block_gadget: EndInnerBlockGadget<F>,
     end_tx_gadget: EndTxGadget<F>,
     // opcode gadgets
     add_sub_gadget: AddSubGadget<F>,
@@ -175,7 +234,7 @@ pub(crate) struct ExecutionConfig<F> {
     address_gadget: AddressGadget<F>,
     bitwise_gadget: BitwiseGadget<F>,
     byte_gadget: ByteGadget<F>,
-    call_gadget: CallGadget<F>,
+    call_op_gadget: CallOpGadget<F>,
     call_value_gadget: CallValueGadget<F>,
     calldatacopy_gadget: CallDataCopyGadget<F>,
     calldataload_gadget: CallDataLoadGadget<F>,
@@ -186,6 +245,7 @@ pub(crate) struct ExecutionConfig<F> {
     codesize_gadget: CodesizeGadget<F>,
     comparator_gadget: ComparatorGadget<F>,
     dup_gadget: DupGadget<F>,
+    exp_gadget: ExpGadget<F>,
     extcodehash_gadget: ExtcodehashGadget<F>,
     gas_gadget: GasGadget<F>,
     gasprice_gadget: GasPriceGadget<F>,
@@ -204,23 +264,19 @@ pub(crate) struct ExecutionConfig<F> {
     pop_gadget: PopGadget<F>,
     push_gadget: PushGadget<F>,
     return_gadget: ReturnGadget<F>,
+    sar_gadget: SarGadget<F>,
     sdiv_smod_gadget: SignedDivModGadget<F>,
     selfbalance_gadget: SelfbalanceGadget<F>,
     sha3_gadget: Sha3Gadget<F>,
     shl_shr_gadget: ShlShrGadget<F>,
-    balance_gadget: DummyGadget<F, 1, 1, { ExecutionState::BALANCE }>,
-    exp_gadget: DummyGadget<F, 2, 1, { ExecutionState::EXP }>,
-    sar_gadget: DummyGadget<F, 2, 1, { ExecutionState::SAR }>,
-    extcodesize_gadget: DummyGadget<F, 1, 1, { ExecutionState::EXTCODESIZE }>,
-    extcodecopy_gadget: DummyGadget<F, 4, 0, { ExecutionState::EXTCODECOPY }>,
-    returndatasize_gadget: DummyGadget<F, 0, 1, { ExecutionState::RETURNDATASIZE }>,
-    returndatacopy_gadget: DummyGadget<F, 3, 0, { ExecutionState::RETURNDATACOPY }>,
-    create_gadget: DummyGadget<F, 3, 1, { ExecutionState::CREATE }>,
-    callcode_gadget: DummyGadget<F, 7, 1, { ExecutionState::CALLCODE }>,
-    delegatecall_gadget: DummyGadget<F, 6, 1, { ExecutionState::DELEGATECALL }>,
-    create2_gadget: DummyGadget<F, 4, 1, { ExecutionState::CREATE2 }>,
-    staticcall_gadget: DummyGadget<F, 6, 1, { ExecutionState::STATICCALL }>,
-    selfdestruct_gadget: DummyGadget<F, 1, 0, { ExecutionState::SELFDESTRUCT }>,
+    balance_gadget: BalanceGadget<F>,
+    extcodesize_gadget: ExtcodesizeGadget<F>,
+    extcodecopy_gadget: ExtcodecopyGadget<F>,
+    returndatasize_gadget: ReturnDataSizeGadget<F>,
+    returndatacopy_gadget: ReturnDataCopyGadget<F>,
+    create_gadget: CreateGadget<F, false, { ExecutionState::CREATE }>,
+    create2_gadget: CreateGadget<F, true, { ExecutionState::CREATE2 }>,
+    selfdestruct_gadget: SelfdestructGadget<F>,
     signed_comparator_gadget: SignedComparatorGadget<F>,
     signextend_gadget: SignextendGadget<F>,
     sload_gadget: SloadGadget<F>,
@@ -235,35 +291,53 @@ pub(crate) struct ExecutionConfig<F> {
     error_oog_constant: ErrorOOGConstantGadget<F>,
     error_oog_static_memory_gadget:
         DummyGadget<F, 0, 0, { ExecutionState::ErrorOutOfGasStaticMemoryExpansion }>,
-    error_stack_overflow: DummyGadget<F, 0, 0, { ExecutionState::ErrorStackOverflow }>,
-    error_stack_underflow: DummyGadget<F, 0, 0, { ExecutionState::ErrorStackUnderflow }>,
-    error_oog_dynamic_memory_gadget:
-        DummyGadget<F, 0, 0, { ExecutionState::ErrorOutOfGasDynamicMemoryExpansion }>,
-    error_oog_log: DummyGadget<F, 0, 0, { ExecutionState::ErrorOutOfGasLOG }>,
-    error_oog_sload: DummyGadget<F, 0, 0, { ExecutionState::ErrorOutOfGasSLOAD }>,
-    error_oog_sstore: DummyGadget<F, 0, 0, { ExecutionState::ErrorOutOfGasSSTORE }>,
-    error_oog_call: DummyGadget<F, 0, 0, { ExecutionState::ErrorOutOfGasCALL }>,
-    error_oog_memory_copy: DummyGadget<F, 0, 0, { ExecutionState::ErrorOutOfGasMemoryCopy }>,
+    error_stack_overflow: ErrorStackGadget<F, { ExecutionState::ErrorStackOverflow }>,
+    error_stack_underflow: ErrorStackGadget<F, { ExecutionState::ErrorStackUnderflow }>,
+    error_oog_dynamic_memory_gadget: ErrorOOGDynamicMemoryGadget<F>,
+    error_oog_log: ErrorOOGLogGadget<F>,
+    error_oog_sload: ErrorOOGSloadGadget<F>,
+    error_oog_sstore: ErrorOOGSstoreGadget<F>,
+    error_oog_call: ErrorOOGCallGadget<F, { ExecutionState::ErrorOutOfGasCALL }>,
+    error_oog_memory_copy: ErrorOOGMemoryCopyGadget<F>,
     error_oog_account_access: DummyGadget<F, 0, 0, { ExecutionState::ErrorOutOfGasAccountAccess }>,
-    error_oog_sha3: DummyGadget<F, 0, 0, { ExecutionState::ErrorOutOfGasSHA3 }>,
-    error_oog_ext_codecopy: DummyGadget<F, 0, 0, { ExecutionState::ErrorOutOfGasEXTCODECOPY }>,
-    error_oog_call_code: DummyGadget<F, 0, 0, { ExecutionState::ErrorOutOfGasCALLCODE }>,
-    error_oog_delegate_call: DummyGadget<F, 0, 0, { ExecutionState::ErrorOutOfGasDELEGATECALL }>,
+    error_oog_sha3: ErrorOOGSha3Gadget<F>,
+    error_oog_ext_codecopy: ErrorOOGExtCodeCopyGadget<F>,
+    error_oog_call_code: ErrorOOGCallGadget<F, { ExecutionState::ErrorOutOfGasCALLCODE }>,
+    error_oog_delegate_call: ErrorOOGCallGadget<F, { ExecutionState::ErrorOutOfGasDELEGATECALL }>,
     error_oog_exp: DummyGadget<F, 0, 0, { ExecutionState::ErrorOutOfGasEXP }>,
     error_oog_create2: DummyGadget<F, 0, 0, { ExecutionState::ErrorOutOfGasCREATE2 }>,
-    error_oog_static_call: DummyGadget<F, 0, 0, { ExecutionState::ErrorOutOfGasSTATICCALL }>,
-    error_oog_self_destruct: DummyGadget<F, 0, 0, { ExecutionState::ErrorOutOfGasSELFDESTRUCT }>,
-    error_oog_code_store: DummyGadget<F, 0, 0, { ExecutionState::ErrorOutOfGasCodeStore }>,
-    error_insufficient_balance: DummyGadget<F, 0, 0, { ExecutionState::ErrorInsufficientBalance }>,
-    error_invalid_jump: DummyGadget<F, 0, 0, { ExecutionState::ErrorInvalidJump }>,
-    error_depth: DummyGadget<F, 0, 0, { ExecutionState::ErrorDepth }>,
-    error_write_protection: DummyGadget<F, 0, 0, { ExecutionState::ErrorWriteProtection }>,
+    error_oog_static_call: ErrorOOGCallGadget<F, { ExecutionState::ErrorOutOfGasSTATICCALL }>,
+    error_oog_self_destruct: ErrorOOGSelfDestructGadget<F>,
+    error_oog_code_store: ErrorOOGCodeStoreGadget<F>,
+    error_insufficient_balance:
+        ErrorPrecheckFailedGadget<F, { ExecutionState::ErrorInsufficientBalance }>,
+    error_invalid_jump: ErrorInvalidJumpGadget<F>,
+    error_depth: ErrorPrecheckFailedGadget<F, { ExecutionState::ErrorDepth }>,
+    error_write_protection: ErrorWriteProtectionGadget<F>,
     error_contract_address_collision:
         DummyGadget<F, 0, 0, { ExecutionState::ErrorContractAddressCollision }>,
-    error_invalid_creation_code: DummyGadget<F, 0, 0, { ExecutionState::ErrorInvalidCreationCode }>,
-    error_return_data_out_of_bound:
-        DummyGadget<F, 0, 0, { ExecutionState::ErrorReturnDataOutOfBound }>,
-    invalid_opcode_gadget: DummyGadget<F, 0, 0, { ExecutionState::ErrorInvalidOpcode }>,
+    error_invalid_creation_code: ErrorInvalidCreationCodeGadget<F>,
+    error_max_code_size_exceeded: ErrorMaxCodeSizeExceededGadget<F>,
+    error_return_data_out_of_bound: ErrorReturnDataOutOfBoundGadget<F>,
+    invalid_opcode_gadget: ErrorInvalidOpcodeGadget<F>,
+    // precompile gadgets
+    precompile_ecrecover_gadget:
+        PrecompileGadget<F, DummyPrecompile<F, { ExecutionState::PrecompileEcRecover }>>,
+    precompile_sha256_gadget:
+        PrecompileGadget<F, DummyPrecompile<F, { ExecutionState::PrecompileSha256 }>>,
+    precompile_ripemd160_gadget:
+        PrecompileGadget<F, DummyPrecompile<F, { ExecutionState::PrecompileRipemd160 }>>,
+    precompile_identity_gadget: PrecompileGadget<F, IdentityCall<F>>,
+    precompile_bigmodexp_gadget:
+        PrecompileGadget<F, DummyPrecompile<F, { ExecutionState::PrecompileBigModExp }>>,
+    precompile_bn256_add_gadget:
+        PrecompileGadget<F, DummyPrecompile<F, { ExecutionState::PrecompileBn256Add }>>,
+    precompile_bn256_scalar_mul_gadget:
+        PrecompileGadget<F, DummyPrecompile<F, { ExecutionState::PrecompileBn256ScalarMul }>>,
+    precompile_bn256_pairing_gadget:
+        PrecompileGadget<F, DummyPrecompile<F, { ExecutionState::PrecompileBn256Pairing }>>,
+    precompile_blake2f_gadget:
+        PrecompileGadget<F, DummyPrecompile<F, { ExecutionState::PrecompileBlake2f }>>,
 }
 
 impl<F: Field> ExecutionConfig<F> {
@@ -279,6 +353,7 @@ impl<F: Field> ExecutionConfig<F> {
         block_table: &dyn LookupTable<F>,
         copy_table: &dyn LookupTable<F>,
         keccak_table: &dyn LookupTable<F>,
+        exp_table: &dyn LookupTable<F>,
     ) -> Self {
         let q_usable = meta.complex_selector();
         let q_step = meta.advice_column();
@@ -394,13 +469,14 @@ impl<F: Field> ExecutionConfig<F> {
             // internal states
             begin_tx_gadget: configure_gadget!(),
             end_block_gadget: configure_gadget!(),
+            end_inner_block_gadget: configure_gadget!(),
             end_tx_gadget: configure_gadget!(),
             // opcode gadgets
             add_sub_gadget: configure_gadget!(),
             addmod_gadget: configure_gadget!(),
             bitwise_gadget: configure_gadget!(),
             byte_gadget: configure_gadget!(),
-            call_gadget: configure_gadget!(),
+            call_op_gadget: configure_gadget!(),
             call_value_gadget: configure_gadget!(),
             calldatacopy_gadget: configure_gadget!(),
             calldataload_gadget: configure_gadget!(),
@@ -411,6 +487,7 @@ impl<F: Field> ExecutionConfig<F> {
             codesize_gadget: configure_gadget!(),
             comparator_gadget: configure_gadget!(),
             dup_gadget: configure_gadget!(),
+            exp_gadget: configure_gadget!(),
             extcodehash_gadget: configure_gadget!(),
             gas_gadget: configure_gadget!(),
             gasprice_gadget: configure_gadget!(),
@@ -435,17 +512,13 @@ impl<F: Field> ExecutionConfig<F> {
             address_gadget: configure_gadget!(),
             balance_gadget: configure_gadget!(),
             blockhash_gadget: configure_gadget!(),
-            exp_gadget: configure_gadget!(),
             sar_gadget: configure_gadget!(),
             extcodesize_gadget: configure_gadget!(),
             extcodecopy_gadget: configure_gadget!(),
             returndatasize_gadget: configure_gadget!(),
             returndatacopy_gadget: configure_gadget!(),
             create_gadget: configure_gadget!(),
-            callcode_gadget: configure_gadget!(),
-            delegatecall_gadget: configure_gadget!(),
             create2_gadget: configure_gadget!(),
-            staticcall_gadget: configure_gadget!(),
             selfdestruct_gadget: configure_gadget!(),
             shl_shr_gadget: configure_gadget!(),
             signed_comparator_gadget: configure_gadget!(),
@@ -484,8 +557,19 @@ impl<F: Field> ExecutionConfig<F> {
             error_depth: configure_gadget!(),
             error_contract_address_collision: configure_gadget!(),
             error_invalid_creation_code: configure_gadget!(),
+            error_max_code_size_exceeded: configure_gadget!(),
             error_return_data_out_of_bound: configure_gadget!(),
             invalid_opcode_gadget: configure_gadget!(),
+            // precompile gadgets
+            precompile_ecrecover_gadget: configure_gadget!(),
+            precompile_sha256_gadget: configure_gadget!(),
+            precompile_ripemd160_gadget: configure_gadget!(),
+            precompile_identity_gadget: configure_gadget!(),
+            precompile_bigmodexp_gadget: configure_gadget!(),
+            precompile_bn256_add_gadget: configure_gadget!(),
+            precompile_bn256_scalar_mul_gadget: configure_gadget!(),
+            precompile_bn256_pairing_gadget: configure_gadget!(),
+            precompile_blake2f_gadget: configure_gadget!(),
             // step and presets
             step: step_curr,
             height_map,
@@ -502,6 +586,7 @@ impl<F: Field> ExecutionConfig<F> {
             block_table,
             copy_table,
             keccak_table,
+            exp_table,
             &power_of_randomness,
             &cell_manager,
         );
@@ -611,9 +696,22 @@ impl<F: Field> ExecutionConfig<F> {
                 .chain(
                     IntoIterator::into_iter([
                         (
-                            "EndTx can only transit to BeginTx or EndBlock",
+                            "EndTx can only transit to BeginTx, EndInnerBlock or EndBlock",
                             ExecutionState::EndTx,
-                            vec![ExecutionState::BeginTx, ExecutionState::EndBlock],
+                            vec![
+                                ExecutionState::BeginTx,
+                                ExecutionState::EndInnerBlock,
+                                ExecutionState::EndBlock,
+                            ],
+                        ),
+                        (
+                            "EndInnerBlock can only transit to BeginTx, EndInnerBlock or EndBlock",
+                            ExecutionState::EndInnerBlock,
+                            vec![
+                                ExecutionState::BeginTx,
+                                ExecutionState::EndInnerBlock,
+                                ExecutionState::EndBlock,
+                            ],
                         ),
                         (
                             "EndBlock can only transit to EndBlock",
@@ -627,9 +725,9 @@ impl<F: Field> ExecutionConfig<F> {
                 .chain(
                     IntoIterator::into_iter([
                         (
-                            "Only EndTx can transit to BeginTx",
+                            "Only EndTx or EndInnerBlock can transit to BeginTx",
                             ExecutionState::BeginTx,
-                            vec![ExecutionState::EndTx],
+                            vec![ExecutionState::EndTx, ExecutionState::EndInnerBlock],
                         ),
                         (
                             "Only ExecutionState which halts or BeginTx can transit to EndTx",
@@ -640,9 +738,18 @@ impl<F: Field> ExecutionConfig<F> {
                                 .collect(),
                         ),
                         (
-                            "Only EndTx or EndBlock can transit to EndBlock",
+                            "Only EndTx or EndInnerBlock can transit to EndInnerBlock",
+                            ExecutionState::EndInnerBlock,
+                            vec![ExecutionState::EndTx, ExecutionState::EndInnerBlock],
+                        ),
+                        (
+                            "Only EndTx, EndInnerBlock or EndBlock can transit to EndBlock",
                             ExecutionState::EndBlock,
-                            vec![ExecutionState::EndTx, ExecutionState::EndBlock],
+                            vec![
+                                ExecutionState::EndTx,
+                                ExecutionState::EndInnerBlock,
+                                ExecutionState::EndBlock,
+                            ],
                         ),
                     ])
                     .filter(move |(_, _, from)| !from.contains(&G::EXECUTION_STATE))
@@ -660,6 +767,25 @@ impl<F: Field> ExecutionConfig<F> {
                 })
         });
 
+        // Enforce the block number transition, which only advances at
+        // EndInnerBlock
+        meta.create_gate("Constrain block number transition", |meta| {
+            let q_usable = meta.query_selector(q_usable);
+            let q_step = meta.query_advice(q_step, Rotation::cur());
+            let q_step_last = meta.query_selector(q_step_last);
+            let delta = (G::EXECUTION_STATE == ExecutionState::EndInnerBlock).expr();
+
+            vec![
+                q_usable
+                    * q_step
+                    * (1.expr() - q_step_last)
+                    * step_curr.execution_state_selector([G::EXECUTION_STATE])
+                    * (step_next.state.block_number.expr()
+                        - step_curr.state.block_number.expr()
+                        - delta),
+            ]
+        });
+
         gadget
     }
 
@@ -674,6 +800,7 @@ impl<F: Field> ExecutionConfig<F> {
         block_table: &dyn LookupTable<F>,
         copy_table: &dyn LookupTable<F>,
         keccak_table: &dyn LookupTable<F>,
+        exp_table: &dyn LookupTable<F>,
         power_of_randomness: &[Expression<F>; 31],
         cell_manager: &CellManager<F>,
     ) {
@@ -690,6 +817,7 @@ impl<F: Field> ExecutionConfig<F> {
                         Table::Byte => byte_table,
                         Table::Copy => copy_table,
                         Table::Keccak => keccak_table,
+                        Table::Exp => exp_table,
                     }
                     .table_exprs(meta);
                     vec![(
@@ -723,28 +851,63 @@ impl<F: Field> ExecutionConfig<F> {
 
                 self.q_step_first.enable(&mut region, offset)?;
 
-                // handle EndBlock
+                // handle EndInnerBlock and EndBlock, which follow the
+                // transactions of each block except the last one, and of the
+                // last block
                 let dummy_tx = Transaction {
                     calls: vec![Default::default()],
                     ..Default::default()
                 };
-                let last_tx = block.txs.last().unwrap_or(&dummy_tx);
-                let end_block_state = &ExecStep {
-                    rw_counter: if block.txs.is_empty() {
-                        0
-                    } else {
-                        // if it is the first tx,  less 1 rw lookup, refer to end_tx gadget
-                        last_tx.steps.last().unwrap().rw_counter + 9 - (last_tx.id == 1) as usize
-                    },
-                    execution_state: ExecutionState::EndBlock,
-                    ..Default::default()
-                };
+                let mut block_end_states = Vec::new();
+                let mut rw_counter = 1;
+                for (idx, ctx) in block.context.ctxs.values().enumerate() {
+                    let block_txs = &block.txs[ctx.cum_num_txs - ctx.num_txs..ctx.cum_num_txs];
+                    if let Some(tx) = block_txs.last() {
+                        // if it is the first tx of the block, less 1 rw lookup,
+                        // refer to end_tx gadget
+                        rw_counter =
+                            tx.steps.last().unwrap().rw_counter + 9 - (ctx.num_txs == 1) as usize;
+                    }
+                    block_end_states.push((
+                        block_txs,
+                        ExecStep {
+                            rw_counter,
+                            execution_state: if idx + 1 == block.context.ctxs.len() {
+                                ExecutionState::EndBlock
+                            } else {
+                                ExecutionState::EndInnerBlock
+                            },
+                            block_number: ctx.number.as_u64(),
+                            ..Default::default()
+                        },
+                    ));
+                }
+                if block_end_states.is_empty() {
+                    block_end_states.push((
+                        &block.txs[..],
+                        ExecStep {
+                            rw_counter,
+                            execution_state: ExecutionState::EndBlock,
+                            ..Default::default()
+                        },
+                    ));
+                }
+
                 // Collect all steps
-                let mut steps = block
-                    .txs
-                    .iter()
-                    .flat_map(|tx| tx.steps.iter().map(move |step| (tx, step)))
-                    .chain(iter::repeat((last_tx, end_block_state)))
+                let mut prev_tx = &dummy_tx;
+                let mut block_steps = Vec::new();
+                for (block_txs, block_end_state) in block_end_states.iter() {
+                    for tx in block_txs.iter() {
+                        block_steps.extend(tx.steps.iter().map(|step| (tx, step)));
+                        prev_tx = tx;
+                    }
+                    block_steps.push((prev_tx, block_end_state));
+                }
+                // The EndBlock step is repeated until the end of the circuit
+                let end_block_step = block_steps.pop().unwrap();
+                let mut steps = block_steps
+                    .into_iter()
+                    .chain(iter::repeat(end_block_step))
                     .peekable();
 
                 let mut last_height = 0;
@@ -908,13 +1071,14 @@ impl<F: Field> ExecutionConfig<F> {
             ExecutionState::BeginTx => assign_exec_step!(self.begin_tx_gadget),
             ExecutionState::EndTx => assign_exec_step!(self.end_tx_gadget),
             ExecutionState::EndBlock => assign_exec_step!(self.end_block_gadget),
+            ExecutionState::EndInnerBlock => assign_exec_step!(self.end_inner_block_gadget),
             // opcode
             ExecutionState::ADD_SUB => assign_exec_step!(self.add_sub_gadget),
             ExecutionState::ADDMOD => assign_exec_step!(self.addmod_gadget),
             ExecutionState::ADDRESS => assign_exec_step!(self.address_gadget),
             ExecutionState::BITWISE => assign_exec_step!(self.bitwise_gadget),
             ExecutionState::BYTE => assign_exec_step!(self.byte_gadget),
-            ExecutionState::CALL => assign_exec_step!(self.call_gadget),
+            ExecutionState::CALL_OP => assign_exec_step!(self.call_op_gadget),
             ExecutionState::CALLDATACOPY => assign_exec_step!(self.calldatacopy_gadget),
             ExecutionState::CALLDATALOAD => assign_exec_step!(self.calldataload_gadget),
             ExecutionState::CALLDATASIZE => assign_exec_step!(self.calldatasize_gadget),
@@ -925,6 +1089,7 @@ impl<F: Field> ExecutionConfig<F> {
             ExecutionState::CODESIZE => assign_exec_step!(self.codesize_gadget),
             ExecutionState::CMP => assign_exec_step!(self.comparator_gadget),
             ExecutionState::DUP => assign_exec_step!(self.dup_gadget),
+            ExecutionState::EXP => assign_exec_step!(self.exp_gadget),
             ExecutionState::EXTCODEHASH => assign_exec_step!(self.extcodehash_gadget),
             ExecutionState::GAS => assign_exec_step!(self.gas_gadget),
             ExecutionState::GASPRICE => assign_exec_step!(self.gasprice_gadget),
@@ -943,6 +1108,7 @@ impl<F: Field> ExecutionConfig<F> {
             ExecutionState::POP => assign_exec_step!(self.pop_gadget),
             ExecutionState::PUSH => assign_exec_step!(self.push_gadget),
             ExecutionState::RETURN => assign_exec_step!(self.return_gadget),
+            ExecutionState::SAR => assign_exec_step!(self.sar_gadget),
             ExecutionState::SCMP => assign_exec_step!(self.signed_comparator_gadget),
             ExecutionState::SDIV_SMOD => assign_exec_step!(self.sdiv_smod_gadget),
             ExecutionState::BLOCKCTXU64 => assign_exec_step!(self.block_ctx_u64_gadget),
@@ -952,17 +1118,12 @@ impl<F: Field> ExecutionConfig<F> {
             ExecutionState::SELFBALANCE => assign_exec_step!(self.selfbalance_gadget),
             // dummy gadgets
             ExecutionState::BALANCE => assign_exec_step!(self.balance_gadget),
-            ExecutionState::EXP => assign_exec_step!(self.exp_gadget),
-            ExecutionState::SAR => assign_exec_step!(self.sar_gadget),
             ExecutionState::EXTCODESIZE => assign_exec_step!(self.extcodesize_gadget),
             ExecutionState::EXTCODECOPY => assign_exec_step!(self.extcodecopy_gadget),
             ExecutionState::RETURNDATASIZE => assign_exec_step!(self.returndatasize_gadget),
             ExecutionState::RETURNDATACOPY => assign_exec_step!(self.returndatacopy_gadget),
             ExecutionState::CREATE => assign_exec_step!(self.create_gadget),
-            ExecutionState::CALLCODE => assign_exec_step!(self.callcode_gadget),
-            ExecutionState::DELEGATECALL => assign_exec_step!(self.delegatecall_gadget),
             ExecutionState::CREATE2 => assign_exec_step!(self.create2_gadget),
-            ExecutionState::STATICCALL => assign_exec_step!(self.staticcall_gadget),
             ExecutionState::SELFDESTRUCT => assign_exec_step!(self.selfdestruct_gadget),
             // end of dummy gadgets
             ExecutionState::SHA3 => assign_exec_step!(self.sha3_gadget),
@@ -1052,6 +1213,9 @@ impl<F: Field> ExecutionConfig<F> {
             ExecutionState::ErrorInvalidCreationCode => {
                 assign_exec_step!(self.error_invalid_creation_code)
             }
+            ExecutionState::ErrorMaxCodeSizeExceeded => {
+                assign_exec_step!(self.error_max_code_size_exceeded)
+            }
             ExecutionState::ErrorReturnDataOutOfBound => {
                 assign_exec_step!(self.error_return_data_out_of_bound)
             }
@@ -1059,6 +1223,34 @@ impl<F: Field> ExecutionConfig<F> {
             ExecutionState::ErrorInvalidOpcode => {
                 assign_exec_step!(self.invalid_opcode_gadget)
             }
+            // precompile gadgets
+            ExecutionState::PrecompileEcRecover => {
+                assign_exec_step!(self.precompile_ecrecover_gadget)
+            }
+            ExecutionState::PrecompileSha256 => {
+                assign_exec_step!(self.precompile_sha256_gadget)
+            }
+            ExecutionState::PrecompileRipemd160 => {
+                assign_exec_step!(self.precompile_ripemd160_gadget)
+            }
+            ExecutionState::PrecompileIdentity => {
+                assign_exec_step!(self.precompile_identity_gadget)
+            }
+            ExecutionState::PrecompileBigModExp => {
+                assign_exec_step!(self.precompile_bigmodexp_gadget)
+            }
+            ExecutionState::PrecompileBn256Add => {
+                assign_exec_step!(self.precompile_bn256_add_gadget)
+            }
+            ExecutionState::PrecompileBn256ScalarMul => {
+                assign_exec_step!(self.precompile_bn256_scalar_mul_gadget)
+            }
+            ExecutionState::PrecompileBn256Pairing => {
+                assign_exec_step!(self.precompile_bn256_pairing_gadget)
+            }
+            ExecutionState::PrecompileBlake2f => {
+                assign_exec_step!(self.precompile_blake2f_gadget)
+            }
 
             _ => unimplemented!("unimplemented ExecutionState: {:?}", step.execution_state),
         }
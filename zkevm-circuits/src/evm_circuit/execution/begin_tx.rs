@@ -17,9 +17,11 @@ use crate::{
     table::{AccountFieldTag, CallContextFieldTag, TxFieldTag as TxContextFieldTag},
     util::Expr,
 };
+use bus_mapping::precompile::PrecompileCalls;
 use eth_types::{evm_types::GasCost, Field, ToLittleEndian, ToScalar};
 use halo2_proofs::circuit::Value;
 use halo2_proofs::plonk::Error;
+use strum::IntoEnumIterator;
 
 #[derive(Clone, Debug)]
 pub(crate) struct BeginTxGadget<F> {
@@ -128,6 +130,16 @@ impl<F: Field> ExecutionGadget<F> for BeginTxGadget<F> {
             0.expr(),
             None,
         );
+        // Precompiled contracts are always warm (EIP 2929)
+        for precompile in PrecompileCalls::iter() {
+            cb.account_access_list_write(
+                tx_id.expr(),
+                u64::from(precompile).expr(),
+                1.expr(),
+                0.expr(),
+                None,
+            );
+        }
 
         // Transfer value from caller to callee
         let transfer_with_gas_fee = TransferWithGasFeeGadget::construct(
@@ -173,7 +185,7 @@ impl<F: Field> ExecutionGadget<F> for BeginTxGadget<F> {
         }
 
         cb.require_step_state_transition(StepStateTransition {
-            // 23 reads and writes:
+            // 32 reads and writes:
             //   - Write CallContext TxId
             //   - Write CallContext RwCounterEndOfReversion
             //   - Write CallContext IsPersistent
@@ -181,6 +193,7 @@ impl<F: Field> ExecutionGadget<F> for BeginTxGadget<F> {
             //   - Write Account Nonce
             //   - Write TxAccessListAccount
             //   - Write TxAccessListAccount
+            //   - Write TxAccessListAccount (x9, for precompiled contracts)
             //   - Write Account Balance
             //   - Write Account Balance
             //   - Read Account CodeHash
@@ -197,7 +210,7 @@ impl<F: Field> ExecutionGadget<F> for BeginTxGadget<F> {
             //   - Write CallContext IsRoot
             //   - Write CallContext IsCreate
             //   - Write CallContext CodeHash
-            rw_counter: Delta(23.expr()),
+            rw_counter: Delta(32.expr()),
             call_id: To(call_id.expr()),
             is_root: To(true.expr()),
             is_create: To(false.expr()),
@@ -237,9 +250,12 @@ impl<F: Field> ExecutionGadget<F> for BeginTxGadget<F> {
         step: &ExecStep,
     ) -> Result<(), Error> {
         let gas_fee = tx.gas_price * tx.gas;
-        let [caller_balance_pair, callee_balance_pair, (callee_code_hash, _)] =
-            [step.rw_indices[7], step.rw_indices[8], step.rw_indices[9]]
-                .map(|idx| block.rws[idx].account_value_pair());
+        let [caller_balance_pair, callee_balance_pair, (callee_code_hash, _)] = [
+            step.rw_indices[16],
+            step.rw_indices[17],
+            step.rw_indices[18],
+        ]
+        .map(|idx| block.rws[idx].account_value_pair());
 
         self.tx_id
             .assign(region, offset, Value::known(F::from(tx.id as u64)))?;
@@ -370,7 +386,7 @@ mod test {
         builder
             .handle_block(&block.eth_block, &block.geth_traces)
             .unwrap();
-        let block = block_convert(&builder.block, &builder.code_db);
+        let block = block_convert(&builder.block, &builder.code_db).unwrap();
         assert_eq!(run_test_circuit(block), Ok(()));
     }
 
@@ -435,7 +451,7 @@ mod test {
         builder
             .handle_block(&block.eth_block, &block.geth_traces)
             .unwrap();
-        let block = block_convert(&builder.block, &builder.code_db);
+        let block = block_convert(&builder.block, &builder.code_db).unwrap();
 
         assert_eq!(run_test_circuit(block), Ok(()));
     }
@@ -42,7 +42,7 @@ impl<F: Field> ExecutionGadget<F> for BlockHashGadget<F> {
         let current_block_number = cb.query_cell();
         cb.block_lookup(
             BlockContextFieldTag::Number.expr(),
-            None,
+            cb.curr.state.block_number.expr(),
             current_block_number.expr(),
         );
 
@@ -62,7 +62,7 @@ impl<F: Field> ExecutionGadget<F> for BlockHashGadget<F> {
         cb.condition(block_lt.expr() * diff_lt.expr(), |cb| {
             cb.block_lookup(
                 BlockContextFieldTag::BlockHash.expr(),
-                Some(from_bytes::expr(&block_number.cells)),
+                from_bytes::expr(&block_number.cells),
                 block_hash.expr(),
             );
         });
@@ -114,17 +114,9 @@ impl<F: Field> ExecutionGadget<F> for BlockHashGadget<F> {
         )?;
         let block_number: F = block_number.to_scalar().unwrap();
 
-        let current_block_number = block.context.number;
-        self.current_block_number.assign(
-            region,
-            offset,
-            Value::known(
-                current_block_number
-                    .to_scalar()
-                    .expect("unexpected U256 -> Scalar conversion failure"),
-            ),
-        )?;
-        let current_block_number: F = current_block_number.to_scalar().unwrap();
+        let current_block_number = F::from(step.block_number);
+        self.current_block_number
+            .assign(region, offset, Value::known(current_block_number))?;
 
         self.block_hash.assign(
             region,
@@ -188,7 +180,7 @@ mod test {
             .expect("could not handle block tx");
 
         test_circuits_using_witness_block(
-            block_convert(&builder.block, &builder.code_db),
+            block_convert(&builder.block, &builder.code_db).unwrap(),
             BytecodeTestConfig::default(),
         )
         .unwrap();
@@ -11,35 +11,47 @@ use crate::{
             },
             from_bytes,
             math_gadget::{
-                BatchedIsZeroGadget, ConstantDivisionGadget, IsEqualGadget, IsZeroGadget,
+                BatchedIsZeroGadget, ConstantDivisionGadget, IsEqualGadget, IsZeroGadget, LtGadget,
                 MinMaxGadget,
             },
             memory_gadget::{MemoryAddressGadget, MemoryExpansionGadget},
-            select, sum, CachedRegion, Cell, Word,
+            not, or, select, sum, CachedRegion, Cell, Word,
         },
         witness::{Block, Call, ExecStep, Transaction},
     },
     table::{AccountFieldTag, CallContextFieldTag},
     util::Expr,
 };
-use bus_mapping::evm::OpcodeId;
+use bus_mapping::{evm::OpcodeId, precompile::PrecompileCalls};
 use eth_types::{
     evm_types::{GasCost, GAS_STIPEND_CALL_WITH_VALUE},
-    Field, ToLittleEndian, ToScalar,
+    Field, ToAddress, ToLittleEndian, ToScalar, U256,
+};
+use halo2_proofs::{
+    circuit::Value,
+    plonk::{Error, Expression},
 };
-use halo2_proofs::{circuit::Value, plonk::Error};
 use keccak256::EMPTY_HASH_LE;
+use strum::IntoEnumIterator;
 
+/// Gadget for call related opcodes. It supports `OpcodeId::CALL`,
+/// `OpcodeId::CALLCODE`, `OpcodeId::DELEGATECALL` and `OpcodeId::STATICCALL`.
 #[derive(Clone, Debug)]
-pub(crate) struct CallGadget<F> {
+pub(crate) struct CallOpGadget<F> {
     opcode: Cell<F>,
+    is_call: IsZeroGadget<F>,
+    is_callcode: IsZeroGadget<F>,
+    is_delegatecall: IsZeroGadget<F>,
+    is_staticcall: IsZeroGadget<F>,
     tx_id: Cell<F>,
     reversion_info: ReversionInfo<F>,
     current_address: Cell<F>,
+    current_caller_address: Cell<F>,
+    current_value: Cell<F>,
     is_static: Cell<F>,
     depth: Cell<F>,
     gas: Word<F>,
-    callee_address: Word<F>,
+    code_address: Word<F>,
     value: Word<F>,
     is_success: Cell<F>,
     gas_is_u64: IsZeroGadget<F>,
@@ -55,29 +67,41 @@ pub(crate) struct CallGadget<F> {
     callee_code_hash: Cell<F>,
     is_empty_nonce_and_balance: BatchedIsZeroGadget<F, 2>,
     is_empty_code_hash: IsEqualGadget<F>,
+    code_address_is_zero: IsZeroGadget<F>,
+    code_address_lt_10: LtGadget<F, N_BYTES_ACCOUNT_ADDRESS>,
     one_64th_gas: ConstantDivisionGadget<F, N_BYTES_GAS>,
     capped_callee_gas_left: MinMaxGadget<F, N_BYTES_GAS>,
 }
 
-impl<F: Field> ExecutionGadget<F> for CallGadget<F> {
-    const NAME: &'static str = "CALL";
+impl<F: Field> ExecutionGadget<F> for CallOpGadget<F> {
+    const NAME: &'static str = "CALL_OP";
 
-    const EXECUTION_STATE: ExecutionState = ExecutionState::CALL;
+    const EXECUTION_STATE: ExecutionState = ExecutionState::CALL_OP;
 
     fn configure(cb: &mut ConstraintBuilder<F>) -> Self {
         let opcode = cb.query_cell();
         cb.opcode_lookup(opcode.expr(), 1.expr());
 
         // We do the responsible opcode check explicitly here because we're not using
-        // the `SameContextGadget` for `CALL`.
+        // the `SameContextGadget` for `CALL`, `CALLCODE`, `DELEGATECALL` and
+        // `STATICCALL`.
+        let [is_call, is_callcode, is_delegatecall, is_staticcall] = [
+            OpcodeId::CALL,
+            OpcodeId::CALLCODE,
+            OpcodeId::DELEGATECALL,
+            OpcodeId::STATICCALL,
+        ]
+        .map(|opcode_id| IsZeroGadget::construct(cb, opcode.expr() - opcode_id.expr()));
         cb.require_equal(
-            "Opcode should be CALL",
-            opcode.expr(),
-            OpcodeId::CALL.expr(),
+            "Opcode should be CALL, CALLCODE, DELEGATECALL or STATICCALL",
+            is_call.expr() + is_callcode.expr() + is_delegatecall.expr() + is_staticcall.expr(),
+            1.expr(),
         );
+        // Only CALL and CALLCODE take the value argument from stack.
+        let has_value_arg = is_call.expr() + is_callcode.expr();
 
         let gas_word = cb.query_word();
-        let callee_address_word = cb.query_word();
+        let code_address_word = cb.query_word();
         let value = cb.query_word();
         let cd_offset = cb.query_cell();
         let cd_length = cb.query_rlc();
@@ -97,21 +121,52 @@ impl<F: Field> ExecutionGadget<F> for CallGadget<F> {
         ]
         .map(|field_tag| cb.call_context(None, field_tag));
 
+        // DELEGATECALL propagates the caller address and value of the current
+        // call to the callee.
+        let (current_caller_address, current_value) = cb.condition(is_delegatecall.expr(), |cb| {
+            (
+                cb.call_context(None, CallContextFieldTag::CallerAddress),
+                cb.call_context(None, CallContextFieldTag::Value),
+            )
+        });
+
         cb.range_lookup(depth.expr(), 1024);
 
         // Lookup values from stack
         cb.stack_pop(gas_word.expr());
-        cb.stack_pop(callee_address_word.expr());
-        cb.stack_pop(value.expr());
-        cb.stack_pop(cd_offset.expr());
-        cb.stack_pop(cd_length.expr());
-        cb.stack_pop(rd_offset.expr());
-        cb.stack_pop(rd_length.expr());
-        cb.stack_push(is_success.expr());
+        cb.stack_pop(code_address_word.expr());
+        cb.condition(has_value_arg.clone(), |cb| {
+            cb.stack_lookup(false.expr(), 2.expr(), value.expr());
+        });
+        cb.condition(1.expr() - has_value_arg.clone(), |cb| {
+            cb.require_zero(
+                "DELEGATECALL and STATICCALL transfer no value",
+                value.expr(),
+            );
+        });
+        for (idx, rlc) in [
+            cd_offset.expr(),
+            cd_length.expr(),
+            rd_offset.expr(),
+            rd_length.expr(),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            cb.stack_lookup(
+                false.expr(),
+                2.expr() + has_value_arg.clone() + idx.expr(),
+                rlc,
+            );
+        }
+        cb.stack_lookup(
+            true.expr(),
+            5.expr() + has_value_arg.clone(),
+            is_success.expr(),
+        );
 
         // Recomposition of random linear combination to integer
-        let callee_address =
-            from_bytes::expr(&callee_address_word.cells[..N_BYTES_ACCOUNT_ADDRESS]);
+        let code_address = from_bytes::expr(&code_address_word.cells[..N_BYTES_ACCOUNT_ADDRESS]);
         let gas = from_bytes::expr(&gas_word.cells[..N_BYTES_GAS]);
         let gas_is_u64 = IsZeroGadget::construct(cb, sum::expr(&gas_word.cells[N_BYTES_GAS..]));
         let cd_address = MemoryAddressGadget::construct(cb, cd_offset, cd_length);
@@ -122,12 +177,25 @@ impl<F: Field> ExecutionGadget<F> for CallGadget<F> {
             [cd_address.address(), rd_address.address()],
         );
 
-        // Add callee to access list
+        // CALLCODE and DELEGATECALL execute the code at `code_address` in the
+        // context of the current account.
+        let caller_address = select::expr(
+            is_delegatecall.expr(),
+            current_caller_address.expr(),
+            current_address.expr(),
+        );
+        let callee_address = select::expr(
+            is_callcode.expr() + is_delegatecall.expr(),
+            current_address.expr(),
+            code_address.clone(),
+        );
+
+        // Add code address to access list
         let is_warm = cb.query_bool();
         let is_warm_prev = cb.query_bool();
         cb.account_access_list_write(
             tx_id.expr(),
-            callee_address.clone(),
+            code_address.clone(),
             is_warm.expr(),
             is_warm_prev.expr(),
             Some(&mut reversion_info),
@@ -151,7 +219,7 @@ impl<F: Field> ExecutionGadget<F> for CallGadget<F> {
         // Verify transfer
         let value_is_zero = IsZeroGadget::construct(cb, sum::expr(&value.cells));
         let has_value = 1.expr() - value_is_zero.expr();
-        cb.condition(has_value.clone(), |cb| {
+        cb.condition(is_call.expr() * has_value.clone(), |cb| {
             cb.require_zero(
                 "CALL with value must not be in static call stack",
                 is_static.expr(),
@@ -169,7 +237,7 @@ impl<F: Field> ExecutionGadget<F> for CallGadget<F> {
         let [callee_nonce, callee_code_hash] = [AccountFieldTag::Nonce, AccountFieldTag::CodeHash]
             .map(|field_tag| {
                 let value = cb.query_cell();
-                cb.account_read(callee_address.clone(), field_tag, value.expr());
+                cb.account_read(code_address.clone(), field_tag, value.expr());
                 value
             });
         let is_empty_nonce_and_balance = BatchedIsZeroGadget::construct(
@@ -187,15 +255,16 @@ impl<F: Field> ExecutionGadget<F> for CallGadget<F> {
                 cb.power_of_randomness(),
             ),
         );
-        let is_empty_account = is_empty_nonce_and_balance.expr() * is_empty_code_hash.expr();
+        // Only CALL might create a new account.
+        let is_empty_account =
+            is_call.expr() * is_empty_nonce_and_balance.expr() * is_empty_code_hash.expr();
         // Sum up gas cost
-        let gas_cost = select::expr(
+        let gas_cost = call_gas_cost_expr(
             is_warm_prev.expr(),
-            GasCost::WARM_ACCESS.expr(),
-            GasCost::COLD_ACCOUNT_ACCESS.expr(),
-        ) + has_value.clone()
-            * (GasCost::CALL_WITH_VALUE.expr() + is_empty_account * GasCost::NEW_ACCOUNT.expr())
-            + memory_expansion.gas_cost();
+            has_value.clone(),
+            is_empty_account,
+            memory_expansion.gas_cost(),
+        );
 
         // Apply EIP 150
         let gas_available = cb.curr.state.gas_left.expr() - gas_cost.clone();
@@ -208,9 +277,31 @@ impl<F: Field> ExecutionGadget<F> for CallGadget<F> {
             all_but_one_64th_gas,
         );
 
-        // TODO: Handle precompiled
+        // Precompiled contracts are at addresses 0x01 to 0x09.
+        let code_address_is_zero = IsZeroGadget::construct(cb, code_address.clone());
+        let code_address_lt_10 = LtGadget::construct(cb, code_address.clone(), 10.expr());
+        let is_precompile = not::expr(code_address_is_zero.expr()) * code_address_lt_10.expr();
 
-        cb.condition(is_empty_code_hash.expr(), |cb| {
+        // The precompile is executed in the next step, whose execution state is
+        // determined by the code address.
+        cb.condition(is_precompile.clone(), |cb| {
+            cb.require_equal(
+                "next execution state is the precompile at code address",
+                PrecompileCalls::iter()
+                    .map(|precompile| {
+                        u64::from(precompile).expr()
+                            * cb.next
+                                .execution_state_selector([ExecutionState::from(precompile)])
+                    })
+                    .reduce(|acc, expr| acc + expr)
+                    .unwrap(),
+                code_address.clone(),
+            );
+        });
+
+        let stack_pointer_delta = 5.expr() + has_value_arg;
+        let is_empty_code = is_empty_code_hash.expr() * not::expr(is_precompile);
+        cb.condition(is_empty_code.clone(), |cb| {
             // Save caller's call state
             for field_tag in [
                 CallContextFieldTag::LastCalleeId,
@@ -221,9 +312,9 @@ impl<F: Field> ExecutionGadget<F> for CallGadget<F> {
             }
 
             cb.require_step_state_transition(StepStateTransition {
-                rw_counter: Delta(24.expr()),
+                rw_counter: Delta(cb.rw_counter_offset()),
                 program_counter: Delta(1.expr()),
-                stack_pointer: Delta(6.expr()),
+                stack_pointer: Delta(stack_pointer_delta.clone()),
                 gas_left: Delta(
                     has_value.clone() * GAS_STIPEND_CALL_WITH_VALUE.expr() - gas_cost.clone(),
                 ),
@@ -233,7 +324,7 @@ impl<F: Field> ExecutionGadget<F> for CallGadget<F> {
             });
         });
 
-        cb.condition(1.expr() - is_empty_code_hash.expr(), |cb| {
+        cb.condition(not::expr(is_empty_code), |cb| {
             // Save caller's call state
             for (field_tag, value) in [
                 (
@@ -242,7 +333,7 @@ impl<F: Field> ExecutionGadget<F> for CallGadget<F> {
                 ),
                 (
                     CallContextFieldTag::StackPointer,
-                    cb.curr.state.stack_pointer.expr() + 6.expr(),
+                    cb.curr.state.stack_pointer.expr() + stack_pointer_delta,
                 ),
                 (
                     CallContextFieldTag::GasLeft,
@@ -265,15 +356,21 @@ impl<F: Field> ExecutionGadget<F> for CallGadget<F> {
                 (CallContextFieldTag::CallerId, cb.curr.state.call_id.expr()),
                 (CallContextFieldTag::TxId, tx_id.expr()),
                 (CallContextFieldTag::Depth, depth.expr() + 1.expr()),
-                (CallContextFieldTag::CallerAddress, current_address.expr()),
+                (CallContextFieldTag::CallerAddress, caller_address),
                 (CallContextFieldTag::CalleeAddress, callee_address),
                 (CallContextFieldTag::CallDataOffset, cd_address.offset()),
                 (CallContextFieldTag::CallDataLength, cd_address.length()),
                 (CallContextFieldTag::ReturnDataOffset, rd_address.offset()),
                 (CallContextFieldTag::ReturnDataLength, rd_address.length()),
-                (CallContextFieldTag::Value, value.expr()),
+                (
+                    CallContextFieldTag::Value,
+                    select::expr(is_delegatecall.expr(), current_value.expr(), value.expr()),
+                ),
                 (CallContextFieldTag::IsSuccess, is_success.expr()),
-                (CallContextFieldTag::IsStatic, is_static.expr()),
+                (
+                    CallContextFieldTag::IsStatic,
+                    or::expr([is_static.expr(), is_staticcall.expr()]),
+                ),
                 (CallContextFieldTag::LastCalleeId, 0.expr()),
                 (CallContextFieldTag::LastCalleeReturnDataOffset, 0.expr()),
                 (CallContextFieldTag::LastCalleeReturnDataLength, 0.expr()),
@@ -288,7 +385,7 @@ impl<F: Field> ExecutionGadget<F> for CallGadget<F> {
             let callee_gas_left = callee_gas_left + has_value * GAS_STIPEND_CALL_WITH_VALUE.expr();
 
             cb.require_step_state_transition(StepStateTransition {
-                rw_counter: Delta(44.expr()),
+                rw_counter: Delta(cb.rw_counter_offset()),
                 call_id: To(callee_call_id.expr()),
                 is_root: To(false.expr()),
                 is_create: To(false.expr()),
@@ -301,13 +398,19 @@ impl<F: Field> ExecutionGadget<F> for CallGadget<F> {
 
         Self {
             opcode,
+            is_call,
+            is_callcode,
+            is_delegatecall,
+            is_staticcall,
             tx_id,
             reversion_info,
             current_address,
+            current_caller_address,
+            current_value,
             is_static,
             depth,
             gas: gas_word,
-            callee_address: callee_address_word,
+            code_address: code_address_word,
             value,
             is_success,
             gas_is_u64,
@@ -323,6 +426,8 @@ impl<F: Field> ExecutionGadget<F> for CallGadget<F> {
             callee_code_hash,
             is_empty_nonce_and_balance,
             is_empty_code_hash,
+            code_address_is_zero,
+            code_address_lt_10,
             one_64th_gas,
             capped_callee_gas_left,
         }
@@ -337,41 +442,61 @@ impl<F: Field> ExecutionGadget<F> for CallGadget<F> {
         call: &Call,
         step: &ExecStep,
     ) -> Result<(), Error> {
-        let [tx_id, current_address, is_static, depth, callee_rw_counter_end_of_reversion, callee_is_persistent] =
-            [
-                step.rw_indices[0],
-                step.rw_indices[3],
-                step.rw_indices[4],
-                step.rw_indices[5],
-                step.rw_indices[15],
-                step.rw_indices[16],
-            ]
-            .map(|idx| block.rws[idx].call_context_value());
-        let [gas, callee_address, value, cd_offset, cd_length, rd_offset, rd_length, is_success] =
-            [
-                step.rw_indices[6],
-                step.rw_indices[7],
-                step.rw_indices[8],
-                step.rw_indices[9],
-                step.rw_indices[10],
-                step.rw_indices[11],
-                step.rw_indices[12],
-                step.rw_indices[13],
-            ]
-            .map(|idx| block.rws[idx].stack_value());
-        let (is_warm, is_warm_prev) = block.rws[step.rw_indices[14]].tx_access_list_value_pair();
+        let opcode = step.opcode.unwrap();
+        let is_call = opcode == OpcodeId::CALL;
+        let is_callcode = opcode == OpcodeId::CALLCODE;
+        let is_delegatecall = opcode == OpcodeId::DELEGATECALL;
+        let has_value_arg = is_call || is_callcode;
+
+        let [tx_id, current_address, is_static, depth] =
+            [0, 3, 4, 5].map(|idx| block.rws[step.rw_indices[idx]].call_context_value());
+        // DELEGATECALL reads caller address and value of the current call
+        // additionally.
+        let (current_caller_address, current_value, rw_offset) = if is_delegatecall {
+            (
+                block.rws[step.rw_indices[6]].call_context_value(),
+                block.rws[step.rw_indices[7]].call_context_value(),
+                8,
+            )
+        } else {
+            (U256::zero(), U256::zero(), 6)
+        };
+
+        // Only CALL and CALLCODE have the value argument on stack.
+        let [gas, code_address] =
+            [rw_offset, rw_offset + 1].map(|idx| block.rws[step.rw_indices[idx]].stack_value());
+        let (value, rw_offset) = if has_value_arg {
+            (
+                block.rws[step.rw_indices[rw_offset + 2]].stack_value(),
+                rw_offset + 3,
+            )
+        } else {
+            (U256::zero(), rw_offset + 2)
+        };
+        let [cd_offset, cd_length, rd_offset, rd_length, is_success] =
+            [0, 1, 2, 3, 4].map(|idx| block.rws[step.rw_indices[rw_offset + idx]].stack_value());
+        let (is_warm, is_warm_prev) =
+            block.rws[step.rw_indices[rw_offset + 5]].tx_access_list_value_pair();
+        let [callee_rw_counter_end_of_reversion, callee_is_persistent] =
+            [6, 7].map(|idx| block.rws[step.rw_indices[rw_offset + idx]].call_context_value());
         let [caller_balance_pair, callee_balance_pair, (callee_nonce, _), (callee_code_hash, _)] =
-            [
-                step.rw_indices[17],
-                step.rw_indices[18],
-                step.rw_indices[19],
-                step.rw_indices[20],
-            ]
-            .map(|idx| block.rws[idx].account_value_pair());
+            [8, 9, 10, 11]
+                .map(|idx| block.rws[step.rw_indices[rw_offset + idx]].account_value_pair());
 
-        let opcode = step.opcode.unwrap();
         self.opcode
             .assign(region, offset, Value::known(F::from(opcode.as_u64())))?;
+        for (gadget, opcode_id) in [
+            (&self.is_call, OpcodeId::CALL),
+            (&self.is_callcode, OpcodeId::CALLCODE),
+            (&self.is_delegatecall, OpcodeId::DELEGATECALL),
+            (&self.is_staticcall, OpcodeId::STATICCALL),
+        ] {
+            gadget.assign(
+                region,
+                offset,
+                F::from(opcode.as_u64()) - F::from(opcode_id.as_u64()),
+            )?;
+        }
 
         self.tx_id
             .assign(region, offset, Value::known(F::from(tx_id.low_u64())))?;
@@ -381,14 +506,27 @@ impl<F: Field> ExecutionGadget<F> for CallGadget<F> {
             call.rw_counter_end_of_reversion,
             call.is_persistent,
         )?;
-        self.current_address.assign(
+        for (cell, address) in [
+            (&self.current_address, current_address),
+            (&self.current_caller_address, current_caller_address),
+        ] {
+            cell.assign(
+                region,
+                offset,
+                Value::known(
+                    address
+                        .to_scalar()
+                        .expect("unexpected Address -> Scalar conversion failure"),
+                ),
+            )?;
+        }
+        self.current_value.assign(
             region,
             offset,
-            Value::known(
-                current_address
-                    .to_scalar()
-                    .expect("unexpected Address -> Scalar conversion failure"),
-            ),
+            Value::known(Word::random_linear_combine(
+                current_value.to_le_bytes(),
+                block.randomness,
+            )),
         )?;
         self.is_static
             .assign(region, offset, Value::known(F::from(is_static.low_u64())))?;
@@ -396,8 +534,16 @@ impl<F: Field> ExecutionGadget<F> for CallGadget<F> {
             .assign(region, offset, Value::known(F::from(depth.low_u64())))?;
 
         self.gas.assign(region, offset, Some(gas.to_le_bytes()))?;
-        self.callee_address
-            .assign(region, offset, Some(callee_address.to_le_bytes()))?;
+        self.code_address
+            .assign(region, offset, Some(code_address.to_le_bytes()))?;
+        let code_address_value: F = code_address
+            .to_address()
+            .to_scalar()
+            .expect("unexpected Address -> Scalar conversion failure");
+        self.code_address_is_zero
+            .assign(region, offset, code_address_value)?;
+        self.code_address_lt_10
+            .assign(region, offset, code_address_value, F::from(10))?;
         self.value
             .assign(region, offset, Some(value.to_le_bytes()))?;
         self.is_success
@@ -470,21 +616,12 @@ impl<F: Field> ExecutionGadget<F> for CallGadget<F> {
             Word::random_linear_combine(*EMPTY_HASH_LE, block.randomness),
         )?;
         let is_empty_account = is_empty_nonce_and_balance * is_empty_code_hash;
-        let has_value = !value.is_zero();
-        let gas_cost = if is_warm_prev {
-            GasCost::WARM_ACCESS.as_u64()
-        } else {
-            GasCost::COLD_ACCOUNT_ACCESS.as_u64()
-        } + if has_value {
-            GasCost::CALL_WITH_VALUE.as_u64()
-                + if is_empty_account == F::one() {
-                    GasCost::NEW_ACCOUNT.as_u64()
-                } else {
-                    0
-                }
-        } else {
-            0
-        } + memory_expansion_gas_cost;
+        let gas_cost = calc_call_gas_cost(
+            is_warm_prev,
+            !value.is_zero(),
+            is_call && is_empty_account == F::one(),
+            memory_expansion_gas_cost,
+        );
         let gas_available = step.gas_left - gas_cost;
         self.one_64th_gas
             .assign(region, offset, gas_available as u128)?;
@@ -498,6 +635,43 @@ impl<F: Field> ExecutionGadget<F> for CallGadget<F> {
     }
 }
 
+/// Gas cost of the call related opcodes before the gas is passed to the
+/// callee, which is the account access cost, the value transfer cost with the
+/// new account cost, and the memory expansion cost.
+pub(crate) fn call_gas_cost_expr<F: Field>(
+    is_warm: Expression<F>,
+    has_value: Expression<F>,
+    is_empty_account: Expression<F>,
+    memory_expansion_gas_cost: Expression<F>,
+) -> Expression<F> {
+    select::expr(
+        is_warm,
+        GasCost::WARM_ACCESS.expr(),
+        GasCost::COLD_ACCOUNT_ACCESS.expr(),
+    ) + has_value
+        * (GasCost::CALL_WITH_VALUE.expr() + is_empty_account * GasCost::NEW_ACCOUNT.expr())
+        + memory_expansion_gas_cost
+}
+
+pub(crate) fn calc_call_gas_cost(
+    is_warm: bool,
+    has_value: bool,
+    is_empty_account: bool,
+    memory_expansion_gas_cost: u64,
+) -> u64 {
+    let access_gas = if is_warm {
+        GasCost::WARM_ACCESS
+    } else {
+        GasCost::COLD_ACCOUNT_ACCESS
+    };
+    let value_transfer_gas = match (has_value, is_empty_account) {
+        (true, true) => GasCost::CALL_WITH_VALUE.as_u64() + GasCost::NEW_ACCOUNT.as_u64(),
+        (true, false) => GasCost::CALL_WITH_VALUE.as_u64(),
+        (false, _) => 0,
+    };
+    access_gas.as_u64() + value_transfer_gas + memory_expansion_gas_cost
+}
+
 #[cfg(test)]
 mod test {
     use crate::evm_circuit::{test::run_test_circuit, witness::block_convert};
@@ -518,7 +692,33 @@ mod test {
         rd_length: u64,
     }
 
-    fn caller(stack: Stack, caller_is_success: bool) -> Account {
+    const TEST_CALL_OPCODES: &[OpcodeId] = &[
+        OpcodeId::CALL,
+        OpcodeId::CALLCODE,
+        OpcodeId::DELEGATECALL,
+        OpcodeId::STATICCALL,
+    ];
+
+    fn call_bytecode(opcode: OpcodeId, address: Address, stack: Stack) -> Bytecode {
+        let mut bytecode = bytecode! {
+            PUSH32(Word::from(stack.rd_length))
+            PUSH32(Word::from(stack.rd_offset))
+            PUSH32(Word::from(stack.cd_length))
+            PUSH32(Word::from(stack.cd_offset))
+        };
+        // Only CALL and CALLCODE take the value argument
+        if matches!(opcode, OpcodeId::CALL | OpcodeId::CALLCODE) {
+            bytecode.push(32, stack.value);
+        }
+        bytecode.append(&bytecode! {
+            PUSH32(address.to_word())
+            PUSH32(Word::from(stack.gas))
+            .write_op(opcode)
+        });
+        bytecode
+    }
+
+    fn caller(opcode: OpcodeId, stack: Stack, caller_is_success: bool) -> Account {
         let terminator = if caller_is_success {
             OpcodeId::RETURN
         } else {
@@ -526,27 +726,13 @@ mod test {
         };
 
         // Call twice for testing both cold and warm access
-        let bytecode = bytecode! {
-            PUSH32(Word::from(stack.rd_length))
-            PUSH32(Word::from(stack.rd_offset))
-            PUSH32(Word::from(stack.cd_length))
-            PUSH32(Word::from(stack.cd_offset))
-            PUSH32(stack.value)
-            PUSH32(Address::repeat_byte(0xff).to_word())
-            PUSH32(Word::from(stack.gas))
-            CALL
-            PUSH32(Word::from(stack.rd_length))
-            PUSH32(Word::from(stack.rd_offset))
-            PUSH32(Word::from(stack.cd_length))
-            PUSH32(Word::from(stack.cd_offset))
-            PUSH32(stack.value)
-            PUSH32(Address::repeat_byte(0xff).to_word())
-            PUSH32(Word::from(stack.gas))
-            CALL
+        let mut bytecode = call_bytecode(opcode, Address::repeat_byte(0xff), stack);
+        bytecode.append(&call_bytecode(opcode, Address::repeat_byte(0xff), stack));
+        bytecode.append(&bytecode! {
             PUSH1(0)
             PUSH1(0)
             .write_op(terminator)
-        };
+        });
 
         Account {
             address: Address::repeat_byte(0xfe),
@@ -624,7 +810,7 @@ mod test {
         builder
             .handle_block(&block_data.eth_block, &block_data.geth_traces)
             .unwrap();
-        let block = block_convert(&builder.block, &builder.code_db);
+        let block = block_convert(&builder.block, &builder.code_db).unwrap();
         assert_eq!(run_test_circuit(block), Ok(()));
     }
 
@@ -661,12 +847,12 @@ mod test {
         builder
             .handle_block(&block_data.eth_block, &block_data.geth_traces)
             .unwrap();
-        let block = block_convert(&builder.block, &builder.code_db);
+        let block = block_convert(&builder.block, &builder.code_db).unwrap();
         assert_eq!(run_test_circuit(block), Ok(()));
     }
 
     #[test]
-    fn call_gadget_simple() {
+    fn callop_gadget_simple() {
         let stacks = vec![
             // With nothing
             Stack::default(),
@@ -708,8 +894,38 @@ mod test {
             },
         ];
         let callees = vec![callee(bytecode! {}), callee(bytecode! { STOP })];
-        for (stack, callee) in stacks.into_iter().cartesian_product(callees.into_iter()) {
-            test_ok(caller(stack, true), callee);
+        for ((opcode, stack), callee) in TEST_CALL_OPCODES
+            .iter()
+            .cartesian_product(stacks.into_iter())
+            .cartesian_product(callees.into_iter())
+        {
+            test_ok(caller(*opcode, stack, true), callee);
+        }
+    }
+
+    #[test]
+    fn callop_gadget_context() {
+        let stack = Stack {
+            gas: 50000,
+            ..Default::default()
+        };
+        // Callee reads the context propagated by each call kind, and writes
+        // to the storage of its executing account if not in static context.
+        for opcode in TEST_CALL_OPCODES {
+            let mut code = bytecode! {
+                CALLER
+                CALLVALUE
+                ADDRESS
+            };
+            if *opcode != OpcodeId::STATICCALL {
+                code.append(&bytecode! {
+                    CALLER
+                    PUSH1(0)
+                    SSTORE
+                });
+            }
+            code.write_op(OpcodeId::STOP);
+            test_ok(caller(*opcode, stack, true), callee(code));
         }
     }
 
@@ -746,29 +962,31 @@ mod test {
             STOP
         };
         let callees = vec![callee(bytecode)];
-        for (stack, callee) in stacks.into_iter().cartesian_product(callees.into_iter()) {
-            test_oog(caller(stack, true), callee);
+        for ((opcode, stack), callee) in TEST_CALL_OPCODES
+            .iter()
+            .cartesian_product(stacks.into_iter())
+            .cartesian_product(callees.into_iter())
+        {
+            test_oog(caller(*opcode, stack, true), callee);
         }
     }
 
     #[test]
-    fn call_gadget_nested() {
-        let callers = vec![
-            caller(
-                Stack {
-                    gas: 100000,
-                    ..Default::default()
-                },
-                true,
-            ),
-            caller(
-                Stack {
-                    gas: 100000,
-                    ..Default::default()
-                },
-                false,
-            ),
-        ];
+    fn callop_gadget_nested() {
+        let callers = TEST_CALL_OPCODES
+            .iter()
+            .cartesian_product([true, false])
+            .map(|(opcode, caller_is_success)| {
+                caller(
+                    *opcode,
+                    Stack {
+                        gas: 100000,
+                        ..Default::default()
+                    },
+                    caller_is_success,
+                )
+            })
+            .collect_vec();
         let callees = vec![
             // Success
             callee(bytecode! { PUSH1(0) PUSH1(0) RETURN }),
@@ -782,7 +1000,7 @@ mod test {
     }
 
     #[test]
-    fn call_gadget_recursive() {
+    fn callop_gadget_recursive() {
         test_ok(
             Account {
                 address: Address::repeat_byte(0xfe),
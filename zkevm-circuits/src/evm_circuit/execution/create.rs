@@ -0,0 +1,675 @@
+use crate::{
+    evm_circuit::{
+        execution::ExecutionGadget,
+        param::{N_BYTES_ACCOUNT_ADDRESS, N_BYTES_GAS, N_BYTES_MEMORY_WORD_SIZE, N_BYTES_U64},
+        step::ExecutionState,
+        util::{
+            common_gadget::TransferGadget,
+            constraint_builder::{
+                ConstraintBuilder, ReversionInfo, StepStateTransition,
+                Transition::{Delta, To},
+            },
+            from_bytes,
+            math_gadget::{ByteSizeGadget, ConstantDivisionGadget, LtGadget},
+            memory_gadget::{MemoryAddressGadget, MemoryCopierGasGadget, MemoryExpansionGadget},
+            not, rlc, select, CachedRegion, Cell, RandomLinearCombination, Word,
+        },
+        witness::{Block, Call, ExecStep, Transaction},
+    },
+    table::{AccountFieldTag, CallContextFieldTag},
+    util::Expr,
+};
+use bus_mapping::{circuit_input_builder::CopyDataType, evm::OpcodeId};
+use eth_types::{evm_types::GasCost, Field, ToBigEndian, ToLittleEndian, U256};
+use ethers_core::utils::rlp;
+use halo2_proofs::{circuit::Value, plonk::Error};
+use keccak256::plain::Keccak;
+
+/// Gadget for CREATE and CREATE2 opcodes.
+///
+/// The new address is verified with a lookup to the keccak table, where the
+/// preimage is `rlp([caller_address, caller_nonce])` for CREATE and
+/// `0xff ++ caller_address ++ salt ++ keccak(init_code)` for CREATE2.
+#[derive(Clone, Debug)]
+pub(crate) struct CreateGadget<F, const IS_CREATE2: bool, const S: ExecutionState> {
+    opcode: Cell<F>,
+    tx_id: Cell<F>,
+    reversion_info: ReversionInfo<F>,
+    caller_address: RandomLinearCombination<F, N_BYTES_ACCOUNT_ADDRESS>,
+    is_static: Cell<F>,
+    depth: Cell<F>,
+    value: Word<F>,
+    salt: Word<F>,
+    is_success: Cell<F>,
+    init_code: MemoryAddressGadget<F>,
+    init_code_rlc: Cell<F>,
+    code_hash: Word<F>,
+    new_address_hash: Word<F>,
+    is_warm_prev: Cell<F>,
+    caller_nonce: RandomLinearCombination<F, N_BYTES_U64>,
+    nonce_lt_128: LtGadget<F, N_BYTES_U64>,
+    nonce_byte_size: ByteSizeGadget<F>,
+    callee_reversion_info: ReversionInfo<F>,
+    transfer: TransferGadget<F>,
+    memory_expansion: MemoryExpansionGadget<F, 1, N_BYTES_MEMORY_WORD_SIZE>,
+    memory_copier_gas: MemoryCopierGasGadget<F, { GasCost::COPY_SHA3 }>,
+    one_64th_gas: ConstantDivisionGadget<F, N_BYTES_GAS>,
+}
+
+impl<F: Field, const IS_CREATE2: bool, const S: ExecutionState> ExecutionGadget<F>
+    for CreateGadget<F, IS_CREATE2, S>
+{
+    const NAME: &'static str = if IS_CREATE2 { "CREATE2" } else { "CREATE" };
+
+    const EXECUTION_STATE: ExecutionState = S;
+
+    fn configure(cb: &mut ConstraintBuilder<F>) -> Self {
+        let opcode = cb.query_cell();
+        cb.opcode_lookup(opcode.expr(), 1.expr());
+
+        // We do the responsible opcode check explicitly here because we're not using
+        // the `SameContextGadget` for `CREATE` and `CREATE2`.
+        cb.require_equal(
+            "Opcode should be CREATE or CREATE2",
+            opcode.expr(),
+            if IS_CREATE2 {
+                OpcodeId::CREATE2
+            } else {
+                OpcodeId::CREATE
+            }
+            .expr(),
+        );
+
+        let value = cb.query_word();
+        let init_code_offset = cb.query_cell();
+        let init_code_length = cb.query_rlc();
+        let salt = cb.query_word();
+        let is_success = cb.query_bool();
+        let new_address_hash = cb.query_word();
+
+        // Use rw_counter of the step which triggers next call as its call_id.
+        let callee_call_id = cb.curr.state.rw_counter.clone();
+
+        let tx_id = cb.call_context(None, CallContextFieldTag::TxId);
+        let mut reversion_info = cb.reversion_info_read(None);
+        let caller_address = cb.query_rlc();
+        let caller_address_expr = from_bytes::expr(&caller_address.cells);
+        cb.call_context_lookup(
+            false.expr(),
+            None,
+            CallContextFieldTag::CalleeAddress,
+            caller_address_expr.clone(),
+        );
+        let [is_static, depth] = [CallContextFieldTag::IsStatic, CallContextFieldTag::Depth]
+            .map(|field_tag| cb.call_context(None, field_tag));
+
+        cb.range_lookup(depth.expr(), 1024);
+        cb.require_zero(
+            "CREATE and CREATE2 must not be in static call stack",
+            is_static.expr(),
+        );
+
+        // The address of the created account is the lower 20 bytes of the
+        // hash, which is pushed on the stack only when the creation succeeds.
+        let new_address_rlc = rlc::expr(
+            &new_address_hash.cells[..N_BYTES_ACCOUNT_ADDRESS]
+                .iter()
+                .map(|cell| cell.expr())
+                .collect::<Vec<_>>(),
+            cb.power_of_randomness(),
+        );
+        let new_address = from_bytes::expr(&new_address_hash.cells[..N_BYTES_ACCOUNT_ADDRESS]);
+
+        // Lookup values from stack
+        cb.stack_pop(value.expr());
+        cb.stack_pop(init_code_offset.expr());
+        cb.stack_pop(init_code_length.expr());
+        if IS_CREATE2 {
+            cb.stack_pop(salt.expr());
+        }
+        cb.stack_push(is_success.expr() * new_address_rlc);
+
+        // Read the init code from memory and verify its hash, which is the code
+        // hash of the callee.
+        let init_code = MemoryAddressGadget::construct(cb, init_code_offset, init_code_length);
+        let init_code_rlc = cb.query_cell();
+        cb.condition(init_code.has_length(), |cb| {
+            cb.copy_table_lookup(
+                cb.curr.state.call_id.expr(),
+                CopyDataType::Memory.expr(),
+                cb.curr.state.call_id.expr(),
+                CopyDataType::RlcAcc.expr(),
+                init_code.offset(),
+                init_code.address(),
+                0.expr(), // dst_addr for CopyDataType::RlcAcc is 0.
+                init_code.length(),
+                init_code_rlc.expr(),
+                init_code.length(),
+            );
+        });
+        cb.condition(not::expr(init_code.has_length()), |cb| {
+            cb.require_zero(
+                "init_code_rlc == 0 for empty init code",
+                init_code_rlc.expr(),
+            );
+        });
+        let code_hash = cb.query_word();
+        cb.keccak_table_lookup(init_code_rlc.expr(), init_code.length(), code_hash.expr());
+
+        // Add the new address to access list
+        let is_warm_prev = cb.query_bool();
+        cb.account_access_list_write(
+            tx_id.expr(),
+            new_address.clone(),
+            1.expr(),
+            is_warm_prev.expr(),
+            Some(&mut reversion_info),
+        );
+
+        // Increase caller's nonce
+        let caller_nonce = cb.query_rlc();
+        let caller_nonce_expr = from_bytes::expr(&caller_nonce.cells);
+        cb.account_write(
+            caller_address_expr.clone(),
+            AccountFieldTag::Nonce,
+            caller_nonce_expr.clone() + 1.expr(),
+            caller_nonce_expr.clone(),
+            Some(&mut reversion_info),
+        );
+
+        // Verify the new address, the hash preimage is RLP encoded for CREATE.
+        let nonce_lt_128 = LtGadget::construct(cb, caller_nonce_expr, 128.expr());
+        let nonce_byte_size = ByteSizeGadget::construct(
+            cb,
+            std::array::from_fn(|idx| {
+                caller_nonce
+                    .cells
+                    .get(idx)
+                    .map_or(0.expr(), |cell| cell.expr())
+            }),
+        );
+        let power_of_randomness = cb.power_of_randomness().to_vec();
+        let (preimage_rlc, preimage_length) = if IS_CREATE2 {
+            // 0xff ++ caller_address ++ salt ++ keccak(init_code)
+            let r_32 = power_of_randomness[30].clone() * power_of_randomness[0].clone();
+            let r_64 = r_32.clone() * r_32.clone();
+            (
+                0xff.expr() * r_64.clone() * power_of_randomness[19].clone()
+                    + caller_address.expr() * r_64
+                    + salt.expr() * r_32
+                    + code_hash.expr(),
+                85.expr(),
+            )
+        } else {
+            // A nonce less than 128 and greater than 0 is encoded as a single
+            // byte, otherwise it's encoded as `0x80 + byte_size` followed by
+            // the big-endian bytes.
+            let nonce_byte_size_expr = nonce_byte_size.byte_size();
+            let is_single_byte = nonce_lt_128.expr() * nonce_byte_size_expr.clone();
+            let nonce_rlp_rlc = caller_nonce.expr()
+                + not::expr(is_single_byte.clone())
+                    * (0x80.expr() + nonce_byte_size_expr.clone())
+                    * nonce_byte_size.randomness_power(&power_of_randomness);
+            let nonce_rlp_length =
+                1.expr() + not::expr(is_single_byte.clone()) * nonce_byte_size_expr;
+            let nonce_rlp_randomness_power = select::expr(
+                is_single_byte,
+                power_of_randomness[0].clone(),
+                power_of_randomness[0].clone()
+                    * nonce_byte_size.randomness_power(&power_of_randomness),
+            );
+            // 0xc0 + list_length ++ 0x94 ++ caller_address ++ rlp(nonce)
+            (
+                ((0xc0.expr() + 21.expr() + nonce_rlp_length.clone())
+                    * power_of_randomness[20].clone()
+                    + 0x94.expr() * power_of_randomness[19].clone()
+                    + caller_address.expr())
+                    * nonce_rlp_randomness_power
+                    + nonce_rlp_rlc,
+                22.expr() + nonce_rlp_length,
+            )
+        };
+        cb.keccak_table_lookup(preimage_rlc, preimage_length, new_address_hash.expr());
+
+        // Propagate rw_counter_end_of_reversion and is_persistent
+        let mut callee_reversion_info = cb.reversion_info_write(Some(callee_call_id.expr()));
+        cb.require_equal(
+            "callee_is_persistent == is_persistent ⋅ is_success",
+            callee_reversion_info.is_persistent(),
+            reversion_info.is_persistent() * is_success.expr(),
+        );
+        cb.condition(is_success.expr() * (1.expr() - reversion_info.is_persistent()), |cb| {
+            cb.require_equal(
+                "callee_rw_counter_end_of_reversion == rw_counter_end_of_reversion - (reversible_write_counter + 1)",
+                callee_reversion_info.rw_counter_end_of_reversion(),
+                reversion_info.rw_counter_of_reversion(),
+            );
+        });
+
+        // Increase callee's nonce and transfer value
+        cb.account_write(
+            new_address.clone(),
+            AccountFieldTag::Nonce,
+            1.expr(),
+            0.expr(),
+            Some(&mut callee_reversion_info),
+        );
+        let transfer = TransferGadget::construct(
+            cb,
+            caller_address_expr.clone(),
+            new_address.clone(),
+            value.clone(),
+            &mut callee_reversion_info,
+        );
+
+        // Sum up gas cost, CREATE2 also pays for hashing the init code
+        let memory_expansion = MemoryExpansionGadget::construct(
+            cb,
+            cb.curr.state.memory_word_size.expr(),
+            [init_code.address()],
+        );
+        let memory_copier_gas =
+            MemoryCopierGasGadget::construct(cb, init_code.length(), memory_expansion.gas_cost());
+        let gas_cost = GasCost::CREATE.expr()
+            + if IS_CREATE2 {
+                memory_copier_gas.gas_cost()
+            } else {
+                memory_expansion.gas_cost()
+            };
+
+        // Apply EIP 150
+        let gas_available = cb.curr.state.gas_left.expr() - gas_cost.clone();
+        let one_64th_gas = ConstantDivisionGadget::construct(cb, gas_available.clone(), 64);
+        let callee_gas_left = gas_available - one_64th_gas.quotient();
+
+        let stack_pointer_delta = if IS_CREATE2 { 3 } else { 2 };
+
+        cb.condition(not::expr(init_code.has_length()), |cb| {
+            // Save caller's call state
+            for field_tag in [
+                CallContextFieldTag::LastCalleeId,
+                CallContextFieldTag::LastCalleeReturnDataOffset,
+                CallContextFieldTag::LastCalleeReturnDataLength,
+            ] {
+                cb.call_context_lookup(true.expr(), None, field_tag, 0.expr());
+            }
+
+            cb.require_step_state_transition(StepStateTransition {
+                rw_counter: Delta(cb.rw_counter_offset()),
+                program_counter: Delta(1.expr()),
+                stack_pointer: Delta(stack_pointer_delta.expr()),
+                gas_left: Delta(-gas_cost.clone()),
+                memory_word_size: To(memory_expansion.next_memory_word_size()),
+                reversible_write_counter: Delta(2.expr() + is_success.expr() * 3.expr()),
+                ..StepStateTransition::default()
+            });
+        });
+
+        cb.condition(init_code.has_length(), |cb| {
+            // Save caller's call state
+            for (field_tag, value) in [
+                (
+                    CallContextFieldTag::ProgramCounter,
+                    cb.curr.state.program_counter.expr() + 1.expr(),
+                ),
+                (
+                    CallContextFieldTag::StackPointer,
+                    cb.curr.state.stack_pointer.expr() + stack_pointer_delta.expr(),
+                ),
+                (
+                    CallContextFieldTag::GasLeft,
+                    cb.curr.state.gas_left.expr() - gas_cost - callee_gas_left.clone(),
+                ),
+                (
+                    CallContextFieldTag::MemorySize,
+                    memory_expansion.next_memory_word_size(),
+                ),
+                (
+                    CallContextFieldTag::ReversibleWriteCounter,
+                    cb.curr.state.reversible_write_counter.expr() + 2.expr(),
+                ),
+            ] {
+                cb.call_context_lookup(true.expr(), None, field_tag, value);
+            }
+
+            // Setup next call's context.
+            for (field_tag, value) in [
+                (CallContextFieldTag::CallerId, cb.curr.state.call_id.expr()),
+                (CallContextFieldTag::TxId, tx_id.expr()),
+                (CallContextFieldTag::Depth, depth.expr() + 1.expr()),
+                (CallContextFieldTag::CallerAddress, caller_address_expr),
+                (CallContextFieldTag::CalleeAddress, new_address),
+                (CallContextFieldTag::CallDataOffset, 0.expr()),
+                (CallContextFieldTag::CallDataLength, 0.expr()),
+                (CallContextFieldTag::ReturnDataOffset, 0.expr()),
+                (CallContextFieldTag::ReturnDataLength, 0.expr()),
+                (CallContextFieldTag::Value, value.expr()),
+                (CallContextFieldTag::IsSuccess, is_success.expr()),
+                (CallContextFieldTag::IsStatic, is_static.expr()),
+                (CallContextFieldTag::LastCalleeId, 0.expr()),
+                (CallContextFieldTag::LastCalleeReturnDataOffset, 0.expr()),
+                (CallContextFieldTag::LastCalleeReturnDataLength, 0.expr()),
+                (CallContextFieldTag::IsRoot, 0.expr()),
+                (CallContextFieldTag::IsCreate, 1.expr()),
+                (CallContextFieldTag::CodeHash, code_hash.expr()),
+            ] {
+                cb.call_context_lookup(true.expr(), Some(callee_call_id.expr()), field_tag, value);
+            }
+
+            cb.require_step_state_transition(StepStateTransition {
+                rw_counter: Delta(cb.rw_counter_offset()),
+                call_id: To(callee_call_id.expr()),
+                is_root: To(false.expr()),
+                is_create: To(true.expr()),
+                code_hash: To(code_hash.expr()),
+                gas_left: To(callee_gas_left),
+                reversible_write_counter: To(3.expr()),
+                ..StepStateTransition::new_context()
+            });
+        });
+
+        Self {
+            opcode,
+            tx_id,
+            reversion_info,
+            caller_address,
+            is_static,
+            depth,
+            value,
+            salt,
+            is_success,
+            init_code,
+            init_code_rlc,
+            code_hash,
+            new_address_hash,
+            is_warm_prev,
+            caller_nonce,
+            nonce_lt_128,
+            nonce_byte_size,
+            callee_reversion_info,
+            transfer,
+            memory_expansion,
+            memory_copier_gas,
+            one_64th_gas,
+        }
+    }
+
+    fn assign_exec_step(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        block: &Block<F>,
+        tx: &Transaction,
+        call: &Call,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        let n_pop = if IS_CREATE2 { 4 } else { 3 };
+        let callee = tx
+            .calls
+            .iter()
+            .find(|callee| callee.id == step.rw_counter)
+            .expect("callee of CREATE or CREATE2 not found");
+
+        let [tx_id, caller_address, is_static, depth] = [0, 3, 4, 5]
+            .map(|idx| step.rw_indices[idx])
+            .map(|idx| block.rws[idx].call_context_value());
+        let [value, init_code_offset, init_code_length] = [6, 7, 8]
+            .map(|idx| step.rw_indices[idx])
+            .map(|idx| block.rws[idx].stack_value());
+        let salt = if IS_CREATE2 {
+            block.rws[step.rw_indices[9]].stack_value()
+        } else {
+            U256::zero()
+        };
+
+        let opcode = step.opcode.unwrap();
+        self.opcode
+            .assign(region, offset, Value::known(F::from(opcode.as_u64())))?;
+
+        self.tx_id
+            .assign(region, offset, Value::known(F::from(tx_id.low_u64())))?;
+        self.reversion_info.assign(
+            region,
+            offset,
+            call.rw_counter_end_of_reversion,
+            call.is_persistent,
+        )?;
+        self.caller_address.assign(
+            region,
+            offset,
+            Some(
+                caller_address.to_le_bytes()[..N_BYTES_ACCOUNT_ADDRESS]
+                    .try_into()
+                    .unwrap(),
+            ),
+        )?;
+        self.is_static
+            .assign(region, offset, Value::known(F::from(is_static.low_u64())))?;
+        self.depth
+            .assign(region, offset, Value::known(F::from(depth.low_u64())))?;
+
+        self.value
+            .assign(region, offset, Some(value.to_le_bytes()))?;
+        self.salt.assign(region, offset, Some(salt.to_le_bytes()))?;
+        self.is_success.assign(
+            region,
+            offset,
+            Value::known(F::from(callee.is_success as u64)),
+        )?;
+
+        let init_code_address = self.init_code.assign(
+            region,
+            offset,
+            init_code_offset,
+            init_code_length,
+            block.randomness,
+        )?;
+        let init_code_length = init_code_length.as_usize();
+        let init_code: Vec<u8> = (7 + n_pop..7 + n_pop + init_code_length)
+            .map(|idx| block.rws[step.rw_indices[idx]].memory_value())
+            .collect();
+        self.init_code_rlc.assign(
+            region,
+            offset,
+            Value::known(rlc::value(init_code.iter().rev(), block.randomness)),
+        )?;
+        self.code_hash
+            .assign(region, offset, Some(callee.code_hash.to_le_bytes()))?;
+
+        let rw_offset = 7 + n_pop + init_code_length;
+        let (_, is_warm_prev) = block.rws[step.rw_indices[rw_offset]].tx_access_list_value_pair();
+        self.is_warm_prev
+            .assign(region, offset, Value::known(F::from(is_warm_prev as u64)))?;
+
+        let (_, caller_nonce) = block.rws[step.rw_indices[rw_offset + 1]].account_value_pair();
+        self.caller_nonce.assign(
+            region,
+            offset,
+            Some(
+                caller_nonce.to_le_bytes()[..N_BYTES_U64]
+                    .try_into()
+                    .unwrap(),
+            ),
+        )?;
+        self.nonce_lt_128.assign(
+            region,
+            offset,
+            F::from(caller_nonce.low_u64()),
+            F::from(128),
+        )?;
+        self.nonce_byte_size.assign(region, offset, caller_nonce)?;
+
+        let preimage = if IS_CREATE2 {
+            let mut keccak = Keccak::default();
+            keccak.update(&init_code);
+            [
+                &[0xff],
+                callee.caller_address.as_bytes(),
+                &salt.to_be_bytes(),
+                keccak.digest().as_slice(),
+            ]
+            .concat()
+        } else {
+            let mut stream = rlp::RlpStream::new();
+            stream.begin_list(2);
+            stream.append(&callee.caller_address);
+            stream.append(&caller_nonce);
+            stream.out().to_vec()
+        };
+        let mut keccak = Keccak::default();
+        keccak.update(&preimage);
+        self.new_address_hash.assign(
+            region,
+            offset,
+            Some(U256::from_big_endian(&keccak.digest()).to_le_bytes()),
+        )?;
+
+        let [callee_rw_counter_end_of_reversion, callee_is_persistent] =
+            [rw_offset + 2, rw_offset + 3]
+                .map(|idx| block.rws[step.rw_indices[idx]].call_context_value());
+        self.callee_reversion_info.assign(
+            region,
+            offset,
+            callee_rw_counter_end_of_reversion.low_u64() as usize,
+            callee_is_persistent.low_u64() != 0,
+        )?;
+
+        let [caller_balance_pair, callee_balance_pair] = [rw_offset + 5, rw_offset + 6]
+            .map(|idx| block.rws[step.rw_indices[idx]].account_value_pair());
+        self.transfer.assign(
+            region,
+            offset,
+            caller_balance_pair,
+            callee_balance_pair,
+            value,
+        )?;
+
+        let (_, memory_expansion_gas_cost) = self.memory_expansion.assign(
+            region,
+            offset,
+            step.memory_word_size(),
+            [init_code_address],
+        )?;
+        let memory_copier_gas_cost = self.memory_copier_gas.assign(
+            region,
+            offset,
+            init_code_length as u64,
+            memory_expansion_gas_cost,
+        )?;
+        let gas_cost = GasCost::CREATE.as_u64()
+            + if IS_CREATE2 {
+                memory_copier_gas_cost
+            } else {
+                memory_expansion_gas_cost
+            };
+        let gas_available = step.gas_left - gas_cost;
+        self.one_64th_gas
+            .assign(region, offset, gas_available as u128)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_util::run_test_circuits;
+    use eth_types::{
+        address, bytecode, bytecode::Bytecode, evm_types::OpcodeId, geth_types::Account, Word,
+    };
+    use mock::{eth, TestContext};
+
+    // RETURN the first `length` bytes of the memory, which are set by the
+    // caller with `MSTORE`.
+    fn initialization_bytecode(deployed_code: &[u8]) -> Bytecode {
+        let mut deployed_code_word = [0u8; 32];
+        deployed_code_word[..deployed_code.len()].copy_from_slice(deployed_code);
+        bytecode! {
+            PUSH32(Word::from_big_endian(&deployed_code_word))
+            PUSH1(0)
+            MSTORE
+            PUSH1(deployed_code.len())
+            PUSH1(0)
+            RETURN
+        }
+    }
+
+    fn creator_bytecode(init_code: &[u8], salt: Option<Word>) -> Bytecode {
+        let mut code = Bytecode::default();
+        // Store the init code in memory by chunks of 32 bytes.
+        for (idx, chunk) in init_code.chunks(32).enumerate() {
+            let mut word = [0u8; 32];
+            word[..chunk.len()].copy_from_slice(chunk);
+            code.push(32, Word::from_big_endian(&word));
+            code.push(32, Word::from(idx * 32));
+            code.write_op(OpcodeId::MSTORE);
+        }
+        if let Some(salt) = salt {
+            code.push(32, salt);
+        }
+        code.push(32, Word::from(init_code.len()));
+        code.push(1, Word::zero());
+        code.push(32, Word::from(0x10));
+        code.write_op(if salt.is_some() {
+            OpcodeId::CREATE2
+        } else {
+            OpcodeId::CREATE
+        });
+        code.write_op(OpcodeId::STOP);
+        code
+    }
+
+    fn test_ok(init_code: Vec<u8>, salt: Option<Word>) {
+        let creator = Account {
+            address: address!("0x00000000000000000000000000000000000cafe1"),
+            balance: eth(10),
+            nonce: Word::one(),
+            code: creator_bytecode(&init_code, salt).into(),
+            ..Default::default()
+        };
+
+        let ctx = TestContext::<2, 1>::new(
+            None,
+            |accs| {
+                accs[0].account(&creator);
+                accs[1]
+                    .address(address!("0x000000000000000000000000000000000000cafe"))
+                    .balance(eth(10));
+            },
+            |mut txs, accs| {
+                txs[0].from(accs[1].address).to(accs[0].address);
+            },
+            |block, _| block,
+        )
+        .unwrap();
+
+        assert_eq!(run_test_circuits(ctx, None), Ok(()));
+    }
+
+    #[test]
+    fn create_gadget_simple() {
+        for deployed_code in [vec![], vec![OpcodeId::STOP.as_u8()], vec![0x60, 0x00]] {
+            test_ok(initialization_bytecode(&deployed_code).to_vec(), None);
+            test_ok(
+                initialization_bytecode(&deployed_code).to_vec(),
+                Some(Word::from(0xcafe)),
+            );
+        }
+    }
+
+    #[test]
+    fn create_gadget_empty_init_code() {
+        test_ok(vec![], None);
+        test_ok(vec![], Some(Word::from(0xcafe)));
+    }
+
+    #[test]
+    fn create_gadget_revert() {
+        let init_code = bytecode! {
+            PUSH1(0)
+            PUSH1(0)
+            REVERT
+        };
+        test_ok(init_code.to_vec(), None);
+        test_ok(init_code.to_vec(), Some(Word::from(0xcafe)));
+    }
+}
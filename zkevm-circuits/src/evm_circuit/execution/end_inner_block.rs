@@ -0,0 +1,175 @@
+use crate::{
+    evm_circuit::{
+        execution::ExecutionGadget,
+        step::ExecutionState,
+        util::{
+            constraint_builder::{
+                ConstraintBuilder, StepStateTransition,
+                Transition::{Delta, Same},
+            },
+            CachedRegion, Cell,
+        },
+        witness::{Block, Call, ExecStep, Transaction},
+    },
+    table::{BlockContextFieldTag, CallContextFieldTag},
+    util::Expr,
+};
+use eth_types::Field;
+use halo2_proofs::{circuit::Value, plonk::Error};
+
+/// Gadget for the boundary between two consecutive blocks proven together,
+/// which follows the last transaction of each block except the last one.  The
+/// block number of the following steps is advanced by the state machine
+/// transition.
+#[derive(Clone, Debug)]
+pub(crate) struct EndInnerBlockGadget<F> {
+    cum_num_txs: Cell<F>,
+}
+
+impl<F: Field> ExecutionGadget<F> for EndInnerBlockGadget<F> {
+    const NAME: &'static str = "EndInnerBlock";
+
+    const EXECUTION_STATE: ExecutionState = ExecutionState::EndInnerBlock;
+
+    fn configure(cb: &mut ConstraintBuilder<F>) -> Self {
+        // The last tx of the block is checked by the end_tx gadget
+        let cum_num_txs = cb.query_cell();
+        cb.block_lookup(
+            BlockContextFieldTag::CumNumTxs.expr(),
+            cb.curr.state.block_number.expr(),
+            cum_num_txs.expr(),
+        );
+
+        // When the boundary is the first step, no tx precedes it
+        cb.add_constraint_first_step(
+            "rw_counter is initialized to be 1",
+            1.expr() - cb.curr.state.rw_counter.expr(),
+        );
+        cb.add_constraint_first_step(
+            "blocks before the first boundary are empty",
+            cum_num_txs.expr(),
+        );
+
+        // The first tx of the next block follows the txs of the previous
+        // blocks
+        cb.condition(
+            cb.next.execution_state_selector([ExecutionState::BeginTx]),
+            |cb| {
+                cb.call_context_lookup(
+                    false.expr(),
+                    Some(cb.next.state.rw_counter.expr()),
+                    CallContextFieldTag::TxId,
+                    cum_num_txs.expr() + 1.expr(),
+                );
+
+                cb.require_step_state_transition(StepStateTransition {
+                    rw_counter: Delta(1.expr()),
+                    ..StepStateTransition::any()
+                });
+            },
+        );
+
+        // Otherwise the next block is empty
+        cb.condition(
+            cb.next.execution_state_selector([
+                ExecutionState::EndInnerBlock,
+                ExecutionState::EndBlock,
+            ]),
+            |cb| {
+                cb.block_lookup(
+                    BlockContextFieldTag::NumTxs.expr(),
+                    cb.curr.state.block_number.expr() + 1.expr(),
+                    0.expr(),
+                );
+
+                cb.require_step_state_transition(StepStateTransition {
+                    rw_counter: Same,
+                    ..StepStateTransition::any()
+                });
+            },
+        );
+
+        Self { cum_num_txs }
+    }
+
+    fn assign_exec_step(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        block: &Block<F>,
+        _: &Transaction,
+        _: &Call,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        let context = &block.context.ctxs[&step.block_number];
+        self.cum_num_txs.assign(
+            region,
+            offset,
+            Value::known(F::from(context.cum_num_txs as u64)),
+        )?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::evm_circuit::{test::run_test_circuit, witness::block_convert};
+    use bus_mapping::mock::BlockData;
+    use eth_types::{geth_types::GethData, Word, H256};
+    use mock::{eth, test_ctx::TestContext, MOCK_ACCOUNTS};
+
+    /// Block with `NTX` transfers from the sender with the given nonce.
+    fn transfer_block<const NTX: usize>(number: u64, nonce: u64) -> GethData {
+        TestContext::<2, NTX>::new(
+            None,
+            |accs| {
+                accs[0].address(MOCK_ACCOUNTS[0]).balance(eth(10));
+                accs[1]
+                    .address(MOCK_ACCOUNTS[1])
+                    .balance(eth(10))
+                    .nonce(Word::from(nonce));
+            },
+            |txs, accs| {
+                for (idx, tx) in txs.into_iter().enumerate() {
+                    tx.from(accs[1].address)
+                        .to(accs[0].address)
+                        .nonce(Word::from(nonce + idx as u64));
+                }
+            },
+            |block, _tx| {
+                block
+                    .number(number)
+                    .parent_hash(H256::from_low_u64_be(number - 1))
+            },
+        )
+        .unwrap()
+        .into()
+    }
+
+    #[test]
+    fn end_inner_block_multiple_blocks() {
+        // The second block is empty, and the cumulative gas used is reset in
+        // the third one.
+        let blocks = [
+            transfer_block::<1>(0xcafe, 0),
+            transfer_block::<0>(0xcaff, 1),
+            transfer_block::<2>(0xcb00, 1),
+        ];
+
+        let mut builder =
+            BlockData::new_from_geth_data(blocks[0].clone()).new_circuit_input_builder();
+        builder
+            .handle_blocks(
+                &blocks
+                    .iter()
+                    .map(|block| (block.eth_block.clone(), block.geth_traces.clone()))
+                    .collect::<Vec<_>>(),
+            )
+            .unwrap();
+        let block = block_convert(&builder.block, &builder.code_db).unwrap();
+        assert_eq!(block.context.ctxs.len(), 3);
+
+        assert_eq!(run_test_circuit(block), Ok(()));
+    }
+}
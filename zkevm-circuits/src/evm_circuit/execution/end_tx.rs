@@ -38,7 +38,10 @@ pub(crate) struct EndTxGadget<F> {
     coinbase: Cell<F>,
     coinbase_reward: UpdateBalanceGadget<F, 2, true>,
     current_cumulative_gas_used: Cell<F>,
+    num_txs: Cell<F>,
+    cum_num_txs: Cell<F>,
     is_first_tx: IsEqualGadget<F>,
+    is_first_tx_in_block: IsEqualGadget<F>,
     is_persistent: Cell<F>,
 }
 
@@ -87,7 +90,7 @@ impl<F: Field> ExecutionGadget<F> for EndTxGadget<F> {
             (BlockContextFieldTag::Coinbase, coinbase.expr()),
             (BlockContextFieldTag::BaseFee, base_fee.expr()),
         ] {
-            cb.block_lookup(tag.expr(), None, value);
+            cb.block_lookup(tag.expr(), cb.curr.state.block_number.expr(), value);
         }
         let effective_tip = cb.query_word();
         let sub_gas_price_by_base_fee =
@@ -115,17 +118,39 @@ impl<F: Field> ExecutionGadget<F> for EndTxGadget<F> {
             cb.curr.state.log_id.expr(),
         );
 
+        // The txs of the block follow the ones of the previous blocks
+        let num_txs = cb.query_cell();
+        let cum_num_txs = cb.query_cell();
+        for (tag, value) in [
+            (BlockContextFieldTag::NumTxs, num_txs.expr()),
+            (BlockContextFieldTag::CumNumTxs, cum_num_txs.expr()),
+        ] {
+            cb.block_lookup(tag.expr(), cb.curr.state.block_number.expr(), value);
+        }
         let is_first_tx = IsEqualGadget::construct(cb, tx_id.expr(), 1.expr());
+        let is_first_tx_in_block = IsEqualGadget::construct(
+            cb,
+            tx_id.expr(),
+            cum_num_txs.expr() - num_txs.expr() + 1.expr(),
+        );
+        cb.condition(is_first_tx.expr(), |cb| {
+            cb.require_equal(
+                "first tx is the first tx of its block",
+                is_first_tx_in_block.expr(),
+                1.expr(),
+            );
+        });
 
+        // The cumulative gas used is reset at the start of each block
         let current_cumulative_gas_used = cb.query_cell();
-        cb.condition(is_first_tx.expr(), |cb| {
+        cb.condition(is_first_tx_in_block.expr(), |cb| {
             cb.require_zero(
-                "current_cumulative_gas_used is zero when tx is first tx",
+                "current_cumulative_gas_used is zero when tx is the first tx of its block",
                 current_cumulative_gas_used.expr(),
             );
         });
 
-        cb.condition(1.expr() - is_first_tx.expr(), |cb| {
+        cb.condition(1.expr() - is_first_tx_in_block.expr(), |cb| {
             cb.tx_receipt_lookup(
                 0.expr(),
                 tx_id.expr() - 1.expr(),
@@ -152,17 +177,26 @@ impl<F: Field> ExecutionGadget<F> for EndTxGadget<F> {
                 );
 
                 cb.require_step_state_transition(StepStateTransition {
-                    rw_counter: Delta(10.expr() - is_first_tx.expr()),
+                    rw_counter: Delta(10.expr() - is_first_tx_in_block.expr()),
                     ..StepStateTransition::any()
                 });
             },
         );
 
         cb.condition(
-            cb.next.execution_state_selector([ExecutionState::EndBlock]),
+            cb.next.execution_state_selector([
+                ExecutionState::EndInnerBlock,
+                ExecutionState::EndBlock,
+            ]),
             |cb| {
+                cb.require_equal(
+                    "tx is the last tx of its block",
+                    tx_id.expr(),
+                    cum_num_txs.expr(),
+                );
+
                 cb.require_step_state_transition(StepStateTransition {
-                    rw_counter: Delta(9.expr() - is_first_tx.expr()),
+                    rw_counter: Delta(9.expr() - is_first_tx_in_block.expr()),
                     ..StepStateTransition::any()
                 });
             },
@@ -182,7 +216,10 @@ impl<F: Field> ExecutionGadget<F> for EndTxGadget<F> {
             coinbase,
             coinbase_reward,
             current_cumulative_gas_used,
+            num_txs,
+            cum_num_txs,
             is_first_tx,
+            is_first_tx_in_block,
             is_persistent,
         }
     }
@@ -239,11 +276,12 @@ impl<F: Field> ExecutionGadget<F> for EndTxGadget<F> {
             vec![gas_fee_refund],
             caller_balance,
         )?;
-        let effective_tip = tx.gas_price - block.context.base_fee;
+        let context = &block.context.ctxs[&step.block_number];
+        let effective_tip = tx.gas_price - context.base_fee;
         self.sub_gas_price_by_base_fee.assign(
             region,
             offset,
-            [effective_tip, block.context.base_fee],
+            [effective_tip, context.base_fee],
             tx.gas_price,
         )?;
         self.mul_effective_tip_by_gas_used.assign(
@@ -257,8 +295,7 @@ impl<F: Field> ExecutionGadget<F> for EndTxGadget<F> {
             region,
             offset,
             Value::known(
-                block
-                    .context
+                context
                     .coinbase
                     .to_scalar()
                     .expect("unexpected Address -> Scalar conversion failure"),
@@ -272,14 +309,23 @@ impl<F: Field> ExecutionGadget<F> for EndTxGadget<F> {
             coinbase_balance,
         )?;
 
-        let current_cumulative_gas_used: u64 = if tx.id == 1 {
+        let first_tx_in_block = context.cum_num_txs - context.num_txs + 1;
+        let current_cumulative_gas_used: u64 = if tx.id == first_tx_in_block {
             0
         } else {
-            // first transaction needs TxReceiptFieldTag::COUNT(3) lookups to tx receipt,
-            // while later transactions need 4 (with one extra cumulative gas read) lookups
+            // every transaction needs TxReceiptFieldTag::COUNT(3) lookups to tx receipt,
+            // while the ones which aren't the first of their block need an extra
+            // cumulative gas read, so the receipt of the previous transaction ends
+            // with its cumulative gas write
+            let num_first_txs = block
+                .context
+                .ctxs
+                .values()
+                .filter(|ctx| ctx.num_txs > 0 && ctx.cum_num_txs - ctx.num_txs < tx.id - 1)
+                .count();
             let rw = &block.rws[(
                 RwTableTag::TxReceipt,
-                (tx.id - 2) * (TxReceiptFieldTag::COUNT + 1) + 2,
+                (tx.id - 1) * (TxReceiptFieldTag::COUNT + 1) - num_first_txs - 1,
             )];
             rw.receipt_value()
         };
@@ -289,8 +335,24 @@ impl<F: Field> ExecutionGadget<F> for EndTxGadget<F> {
             offset,
             Value::known(F::from(current_cumulative_gas_used)),
         )?;
+        self.num_txs.assign(
+            region,
+            offset,
+            Value::known(F::from(context.num_txs as u64)),
+        )?;
+        self.cum_num_txs.assign(
+            region,
+            offset,
+            Value::known(F::from(context.cum_num_txs as u64)),
+        )?;
         self.is_first_tx
             .assign(region, offset, F::from(tx.id as u64), F::one())?;
+        self.is_first_tx_in_block.assign(
+            region,
+            offset,
+            F::from(tx.id as u64),
+            F::from(first_tx_in_block as u64),
+        )?;
         self.is_persistent.assign(
             region,
             offset,
@@ -313,7 +375,7 @@ mod test {
         builder
             .handle_block(&block_data.eth_block, &block_data.geth_traces)
             .unwrap();
-        let block = block_convert(&builder.block, &builder.code_db);
+        let block = block_convert(&builder.block, &builder.code_db).unwrap();
 
         assert_eq!(run_test_circuit(block), Ok(()));
     }
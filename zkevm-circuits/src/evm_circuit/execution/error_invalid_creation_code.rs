@@ -0,0 +1,177 @@
+use crate::{
+    evm_circuit::{
+        execution::ExecutionGadget,
+        step::ExecutionState,
+        util::{
+            common_gadget::CommonErrorGadget, constraint_builder::ConstraintBuilder,
+            memory_gadget::MemoryAddressGadget, CachedRegion, Cell,
+        },
+        witness::{Block, Call, ExecStep, Transaction},
+    },
+    util::Expr,
+};
+use bus_mapping::evm::OpcodeId;
+use eth_types::Field;
+use halo2_proofs::{circuit::Value, plonk::Error};
+
+/// Gadget for the RETURN of a creation which fails because the code to be
+/// deployed starts with the byte `0xef`, see
+/// [EIP-3541](https://eips.ethereum.org/EIPS/eip-3541).
+#[derive(Clone, Debug)]
+pub(crate) struct ErrorInvalidCreationCodeGadget<F> {
+    opcode: Cell<F>,
+    memory_address: MemoryAddressGadget<F>,
+    first_byte: Cell<F>,
+    common_error_gadget: CommonErrorGadget<F>,
+}
+
+impl<F: Field> ExecutionGadget<F> for ErrorInvalidCreationCodeGadget<F> {
+    const NAME: &'static str = "ErrorInvalidCreationCode";
+
+    const EXECUTION_STATE: ExecutionState = ExecutionState::ErrorInvalidCreationCode;
+
+    fn configure(cb: &mut ConstraintBuilder<F>) -> Self {
+        let opcode = cb.query_cell();
+        cb.require_equal(
+            "ErrorInvalidCreationCode opcode must be RETURN",
+            opcode.expr(),
+            OpcodeId::RETURN.expr(),
+        );
+        cb.require_equal(
+            "ErrorInvalidCreationCode only happens in a creation",
+            cb.curr.state.is_create.expr(),
+            1.expr(),
+        );
+
+        let offset = cb.query_cell();
+        let length = cb.query_rlc();
+        cb.stack_pop(offset.expr());
+        cb.stack_pop(length.expr());
+        let memory_address = MemoryAddressGadget::construct(cb, offset, length);
+        cb.require_equal(
+            "code to be deployed is not empty",
+            memory_address.has_length(),
+            1.expr(),
+        );
+
+        let first_byte = cb.query_cell();
+        cb.memory_lookup(
+            false.expr(),
+            memory_address.offset(),
+            first_byte.expr(),
+            None,
+        );
+        cb.require_equal(
+            "first byte of the code to be deployed is 0xef",
+            first_byte.expr(),
+            0xef.expr(),
+        );
+
+        let common_error_gadget = CommonErrorGadget::construct(cb, opcode.expr());
+
+        Self {
+            opcode,
+            memory_address,
+            first_byte,
+            common_error_gadget,
+        }
+    }
+
+    fn assign_exec_step(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        block: &Block<F>,
+        _tx: &Transaction,
+        call: &Call,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        let opcode = step.opcode.unwrap();
+        self.opcode
+            .assign(region, offset, Value::known(F::from(opcode.as_u64())))?;
+
+        let [memory_offset, length] = [0, 1]
+            .map(|idx| step.rw_indices[idx])
+            .map(|idx| block.rws[idx].stack_value());
+        self.memory_address
+            .assign(region, offset, memory_offset, length, block.randomness)?;
+        let first_byte = block.rws[step.rw_indices[2]].memory_value();
+        self.first_byte
+            .assign(region, offset, Value::known(F::from(first_byte as u64)))?;
+
+        self.common_error_gadget
+            .assign(region, offset, block, call, step, 3)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_util::run_test_circuits;
+    use eth_types::{
+        address, bytecode, bytecode::Bytecode, evm_types::OpcodeId, geth_types::Account, Word,
+    };
+    use mock::{eth, TestContext};
+
+    fn test_invalid_creation_code(deployed_code: &[u8]) {
+        // Init code which RETURNs the deployed code, stored at the beginning of
+        // the memory.
+        let mut deployed_code_word = [0u8; 32];
+        deployed_code_word[..deployed_code.len()].copy_from_slice(deployed_code);
+        let init_code = bytecode! {
+            PUSH32(Word::from_big_endian(&deployed_code_word))
+            PUSH1(0)
+            MSTORE
+            PUSH1(deployed_code.len())
+            PUSH1(0)
+            RETURN
+        }
+        .to_vec();
+
+        // Store the init code in memory by chunks of 32 bytes and create.
+        let mut code = Bytecode::default();
+        for (idx, chunk) in init_code.chunks(32).enumerate() {
+            let mut word = [0u8; 32];
+            word[..chunk.len()].copy_from_slice(chunk);
+            code.push(32, Word::from_big_endian(&word));
+            code.push(32, Word::from(idx * 32));
+            code.write_op(OpcodeId::MSTORE);
+        }
+        code.append(&bytecode! {
+            PUSH1(init_code.len()) // length
+            PUSH1(0) // offset
+            PUSH1(0) // value
+            CREATE
+            STOP
+        });
+        let creator = Account {
+            address: address!("0x00000000000000000000000000000000000cafe1"),
+            balance: eth(10),
+            nonce: Word::one(),
+            code: code.into(),
+            ..Default::default()
+        };
+
+        let ctx = TestContext::<2, 1>::new(
+            None,
+            |accs| {
+                accs[0].account(&creator);
+                accs[1]
+                    .address(address!("0x000000000000000000000000000000000000cafe"))
+                    .balance(eth(10));
+            },
+            |mut txs, accs| {
+                txs[0].from(accs[1].address).to(accs[0].address);
+            },
+            |block, _| block,
+        )
+        .unwrap();
+
+        assert_eq!(run_test_circuits(ctx, None), Ok(()));
+    }
+
+    #[test]
+    fn error_invalid_creation_code() {
+        test_invalid_creation_code(&[0xef]);
+        test_invalid_creation_code(&[0xef, 0x00, 0x01]);
+    }
+}
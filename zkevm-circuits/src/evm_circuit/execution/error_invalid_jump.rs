@@ -0,0 +1,294 @@
+use crate::{
+    evm_circuit::{
+        execution::ExecutionGadget,
+        param::N_BYTES_PROGRAM_COUNTER,
+        step::ExecutionState,
+        util::{
+            common_gadget::CommonErrorGadget,
+            constraint_builder::ConstraintBuilder,
+            from_bytes,
+            math_gadget::{IsEqualGadget, IsZeroGadget, LtGadget},
+            not, sum, CachedRegion, Cell, Word,
+        },
+        witness::{Block, Call, ExecStep, Transaction},
+    },
+    util::Expr,
+};
+use eth_types::{evm_types::OpcodeId, Field, ToLittleEndian, U256};
+use halo2_proofs::{circuit::Value, plonk::Error};
+
+/// Gadget for JUMP and JUMPI which fail because the destination is out of the
+/// code, or is not a JUMPDEST opcode (including a JUMPDEST byte in the data of
+/// a PUSH* operation).  JUMPI only fails when the condition is not zero.
+#[derive(Clone, Debug)]
+pub(crate) struct ErrorInvalidJumpGadget<F> {
+    opcode: Cell<F>,
+    is_jumpi: IsEqualGadget<F>,
+    destination: Word<F>,
+    condition: Cell<F>,
+    is_condition_zero: IsZeroGadget<F>,
+    code_length: Cell<F>,
+    destination_hi_is_zero: IsZeroGadget<F>,
+    destination_lt_code_length: LtGadget<F, N_BYTES_PROGRAM_COUNTER>,
+    value: Cell<F>,
+    is_code: Cell<F>,
+    is_jumpdest: IsEqualGadget<F>,
+    common_error_gadget: CommonErrorGadget<F>,
+}
+
+impl<F: Field> ExecutionGadget<F> for ErrorInvalidJumpGadget<F> {
+    const NAME: &'static str = "ErrorInvalidJump";
+
+    const EXECUTION_STATE: ExecutionState = ExecutionState::ErrorInvalidJump;
+
+    fn configure(cb: &mut ConstraintBuilder<F>) -> Self {
+        let opcode = cb.query_cell();
+        let is_jumpi = IsEqualGadget::construct(cb, opcode.expr(), OpcodeId::JUMPI.expr());
+        cb.condition(not::expr(is_jumpi.expr()), |cb| {
+            cb.require_equal(
+                "ErrorInvalidJump only happens in JUMP or JUMPI",
+                opcode.expr(),
+                OpcodeId::JUMP.expr(),
+            );
+        });
+
+        // Pop the destination, and the condition for JUMPI which must be
+        // non-zero to take the jump.
+        let destination = cb.query_word();
+        let condition = cb.query_cell();
+        cb.stack_pop(destination.expr());
+        let is_condition_zero = IsZeroGadget::construct(cb, condition.expr());
+        cb.condition(is_jumpi.expr(), |cb| {
+            cb.stack_pop(condition.expr());
+            cb.require_zero("JUMPI condition is not zero", is_condition_zero.expr());
+        });
+
+        // Determine if the destination is within the code.
+        let code_length = cb.bytecode_length(cb.curr.state.code_hash.expr());
+        let destination_hi_is_zero =
+            IsZeroGadget::construct(cb, sum::expr(&destination.cells[N_BYTES_PROGRAM_COUNTER..]));
+        let destination_lo = from_bytes::expr(&destination.cells[..N_BYTES_PROGRAM_COUNTER]);
+        let destination_lt_code_length =
+            LtGadget::construct(cb, destination_lo.clone(), code_length.expr());
+        let is_within_code = destination_hi_is_zero.expr() * destination_lt_code_length.expr();
+
+        // A destination within the code must not be a JUMPDEST opcode.
+        let value = cb.query_cell();
+        let is_code = cb.query_cell();
+        let is_jumpdest = IsEqualGadget::construct(cb, value.expr(), OpcodeId::JUMPDEST.expr());
+        cb.condition(is_within_code, |cb| {
+            cb.bytecode_lookup(
+                cb.curr.state.code_hash.expr(),
+                destination_lo,
+                is_code.expr(),
+                value.expr(),
+            );
+            cb.require_zero(
+                "destination is not a JUMPDEST opcode",
+                is_code.expr() * is_jumpdest.expr(),
+            );
+        });
+
+        let common_error_gadget = CommonErrorGadget::construct(cb, opcode.expr());
+
+        Self {
+            opcode,
+            is_jumpi,
+            destination,
+            condition,
+            is_condition_zero,
+            code_length,
+            destination_hi_is_zero,
+            destination_lt_code_length,
+            value,
+            is_code,
+            is_jumpdest,
+            common_error_gadget,
+        }
+    }
+
+    fn assign_exec_step(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        block: &Block<F>,
+        _: &Transaction,
+        call: &Call,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        let opcode = step.opcode.unwrap();
+        let is_jumpi = opcode == OpcodeId::JUMPI;
+        self.opcode
+            .assign(region, offset, Value::known(F::from(opcode.as_u64())))?;
+        self.is_jumpi.assign(
+            region,
+            offset,
+            F::from(opcode.as_u64()),
+            F::from(OpcodeId::JUMPI.as_u64()),
+        )?;
+
+        let destination = block.rws[step.rw_indices[0]].stack_value();
+        self.destination
+            .assign(region, offset, Some(destination.to_le_bytes()))?;
+        let condition = if is_jumpi {
+            block.rws[step.rw_indices[1]].stack_value()
+        } else {
+            U256::zero()
+        };
+        let condition = Word::random_linear_combine(condition.to_le_bytes(), block.randomness);
+        self.condition
+            .assign(region, offset, Value::known(condition))?;
+        self.is_condition_zero.assign(region, offset, condition)?;
+
+        let code = block
+            .bytecodes
+            .get(&call.code_hash)
+            .expect("could not find current environment's bytecode");
+        let code_length = code.bytes.len() as u64;
+        self.code_length
+            .assign(region, offset, Value::known(F::from(code_length)))?;
+
+        let destination_bytes = destination.to_le_bytes();
+        let destination_hi = destination_bytes[N_BYTES_PROGRAM_COUNTER..]
+            .iter()
+            .fold(0, |acc, byte| acc + *byte as u64);
+        self.destination_hi_is_zero
+            .assign(region, offset, F::from(destination_hi))?;
+        let destination_lo = destination.low_u64();
+        self.destination_lt_code_length.assign(
+            region,
+            offset,
+            F::from(destination_lo),
+            F::from(code_length),
+        )?;
+
+        let (value, is_code) = if destination_hi == 0 && destination_lo < code_length {
+            code.get(destination_lo as usize)
+        } else {
+            (0, false)
+        };
+        self.value
+            .assign(region, offset, Value::known(F::from(value as u64)))?;
+        self.is_code
+            .assign(region, offset, Value::known(F::from(is_code as u64)))?;
+        self.is_jumpdest.assign(
+            region,
+            offset,
+            F::from(value as u64),
+            F::from(OpcodeId::JUMPDEST.as_u64()),
+        )?;
+
+        self.common_error_gadget.assign(
+            region,
+            offset,
+            block,
+            call,
+            step,
+            if is_jumpi { 2 } else { 1 },
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_util::run_test_circuits;
+    use eth_types::{address, bytecode, geth_types::Account, Bytecode, ToWord, Word};
+    use mock::{eth, TestContext};
+
+    fn test_invalid_jump(code: Bytecode, is_root: bool) {
+        let contract = Account {
+            address: address!("0x00000000000000000000000000000000000cafe2"),
+            balance: eth(10),
+            code: code.into(),
+            ..Default::default()
+        };
+        let caller = Account {
+            address: address!("0x00000000000000000000000000000000000cafe1"),
+            balance: eth(10),
+            code: bytecode! {
+                PUSH1(0) // retLength
+                PUSH1(0) // retOffset
+                PUSH1(0) // argsLength
+                PUSH1(0) // argsOffset
+                PUSH1(0) // value
+                PUSH20(contract.address.to_word())
+                PUSH2(0xffff) // gas
+                CALL
+                STOP
+            }
+            .into(),
+            ..Default::default()
+        };
+
+        let ctx = TestContext::<3, 1>::new(
+            None,
+            |accs| {
+                accs[0].account(&caller);
+                accs[1].account(&contract);
+                accs[2]
+                    .address(address!("0x000000000000000000000000000000000000cafe"))
+                    .balance(eth(10));
+            },
+            |mut txs, accs| {
+                if is_root {
+                    txs[0]
+                        .from(accs[2].address)
+                        .to(accs[1].address)
+                        .gas(Word::from(100_000u64));
+                } else {
+                    txs[0].from(accs[2].address).to(accs[0].address);
+                }
+            },
+            |block, _| block,
+        )
+        .unwrap();
+
+        assert_eq!(run_test_circuits(ctx, None), Ok(()));
+    }
+
+    fn invalid_jump_codes() -> Vec<Bytecode> {
+        vec![
+            // Destination is out of the code.
+            bytecode! {
+                PUSH1(0xff)
+                JUMP
+            },
+            // Destination is far out of the code.
+            bytecode! {
+                PUSH32(Word::MAX)
+                JUMP
+            },
+            // Destination is not a JUMPDEST.
+            bytecode! {
+                PUSH1(0x00)
+                JUMP
+            },
+            // Destination is a JUMPDEST byte in the data of PUSH1.
+            bytecode! {
+                PUSH1(0x5b)
+                PUSH1(0x01)
+                JUMP
+            },
+            // JUMPI with non-zero condition to an invalid destination.
+            bytecode! {
+                PUSH1(0x01)
+                PUSH1(0xff)
+                JUMPI
+            },
+        ]
+    }
+
+    #[test]
+    fn error_invalid_jump_root() {
+        for code in invalid_jump_codes() {
+            test_invalid_jump(code, true);
+        }
+    }
+
+    #[test]
+    fn error_invalid_jump_internal() {
+        for code in invalid_jump_codes() {
+            test_invalid_jump(code, false);
+        }
+    }
+}
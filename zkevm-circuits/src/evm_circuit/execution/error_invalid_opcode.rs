@@ -0,0 +1,148 @@
+use crate::{
+    evm_circuit::{
+        execution::ExecutionGadget,
+        step::ExecutionState,
+        table::{FixedTableTag, Lookup},
+        util::{
+            common_gadget::CommonErrorGadget, constraint_builder::ConstraintBuilder, CachedRegion,
+            Cell,
+        },
+        witness::{Block, Call, ExecStep, Transaction},
+    },
+    util::Expr,
+};
+use eth_types::Field;
+use halo2_proofs::{circuit::Value, plonk::Error};
+
+/// Gadget for an undefined opcode or the designated `INVALID` opcode, which
+/// fails and consumes all the gas left.
+#[derive(Clone, Debug)]
+pub(crate) struct ErrorInvalidOpcodeGadget<F> {
+    opcode: Cell<F>,
+    common_error_gadget: CommonErrorGadget<F>,
+}
+
+impl<F: Field> ExecutionGadget<F> for ErrorInvalidOpcodeGadget<F> {
+    const NAME: &'static str = "ErrorInvalidOpcode";
+
+    const EXECUTION_STATE: ExecutionState = ExecutionState::ErrorInvalidOpcode;
+
+    fn configure(cb: &mut ConstraintBuilder<F>) -> Self {
+        let opcode = cb.query_cell();
+        cb.add_lookup(
+            "Invalid opcode lookup",
+            Lookup::Fixed {
+                tag: FixedTableTag::InvalidOpcode.expr(),
+                values: [opcode.expr(), 0.expr(), 0.expr()],
+            },
+        );
+
+        let common_error_gadget = CommonErrorGadget::construct(cb, opcode.expr());
+
+        Self {
+            opcode,
+            common_error_gadget,
+        }
+    }
+
+    fn assign_exec_step(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        block: &Block<F>,
+        _: &Transaction,
+        call: &Call,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        let opcode = step.opcode.unwrap();
+        self.opcode
+            .assign(region, offset, Value::known(F::from(opcode.as_u64())))?;
+
+        self.common_error_gadget
+            .assign(region, offset, block, call, step, 0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_util::run_test_circuits;
+    use eth_types::{address, bytecode, geth_types::Account, Bytecode, ToWord, Word};
+    use mock::{eth, TestContext};
+
+    fn test_invalid_opcode(code: Bytecode, is_root: bool) {
+        let contract = Account {
+            address: address!("0x00000000000000000000000000000000000cafe2"),
+            balance: eth(10),
+            code: code.into(),
+            ..Default::default()
+        };
+        let caller = Account {
+            address: address!("0x00000000000000000000000000000000000cafe1"),
+            balance: eth(10),
+            code: bytecode! {
+                PUSH1(0) // retLength
+                PUSH1(0) // retOffset
+                PUSH1(0) // argsLength
+                PUSH1(0) // argsOffset
+                PUSH1(0) // value
+                PUSH20(contract.address.to_word())
+                PUSH2(0xffff) // gas
+                CALL
+                STOP
+            }
+            .into(),
+            ..Default::default()
+        };
+
+        let ctx = TestContext::<3, 1>::new(
+            None,
+            |accs| {
+                accs[0].account(&caller);
+                accs[1].account(&contract);
+                accs[2]
+                    .address(address!("0x000000000000000000000000000000000000cafe"))
+                    .balance(eth(10));
+            },
+            |mut txs, accs| {
+                if is_root {
+                    txs[0]
+                        .from(accs[2].address)
+                        .to(accs[1].address)
+                        .gas(Word::from(100_000u64));
+                } else {
+                    txs[0].from(accs[2].address).to(accs[0].address);
+                }
+            },
+            |block, _| block,
+        )
+        .unwrap();
+
+        assert_eq!(run_test_circuits(ctx, None), Ok(()));
+    }
+
+    fn invalid_opcode_codes() -> Vec<Bytecode> {
+        [0x0c, 0x21, 0x4f, 0xef, 0xfe]
+            .into_iter()
+            .map(|byte| {
+                let mut code = Bytecode::default();
+                code.push(1, Word::one());
+                code.write(byte, true);
+                code
+            })
+            .collect()
+    }
+
+    #[test]
+    fn error_invalid_opcode_root() {
+        for code in invalid_opcode_codes() {
+            test_invalid_opcode(code, true);
+        }
+    }
+
+    #[test]
+    fn error_invalid_opcode_internal() {
+        for code in invalid_opcode_codes() {
+            test_invalid_opcode(code, false);
+        }
+    }
+}
@@ -0,0 +1,164 @@
+use crate::{
+    evm_circuit::{
+        execution::ExecutionGadget,
+        param::N_BYTES_MEMORY_ADDRESS,
+        step::ExecutionState,
+        util::{
+            common_gadget::CommonErrorGadget, constraint_builder::ConstraintBuilder,
+            math_gadget::LtGadget, memory_gadget::MemoryAddressGadget, CachedRegion, Cell,
+        },
+        witness::{Block, Call, ExecStep, Transaction},
+    },
+    util::Expr,
+};
+use bus_mapping::evm::OpcodeId;
+use eth_types::{evm_types::MAX_CODE_SIZE, Field};
+use halo2_proofs::{circuit::Value, plonk::Error};
+
+/// Gadget for the RETURN of a creation which fails because the code to be
+/// deployed is larger than `MAX_CODE_SIZE`, see
+/// [EIP-170](https://eips.ethereum.org/EIPS/eip-170).
+#[derive(Clone, Debug)]
+pub(crate) struct ErrorMaxCodeSizeExceededGadget<F> {
+    opcode: Cell<F>,
+    memory_address: MemoryAddressGadget<F>,
+    // constrain the code size is larger than the limit
+    max_code_size_exceed: LtGadget<F, N_BYTES_MEMORY_ADDRESS>,
+    common_error_gadget: CommonErrorGadget<F>,
+}
+
+impl<F: Field> ExecutionGadget<F> for ErrorMaxCodeSizeExceededGadget<F> {
+    const NAME: &'static str = "ErrorMaxCodeSizeExceeded";
+
+    const EXECUTION_STATE: ExecutionState = ExecutionState::ErrorMaxCodeSizeExceeded;
+
+    fn configure(cb: &mut ConstraintBuilder<F>) -> Self {
+        let opcode = cb.query_cell();
+        cb.require_equal(
+            "ErrorMaxCodeSizeExceeded opcode must be RETURN",
+            opcode.expr(),
+            OpcodeId::RETURN.expr(),
+        );
+        cb.require_equal(
+            "ErrorMaxCodeSizeExceeded only happens in a creation",
+            cb.curr.state.is_create.expr(),
+            1.expr(),
+        );
+
+        let offset = cb.query_cell();
+        let length = cb.query_rlc();
+        cb.stack_pop(offset.expr());
+        cb.stack_pop(length.expr());
+        let memory_address = MemoryAddressGadget::construct(cb, offset, length);
+
+        let max_code_size_exceed =
+            LtGadget::construct(cb, MAX_CODE_SIZE.expr(), memory_address.length());
+        cb.require_equal(
+            "code size is larger than MAX_CODE_SIZE",
+            max_code_size_exceed.expr(),
+            1.expr(),
+        );
+
+        let common_error_gadget = CommonErrorGadget::construct(cb, opcode.expr());
+
+        Self {
+            opcode,
+            memory_address,
+            max_code_size_exceed,
+            common_error_gadget,
+        }
+    }
+
+    fn assign_exec_step(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        block: &Block<F>,
+        _tx: &Transaction,
+        call: &Call,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        let opcode = step.opcode.unwrap();
+        self.opcode
+            .assign(region, offset, Value::known(F::from(opcode.as_u64())))?;
+
+        let [memory_offset, length] = [0, 1]
+            .map(|idx| step.rw_indices[idx])
+            .map(|idx| block.rws[idx].stack_value());
+        self.memory_address
+            .assign(region, offset, memory_offset, length, block.randomness)?;
+        self.max_code_size_exceed.assign(
+            region,
+            offset,
+            F::from(MAX_CODE_SIZE),
+            F::from(length.as_u64()),
+        )?;
+
+        self.common_error_gadget
+            .assign(region, offset, block, call, step, 2)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_util::run_test_circuits;
+    use eth_types::{
+        address, bytecode, bytecode::Bytecode, evm_types::MAX_CODE_SIZE, geth_types::Account, Word,
+    };
+    use mock::{eth, TestContext};
+
+    fn test_max_code_size_exceeded(length: u64) {
+        // Init code which RETURNs `length` bytes of zeros from memory.
+        let init_code = bytecode! {
+            PUSH2(length)
+            PUSH1(0)
+            RETURN
+        }
+        .to_vec();
+        let mut init_code_word = [0u8; 32];
+        init_code_word[..init_code.len()].copy_from_slice(&init_code);
+        let code: Bytecode = bytecode! {
+            PUSH32(Word::from_big_endian(&init_code_word))
+            PUSH1(0)
+            MSTORE
+            PUSH1(init_code.len()) // length
+            PUSH1(0) // offset
+            PUSH1(0) // value
+            CREATE
+            STOP
+        };
+        let creator = Account {
+            address: address!("0x00000000000000000000000000000000000cafe1"),
+            balance: eth(10),
+            nonce: Word::one(),
+            code: code.into(),
+            ..Default::default()
+        };
+
+        let ctx = TestContext::<2, 1>::new(
+            None,
+            |accs| {
+                accs[0].account(&creator);
+                accs[1]
+                    .address(address!("0x000000000000000000000000000000000000cafe"))
+                    .balance(eth(10));
+            },
+            |mut txs, accs| {
+                txs[0]
+                    .from(accs[1].address)
+                    .to(accs[0].address)
+                    .gas(Word::from(10_000_000u64));
+            },
+            |block, _| block,
+        )
+        .unwrap();
+
+        assert_eq!(run_test_circuits(ctx, None), Ok(()));
+    }
+
+    #[test]
+    fn error_max_code_size_exceeded() {
+        test_max_code_size_exceeded(MAX_CODE_SIZE + 1);
+        test_max_code_size_exceeded(0xffff);
+    }
+}
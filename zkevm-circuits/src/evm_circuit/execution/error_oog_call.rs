@@ -0,0 +1,415 @@
+use crate::{
+    evm_circuit::{
+        execution::{
+            callop::{calc_call_gas_cost, call_gas_cost_expr},
+            ExecutionGadget,
+        },
+        param::{N_BYTES_ACCOUNT_ADDRESS, N_BYTES_GAS, N_BYTES_MEMORY_WORD_SIZE},
+        step::ExecutionState,
+        util::{
+            common_gadget::CommonErrorGadget,
+            constraint_builder::ConstraintBuilder,
+            from_bytes,
+            math_gadget::{BatchedIsZeroGadget, IsEqualGadget, IsZeroGadget, LtGadget},
+            memory_gadget::{MemoryExpandedAddressGadget, MemoryExpansionGadget},
+            not, or, sum, CachedRegion, Cell, Word,
+        },
+        witness::{Block, Call, ExecStep, Transaction},
+    },
+    table::{AccountFieldTag, CallContextFieldTag},
+    util::Expr,
+};
+use eth_types::{evm_types::OpcodeId, Field, ToLittleEndian, ToScalar, U256};
+use halo2_proofs::{circuit::Value, plonk::Error};
+use keccak256::EMPTY_HASH_LE;
+
+/// Gadget for CALL, CALLCODE, DELEGATECALL and STATICCALL which fail because
+/// the gas left is not enough to pay for the account access, the value
+/// transfer and the memory expansion, or the memory address overflows.
+#[derive(Clone, Debug)]
+pub(crate) struct ErrorOOGCallGadget<F, const S: ExecutionState> {
+    opcode: Cell<F>,
+    tx_id: Cell<F>,
+    gas: Word<F>,
+    code_address: Word<F>,
+    value: Word<F>,
+    value_is_zero: IsZeroGadget<F>,
+    cd_address: MemoryExpandedAddressGadget<F>,
+    rd_address: MemoryExpandedAddressGadget<F>,
+    memory_expansion: MemoryExpansionGadget<F, 2, N_BYTES_MEMORY_WORD_SIZE>,
+    is_warm: Cell<F>,
+    callee_nonce: Cell<F>,
+    callee_balance: Cell<F>,
+    callee_code_hash: Cell<F>,
+    is_empty_nonce_and_balance: BatchedIsZeroGadget<F, 2>,
+    is_empty_code_hash: IsEqualGadget<F>,
+    // constrain gas left is less than required
+    insufficient_gas: LtGadget<F, N_BYTES_GAS>,
+    common_error_gadget: CommonErrorGadget<F>,
+}
+
+impl<F: Field, const S: ExecutionState> ErrorOOGCallGadget<F, S> {
+    fn opcode_id() -> OpcodeId {
+        match S {
+            ExecutionState::ErrorOutOfGasCALL => OpcodeId::CALL,
+            ExecutionState::ErrorOutOfGasCALLCODE => OpcodeId::CALLCODE,
+            ExecutionState::ErrorOutOfGasDELEGATECALL => OpcodeId::DELEGATECALL,
+            ExecutionState::ErrorOutOfGasSTATICCALL => OpcodeId::STATICCALL,
+            _ => unreachable!("ErrorOOGCallGadget only handles call related opcodes"),
+        }
+    }
+
+    /// Only CALL and CALLCODE take the value argument from stack.
+    fn has_value_arg() -> bool {
+        matches!(Self::opcode_id(), OpcodeId::CALL | OpcodeId::CALLCODE)
+    }
+}
+
+impl<F: Field, const S: ExecutionState> ExecutionGadget<F> for ErrorOOGCallGadget<F, S> {
+    const NAME: &'static str = "ErrorOutOfGasCall";
+
+    const EXECUTION_STATE: ExecutionState = S;
+
+    fn configure(cb: &mut ConstraintBuilder<F>) -> Self {
+        let opcode_id = Self::opcode_id();
+        let opcode = cb.query_cell();
+        cb.require_equal(
+            "ErrorOutOfGasCall opcode must match the execution state",
+            opcode.expr(),
+            opcode_id.expr(),
+        );
+
+        let tx_id = cb.call_context(None, CallContextFieldTag::TxId);
+
+        let gas = cb.query_word();
+        let code_address = cb.query_word();
+        let value = cb.query_word();
+        let cd_address = MemoryExpandedAddressGadget::construct(cb);
+        let rd_address = MemoryExpandedAddressGadget::construct(cb);
+
+        cb.stack_pop(gas.expr());
+        cb.stack_pop(code_address.expr());
+        if Self::has_value_arg() {
+            cb.stack_pop(value.expr());
+        } else {
+            cb.require_zero(
+                "DELEGATECALL and STATICCALL transfer no value",
+                value.expr(),
+            );
+        }
+        cb.stack_pop(cd_address.offset_rlc());
+        cb.stack_pop(cd_address.length_rlc());
+        cb.stack_pop(rd_address.offset_rlc());
+        cb.stack_pop(rd_address.length_rlc());
+
+        let memory_expansion = MemoryExpansionGadget::construct(
+            cb,
+            cb.curr.state.memory_word_size.expr(),
+            [cd_address.address(), rd_address.address()],
+        );
+
+        // The code address is read from the access list without being added.
+        let code_address_expr = from_bytes::expr(&code_address.cells[..N_BYTES_ACCOUNT_ADDRESS]);
+        let is_warm = cb.query_bool();
+        cb.account_access_list_read(tx_id.expr(), code_address_expr.clone(), is_warm.expr());
+
+        // Only CALL might create a new account, so only CALL reads the callee
+        // account to check if it's empty.
+        let [callee_nonce, callee_balance, callee_code_hash] = [(); 3].map(|_| cb.query_cell());
+        if opcode_id == OpcodeId::CALL {
+            for (field_tag, value) in [
+                (AccountFieldTag::Nonce, &callee_nonce),
+                (AccountFieldTag::Balance, &callee_balance),
+                (AccountFieldTag::CodeHash, &callee_code_hash),
+            ] {
+                cb.account_read(code_address_expr.clone(), field_tag, value.expr());
+            }
+        }
+        let is_empty_nonce_and_balance =
+            BatchedIsZeroGadget::construct(cb, [callee_nonce.expr(), callee_balance.expr()]);
+        let is_empty_code_hash = IsEqualGadget::construct(
+            cb,
+            callee_code_hash.expr(),
+            Word::random_linear_combine_expr(
+                (*EMPTY_HASH_LE).map(|byte| byte.expr()),
+                cb.power_of_randomness(),
+            ),
+        );
+        let is_empty_account = if opcode_id == OpcodeId::CALL {
+            is_empty_nonce_and_balance.expr() * is_empty_code_hash.expr()
+        } else {
+            0.expr()
+        };
+
+        let value_is_zero = IsZeroGadget::construct(cb, sum::expr(&value.cells));
+        let gas_cost = call_gas_cost_expr(
+            is_warm.expr(),
+            not::expr(value_is_zero.expr()),
+            is_empty_account,
+            memory_expansion.gas_cost(),
+        );
+        let insufficient_gas = LtGadget::construct(cb, cb.curr.state.gas_left.expr(), gas_cost);
+        cb.require_equal(
+            "memory address overflows or gas left is less than gas required",
+            or::expr([
+                cd_address.overflow(),
+                rd_address.overflow(),
+                insufficient_gas.expr(),
+            ]),
+            1.expr(),
+        );
+
+        let common_error_gadget = CommonErrorGadget::construct(cb, opcode.expr());
+
+        Self {
+            opcode,
+            tx_id,
+            gas,
+            code_address,
+            value,
+            value_is_zero,
+            cd_address,
+            rd_address,
+            memory_expansion,
+            is_warm,
+            callee_nonce,
+            callee_balance,
+            callee_code_hash,
+            is_empty_nonce_and_balance,
+            is_empty_code_hash,
+            insufficient_gas,
+            common_error_gadget,
+        }
+    }
+
+    fn assign_exec_step(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        block: &Block<F>,
+        tx: &Transaction,
+        call: &Call,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        let opcode = step.opcode.unwrap();
+        self.opcode
+            .assign(region, offset, Value::known(F::from(opcode.as_u64())))?;
+
+        self.tx_id
+            .assign(region, offset, Value::known(F::from(tx.id as u64)))?;
+
+        let [gas, code_address] = [1, 2].map(|idx| block.rws[step.rw_indices[idx]].stack_value());
+        let (value, rw_offset) = if Self::has_value_arg() {
+            (block.rws[step.rw_indices[3]].stack_value(), 4)
+        } else {
+            (U256::zero(), 3)
+        };
+        let [cd_offset, cd_length, rd_offset, rd_length] =
+            [0, 1, 2, 3].map(|idx| block.rws[step.rw_indices[rw_offset + idx]].stack_value());
+        let (is_warm, _) = block.rws[step.rw_indices[rw_offset + 4]].tx_access_list_value_pair();
+        let (callee_nonce, callee_balance, callee_code_hash, rw_offset) =
+            if opcode == OpcodeId::CALL {
+                let [(nonce, _), (balance, _), (code_hash, _)] = [5, 6, 7]
+                    .map(|idx| block.rws[step.rw_indices[rw_offset + idx]].account_value_pair());
+                (nonce, balance, code_hash, rw_offset + 8)
+            } else {
+                (U256::zero(), U256::zero(), U256::zero(), rw_offset + 5)
+            };
+
+        self.gas.assign(region, offset, Some(gas.to_le_bytes()))?;
+        self.code_address
+            .assign(region, offset, Some(code_address.to_le_bytes()))?;
+        self.value
+            .assign(region, offset, Some(value.to_le_bytes()))?;
+        self.value_is_zero
+            .assign(region, offset, sum::value(&value.to_le_bytes()))?;
+
+        let (cd_address, _) = self
+            .cd_address
+            .assign(region, offset, cd_offset, cd_length)?;
+        let (rd_address, _) = self
+            .rd_address
+            .assign(region, offset, rd_offset, rd_length)?;
+        let (_, memory_expansion_gas_cost) = self.memory_expansion.assign(
+            region,
+            offset,
+            step.memory_word_size(),
+            [cd_address, rd_address],
+        )?;
+
+        self.is_warm
+            .assign(region, offset, Value::known(F::from(is_warm as u64)))?;
+
+        self.callee_nonce.assign(
+            region,
+            offset,
+            Value::known(
+                callee_nonce
+                    .to_scalar()
+                    .expect("unexpected U256 -> Scalar conversion failure"),
+            ),
+        )?;
+        let callee_balance =
+            Word::random_linear_combine(callee_balance.to_le_bytes(), block.randomness);
+        self.callee_balance
+            .assign(region, offset, Value::known(callee_balance))?;
+        let callee_code_hash =
+            Word::random_linear_combine(callee_code_hash.to_le_bytes(), block.randomness);
+        self.callee_code_hash
+            .assign(region, offset, Value::known(callee_code_hash))?;
+        let is_empty_nonce_and_balance = self.is_empty_nonce_and_balance.assign(
+            region,
+            offset,
+            [F::from(callee_nonce.low_u64()), callee_balance],
+        )?;
+        let is_empty_code_hash = self.is_empty_code_hash.assign(
+            region,
+            offset,
+            callee_code_hash,
+            Word::random_linear_combine(*EMPTY_HASH_LE, block.randomness),
+        )?;
+        let is_empty_account =
+            opcode == OpcodeId::CALL && is_empty_nonce_and_balance * is_empty_code_hash == F::one();
+
+        let gas_cost = calc_call_gas_cost(
+            is_warm,
+            !value.is_zero(),
+            is_empty_account,
+            memory_expansion_gas_cost,
+        );
+        self.insufficient_gas
+            .assign(region, offset, F::from(step.gas_left), F::from(gas_cost))?;
+
+        self.common_error_gadget
+            .assign(region, offset, block, call, step, rw_offset)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_util::run_test_circuits;
+    use eth_types::{
+        address, bytecode, evm_types::OpcodeId, geth_types::Account, Bytecode, ToWord, Word,
+    };
+    use mock::{eth, TestContext};
+
+    fn call_code(opcode: OpcodeId, value: Word, rd_length: Word) -> Bytecode {
+        let mut code = bytecode! {
+            PUSH32(rd_length) // retLength
+            PUSH1(0) // retOffset
+            PUSH1(0) // argsLength
+            PUSH1(0) // argsOffset
+        };
+        if matches!(opcode, OpcodeId::CALL | OpcodeId::CALLCODE) {
+            code.push(32, value);
+        }
+        code.append(&bytecode! {
+            PUSH20(0xcafe) // address
+            PUSH2(0xffff) // gas
+        });
+        code.write_op(opcode);
+        code.write_op(OpcodeId::STOP);
+        code
+    }
+
+    fn test_oog_call(code: Bytecode, gas: u64, is_root: bool) {
+        let contract = Account {
+            address: address!("0x00000000000000000000000000000000000cafe2"),
+            balance: eth(10),
+            code: code.into(),
+            ..Default::default()
+        };
+        let caller = Account {
+            address: address!("0x00000000000000000000000000000000000cafe1"),
+            balance: eth(10),
+            code: bytecode! {
+                PUSH1(0) // retLength
+                PUSH1(0) // retOffset
+                PUSH1(0) // argsLength
+                PUSH1(0) // argsOffset
+                PUSH1(0) // value
+                PUSH20(contract.address.to_word())
+                PUSH32(gas)
+                CALL
+                STOP
+            }
+            .into(),
+            ..Default::default()
+        };
+
+        let ctx = TestContext::<3, 1>::new(
+            None,
+            |accs| {
+                accs[0].account(&caller);
+                accs[1].account(&contract);
+                accs[2]
+                    .address(address!("0x000000000000000000000000000000000000cafe"))
+                    .balance(eth(10));
+            },
+            |mut txs, accs| {
+                if is_root {
+                    txs[0]
+                        .from(accs[2].address)
+                        .to(accs[1].address)
+                        .gas(Word::from(21_000u64 + gas));
+                } else {
+                    txs[0].from(accs[2].address).to(accs[0].address);
+                }
+            },
+            |block, _| block,
+        )
+        .unwrap();
+
+        assert_eq!(run_test_circuits(ctx, None), Ok(()));
+    }
+
+    fn test_oog_call_opcode(opcode: OpcodeId) {
+        let n_pushes = if matches!(opcode, OpcodeId::CALL | OpcodeId::CALLCODE) {
+            7
+        } else {
+            6
+        };
+        for is_root in [true, false] {
+            // The cold account access costs 2600.
+            test_oog_call(
+                call_code(opcode, Word::zero(), Word::zero()),
+                3 * n_pushes + 2599,
+                is_root,
+            );
+            // The memory address overflows.
+            test_oog_call(
+                call_code(opcode, Word::zero(), Word::MAX),
+                3 * n_pushes + 100_000,
+                is_root,
+            );
+        }
+    }
+
+    #[test]
+    fn error_oog_call() {
+        test_oog_call_opcode(OpcodeId::CALL);
+        // The value transfer costs 9000 besides the cold account access.
+        for is_root in [true, false] {
+            test_oog_call(
+                call_code(OpcodeId::CALL, Word::from(1), Word::zero()),
+                3 * 7 + 2600 + 8999,
+                is_root,
+            );
+        }
+    }
+
+    #[test]
+    fn error_oog_call_code() {
+        test_oog_call_opcode(OpcodeId::CALLCODE);
+    }
+
+    #[test]
+    fn error_oog_delegate_call() {
+        test_oog_call_opcode(OpcodeId::DELEGATECALL);
+    }
+
+    #[test]
+    fn error_oog_static_call() {
+        test_oog_call_opcode(OpcodeId::STATICCALL);
+    }
+}
@@ -0,0 +1,214 @@
+use crate::{
+    evm_circuit::{
+        execution::ExecutionGadget,
+        param::{N_BYTES_GAS, N_BYTES_MEMORY_ADDRESS, N_BYTES_MEMORY_WORD_SIZE},
+        step::ExecutionState,
+        util::{
+            common_gadget::CommonErrorGadget,
+            constraint_builder::ConstraintBuilder,
+            math_gadget::LtGadget,
+            memory_gadget::{MemoryAddressGadget, MemoryExpansionGadget},
+            CachedRegion, Cell,
+        },
+        witness::{Block, Call, ExecStep, Transaction},
+    },
+    util::Expr,
+};
+use bus_mapping::evm::OpcodeId;
+use eth_types::{
+    evm_types::{GasCost, MAX_CODE_SIZE},
+    Field,
+};
+use halo2_proofs::{circuit::Value, plonk::Error};
+
+/// Gadget for the RETURN of a creation which fails because the gas left is not
+/// enough to pay for storing the deployed code.
+#[derive(Clone, Debug)]
+pub(crate) struct ErrorOOGCodeStoreGadget<F> {
+    opcode: Cell<F>,
+    memory_address: MemoryAddressGadget<F>,
+    memory_expansion: MemoryExpansionGadget<F, 1, N_BYTES_MEMORY_WORD_SIZE>,
+    // constrain the code size is not larger than the limit
+    max_code_size_exceed: LtGadget<F, N_BYTES_MEMORY_ADDRESS>,
+    // constrain gas left is less than required
+    insufficient_gas: LtGadget<F, N_BYTES_GAS>,
+    common_error_gadget: CommonErrorGadget<F>,
+}
+
+impl<F: Field> ExecutionGadget<F> for ErrorOOGCodeStoreGadget<F> {
+    const NAME: &'static str = "ErrorOutOfGasCodeStore";
+
+    const EXECUTION_STATE: ExecutionState = ExecutionState::ErrorOutOfGasCodeStore;
+
+    fn configure(cb: &mut ConstraintBuilder<F>) -> Self {
+        let opcode = cb.query_cell();
+        cb.require_equal(
+            "ErrorOutOfGasCodeStore opcode must be RETURN",
+            opcode.expr(),
+            OpcodeId::RETURN.expr(),
+        );
+        cb.require_equal(
+            "ErrorOutOfGasCodeStore only happens in a creation",
+            cb.curr.state.is_create.expr(),
+            1.expr(),
+        );
+
+        let offset = cb.query_cell();
+        let length = cb.query_rlc();
+        cb.stack_pop(offset.expr());
+        cb.stack_pop(length.expr());
+        let memory_address = MemoryAddressGadget::construct(cb, offset, length);
+        let memory_expansion = MemoryExpansionGadget::construct(
+            cb,
+            cb.curr.state.memory_word_size.expr(),
+            [memory_address.address()],
+        );
+
+        let max_code_size_exceed =
+            LtGadget::construct(cb, MAX_CODE_SIZE.expr(), memory_address.length());
+        cb.require_zero(
+            "code size is not larger than MAX_CODE_SIZE",
+            max_code_size_exceed.expr(),
+        );
+
+        let insufficient_gas = LtGadget::construct(
+            cb,
+            cb.curr.state.gas_left.expr(),
+            GasCost::CODE_DEPOSIT_BYTE_COST.expr() * memory_address.length()
+                + memory_expansion.gas_cost(),
+        );
+        cb.require_equal(
+            "gas left is less than gas required",
+            insufficient_gas.expr(),
+            1.expr(),
+        );
+
+        let common_error_gadget = CommonErrorGadget::construct(cb, opcode.expr());
+
+        Self {
+            opcode,
+            memory_address,
+            memory_expansion,
+            max_code_size_exceed,
+            insufficient_gas,
+            common_error_gadget,
+        }
+    }
+
+    fn assign_exec_step(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        block: &Block<F>,
+        _tx: &Transaction,
+        call: &Call,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        let opcode = step.opcode.unwrap();
+        self.opcode
+            .assign(region, offset, Value::known(F::from(opcode.as_u64())))?;
+
+        let [memory_offset, length] = [0, 1]
+            .map(|idx| step.rw_indices[idx])
+            .map(|idx| block.rws[idx].stack_value());
+        let memory_address =
+            self.memory_address
+                .assign(region, offset, memory_offset, length, block.randomness)?;
+        let (_, memory_expansion_gas_cost) = self.memory_expansion.assign(
+            region,
+            offset,
+            step.memory_word_size(),
+            [memory_address],
+        )?;
+
+        self.max_code_size_exceed.assign(
+            region,
+            offset,
+            F::from(MAX_CODE_SIZE),
+            F::from(length.as_u64()),
+        )?;
+        self.insufficient_gas.assign(
+            region,
+            offset,
+            F::from(step.gas_left),
+            F::from(
+                GasCost::CODE_DEPOSIT_BYTE_COST.as_u64() * length.as_u64()
+                    + memory_expansion_gas_cost,
+            ),
+        )?;
+
+        self.common_error_gadget
+            .assign(region, offset, block, call, step, 2)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_util::run_test_circuits;
+    use eth_types::{address, bytecode, bytecode::Bytecode, geth_types::Account, Word};
+    use mock::{eth, TestContext};
+
+    // Init code which RETURNs `length` bytes of zeros from memory, i.e. deploys
+    // a code of `length` STOPs.
+    fn initialization_bytecode(length: u64) -> Bytecode {
+        bytecode! {
+            PUSH2(length)
+            PUSH1(0)
+            RETURN
+        }
+    }
+
+    fn creator_bytecode(init_code: &[u8]) -> Bytecode {
+        let mut init_code_word = [0u8; 32];
+        init_code_word[..init_code.len()].copy_from_slice(init_code);
+        bytecode! {
+            PUSH32(Word::from_big_endian(&init_code_word))
+            PUSH1(0)
+            MSTORE
+            PUSH1(init_code.len()) // length
+            PUSH1(0) // offset
+            PUSH1(0) // value
+            CREATE
+            STOP
+        }
+    }
+
+    fn test_oog_code_store(length: u64, tx_gas: u64) {
+        let init_code = initialization_bytecode(length).to_vec();
+        let creator = Account {
+            address: address!("0x00000000000000000000000000000000000cafe1"),
+            balance: eth(10),
+            nonce: Word::one(),
+            code: creator_bytecode(&init_code).into(),
+            ..Default::default()
+        };
+
+        let ctx = TestContext::<2, 1>::new(
+            None,
+            |accs| {
+                accs[0].account(&creator);
+                accs[1]
+                    .address(address!("0x000000000000000000000000000000000000cafe"))
+                    .balance(eth(10));
+            },
+            |mut txs, accs| {
+                txs[0]
+                    .from(accs[1].address)
+                    .to(accs[0].address)
+                    .gas(tx_gas.into());
+            },
+            |block, _| block,
+        )
+        .unwrap();
+
+        assert_eq!(run_test_circuits(ctx, None), Ok(()));
+    }
+
+    #[test]
+    fn error_oog_code_store_simple() {
+        // Storing 0x200 bytes costs 102400 gas, which is more than the gas
+        // left to the init code.
+        test_oog_code_store(0x200, 100000);
+        test_oog_code_store(0x5000, 1000000);
+    }
+}
@@ -0,0 +1,261 @@
+use crate::{
+    evm_circuit::{
+        execution::ExecutionGadget,
+        param::{N_BYTES_ACCOUNT_ADDRESS, N_BYTES_GAS, N_BYTES_MEMORY_WORD_SIZE},
+        step::ExecutionState,
+        util::{
+            common_gadget::CommonErrorGadget,
+            constraint_builder::ConstraintBuilder,
+            from_bytes,
+            math_gadget::LtGadget,
+            memory_gadget::{MemoryAddressGadget, MemoryCopierGasGadget, MemoryExpansionGadget},
+            select, CachedRegion, Cell, RandomLinearCombination, Word,
+        },
+        witness::{Block, Call, ExecStep, Transaction},
+    },
+    table::CallContextFieldTag,
+    util::Expr,
+};
+use bus_mapping::evm::OpcodeId;
+use eth_types::{evm_types::GasCost, Field, ToAddress, ToLittleEndian};
+use halo2_proofs::{circuit::Value, plonk::Error};
+
+/// Gadget for EXTCODECOPY which fails because the gas left is not enough to
+/// pay for the account access, the memory expansion and the copy.
+#[derive(Clone, Debug)]
+pub(crate) struct ErrorOOGExtCodeCopyGadget<F> {
+    opcode: Cell<F>,
+    external_address: RandomLinearCombination<F, N_BYTES_ACCOUNT_ADDRESS>,
+    code_offset: Word<F>,
+    memory_address: MemoryAddressGadget<F>,
+    tx_id: Cell<F>,
+    is_warm: Cell<F>,
+    memory_expansion: MemoryExpansionGadget<F, 1, N_BYTES_MEMORY_WORD_SIZE>,
+    memory_copier_gas: MemoryCopierGasGadget<F, { GasCost::COPY }>,
+    // constrain gas left is less than required
+    insufficient_gas: LtGadget<F, N_BYTES_GAS>,
+    common_error_gadget: CommonErrorGadget<F>,
+}
+
+impl<F: Field> ExecutionGadget<F> for ErrorOOGExtCodeCopyGadget<F> {
+    const NAME: &'static str = "ErrorOutOfGasEXTCODECOPY";
+
+    const EXECUTION_STATE: ExecutionState = ExecutionState::ErrorOutOfGasEXTCODECOPY;
+
+    fn configure(cb: &mut ConstraintBuilder<F>) -> Self {
+        let opcode = cb.query_cell();
+        cb.require_equal(
+            "ErrorOutOfGasEXTCODECOPY opcode must be EXTCODECOPY",
+            opcode.expr(),
+            OpcodeId::EXTCODECOPY.expr(),
+        );
+
+        let external_address = cb.query_rlc();
+        let memory_offset = cb.query_cell();
+        let code_offset = cb.query_word();
+        let memory_length = cb.query_rlc();
+
+        cb.stack_pop(external_address.expr());
+        cb.stack_pop(memory_offset.expr());
+        cb.stack_pop(code_offset.expr());
+        cb.stack_pop(memory_length.expr());
+
+        let tx_id = cb.call_context(None, CallContextFieldTag::TxId);
+        let is_warm = cb.query_bool();
+        cb.account_access_list_read(
+            tx_id.expr(),
+            from_bytes::expr(&external_address.cells),
+            is_warm.expr(),
+        );
+
+        let memory_address = MemoryAddressGadget::construct(cb, memory_offset, memory_length);
+        let memory_expansion = MemoryExpansionGadget::construct(
+            cb,
+            cb.curr.state.memory_word_size.expr(),
+            [memory_address.address()],
+        );
+        let memory_copier_gas = MemoryCopierGasGadget::construct(
+            cb,
+            memory_address.length(),
+            memory_expansion.gas_cost(),
+        );
+        let gas_cost = memory_copier_gas.gas_cost()
+            + select::expr(
+                is_warm.expr(),
+                GasCost::WARM_ACCESS.expr(),
+                GasCost::COLD_ACCOUNT_ACCESS.expr(),
+            );
+
+        let insufficient_gas = LtGadget::construct(cb, cb.curr.state.gas_left.expr(), gas_cost);
+        cb.require_equal(
+            "gas left is less than gas required",
+            insufficient_gas.expr(),
+            1.expr(),
+        );
+
+        let common_error_gadget = CommonErrorGadget::construct(cb, opcode.expr());
+
+        Self {
+            opcode,
+            external_address,
+            code_offset,
+            memory_address,
+            tx_id,
+            is_warm,
+            memory_expansion,
+            memory_copier_gas,
+            insufficient_gas,
+            common_error_gadget,
+        }
+    }
+
+    fn assign_exec_step(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        block: &Block<F>,
+        tx: &Transaction,
+        call: &Call,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        let opcode = step.opcode.unwrap();
+        self.opcode
+            .assign(region, offset, Value::known(F::from(opcode.as_u64())))?;
+
+        let [external_address, memory_offset, code_offset, memory_length] =
+            [0, 1, 2, 3].map(|idx| block.rws[step.rw_indices[idx]].stack_value());
+        let mut le_bytes = external_address.to_address().0;
+        le_bytes.reverse();
+        self.external_address
+            .assign(region, offset, Some(le_bytes))?;
+        self.code_offset
+            .assign(region, offset, Some(code_offset.to_le_bytes()))?;
+        let memory_address = self.memory_address.assign(
+            region,
+            offset,
+            memory_offset,
+            memory_length,
+            block.randomness,
+        )?;
+
+        self.tx_id
+            .assign(region, offset, Value::known(F::from(tx.id as u64)))?;
+        let (is_warm, _) = block.rws[step.rw_indices[5]].tx_access_list_value_pair();
+        self.is_warm
+            .assign(region, offset, Value::known(F::from(is_warm as u64)))?;
+
+        let (_, memory_expansion_gas_cost) = self.memory_expansion.assign(
+            region,
+            offset,
+            step.memory_word_size(),
+            [memory_address],
+        )?;
+        let memory_copier_gas = self.memory_copier_gas.assign(
+            region,
+            offset,
+            memory_length.as_u64(),
+            memory_expansion_gas_cost,
+        )?;
+        let gas_cost = memory_copier_gas
+            + if is_warm {
+                GasCost::WARM_ACCESS.as_u64()
+            } else {
+                GasCost::COLD_ACCOUNT_ACCESS.as_u64()
+            };
+        self.insufficient_gas
+            .assign(region, offset, F::from(step.gas_left), F::from(gas_cost))?;
+
+        self.common_error_gadget
+            .assign(region, offset, block, call, step, 6)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_util::run_test_circuits;
+    use eth_types::{address, bytecode, geth_types::Account, Bytecode, ToWord, Word};
+    use mock::{eth, TestContext};
+
+    fn test_oog_ext_codecopy(is_warm: bool, is_root: bool) {
+        let external_address = address!("0x0000000000000000000000000000000000000bee");
+        let mut code = Bytecode::default();
+        if is_warm {
+            code.append(&bytecode! {
+                PUSH20(external_address.to_word())
+                EXTCODEHASH
+                POP
+            });
+        }
+        code.append(&bytecode! {
+            PUSH32(0x100) // length
+            PUSH32(0x00) // code_offset
+            PUSH32(0x00) // memory_offset
+            PUSH20(external_address.to_word())
+            EXTCODECOPY
+            STOP
+        });
+        let contract = Account {
+            address: address!("0x00000000000000000000000000000000000cafe2"),
+            balance: eth(10),
+            code: code.into(),
+            ..Default::default()
+        };
+        // Gas left before EXTCODECOPY is 2000 for the cold case and 100 for the
+        // warm one, which is less than the access cost plus the copy cost.
+        let gas = if is_warm { 2605 + 12 + 100 } else { 12 + 2000 };
+        let caller = Account {
+            address: address!("0x00000000000000000000000000000000000cafe1"),
+            balance: eth(10),
+            code: bytecode! {
+                PUSH1(0) // retLength
+                PUSH1(0) // retOffset
+                PUSH1(0) // argsLength
+                PUSH1(0) // argsOffset
+                PUSH1(0) // value
+                PUSH20(contract.address.to_word())
+                PUSH2(gas)
+                CALL
+                STOP
+            }
+            .into(),
+            ..Default::default()
+        };
+
+        let ctx = TestContext::<3, 1>::new(
+            None,
+            |accs| {
+                accs[0].account(&caller);
+                accs[1].account(&contract);
+                accs[2]
+                    .address(address!("0x000000000000000000000000000000000000cafe"))
+                    .balance(eth(10));
+            },
+            |mut txs, accs| {
+                if is_root {
+                    txs[0]
+                        .from(accs[2].address)
+                        .to(accs[1].address)
+                        .gas(Word::from(21_000u64 + gas));
+                } else {
+                    txs[0].from(accs[2].address).to(accs[0].address);
+                }
+            },
+            |block, _| block,
+        )
+        .unwrap();
+
+        assert_eq!(run_test_circuits(ctx, None), Ok(()));
+    }
+
+    #[test]
+    fn error_oog_ext_codecopy_root() {
+        test_oog_ext_codecopy(false, true);
+        test_oog_ext_codecopy(true, true);
+    }
+
+    #[test]
+    fn error_oog_ext_codecopy_internal() {
+        test_oog_ext_codecopy(false, false);
+        test_oog_ext_codecopy(true, false);
+    }
+}
@@ -0,0 +1,226 @@
+use crate::{
+    evm_circuit::{
+        execution::ExecutionGadget,
+        param::{N_BYTES_GAS, N_BYTES_MEMORY_WORD_SIZE},
+        step::ExecutionState,
+        util::{
+            common_gadget::CommonErrorGadget,
+            constraint_builder::ConstraintBuilder,
+            math_gadget::LtGadget,
+            memory_gadget::{MemoryExpandedAddressGadget, MemoryExpansionGadget},
+            or, CachedRegion, Cell,
+        },
+        witness::{Block, Call, ExecStep, Transaction},
+    },
+    util::Expr,
+};
+use eth_types::{
+    evm_types::{GasCost, OpcodeId},
+    Field,
+};
+use halo2_proofs::{circuit::Value, plonk::Error};
+
+/// Gadget for LOGN which fails because the gas left is not enough to pay for
+/// the topics, the data and the memory expansion, or the memory address
+/// overflows.
+#[derive(Clone, Debug)]
+pub(crate) struct ErrorOOGLogGadget<F> {
+    opcode: Cell<F>,
+    memory_address: MemoryExpandedAddressGadget<F>,
+    memory_expansion: MemoryExpansionGadget<F, 1, N_BYTES_MEMORY_WORD_SIZE>,
+    // constrain gas left is less than required
+    insufficient_gas: LtGadget<F, N_BYTES_GAS>,
+    common_error_gadget: CommonErrorGadget<F>,
+}
+
+impl<F: Field> ExecutionGadget<F> for ErrorOOGLogGadget<F> {
+    const NAME: &'static str = "ErrorOutOfGasLOG";
+
+    const EXECUTION_STATE: ExecutionState = ExecutionState::ErrorOutOfGasLOG;
+
+    fn configure(cb: &mut ConstraintBuilder<F>) -> Self {
+        let opcode = cb.query_cell();
+        let topic_count = opcode.expr() - OpcodeId::LOG0.as_u8().expr();
+        cb.require_in_set(
+            "ErrorOutOfGasLOG opcode must be LOG0, LOG1, LOG2, LOG3 or LOG4",
+            topic_count.clone(),
+            (0..5).map(|topic_count| topic_count.expr()).collect(),
+        );
+
+        // The topics are not popped since they don't affect the gas cost.
+        let memory_address = MemoryExpandedAddressGadget::construct(cb);
+        cb.stack_pop(memory_address.offset_rlc());
+        cb.stack_pop(memory_address.length_rlc());
+
+        let memory_expansion = MemoryExpansionGadget::construct(
+            cb,
+            cb.curr.state.memory_word_size.expr(),
+            [memory_address.address()],
+        );
+        let gas_cost = GasCost::LOG.as_u64().expr()
+            + GasCost::LOG.as_u64().expr() * topic_count
+            + 8.expr() * memory_address.length()
+            + memory_expansion.gas_cost();
+        let insufficient_gas = LtGadget::construct(cb, cb.curr.state.gas_left.expr(), gas_cost);
+        cb.require_equal(
+            "memory address overflows or gas left is less than gas required",
+            or::expr([memory_address.overflow(), insufficient_gas.expr()]),
+            1.expr(),
+        );
+
+        let common_error_gadget = CommonErrorGadget::construct(cb, opcode.expr());
+
+        Self {
+            opcode,
+            memory_address,
+            memory_expansion,
+            insufficient_gas,
+            common_error_gadget,
+        }
+    }
+
+    fn assign_exec_step(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        block: &Block<F>,
+        _: &Transaction,
+        call: &Call,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        let opcode = step.opcode.unwrap();
+        self.opcode
+            .assign(region, offset, Value::known(F::from(opcode.as_u64())))?;
+
+        let [memory_offset, memory_length] =
+            [0, 1].map(|idx| block.rws[step.rw_indices[idx]].stack_value());
+        let (memory_address, memory_length) =
+            self.memory_address
+                .assign(region, offset, memory_offset, memory_length)?;
+
+        let (_, memory_expansion_gas_cost) = self.memory_expansion.assign(
+            region,
+            offset,
+            step.memory_word_size(),
+            [memory_address],
+        )?;
+        let topic_count = opcode.as_u64() - OpcodeId::LOG0.as_u64();
+        let gas_cost = GasCost::LOG.as_u64()
+            + GasCost::LOG.as_u64() * topic_count
+            + 8 * memory_length
+            + memory_expansion_gas_cost;
+        self.insufficient_gas
+            .assign(region, offset, F::from(step.gas_left), F::from(gas_cost))?;
+
+        self.common_error_gadget
+            .assign(region, offset, block, call, step, 2)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_util::run_test_circuits;
+    use eth_types::{
+        address, bytecode, evm_types::OpcodeId, geth_types::Account, Bytecode, ToWord, Word,
+    };
+    use mock::{eth, TestContext};
+
+    fn test_oog_log(opcode: OpcodeId, memory_offset: Word, length: Word, is_root: bool) {
+        let mut code = Bytecode::default();
+        let topic_count = opcode.as_u64() - OpcodeId::LOG0.as_u64();
+        for topic in 0..topic_count {
+            code.push(32, Word::from(topic));
+        }
+        code.append(&bytecode! {
+            PUSH32(length)
+            PUSH32(memory_offset)
+        });
+        code.write_op(opcode);
+        code.write_op(OpcodeId::STOP);
+        let contract = Account {
+            address: address!("0x00000000000000000000000000000000000cafe2"),
+            balance: eth(10),
+            code: code.into(),
+            ..Default::default()
+        };
+        // Gas left before LOGN is 2000, which is less than the cost to log
+        // 0x100 bytes.
+        let gas = 3 * (topic_count + 2) + 2000;
+        let caller = Account {
+            address: address!("0x00000000000000000000000000000000000cafe1"),
+            balance: eth(10),
+            code: bytecode! {
+                PUSH1(0) // retLength
+                PUSH1(0) // retOffset
+                PUSH1(0) // argsLength
+                PUSH1(0) // argsOffset
+                PUSH1(0) // value
+                PUSH20(contract.address.to_word())
+                PUSH2(gas)
+                CALL
+                STOP
+            }
+            .into(),
+            ..Default::default()
+        };
+
+        let ctx = TestContext::<3, 1>::new(
+            None,
+            |accs| {
+                accs[0].account(&caller);
+                accs[1].account(&contract);
+                accs[2]
+                    .address(address!("0x000000000000000000000000000000000000cafe"))
+                    .balance(eth(10));
+            },
+            |mut txs, accs| {
+                if is_root {
+                    txs[0]
+                        .from(accs[2].address)
+                        .to(accs[1].address)
+                        .gas(Word::from(21_000u64 + gas));
+                } else {
+                    txs[0].from(accs[2].address).to(accs[0].address);
+                }
+            },
+            |block, _| block,
+        )
+        .unwrap();
+
+        assert_eq!(run_test_circuits(ctx, None), Ok(()));
+    }
+
+    fn test_cases() -> Vec<(OpcodeId, Word, Word)> {
+        [
+            OpcodeId::LOG0,
+            OpcodeId::LOG1,
+            OpcodeId::LOG2,
+            OpcodeId::LOG3,
+            OpcodeId::LOG4,
+        ]
+        .into_iter()
+        .flat_map(|opcode| {
+            [
+                // insufficient gas
+                (opcode, Word::from(0x20), Word::from(0x100)),
+                // memory offset overflows u64
+                (opcode, Word::from(u64::MAX) + 1, Word::from(0x20)),
+            ]
+        })
+        .collect()
+    }
+
+    #[test]
+    fn error_oog_log_root() {
+        for (opcode, memory_offset, length) in test_cases() {
+            test_oog_log(opcode, memory_offset, length, true);
+        }
+    }
+
+    #[test]
+    fn error_oog_log_internal() {
+        for (opcode, memory_offset, length) in test_cases() {
+            test_oog_log(opcode, memory_offset, length, false);
+        }
+    }
+}
@@ -0,0 +1,252 @@
+use crate::{
+    evm_circuit::{
+        execution::ExecutionGadget,
+        param::{N_BYTES_GAS, N_BYTES_MEMORY_WORD_SIZE},
+        step::ExecutionState,
+        util::{
+            common_gadget::CommonErrorGadget,
+            constraint_builder::ConstraintBuilder,
+            math_gadget::LtGadget,
+            memory_gadget::{
+                MemoryCopierGasGadget, MemoryExpandedAddressGadget, MemoryExpansionGadget,
+            },
+            or, CachedRegion, Cell, Word,
+        },
+        witness::{Block, Call, ExecStep, Transaction},
+    },
+    util::Expr,
+};
+use eth_types::{
+    evm_types::{GasCost, OpcodeId},
+    Field, ToLittleEndian,
+};
+use halo2_proofs::{circuit::Value, plonk::Error};
+
+/// Gadget for CALLDATACOPY, CODECOPY and RETURNDATACOPY which fail because the
+/// gas left is not enough to pay for the memory expansion and the copy, or the
+/// memory address overflows.
+#[derive(Clone, Debug)]
+pub(crate) struct ErrorOOGMemoryCopyGadget<F> {
+    opcode: Cell<F>,
+    data_offset: Cell<F>,
+    memory_address: MemoryExpandedAddressGadget<F>,
+    memory_expansion: MemoryExpansionGadget<F, 1, N_BYTES_MEMORY_WORD_SIZE>,
+    memory_copier_gas: MemoryCopierGasGadget<F, { GasCost::COPY }>,
+    // constrain gas left is less than required
+    insufficient_gas: LtGadget<F, N_BYTES_GAS>,
+    common_error_gadget: CommonErrorGadget<F>,
+}
+
+impl<F: Field> ExecutionGadget<F> for ErrorOOGMemoryCopyGadget<F> {
+    const NAME: &'static str = "ErrorOutOfGasMemoryCopy";
+
+    const EXECUTION_STATE: ExecutionState = ExecutionState::ErrorOutOfGasMemoryCopy;
+
+    fn configure(cb: &mut ConstraintBuilder<F>) -> Self {
+        let opcode = cb.query_cell();
+        cb.require_in_set(
+            "ErrorOutOfGasMemoryCopy opcode must be CALLDATACOPY, CODECOPY or RETURNDATACOPY",
+            opcode.expr(),
+            vec![
+                OpcodeId::CALLDATACOPY.expr(),
+                OpcodeId::CODECOPY.expr(),
+                OpcodeId::RETURNDATACOPY.expr(),
+            ],
+        );
+
+        let memory_address = MemoryExpandedAddressGadget::construct(cb);
+        let data_offset = cb.query_cell();
+        cb.stack_pop(memory_address.offset_rlc());
+        cb.stack_pop(data_offset.expr());
+        cb.stack_pop(memory_address.length_rlc());
+
+        let memory_expansion = MemoryExpansionGadget::construct(
+            cb,
+            cb.curr.state.memory_word_size.expr(),
+            [memory_address.address()],
+        );
+        let memory_copier_gas = MemoryCopierGasGadget::construct(
+            cb,
+            memory_address.length(),
+            memory_expansion.gas_cost(),
+        );
+        // CALLDATACOPY, CODECOPY and RETURNDATACOPY have the same constant gas
+        // cost.
+        let insufficient_gas = LtGadget::construct(
+            cb,
+            cb.curr.state.gas_left.expr(),
+            GasCost::FASTEST.expr() + memory_copier_gas.gas_cost(),
+        );
+        cb.require_equal(
+            "memory address overflows or gas left is less than gas required",
+            or::expr([memory_address.overflow(), insufficient_gas.expr()]),
+            1.expr(),
+        );
+
+        let common_error_gadget = CommonErrorGadget::construct(cb, opcode.expr());
+
+        Self {
+            opcode,
+            data_offset,
+            memory_address,
+            memory_expansion,
+            memory_copier_gas,
+            insufficient_gas,
+            common_error_gadget,
+        }
+    }
+
+    fn assign_exec_step(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        block: &Block<F>,
+        _: &Transaction,
+        call: &Call,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        let opcode = step.opcode.unwrap();
+        self.opcode
+            .assign(region, offset, Value::known(F::from(opcode.as_u64())))?;
+
+        let [memory_offset, data_offset, memory_length] =
+            [0, 1, 2].map(|idx| block.rws[step.rw_indices[idx]].stack_value());
+        self.data_offset.assign(
+            region,
+            offset,
+            Value::known(Word::random_linear_combine(
+                data_offset.to_le_bytes(),
+                block.randomness,
+            )),
+        )?;
+        let (memory_address, memory_length) =
+            self.memory_address
+                .assign(region, offset, memory_offset, memory_length)?;
+
+        let (_, memory_expansion_gas_cost) = self.memory_expansion.assign(
+            region,
+            offset,
+            step.memory_word_size(),
+            [memory_address],
+        )?;
+        let memory_copier_gas = self.memory_copier_gas.assign(
+            region,
+            offset,
+            memory_length,
+            memory_expansion_gas_cost,
+        )?;
+        self.insufficient_gas.assign(
+            region,
+            offset,
+            F::from(step.gas_left),
+            F::from(GasCost::FASTEST.as_u64() + memory_copier_gas),
+        )?;
+
+        self.common_error_gadget
+            .assign(region, offset, block, call, step, 3)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_util::run_test_circuits;
+    use eth_types::{address, bytecode, evm_types::OpcodeId, geth_types::Account, ToWord, Word};
+    use mock::{eth, TestContext};
+
+    fn test_oog_memory_copy(opcode: OpcodeId, memory_offset: Word, length: Word, is_root: bool) {
+        let mut code = bytecode! {
+            PUSH32(length)
+            PUSH32(0x00) // data_offset
+            PUSH32(memory_offset)
+        };
+        code.write_op(opcode);
+        code.write_op(OpcodeId::STOP);
+        let contract = Account {
+            address: address!("0x00000000000000000000000000000000000cafe2"),
+            balance: eth(10),
+            code: code.into(),
+            ..Default::default()
+        };
+        // Gas left before the copy is 3000, which is less than the cost to
+        // copy 0x10000 bytes.
+        let gas = 9 + 3000;
+        let caller = Account {
+            address: address!("0x00000000000000000000000000000000000cafe1"),
+            balance: eth(10),
+            code: bytecode! {
+                PUSH1(0) // retLength
+                PUSH1(0) // retOffset
+                PUSH1(0) // argsLength
+                PUSH1(0) // argsOffset
+                PUSH1(0) // value
+                PUSH20(contract.address.to_word())
+                PUSH2(gas)
+                CALL
+                STOP
+            }
+            .into(),
+            ..Default::default()
+        };
+
+        let ctx = TestContext::<3, 1>::new(
+            None,
+            |accs| {
+                accs[0].account(&caller);
+                accs[1].account(&contract);
+                accs[2]
+                    .address(address!("0x000000000000000000000000000000000000cafe"))
+                    .balance(eth(10));
+            },
+            |mut txs, accs| {
+                if is_root {
+                    txs[0]
+                        .from(accs[2].address)
+                        .to(accs[1].address)
+                        .gas(Word::from(21_000u64 + gas));
+                } else {
+                    txs[0].from(accs[2].address).to(accs[0].address);
+                }
+            },
+            |block, _| block,
+        )
+        .unwrap();
+
+        assert_eq!(run_test_circuits(ctx, None), Ok(()));
+    }
+
+    fn test_cases() -> Vec<(OpcodeId, Word, Word)> {
+        [
+            OpcodeId::CALLDATACOPY,
+            OpcodeId::CODECOPY,
+            OpcodeId::RETURNDATACOPY,
+        ]
+        .into_iter()
+        .flat_map(|opcode| {
+            [
+                // insufficient gas
+                (opcode, Word::from(0x20), Word::from(0x10000)),
+                // memory offset overflows u64
+                (opcode, Word::from(u64::MAX) + 1, Word::from(0x20)),
+                // expanded address overflows the maximum
+                (opcode, Word::from(0x1FFFFFFFE0u64), Word::from(0x01)),
+                // expanded address overflows u256
+                (opcode, Word::MAX, Word::MAX),
+            ]
+        })
+        .collect()
+    }
+
+    #[test]
+    fn error_oog_memory_copy_root() {
+        for (opcode, memory_offset, length) in test_cases() {
+            test_oog_memory_copy(opcode, memory_offset, length, true);
+        }
+    }
+
+    #[test]
+    fn error_oog_memory_copy_internal() {
+        for (opcode, memory_offset, length) in test_cases() {
+            test_oog_memory_copy(opcode, memory_offset, length, false);
+        }
+    }
+}
@@ -0,0 +1,277 @@
+use crate::{
+    evm_circuit::{
+        execution::ExecutionGadget,
+        param::{N_BYTES_ACCOUNT_ADDRESS, N_BYTES_GAS},
+        step::ExecutionState,
+        util::{
+            common_gadget::CommonErrorGadget,
+            constraint_builder::ConstraintBuilder,
+            from_bytes,
+            math_gadget::{BatchedIsZeroGadget, IsEqualGadget, IsZeroGadget, LtGadget},
+            not, select, sum, CachedRegion, Cell, Word,
+        },
+        witness::{Block, Call, ExecStep, Transaction},
+    },
+    table::{AccountFieldTag, CallContextFieldTag},
+    util::Expr,
+};
+use bus_mapping::evm::OpcodeId;
+use eth_types::{evm_types::GasCost, Field, ToLittleEndian, ToScalar};
+use halo2_proofs::{circuit::Value, plonk::Error};
+use keccak256::EMPTY_HASH_LE;
+
+/// Gadget for SELFDESTRUCT which fails because the gas left is not enough to
+/// pay for the access of the beneficiary and the possible account creation.
+#[derive(Clone, Debug)]
+pub(crate) struct ErrorOOGSelfDestructGadget<F> {
+    opcode: Cell<F>,
+    beneficiary: Word<F>,
+    tx_id: Cell<F>,
+    current_address: Cell<F>,
+    is_warm: Cell<F>,
+    balance: Word<F>,
+    beneficiary_nonce: Cell<F>,
+    beneficiary_balance: Word<F>,
+    beneficiary_code_hash: Cell<F>,
+    balance_is_zero: IsZeroGadget<F>,
+    is_empty_nonce_and_balance: BatchedIsZeroGadget<F, 2>,
+    is_empty_code_hash: IsEqualGadget<F>,
+    // constrain gas left is less than required
+    insufficient_gas: LtGadget<F, N_BYTES_GAS>,
+    common_error_gadget: CommonErrorGadget<F>,
+}
+
+impl<F: Field> ExecutionGadget<F> for ErrorOOGSelfDestructGadget<F> {
+    const NAME: &'static str = "ErrorOutOfGasSELFDESTRUCT";
+
+    const EXECUTION_STATE: ExecutionState = ExecutionState::ErrorOutOfGasSELFDESTRUCT;
+
+    fn configure(cb: &mut ConstraintBuilder<F>) -> Self {
+        let opcode = cb.query_cell();
+        cb.require_equal(
+            "ErrorOutOfGasSELFDESTRUCT opcode must be SELFDESTRUCT",
+            opcode.expr(),
+            OpcodeId::SELFDESTRUCT.expr(),
+        );
+
+        let beneficiary = cb.query_word();
+        cb.stack_pop(beneficiary.expr());
+        let beneficiary_address = from_bytes::expr(&beneficiary.cells[..N_BYTES_ACCOUNT_ADDRESS]);
+
+        let [tx_id, current_address] = [
+            CallContextFieldTag::TxId,
+            CallContextFieldTag::CalleeAddress,
+        ]
+        .map(|field_tag| cb.call_context(None, field_tag));
+
+        let is_warm = cb.query_bool();
+        cb.account_access_list_read(tx_id.expr(), beneficiary_address.clone(), is_warm.expr());
+
+        let balance = cb.query_word();
+        cb.account_read(
+            current_address.expr(),
+            AccountFieldTag::Balance,
+            balance.expr(),
+        );
+
+        let beneficiary_nonce = cb.query_cell();
+        let beneficiary_balance = cb.query_word();
+        let beneficiary_code_hash = cb.query_cell();
+        for (field_tag, value) in [
+            (AccountFieldTag::Nonce, beneficiary_nonce.expr()),
+            (AccountFieldTag::Balance, beneficiary_balance.expr()),
+            (AccountFieldTag::CodeHash, beneficiary_code_hash.expr()),
+        ] {
+            cb.account_read(beneficiary_address.clone(), field_tag, value);
+        }
+
+        let balance_is_zero = IsZeroGadget::construct(cb, sum::expr(&balance.cells));
+        let is_empty_nonce_and_balance = BatchedIsZeroGadget::construct(
+            cb,
+            [beneficiary_nonce.expr(), beneficiary_balance.expr()],
+        );
+        let is_empty_code_hash = IsEqualGadget::construct(
+            cb,
+            beneficiary_code_hash.expr(),
+            Word::random_linear_combine_expr(
+                (*EMPTY_HASH_LE).map(|byte| byte.expr()),
+                cb.power_of_randomness(),
+            ),
+        );
+        let gas_cost = GasCost::SELFDESTRUCT.expr()
+            + select::expr(
+                is_warm.expr(),
+                0.expr(),
+                GasCost::COLD_ACCOUNT_ACCESS.expr(),
+            )
+            + not::expr(balance_is_zero.expr())
+                * is_empty_nonce_and_balance.expr()
+                * is_empty_code_hash.expr()
+                * GasCost::NEW_ACCOUNT.expr();
+
+        let insufficient_gas = LtGadget::construct(cb, cb.curr.state.gas_left.expr(), gas_cost);
+        cb.require_equal(
+            "gas left is less than gas required",
+            insufficient_gas.expr(),
+            1.expr(),
+        );
+
+        let common_error_gadget = CommonErrorGadget::construct(cb, opcode.expr());
+
+        Self {
+            opcode,
+            beneficiary,
+            tx_id,
+            current_address,
+            is_warm,
+            balance,
+            beneficiary_nonce,
+            beneficiary_balance,
+            beneficiary_code_hash,
+            balance_is_zero,
+            is_empty_nonce_and_balance,
+            is_empty_code_hash,
+            insufficient_gas,
+            common_error_gadget,
+        }
+    }
+
+    fn assign_exec_step(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        block: &Block<F>,
+        _tx: &Transaction,
+        call: &Call,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        let opcode = step.opcode.unwrap();
+        self.opcode
+            .assign(region, offset, Value::known(F::from(opcode.as_u64())))?;
+
+        let beneficiary = block.rws[step.rw_indices[0]].stack_value();
+        let [tx_id, current_address] =
+            [1, 2].map(|idx| block.rws[step.rw_indices[idx]].call_context_value());
+        let (is_warm, _) = block.rws[step.rw_indices[3]].tx_access_list_value_pair();
+        let [(balance, _), (beneficiary_nonce, _), (beneficiary_balance, _), (beneficiary_code_hash, _)] =
+            [4, 5, 6, 7].map(|idx| block.rws[step.rw_indices[idx]].account_value_pair());
+
+        self.beneficiary
+            .assign(region, offset, Some(beneficiary.to_le_bytes()))?;
+        self.tx_id
+            .assign(region, offset, Value::known(F::from(tx_id.low_u64())))?;
+        self.current_address.assign(
+            region,
+            offset,
+            Value::known(
+                current_address
+                    .to_scalar()
+                    .expect("unexpected Address -> Scalar conversion failure"),
+            ),
+        )?;
+        self.is_warm
+            .assign(region, offset, Value::known(F::from(is_warm as u64)))?;
+        self.balance
+            .assign(region, offset, Some(balance.to_le_bytes()))?;
+        self.beneficiary_nonce.assign(
+            region,
+            offset,
+            Value::known(
+                beneficiary_nonce
+                    .to_scalar()
+                    .expect("unexpected U256 -> Scalar conversion failure"),
+            ),
+        )?;
+        self.beneficiary_balance
+            .assign(region, offset, Some(beneficiary_balance.to_le_bytes()))?;
+        self.beneficiary_code_hash.assign(
+            region,
+            offset,
+            Value::known(Word::random_linear_combine(
+                beneficiary_code_hash.to_le_bytes(),
+                block.randomness,
+            )),
+        )?;
+
+        self.balance_is_zero
+            .assign(region, offset, sum::value(&balance.to_le_bytes()))?;
+        let is_empty_nonce_and_balance = self.is_empty_nonce_and_balance.assign(
+            region,
+            offset,
+            [
+                F::from(beneficiary_nonce.low_u64()),
+                Word::random_linear_combine(beneficiary_balance.to_le_bytes(), block.randomness),
+            ],
+        )?;
+        let is_empty_code_hash = self.is_empty_code_hash.assign(
+            region,
+            offset,
+            Word::random_linear_combine(beneficiary_code_hash.to_le_bytes(), block.randomness),
+            Word::random_linear_combine(*EMPTY_HASH_LE, block.randomness),
+        )?;
+        let is_empty_account = is_empty_nonce_and_balance * is_empty_code_hash == F::one();
+
+        let gas_cost = GasCost::SELFDESTRUCT.as_u64()
+            + if is_warm {
+                0
+            } else {
+                GasCost::COLD_ACCOUNT_ACCESS.as_u64()
+            }
+            + if !balance.is_zero() && is_empty_account {
+                GasCost::NEW_ACCOUNT.as_u64()
+            } else {
+                0
+            };
+        self.insufficient_gas
+            .assign(region, offset, F::from(step.gas_left), F::from(gas_cost))?;
+
+        self.common_error_gadget
+            .assign(region, offset, block, call, step, 8)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_util::run_test_circuits;
+    use eth_types::{address, bytecode, geth_types::Account, Address, ToWord, Word};
+    use mock::{eth, test_ctx::helpers::root_or_internal_call_ctx};
+
+    fn test_oog_self_destruct(beneficiary: Address, is_root: bool) {
+        let code = bytecode! {
+            PUSH20(beneficiary.to_word())
+            SELFDESTRUCT
+        };
+        let contract = Account {
+            address: address!("0x00000000000000000000000000000000000cafe2"),
+            balance: eth(10),
+            nonce: Word::one(),
+            code: code.into(),
+            ..Default::default()
+        };
+        // In the root call, PUSH20 and the constant gas of SELFDESTRUCT are
+        // paid, but not the cold access of beneficiary.  In an internal call,
+        // only 5000 gas is given, which is not enough even for a warm
+        // beneficiary.
+        let gas = if is_root { 5003 } else { 5000 };
+        let ctx = root_or_internal_call_ctx(contract, gas, is_root).unwrap();
+
+        assert_eq!(run_test_circuits(ctx, None), Ok(()));
+    }
+
+    #[test]
+    fn error_oog_self_destruct_root() {
+        test_oog_self_destruct(address!("0x0000000000000000000000000000000000000bee"), true);
+    }
+
+    #[test]
+    fn error_oog_self_destruct_internal() {
+        test_oog_self_destruct(
+            address!("0x0000000000000000000000000000000000000bee"),
+            false,
+        );
+        test_oog_self_destruct(
+            address!("0x000000000000000000000000000000000000cafe"),
+            false,
+        );
+    }
+}
@@ -0,0 +1,250 @@
+use crate::{
+    evm_circuit::{
+        execution::{sload::SloadGasGadget, ExecutionGadget},
+        param::N_BYTES_GAS,
+        step::ExecutionState,
+        util::{
+            common_gadget::CommonErrorGadget, constraint_builder::ConstraintBuilder,
+            math_gadget::LtGadget, CachedRegion, Cell, Word,
+        },
+        witness::{Block, Call, ExecStep, Transaction},
+    },
+    table::CallContextFieldTag,
+    util::Expr,
+};
+use eth_types::{
+    evm_types::{GasCost, OpcodeId},
+    Field, ToLittleEndian, ToScalar,
+};
+use halo2_proofs::{circuit::Value, plonk::Error};
+
+/// Gadget for SLOAD which fails because the gas left is not enough to pay for
+/// the storage slot access.
+#[derive(Clone, Debug)]
+pub(crate) struct ErrorOOGSloadGadget<F> {
+    opcode: Cell<F>,
+    tx_id: Cell<F>,
+    callee_address: Cell<F>,
+    key: Cell<F>,
+    value: Cell<F>,
+    committed_value: Cell<F>,
+    is_warm: Cell<F>,
+    // constrain gas left is less than required
+    insufficient_gas: LtGadget<F, N_BYTES_GAS>,
+    common_error_gadget: CommonErrorGadget<F>,
+}
+
+impl<F: Field> ExecutionGadget<F> for ErrorOOGSloadGadget<F> {
+    const NAME: &'static str = "ErrorOutOfGasSLOAD";
+
+    const EXECUTION_STATE: ExecutionState = ExecutionState::ErrorOutOfGasSLOAD;
+
+    fn configure(cb: &mut ConstraintBuilder<F>) -> Self {
+        let opcode = cb.query_cell();
+        cb.require_equal(
+            "ErrorOutOfGasSLOAD opcode must be SLOAD",
+            opcode.expr(),
+            OpcodeId::SLOAD.expr(),
+        );
+
+        let tx_id = cb.call_context(None, CallContextFieldTag::TxId);
+        let callee_address = cb.call_context(None, CallContextFieldTag::CalleeAddress);
+
+        let key = cb.query_cell();
+        cb.stack_pop(key.expr());
+
+        let value = cb.query_cell();
+        let committed_value = cb.query_cell();
+        cb.account_storage_read(
+            callee_address.expr(),
+            key.expr(),
+            value.expr(),
+            tx_id.expr(),
+            committed_value.expr(),
+        );
+
+        let is_warm = cb.query_bool();
+        cb.account_storage_access_list_read(
+            tx_id.expr(),
+            callee_address.expr(),
+            key.expr(),
+            is_warm.expr(),
+        );
+
+        let gas_cost = SloadGasGadget::construct(cb, is_warm.expr()).expr();
+        let insufficient_gas = LtGadget::construct(cb, cb.curr.state.gas_left.expr(), gas_cost);
+        cb.require_equal(
+            "gas left is less than gas required",
+            insufficient_gas.expr(),
+            1.expr(),
+        );
+
+        let common_error_gadget = CommonErrorGadget::construct(cb, opcode.expr());
+
+        Self {
+            opcode,
+            tx_id,
+            callee_address,
+            key,
+            value,
+            committed_value,
+            is_warm,
+            insufficient_gas,
+            common_error_gadget,
+        }
+    }
+
+    fn assign_exec_step(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        block: &Block<F>,
+        tx: &Transaction,
+        call: &Call,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        let opcode = step.opcode.unwrap();
+        self.opcode
+            .assign(region, offset, Value::known(F::from(opcode.as_u64())))?;
+
+        self.tx_id
+            .assign(region, offset, Value::known(F::from(tx.id as u64)))?;
+        self.callee_address.assign(
+            region,
+            offset,
+            Value::known(
+                call.callee_address
+                    .to_scalar()
+                    .expect("unexpected Address -> Scalar conversion failure"),
+            ),
+        )?;
+
+        let key = block.rws[step.rw_indices[2]].stack_value();
+        let (_, value, _, committed_value) = block.rws[step.rw_indices[3]].storage_value_aux();
+        for (cell, word) in [
+            (&self.key, key),
+            (&self.value, value),
+            (&self.committed_value, committed_value),
+        ] {
+            cell.assign(
+                region,
+                offset,
+                Value::known(Word::random_linear_combine(
+                    word.to_le_bytes(),
+                    block.randomness,
+                )),
+            )?;
+        }
+
+        let (is_warm, _) = block.rws[step.rw_indices[4]].tx_access_list_value_pair();
+        self.is_warm
+            .assign(region, offset, Value::known(F::from(is_warm as u64)))?;
+
+        let gas_cost = if is_warm {
+            GasCost::WARM_ACCESS
+        } else {
+            GasCost::COLD_SLOAD
+        };
+        self.insufficient_gas.assign(
+            region,
+            offset,
+            F::from(step.gas_left),
+            F::from(gas_cost.as_u64()),
+        )?;
+
+        self.common_error_gadget
+            .assign(region, offset, block, call, step, 5)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_util::run_test_circuits;
+    use eth_types::{address, bytecode, geth_types::Account, Bytecode, ToWord, Word};
+    use mock::{eth, TestContext};
+
+    fn test_oog_sload(is_warm: bool, is_root: bool) {
+        let key = Word::from(0x1234);
+        let mut code = Bytecode::default();
+        if is_warm {
+            code.append(&bytecode! {
+                PUSH32(key)
+                SLOAD
+                POP
+            });
+        }
+        code.append(&bytecode! {
+            PUSH32(key)
+            SLOAD
+            STOP
+        });
+        let contract = Account {
+            address: address!("0x00000000000000000000000000000000000cafe2"),
+            balance: eth(10),
+            code: code.into(),
+            storage: [(key, Word::from(0xcafe))].into(),
+            ..Default::default()
+        };
+        // Gas left before the last SLOAD is 2000 for the cold case and 99 for
+        // the warm one, which is less than the cost.
+        let gas = if is_warm {
+            3 + 2100 + 2 + 3 + 99
+        } else {
+            3 + 2000
+        };
+        let caller = Account {
+            address: address!("0x00000000000000000000000000000000000cafe1"),
+            balance: eth(10),
+            code: bytecode! {
+                PUSH1(0) // retLength
+                PUSH1(0) // retOffset
+                PUSH1(0) // argsLength
+                PUSH1(0) // argsOffset
+                PUSH1(0) // value
+                PUSH20(contract.address.to_word())
+                PUSH2(gas)
+                CALL
+                STOP
+            }
+            .into(),
+            ..Default::default()
+        };
+
+        let ctx = TestContext::<3, 1>::new(
+            None,
+            |accs| {
+                accs[0].account(&caller);
+                accs[1].account(&contract);
+                accs[2]
+                    .address(address!("0x000000000000000000000000000000000000cafe"))
+                    .balance(eth(10));
+            },
+            |mut txs, accs| {
+                if is_root {
+                    txs[0]
+                        .from(accs[2].address)
+                        .to(accs[1].address)
+                        .gas(Word::from(21_000u64 + gas));
+                } else {
+                    txs[0].from(accs[2].address).to(accs[0].address);
+                }
+            },
+            |block, _| block,
+        )
+        .unwrap();
+
+        assert_eq!(run_test_circuits(ctx, None), Ok(()));
+    }
+
+    #[test]
+    fn error_oog_sload_root() {
+        test_oog_sload(false, true);
+        test_oog_sload(true, true);
+    }
+
+    #[test]
+    fn error_oog_sload_internal() {
+        test_oog_sload(false, false);
+        test_oog_sload(true, false);
+    }
+}
@@ -0,0 +1,297 @@
+use crate::{
+    evm_circuit::{
+        execution::{
+            sstore::{calc_expected_gas_cost, SstoreGasGadget},
+            ExecutionGadget,
+        },
+        param::N_BYTES_GAS,
+        step::ExecutionState,
+        util::{
+            common_gadget::CommonErrorGadget, constraint_builder::ConstraintBuilder,
+            math_gadget::LtGadget, or, CachedRegion, Cell, Word,
+        },
+        witness::{Block, Call, ExecStep, Transaction},
+    },
+    table::CallContextFieldTag,
+    util::Expr,
+};
+use eth_types::{
+    evm_types::{GasCost, OpcodeId},
+    Field, ToLittleEndian, ToScalar,
+};
+use halo2_proofs::{circuit::Value, plonk::Error};
+
+/// Gadget for SSTORE which fails because the gas left is not more than the
+/// sentry of EIP-2200, or not enough to pay for the storage slot update.
+#[derive(Clone, Debug)]
+pub(crate) struct ErrorOOGSstoreGadget<F> {
+    opcode: Cell<F>,
+    tx_id: Cell<F>,
+    callee_address: Cell<F>,
+    key: Cell<F>,
+    value: Cell<F>,
+    value_prev: Cell<F>,
+    original_value: Cell<F>,
+    is_warm: Cell<F>,
+    gas_cost: SstoreGasGadget<F>,
+    // constrain gas left is not more than the sentry
+    insufficient_gas_sentry: LtGadget<F, N_BYTES_GAS>,
+    // constrain gas left is less than required
+    insufficient_gas_cost: LtGadget<F, N_BYTES_GAS>,
+    common_error_gadget: CommonErrorGadget<F>,
+}
+
+impl<F: Field> ExecutionGadget<F> for ErrorOOGSstoreGadget<F> {
+    const NAME: &'static str = "ErrorOutOfGasSSTORE";
+
+    const EXECUTION_STATE: ExecutionState = ExecutionState::ErrorOutOfGasSSTORE;
+
+    fn configure(cb: &mut ConstraintBuilder<F>) -> Self {
+        let opcode = cb.query_cell();
+        cb.require_equal(
+            "ErrorOutOfGasSSTORE opcode must be SSTORE",
+            opcode.expr(),
+            OpcodeId::SSTORE.expr(),
+        );
+
+        let tx_id = cb.call_context(None, CallContextFieldTag::TxId);
+        let callee_address = cb.call_context(None, CallContextFieldTag::CalleeAddress);
+
+        let key = cb.query_cell();
+        let value = cb.query_cell();
+        cb.stack_pop(key.expr());
+        cb.stack_pop(value.expr());
+
+        let value_prev = cb.query_cell();
+        let original_value = cb.query_cell();
+        cb.account_storage_read(
+            callee_address.expr(),
+            key.expr(),
+            value_prev.expr(),
+            tx_id.expr(),
+            original_value.expr(),
+        );
+
+        let is_warm = cb.query_bool();
+        cb.account_storage_access_list_read(
+            tx_id.expr(),
+            callee_address.expr(),
+            key.expr(),
+            is_warm.expr(),
+        );
+
+        let gas_cost = SstoreGasGadget::construct(
+            cb,
+            value.clone(),
+            value_prev.clone(),
+            original_value.clone(),
+            is_warm.clone(),
+        );
+
+        // SSTORE fails if the gas left is less than or equal to the sentry,
+        // even if the gas cost itself is lower.
+        let insufficient_gas_sentry = LtGadget::construct(
+            cb,
+            cb.curr.state.gas_left.expr(),
+            GasCost::SSTORE_SENTRY.expr() + 1.expr(),
+        );
+        let insufficient_gas_cost =
+            LtGadget::construct(cb, cb.curr.state.gas_left.expr(), gas_cost.expr());
+        cb.require_equal(
+            "gas left is not more than the sentry or less than gas required",
+            or::expr([insufficient_gas_sentry.expr(), insufficient_gas_cost.expr()]),
+            1.expr(),
+        );
+
+        let common_error_gadget = CommonErrorGadget::construct(cb, opcode.expr());
+
+        Self {
+            opcode,
+            tx_id,
+            callee_address,
+            key,
+            value,
+            value_prev,
+            original_value,
+            is_warm,
+            gas_cost,
+            insufficient_gas_sentry,
+            insufficient_gas_cost,
+            common_error_gadget,
+        }
+    }
+
+    fn assign_exec_step(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        block: &Block<F>,
+        tx: &Transaction,
+        call: &Call,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        let opcode = step.opcode.unwrap();
+        self.opcode
+            .assign(region, offset, Value::known(F::from(opcode.as_u64())))?;
+
+        self.tx_id
+            .assign(region, offset, Value::known(F::from(tx.id as u64)))?;
+        self.callee_address.assign(
+            region,
+            offset,
+            Value::known(
+                call.callee_address
+                    .to_scalar()
+                    .expect("unexpected Address -> Scalar conversion failure"),
+            ),
+        )?;
+
+        let [key, value] =
+            [step.rw_indices[2], step.rw_indices[3]].map(|idx| block.rws[idx].stack_value());
+        self.key.assign(
+            region,
+            offset,
+            Value::known(Word::random_linear_combine(
+                key.to_le_bytes(),
+                block.randomness,
+            )),
+        )?;
+
+        let (_, value_prev, _, original_value) = block.rws[step.rw_indices[4]].storage_value_aux();
+        let (is_warm, _) = block.rws[step.rw_indices[5]].tx_access_list_value_pair();
+
+        // The value, previous value, original value and warmth cells are
+        // assigned by the gas cost gadget.
+        let gas_cost = calc_expected_gas_cost(value, value_prev, original_value, is_warm);
+        self.gas_cost.assign(
+            region,
+            offset,
+            gas_cost,
+            value,
+            value_prev,
+            original_value,
+            is_warm,
+            block.randomness,
+        )?;
+
+        self.insufficient_gas_sentry.assign(
+            region,
+            offset,
+            F::from(step.gas_left),
+            F::from(GasCost::SSTORE_SENTRY.as_u64() + 1),
+        )?;
+        self.insufficient_gas_cost.assign(
+            region,
+            offset,
+            F::from(step.gas_left),
+            F::from(gas_cost),
+        )?;
+
+        self.common_error_gadget
+            .assign(region, offset, block, call, step, 6)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_util::run_test_circuits;
+    use eth_types::{address, bytecode, geth_types::Account, Bytecode, ToWord, Word};
+    use mock::{eth, TestContext};
+
+    const KEY: u64 = 0x1234;
+
+    fn test_oog_sstore(code: Bytecode, gas: u64, is_root: bool) {
+        let contract = Account {
+            address: address!("0x00000000000000000000000000000000000cafe2"),
+            balance: eth(10),
+            code: code.into(),
+            storage: [(Word::from(KEY), Word::from(0xcafe))].into(),
+            ..Default::default()
+        };
+        let caller = Account {
+            address: address!("0x00000000000000000000000000000000000cafe1"),
+            balance: eth(10),
+            code: bytecode! {
+                PUSH1(0) // retLength
+                PUSH1(0) // retOffset
+                PUSH1(0) // argsLength
+                PUSH1(0) // argsOffset
+                PUSH1(0) // value
+                PUSH20(contract.address.to_word())
+                PUSH32(gas)
+                CALL
+                STOP
+            }
+            .into(),
+            ..Default::default()
+        };
+
+        let ctx = TestContext::<3, 1>::new(
+            None,
+            |accs| {
+                accs[0].account(&caller);
+                accs[1].account(&contract);
+                accs[2]
+                    .address(address!("0x000000000000000000000000000000000000cafe"))
+                    .balance(eth(10));
+            },
+            |mut txs, accs| {
+                if is_root {
+                    txs[0]
+                        .from(accs[2].address)
+                        .to(accs[1].address)
+                        .gas(Word::from(21_000u64 + gas));
+                } else {
+                    txs[0].from(accs[2].address).to(accs[0].address);
+                }
+            },
+            |block, _| block,
+        )
+        .unwrap();
+
+        assert_eq!(run_test_circuits(ctx, None), Ok(()));
+    }
+
+    fn sstore_code(value: u64) -> Bytecode {
+        bytecode! {
+            PUSH32(value)
+            PUSH32(KEY)
+            SSTORE
+            STOP
+        }
+    }
+
+    fn warm_sstore_code(value: u64) -> Bytecode {
+        bytecode! {
+            PUSH32(KEY)
+            SLOAD
+            POP
+            PUSH32(value)
+            PUSH32(KEY)
+            SSTORE
+            STOP
+        }
+    }
+
+    #[test]
+    fn error_oog_sstore_sentry() {
+        // The cold no-op costs 2200, which is less than the sentry 2300.
+        for is_root in [true, false] {
+            test_oog_sstore(sstore_code(0xcafe), 6 + 2300, is_root);
+        }
+    }
+
+    #[test]
+    fn error_oog_sstore_root() {
+        // The cold reset costs 5000.
+        test_oog_sstore(sstore_code(0xbeef), 6 + 4999, true);
+        // The warm reset costs 2900.
+        test_oog_sstore(warm_sstore_code(0xbeef), 3 + 2100 + 2 + 6 + 2899, true);
+    }
+
+    #[test]
+    fn error_oog_sstore_internal() {
+        test_oog_sstore(sstore_code(0xbeef), 6 + 4999, false);
+        test_oog_sstore(warm_sstore_code(0xbeef), 3 + 2100 + 2 + 6 + 2899, false);
+    }
+}
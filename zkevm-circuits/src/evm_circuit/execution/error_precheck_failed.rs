@@ -0,0 +1,674 @@
+use crate::{
+    evm_circuit::{
+        execution::{
+            callop::{calc_call_gas_cost, call_gas_cost_expr},
+            ExecutionGadget,
+        },
+        param::{N_BYTES_ACCOUNT_ADDRESS, N_BYTES_GAS, N_BYTES_MEMORY_WORD_SIZE},
+        step::ExecutionState,
+        util::{
+            constraint_builder::{
+                ConstraintBuilder, ReversionInfo, StepStateTransition,
+                Transition::{Delta, To},
+            },
+            from_bytes,
+            math_gadget::{
+                BatchedIsZeroGadget, IsEqualGadget, IsZeroGadget, LtGadget, LtWordGadget,
+                RangeCheckGadget,
+            },
+            memory_gadget::{MemoryAddressGadget, MemoryCopierGasGadget, MemoryExpansionGadget},
+            not, select, sum, CachedRegion, Cell, Word,
+        },
+        witness::{Block, Call, ExecStep, Transaction},
+    },
+    table::{AccountFieldTag, CallContextFieldTag},
+    util::Expr,
+};
+use eth_types::{
+    evm_types::{GasCost, OpcodeId, GAS_STIPEND_CALL_WITH_VALUE},
+    Field, ToLittleEndian, ToScalar, U256,
+};
+use halo2_proofs::{circuit::Value, plonk::Error};
+use keccak256::EMPTY_HASH_LE;
+
+/// The maximum depth of a call which can still call or create.
+const MAX_CALL_DEPTH: u64 = 1024;
+
+/// Gadget for CALL, CALLCODE, DELEGATECALL, STATICCALL, CREATE and CREATE2
+/// which fail the precheck of the callee, because the depth of the current
+/// call is over the limit, or the current account can't afford the value to
+/// transfer.
+///
+/// The current call doesn't halt.  The gas cost of the call is charged and the
+/// code address is added into the access list as a successful call, but the
+/// gas passed to the callee is returned, 0 is pushed onto the stack and the
+/// return data of the current call is cleared.  CREATE and CREATE2 only charge
+/// their gas cost, since they fail before the nonce of the current account is
+/// increased and the new address is added into the access list.
+#[derive(Clone, Debug)]
+pub(crate) struct ErrorPrecheckFailedGadget<F, const S: ExecutionState> {
+    opcode: Cell<F>,
+    is_call: IsZeroGadget<F>,
+    is_callcode: IsZeroGadget<F>,
+    is_delegatecall: IsZeroGadget<F>,
+    is_staticcall: IsZeroGadget<F>,
+    is_create: IsZeroGadget<F>,
+    is_create2: IsZeroGadget<F>,
+    tx_id: Cell<F>,
+    reversion_info: ReversionInfo<F>,
+    current_address: Cell<F>,
+    is_static: Cell<F>,
+    depth: Cell<F>,
+    gas: Word<F>,
+    code_address: Word<F>,
+    value: Word<F>,
+    salt: Word<F>,
+    value_is_zero: IsZeroGadget<F>,
+    // call data for calls, and init code for creates
+    cd_address: MemoryAddressGadget<F>,
+    rd_address: MemoryAddressGadget<F>,
+    memory_expansion: MemoryExpansionGadget<F, 2, N_BYTES_MEMORY_WORD_SIZE>,
+    // CREATE2 also pays for hashing the init code
+    memory_copier_gas: MemoryCopierGasGadget<F, { GasCost::COPY_SHA3 }>,
+    is_warm_prev: Cell<F>,
+    callee_nonce: Cell<F>,
+    callee_balance: Cell<F>,
+    callee_code_hash: Cell<F>,
+    is_empty_nonce_and_balance: BatchedIsZeroGadget<F, 2>,
+    is_empty_code_hash: IsEqualGadget<F>,
+    sufficient_gas_left: RangeCheckGadget<F, N_BYTES_GAS>,
+    // constrain the depth is within the limit for insufficient balance, and
+    // over the limit for depth
+    depth_ok: LtGadget<F, 2>,
+    caller_balance: Word<F>,
+    // constrain the balance is less than the value to transfer
+    insufficient_balance: LtWordGadget<F>,
+}
+
+impl<F: Field, const S: ExecutionState> ExecutionGadget<F> for ErrorPrecheckFailedGadget<F, S> {
+    const NAME: &'static str = "ErrorPrecheckFailed";
+
+    const EXECUTION_STATE: ExecutionState = S;
+
+    fn configure(cb: &mut ConstraintBuilder<F>) -> Self {
+        let opcode = cb.query_cell();
+        cb.opcode_lookup(opcode.expr(), 1.expr());
+
+        let [is_call, is_callcode, is_delegatecall, is_staticcall, is_create, is_create2] = [
+            OpcodeId::CALL,
+            OpcodeId::CALLCODE,
+            OpcodeId::DELEGATECALL,
+            OpcodeId::STATICCALL,
+            OpcodeId::CREATE,
+            OpcodeId::CREATE2,
+        ]
+        .map(|opcode_id| IsZeroGadget::construct(cb, opcode.expr() - opcode_id.expr()));
+        // Only CALL, CALLCODE, CREATE and CREATE2 transfer value, so only they
+        // can fail because of insufficient balance.
+        let opcode_sum = match S {
+            ExecutionState::ErrorDepth => {
+                is_call.expr()
+                    + is_callcode.expr()
+                    + is_delegatecall.expr()
+                    + is_staticcall.expr()
+                    + is_create.expr()
+                    + is_create2.expr()
+            }
+            ExecutionState::ErrorInsufficientBalance => {
+                is_call.expr() + is_callcode.expr() + is_create.expr() + is_create2.expr()
+            }
+            _ => unreachable!(
+                "ErrorPrecheckFailedGadget only handles depth and insufficient balance"
+            ),
+        };
+        cb.require_equal(
+            "Opcode should be a call or create related opcode of the error",
+            opcode_sum,
+            1.expr(),
+        );
+        let has_value_arg = is_call.expr() + is_callcode.expr();
+        let is_create_family = is_create.expr() + is_create2.expr();
+        let is_call_family = 1.expr() - is_create_family.clone();
+
+        let tx_id = cb.call_context(None, CallContextFieldTag::TxId);
+        let mut reversion_info = cb.reversion_info_read(None);
+        let [current_address, is_static, depth] = [
+            CallContextFieldTag::CalleeAddress,
+            CallContextFieldTag::IsStatic,
+            CallContextFieldTag::Depth,
+        ]
+        .map(|field_tag| cb.call_context(None, field_tag));
+
+        let gas = cb.query_word();
+        let code_address = cb.query_word();
+        let value = cb.query_word();
+        let salt = cb.query_word();
+        let cd_offset = cb.query_cell();
+        let cd_length = cb.query_rlc();
+        let rd_offset = cb.query_cell();
+        let rd_length = cb.query_rlc();
+
+        // Lookup values from stack, and push 0 as the result.
+        cb.condition(is_call_family.clone(), |cb| {
+            cb.stack_lookup(false.expr(), 0.expr(), gas.expr());
+            cb.stack_lookup(false.expr(), 1.expr(), code_address.expr());
+        });
+        cb.condition(has_value_arg.clone(), |cb| {
+            cb.stack_lookup(false.expr(), 2.expr(), value.expr());
+        });
+        cb.condition(is_delegatecall.expr() + is_staticcall.expr(), |cb| {
+            cb.require_zero(
+                "DELEGATECALL and STATICCALL transfer no value",
+                value.expr(),
+            );
+        });
+        cb.condition(is_call_family.clone(), |cb| {
+            for (idx, rlc) in [
+                cd_offset.expr(),
+                cd_length.expr(),
+                rd_offset.expr(),
+                rd_length.expr(),
+            ]
+            .into_iter()
+            .enumerate()
+            {
+                cb.stack_lookup(
+                    false.expr(),
+                    2.expr() + has_value_arg.clone() + idx.expr(),
+                    rlc,
+                );
+            }
+            cb.stack_lookup(true.expr(), 5.expr() + has_value_arg.clone(), 0.expr());
+        });
+        cb.condition(is_create_family.clone(), |cb| {
+            for (idx, rlc) in [value.expr(), cd_offset.expr(), cd_length.expr()]
+                .into_iter()
+                .enumerate()
+            {
+                cb.stack_lookup(false.expr(), idx.expr(), rlc);
+            }
+        });
+        cb.condition(is_create2.expr(), |cb| {
+            cb.stack_lookup(false.expr(), 3.expr(), salt.expr());
+        });
+        cb.condition(is_create_family.clone(), |cb| {
+            cb.stack_lookup(true.expr(), 2.expr() + is_create2.expr(), 0.expr());
+            // The init code is the only memory range of the creation.
+            cb.require_zero("CREATE and CREATE2 have no return data", rd_length.expr());
+            cb.require_zero(
+                "CREATE and CREATE2 must not be in static call stack",
+                is_static.expr(),
+            );
+        });
+
+        let value_is_zero = IsZeroGadget::construct(cb, sum::expr(&value.cells));
+        let has_value = not::expr(value_is_zero.expr());
+        cb.condition(is_call.expr() * has_value.clone(), |cb| {
+            cb.require_zero(
+                "CALL with value must not be in static call stack",
+                is_static.expr(),
+            );
+        });
+
+        let cd_address = MemoryAddressGadget::construct(cb, cd_offset, cd_length);
+        let rd_address = MemoryAddressGadget::construct(cb, rd_offset, rd_length);
+        let memory_expansion = MemoryExpansionGadget::construct(
+            cb,
+            cb.curr.state.memory_word_size.expr(),
+            [cd_address.address(), rd_address.address()],
+        );
+        let memory_copier_gas =
+            MemoryCopierGasGadget::construct(cb, cd_address.length(), memory_expansion.gas_cost());
+
+        // Add code address to access list
+        let code_address_expr = from_bytes::expr(&code_address.cells[..N_BYTES_ACCOUNT_ADDRESS]);
+        let is_warm_prev = cb.query_bool();
+        cb.condition(is_call_family.clone(), |cb| {
+            cb.account_access_list_write(
+                tx_id.expr(),
+                code_address_expr.clone(),
+                true.expr(),
+                is_warm_prev.expr(),
+                Some(&mut reversion_info),
+            );
+        });
+
+        // Only CALL might create a new account.
+        let [callee_nonce, callee_balance, callee_code_hash] = [(); 3].map(|_| cb.query_cell());
+        cb.condition(is_call.expr(), |cb| {
+            for (field_tag, value) in [
+                (AccountFieldTag::Nonce, &callee_nonce),
+                (AccountFieldTag::Balance, &callee_balance),
+                (AccountFieldTag::CodeHash, &callee_code_hash),
+            ] {
+                cb.account_read(code_address_expr.clone(), field_tag, value.expr());
+            }
+        });
+        let is_empty_nonce_and_balance =
+            BatchedIsZeroGadget::construct(cb, [callee_nonce.expr(), callee_balance.expr()]);
+        let is_empty_code_hash = IsEqualGadget::construct(
+            cb,
+            callee_code_hash.expr(),
+            Word::random_linear_combine_expr(
+                (*EMPTY_HASH_LE).map(|byte| byte.expr()),
+                cb.power_of_randomness(),
+            ),
+        );
+        let is_empty_account =
+            is_call.expr() * is_empty_nonce_and_balance.expr() * is_empty_code_hash.expr();
+        let gas_cost = select::expr(
+            is_create_family.clone(),
+            GasCost::CREATE.expr()
+                + select::expr(
+                    is_create2.expr(),
+                    memory_copier_gas.gas_cost(),
+                    memory_expansion.gas_cost(),
+                ),
+            call_gas_cost_expr(
+                is_warm_prev.expr(),
+                has_value.clone(),
+                is_empty_account,
+                memory_expansion.gas_cost(),
+            ),
+        );
+        let sufficient_gas_left =
+            RangeCheckGadget::construct(cb, cb.curr.state.gas_left.expr() - gas_cost.clone());
+
+        // The depth is checked before the balance.
+        let depth_ok = LtGadget::construct(cb, depth.expr(), (MAX_CALL_DEPTH + 1).expr());
+        let caller_balance = cb.query_word();
+        let insufficient_balance = LtWordGadget::construct(cb, &caller_balance, &value);
+        match S {
+            ExecutionState::ErrorDepth => {
+                cb.require_zero("depth is over the limit", depth_ok.expr());
+            }
+            ExecutionState::ErrorInsufficientBalance => {
+                cb.require_equal("depth is within the limit", depth_ok.expr(), 1.expr());
+                cb.account_read(
+                    current_address.expr(),
+                    AccountFieldTag::Balance,
+                    caller_balance.expr(),
+                );
+                cb.require_equal(
+                    "balance is less than the value to transfer",
+                    insufficient_balance.expr(),
+                    1.expr(),
+                );
+            }
+            _ => unreachable!(
+                "ErrorPrecheckFailedGadget only handles depth and insufficient balance"
+            ),
+        }
+
+        // The callee is never executed, so the return data is cleared.
+        for field_tag in [
+            CallContextFieldTag::LastCalleeId,
+            CallContextFieldTag::LastCalleeReturnDataOffset,
+            CallContextFieldTag::LastCalleeReturnDataLength,
+        ] {
+            cb.call_context_lookup(true.expr(), None, field_tag, 0.expr());
+        }
+
+        // The gas passed to the callee is returned, including the stipend of
+        // CALL and CALLCODE.
+        cb.require_step_state_transition(StepStateTransition {
+            rw_counter: Delta(cb.rw_counter_offset()),
+            program_counter: Delta(1.expr()),
+            stack_pointer: Delta(
+                5.expr() * is_call_family.clone()
+                    + has_value_arg.clone()
+                    + 2.expr() * is_create_family
+                    + is_create2.expr(),
+            ),
+            gas_left: Delta(
+                has_value_arg * has_value * GAS_STIPEND_CALL_WITH_VALUE.expr() - gas_cost,
+            ),
+            memory_word_size: To(memory_expansion.next_memory_word_size()),
+            reversible_write_counter: Delta(is_call_family),
+            ..StepStateTransition::default()
+        });
+
+        Self {
+            opcode,
+            is_call,
+            is_callcode,
+            is_delegatecall,
+            is_staticcall,
+            is_create,
+            is_create2,
+            tx_id,
+            reversion_info,
+            current_address,
+            is_static,
+            depth,
+            gas,
+            code_address,
+            value,
+            salt,
+            value_is_zero,
+            cd_address,
+            rd_address,
+            memory_expansion,
+            memory_copier_gas,
+            is_warm_prev,
+            callee_nonce,
+            callee_balance,
+            callee_code_hash,
+            is_empty_nonce_and_balance,
+            is_empty_code_hash,
+            sufficient_gas_left,
+            depth_ok,
+            caller_balance,
+            insufficient_balance,
+        }
+    }
+
+    fn assign_exec_step(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        block: &Block<F>,
+        _: &Transaction,
+        call: &Call,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        let opcode = step.opcode.unwrap();
+        let is_call = opcode == OpcodeId::CALL;
+        let is_create = opcode.is_create();
+        let has_value_arg = is_call || opcode == OpcodeId::CALLCODE;
+
+        let [tx_id, current_address, is_static, depth] =
+            [0, 3, 4, 5].map(|idx| block.rws[step.rw_indices[idx]].call_context_value());
+
+        let (gas, code_address, value, salt, [cd_offset, cd_length, rd_offset, rd_length]) =
+            if is_create {
+                let [value, cd_offset, cd_length] =
+                    [6, 7, 8].map(|idx| block.rws[step.rw_indices[idx]].stack_value());
+                let salt = if opcode == OpcodeId::CREATE2 {
+                    block.rws[step.rw_indices[9]].stack_value()
+                } else {
+                    U256::zero()
+                };
+                (
+                    U256::zero(),
+                    U256::zero(),
+                    value,
+                    salt,
+                    [cd_offset, cd_length, U256::zero(), U256::zero()],
+                )
+            } else {
+                // Only CALL and CALLCODE have the value argument on stack.
+                let [gas, code_address] =
+                    [6, 7].map(|idx| block.rws[step.rw_indices[idx]].stack_value());
+                let (value, rw_offset) = if has_value_arg {
+                    (block.rws[step.rw_indices[8]].stack_value(), 9)
+                } else {
+                    (U256::zero(), 8)
+                };
+                (
+                    gas,
+                    code_address,
+                    value,
+                    U256::zero(),
+                    [0, 1, 2, 3]
+                        .map(|idx| block.rws[step.rw_indices[rw_offset + idx]].stack_value()),
+                )
+            };
+        // Skip the stack reads and the stack write of the result.
+        let rw_offset =
+            6 + match opcode {
+                OpcodeId::CALL | OpcodeId::CALLCODE => 7,
+                OpcodeId::CREATE => 3,
+                OpcodeId::CREATE2 => 4,
+                _ => 6,
+            } + 1;
+        let (is_warm_prev, rw_offset) = if is_create {
+            (false, rw_offset)
+        } else {
+            (
+                block.rws[step.rw_indices[rw_offset]]
+                    .tx_access_list_value_pair()
+                    .1,
+                rw_offset + 1,
+            )
+        };
+        let ([callee_nonce, callee_balance, callee_code_hash], rw_offset) = if is_call {
+            (
+                [0, 1, 2].map(|idx| {
+                    block.rws[step.rw_indices[rw_offset + idx]]
+                        .account_value_pair()
+                        .0
+                }),
+                rw_offset + 3,
+            )
+        } else {
+            ([U256::zero(); 3], rw_offset)
+        };
+        let caller_balance = if S == ExecutionState::ErrorInsufficientBalance {
+            block.rws[step.rw_indices[rw_offset]].account_value_pair().0
+        } else {
+            U256::zero()
+        };
+
+        self.opcode
+            .assign(region, offset, Value::known(F::from(opcode.as_u64())))?;
+        for (gadget, opcode_id) in [
+            (&self.is_call, OpcodeId::CALL),
+            (&self.is_callcode, OpcodeId::CALLCODE),
+            (&self.is_delegatecall, OpcodeId::DELEGATECALL),
+            (&self.is_staticcall, OpcodeId::STATICCALL),
+            (&self.is_create, OpcodeId::CREATE),
+            (&self.is_create2, OpcodeId::CREATE2),
+        ] {
+            gadget.assign(
+                region,
+                offset,
+                F::from(opcode.as_u64()) - F::from(opcode_id.as_u64()),
+            )?;
+        }
+
+        self.tx_id
+            .assign(region, offset, Value::known(F::from(tx_id.low_u64())))?;
+        self.reversion_info.assign(
+            region,
+            offset,
+            call.rw_counter_end_of_reversion,
+            call.is_persistent,
+        )?;
+        self.current_address.assign(
+            region,
+            offset,
+            Value::known(
+                current_address
+                    .to_scalar()
+                    .expect("unexpected U256 -> Scalar conversion failure"),
+            ),
+        )?;
+        self.is_static
+            .assign(region, offset, Value::known(F::from(is_static.low_u64())))?;
+        self.depth
+            .assign(region, offset, Value::known(F::from(depth.low_u64())))?;
+
+        self.gas.assign(region, offset, Some(gas.to_le_bytes()))?;
+        self.code_address
+            .assign(region, offset, Some(code_address.to_le_bytes()))?;
+        self.value
+            .assign(region, offset, Some(value.to_le_bytes()))?;
+        self.salt.assign(region, offset, Some(salt.to_le_bytes()))?;
+        self.value_is_zero
+            .assign(region, offset, sum::value(&value.to_le_bytes()))?;
+
+        let cd_address =
+            self.cd_address
+                .assign(region, offset, cd_offset, cd_length, block.randomness)?;
+        let rd_address =
+            self.rd_address
+                .assign(region, offset, rd_offset, rd_length, block.randomness)?;
+        let (_, memory_expansion_gas_cost) = self.memory_expansion.assign(
+            region,
+            offset,
+            step.memory_word_size(),
+            [cd_address, rd_address],
+        )?;
+        let memory_copier_gas_cost = self.memory_copier_gas.assign(
+            region,
+            offset,
+            cd_length.low_u64(),
+            memory_expansion_gas_cost,
+        )?;
+
+        self.is_warm_prev
+            .assign(region, offset, Value::known(F::from(is_warm_prev as u64)))?;
+
+        self.callee_nonce.assign(
+            region,
+            offset,
+            Value::known(
+                callee_nonce
+                    .to_scalar()
+                    .expect("unexpected U256 -> Scalar conversion failure"),
+            ),
+        )?;
+        let callee_balance =
+            Word::random_linear_combine(callee_balance.to_le_bytes(), block.randomness);
+        self.callee_balance
+            .assign(region, offset, Value::known(callee_balance))?;
+        let callee_code_hash =
+            Word::random_linear_combine(callee_code_hash.to_le_bytes(), block.randomness);
+        self.callee_code_hash
+            .assign(region, offset, Value::known(callee_code_hash))?;
+        let is_empty_nonce_and_balance = self.is_empty_nonce_and_balance.assign(
+            region,
+            offset,
+            [F::from(callee_nonce.low_u64()), callee_balance],
+        )?;
+        let is_empty_code_hash = self.is_empty_code_hash.assign(
+            region,
+            offset,
+            callee_code_hash,
+            Word::random_linear_combine(*EMPTY_HASH_LE, block.randomness),
+        )?;
+        let is_empty_account =
+            is_call && is_empty_nonce_and_balance * is_empty_code_hash == F::one();
+
+        let gas_cost = match opcode {
+            OpcodeId::CREATE => GasCost::CREATE.as_u64() + memory_expansion_gas_cost,
+            OpcodeId::CREATE2 => GasCost::CREATE.as_u64() + memory_copier_gas_cost,
+            _ => calc_call_gas_cost(
+                is_warm_prev,
+                !value.is_zero(),
+                is_empty_account,
+                memory_expansion_gas_cost,
+            ),
+        };
+        self.sufficient_gas_left
+            .assign(region, offset, F::from(step.gas_left - gas_cost))?;
+
+        self.depth_ok.assign(
+            region,
+            offset,
+            F::from(depth.low_u64()),
+            F::from(MAX_CALL_DEPTH + 1),
+        )?;
+        self.caller_balance
+            .assign(region, offset, Some(caller_balance.to_le_bytes()))?;
+        self.insufficient_balance
+            .assign(region, offset, caller_balance, value)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_util::run_test_circuits;
+    use eth_types::{address, bytecode, evm_types::OpcodeId, geth_types::Account, Word};
+    use mock::{eth, TestContext};
+
+    fn test_insufficient_balance(opcode: OpcodeId, balance: Word, value: Word) {
+        let mut code = if opcode.is_create() {
+            let mut code = bytecode! {
+                // Store the init code `STOP` in memory.
+                PUSH1(0)
+                PUSH1(0)
+                MSTORE8
+            };
+            if opcode == OpcodeId::CREATE2 {
+                code.push(32, Word::from(0xcafe)); // salt
+            }
+            code.append(&bytecode! {
+                PUSH1(1) // length
+                PUSH1(0) // offset
+                PUSH32(value) // value
+            });
+            code
+        } else {
+            bytecode! {
+                PUSH1(0) // retLength
+                PUSH1(0) // retOffset
+                PUSH1(0) // argsLength
+                PUSH1(0) // argsOffset
+                PUSH32(value) // value
+                PUSH20(0xcafe) // address
+                PUSH2(0xffff) // gas
+            }
+        };
+        code.write_op(opcode);
+        // The call continues with 0 on the stack.
+        code.append(&bytecode! {
+            PUSH1(0)
+            MSTORE
+            STOP
+        });
+        let contract = Account {
+            address: address!("0x00000000000000000000000000000000000cafe2"),
+            balance,
+            code: code.into(),
+            ..Default::default()
+        };
+
+        let ctx = TestContext::<2, 1>::new(
+            None,
+            |accs| {
+                accs[0].account(&contract);
+                accs[1]
+                    .address(address!("0x000000000000000000000000000000000000cafe"))
+                    .balance(eth(10));
+            },
+            |mut txs, accs| {
+                txs[0]
+                    .from(accs[1].address)
+                    .to(accs[0].address)
+                    .gas(Word::from(200_000u64));
+            },
+            |block, _| block,
+        )
+        .unwrap();
+
+        assert_eq!(run_test_circuits(ctx, None), Ok(()));
+    }
+
+    #[test]
+    fn error_insufficient_balance_call() {
+        test_insufficient_balance(OpcodeId::CALL, Word::zero(), Word::one());
+        test_insufficient_balance(OpcodeId::CALL, eth(1), eth(2));
+    }
+
+    #[test]
+    fn error_insufficient_balance_callcode() {
+        test_insufficient_balance(OpcodeId::CALLCODE, Word::zero(), Word::one());
+        test_insufficient_balance(OpcodeId::CALLCODE, eth(1), eth(2));
+    }
+
+    #[test]
+    fn error_insufficient_balance_create() {
+        test_insufficient_balance(OpcodeId::CREATE, Word::zero(), Word::one());
+        test_insufficient_balance(OpcodeId::CREATE, eth(1), eth(2));
+    }
+
+    #[test]
+    fn error_insufficient_balance_create2() {
+        test_insufficient_balance(OpcodeId::CREATE2, Word::zero(), Word::one());
+        test_insufficient_balance(OpcodeId::CREATE2, eth(1), eth(2));
+    }
+}
@@ -0,0 +1,252 @@
+use crate::{
+    evm_circuit::{
+        execution::ExecutionGadget,
+        param::N_BYTES_U64,
+        step::ExecutionState,
+        util::{
+            common_gadget::CommonErrorGadget,
+            constraint_builder::ConstraintBuilder,
+            from_bytes,
+            math_gadget::{IsZeroGadget, LtGadget},
+            not, or, sum, CachedRegion, Cell, RandomLinearCombination, Word,
+        },
+        witness::{Block, Call, ExecStep, Transaction},
+    },
+    table::CallContextFieldTag,
+    util::Expr,
+};
+use bus_mapping::evm::OpcodeId;
+use eth_types::{Field, ToLittleEndian, ToScalar};
+use halo2_proofs::{circuit::Value, plonk::Error};
+
+/// Gadget for RETURNDATACOPY which fails because `offset + size` exceeds the
+/// return data of the last callee.
+#[derive(Clone, Debug)]
+pub(crate) struct ErrorReturnDataOutOfBoundGadget<F> {
+    opcode: Cell<F>,
+    dest_offset: Cell<F>,
+    data_offset: Word<F>,
+    size: RandomLinearCombination<F, N_BYTES_U64>,
+    return_data_length: Cell<F>,
+    is_data_offset_within_u64: IsZeroGadget<F>,
+    // constrain `return_data_length < data_offset + size`
+    is_end_over_return_data: LtGadget<F, { N_BYTES_U64 + 1 }>,
+    common_error_gadget: CommonErrorGadget<F>,
+}
+
+impl<F: Field> ExecutionGadget<F> for ErrorReturnDataOutOfBoundGadget<F> {
+    const NAME: &'static str = "ErrorReturnDataOutOfBound";
+
+    const EXECUTION_STATE: ExecutionState = ExecutionState::ErrorReturnDataOutOfBound;
+
+    fn configure(cb: &mut ConstraintBuilder<F>) -> Self {
+        let opcode = cb.query_cell();
+        cb.require_equal(
+            "ErrorReturnDataOutOfBound opcode must be RETURNDATACOPY",
+            opcode.expr(),
+            OpcodeId::RETURNDATACOPY.expr(),
+        );
+
+        let dest_offset = cb.query_cell();
+        let data_offset = cb.query_word();
+        // The size must be within u64, otherwise it fails earlier because of
+        // gas overflow.
+        let size = cb.query_rlc();
+
+        cb.stack_pop(dest_offset.expr());
+        cb.stack_pop(data_offset.expr());
+        cb.stack_pop(size.expr());
+
+        let return_data_length =
+            cb.call_context(None, CallContextFieldTag::LastCalleeReturnDataLength);
+
+        let is_data_offset_within_u64 =
+            IsZeroGadget::construct(cb, sum::expr(&data_offset.cells[N_BYTES_U64..]));
+        let is_end_over_return_data = LtGadget::construct(
+            cb,
+            return_data_length.expr(),
+            from_bytes::expr(&data_offset.cells[..N_BYTES_U64]) + from_bytes::expr(&size.cells),
+        );
+
+        // Either `data_offset` overflows u64, or `data_offset + size` is over
+        // the return data.
+        cb.require_equal(
+            "data_offset > u64::MAX or data_offset + size > return_data_length",
+            or::expr([
+                not::expr(is_data_offset_within_u64.expr()),
+                is_end_over_return_data.expr(),
+            ]),
+            1.expr(),
+        );
+
+        let common_error_gadget = CommonErrorGadget::construct(cb, opcode.expr());
+
+        Self {
+            opcode,
+            dest_offset,
+            data_offset,
+            size,
+            return_data_length,
+            is_data_offset_within_u64,
+            is_end_over_return_data,
+            common_error_gadget,
+        }
+    }
+
+    fn assign_exec_step(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        block: &Block<F>,
+        _tx: &Transaction,
+        call: &Call,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        let opcode = step.opcode.unwrap();
+        self.opcode
+            .assign(region, offset, Value::known(F::from(opcode.as_u64())))?;
+
+        let [dest_offset, data_offset, size] =
+            [0, 1, 2].map(|idx| block.rws[step.rw_indices[idx]].stack_value());
+        let return_data_length = block.rws[step.rw_indices[3]].call_context_value();
+
+        self.dest_offset.assign(
+            region,
+            offset,
+            Value::known(
+                dest_offset
+                    .to_scalar()
+                    .expect("unexpected U256 -> Scalar conversion failure"),
+            ),
+        )?;
+        self.data_offset
+            .assign(region, offset, Some(data_offset.to_le_bytes()))?;
+        self.size.assign(
+            region,
+            offset,
+            Some(size.to_le_bytes()[..N_BYTES_U64].try_into().unwrap()),
+        )?;
+        self.return_data_length.assign(
+            region,
+            offset,
+            Value::known(F::from(return_data_length.as_u64())),
+        )?;
+
+        let data_offset_bytes = data_offset.to_le_bytes();
+        self.is_data_offset_within_u64.assign(
+            region,
+            offset,
+            sum::value(&data_offset_bytes[N_BYTES_U64..]),
+        )?;
+        self.is_end_over_return_data.assign(
+            region,
+            offset,
+            F::from(return_data_length.as_u64()),
+            F::from(data_offset.low_u64()) + F::from(size.low_u64()),
+        )?;
+
+        self.common_error_gadget
+            .assign(region, offset, block, call, step, 4)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_util::run_test_circuits;
+    use eth_types::{address, bytecode, geth_types::Account, ToWord, Word};
+    use mock::{eth, TestContext};
+
+    // Caller calls the callee, which returns 1 byte, then tries to copy
+    // `offset + size` bytes of the return data.
+    fn test_return_data_oob(offset: Word, size: Word, is_root: bool) {
+        let callee = Account {
+            address: address!("0x00000000000000000000000000000000000cafe2"),
+            balance: eth(10),
+            code: bytecode! {
+                PUSH1(0x01) // size
+                PUSH1(0x00) // offset
+                RETURN
+            }
+            .into(),
+            ..Default::default()
+        };
+        let code_b = bytecode! {
+            PUSH1(0) // retLength
+            PUSH1(0) // retOffset
+            PUSH1(0) // argsLength
+            PUSH1(0) // argsOffset
+            PUSH1(0) // value
+            PUSH20(callee.address.to_word())
+            GAS
+            CALL
+            POP
+            PUSH32(size)
+            PUSH32(offset)
+            PUSH1(0) // dest_offset
+            RETURNDATACOPY
+            STOP
+        };
+        let contract = Account {
+            address: address!("0x00000000000000000000000000000000000cafe1"),
+            balance: eth(10),
+            code: code_b.into(),
+            ..Default::default()
+        };
+
+        // Internal case: another contract calls the contract above.
+        let caller = Account {
+            address: address!("0x00000000000000000000000000000000000cafe0"),
+            balance: eth(10),
+            code: bytecode! {
+                PUSH1(0) // retLength
+                PUSH1(0) // retOffset
+                PUSH1(0) // argsLength
+                PUSH1(0) // argsOffset
+                PUSH1(0) // value
+                PUSH20(contract.address.to_word())
+                PUSH2(10000) // gas
+                CALL
+                STOP
+            }
+            .into(),
+            ..Default::default()
+        };
+
+        let ctx = TestContext::<4, 1>::new(
+            None,
+            |accs| {
+                accs[0]
+                    .address(address!("0x000000000000000000000000000000000000cafe"))
+                    .balance(eth(10));
+                accs[1].account(&caller);
+                accs[2].account(&contract);
+                accs[3].account(&callee);
+            },
+            |mut txs, accs| {
+                txs[0].from(accs[0].address).to(if is_root {
+                    accs[2].address
+                } else {
+                    accs[1].address
+                });
+            },
+            |block, _| block,
+        )
+        .unwrap();
+
+        assert_eq!(run_test_circuits(ctx, None), Ok(()));
+    }
+
+    #[test]
+    fn error_return_data_out_of_bound_root() {
+        test_return_data_oob(Word::zero(), Word::from(2), true);
+        test_return_data_oob(Word::one(), Word::one(), true);
+        test_return_data_oob(Word::from(u64::MAX) + 1, Word::zero(), true);
+    }
+
+    #[test]
+    fn error_return_data_out_of_bound_internal() {
+        test_return_data_oob(Word::zero(), Word::from(2), false);
+        test_return_data_oob(Word::from(2), Word::zero(), false);
+        test_return_data_oob(Word::MAX, Word::one(), false);
+    }
+}
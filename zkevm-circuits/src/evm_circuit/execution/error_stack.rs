@@ -0,0 +1,220 @@
+use crate::{
+    evm_circuit::{
+        execution::ExecutionGadget,
+        step::ExecutionState,
+        table::{FixedTableTag, Lookup},
+        util::{
+            common_gadget::CommonErrorGadget, constraint_builder::ConstraintBuilder,
+            math_gadget::LtGadget, CachedRegion, Cell,
+        },
+        witness::{Block, Call, ExecStep, Transaction},
+    },
+    util::Expr,
+};
+use eth_types::Field;
+use halo2_proofs::{circuit::Value, plonk::Error};
+
+/// Gadget for any opcode which fails because the stack pointer is out of the
+/// valid range of the opcode, which is the stack overflow when it's less than
+/// the minimum, or the stack underflow when it's greater than the maximum.
+#[derive(Clone, Debug)]
+pub(crate) struct ErrorStackGadget<F, const S: ExecutionState> {
+    opcode: Cell<F>,
+    min_stack_pointer: Cell<F>,
+    max_stack_pointer: Cell<F>,
+    // constrain stack pointer is out of range
+    out_of_range: LtGadget<F, 2>,
+    common_error_gadget: CommonErrorGadget<F>,
+}
+
+impl<F: Field, const S: ExecutionState> ExecutionGadget<F> for ErrorStackGadget<F, S> {
+    const NAME: &'static str = "ErrorStack";
+
+    const EXECUTION_STATE: ExecutionState = S;
+
+    fn configure(cb: &mut ConstraintBuilder<F>) -> Self {
+        let opcode = cb.query_cell();
+        let min_stack_pointer = cb.query_cell();
+        let max_stack_pointer = cb.query_cell();
+
+        cb.add_lookup(
+            "Stack pointer range lookup",
+            Lookup::Fixed {
+                tag: FixedTableTag::StackPointerRange.expr(),
+                values: [
+                    opcode.expr(),
+                    min_stack_pointer.expr(),
+                    max_stack_pointer.expr(),
+                ],
+            },
+        );
+
+        let out_of_range = match S {
+            ExecutionState::ErrorStackOverflow => LtGadget::construct(
+                cb,
+                cb.curr.state.stack_pointer.expr(),
+                min_stack_pointer.expr(),
+            ),
+            ExecutionState::ErrorStackUnderflow => LtGadget::construct(
+                cb,
+                max_stack_pointer.expr(),
+                cb.curr.state.stack_pointer.expr(),
+            ),
+            _ => unreachable!("ErrorStackGadget only handles stack overflow and underflow"),
+        };
+        cb.require_equal(
+            "stack pointer is out of range",
+            out_of_range.expr(),
+            1.expr(),
+        );
+
+        let common_error_gadget = CommonErrorGadget::construct(cb, opcode.expr());
+
+        Self {
+            opcode,
+            min_stack_pointer,
+            max_stack_pointer,
+            out_of_range,
+            common_error_gadget,
+        }
+    }
+
+    fn assign_exec_step(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        block: &Block<F>,
+        _: &Transaction,
+        call: &Call,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        let opcode = step.opcode.unwrap();
+        self.opcode
+            .assign(region, offset, Value::known(F::from(opcode.as_u64())))?;
+
+        let (min_stack_pointer, max_stack_pointer) = opcode.valid_stack_ptr_range();
+        self.min_stack_pointer.assign(
+            region,
+            offset,
+            Value::known(F::from(min_stack_pointer as u64)),
+        )?;
+        self.max_stack_pointer.assign(
+            region,
+            offset,
+            Value::known(F::from(max_stack_pointer as u64)),
+        )?;
+
+        let stack_pointer = F::from(step.stack_pointer as u64);
+        match S {
+            ExecutionState::ErrorStackOverflow => self.out_of_range.assign(
+                region,
+                offset,
+                stack_pointer,
+                F::from(min_stack_pointer as u64),
+            )?,
+            ExecutionState::ErrorStackUnderflow => self.out_of_range.assign(
+                region,
+                offset,
+                F::from(max_stack_pointer as u64),
+                stack_pointer,
+            )?,
+            _ => unreachable!("ErrorStackGadget only handles stack overflow and underflow"),
+        };
+
+        self.common_error_gadget
+            .assign(region, offset, block, call, step, 0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_util::run_test_circuits;
+    use eth_types::{address, bytecode, geth_types::Account, Bytecode, ToWord, Word};
+    use mock::{eth, TestContext};
+
+    fn test_stack_error(code: Bytecode, is_root: bool) {
+        let contract = Account {
+            address: address!("0x00000000000000000000000000000000000cafe2"),
+            balance: eth(10),
+            code: code.into(),
+            ..Default::default()
+        };
+        let caller = Account {
+            address: address!("0x00000000000000000000000000000000000cafe1"),
+            balance: eth(10),
+            code: bytecode! {
+                PUSH1(0) // retLength
+                PUSH1(0) // retOffset
+                PUSH1(0) // argsLength
+                PUSH1(0) // argsOffset
+                PUSH1(0) // value
+                PUSH20(contract.address.to_word())
+                PUSH2(0xffff) // gas
+                CALL
+                STOP
+            }
+            .into(),
+            ..Default::default()
+        };
+
+        let ctx = TestContext::<3, 1>::new(
+            None,
+            |accs| {
+                accs[0].account(&caller);
+                accs[1].account(&contract);
+                accs[2]
+                    .address(address!("0x000000000000000000000000000000000000cafe"))
+                    .balance(eth(10));
+            },
+            |mut txs, accs| {
+                if is_root {
+                    txs[0]
+                        .from(accs[2].address)
+                        .to(accs[1].address)
+                        .gas(Word::from(100_000u64));
+                } else {
+                    txs[0].from(accs[2].address).to(accs[0].address);
+                }
+            },
+            |block, _| block,
+        )
+        .unwrap();
+
+        assert_eq!(run_test_circuits(ctx, None), Ok(()));
+    }
+
+    fn stack_overflow_code() -> Bytecode {
+        let mut code = Bytecode::default();
+        for _ in 0..1025 {
+            code.push(1, Word::zero());
+        }
+        code
+    }
+
+    fn stack_underflow_code() -> Bytecode {
+        bytecode! {
+            PUSH1(1)
+            ADD
+        }
+    }
+
+    #[test]
+    fn error_stack_overflow_root() {
+        test_stack_error(stack_overflow_code(), true);
+    }
+
+    #[test]
+    fn error_stack_overflow_internal() {
+        test_stack_error(stack_overflow_code(), false);
+    }
+
+    #[test]
+    fn error_stack_underflow_root() {
+        test_stack_error(stack_underflow_code(), true);
+    }
+
+    #[test]
+    fn error_stack_underflow_internal() {
+        test_stack_error(stack_underflow_code(), false);
+    }
+}
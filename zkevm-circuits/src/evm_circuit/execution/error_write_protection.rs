@@ -0,0 +1,233 @@
+use crate::{
+    evm_circuit::{
+        execution::ExecutionGadget,
+        step::ExecutionState,
+        util::{
+            common_gadget::CommonErrorGadget, constraint_builder::ConstraintBuilder,
+            math_gadget::IsZeroGadget, sum, CachedRegion, Cell, Word,
+        },
+        witness::{Block, Call, ExecStep, Transaction},
+    },
+    table::CallContextFieldTag,
+    util::Expr,
+};
+use eth_types::{evm_types::OpcodeId, Field, ToLittleEndian, U256};
+use halo2_proofs::{circuit::Value, plonk::Error};
+
+/// Gadget for the state modifying opcodes which fail because of the write
+/// protection in a static call, which are SSTORE, LOG0 to LOG4, CREATE,
+/// CREATE2, SELFDESTRUCT, and CALL with value.
+#[derive(Clone, Debug)]
+pub(crate) struct ErrorWriteProtectionGadget<F> {
+    opcode: Cell<F>,
+    is_call: IsZeroGadget<F>,
+    gas: Word<F>,
+    code_address: Word<F>,
+    value: Word<F>,
+    is_value_zero: IsZeroGadget<F>,
+    common_error_gadget: CommonErrorGadget<F>,
+}
+
+impl<F: Field> ExecutionGadget<F> for ErrorWriteProtectionGadget<F> {
+    const NAME: &'static str = "ErrorWriteProtection";
+
+    const EXECUTION_STATE: ExecutionState = ExecutionState::ErrorWriteProtection;
+
+    fn configure(cb: &mut ConstraintBuilder<F>) -> Self {
+        let opcode = cb.query_cell();
+        cb.require_in_set(
+            "ErrorWriteProtection opcode must modify the state",
+            opcode.expr(),
+            vec![
+                OpcodeId::SSTORE.expr(),
+                OpcodeId::LOG0.expr(),
+                OpcodeId::LOG1.expr(),
+                OpcodeId::LOG2.expr(),
+                OpcodeId::LOG3.expr(),
+                OpcodeId::LOG4.expr(),
+                OpcodeId::CREATE.expr(),
+                OpcodeId::CREATE2.expr(),
+                OpcodeId::SELFDESTRUCT.expr(),
+                OpcodeId::CALL.expr(),
+            ],
+        );
+
+        let is_static = cb.call_context(None, CallContextFieldTag::IsStatic);
+        cb.require_equal("is_static is true", is_static.expr(), 1.expr());
+
+        // CALL only breaks the write protection when it transfers value.
+        let is_call = IsZeroGadget::construct(cb, opcode.expr() - OpcodeId::CALL.expr());
+        let gas = cb.query_word();
+        let code_address = cb.query_word();
+        let value = cb.query_word();
+        let is_value_zero = IsZeroGadget::construct(cb, sum::expr(&value.cells));
+        cb.condition(is_call.expr(), |cb| {
+            cb.stack_pop(gas.expr());
+            cb.stack_pop(code_address.expr());
+            cb.stack_pop(value.expr());
+            cb.require_zero("CALL transfers value", is_value_zero.expr());
+        });
+
+        let common_error_gadget = CommonErrorGadget::construct(cb, opcode.expr());
+
+        Self {
+            opcode,
+            is_call,
+            gas,
+            code_address,
+            value,
+            is_value_zero,
+            common_error_gadget,
+        }
+    }
+
+    fn assign_exec_step(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        block: &Block<F>,
+        _: &Transaction,
+        call: &Call,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        let opcode = step.opcode.unwrap();
+        self.opcode
+            .assign(region, offset, Value::known(F::from(opcode.as_u64())))?;
+        self.is_call.assign(
+            region,
+            offset,
+            F::from(opcode.as_u64()) - F::from(OpcodeId::CALL.as_u64()),
+        )?;
+
+        let is_call = opcode == OpcodeId::CALL;
+        let [gas, code_address, value] = if is_call {
+            [1, 2, 3].map(|idx| block.rws[step.rw_indices[idx]].stack_value())
+        } else {
+            [U256::zero(); 3]
+        };
+        self.gas.assign(region, offset, Some(gas.to_le_bytes()))?;
+        self.code_address
+            .assign(region, offset, Some(code_address.to_le_bytes()))?;
+        self.value
+            .assign(region, offset, Some(value.to_le_bytes()))?;
+        self.is_value_zero
+            .assign(region, offset, sum::value(&value.to_le_bytes()))?;
+
+        self.common_error_gadget.assign(
+            region,
+            offset,
+            block,
+            call,
+            step,
+            if is_call { 4 } else { 1 },
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_util::run_test_circuits;
+    use eth_types::{address, bytecode, geth_types::Account, Bytecode, ToWord, Word};
+    use mock::{eth, TestContext};
+
+    fn test_write_protection(code: Bytecode) {
+        let contract = Account {
+            address: address!("0x00000000000000000000000000000000000cafe2"),
+            balance: eth(10),
+            code: code.into(),
+            ..Default::default()
+        };
+        let caller = Account {
+            address: address!("0x00000000000000000000000000000000000cafe1"),
+            balance: eth(10),
+            code: bytecode! {
+                PUSH1(0) // retLength
+                PUSH1(0) // retOffset
+                PUSH1(0) // argsLength
+                PUSH1(0) // argsOffset
+                PUSH20(contract.address.to_word())
+                PUSH2(0xffff) // gas
+                STATICCALL
+                STOP
+            }
+            .into(),
+            ..Default::default()
+        };
+
+        let ctx = TestContext::<3, 1>::new(
+            None,
+            |accs| {
+                accs[0].account(&caller);
+                accs[1].account(&contract);
+                accs[2]
+                    .address(address!("0x000000000000000000000000000000000000cafe"))
+                    .balance(eth(10));
+            },
+            |mut txs, accs| {
+                txs[0]
+                    .from(accs[2].address)
+                    .to(accs[0].address)
+                    .gas(Word::from(100_000u64));
+            },
+            |block, _| block,
+        )
+        .unwrap();
+
+        assert_eq!(run_test_circuits(ctx, None), Ok(()));
+    }
+
+    #[test]
+    fn error_write_protection_sstore() {
+        test_write_protection(bytecode! {
+            PUSH1(1) // value
+            PUSH1(0) // key
+            SSTORE
+            STOP
+        });
+    }
+
+    #[test]
+    fn error_write_protection_log() {
+        test_write_protection(bytecode! {
+            PUSH1(0) // topic
+            PUSH1(0) // length
+            PUSH1(0) // offset
+            LOG1
+            STOP
+        });
+    }
+
+    #[test]
+    fn error_write_protection_create() {
+        test_write_protection(bytecode! {
+            PUSH1(0) // length
+            PUSH1(0) // offset
+            PUSH1(0) // value
+            CREATE
+            STOP
+        });
+    }
+
+    #[test]
+    fn error_write_protection_selfdestruct() {
+        test_write_protection(bytecode! {
+            PUSH20(0xcafe) // beneficiary
+            SELFDESTRUCT
+        });
+    }
+
+    #[test]
+    fn error_write_protection_call_with_value() {
+        test_write_protection(bytecode! {
+            PUSH1(0) // retLength
+            PUSH1(0) // retOffset
+            PUSH1(0) // argsLength
+            PUSH1(0) // argsOffset
+            PUSH1(1) // value
+            PUSH20(0xcafe) // address
+            PUSH2(0xffff) // gas
+            CALL
+            STOP
+        });
+    }
+}
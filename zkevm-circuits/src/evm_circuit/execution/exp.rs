@@ -0,0 +1,212 @@
+use crate::{
+    evm_circuit::{
+        execution::ExecutionGadget,
+        step::ExecutionState,
+        util::{
+            self,
+            common_gadget::SameContextGadget,
+            constraint_builder::{ConstraintBuilder, StepStateTransition, Transition::Delta},
+            from_bytes,
+            math_gadget::{ByteSizeGadget, IsEqualGadget, IsZeroGadget},
+            split_u256, CachedRegion,
+        },
+        witness::{Block, Call, ExecStep, Transaction},
+    },
+    util::Expr,
+};
+use bus_mapping::evm::OpcodeId;
+use eth_types::{evm_types::GasCost, Field, ToLittleEndian, ToScalar};
+use halo2_proofs::plonk::Error;
+
+/// ExpGadget verifies opcode EXP, i.e. base ^ exponent == exponentiation
+/// (mod 2^256). Exponents 0 and 1 are verified directly, any other exponent is
+/// verified with a lookup to the exponentiation table.
+#[derive(Clone, Debug)]
+pub(crate) struct ExpGadget<F> {
+    same_context: SameContextGadget<F>,
+    base: util::Word<F>,
+    exponent: util::Word<F>,
+    exponentiation: util::Word<F>,
+    /// Check if the lower 128 bits of the exponent are zero
+    exponent_lo_is_zero: IsZeroGadget<F>,
+    /// Check if the higher 128 bits of the exponent are zero
+    exponent_hi_is_zero: IsZeroGadget<F>,
+    /// Check if the lower 128 bits of the exponent are one
+    exponent_lo_is_one: IsEqualGadget<F>,
+    /// Number of bytes of the exponent, which the dynamic gas cost depends on
+    exponent_byte_size: ByteSizeGadget<F>,
+}
+
+impl<F: Field> ExecutionGadget<F> for ExpGadget<F> {
+    const NAME: &'static str = "EXP";
+
+    const EXECUTION_STATE: ExecutionState = ExecutionState::EXP;
+
+    fn configure(cb: &mut ConstraintBuilder<F>) -> Self {
+        let opcode = cb.query_cell();
+
+        let base = cb.query_word();
+        let exponent = cb.query_word();
+        let exponentiation = cb.query_word();
+
+        // Pop base and exponent from the stack, push the exponentiation on the
+        // stack
+        cb.stack_pop(base.expr());
+        cb.stack_pop(exponent.expr());
+        cb.stack_push(exponentiation.expr());
+
+        let base_lo = from_bytes::expr(&base.cells[0..16]);
+        let base_hi = from_bytes::expr(&base.cells[16..32]);
+        let exponent_lo = from_bytes::expr(&exponent.cells[0..16]);
+        let exponent_hi = from_bytes::expr(&exponent.cells[16..32]);
+        let exponentiation_lo = from_bytes::expr(&exponentiation.cells[0..16]);
+        let exponentiation_hi = from_bytes::expr(&exponentiation.cells[16..32]);
+
+        let exponent_lo_is_zero = IsZeroGadget::construct(cb, exponent_lo.clone());
+        let exponent_hi_is_zero = IsZeroGadget::construct(cb, exponent_hi.clone());
+        let exponent_lo_is_one = IsEqualGadget::construct(cb, exponent_lo.clone(), 1.expr());
+        let exponent_is_zero = exponent_lo_is_zero.expr() * exponent_hi_is_zero.expr();
+        let exponent_is_one = exponent_lo_is_one.expr() * exponent_hi_is_zero.expr();
+
+        cb.condition(exponent_is_zero.clone(), |cb| {
+            cb.require_equal(
+                "exponentiation == 1 if exponent == 0",
+                exponentiation.expr(),
+                1.expr(),
+            );
+        });
+        cb.condition(exponent_is_one.clone(), |cb| {
+            cb.require_equal(
+                "exponentiation == base if exponent == 1",
+                exponentiation.expr(),
+                base.expr(),
+            );
+        });
+        cb.condition(
+            (1.expr() - exponent_is_zero) * (1.expr() - exponent_is_one),
+            |cb| {
+                cb.exp_table_lookup(
+                    [base_lo, base_hi],
+                    [exponent_lo, exponent_hi],
+                    [exponentiation_lo, exponentiation_hi],
+                );
+            },
+        );
+
+        // The dynamic gas cost is charged for every byte of the exponent.
+        let exponent_byte_size =
+            ByteSizeGadget::construct(cb, exponent.cells.clone().map(|cell| cell.expr()));
+        let gas_cost = OpcodeId::EXP.constant_gas_cost().expr()
+            + GasCost::EXP_BYTE_TIMES.expr() * exponent_byte_size.byte_size();
+
+        let step_state_transition = StepStateTransition {
+            rw_counter: Delta(3.expr()),
+            program_counter: Delta(1.expr()),
+            stack_pointer: Delta(1.expr()),
+            gas_left: Delta(-gas_cost),
+            ..Default::default()
+        };
+        let same_context = SameContextGadget::construct(cb, opcode, step_state_transition);
+
+        Self {
+            same_context,
+            base,
+            exponent,
+            exponentiation,
+            exponent_lo_is_zero,
+            exponent_hi_is_zero,
+            exponent_lo_is_one,
+            exponent_byte_size,
+        }
+    }
+
+    fn assign_exec_step(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        block: &Block<F>,
+        _: &Transaction,
+        _: &Call,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        self.same_context.assign_exec_step(region, offset, step)?;
+
+        let [base, exponent, exponentiation] = [0, 1, 2]
+            .map(|idx| step.rw_indices[idx])
+            .map(|idx| block.rws[idx].stack_value());
+        self.base.assign(region, offset, Some(base.to_le_bytes()))?;
+        self.exponent
+            .assign(region, offset, Some(exponent.to_le_bytes()))?;
+        self.exponentiation
+            .assign(region, offset, Some(exponentiation.to_le_bytes()))?;
+
+        let (exponent_lo, exponent_hi) = split_u256(&exponent);
+        let exponent_lo_scalar: F = exponent_lo.to_scalar().unwrap();
+        let exponent_hi_scalar: F = exponent_hi.to_scalar().unwrap();
+        self.exponent_lo_is_zero
+            .assign(region, offset, exponent_lo_scalar)?;
+        self.exponent_hi_is_zero
+            .assign(region, offset, exponent_hi_scalar)?;
+        self.exponent_lo_is_one
+            .assign(region, offset, exponent_lo_scalar, F::one())?;
+        self.exponent_byte_size.assign(region, offset, exponent)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{evm_circuit::test::rand_word, test_util::run_test_circuits};
+    use eth_types::{bytecode, Word};
+    use mock::TestContext;
+
+    fn test_ok(base: Word, exponent: Word) {
+        let bytecode = bytecode! {
+            PUSH32(exponent)
+            PUSH32(base)
+            EXP
+            STOP
+        };
+
+        assert_eq!(
+            run_test_circuits(
+                TestContext::<2, 1>::simple_ctx_with_bytecode(bytecode).unwrap(),
+                None
+            ),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn exp_gadget_zero() {
+        test_ok(0.into(), 0.into());
+        test_ok(3.into(), 0.into());
+        test_ok(0.into(), 3.into());
+    }
+
+    #[test]
+    fn exp_gadget_one() {
+        test_ok(1.into(), 1.into());
+        test_ok(7.into(), 1.into());
+        test_ok(Word::MAX, 1.into());
+    }
+
+    #[test]
+    fn exp_gadget_simple() {
+        test_ok(2.into(), 2.into());
+        test_ok(3.into(), 13.into());
+        test_ok(0xcafe.into(), 0x100.into());
+    }
+
+    #[test]
+    fn exp_gadget_overflow() {
+        test_ok(2.into(), 256.into());
+        test_ok(Word::MAX, Word::MAX);
+    }
+
+    #[test]
+    fn exp_gadget_rand() {
+        test_ok(rand_word(), rand_word());
+    }
+}
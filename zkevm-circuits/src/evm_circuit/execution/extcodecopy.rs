@@ -0,0 +1,323 @@
+use crate::{
+    evm_circuit::{
+        execution::ExecutionGadget,
+        param::{N_BYTES_ACCOUNT_ADDRESS, N_BYTES_MEMORY_ADDRESS, N_BYTES_MEMORY_WORD_SIZE},
+        step::ExecutionState,
+        util::{
+            common_gadget::SameContextGadget,
+            constraint_builder::{
+                ConstraintBuilder, ReversionInfo, StepStateTransition,
+                Transition::{Delta, To},
+            },
+            from_bytes,
+            memory_gadget::{MemoryAddressGadget, MemoryCopierGasGadget, MemoryExpansionGadget},
+            not, select, CachedRegion, Cell, MemoryAddress, RandomLinearCombination, Word,
+        },
+        witness::{Block, Call, ExecStep, Transaction},
+    },
+    table::{AccountFieldTag, CallContextFieldTag},
+    util::Expr,
+};
+use bus_mapping::circuit_input_builder::CopyDataType;
+use eth_types::{evm_types::GasCost, Field, ToAddress, ToLittleEndian, ToScalar};
+use halo2_proofs::{circuit::Value, plonk::Error};
+
+#[derive(Clone, Debug)]
+pub(crate) struct ExtcodecopyGadget<F> {
+    same_context: SameContextGadget<F>,
+    external_address: RandomLinearCombination<F, N_BYTES_ACCOUNT_ADDRESS>,
+    memory_address: MemoryAddressGadget<F>,
+    code_offset: MemoryAddress<F>,
+    tx_id: Cell<F>,
+    reversion_info: ReversionInfo<F>,
+    is_warm: Cell<F>,
+    code_hash: Cell<F>,
+    code_size: Cell<F>,
+    copy_rwc_inc: Cell<F>,
+    memory_expansion: MemoryExpansionGadget<F, 1, N_BYTES_MEMORY_WORD_SIZE>,
+    memory_copier_gas: MemoryCopierGasGadget<F, { GasCost::COPY }>,
+}
+
+impl<F: Field> ExecutionGadget<F> for ExtcodecopyGadget<F> {
+    const NAME: &'static str = "EXTCODECOPY";
+
+    const EXECUTION_STATE: ExecutionState = ExecutionState::EXTCODECOPY;
+
+    fn configure(cb: &mut ConstraintBuilder<F>) -> Self {
+        let opcode = cb.query_cell();
+
+        let external_address = cb.query_rlc();
+        let memory_offset = cb.query_cell();
+        let code_offset = cb.query_rlc();
+        let memory_length = cb.query_rlc();
+
+        cb.stack_pop(external_address.expr());
+        cb.stack_pop(memory_offset.expr());
+        cb.stack_pop(code_offset.expr());
+        cb.stack_pop(memory_length.expr());
+
+        let tx_id = cb.call_context(None, CallContextFieldTag::TxId);
+        let mut reversion_info = cb.reversion_info_read(None);
+        let is_warm = cb.query_bool();
+        cb.account_access_list_write(
+            tx_id.expr(),
+            from_bytes::expr(&external_address.cells),
+            1.expr(),
+            is_warm.expr(),
+            Some(&mut reversion_info),
+        );
+
+        let code_hash = cb.query_cell();
+        cb.account_read(
+            from_bytes::expr(&external_address.cells),
+            AccountFieldTag::CodeHash,
+            code_hash.expr(),
+        );
+        let code_size = cb.bytecode_length(code_hash.expr());
+
+        let memory_address = MemoryAddressGadget::construct(cb, memory_offset, memory_length);
+        let memory_expansion = MemoryExpansionGadget::construct(
+            cb,
+            cb.curr.state.memory_word_size.expr(),
+            [memory_address.address()],
+        );
+        let memory_copier_gas = MemoryCopierGasGadget::construct(
+            cb,
+            memory_address.length(),
+            memory_expansion.gas_cost(),
+        );
+        let gas_cost = memory_copier_gas.gas_cost()
+            + select::expr(
+                is_warm.expr(),
+                GasCost::WARM_ACCESS.expr(),
+                GasCost::COLD_ACCOUNT_ACCESS.expr(),
+            );
+
+        // Out of bound bytes of the bytecode are padded with 0s by the copy
+        // circuit.
+        let copy_rwc_inc = cb.query_cell();
+        cb.condition(memory_address.has_length(), |cb| {
+            cb.copy_table_lookup(
+                code_hash.expr(),
+                CopyDataType::Bytecode.expr(),
+                cb.curr.state.call_id.expr(),
+                CopyDataType::Memory.expr(),
+                from_bytes::expr(&code_offset.cells),
+                code_size.expr(),
+                memory_address.offset(),
+                memory_address.length(),
+                0.expr(), // for EXTCODECOPY, rlc_acc is 0
+                copy_rwc_inc.expr(),
+            );
+        });
+        cb.condition(not::expr(memory_address.has_length()), |cb| {
+            cb.require_zero(
+                "if no bytes to copy, copy table rwc inc == 0",
+                copy_rwc_inc.expr(),
+            );
+        });
+
+        let step_state_transition = StepStateTransition {
+            rw_counter: Delta(cb.rw_counter_offset()),
+            program_counter: Delta(1.expr()),
+            stack_pointer: Delta(4.expr()),
+            memory_word_size: To(memory_expansion.next_memory_word_size()),
+            gas_left: Delta(-gas_cost),
+            reversible_write_counter: Delta(1.expr()),
+            ..Default::default()
+        };
+        let same_context = SameContextGadget::construct(cb, opcode, step_state_transition);
+
+        Self {
+            same_context,
+            external_address,
+            memory_address,
+            code_offset,
+            tx_id,
+            reversion_info,
+            is_warm,
+            code_hash,
+            code_size,
+            copy_rwc_inc,
+            memory_expansion,
+            memory_copier_gas,
+        }
+    }
+
+    fn assign_exec_step(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        block: &Block<F>,
+        tx: &Transaction,
+        call: &Call,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        self.same_context.assign_exec_step(region, offset, step)?;
+
+        let [external_address, memory_offset, code_offset, memory_length] =
+            [0, 1, 2, 3].map(|idx| block.rws[step.rw_indices[idx]].stack_value());
+        let mut le_bytes = external_address.to_address().0;
+        le_bytes.reverse();
+        self.external_address
+            .assign(region, offset, Some(le_bytes))?;
+        let memory_address = self.memory_address.assign(
+            region,
+            offset,
+            memory_offset,
+            memory_length,
+            block.randomness,
+        )?;
+        self.code_offset.assign(
+            region,
+            offset,
+            Some(
+                code_offset.to_le_bytes()[..N_BYTES_MEMORY_ADDRESS]
+                    .try_into()
+                    .unwrap(),
+            ),
+        )?;
+
+        self.tx_id
+            .assign(region, offset, Value::known(F::from(tx.id as u64)))?;
+        self.reversion_info.assign(
+            region,
+            offset,
+            call.rw_counter_end_of_reversion,
+            call.is_persistent,
+        )?;
+
+        let (_, is_warm) = block.rws[step.rw_indices[7]].tx_access_list_value_pair();
+        self.is_warm
+            .assign(region, offset, Value::known(F::from(is_warm as u64)))?;
+
+        let (code_hash, _) = block.rws[step.rw_indices[8]].account_value_pair();
+        self.code_hash.assign(
+            region,
+            offset,
+            Value::known(Word::random_linear_combine(
+                code_hash.to_le_bytes(),
+                block.randomness,
+            )),
+        )?;
+        let code_size = block
+            .bytecodes
+            .get(&code_hash)
+            .expect("could not find external bytecode")
+            .bytes
+            .len() as u64;
+        self.code_size
+            .assign(region, offset, Value::known(F::from(code_size)))?;
+
+        // rw_counter increase from copy table lookup is number of bytes copied.
+        self.copy_rwc_inc.assign(
+            region,
+            offset,
+            Value::known(
+                memory_length
+                    .to_scalar()
+                    .expect("unexpected U256 -> Scalar conversion failure"),
+            ),
+        )?;
+
+        let (_, memory_expansion_gas_cost) = self.memory_expansion.assign(
+            region,
+            offset,
+            step.memory_word_size(),
+            [memory_address],
+        )?;
+        self.memory_copier_gas.assign(
+            region,
+            offset,
+            memory_length.as_u64(),
+            memory_expansion_gas_cost,
+        )?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_util::run_test_circuits;
+    use eth_types::{address, bytecode, Address, Bytecode, Bytes, ToWord, Word};
+    use lazy_static::lazy_static;
+    use mock::TestContext;
+
+    lazy_static! {
+        static ref EXTERNAL_ADDRESS: Address =
+            address!("0xaabbccddee000000000000000000000000000000");
+    }
+
+    fn test_ok(
+        external_code: Option<Bytes>,
+        memory_offset: usize,
+        code_offset: usize,
+        length: usize,
+        is_warm: bool,
+    ) {
+        let mut code = Bytecode::default();
+        if is_warm {
+            code.append(&bytecode! {
+                PUSH20(EXTERNAL_ADDRESS.to_word())
+                EXTCODEHASH
+                POP
+            });
+        }
+        code.append(&bytecode! {
+            PUSH32(length)
+            PUSH32(code_offset)
+            PUSH32(memory_offset)
+            PUSH20(EXTERNAL_ADDRESS.to_word())
+            EXTCODECOPY
+            STOP
+        });
+
+        let ctx = TestContext::<3, 1>::new(
+            None,
+            |accs| {
+                accs[0]
+                    .address(address!("0x0000000000000000000000000000000000000010"))
+                    .balance(Word::from(1u64 << 20))
+                    .code(code);
+                accs[1].address(*EXTERNAL_ADDRESS);
+                if let Some(external_code) = external_code {
+                    accs[1].code(external_code);
+                }
+                accs[2]
+                    .address(address!("0x0000000000000000000000000000000000cafe01"))
+                    .balance(Word::from(1u64 << 20));
+            },
+            |mut txs, accs| {
+                txs[0].to(accs[0].address).from(accs[2].address);
+            },
+            |block, _tx| block.number(0xcafeu64),
+        )
+        .unwrap();
+
+        assert_eq!(run_test_circuits(ctx, None), Ok(()));
+    }
+
+    #[test]
+    fn extcodecopy_empty_account() {
+        test_ok(None, 0x00, 0x00, 0x00, false);
+        test_ok(None, 0x00, 0x00, 0x20, true);
+    }
+
+    #[test]
+    fn extcodecopy_cold_account() {
+        test_ok(Some(Bytes::from([10, 40])), 0x00, 0x00, 0x02, false);
+        test_ok(Some(Bytes::from([10, 40])), 0x20, 0x00, 0x40, false);
+    }
+
+    #[test]
+    fn extcodecopy_warm_account() {
+        test_ok(Some(Bytes::from([10, 40])), 0x00, 0x00, 0x02, true);
+        test_ok(Some(Bytes::from([10, 40])), 0x20, 0x01, 0x40, true);
+    }
+
+    #[test]
+    fn extcodecopy_zero_length() {
+        test_ok(Some(Bytes::from([10, 40])), 0x40, 0x00, 0x00, true);
+    }
+}
@@ -202,13 +202,12 @@ mod test {
             .map(|a| a.address)
             .unwrap_or(*EXTERNAL_ADDRESS);
 
-        // Make the external account warm, if needed, by first getting its external code
-        // hash.
+        // Make the external account warm, if needed, by first getting its balance.
         let mut code = Bytecode::default();
         if is_warm {
             code.append(&bytecode! {
                 PUSH20(external_address.to_word())
-                EXTCODEHASH // TODO: Change this to BALANCE once is implemented
+                BALANCE
                 POP
             });
         }
@@ -253,7 +252,7 @@ mod test {
             .expect("could not handle block tx");
 
         test_circuits_using_witness_block(
-            block_convert(&builder.block, &builder.code_db),
+            block_convert(&builder.block, &builder.code_db).unwrap(),
             BytecodeTestConfig::default(),
         )
         .unwrap();
@@ -0,0 +1,213 @@
+use crate::{
+    evm_circuit::{
+        execution::ExecutionGadget,
+        param::{N_BYTES_ACCOUNT_ADDRESS, N_BYTES_U64},
+        step::ExecutionState,
+        util::{
+            common_gadget::SameContextGadget,
+            constraint_builder::{
+                ConstraintBuilder, ReversionInfo, StepStateTransition, Transition::Delta,
+            },
+            from_bytes, select, CachedRegion, Cell, RandomLinearCombination, Word,
+        },
+        witness::{Block, Call, ExecStep, Transaction},
+    },
+    table::{AccountFieldTag, CallContextFieldTag},
+    util::Expr,
+};
+use eth_types::{evm_types::GasCost, Field, ToAddress, ToLittleEndian};
+use halo2_proofs::{circuit::Value, plonk::Error};
+
+#[derive(Clone, Debug)]
+pub(crate) struct ExtcodesizeGadget<F> {
+    same_context: SameContextGadget<F>,
+    external_address: RandomLinearCombination<F, N_BYTES_ACCOUNT_ADDRESS>,
+    tx_id: Cell<F>,
+    reversion_info: ReversionInfo<F>,
+    is_warm: Cell<F>,
+    code_hash: Cell<F>,
+    code_size: RandomLinearCombination<F, N_BYTES_U64>,
+}
+
+impl<F: Field> ExecutionGadget<F> for ExtcodesizeGadget<F> {
+    const NAME: &'static str = "EXTCODESIZE";
+
+    const EXECUTION_STATE: ExecutionState = ExecutionState::EXTCODESIZE;
+
+    fn configure(cb: &mut ConstraintBuilder<F>) -> Self {
+        let opcode = cb.query_cell();
+
+        let external_address = cb.query_rlc();
+        cb.stack_pop(external_address.expr());
+
+        let tx_id = cb.call_context(None, CallContextFieldTag::TxId);
+        let mut reversion_info = cb.reversion_info_read(None);
+        let is_warm = cb.query_bool();
+        cb.account_access_list_write(
+            tx_id.expr(),
+            from_bytes::expr(&external_address.cells),
+            1.expr(),
+            is_warm.expr(),
+            Some(&mut reversion_info),
+        );
+
+        let code_hash = cb.query_cell();
+        cb.account_read(
+            from_bytes::expr(&external_address.cells),
+            AccountFieldTag::CodeHash,
+            code_hash.expr(),
+        );
+
+        // The code size of an empty account is 0, since its code hash is the
+        // hash of empty bytecode.
+        let code_size = cb.query_rlc();
+        let code_length = cb.bytecode_length(code_hash.expr());
+        cb.require_equal(
+            "code_size == bytecode length",
+            from_bytes::expr(&code_size.cells),
+            code_length.expr(),
+        );
+        cb.stack_push(code_size.expr());
+
+        let gas_cost = select::expr(
+            is_warm.expr(),
+            GasCost::WARM_ACCESS.expr(),
+            GasCost::COLD_ACCOUNT_ACCESS.expr(),
+        );
+        let step_state_transition = StepStateTransition {
+            rw_counter: Delta(cb.rw_counter_offset()),
+            program_counter: Delta(1.expr()),
+            stack_pointer: Delta(0.expr()),
+            gas_left: Delta(-gas_cost),
+            reversible_write_counter: Delta(1.expr()),
+            ..Default::default()
+        };
+        let same_context = SameContextGadget::construct(cb, opcode, step_state_transition);
+
+        Self {
+            same_context,
+            external_address,
+            tx_id,
+            reversion_info,
+            is_warm,
+            code_hash,
+            code_size,
+        }
+    }
+
+    fn assign_exec_step(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        block: &Block<F>,
+        tx: &Transaction,
+        call: &Call,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        self.same_context.assign_exec_step(region, offset, step)?;
+
+        let external_address = block.rws[step.rw_indices[0]].stack_value().to_address();
+        let mut le_bytes = external_address.0;
+        le_bytes.reverse();
+        self.external_address
+            .assign(region, offset, Some(le_bytes))?;
+
+        self.tx_id
+            .assign(region, offset, Value::known(F::from(tx.id as u64)))?;
+        self.reversion_info.assign(
+            region,
+            offset,
+            call.rw_counter_end_of_reversion,
+            call.is_persistent,
+        )?;
+
+        let (_, is_warm) = block.rws[step.rw_indices[4]].tx_access_list_value_pair();
+        self.is_warm
+            .assign(region, offset, Value::known(F::from(is_warm as u64)))?;
+
+        let (code_hash, _) = block.rws[step.rw_indices[5]].account_value_pair();
+        self.code_hash.assign(
+            region,
+            offset,
+            Value::known(Word::random_linear_combine(
+                code_hash.to_le_bytes(),
+                block.randomness,
+            )),
+        )?;
+
+        let code_size = block.rws[step.rw_indices[6]].stack_value().as_u64();
+        self.code_size
+            .assign(region, offset, Some(code_size.to_le_bytes()))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_util::run_test_circuits;
+    use eth_types::{address, bytecode, Address, Bytecode, Bytes, ToWord, Word};
+    use lazy_static::lazy_static;
+    use mock::TestContext;
+
+    lazy_static! {
+        static ref EXTERNAL_ADDRESS: Address =
+            address!("0xaabbccddee000000000000000000000000000000");
+    }
+
+    fn test_ok(external_code: Option<Bytes>, is_warm: bool) {
+        let mut code = Bytecode::default();
+        if is_warm {
+            code.append(&bytecode! {
+                PUSH20(EXTERNAL_ADDRESS.to_word())
+                EXTCODESIZE
+                POP
+            });
+        }
+        code.append(&bytecode! {
+            PUSH20(EXTERNAL_ADDRESS.to_word())
+            EXTCODESIZE
+            STOP
+        });
+
+        let ctx = TestContext::<3, 1>::new(
+            None,
+            |accs| {
+                accs[0]
+                    .address(address!("0x0000000000000000000000000000000000000010"))
+                    .balance(Word::from(1u64 << 20))
+                    .code(code);
+                accs[1].address(*EXTERNAL_ADDRESS);
+                if let Some(external_code) = external_code {
+                    accs[1].code(external_code);
+                }
+                accs[2]
+                    .address(address!("0x0000000000000000000000000000000000cafe01"))
+                    .balance(Word::from(1u64 << 20));
+            },
+            |mut txs, accs| {
+                txs[0].to(accs[0].address).from(accs[2].address);
+            },
+            |block, _tx| block.number(0xcafeu64),
+        )
+        .unwrap();
+
+        assert_eq!(run_test_circuits(ctx, None), Ok(()));
+    }
+
+    #[test]
+    fn extcodesize_gadget_empty_account() {
+        test_ok(None, false);
+        test_ok(None, true);
+    }
+
+    #[test]
+    fn extcodesize_gadget_cold_account() {
+        test_ok(Some(Bytes::from([0x60, 0x01, 0x60, 0x02, 0x01])), false);
+    }
+
+    #[test]
+    fn extcodesize_gadget_warm_account() {
+        test_ok(Some(Bytes::from([0x60, 0x01, 0x60, 0x02, 0x01])), true);
+    }
+}
@@ -0,0 +1,331 @@
+use crate::{
+    evm_circuit::{
+        execution::ExecutionGadget,
+        param::N_BYTES_U64,
+        step::ExecutionState,
+        util::{
+            common_gadget::RestoreContextGadget,
+            constraint_builder::ConstraintBuilder,
+            math_gadget::{IsZeroGadget, MinMaxGadget},
+            not, rlc, CachedRegion, Cell,
+        },
+        witness::{Block, Call, ExecStep, Transaction},
+    },
+    table::CallContextFieldTag,
+    util::Expr,
+};
+use bus_mapping::circuit_input_builder::CopyDataType;
+use eth_types::{Field, ToScalar};
+use halo2_proofs::{circuit::Value, plonk::Error};
+use std::{fmt::Debug, marker::PhantomData};
+
+mod identity;
+
+pub(crate) use identity::IdentityCall;
+
+/// Input and output of a precompiled contract call, shared between the
+/// [`PrecompileGadget`] which moves the data around and the [`PrecompileCall`]
+/// which constrains the relation between them.
+#[derive(Clone, Debug)]
+pub(crate) struct PrecompileIo<F> {
+    pub(crate) input_length: Cell<F>,
+    /// RLC of the input bytes, which is 0 for empty input.
+    pub(crate) input_rlc: Cell<F>,
+    pub(crate) output_length: Cell<F>,
+    /// RLC of the output bytes, which is 0 for empty output.
+    pub(crate) output_rlc: Cell<F>,
+    /// Gas consumed by the precompiled contract, which is all the gas left if
+    /// the call fails.
+    pub(crate) gas_cost: Cell<F>,
+    pub(crate) is_success: Cell<F>,
+}
+
+/// The relation between the input and output of a precompiled contract, and
+/// its gas cost.  Each precompile implements this trait, either with its own
+/// constraints or with a lookup into the table of a dedicated circuit.
+pub(crate) trait PrecompileCall<F: Field>: Clone + Debug {
+    const NAME: &'static str;
+
+    const EXECUTION_STATE: ExecutionState;
+
+    fn configure(cb: &mut ConstraintBuilder<F>, io: &PrecompileIo<F>) -> Self;
+
+    fn assign(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        block: &Block<F>,
+        call: &Call,
+        step: &ExecStep,
+        input: &[u8],
+        output: &[u8],
+    ) -> Result<(), Error>;
+}
+
+/// Gadget for the virtual step executing a precompiled contract, which copies
+/// the input from the caller's memory, writes the output to the callee's
+/// memory as its return data, copies the output to the caller's memory, and
+/// then restores the caller's context.
+#[derive(Clone, Debug)]
+pub(crate) struct PrecompileGadget<F, P> {
+    caller_id: Cell<F>,
+    call_data_offset: Cell<F>,
+    return_data_offset: Cell<F>,
+    return_data_length: Cell<F>,
+    io: PrecompileIo<F>,
+    input_is_empty: IsZeroGadget<F>,
+    output_is_empty: IsZeroGadget<F>,
+    copy_length: MinMaxGadget<F, N_BYTES_U64>,
+    copy_length_is_zero: IsZeroGadget<F>,
+    restore_context: RestoreContextGadget<F>,
+    precompile: P,
+}
+
+impl<F: Field, P: PrecompileCall<F>> ExecutionGadget<F> for PrecompileGadget<F, P> {
+    const NAME: &'static str = P::NAME;
+
+    const EXECUTION_STATE: ExecutionState = P::EXECUTION_STATE;
+
+    fn configure(cb: &mut ConstraintBuilder<F>) -> Self {
+        let [caller_id, call_data_offset, call_data_length, return_data_offset, return_data_length, is_success] =
+            [
+                CallContextFieldTag::CallerId,
+                CallContextFieldTag::CallDataOffset,
+                CallContextFieldTag::CallDataLength,
+                CallContextFieldTag::ReturnDataOffset,
+                CallContextFieldTag::ReturnDataLength,
+                CallContextFieldTag::IsSuccess,
+            ]
+            .map(|field_tag| cb.call_context(None, field_tag));
+
+        // Precompiled contracts are only reached by an internal call.
+        cb.require_zero(
+            "precompile is not a root call",
+            cb.curr.state.is_root.expr(),
+        );
+
+        let io = PrecompileIo {
+            input_length: call_data_length,
+            input_rlc: cb.query_cell(),
+            output_length: cb.query_cell(),
+            output_rlc: cb.query_cell(),
+            gas_cost: cb.query_cell(),
+            is_success,
+        };
+
+        // A failed call returns nothing and consumes all the gas.
+        cb.condition(not::expr(io.is_success.expr()), |cb| {
+            cb.require_zero("failed precompile has no output", io.output_length.expr());
+            cb.require_equal(
+                "failed precompile consumes all gas",
+                io.gas_cost.expr(),
+                cb.curr.state.gas_left.expr(),
+            );
+        });
+
+        // Copy the input from the caller's memory.
+        let input_is_empty = IsZeroGadget::construct(cb, io.input_length.expr());
+        cb.condition(not::expr(input_is_empty.expr()), |cb| {
+            cb.copy_table_lookup(
+                caller_id.expr(),
+                CopyDataType::Memory.expr(),
+                cb.curr.state.call_id.expr(),
+                CopyDataType::RlcAcc.expr(),
+                call_data_offset.expr(),
+                call_data_offset.expr() + io.input_length.expr(),
+                0.expr(),
+                io.input_length.expr(),
+                io.input_rlc.expr(),
+                io.input_length.expr(),
+            );
+        });
+        cb.condition(input_is_empty.expr(), |cb| {
+            cb.require_zero("input rlc is 0 for empty input", io.input_rlc.expr());
+        });
+
+        // Write the output to the callee's memory as its return data.
+        let output_is_empty = IsZeroGadget::construct(cb, io.output_length.expr());
+        cb.condition(not::expr(output_is_empty.expr()), |cb| {
+            cb.copy_table_lookup(
+                cb.curr.state.call_id.expr(),
+                CopyDataType::RlcAcc.expr(),
+                cb.curr.state.call_id.expr(),
+                CopyDataType::Memory.expr(),
+                0.expr(),
+                io.output_length.expr(),
+                0.expr(),
+                io.output_length.expr(),
+                io.output_rlc.expr(),
+                io.output_length.expr(),
+            );
+        });
+        cb.condition(output_is_empty.expr(), |cb| {
+            cb.require_zero("output rlc is 0 for empty output", io.output_rlc.expr());
+        });
+
+        // Copy the output to the caller's memory, up to the requested length.
+        let copy_length =
+            MinMaxGadget::construct(cb, return_data_length.expr(), io.output_length.expr());
+        let copy_length_is_zero = IsZeroGadget::construct(cb, copy_length.min());
+        cb.condition(not::expr(copy_length_is_zero.expr()), |cb| {
+            cb.copy_table_lookup(
+                cb.curr.state.call_id.expr(),
+                CopyDataType::Memory.expr(),
+                caller_id.expr(),
+                CopyDataType::Memory.expr(),
+                0.expr(),
+                io.output_length.expr(),
+                return_data_offset.expr(),
+                copy_length.min(),
+                0.expr(),
+                copy_length.min() + copy_length.min(),
+            );
+        });
+
+        let precompile = P::configure(cb, &io);
+
+        let restore_context = RestoreContextGadget::construct(
+            cb,
+            io.is_success.expr(),
+            0.expr(),
+            0.expr(),
+            0.expr(),
+            io.output_length.expr(),
+            io.gas_cost.expr(),
+        );
+
+        Self {
+            caller_id,
+            call_data_offset,
+            return_data_offset,
+            return_data_length,
+            io,
+            input_is_empty,
+            output_is_empty,
+            copy_length,
+            copy_length_is_zero,
+            restore_context,
+            precompile,
+        }
+    }
+
+    fn assign_exec_step(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        block: &Block<F>,
+        _: &Transaction,
+        call: &Call,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        let caller_id = block.rws[step.rw_indices[0]].call_context_value();
+        for (cell, value) in [
+            (&self.caller_id, caller_id),
+            (&self.call_data_offset, call.call_data_offset.into()),
+            (&self.io.input_length, call.call_data_length.into()),
+            (&self.return_data_offset, call.return_data_offset.into()),
+            (&self.return_data_length, call.return_data_length.into()),
+            (&self.io.is_success, (call.is_success as u64).into()),
+        ] {
+            cell.assign(
+                region,
+                offset,
+                Value::known(
+                    value
+                        .to_scalar()
+                        .expect("unexpected U256 -> Scalar conversion failure"),
+                ),
+            )?;
+        }
+
+        // The output length is written to the caller's last callee return data
+        // length by the last lookup of the step.
+        let input_length = call.call_data_length as usize;
+        let output_length = block.rws[*step.rw_indices.last().unwrap()]
+            .call_context_value()
+            .as_usize();
+        let input: Vec<u8> = (0..input_length)
+            .map(|i| block.rws[step.rw_indices[6 + i]].memory_value())
+            .collect();
+        let output: Vec<u8> = (0..output_length)
+            .map(|i| block.rws[step.rw_indices[6 + input_length + i]].memory_value())
+            .collect();
+
+        self.io.output_length.assign(
+            region,
+            offset,
+            Value::known(F::from(output_length as u64)),
+        )?;
+        for (cell, bytes) in [(&self.io.input_rlc, &input), (&self.io.output_rlc, &output)] {
+            cell.assign(
+                region,
+                offset,
+                Value::known(rlc::value(bytes.iter().rev(), block.randomness)),
+            )?;
+        }
+        self.io
+            .gas_cost
+            .assign(region, offset, Value::known(F::from(step.gas_cost)))?;
+
+        self.input_is_empty
+            .assign(region, offset, F::from(input_length as u64))?;
+        self.output_is_empty
+            .assign(region, offset, F::from(output_length as u64))?;
+        let (copy_length, _) = self.copy_length.assign(
+            region,
+            offset,
+            F::from(call.return_data_length),
+            F::from(output_length as u64),
+        )?;
+        self.copy_length_is_zero
+            .assign(region, offset, copy_length)?;
+
+        self.precompile
+            .assign(region, offset, block, call, step, &input, &output)?;
+
+        let copy_rw_increase = 2 * std::cmp::min(call.return_data_length as usize, output_length);
+        self.restore_context.assign(
+            region,
+            offset,
+            block,
+            call,
+            step,
+            6 + input_length + output_length + copy_rw_increase,
+        )
+    }
+}
+
+/// Placeholder for the precompiled contracts whose relation is not
+/// constrained yet.  Its constraints are never satisfied, so that a step
+/// calling such a precompile can't be proven with a forged output or gas cost.
+#[derive(Clone, Debug)]
+pub(crate) struct DummyPrecompile<F, const S: ExecutionState> {
+    _marker: PhantomData<F>,
+}
+
+impl<F: Field, const S: ExecutionState> PrecompileCall<F> for DummyPrecompile<F, S> {
+    const NAME: &'static str = "DUMMY_PRECOMPILE";
+
+    const EXECUTION_STATE: ExecutionState = S;
+
+    fn configure(cb: &mut ConstraintBuilder<F>, _: &PrecompileIo<F>) -> Self {
+        cb.require_zero("precompile is not supported yet", 1.expr());
+
+        Self {
+            _marker: PhantomData,
+        }
+    }
+
+    fn assign(
+        &self,
+        _: &mut CachedRegion<'_, '_, F>,
+        _: usize,
+        _: &Block<F>,
+        _: &Call,
+        _: &ExecStep,
+        _: &[u8],
+        _: &[u8],
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+}
@@ -0,0 +1,151 @@
+use super::{PrecompileCall, PrecompileIo};
+use crate::{
+    evm_circuit::{
+        param::N_BYTES_GAS,
+        step::ExecutionState,
+        util::{
+            constraint_builder::ConstraintBuilder, math_gadget::LtGadget,
+            memory_gadget::MemoryWordSizeGadget, not, CachedRegion,
+        },
+        witness::{Block, Call, ExecStep},
+    },
+    util::Expr,
+};
+use eth_types::{evm_types::GasCost, Field};
+use halo2_proofs::plonk::Error;
+
+/// The identity precompile, whose output is its input, and which costs
+/// `15 + 3 * words` gas.  The call fails only if the gas left is not enough.
+#[derive(Clone, Debug)]
+pub(crate) struct IdentityCall<F> {
+    input_word_size: MemoryWordSizeGadget<F>,
+    insufficient_gas: LtGadget<F, N_BYTES_GAS>,
+}
+
+impl<F: Field> PrecompileCall<F> for IdentityCall<F> {
+    const NAME: &'static str = "IDENTITY";
+
+    const EXECUTION_STATE: ExecutionState = ExecutionState::PrecompileIdentity;
+
+    fn configure(cb: &mut ConstraintBuilder<F>, io: &PrecompileIo<F>) -> Self {
+        let input_word_size = MemoryWordSizeGadget::construct(cb, io.input_length.expr());
+        let required_gas = GasCost::PRECOMPILE_IDENTITY_BASE.expr()
+            + GasCost::PRECOMPILE_IDENTITY_PER_WORD.expr() * input_word_size.expr();
+        let insufficient_gas =
+            LtGadget::construct(cb, cb.curr.state.gas_left.expr(), required_gas.clone());
+        cb.require_equal(
+            "identity succeeds iff gas left is enough",
+            io.is_success.expr(),
+            not::expr(insufficient_gas.expr()),
+        );
+
+        cb.condition(io.is_success.expr(), |cb| {
+            cb.require_equal(
+                "output length == input length",
+                io.output_length.expr(),
+                io.input_length.expr(),
+            );
+            cb.require_equal("output == input", io.output_rlc.expr(), io.input_rlc.expr());
+            cb.require_equal(
+                "gas cost == 15 + 3 * words",
+                io.gas_cost.expr(),
+                required_gas,
+            );
+        });
+
+        Self {
+            input_word_size,
+            insufficient_gas,
+        }
+    }
+
+    fn assign(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        _: &Block<F>,
+        call: &Call,
+        step: &ExecStep,
+        _: &[u8],
+        _: &[u8],
+    ) -> Result<(), Error> {
+        let input_word_size = self
+            .input_word_size
+            .assign(region, offset, call.call_data_length)?;
+        let required_gas = GasCost::PRECOMPILE_IDENTITY_BASE.as_u64()
+            + GasCost::PRECOMPILE_IDENTITY_PER_WORD.as_u64() * input_word_size;
+        self.insufficient_gas.assign(
+            region,
+            offset,
+            F::from(step.gas_left),
+            F::from(required_gas),
+        )?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_util::run_test_circuits;
+    use eth_types::{address, bytecode, word, Word};
+    use mock::TestContext;
+
+    fn test_ok(call_data_length: usize, return_data_length: usize, gas: u64) {
+        let code = bytecode! {
+            PUSH32(word!("0x0102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f20"))
+            PUSH1(0x00)
+            MSTORE
+            PUSH32(word!("0x2122232425262728292a2b2c2d2e2f303132333435363738393a3b3c3d3e3f40"))
+            PUSH1(0x20)
+            MSTORE
+            PUSH32(return_data_length) // retLength
+            PUSH1(0x40) // retOffset
+            PUSH32(call_data_length) // argsLength
+            PUSH1(0x00) // argsOffset
+            PUSH1(0x04) // address
+            PUSH32(gas) // gas
+            STATICCALL
+            STOP
+        };
+
+        let ctx = TestContext::<2, 1>::new(
+            None,
+            |accs| {
+                accs[0]
+                    .address(address!("0x0000000000000000000000000000000000000010"))
+                    .balance(Word::from(1u64 << 20))
+                    .code(code);
+                accs[1]
+                    .address(address!("0x0000000000000000000000000000000000cafe01"))
+                    .balance(Word::from(1u64 << 20));
+            },
+            |mut txs, accs| {
+                txs[0].to(accs[0].address).from(accs[1].address);
+            },
+            |block, _tx| block.number(0xcafeu64),
+        )
+        .unwrap();
+
+        assert_eq!(run_test_circuits(ctx, None), Ok(()));
+    }
+
+    #[test]
+    fn precompile_identity_empty_input() {
+        test_ok(0x00, 0x20, 0xffff);
+    }
+
+    #[test]
+    fn precompile_identity() {
+        test_ok(0x20, 0x20, 0xffff);
+        test_ok(0x3f, 0x10, 0xffff);
+        test_ok(0x10, 0x40, 0xffff);
+        test_ok(0x40, 0x00, 0xffff);
+    }
+
+    #[test]
+    fn precompile_identity_out_of_gas() {
+        // 15 + 3 * 2 = 21 gas is required for 2 words.
+        test_ok(0x40, 0x20, 20);
+    }
+}
@@ -6,21 +6,22 @@ use crate::{
         util::{
             common_gadget::RestoreContextGadget,
             constraint_builder::{
-                ConstraintBuilder, StepStateTransition,
+                ConstraintBuilder, ReversionInfo, StepStateTransition,
                 Transition::{Delta, To},
             },
             math_gadget::{IsZeroGadget, MinMaxGadget},
             memory_gadget::{MemoryAddressGadget, MemoryExpansionGadget},
-            not, CachedRegion, Cell,
+            not, CachedRegion, Cell, Word,
         },
         witness::{Block, Call, ExecStep, Transaction},
     },
-    table::CallContextFieldTag,
+    table::{AccountFieldTag, CallContextFieldTag},
     util::Expr,
 };
 use bus_mapping::{circuit_input_builder::CopyDataType, evm::OpcodeId};
-use eth_types::Field;
+use eth_types::{evm_types::GasCost, Field, ToLittleEndian, ToScalar};
 use halo2_proofs::{circuit::Value, plonk::Error};
+use keccak256::EMPTY_HASH_LE;
 
 #[derive(Clone, Debug)]
 pub(crate) struct ReturnGadget<F> {
@@ -38,6 +39,10 @@ pub(crate) struct ReturnGadget<F> {
     return_data_offset: Cell<F>,
     return_data_length: Cell<F>,
 
+    deployed_code_hash: Cell<F>,
+    callee_address: Cell<F>,
+    reversion_info: ReversionInfo<F>,
+
     memory_expansion: MemoryExpansionGadget<F, 1, N_BYTES_MEMORY_WORD_SIZE>,
 }
 
@@ -91,12 +96,52 @@ impl<F: Field> ExecutionGadget<F> for ReturnGadget<F> {
                 range.length(),
             );
         });
-        cb.condition(
-            is_create.clone() * is_success.expr() * not::expr(copy_rw_increase_is_zero.expr()),
-            |_cb| {
-                // TODO: copy_table_lookup for contract creation.
-            },
-        );
+        cb.condition(is_create.clone() * not::expr(is_success.expr()), |cb| {
+            cb.require_zero(
+                "rw counter is 0 if there is no copy event",
+                copy_rw_increase.expr(),
+            );
+        });
+        let is_code_deposit =
+            is_create.clone() * is_success.expr() * not::expr(copy_rw_increase_is_zero.expr());
+        let deployed_code_hash = cb.query_cell();
+        let (callee_address, reversion_info) = cb.condition(is_code_deposit.clone(), |cb| {
+            cb.copy_table_lookup(
+                cb.curr.state.call_id.expr(),
+                CopyDataType::Memory.expr(),
+                deployed_code_hash.expr(),
+                CopyDataType::Bytecode.expr(),
+                range.offset(),
+                range.address(),
+                0.expr(),
+                range.length(),
+                0.expr(),
+                copy_rw_increase.expr(),
+            );
+
+            // Set the code hash of the created account to the one of the
+            // deployed code.
+            let callee_address = cb.call_context(None, CallContextFieldTag::CalleeAddress);
+            let mut reversion_info = cb.reversion_info_read(None);
+            cb.account_write(
+                callee_address.expr(),
+                AccountFieldTag::CodeHash,
+                deployed_code_hash.expr(),
+                Word::random_linear_combine_expr(
+                    (*EMPTY_HASH_LE).map(|byte| byte.expr()),
+                    cb.power_of_randomness(),
+                ),
+                Some(&mut reversion_info),
+            );
+
+            (callee_address, reversion_info)
+        });
+
+        // The code deposit is charged for every byte of the deployed code.
+        let code_deposit_cost = is_create.clone()
+            * is_success.expr()
+            * GasCost::CODE_DEPOSIT_BYTE_COST.expr()
+            * range.length();
 
         // Case B in the specs.
         cb.condition(is_root.expr(), |cb| {
@@ -115,7 +160,7 @@ impl<F: Field> ExecutionGadget<F> for ReturnGadget<F> {
                         + not::expr(is_success.expr())
                             * cb.curr.state.reversible_write_counter.expr(),
                 ),
-                gas_left: Delta(-memory_expansion.gas_cost()),
+                gas_left: Delta(-memory_expansion.gas_cost() - code_deposit_cost.clone()),
                 reversible_write_counter: To(0.expr()),
                 memory_word_size: To(0.expr()),
                 ..StepStateTransition::default()
@@ -126,13 +171,17 @@ impl<F: Field> ExecutionGadget<F> for ReturnGadget<F> {
         // TODO: have copy_table_lookup update rw_counter expression so that this can go
         // at the end of the constraints.
         let restore_context = cb.condition(not::expr(is_root.expr()), |cb| {
+            // A successful creation returns the code to be deployed instead
+            // of return data.
+            let has_return_data = not::expr(is_create.clone() * is_success.expr());
             RestoreContextGadget::construct(
                 cb,
                 is_success.expr(),
-                2.expr() * not::expr(is_create.clone()) + copy_rw_increase.expr(),
-                range.offset(),
-                range.length(),
-                memory_expansion.gas_cost(),
+                not::expr(is_create.clone()) * (2.expr() + copy_rw_increase.expr()),
+                is_code_deposit.clone(),
+                has_return_data.clone() * range.offset(),
+                has_return_data * range.length(),
+                memory_expansion.gas_cost() + code_deposit_cost.clone(),
             )
         });
 
@@ -193,6 +242,9 @@ impl<F: Field> ExecutionGadget<F> for ReturnGadget<F> {
             copy_rw_increase_is_zero,
             return_data_offset,
             return_data_length,
+            deployed_code_hash,
+            callee_address,
+            reversion_info,
             restore_context,
             memory_expansion,
         }
@@ -239,7 +291,36 @@ impl<F: Field> ExecutionGadget<F> for ReturnGadget<F> {
             )?;
         }
 
-        let copy_rw_increase = if call.is_create {
+        let is_code_deposit = call.is_create && call.is_success && !length.is_zero();
+        if is_code_deposit {
+            let (deployed_code_hash, _) =
+                block.rws[step.rw_indices[6 + length.as_usize()]].account_value_pair();
+            self.deployed_code_hash.assign(
+                region,
+                offset,
+                Value::known(Word::random_linear_combine(
+                    deployed_code_hash.to_le_bytes(),
+                    block.randomness,
+                )),
+            )?;
+            self.callee_address.assign(
+                region,
+                offset,
+                Value::known(
+                    call.callee_address
+                        .to_scalar()
+                        .expect("unexpected Address -> Scalar conversion failure"),
+                ),
+            )?;
+            self.reversion_info.assign(
+                region,
+                offset,
+                call.rw_counter_end_of_reversion,
+                call.is_persistent,
+            )?;
+        }
+
+        let copy_rw_increase = if call.is_create && call.is_success {
             length.as_u64()
         } else if !call.is_root {
             2 * std::cmp::min(call.return_data_length, length.as_u64())
@@ -252,8 +333,14 @@ impl<F: Field> ExecutionGadget<F> for ReturnGadget<F> {
             .assign(region, offset, F::from(copy_rw_increase))?;
 
         if !call.is_root {
+            // Skip the rw lookups of the code deposit.
+            let rw_offset = if is_code_deposit {
+                3 + length.as_usize() + 4
+            } else {
+                3
+            };
             self.restore_context
-                .assign(region, offset, block, call, step, 3)?;
+                .assign(region, offset, block, call, step, rw_offset)?;
         }
 
         Ok(())
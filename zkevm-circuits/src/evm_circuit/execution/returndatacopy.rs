@@ -0,0 +1,294 @@
+use crate::{
+    evm_circuit::{
+        execution::ExecutionGadget,
+        param::{N_BYTES_MEMORY_ADDRESS, N_BYTES_MEMORY_WORD_SIZE, N_BYTES_U64},
+        step::ExecutionState,
+        util::{
+            common_gadget::SameContextGadget,
+            constraint_builder::{
+                ConstraintBuilder, StepStateTransition,
+                Transition::{Delta, To},
+            },
+            from_bytes,
+            math_gadget::LtGadget,
+            memory_gadget::{MemoryAddressGadget, MemoryCopierGasGadget, MemoryExpansionGadget},
+            CachedRegion, Cell, MemoryAddress,
+        },
+        witness::{Block, Call, ExecStep, Transaction},
+    },
+    table::CallContextFieldTag,
+    util::Expr,
+};
+use bus_mapping::{circuit_input_builder::CopyDataType, evm::OpcodeId};
+use eth_types::{evm_types::GasCost, Field, ToLittleEndian, ToScalar};
+use halo2_proofs::{circuit::Value, plonk::Error};
+
+/// Gadget for RETURNDATACOPY, which copies the return data of the last callee
+/// from its memory to the memory of the current call.
+#[derive(Clone, Debug)]
+pub(crate) struct ReturnDataCopyGadget<F> {
+    same_context: SameContextGadget<F>,
+    memory_address: MemoryAddressGadget<F>,
+    data_offset: MemoryAddress<F>,
+    last_callee_id: Cell<F>,
+    return_data_offset: Cell<F>,
+    return_data_size: Cell<F>,
+    // constrain the copied data is within the return data
+    out_of_bound: LtGadget<F, N_BYTES_U64>,
+    memory_expansion: MemoryExpansionGadget<F, 1, N_BYTES_MEMORY_WORD_SIZE>,
+    memory_copier_gas: MemoryCopierGasGadget<F, { GasCost::COPY }>,
+}
+
+impl<F: Field> ExecutionGadget<F> for ReturnDataCopyGadget<F> {
+    const NAME: &'static str = "RETURNDATACOPY";
+
+    const EXECUTION_STATE: ExecutionState = ExecutionState::RETURNDATACOPY;
+
+    fn configure(cb: &mut ConstraintBuilder<F>) -> Self {
+        let opcode = cb.query_cell();
+
+        let dest_offset = cb.query_cell();
+        let data_offset = cb.query_rlc();
+        let size = cb.query_rlc();
+
+        // Pop dest_offset, offset, size from stack
+        cb.stack_pop(dest_offset.expr());
+        cb.stack_pop(data_offset.expr());
+        cb.stack_pop(size.expr());
+
+        let memory_address = MemoryAddressGadget::construct(cb, dest_offset, size);
+
+        // Lookup the last callee and where its return data is in its memory
+        let [last_callee_id, return_data_offset, return_data_size] = [
+            CallContextFieldTag::LastCalleeId,
+            CallContextFieldTag::LastCalleeReturnDataOffset,
+            CallContextFieldTag::LastCalleeReturnDataLength,
+        ]
+        .map(|field_tag| cb.call_context(None, field_tag));
+
+        // Copying out of the return data halts with
+        // `ExecutionState::ErrorReturnDataOutOfBound`.
+        let out_of_bound = LtGadget::construct(
+            cb,
+            return_data_size.expr(),
+            from_bytes::expr(&data_offset.cells) + memory_address.length(),
+        );
+        cb.require_zero("offset + size <= return_data_size", out_of_bound.expr());
+
+        // Calculate the next memory size and the gas cost for this memory
+        // access
+        let memory_expansion = MemoryExpansionGadget::construct(
+            cb,
+            cb.curr.state.memory_word_size.expr(),
+            [memory_address.address()],
+        );
+        let memory_copier_gas = MemoryCopierGasGadget::construct(
+            cb,
+            memory_address.length(),
+            memory_expansion.gas_cost(),
+        );
+
+        // Every byte is read from the memory of last callee and written to the
+        // memory of current call.
+        cb.condition(memory_address.has_length(), |cb| {
+            cb.copy_table_lookup(
+                last_callee_id.expr(),
+                CopyDataType::Memory.expr(),
+                cb.curr.state.call_id.expr(),
+                CopyDataType::Memory.expr(),
+                return_data_offset.expr() + from_bytes::expr(&data_offset.cells),
+                return_data_offset.expr() + return_data_size.expr(),
+                memory_address.offset(),
+                memory_address.length(),
+                0.expr(), // for RETURNDATACOPY rlc_acc is 0
+                memory_address.length() * 2.expr(),
+            );
+        });
+
+        // State transition
+        let step_state_transition = StepStateTransition {
+            rw_counter: Delta(cb.rw_counter_offset()),
+            program_counter: Delta(1.expr()),
+            stack_pointer: Delta(3.expr()),
+            gas_left: Delta(
+                -(OpcodeId::RETURNDATACOPY.constant_gas_cost().expr()
+                    + memory_copier_gas.gas_cost()),
+            ),
+            memory_word_size: To(memory_expansion.next_memory_word_size()),
+            ..Default::default()
+        };
+        let same_context = SameContextGadget::construct(cb, opcode, step_state_transition);
+
+        Self {
+            same_context,
+            memory_address,
+            data_offset,
+            last_callee_id,
+            return_data_offset,
+            return_data_size,
+            out_of_bound,
+            memory_expansion,
+            memory_copier_gas,
+        }
+    }
+
+    fn assign_exec_step(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        block: &Block<F>,
+        _: &Transaction,
+        _: &Call,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        self.same_context.assign_exec_step(region, offset, step)?;
+
+        let [dest_offset, data_offset, size] =
+            [0, 1, 2].map(|idx| block.rws[step.rw_indices[idx]].stack_value());
+        let memory_address =
+            self.memory_address
+                .assign(region, offset, dest_offset, size, block.randomness)?;
+        self.data_offset.assign(
+            region,
+            offset,
+            Some(
+                data_offset.to_le_bytes()[..N_BYTES_MEMORY_ADDRESS]
+                    .try_into()
+                    .unwrap(),
+            ),
+        )?;
+
+        let [last_callee_id, return_data_offset, return_data_size] =
+            [3, 4, 5].map(|idx| block.rws[step.rw_indices[idx]].call_context_value());
+        for (cell, value) in [
+            (&self.last_callee_id, last_callee_id),
+            (&self.return_data_offset, return_data_offset),
+            (&self.return_data_size, return_data_size),
+        ] {
+            cell.assign(
+                region,
+                offset,
+                Value::known(
+                    value
+                        .to_scalar()
+                        .expect("unexpected U256 -> Scalar conversion failure"),
+                ),
+            )?;
+        }
+
+        self.out_of_bound.assign(
+            region,
+            offset,
+            F::from(return_data_size.as_u64()),
+            F::from(data_offset.as_u64() + size.as_u64()),
+        )?;
+
+        // Memory expansion
+        let (_, memory_expansion_gas_cost) = self.memory_expansion.assign(
+            region,
+            offset,
+            step.memory_word_size(),
+            [memory_address],
+        )?;
+
+        self.memory_copier_gas.assign(
+            region,
+            offset,
+            size.as_u64(),
+            memory_expansion_gas_cost as u64,
+        )?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_util::run_test_circuits;
+    use eth_types::{address, bytecode, bytecode::Bytecode, evm_types::OpcodeId, Word};
+    use mock::TestContext;
+
+    // Callee stores 0xff..ff in memory and returns or reverts with
+    // `return_data_size` bytes of memory.
+    fn callee_bytecode(return_data_size: usize, is_return: bool) -> Bytecode {
+        let mut code = bytecode! {
+            PUSH32(Word::MAX)
+            PUSH1(0)
+            MSTORE
+            PUSH32(return_data_size)
+            PUSH1(0)
+        };
+        if is_return {
+            code.write_op(OpcodeId::RETURN);
+        } else {
+            code.write_op(OpcodeId::REVERT);
+        }
+        code
+    }
+
+    fn test_ok(
+        return_data_size: usize,
+        is_return: bool,
+        dest_offset: usize,
+        offset: usize,
+        size: usize,
+    ) {
+        let code_a = bytecode! {
+            PUSH1(0) // retLength
+            PUSH1(0) // retOffset
+            PUSH1(0) // argsLength
+            PUSH1(0) // argsOffset
+            PUSH1(0) // value
+            PUSH32(0x20) // addr
+            GAS
+            CALL
+            PUSH32(size)
+            PUSH32(offset)
+            PUSH32(dest_offset)
+            RETURNDATACOPY
+            STOP
+        };
+
+        let ctx = TestContext::<3, 1>::new(
+            None,
+            |accs| {
+                accs[0]
+                    .address(address!("0x0000000000000000000000000000000000000000"))
+                    .balance(Word::from(1u64 << 30));
+                accs[1]
+                    .address(address!("0x0000000000000000000000000000000000000010"))
+                    .balance(Word::from(1u64 << 20))
+                    .code(code_a);
+                accs[2]
+                    .address(address!("0x0000000000000000000000000000000000000020"))
+                    .balance(Word::from(1u64 << 20))
+                    .code(callee_bytecode(return_data_size, is_return));
+            },
+            |mut txs, accs| {
+                txs[0].from(accs[0].address).to(accs[1].address);
+            },
+            |block, _tx| block.number(0xcafeu64),
+        )
+        .unwrap();
+
+        assert_eq!(run_test_circuits(ctx, None), Ok(()));
+    }
+
+    #[test]
+    fn returndatacopy_gadget_simple() {
+        test_ok(0x20, true, 0x00, 0x00, 0x20);
+        test_ok(0x20, false, 0x00, 0x00, 0x20);
+    }
+
+    #[test]
+    fn returndatacopy_gadget_partial() {
+        test_ok(0x40, true, 0x10, 0x08, 0x20);
+        test_ok(0x40, false, 0x41, 0x20, 0x01);
+    }
+
+    #[test]
+    fn returndatacopy_gadget_zero_length() {
+        test_ok(0x00, true, 0x00, 0x00, 0x00);
+        test_ok(0x20, true, 0x40, 0x20, 0x00);
+    }
+}
@@ -0,0 +1,285 @@
+use crate::{
+    evm_circuit::{
+        execution::ExecutionGadget,
+        step::ExecutionState,
+        table::{FixedTableTag, Lookup},
+        util::{
+            self,
+            common_gadget::SameContextGadget,
+            constraint_builder::{ConstraintBuilder, StepStateTransition, Transition::Delta},
+            from_bytes,
+            math_gadget::{IsZeroGadget, LtGadget, LtWordGadget, MulAddWordsGadget},
+            select, sum, CachedRegion,
+        },
+        witness::{Block, Call, ExecStep, Transaction},
+    },
+    util::Expr,
+};
+use bus_mapping::evm::OpcodeId;
+use eth_types::{Field, ToLittleEndian, U256};
+use halo2_proofs::plonk::Error;
+
+/// SarGadget verifies opcode SAR, i.e. push == floor(pop2 / 2^pop1) when pop2
+/// is interpreted as a signed (two's complement) 256-bit integer.
+///
+/// For a negative `a`, `floor(a / 2^shift) == !(!a >> shift)`, so the gadget
+/// verifies a logical shift right of `dividend = is_neg ? !a : a`, and then
+/// negates the quotient bitwise again for negative values. When shift >= 256
+/// the divisor is zero, the quotient is zero and the result is therefore 0 for
+/// non-negative values and -1 for negative ones.
+#[derive(Clone, Debug)]
+pub(crate) struct SarGadget<F> {
+    same_context: SameContextGadget<F>,
+    /// Shift word
+    shift: util::Word<F>,
+    /// Value to be shifted
+    a: util::Word<F>,
+    /// Result of the arithmetic shift
+    result: util::Word<F>,
+    quotient: util::Word<F>,
+    divisor: util::Word<F>,
+    remainder: util::Word<F>,
+    dividend: util::Word<F>,
+    /// Check if the value is negative, i.e. its most significant byte >= 128
+    a_is_neg: LtGadget<F, 1>,
+    /// Check if shift < 256, i.e. the higher 31 bytes of shift are zero
+    shift_hi_is_zero: IsZeroGadget<F>,
+    /// Gadget that verifies quotient * divisor + remainder = dividend
+    mul_add_words: MulAddWordsGadget<F>,
+    /// Check if divisor is zero
+    divisor_is_zero: IsZeroGadget<F>,
+    /// Check if remainder < divisor when divisor != 0
+    remainder_lt_divisor: LtWordGadget<F>,
+}
+
+impl<F: Field> ExecutionGadget<F> for SarGadget<F> {
+    const NAME: &'static str = "SAR";
+
+    const EXECUTION_STATE: ExecutionState = ExecutionState::SAR;
+
+    fn configure(cb: &mut ConstraintBuilder<F>) -> Self {
+        let opcode = cb.query_cell();
+
+        let shift = cb.query_word();
+        let a = cb.query_word();
+        let result = cb.query_word();
+        let quotient = cb.query_word();
+        let divisor = cb.query_word();
+        let remainder = cb.query_word();
+        let dividend = cb.query_word();
+
+        cb.stack_pop(shift.expr());
+        cb.stack_pop(a.expr());
+        cb.stack_push(result.expr());
+
+        let a_is_neg = LtGadget::construct(cb, 127.expr(), a.cells[31].expr());
+        let shift_hi_is_zero = IsZeroGadget::construct(cb, sum::expr(&shift.cells[1..]));
+        let mul_add_words =
+            MulAddWordsGadget::construct(cb, [&quotient, &divisor, &remainder, &dividend]);
+        let divisor_is_zero = IsZeroGadget::construct(cb, sum::expr(&divisor.cells));
+        let remainder_lt_divisor = LtWordGadget::construct(cb, &remainder, &divisor);
+
+        // Byte-wise, dividend == !a and result == !quotient for negative a,
+        // dividend == a and result == quotient otherwise.
+        for idx in 0..32 {
+            cb.require_equal(
+                "dividend == is_neg ? !a : a",
+                dividend.cells[idx].expr(),
+                select::expr(
+                    a_is_neg.expr(),
+                    255.expr() - a.cells[idx].expr(),
+                    a.cells[idx].expr(),
+                ),
+            );
+            cb.require_equal(
+                "result == is_neg ? !quotient : quotient",
+                result.cells[idx].expr(),
+                select::expr(
+                    a_is_neg.expr(),
+                    255.expr() - quotient.cells[idx].expr(),
+                    quotient.cells[idx].expr(),
+                ),
+            );
+        }
+
+        cb.require_equal(
+            "divisor == 0 if and only if shift >= 256",
+            divisor_is_zero.expr(),
+            1.expr() - shift_hi_is_zero.expr(),
+        );
+
+        cb.require_zero("overflow == 0", mul_add_words.overflow());
+
+        cb.condition(divisor_is_zero.expr(), |cb| {
+            cb.require_zero(
+                "quotient == 0 when divisor == 0",
+                sum::expr(&quotient.cells),
+            );
+        });
+
+        // Constrain remainder < divisor and divisor == 2^shift when shift <
+        // 256, where divisor_lo == 2^shift when shift < 128, and divisor_hi ==
+        // 2^(shift - 128) otherwise.
+        let divisor_lo = from_bytes::expr(&divisor.cells[..16]);
+        let divisor_hi = from_bytes::expr(&divisor.cells[16..]);
+        cb.condition(1.expr() - divisor_is_zero.expr(), |cb| {
+            cb.require_equal(
+                "remainder < divisor when divisor != 0",
+                remainder_lt_divisor.expr(),
+                1.expr(),
+            );
+            cb.add_lookup(
+                "Pow2 lookup of shift, divisor_lo and divisor_hi",
+                Lookup::Fixed {
+                    tag: FixedTableTag::Pow2.expr(),
+                    values: [shift.cells[0].expr(), divisor_lo, divisor_hi],
+                },
+            );
+        });
+
+        let step_state_transition = StepStateTransition {
+            rw_counter: Delta(3.expr()),
+            program_counter: Delta(1.expr()),
+            stack_pointer: Delta(1.expr()),
+            gas_left: Delta(-OpcodeId::SAR.constant_gas_cost().expr()),
+            ..Default::default()
+        };
+        let same_context = SameContextGadget::construct(cb, opcode, step_state_transition);
+
+        Self {
+            same_context,
+            shift,
+            a,
+            result,
+            quotient,
+            divisor,
+            remainder,
+            dividend,
+            a_is_neg,
+            shift_hi_is_zero,
+            mul_add_words,
+            divisor_is_zero,
+            remainder_lt_divisor,
+        }
+    }
+
+    fn assign_exec_step(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        block: &Block<F>,
+        _: &Transaction,
+        _: &Call,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        self.same_context.assign_exec_step(region, offset, step)?;
+
+        let [shift, a, result] = [0, 1, 2]
+            .map(|idx| step.rw_indices[idx])
+            .map(|idx| block.rws[idx].stack_value());
+
+        let a_is_neg = a.bit(255);
+        let dividend = if a_is_neg { !a } else { a };
+        let (divisor, quotient) = if shift < U256::from(256) {
+            let divisor = U256::from(1) << shift.low_u32();
+            (divisor, dividend / divisor)
+        } else {
+            (U256::zero(), U256::zero())
+        };
+        let remainder = dividend - quotient * divisor;
+
+        self.shift
+            .assign(region, offset, Some(shift.to_le_bytes()))?;
+        self.a.assign(region, offset, Some(a.to_le_bytes()))?;
+        self.result
+            .assign(region, offset, Some(result.to_le_bytes()))?;
+        self.quotient
+            .assign(region, offset, Some(quotient.to_le_bytes()))?;
+        self.divisor
+            .assign(region, offset, Some(divisor.to_le_bytes()))?;
+        self.remainder
+            .assign(region, offset, Some(remainder.to_le_bytes()))?;
+        self.dividend
+            .assign(region, offset, Some(dividend.to_le_bytes()))?;
+
+        self.a_is_neg.assign(
+            region,
+            offset,
+            127.into(),
+            u64::from(a.to_le_bytes()[31]).into(),
+        )?;
+        let shift_hi_sum = (1..32).fold(0, |acc, idx| acc + shift.byte(idx) as u64);
+        self.shift_hi_is_zero
+            .assign(region, offset, F::from(shift_hi_sum))?;
+        self.mul_add_words
+            .assign(region, offset, [quotient, divisor, remainder, dividend])?;
+        let divisor_sum = (0..32).fold(0, |acc, idx| acc + divisor.byte(idx) as u64);
+        self.divisor_is_zero
+            .assign(region, offset, F::from(divisor_sum))?;
+        self.remainder_lt_divisor
+            .assign(region, offset, remainder, divisor)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{evm_circuit::test::rand_word, test_util::run_test_circuits};
+    use eth_types::{bytecode, Word};
+    use mock::TestContext;
+
+    fn test_ok(a: Word, shift: Word) {
+        let bytecode = bytecode! {
+            PUSH32(a)
+            PUSH32(shift)
+            #[start]
+            SAR
+            STOP
+        };
+
+        assert_eq!(
+            run_test_circuits(
+                TestContext::<2, 1>::simple_ctx_with_bytecode(bytecode).unwrap(),
+                None
+            ),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn sar_gadget_positive() {
+        test_ok(Word::from(0xABCD), Word::from(8));
+        test_ok(Word::from(0x1234), Word::from(7));
+        test_ok(Word::from(0x8765), Word::from(17));
+        test_ok(Word::from(0x4321), Word::from(0));
+        test_ok(Word::MAX >> 1, Word::from(128));
+        test_ok(Word::MAX >> 1, Word::from(255));
+    }
+
+    #[test]
+    fn sar_gadget_negative() {
+        let min_word = Word::one() << 255;
+        test_ok(Word::MAX, Word::from(0));
+        test_ok(Word::MAX, Word::from(1));
+        test_ok(Word::MAX, Word::from(255));
+        test_ok(min_word, Word::from(1));
+        test_ok(min_word, Word::from(127));
+        test_ok(min_word, Word::from(128));
+        test_ok(min_word, Word::from(255));
+        test_ok(Word::MAX - Word::from(0xABCD), Word::from(8));
+    }
+
+    #[test]
+    fn sar_gadget_overflow_shift() {
+        test_ok(Word::from(0xFFFF), Word::from(256));
+        test_ok(Word::from(0x12345), Word::from(256 + 8 + 1));
+        test_ok(Word::MAX, Word::from(256));
+        test_ok(Word::one() << 255, Word::MAX);
+    }
+
+    #[test]
+    fn sar_gadget_rand() {
+        let a = rand_word();
+        test_ok(a, Word::from(a.byte(0)));
+        test_ok(rand_word(), rand_word());
+    }
+}
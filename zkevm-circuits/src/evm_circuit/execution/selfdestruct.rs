@@ -0,0 +1,448 @@
+use crate::{
+    evm_circuit::{
+        execution::ExecutionGadget,
+        param::N_BYTES_ACCOUNT_ADDRESS,
+        step::ExecutionState,
+        util::{
+            common_gadget::{RestoreContextGadget, UpdateBalanceGadget},
+            constraint_builder::{
+                ConstraintBuilder, ReversionInfo, StepStateTransition,
+                Transition::{Delta, Same},
+            },
+            from_bytes,
+            math_gadget::{BatchedIsZeroGadget, IsEqualGadget, IsZeroGadget},
+            not, select, sum, CachedRegion, Cell, Word,
+        },
+        witness::{Block, Call, ExecStep, Transaction},
+    },
+    table::{AccountFieldTag, CallContextFieldTag},
+    util::Expr,
+};
+use bus_mapping::evm::OpcodeId;
+use eth_types::{evm_types::GasCost, Field, ToAddress, ToLittleEndian, ToScalar};
+use halo2_proofs::{circuit::Value, plonk::Error};
+use keccak256::EMPTY_HASH_LE;
+
+/// Gadget for SELFDESTRUCT, which moves the whole balance of the current
+/// account to the beneficiary, marks the current account as destructed and
+/// halts the current call successfully.
+#[derive(Clone, Debug)]
+pub(crate) struct SelfdestructGadget<F> {
+    opcode: Cell<F>,
+    beneficiary: Word<F>,
+    tx_id: Cell<F>,
+    reversion_info: ReversionInfo<F>,
+    current_address: Cell<F>,
+    is_static: Cell<F>,
+    is_warm_prev: Cell<F>,
+    beneficiary_nonce: Cell<F>,
+    beneficiary_code_hash: Cell<F>,
+    value: Word<F>,
+    receiver: UpdateBalanceGadget<F, 2, true>,
+    is_beneficiary_self: IsEqualGadget<F>,
+    is_destructed_prev: Cell<F>,
+    value_is_zero: IsZeroGadget<F>,
+    is_empty_nonce_and_balance: BatchedIsZeroGadget<F, 2>,
+    is_empty_code_hash: IsEqualGadget<F>,
+    restore_context: RestoreContextGadget<F>,
+}
+
+impl<F: Field> ExecutionGadget<F> for SelfdestructGadget<F> {
+    const NAME: &'static str = "SELFDESTRUCT";
+
+    const EXECUTION_STATE: ExecutionState = ExecutionState::SELFDESTRUCT;
+
+    fn configure(cb: &mut ConstraintBuilder<F>) -> Self {
+        let opcode = cb.query_cell();
+        cb.opcode_lookup(opcode.expr(), 1.expr());
+
+        // We do the responsible opcode check explicitly here because we're not using
+        // the `SameContextGadget` for `SELFDESTRUCT`.
+        cb.require_equal(
+            "Opcode should be SELFDESTRUCT",
+            opcode.expr(),
+            OpcodeId::SELFDESTRUCT.expr(),
+        );
+
+        let beneficiary = cb.query_word();
+        cb.stack_pop(beneficiary.expr());
+        let beneficiary_address = from_bytes::expr(&beneficiary.cells[..N_BYTES_ACCOUNT_ADDRESS]);
+
+        let tx_id = cb.call_context(None, CallContextFieldTag::TxId);
+        let mut reversion_info = cb.reversion_info_read(None);
+        let [current_address, is_static] = [
+            CallContextFieldTag::CalleeAddress,
+            CallContextFieldTag::IsStatic,
+        ]
+        .map(|field_tag| cb.call_context(None, field_tag));
+        cb.require_zero(
+            "SELFDESTRUCT must not be in static call stack",
+            is_static.expr(),
+        );
+
+        // Add beneficiary to access list
+        let is_warm_prev = cb.query_bool();
+        cb.account_access_list_write(
+            tx_id.expr(),
+            beneficiary_address.clone(),
+            1.expr(),
+            is_warm_prev.expr(),
+            Some(&mut reversion_info),
+        );
+
+        let [beneficiary_nonce, beneficiary_code_hash] =
+            [AccountFieldTag::Nonce, AccountFieldTag::CodeHash].map(|field_tag| {
+                let value = cb.query_cell();
+                cb.account_read(beneficiary_address.clone(), field_tag, value.expr());
+                value
+            });
+
+        // The whole balance is first added to the beneficiary, then the balance
+        // of the current account is set to 0, so the balance is burnt if the
+        // beneficiary is the current account itself.
+        let value = cb.query_word();
+        let receiver = UpdateBalanceGadget::construct(
+            cb,
+            beneficiary_address.clone(),
+            vec![value.clone()],
+            Some(&mut reversion_info),
+        );
+        let is_beneficiary_self =
+            IsEqualGadget::construct(cb, beneficiary_address, current_address.expr());
+        cb.condition(is_beneficiary_self.expr(), |cb| {
+            cb.require_equal(
+                "value == beneficiary balance_prev when beneficiary is self",
+                value.expr(),
+                receiver.balance_prev().expr(),
+            );
+        });
+        cb.account_write(
+            current_address.expr(),
+            AccountFieldTag::Balance,
+            0.expr(),
+            select::expr(
+                is_beneficiary_self.expr(),
+                receiver.balance().expr(),
+                value.expr(),
+            ),
+            Some(&mut reversion_info),
+        );
+
+        let is_destructed_prev = cb.query_bool();
+        cb.account_destructed_write(
+            current_address.expr(),
+            1.expr(),
+            is_destructed_prev.expr(),
+            Some(&mut reversion_info),
+        );
+
+        // Verify gas cost
+        let value_is_zero = IsZeroGadget::construct(cb, sum::expr(&value.cells));
+        let is_empty_nonce_and_balance = BatchedIsZeroGadget::construct(
+            cb,
+            [beneficiary_nonce.expr(), receiver.balance_prev().expr()],
+        );
+        let is_empty_code_hash = IsEqualGadget::construct(
+            cb,
+            beneficiary_code_hash.expr(),
+            Word::random_linear_combine_expr(
+                (*EMPTY_HASH_LE).map(|byte| byte.expr()),
+                cb.power_of_randomness(),
+            ),
+        );
+        let gas_cost = GasCost::SELFDESTRUCT.expr()
+            + select::expr(
+                is_warm_prev.expr(),
+                0.expr(),
+                GasCost::COLD_ACCOUNT_ACCESS.expr(),
+            )
+            + not::expr(value_is_zero.expr())
+                * is_empty_nonce_and_balance.expr()
+                * is_empty_code_hash.expr()
+                * GasCost::NEW_ACCOUNT.expr();
+
+        // Call ends with SELFDESTRUCT must be successful
+        cb.call_context_lookup(false.expr(), None, CallContextFieldTag::IsSuccess, 1.expr());
+
+        let is_to_end_tx = cb.next.execution_state_selector([ExecutionState::EndTx]);
+        cb.require_equal(
+            "Go to EndTx only when is_root",
+            cb.curr.state.is_root.expr(),
+            is_to_end_tx,
+        );
+
+        // When it's a root call
+        cb.condition(cb.curr.state.is_root.expr(), |cb| {
+            // When a transaction ends with SELFDESTRUCT, this call must be persistent
+            cb.require_equal(
+                "is_persistent == 1 for root call",
+                reversion_info.is_persistent(),
+                1.expr(),
+            );
+
+            // Do step state transition
+            cb.require_step_state_transition(StepStateTransition {
+                call_id: Same,
+                rw_counter: Delta(cb.rw_counter_offset()),
+                gas_left: Delta(-gas_cost.clone()),
+                ..StepStateTransition::any()
+            });
+        });
+
+        // When it's an internal call
+        let restore_context = cb.condition(not::expr(cb.curr.state.is_root.expr()), |cb| {
+            RestoreContextGadget::construct(
+                cb,
+                true.expr(),
+                0.expr(),
+                4.expr(),
+                0.expr(),
+                0.expr(),
+                gas_cost,
+            )
+        });
+
+        Self {
+            opcode,
+            beneficiary,
+            tx_id,
+            reversion_info,
+            current_address,
+            is_static,
+            is_warm_prev,
+            beneficiary_nonce,
+            beneficiary_code_hash,
+            value,
+            receiver,
+            is_beneficiary_self,
+            is_destructed_prev,
+            value_is_zero,
+            is_empty_nonce_and_balance,
+            is_empty_code_hash,
+            restore_context,
+        }
+    }
+
+    fn assign_exec_step(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        block: &Block<F>,
+        _: &Transaction,
+        call: &Call,
+        step: &ExecStep,
+    ) -> Result<(), Error> {
+        let opcode = step.opcode.unwrap();
+        self.opcode
+            .assign(region, offset, Value::known(F::from(opcode.as_u64())))?;
+
+        let beneficiary = block.rws[step.rw_indices[0]].stack_value();
+        let [tx_id, current_address, is_static] =
+            [1, 4, 5].map(|idx| block.rws[step.rw_indices[idx]].call_context_value());
+        let (_, is_warm_prev) = block.rws[step.rw_indices[6]].tx_access_list_value_pair();
+        let [(beneficiary_nonce, _), (beneficiary_code_hash, _), (beneficiary_balance, beneficiary_balance_prev)] =
+            [7, 8, 9].map(|idx| block.rws[step.rw_indices[idx]].account_value_pair());
+        let (_, is_destructed_prev) =
+            block.rws[step.rw_indices[11]].account_destructed_value_pair();
+        let value = beneficiary_balance - beneficiary_balance_prev;
+
+        self.beneficiary
+            .assign(region, offset, Some(beneficiary.to_le_bytes()))?;
+        self.tx_id
+            .assign(region, offset, Value::known(F::from(tx_id.low_u64())))?;
+        self.reversion_info.assign(
+            region,
+            offset,
+            call.rw_counter_end_of_reversion,
+            call.is_persistent,
+        )?;
+        let current_address = current_address
+            .to_scalar()
+            .expect("unexpected Address -> Scalar conversion failure");
+        self.current_address
+            .assign(region, offset, Value::known(current_address))?;
+        self.is_static
+            .assign(region, offset, Value::known(F::from(is_static.low_u64())))?;
+        self.is_warm_prev
+            .assign(region, offset, Value::known(F::from(is_warm_prev as u64)))?;
+
+        self.beneficiary_nonce.assign(
+            region,
+            offset,
+            Value::known(
+                beneficiary_nonce
+                    .to_scalar()
+                    .expect("unexpected U256 -> Scalar conversion failure"),
+            ),
+        )?;
+        self.beneficiary_code_hash.assign(
+            region,
+            offset,
+            Value::known(Word::random_linear_combine(
+                beneficiary_code_hash.to_le_bytes(),
+                block.randomness,
+            )),
+        )?;
+
+        self.value
+            .assign(region, offset, Some(value.to_le_bytes()))?;
+        self.receiver.assign(
+            region,
+            offset,
+            beneficiary_balance_prev,
+            vec![value],
+            beneficiary_balance,
+        )?;
+        self.is_beneficiary_self.assign(
+            region,
+            offset,
+            beneficiary
+                .to_address()
+                .to_scalar()
+                .expect("unexpected Address -> Scalar conversion failure"),
+            current_address,
+        )?;
+        self.is_destructed_prev.assign(
+            region,
+            offset,
+            Value::known(F::from(is_destructed_prev as u64)),
+        )?;
+
+        self.value_is_zero
+            .assign(region, offset, sum::value(&value.to_le_bytes()))?;
+        self.is_empty_nonce_and_balance.assign(
+            region,
+            offset,
+            [
+                F::from(beneficiary_nonce.low_u64()),
+                Word::random_linear_combine(
+                    beneficiary_balance_prev.to_le_bytes(),
+                    block.randomness,
+                ),
+            ],
+        )?;
+        self.is_empty_code_hash.assign(
+            region,
+            offset,
+            Word::random_linear_combine(beneficiary_code_hash.to_le_bytes(), block.randomness),
+            Word::random_linear_combine(*EMPTY_HASH_LE, block.randomness),
+        )?;
+
+        if !call.is_root {
+            self.restore_context
+                .assign(region, offset, block, call, step, 13)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_util::run_test_circuits;
+    use eth_types::{
+        address, bytecode, bytecode::Bytecode, geth_types::Account, Address, ToWord, Word,
+    };
+    use mock::{eth, TestContext};
+
+    fn selfdestruct_code(beneficiary: Address) -> Bytecode {
+        bytecode! {
+            PUSH20(beneficiary.to_word())
+            SELFDESTRUCT
+        }
+    }
+
+    fn test_root_ok(beneficiary: Address) {
+        let contract = Account {
+            address: address!("0x00000000000000000000000000000000000cafe1"),
+            balance: eth(10),
+            code: selfdestruct_code(beneficiary).into(),
+            ..Default::default()
+        };
+
+        let ctx = TestContext::<2, 1>::new(
+            None,
+            |accs| {
+                accs[0].account(&contract);
+                accs[1]
+                    .address(address!("0x000000000000000000000000000000000000cafe"))
+                    .balance(eth(10));
+            },
+            |mut txs, accs| {
+                txs[0]
+                    .from(accs[1].address)
+                    .to(accs[0].address)
+                    .value(eth(1));
+            },
+            |block, _| block,
+        )
+        .unwrap();
+
+        assert_eq!(run_test_circuits(ctx, None), Ok(()));
+    }
+
+    fn test_internal_ok(beneficiary: Address) {
+        let callee = Account {
+            address: address!("0x00000000000000000000000000000000000cafe2"),
+            balance: eth(10),
+            nonce: Word::one(),
+            code: selfdestruct_code(beneficiary).into(),
+            ..Default::default()
+        };
+        let caller = Account {
+            address: address!("0x00000000000000000000000000000000000cafe1"),
+            balance: eth(10),
+            code: bytecode! {
+                PUSH1(0) // retLength
+                PUSH1(0) // retOffset
+                PUSH1(0) // argsLength
+                PUSH1(0) // argsOffset
+                PUSH1(0) // value
+                PUSH20(callee.address.to_word())
+                PUSH2(50000) // gas
+                CALL
+                STOP
+            }
+            .into(),
+            ..Default::default()
+        };
+
+        let ctx = TestContext::<3, 1>::new(
+            None,
+            |accs| {
+                accs[0].account(&caller);
+                accs[1].account(&callee);
+                accs[2]
+                    .address(address!("0x000000000000000000000000000000000000cafe"))
+                    .balance(eth(10));
+            },
+            |mut txs, accs| {
+                txs[0].from(accs[2].address).to(accs[0].address);
+            },
+            |block, _| block,
+        )
+        .unwrap();
+
+        assert_eq!(run_test_circuits(ctx, None), Ok(()));
+    }
+
+    #[test]
+    fn selfdestruct_root() {
+        // Empty beneficiary
+        test_root_ok(address!("0x0000000000000000000000000000000000000bee"));
+        // Existing beneficiary
+        test_root_ok(address!("0x000000000000000000000000000000000000cafe"));
+        // Beneficiary is the contract itself
+        test_root_ok(address!("0x00000000000000000000000000000000000cafe1"));
+    }
+
+    #[test]
+    fn selfdestruct_internal() {
+        // Empty beneficiary
+        test_internal_ok(address!("0x0000000000000000000000000000000000000bee"));
+        // Beneficiary is the caller
+        test_internal_ok(address!("0x00000000000000000000000000000000000cafe1"));
+        // Beneficiary is the contract itself
+        test_internal_ok(address!("0x00000000000000000000000000000000000cafe2"));
+    }
+}
@@ -518,7 +518,7 @@ impl<F: Field> SstoreTxRefundGadget<F> {
     }
 }
 
-fn calc_expected_gas_cost(
+pub(crate) fn calc_expected_gas_cost(
     value: eth_types::Word,
     value_prev: eth_types::Word,
     original_value: eth_types::Word,
@@ -4,7 +4,7 @@ use super::table::Table;
 pub(crate) const STEP_WIDTH: usize = 128;
 /// Step height
 pub const MAX_STEP_HEIGHT: usize = 21;
-pub(crate) const N_CELLS_STEP_STATE: usize = 11;
+pub(crate) const N_CELLS_STEP_STATE: usize = 12;
 
 /// Lookups done per row.
 pub(crate) const LOOKUP_CONFIG: &[(Table, usize)] = &[
@@ -16,6 +16,7 @@ pub(crate) const LOOKUP_CONFIG: &[(Table, usize)] = &[
     (Table::Byte, 24),
     (Table::Copy, 1),
     (Table::Keccak, 1),
+    (Table::Exp, 1),
 ];
 
 /// Maximum number of bytes that an integer can fit in field without wrapping
@@ -36,6 +37,11 @@ pub(crate) const N_BYTES_ACCOUNT_ADDRESS: usize = 20;
 pub(crate) const N_BYTES_MEMORY_ADDRESS: usize = 5;
 pub(crate) const N_BYTES_MEMORY_WORD_SIZE: usize = 4;
 
+// The maximum memory address which could be expanded to, which is the same as
+// go-ethereum.  Any greater address results in a gas uint overflow error, which
+// is reported as an out-of-gas error.
+pub(crate) const MAX_EXPANDED_MEMORY_ADDRESS: u64 = 0x1FFFFFFFE0;
+
 pub(crate) const STACK_CAPACITY: usize = 1024;
 
 // Number of bytes that will be used of prorgam counter. Although the maximum
@@ -7,7 +7,7 @@ use crate::{
     },
     util::Expr,
 };
-use bus_mapping::evm::OpcodeId;
+use bus_mapping::{evm::OpcodeId, precompile::PrecompileCalls};
 use eth_types::ToLittleEndian;
 use halo2_proofs::{
     arithmetic::FieldExt,
@@ -24,6 +24,7 @@ pub enum ExecutionState {
     // Internal state
     BeginTx,
     EndTx,
+    EndInnerBlock,
     EndBlock,
     // Opcode successful cases
     STOP,
@@ -80,12 +81,9 @@ pub enum ExecutionState {
     SWAP, // SWAP1, SWAP2, ..., SWAP16
     LOG,  // LOG0, LOG1, ..., LOG4
     CREATE,
-    CALL,
-    CALLCODE,
+    CALL_OP, // CALL, CALLCODE, DELEGATECALL, STATICCALL
     RETURN,
-    DELEGATECALL,
     CREATE2,
-    STATICCALL,
     REVERT,
     SELFDESTRUCT,
     // Error cases
@@ -118,6 +116,16 @@ pub enum ExecutionState {
     ErrorOutOfGasCREATE2,
     ErrorOutOfGasSTATICCALL,
     ErrorOutOfGasSELFDESTRUCT,
+    // Precompiles
+    PrecompileEcRecover,
+    PrecompileSha256,
+    PrecompileRipemd160,
+    PrecompileIdentity,
+    PrecompileBigModExp,
+    PrecompileBn256Add,
+    PrecompileBn256ScalarMul,
+    PrecompileBn256Pairing,
+    PrecompileBlake2f,
 }
 
 impl Default for ExecutionState {
@@ -126,6 +134,22 @@ impl Default for ExecutionState {
     }
 }
 
+impl From<PrecompileCalls> for ExecutionState {
+    fn from(value: PrecompileCalls) -> Self {
+        match value {
+            PrecompileCalls::ECRecover => Self::PrecompileEcRecover,
+            PrecompileCalls::Sha256 => Self::PrecompileSha256,
+            PrecompileCalls::Ripemd160 => Self::PrecompileRipemd160,
+            PrecompileCalls::Identity => Self::PrecompileIdentity,
+            PrecompileCalls::Modexp => Self::PrecompileBigModExp,
+            PrecompileCalls::Bn256Add => Self::PrecompileBn256Add,
+            PrecompileCalls::Bn256ScalarMul => Self::PrecompileBn256ScalarMul,
+            PrecompileCalls::Bn256Pairing => Self::PrecompileBn256Pairing,
+            PrecompileCalls::Blake2F => Self::PrecompileBlake2f,
+        }
+    }
+}
+
 impl ExecutionState {
     pub(crate) const fn as_u64(&self) -> u64 {
         *self as u64
@@ -146,8 +170,6 @@ impl ExecutionState {
                 | Self::ErrorStackOverflow
                 | Self::ErrorStackUnderflow
                 | Self::ErrorWriteProtection
-                | Self::ErrorDepth
-                | Self::ErrorInsufficientBalance
                 | Self::ErrorContractAddressCollision
                 | Self::ErrorInvalidCreationCode
                 | Self::ErrorMaxCodeSizeExceeded
@@ -309,12 +331,14 @@ impl ExecutionState {
                 OpcodeId::LOG4,
             ],
             Self::CREATE => vec![OpcodeId::CREATE],
-            Self::CALL => vec![OpcodeId::CALL],
-            Self::CALLCODE => vec![OpcodeId::CALLCODE],
+            Self::CALL_OP => vec![
+                OpcodeId::CALL,
+                OpcodeId::CALLCODE,
+                OpcodeId::DELEGATECALL,
+                OpcodeId::STATICCALL,
+            ],
             Self::RETURN => vec![OpcodeId::RETURN],
-            Self::DELEGATECALL => vec![OpcodeId::DELEGATECALL],
             Self::CREATE2 => vec![OpcodeId::CREATE2],
-            Self::STATICCALL => vec![OpcodeId::STATICCALL],
             Self::REVERT => vec![OpcodeId::REVERT],
             Self::SELFDESTRUCT => vec![OpcodeId::SELFDESTRUCT],
             _ => vec![],
@@ -453,6 +477,8 @@ pub(crate) struct StepState<F> {
     pub(crate) reversible_write_counter: Cell<F>,
     /// The counter for log index
     pub(crate) log_id: Cell<F>,
+    /// The number of the block whose transactions are executed
+    pub(crate) block_number: Cell<F>,
 }
 
 #[derive(Clone, Debug)]
@@ -485,6 +511,7 @@ impl<F: FieldExt> Step<F> {
                 memory_word_size: cell_manager.query_cell(CellType::Storage),
                 reversible_write_counter: cell_manager.query_cell(CellType::Storage),
                 log_id: cell_manager.query_cell(CellType::Storage),
+                block_number: cell_manager.query_cell(CellType::Storage),
             }
         };
         Self {
@@ -564,6 +591,9 @@ impl<F: FieldExt> Step<F> {
         self.state
             .log_id
             .assign(region, offset, Value::known(F::from(step.log_id as u64)))?;
+        self.state
+            .block_number
+            .assign(region, offset, Value::known(F::from(step.block_number)))?;
         Ok(())
     }
 }
@@ -1,5 +1,6 @@
 use crate::evm_circuit::step::ExecutionState;
 use crate::impl_expr;
+use bus_mapping::evm::OpcodeId;
 use eth_types::Field;
 use gadgets::util::Expr;
 use halo2_proofs::plonk::Expression;
@@ -22,6 +23,8 @@ pub enum FixedTableTag {
     BitwiseXor,
     ResponsibleOpcode,
     Pow2,
+    StackPointerRange,
+    InvalidOpcode,
 }
 impl_expr!(FixedTableTag);
 
@@ -91,6 +94,26 @@ impl FixedTableTag {
                 };
                 [tag, F::from(value), pow_lo, pow_hi]
             })),
+            Self::StackPointerRange => Box::new(
+                (0..=u8::MAX)
+                    .filter_map(|byte| OpcodeId::try_from(byte).ok())
+                    .map(move |opcode| {
+                        let (min_stack_pointer, max_stack_pointer) = opcode.valid_stack_ptr_range();
+                        [
+                            tag,
+                            F::from(opcode.as_u64()),
+                            F::from(min_stack_pointer as u64),
+                            F::from(max_stack_pointer as u64),
+                        ]
+                    }),
+            ),
+            Self::InvalidOpcode => Box::new(
+                (0..=u8::MAX)
+                    .filter(|byte| {
+                        matches!(OpcodeId::try_from(*byte), Err(_) | Ok(OpcodeId::INVALID(_)))
+                    })
+                    .map(move |byte| [tag, F::from(byte as u64), F::zero(), F::zero()]),
+            ),
         }
     }
 }
@@ -105,6 +128,7 @@ pub(crate) enum Table {
     Byte,
     Copy,
     Keccak,
+    Exp,
 }
 
 #[derive(Clone, Debug)]
@@ -196,12 +220,12 @@ pub(crate) enum Lookup<F> {
         /// Value corresponding to the tag.
         value: Expression<F>,
     },
-    /// Lookup to block table, which contains constants of this block.
+    /// Lookup to block table, which contains constants of the blocks.
     Block {
         /// Tag to specify which field to read.
         field_tag: Expression<F>,
-        /// Stores the block number only when field_tag is BlockHash, otherwise
-        /// should be set to 0.
+        /// Stores the number of the hashed block when field_tag is BlockHash,
+        /// otherwise the number of the block the field belongs to.
         number: Expression<F>,
         /// Value of the field.
         value: Expression<F>,
@@ -251,6 +275,21 @@ pub(crate) enum Lookup<F> {
         /// the final output keccak256 hash of the input.
         output_rlc: Expression<F>,
     },
+    /// Lookup to exponentiation table.
+    ExpTable {
+        /// Lower 128 bits of the base.
+        base_lo: Expression<F>,
+        /// Higher 128 bits of the base.
+        base_hi: Expression<F>,
+        /// Lower 128 bits of the exponent.
+        exponent_lo: Expression<F>,
+        /// Higher 128 bits of the exponent.
+        exponent_hi: Expression<F>,
+        /// Lower 128 bits of the exponentiation result.
+        exponentiation_lo: Expression<F>,
+        /// Higher 128 bits of the exponentiation result.
+        exponentiation_hi: Expression<F>,
+    },
     /// Conditional lookup enabled by the first element.
     Conditional(Expression<F>, Box<Lookup<F>>),
 }
@@ -270,6 +309,7 @@ impl<F: Field> Lookup<F> {
             Self::Byte { .. } => Table::Byte,
             Self::CopyTable { .. } => Table::Copy,
             Self::KeccakTable { .. } => Table::Keccak,
+            Self::ExpTable { .. } => Table::Exp,
             Self::Conditional(_, lookup) => lookup.table(),
         }
     }
@@ -360,11 +400,27 @@ impl<F: Field> Lookup<F> {
                 input_len,
                 output_rlc,
             } => vec![
-                1.expr(), // is_enabled
+                1.expr(), // is_final
                 input_rlc.clone(),
                 input_len.clone(),
                 output_rlc.clone(),
             ],
+            Self::ExpTable {
+                base_lo,
+                base_hi,
+                exponent_lo,
+                exponent_hi,
+                exponentiation_lo,
+                exponentiation_hi,
+            } => vec![
+                1.expr(), // is_step
+                base_lo.clone(),
+                base_hi.clone(),
+                exponent_lo.clone(),
+                exponent_hi.clone(),
+                exponentiation_lo.clone(),
+                exponentiation_hi.clone(),
+            ],
             Self::Conditional(condition, lookup) => lookup
                 .input_exprs()
                 .into_iter()
@@ -2,6 +2,7 @@ use super::CachedRegion;
 use crate::{
     evm_circuit::{
         param::N_BYTES_GAS,
+        step::ExecutionState,
         table::{FixedTableTag, Lookup},
         util::{
             constraint_builder::{
@@ -102,6 +103,8 @@ impl<F: Field> RestoreContextGadget<F> {
         is_success: Expression<F>,
         // Expression for the number of rw lookups that occur after this gadget is constructed.
         subsequent_rw_lookups: Expression<F>,
+        // Expression for the number of reversible writes done by the current step.
+        reversible_write_counter_increase: Expression<F>,
         return_data_offset: Expression<F>,
         return_data_length: Expression<F>,
         memory_expansion_cost: Expression<F>,
@@ -152,7 +155,9 @@ impl<F: Field> RestoreContextGadget<F> {
         // failure, we don't need to accumulate reversible_write_counter because
         // what happened in the sub-call has been reverted.
         let reversible_write_counter = caller_reversible_write_counter.expr()
-            + is_success.clone() * cb.curr.state.reversible_write_counter.expr();
+            + is_success.clone()
+                * (cb.curr.state.reversible_write_counter.expr()
+                    + reversible_write_counter_increase);
 
         let rw_counter_offset = cb.rw_counter_offset()
             + subsequent_rw_lookups
@@ -241,6 +246,102 @@ impl<F: Field> RestoreContextGadget<F> {
     }
 }
 
+/// Construction of the common part of the execution states that halt in
+/// exception. The current call is failed, so its writes are reverted, then it
+/// goes to `EndTx` for a root call, or restores the caller's state for an
+/// internal call.
+#[derive(Clone, Debug)]
+pub(crate) struct CommonErrorGadget<F> {
+    rw_counter_end_of_reversion: Cell<F>,
+    restore_context: RestoreContextGadget<F>,
+}
+
+impl<F: Field> CommonErrorGadget<F> {
+    pub(crate) fn construct(cb: &mut ConstraintBuilder<F>, opcode: Expression<F>) -> Self {
+        cb.opcode_lookup(opcode, 1.expr());
+
+        let rw_counter_end_of_reversion = cb.query_cell();
+
+        // Current call must fail
+        cb.call_context_lookup(false.expr(), None, CallContextFieldTag::IsSuccess, 0.expr());
+        cb.call_context_lookup(
+            false.expr(),
+            None,
+            CallContextFieldTag::RwCounterEndOfReversion,
+            rw_counter_end_of_reversion.expr(),
+        );
+
+        // Go to EndTx only when is_root
+        let is_to_end_tx = cb.next.execution_state_selector([ExecutionState::EndTx]);
+        cb.require_equal(
+            "Go to EndTx only when is_root",
+            cb.curr.state.is_root.expr(),
+            is_to_end_tx,
+        );
+
+        // When it's a root call
+        cb.condition(cb.curr.state.is_root.expr(), |cb| {
+            // Do step state transition
+            cb.require_step_state_transition(StepStateTransition {
+                call_id: Same,
+                rw_counter: Delta(
+                    cb.rw_counter_offset() + cb.curr.state.reversible_write_counter.expr(),
+                ),
+                // All the gas left is consumed.
+                gas_left: To(0.expr()),
+                ..StepStateTransition::any()
+            });
+        });
+
+        // When it's an internal call, need to restore caller's state as finishing this
+        // call. Restore caller state to next StepState
+        let restore_context = cb.condition(not::expr(cb.curr.state.is_root.expr()), |cb| {
+            RestoreContextGadget::construct(
+                cb,
+                false.expr(),
+                0.expr(),
+                0.expr(),
+                0.expr(),
+                0.expr(),
+                0.expr(),
+            )
+        });
+
+        // Every write of the failed call is reverted right after this step.
+        cb.require_equal(
+            "rw_counter_end_of_reversion == rw_counter_end_of_step + reversible_write_counter",
+            rw_counter_end_of_reversion.expr(),
+            cb.curr.state.rw_counter.expr() + cb.rw_counter_offset() - 1.expr()
+                + cb.curr.state.reversible_write_counter.expr(),
+        );
+
+        Self {
+            rw_counter_end_of_reversion,
+            restore_context,
+        }
+    }
+
+    /// Assign the witness, where `rw_offset` is the number of rw lookups done
+    /// by the execution state before this gadget is constructed.
+    pub(crate) fn assign(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        block: &Block<F>,
+        call: &Call,
+        step: &ExecStep,
+        rw_offset: usize,
+    ) -> Result<(), Error> {
+        self.rw_counter_end_of_reversion.assign(
+            region,
+            offset,
+            Value::known(F::from(call.rw_counter_end_of_reversion as u64)),
+        )?;
+        self.restore_context
+            .assign(region, offset, block, call, step, rw_offset + 2)
+    }
+}
+
 #[derive(Clone, Debug)]
 pub(crate) struct UpdateBalanceGadget<F, const N_ADDENDS: usize, const INCREASE: bool> {
     add_words: AddWordsGadget<F, N_ADDENDS, true>,
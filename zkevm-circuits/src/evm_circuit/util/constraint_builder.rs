@@ -113,9 +113,20 @@ impl<F: Field> ReversionInfo<F> {
     /// Returns `rw_counter_end_of_reversion - reversible_write_counter` and
     /// increases `reversible_write_counter` by `1`.
     pub(crate) fn rw_counter_of_reversion(&mut self) -> Expression<F> {
+        self.conditional_rw_counter_of_reversion(None)
+    }
+
+    /// Same as [`Self::rw_counter_of_reversion`], but only increases
+    /// `reversible_write_counter` when the condition of the reversible write
+    /// is met.
+    fn conditional_rw_counter_of_reversion(
+        &mut self,
+        condition: Option<Expression<F>>,
+    ) -> Expression<F> {
         let rw_counter_of_reversion =
             self.rw_counter_end_of_reversion.expr() - self.reversible_write_counter.expr();
-        self.reversible_write_counter = self.reversible_write_counter.clone() + 1.expr();
+        self.reversible_write_counter =
+            self.reversible_write_counter.clone() + condition.unwrap_or_else(|| 1.expr());
         rw_counter_of_reversion
     }
 
@@ -606,14 +617,14 @@ impl<'a, F: Field> ConstraintBuilder<'a, F> {
     pub(crate) fn block_lookup(
         &mut self,
         tag: Expression<F>,
-        number: Option<Expression<F>>,
+        number: Expression<F>,
         val: Expression<F>,
     ) {
         self.add_lookup(
             "Block lookup",
             Lookup::Block {
                 field_tag: tag,
-                number: number.unwrap_or_else(|| 0.expr()),
+                number,
                 value: val,
             },
         );
@@ -689,12 +700,19 @@ impl<'a, F: Field> ConstraintBuilder<'a, F> {
         self.rw_lookup(name, true.expr(), tag, values.clone());
 
         if let Some(reversion_info) = reversion_info {
-            // Revert if is_persistent is 0
-            self.condition(1.expr() - reversion_info.is_persistent(), |cb| {
+            // Revert if is_persistent is 0, and only if the write happens when
+            // it's under a condition.
+            let condition = self.condition.take();
+            let reversion_condition = 1.expr() - reversion_info.is_persistent();
+            let reversion_condition = match condition.clone() {
+                Some(condition) => condition * reversion_condition,
+                None => reversion_condition,
+            };
+            self.condition(reversion_condition, |cb| {
                 let name = format!("{} with reversion", name);
                 cb.rw_lookup_with_counter(
                     &name,
-                    reversion_info.rw_counter_of_reversion(),
+                    reversion_info.conditional_rw_counter_of_reversion(condition.clone()),
                     true.expr(),
                     tag,
                     RwValues {
@@ -704,11 +722,35 @@ impl<'a, F: Field> ConstraintBuilder<'a, F> {
                     },
                 )
             });
+            self.condition = condition;
         }
     }
 
     // Access list
 
+    pub(crate) fn account_access_list_read(
+        &mut self,
+        tx_id: Expression<F>,
+        account_address: Expression<F>,
+        value: Expression<F>,
+    ) {
+        self.rw_lookup(
+            "TxAccessListAccount read",
+            false.expr(),
+            RwTableTag::TxAccessListAccount,
+            RwValues::new(
+                tx_id,
+                account_address,
+                0.expr(),
+                0.expr(),
+                value.clone(),
+                value,
+                0.expr(),
+                0.expr(),
+            ),
+        );
+    }
+
     pub(crate) fn account_access_list_write(
         &mut self,
         tx_id: Expression<F>,
@@ -734,6 +776,30 @@ impl<'a, F: Field> ConstraintBuilder<'a, F> {
         );
     }
 
+    pub(crate) fn account_storage_access_list_read(
+        &mut self,
+        tx_id: Expression<F>,
+        account_address: Expression<F>,
+        storage_key: Expression<F>,
+        value: Expression<F>,
+    ) {
+        self.rw_lookup(
+            "TxAccessListAccountStorage read",
+            false.expr(),
+            RwTableTag::TxAccessListAccountStorage,
+            RwValues::new(
+                tx_id,
+                account_address,
+                0.expr(),
+                storage_key,
+                value.clone(),
+                value,
+                0.expr(),
+                0.expr(),
+            ),
+        );
+    }
+
     pub(crate) fn account_storage_access_list_write(
         &mut self,
         tx_id: Expression<F>,
@@ -854,6 +920,32 @@ impl<'a, F: Field> ConstraintBuilder<'a, F> {
         );
     }
 
+    // Account Destructed
+
+    pub(crate) fn account_destructed_write(
+        &mut self,
+        account_address: Expression<F>,
+        value: Expression<F>,
+        value_prev: Expression<F>,
+        reversion_info: Option<&mut ReversionInfo<F>>,
+    ) {
+        self.reversible_write(
+            "AccountDestructed write",
+            RwTableTag::AccountDestructed,
+            RwValues::new(
+                0.expr(),
+                account_address,
+                0.expr(),
+                0.expr(),
+                value,
+                value_prev,
+                0.expr(),
+                0.expr(),
+            ),
+            reversion_info,
+        );
+    }
+
     // Account Storage
 
     pub(crate) fn account_storage_read(
@@ -1157,6 +1249,30 @@ impl<'a, F: Field> ConstraintBuilder<'a, F> {
         );
     }
 
+    // Exponentiation Table
+
+    pub(crate) fn exp_table_lookup(
+        &mut self,
+        base_lo_hi: [Expression<F>; 2],
+        exponent_lo_hi: [Expression<F>; 2],
+        exponentiation_lo_hi: [Expression<F>; 2],
+    ) {
+        let [base_lo, base_hi] = base_lo_hi;
+        let [exponent_lo, exponent_hi] = exponent_lo_hi;
+        let [exponentiation_lo, exponentiation_hi] = exponentiation_lo_hi;
+        self.add_lookup(
+            "exponentiation lookup",
+            Lookup::ExpTable {
+                base_lo,
+                base_hi,
+                exponent_lo,
+                exponent_hi,
+                exponentiation_lo,
+                exponentiation_hi,
+            },
+        );
+    }
+
     // Validation
 
     pub(crate) fn validate_degree(&self, degree: usize, name: &'static str) {
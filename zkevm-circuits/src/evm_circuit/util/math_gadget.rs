@@ -1334,3 +1334,126 @@ impl<F: Field> AbsWordGadget<F> {
         &self.is_neg
     }
 }
+
+/// Returns the number of bytes needed to represent a 256-bit word, i.e. the
+/// index of its most significant non-zero byte plus one, or `0` when the word
+/// is zero.
+#[derive(Clone, Debug)]
+pub(crate) struct ByteSizeGadget<F> {
+    /// One-hot selector of the byte size, where `byte_size_selectors[i] == 1`
+    /// means the byte size is `i`.
+    byte_size_selectors: [Cell<F>; 33],
+    /// Most significant non-zero byte, or `0` when the value is zero.
+    most_significant_nonzero_byte: Cell<F>,
+    /// Inverse of the most significant non-zero byte, used to prove it is
+    /// non-zero.
+    most_significant_nonzero_byte_inverse: Cell<F>,
+    byte_size: Expression<F>,
+}
+
+impl<F: Field> ByteSizeGadget<F> {
+    pub(crate) fn construct(cb: &mut ConstraintBuilder<F>, values: [Expression<F>; 32]) -> Self {
+        let byte_size_selectors = [(); 33].map(|_| cb.query_bool());
+        let most_significant_nonzero_byte = cb.query_cell();
+        let most_significant_nonzero_byte_inverse = cb.query_cell();
+
+        cb.require_equal(
+            "exactly one byte size is selected",
+            sum::expr(&byte_size_selectors),
+            1.expr(),
+        );
+
+        // Every byte at or above the byte size must be zero.
+        for (idx, value) in values.iter().enumerate() {
+            cb.require_zero(
+                "bytes above the byte size are zero",
+                sum::expr(&byte_size_selectors[..=idx]) * value.clone(),
+            );
+        }
+
+        // The byte right below the byte size must be non-zero, unless the
+        // value is zero.
+        cb.require_equal(
+            "most significant non-zero byte is the one below the byte size",
+            most_significant_nonzero_byte.expr(),
+            sum::expr(
+                byte_size_selectors[1..]
+                    .iter()
+                    .zip(values.iter())
+                    .map(|(selector, value)| selector.expr() * value.clone()),
+            ),
+        );
+        cb.require_equal(
+            "most significant non-zero byte ⋅ inverse == 1 unless value is zero",
+            most_significant_nonzero_byte.expr() * most_significant_nonzero_byte_inverse.expr(),
+            1.expr() - byte_size_selectors[0].expr(),
+        );
+
+        let byte_size = sum::expr(
+            byte_size_selectors
+                .iter()
+                .enumerate()
+                .map(|(idx, selector)| selector.expr() * idx.expr()),
+        );
+
+        Self {
+            byte_size_selectors,
+            most_significant_nonzero_byte,
+            most_significant_nonzero_byte_inverse,
+            byte_size,
+        }
+    }
+
+    pub(crate) fn assign(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        value: Word,
+    ) -> Result<(), Error> {
+        let bytes = value.to_le_bytes();
+        let byte_size = bytes
+            .iter()
+            .rposition(|byte| *byte != 0)
+            .map_or(0, |idx| idx + 1);
+
+        for (idx, selector) in self.byte_size_selectors.iter().enumerate() {
+            selector.assign(
+                region,
+                offset,
+                Value::known(F::from((idx == byte_size) as u64)),
+            )?;
+        }
+        let most_significant_nonzero_byte = if byte_size == 0 {
+            F::zero()
+        } else {
+            F::from(bytes[byte_size - 1] as u64)
+        };
+        self.most_significant_nonzero_byte.assign(
+            region,
+            offset,
+            Value::known(most_significant_nonzero_byte),
+        )?;
+        self.most_significant_nonzero_byte_inverse.assign(
+            region,
+            offset,
+            Value::known(most_significant_nonzero_byte.invert().unwrap_or(F::zero())),
+        )?;
+        Ok(())
+    }
+
+    pub(crate) fn byte_size(&self) -> Expression<F> {
+        self.byte_size.clone()
+    }
+
+    /// Returns `r^byte_size` given the powers of randomness `[r, r^2, ...,
+    /// r^31]`, which is only valid for a byte size less than 32.
+    pub(crate) fn randomness_power(&self, power_of_randomness: &[Expression<F>]) -> Expression<F> {
+        self.byte_size_selectors[0].expr()
+            + sum::expr(
+                self.byte_size_selectors[1..32]
+                    .iter()
+                    .zip(power_of_randomness.iter())
+                    .map(|(selector, power)| selector.expr() * power.clone()),
+            )
+    }
+}
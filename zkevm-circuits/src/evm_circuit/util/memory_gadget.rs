@@ -1,12 +1,18 @@
 use super::CachedRegion;
 use crate::{
     evm_circuit::{
-        param::{N_BYTES_GAS, N_BYTES_MEMORY_ADDRESS, N_BYTES_MEMORY_WORD_SIZE},
+        param::{
+            MAX_EXPANDED_MEMORY_ADDRESS, N_BYTES_GAS, N_BYTES_MEMORY_ADDRESS,
+            N_BYTES_MEMORY_WORD_SIZE, N_BYTES_U64,
+        },
         util::{
             constraint_builder::ConstraintBuilder,
             from_bytes,
-            math_gadget::{ConstantDivisionGadget, IsZeroGadget, MinMaxGadget, RangeCheckGadget},
-            select, sum, Cell, MemoryAddress, Word,
+            math_gadget::{
+                AddWordsGadget, ConstantDivisionGadget, IsZeroGadget, LtGadget, MinMaxGadget,
+                RangeCheckGadget,
+            },
+            not, or, select, sum, Cell, MemoryAddress, Word,
         },
     },
     util::Expr,
@@ -157,6 +163,131 @@ impl<F: Field> MemoryAddressGadget<F> {
     }
 }
 
+/// Decodes the dynamic memory offset and length from full words for the
+/// out-of-gas error gadgets, where both could be any value.  The expanded
+/// address `offset + length` overflows if it's greater than
+/// `MAX_EXPANDED_MEMORY_ADDRESS`, which always results in an out-of-gas error.
+/// When it overflows, or the length is zero, there is no memory expansion, so
+/// both the address and length are 0.
+#[derive(Clone, Debug)]
+pub(crate) struct MemoryExpandedAddressGadget<F> {
+    length_is_zero: IsZeroGadget<F>,
+    offset_length_sum: AddWordsGadget<F, 2, false>,
+    sum_lt_cap: LtGadget<F, N_BYTES_U64>,
+    sum_within_u64: IsZeroGadget<F>,
+}
+
+impl<F: Field> MemoryExpandedAddressGadget<F> {
+    pub(crate) fn construct(cb: &mut ConstraintBuilder<F>) -> Self {
+        let offset = cb.query_word();
+        let length = cb.query_word();
+        let expanded_address = cb.query_word();
+
+        let length_is_zero = IsZeroGadget::construct(cb, sum::expr(&length.cells));
+        let offset_length_sum = AddWordsGadget::construct(cb, [offset, length], expanded_address);
+        let expanded_address = offset_length_sum.sum();
+        let sum_lt_cap = LtGadget::construct(
+            cb,
+            from_bytes::expr(&expanded_address.cells[..N_BYTES_U64]),
+            (MAX_EXPANDED_MEMORY_ADDRESS + 1).expr(),
+        );
+        let sum_within_u64 = IsZeroGadget::construct(
+            cb,
+            sum::expr(&expanded_address.cells[N_BYTES_U64..])
+                + offset_length_sum.carry().as_ref().unwrap().expr(),
+        );
+
+        Self {
+            length_is_zero,
+            offset_length_sum,
+            sum_lt_cap,
+            sum_within_u64,
+        }
+    }
+
+    /// Returns the expanded address and the length, which are both 0 if the
+    /// length is zero or the expanded address overflows.
+    pub(crate) fn assign(
+        &self,
+        region: &mut CachedRegion<'_, '_, F>,
+        offset: usize,
+        memory_offset: U256,
+        memory_length: U256,
+    ) -> Result<(u64, u64), Error> {
+        let length_bytes = memory_length.to_le_bytes();
+        self.length_is_zero
+            .assign(region, offset, sum::value(&length_bytes))?;
+
+        let (expanded_address, overflow) = memory_offset.overflowing_add(memory_length);
+        self.offset_length_sum.assign(
+            region,
+            offset,
+            [memory_offset, memory_length],
+            expanded_address,
+        )?;
+
+        let sum_bytes = expanded_address.to_le_bytes();
+        let sum_lo = from_bytes::value::<F>(&sum_bytes[..N_BYTES_U64]);
+        self.sum_lt_cap.assign(
+            region,
+            offset,
+            sum_lo,
+            F::from(MAX_EXPANDED_MEMORY_ADDRESS + 1),
+        )?;
+        self.sum_within_u64.assign(
+            region,
+            offset,
+            sum::value::<F>(&sum_bytes[N_BYTES_U64..]) + F::from(overflow as u64),
+        )?;
+
+        let is_expanded = !memory_length.is_zero()
+            && !overflow
+            && expanded_address <= U256::from(MAX_EXPANDED_MEMORY_ADDRESS);
+        Ok(if is_expanded {
+            (expanded_address.as_u64(), memory_length.as_u64())
+        } else {
+            (0, 0)
+        })
+    }
+
+    pub(crate) fn offset_rlc(&self) -> Expression<F> {
+        self.offset_length_sum.addends()[0].expr()
+    }
+
+    pub(crate) fn length_rlc(&self) -> Expression<F> {
+        self.offset_length_sum.addends()[1].expr()
+    }
+
+    /// Returns 1 if the expanded address is within range, which is always the
+    /// case for zero length.
+    pub(crate) fn within_range(&self) -> Expression<F> {
+        or::expr([
+            self.length_is_zero.expr(),
+            self.sum_within_u64.expr() * self.sum_lt_cap.expr(),
+        ])
+    }
+
+    /// Returns 1 if the expanded address overflows.
+    pub(crate) fn overflow(&self) -> Expression<F> {
+        not::expr(self.within_range())
+    }
+
+    /// Returns the length which is within range, or 0 if it overflows.
+    pub(crate) fn length(&self) -> Expression<F> {
+        self.within_range()
+            * from_bytes::expr(&self.offset_length_sum.addends()[1].cells[..N_BYTES_U64])
+    }
+
+    /// Returns the expanded address, or 0 if the length is zero or it
+    /// overflows.
+    pub(crate) fn address(&self) -> Expression<F> {
+        not::expr(self.length_is_zero.expr())
+            * self.sum_within_u64.expr()
+            * self.sum_lt_cap.expr()
+            * from_bytes::expr(&self.offset_length_sum.sum().cells[..N_BYTES_U64])
+    }
+}
+
 /// Calculates the memory size in words required for a memory access at the
 /// specified address.
 /// `memory_word_size = ceil(address/32) = floor((address + 31) / 32)`
@@ -0,0 +1,542 @@
+//! The Exponentiation circuit implements constraints for the exponentiation
+//! by squaring of the EXP opcode, exposing its intermediate results through
+//! the exponentiation table.
+//!
+//! Each multiplication step `a * b == d (mod 2^256)` of an exponentiation
+//! spans 4 rows, laying out the bytes of `a`, `b`, `d` and of the intermediate
+//! exponent `e` (such that `d == base ^ e`) one per row. Steps are assigned
+//! from the final multiplication down to the first squaring `base * base`, so
+//! that each step is proven correct from the one that follows it.
+
+use bus_mapping::circuit_input_builder::ExpEvent;
+use eth_types::{Field, ToLittleEndian, ToScalar, Word};
+use gadgets::util::{and, not, Expr};
+use halo2_proofs::{
+    circuit::{Layouter, Region, Value},
+    plonk::{Advice, Column, ConstraintSystem, Error, Fixed, TableColumn, VirtualCells},
+    poly::Rotation,
+};
+use std::marker::PhantomData;
+
+use crate::{
+    evm_circuit::{
+        util::{
+            constraint_builder::BaseConstraintBuilder, from_bytes, pow_of_two_expr, split_u256,
+            split_u256_limb64,
+        },
+        witness::Block,
+    },
+    table::ExpTable,
+};
+
+/// Number of rows taken by each multiplication step.
+pub const ROWS_PER_STEP: usize = 4;
+
+/// Number of bytes used for each carry of a multiplication step.
+const N_BYTES_CARRY: usize = 9;
+
+/// Layout of the Exponentiation circuit.
+#[derive(Clone, Debug)]
+pub struct ExpCircuit<F> {
+    /// Whether the row is enabled or not, used for the byte range checks.
+    pub q_enable: Column<Fixed>,
+    /// Little-endian bytes of `a`, `b`, `d` and `e`, one per row of a step.
+    pub bytes: [Column<Advice>; 32],
+    /// Little-endian bytes of the low and high carries of `a * b`, in the first
+    /// and the second row of a step.
+    pub carry: [Column<Advice>; N_BYTES_CARRY],
+    /// Carry from the low to the high 128 bits of the intermediate exponent,
+    /// when deriving it from the exponent of the following step.
+    pub exp_carry: Column<Advice>,
+    /// Whether the step is the last one, i.e. the squaring `base * base`.
+    pub is_last: Column<Advice>,
+    /// Whether the step is a multiplication by the base, rather than a
+    /// squaring.
+    pub is_mul: Column<Advice>,
+    /// Lookup table of all byte values.
+    pub u8_table: TableColumn,
+    /// The Exponentiation Table contains the columns that are exposed via the
+    /// lookup expressions.
+    pub exp_table: ExpTable,
+    _marker: PhantomData<F>,
+}
+
+impl<F: Field> ExpCircuit<F> {
+    /// Configure the Exponentiation Circuit constraining the multiplication
+    /// steps of every exponentiation by squaring.
+    pub fn configure(meta: &mut ConstraintSystem<F>, exp_table: ExpTable) -> Self {
+        let q_enable = meta.fixed_column();
+        let bytes = [(); 32].map(|_| meta.advice_column());
+        let carry = [(); N_BYTES_CARRY].map(|_| meta.advice_column());
+        let exp_carry = meta.advice_column();
+        let is_last = meta.advice_column();
+        let is_mul = meta.advice_column();
+        let u8_table = meta.lookup_table_column();
+
+        meta.create_gate("verify exponentiation step", |meta| {
+            let mut cb = BaseConstraintBuilder::default();
+
+            // Returns the low and high 128 bits of the word laid out at the
+            // rotation.
+            let lo_hi = |meta: &mut VirtualCells<F>, rotation: i32| {
+                let bytes = bytes.map(|column| meta.query_advice(column, Rotation(rotation)));
+                (
+                    from_bytes::expr(&bytes[0..16]),
+                    from_bytes::expr(&bytes[16..32]),
+                )
+            };
+
+            let is_last_expr = meta.query_advice(is_last, Rotation::cur());
+            let is_mul_expr = meta.query_advice(is_mul, Rotation::cur());
+            let exp_carry_expr = meta.query_advice(exp_carry, Rotation::cur());
+            cb.require_boolean("is_last is boolean", is_last_expr.clone());
+            cb.require_boolean("is_mul is boolean", is_mul_expr.clone());
+            cb.require_boolean("exp_carry is boolean", exp_carry_expr.clone());
+
+            // a * b == d (mod 2^256), following the approach of the
+            // MulAddWordsGadget.
+            let limbs = |meta: &mut VirtualCells<F>, rotation: i32| {
+                let bytes = bytes.map(|column| meta.query_advice(column, Rotation(rotation)));
+                [0, 1, 2, 3].map(|i| from_bytes::expr(&bytes[i * 8..(i + 1) * 8]))
+            };
+            let a = limbs(meta, 0);
+            let b = limbs(meta, 1);
+            let carry_lo = from_bytes::expr(&carry.map(|c| meta.query_advice(c, Rotation::cur())));
+            let carry_hi = from_bytes::expr(&carry.map(|c| meta.query_advice(c, Rotation::next())));
+            let t0 = a[0].clone() * b[0].clone();
+            let t1 = a[0].clone() * b[1].clone() + a[1].clone() * b[0].clone();
+            let t2 = a[0].clone() * b[2].clone()
+                + a[1].clone() * b[1].clone()
+                + a[2].clone() * b[0].clone();
+            let t3 = a[0].clone() * b[3].clone()
+                + a[1].clone() * b[2].clone()
+                + a[2].clone() * b[1].clone()
+                + a[3].clone() * b[0].clone();
+            let (d_lo, d_hi) = lo_hi(meta, 2);
+            cb.require_equal(
+                "(a * b)_lo == d_lo + carry_lo ⋅ 2^128",
+                t0 + t1 * pow_of_two_expr(64),
+                d_lo.clone() + carry_lo.clone() * pow_of_two_expr(128),
+            );
+            cb.require_equal(
+                "(a * b)_hi + carry_lo == d_hi + carry_hi ⋅ 2^128",
+                t2 + t3 * pow_of_two_expr(64) + carry_lo,
+                d_hi.clone() + carry_hi * pow_of_two_expr(128),
+            );
+
+            // The exponentiation table exposes d and e.
+            let (e_lo, e_hi) = lo_hi(meta, 3);
+            cb.require_equal(
+                "exponentiation_lo == d_lo",
+                meta.query_advice(exp_table.exponentiation_lo, Rotation::cur()),
+                d_lo,
+            );
+            cb.require_equal(
+                "exponentiation_hi == d_hi",
+                meta.query_advice(exp_table.exponentiation_hi, Rotation::cur()),
+                d_hi,
+            );
+            cb.require_equal(
+                "exponent_lo == e_lo",
+                meta.query_advice(exp_table.exponent_lo, Rotation::cur()),
+                e_lo.clone(),
+            );
+            cb.require_equal(
+                "exponent_hi == e_hi",
+                meta.query_advice(exp_table.exponent_hi, Rotation::cur()),
+                e_hi.clone(),
+            );
+
+            let base_lo = meta.query_advice(exp_table.base_lo, Rotation::cur());
+            let base_hi = meta.query_advice(exp_table.base_hi, Rotation::cur());
+            let (a_lo, a_hi) = lo_hi(meta, 0);
+            let (b_lo, b_hi) = lo_hi(meta, 1);
+
+            // The last step squares the base: base ^ 2.
+            cb.condition(is_last_expr.clone(), |cb| {
+                cb.require_equal("a_lo == base_lo", a_lo.clone(), base_lo.clone());
+                cb.require_equal("a_hi == base_hi", a_hi.clone(), base_hi.clone());
+                cb.require_equal("b_lo == base_lo", b_lo.clone(), base_lo.clone());
+                cb.require_equal("b_hi == base_hi", b_hi.clone(), base_hi.clone());
+                cb.require_equal("e_lo == 2", e_lo.clone(), 2.expr());
+                cb.require_zero("e_hi == 0", e_hi.clone());
+                cb.require_zero("is_mul == 0", is_mul_expr.clone());
+            });
+
+            // Any other step takes its multiplier from the result of the
+            // following step, d' == base ^ e'.
+            let (next_d_lo, next_d_hi) = lo_hi(meta, 6);
+            cb.condition(not::expr(is_last_expr.clone()), |cb| {
+                cb.require_equal(
+                    "next step is enabled",
+                    meta.query_fixed(exp_table.is_step, Rotation(4)),
+                    1.expr(),
+                );
+                cb.require_equal(
+                    "next base_lo == base_lo",
+                    meta.query_advice(exp_table.base_lo, Rotation(4)),
+                    base_lo.clone(),
+                );
+                cb.require_equal(
+                    "next base_hi == base_hi",
+                    meta.query_advice(exp_table.base_hi, Rotation(4)),
+                    base_hi.clone(),
+                );
+                cb.require_equal("a_lo == next d_lo", a_lo.clone(), next_d_lo);
+                cb.require_equal("a_hi == next d_hi", a_hi.clone(), next_d_hi);
+            });
+
+            let (next_e_lo, next_e_hi) = lo_hi(meta, 7);
+            // Multiplication by the base: e == e' + 1.
+            cb.condition(
+                and::expr([not::expr(is_last_expr.clone()), is_mul_expr.clone()]),
+                |cb| {
+                    cb.require_equal("b_lo == base_lo", b_lo.clone(), base_lo);
+                    cb.require_equal("b_hi == base_hi", b_hi.clone(), base_hi);
+                    cb.require_equal(
+                        "e_lo + exp_carry ⋅ 2^128 == e'_lo + 1",
+                        e_lo.clone() + exp_carry_expr.clone() * pow_of_two_expr(128),
+                        next_e_lo.clone() + 1.expr(),
+                    );
+                    cb.require_equal(
+                        "e_hi == e'_hi + exp_carry",
+                        e_hi.clone(),
+                        next_e_hi.clone() + exp_carry_expr.clone(),
+                    );
+                },
+            );
+            // Squaring: e == 2 ⋅ e'.
+            cb.condition(
+                and::expr([not::expr(is_last_expr), not::expr(is_mul_expr)]),
+                |cb| {
+                    cb.require_equal("b_lo == a_lo", b_lo, a_lo);
+                    cb.require_equal("b_hi == a_hi", b_hi, a_hi);
+                    cb.require_equal(
+                        "e_lo + exp_carry ⋅ 2^128 == 2 ⋅ e'_lo",
+                        e_lo + exp_carry_expr.clone() * pow_of_two_expr(128),
+                        next_e_lo * 2.expr(),
+                    );
+                    cb.require_equal(
+                        "e_hi == 2 ⋅ e'_hi + exp_carry",
+                        e_hi,
+                        next_e_hi * 2.expr() + exp_carry_expr,
+                    );
+                },
+            );
+
+            cb.gate(meta.query_fixed(exp_table.is_step, Rotation::cur()))
+        });
+
+        for column in bytes.iter().chain(carry.iter()) {
+            meta.lookup("exponentiation byte range check", |meta| {
+                vec![(
+                    meta.query_fixed(q_enable, Rotation::cur())
+                        * meta.query_advice(*column, Rotation::cur()),
+                    u8_table,
+                )]
+            });
+        }
+
+        Self {
+            q_enable,
+            bytes,
+            carry,
+            exp_carry,
+            is_last,
+            is_mul,
+            u8_table,
+            exp_table,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Assign a witness block to the Exponentiation Circuit.
+    pub fn assign_block(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        block: &Block<F>,
+    ) -> Result<(), Error> {
+        self.load_u8_table(layouter)?;
+
+        layouter.assign_region(
+            || "assign exponentiation table",
+            |mut region| {
+                let mut offset = 0;
+                for exp_event in block.exp_events.iter() {
+                    self.assign_exp_event(&mut region, offset, exp_event)?;
+                    offset += exp_event.steps.len() * ROWS_PER_STEP;
+                }
+                // pad a step worth of rows in the end, queried by the rotations
+                // of the last step
+                for _ in 0..ROWS_PER_STEP {
+                    self.assign_row(&mut region, offset, false, [F::zero(); 6], [F::zero(); 3])?;
+                    self.assign_bytes(&mut region, offset, Word::zero(), Word::zero())?;
+                    offset += 1;
+                }
+                Ok(())
+            },
+        )
+    }
+
+    fn load_u8_table(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_table(
+            || "u8 table",
+            |mut table| {
+                for value in 0..256 {
+                    table.assign_cell(
+                        || format!("u8 table row {}", value),
+                        self.u8_table,
+                        value,
+                        || Value::known(F::from(value as u64)),
+                    )?;
+                }
+                Ok(())
+            },
+        )
+    }
+
+    fn assign_exp_event(
+        &self,
+        region: &mut Region<F>,
+        mut offset: usize,
+        exp_event: &ExpEvent,
+    ) -> Result<(), Error> {
+        let table_rows = ExpTable::assignments::<F>(exp_event);
+        let mut exponent = exp_event.exponent;
+        for (idx, (step, table_row)) in exp_event.steps.iter().zip(table_rows).enumerate() {
+            let is_last = idx + 1 == exp_event.steps.len();
+            let is_mul = exponent.bit(0);
+            let next_exponent = if is_mul { exponent - 1 } else { exponent >> 1 };
+            let exp_carry = if is_last {
+                Word::zero()
+            } else {
+                let (next_exponent_lo, _) = split_u256(&next_exponent);
+                let addend = if is_mul {
+                    Word::one()
+                } else {
+                    next_exponent_lo
+                };
+                (next_exponent_lo + addend) >> 128
+            };
+            let (carry_lo, carry_hi) = mul_carries(step.a, step.b, step.d);
+
+            let flags = [
+                exp_carry,
+                Word::from(is_last as u64),
+                Word::from(is_mul as u64),
+            ]
+            .map(|value| value.to_scalar().unwrap());
+            self.assign_row(region, offset, true, table_row, flags)?;
+
+            for (row, (word, carry)) in [
+                (step.a, carry_lo),
+                (step.b, carry_hi),
+                (step.d, Word::zero()),
+                (exponent, Word::zero()),
+            ]
+            .into_iter()
+            .enumerate()
+            {
+                if row > 0 {
+                    self.assign_row(region, offset, false, [F::zero(); 6], [F::zero(); 3])?;
+                }
+                self.assign_bytes(region, offset, word, carry)?;
+                offset += 1;
+            }
+
+            exponent = next_exponent;
+        }
+        Ok(())
+    }
+
+    /// Assign the exponentiation table and the step flags `exp_carry`,
+    /// `is_last` and `is_mul` of a row.
+    fn assign_row(
+        &self,
+        region: &mut Region<F>,
+        offset: usize,
+        is_step: bool,
+        table_row: [F; 6],
+        flags: [F; 3],
+    ) -> Result<(), Error> {
+        region.assign_fixed(
+            || format!("assign is_step {}", offset),
+            self.exp_table.is_step,
+            offset,
+            || Value::known(F::from(is_step as u64)),
+        )?;
+        for (column, value) in self.exp_table.columns().into_iter().zip(table_row) {
+            region.assign_advice(
+                || format!("assign exponentiation table {}", offset),
+                column,
+                offset,
+                || Value::known(value),
+            )?;
+        }
+        for (column, value) in [self.exp_carry, self.is_last, self.is_mul]
+            .into_iter()
+            .zip(flags)
+        {
+            region.assign_advice(
+                || format!("assign step flags {}", offset),
+                column,
+                offset,
+                || Value::known(value),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Assign the bytes of a word and of a carry in a row, enabling their
+    /// range checks.
+    fn assign_bytes(
+        &self,
+        region: &mut Region<F>,
+        offset: usize,
+        word: Word,
+        carry: Word,
+    ) -> Result<(), Error> {
+        region.assign_fixed(
+            || format!("assign q_enable {}", offset),
+            self.q_enable,
+            offset,
+            || Value::known(F::one()),
+        )?;
+        for (column, byte) in self.bytes.iter().zip(word.to_le_bytes()) {
+            region.assign_advice(
+                || format!("assign bytes {}", offset),
+                *column,
+                offset,
+                || Value::known(F::from(byte as u64)),
+            )?;
+        }
+        for (column, byte) in self.carry.iter().zip(carry.to_le_bytes()) {
+            region.assign_advice(
+                || format!("assign carry {}", offset),
+                *column,
+                offset,
+                || Value::known(F::from(byte as u64)),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the low and high carries of `a * b == d (mod 2^256)`, as computed
+/// by the MulAddWordsGadget.
+fn mul_carries(a: Word, b: Word, d: Word) -> (Word, Word) {
+    let a_limbs = split_u256_limb64(&a);
+    let b_limbs = split_u256_limb64(&b);
+    let (d_lo, d_hi) = split_u256(&d);
+
+    let t0 = a_limbs[0] * b_limbs[0];
+    let t1 = a_limbs[0] * b_limbs[1] + a_limbs[1] * b_limbs[0];
+    let t2 = a_limbs[0] * b_limbs[2] + a_limbs[1] * b_limbs[1] + a_limbs[2] * b_limbs[0];
+    let t3 = a_limbs[0] * b_limbs[3]
+        + a_limbs[1] * b_limbs[2]
+        + a_limbs[2] * b_limbs[1]
+        + a_limbs[3] * b_limbs[0];
+
+    let carry_lo = (t0 + (t1 << 64) - d_lo) >> 128;
+    let carry_hi = (t2 + (t3 << 64) + carry_lo - d_hi) >> 128;
+    (carry_lo, carry_hi)
+}
+
+/// Dev helpers
+#[cfg(any(feature = "test", test))]
+pub mod dev {
+    use super::*;
+    use halo2_proofs::{
+        circuit::SimpleFloorPlanner,
+        dev::{MockProver, VerifyFailure},
+        plonk::Circuit,
+    };
+
+    #[derive(Default)]
+    struct ExpCircuitTester<F> {
+        block: Block<F>,
+    }
+
+    impl<F: Field> Circuit<F> for ExpCircuitTester<F> {
+        type Config = ExpCircuit<F>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let exp_table = ExpTable::construct(meta);
+            ExpCircuit::configure(meta, exp_table)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            config.assign_block(&mut layouter, &self.block)
+        }
+    }
+
+    /// Test exponentiation circuit with the provided block witness
+    pub fn test_exp_circuit<F: Field>(k: u32, block: Block<F>) -> Result<(), Vec<VerifyFailure>> {
+        let circuit = ExpCircuitTester::<F> { block };
+        let prover = MockProver::<F>::run(k, &circuit, vec![]).unwrap();
+        prover.verify()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::dev::test_exp_circuit;
+    use bus_mapping::{circuit_input_builder::CircuitInputBuilder, mock::BlockData};
+    use eth_types::{bytecode, geth_types::GethData, Word};
+    use mock::TestContext;
+
+    use crate::evm_circuit::witness::block_convert;
+
+    fn gen_data(base: Word, exponent: Word) -> CircuitInputBuilder {
+        let code = bytecode! {
+            PUSH32(exponent)
+            PUSH32(base)
+            EXP
+            STOP
+        };
+        let test_ctx = TestContext::<2, 1>::simple_ctx_with_bytecode(code).unwrap();
+        let block: GethData = test_ctx.into();
+        let mut builder = BlockData::new_from_geth_data(block.clone()).new_circuit_input_builder();
+        builder
+            .handle_block(&block.eth_block, &block.geth_traces)
+            .unwrap();
+        builder
+    }
+
+    fn test_ok(base: Word, exponent: Word, k: u32) {
+        let builder = gen_data(base, exponent);
+        let block = block_convert(&builder.block, &builder.code_db).unwrap();
+        assert_eq!(test_exp_circuit(k, block), Ok(()));
+    }
+
+    #[test]
+    fn exp_circuit_single() {
+        test_ok(2.into(), 2.into(), 10);
+        test_ok(3.into(), 7.into(), 10);
+        test_ok(3.into(), 13.into(), 10);
+    }
+
+    #[test]
+    fn exp_circuit_big() {
+        test_ok(
+            3.into(),
+            Word::from_dec_str("1000000000000000000000").unwrap(),
+            10,
+        );
+    }
+
+    #[test]
+    fn exp_circuit_overflow() {
+        test_ok(Word::MAX, Word::MAX, 12);
+        test_ok(2.into(), 256.into(), 10);
+    }
+}
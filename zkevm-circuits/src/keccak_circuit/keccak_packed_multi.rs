@@ -1538,6 +1538,16 @@ impl<F: Field> KeccakPackedConfig<F> {
         }
     }
 
+    /// Returns the number of rows required to hash all the inputs, including
+    /// the dummy round at the start.
+    pub fn get_num_rows_required(inputs: &[Vec<u8>]) -> usize {
+        let num_keccak_f = inputs
+            .iter()
+            .map(|bytes| bytes.len() / RATE + 1)
+            .sum::<usize>();
+        get_num_rows_per_round() * (1 + num_keccak_f * (NUM_ROUNDS + 1))
+    }
+
     /// Sets the witness using the data to be hashed
     pub fn assign_from_witness(
         &self,
@@ -19,7 +19,9 @@
 pub mod bytecode_circuit;
 pub mod copy_circuit;
 pub mod evm_circuit;
+pub mod exp_circuit;
 pub mod keccak_circuit;
+pub mod mpt_circuit;
 pub mod pi_circuit;
 pub mod state_circuit;
 pub mod super_circuit;
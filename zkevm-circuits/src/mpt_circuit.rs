@@ -0,0 +1,3192 @@
+//! The MPT circuit implementation.
+//!
+//! The MPT circuit proves the Merkle Patricia Trie updates of the MptTable,
+//! which the State Circuit looks up for the last access of each account field
+//! and storage slot.  Each update is proven with the `eth_getProof` nodes of
+//! its key in the trie before and after the update (the old and new paths).
+//! The rows of an update are:
+//! - The 64 nibbles of the key of the account, which is the keccak hash of its
+//!   address, and for storage updates the nibbles of the key of the storage
+//!   slot.  The keccak preimages are the address and storage key of the
+//!   MptTable.
+//! - The bytes of the nodes of the old path and then of the new path, one per
+//!   row, from the node hashing to the old or new root of the update.  Each
+//!   byte is looked up in a table of RLP prefixes to parse the items of its
+//!   node: the child of a branch is its item at the nibble of the key, the path
+//!   of an extension or leaf is matched against the following nibbles of the
+//!   key, and the value is the field of the account leaf given by the proof
+//!   type, or the value of the storage leaf.  For storage updates, the account
+//!   leaf is followed by the storage trie from its storage root.
+//! - A path ends at the leaf of the key, which contains the old or new value,
+//!   or at the first nibble of the key which is not in the trie, in which case
+//!   the value is 0.
+//! - Each node is hashed via a lookup to the keccak table, except the nodes
+//!   shorter than 32 bytes, which are inlined in their parent node.
+//! - The old and new paths have the same nodes off the key, so that an update
+//!   can't change the other keys of the trie: each node of a path is looked up
+//!   in the other path at the same index and depth, with the same type and the
+//!   same RLC of its bytes off the key.  These are the siblings of the child of
+//!   a branch, the other fields of an account leaf, and all the items of an
+//!   extension or leaf of another key.  The empty trie, and the leaf of the key
+//!   when it's inserted or deleted, are the only nodes which can be in a single
+//!   path, and the other fields of such an account leaf are those of the empty
+//!   account.  So the updates which split a leaf or an extension, or which
+//!   collapse a branch into its remaining child, can't be proven yet.
+//!
+//! The kind of each row and the boundaries of the keys, nodes and paths are
+//! advice columns constrained by the transitions between consecutive rows,
+//! and the rows after the updates are padding.  All the rows of an update hold
+//! its MptTable row, and the updates chain their roots from the old to the new
+//! state root, which are the public inputs of the circuit.
+
+use crate::{
+    evm_circuit::util::constraint_builder::BaseConstraintBuilder,
+    table::{DynamicTableColumns, KeccakTable, MptTable, ProofType},
+    util::{
+        power_of_randomness_from_instance, random_linear_combine_word as rlc, Challenges, Expr,
+    },
+    witness::{MptProof, MptUpdate, MptUpdateWitness},
+};
+use eth_types::{Field, ToBigEndian, ToLittleEndian, Word};
+use ethers_core::utils::{keccak256, rlp::Rlp};
+use gadgets::util::{and, not, select, sum};
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, Region, SimpleFloorPlanner, Value},
+    plonk::{
+        Advice, Circuit, Column, ConstraintSystem, Error, Expression, Fixed, Instance, TableColumn,
+        VirtualCells,
+    },
+    poly::Rotation,
+};
+use itertools::Itertools;
+use log::error;
+use std::{iter::once, marker::PhantomData, ops::Range};
+
+/// Number of nibbles of a key, which is a keccak hash
+const KEY_NIBBLES: usize = 64;
+
+/// The empty trie, which hashes to the empty root
+const EMPTY_NODE: [u8; 1] = [0x80];
+
+/// Proof types supported by the circuit, each with a flag column
+const PROOF_TYPES: [ProofType; 5] = [
+    ProofType::NonceChanged,
+    ProofType::BalanceChanged,
+    ProofType::CodeHashExists,
+    ProofType::AccountDoesNotExist,
+    ProofType::StorageChanged,
+];
+
+/// Kinds of the RLP prefixes in the nodes of the trie
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum RlpKind {
+    /// A string of a single byte below 0x80, which is its own prefix
+    Single,
+    /// A string of at most 55 bytes
+    String,
+    /// A string of at most 255 bytes, whose length is the next byte
+    LongString,
+    /// A list of at most 55 bytes
+    List,
+    /// A list of at most 255 bytes, whose length is the next byte
+    LongList,
+    /// A list of at most 65535 bytes, whose length is the next 2 bytes
+    LongList2,
+}
+
+const RLP_KINDS: usize = 6;
+
+impl RlpKind {
+    /// Return the kind of a prefix byte, together with the length of the
+    /// string or list for short ones.
+    fn from_byte(byte: u8) -> Option<(Self, u8)> {
+        match byte {
+            0x00..=0x7f => Some((Self::Single, 0)),
+            0x80..=0xb7 => Some((Self::String, byte - 0x80)),
+            0xb8 => Some((Self::LongString, 0)),
+            0xc0..=0xf7 => Some((Self::List, byte - 0xc0)),
+            0xf8 => Some((Self::LongList, 0)),
+            0xf9 => Some((Self::LongList2, 0)),
+            _ => None,
+        }
+    }
+}
+
+/// Types of the nodes of a path
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum NodeType {
+    Branch,
+    Extension,
+    Leaf,
+    /// The empty trie
+    Empty,
+}
+
+const NODE_TYPES: usize = 4;
+
+/// A byte with its nibbles, and the kind and length of the RLP prefix it is.
+/// These are columns of the rows of the circuit, and of the byte table they
+/// are looked up in.
+#[derive(Clone, Copy, Debug, Default)]
+struct ByteColumns<C> {
+    byte: C,
+    nibble_hi: C,
+    nibble_lo: C,
+    kinds: [C; RLP_KINDS],
+    rlp_len: C,
+}
+
+impl<C: Copy> ByteColumns<C> {
+    fn construct(mut column: impl FnMut() -> C) -> Self {
+        Self {
+            byte: column(),
+            nibble_hi: column(),
+            nibble_lo: column(),
+            kinds: [(); RLP_KINDS].map(|_| column()),
+            rlp_len: column(),
+        }
+    }
+
+    fn map<D>(&self, mut f: impl FnMut(C) -> D) -> ByteColumns<D> {
+        ByteColumns {
+            byte: f(self.byte),
+            nibble_hi: f(self.nibble_hi),
+            nibble_lo: f(self.nibble_lo),
+            kinds: self.kinds.map(&mut f),
+            rlp_len: f(self.rlp_len),
+        }
+    }
+
+    fn columns(&self) -> Vec<C> {
+        [self.byte, self.nibble_hi, self.nibble_lo]
+            .into_iter()
+            .chain(self.kinds)
+            .chain(once(self.rlp_len))
+            .collect()
+    }
+}
+
+impl ByteColumns<u64> {
+    fn from_byte(byte: u8) -> Self {
+        let rlp_kind = RlpKind::from_byte(byte);
+        let mut kinds = [0; RLP_KINDS];
+        if let Some((kind, _)) = rlp_kind {
+            kinds[kind as usize] = 1;
+        }
+        Self {
+            byte: byte as u64,
+            nibble_hi: (byte >> 4) as u64,
+            nibble_lo: (byte & 0xf) as u64,
+            kinds,
+            rlp_len: rlp_kind.map_or(0, |(_, len)| len as u64),
+        }
+    }
+}
+
+/// Columns of the rows of the nibbles of a key
+#[derive(Clone, Copy, Debug)]
+struct KeyColumns {
+    // Whether the row is the first or last nibble of the key
+    first: Column<Advice>,
+    last: Column<Advice>,
+    // Whether the nibble is the low nibble of the byte of the row
+    is_odd: Column<Advice>,
+    // RLC of the key bytes up to the row
+    acc: Column<Advice>,
+    // Byte of the keccak preimage of the key in the row, and the RLC, value
+    // and length of the preimage up to the row
+    is_preimage: Column<Advice>,
+    preimage_byte: Column<Advice>,
+    preimage_rlc: Column<Advice>,
+    preimage_int: Column<Advice>,
+    preimage_len: Column<Advice>,
+}
+
+impl KeyColumns {
+    fn columns(&self) -> Vec<Column<Advice>> {
+        vec![
+            self.first,
+            self.last,
+            self.is_odd,
+            self.acc,
+            self.is_preimage,
+            self.preimage_byte,
+            self.preimage_rlc,
+            self.preimage_int,
+            self.preimage_len,
+        ]
+    }
+}
+
+/// Columns of the rows of the bytes of a node
+#[derive(Clone, Copy, Debug)]
+struct NodeColumns {
+    // Whether the row is the first or last byte of the node
+    first: Column<Advice>,
+    last: Column<Advice>,
+    // RLC and length of the node bytes up to the row, as in the keccak table
+    rlc: Column<Advice>,
+    len: Column<Advice>,
+    // RLC of the keccak hash of the node, or of the node itself when it's
+    // inlined in its parent node
+    hash_rlc: Column<Advice>,
+    is_inline: Column<Advice>,
+    // Flags of the NodeType of the node
+    node_type: [Column<Advice>; NODE_TYPES],
+    // Number of the remaining bytes of the node
+    list_rem: Column<Advice>,
+    // Whether the byte is the first of an item, the number of the remaining
+    // bytes of the item, and the index of the item, which is 0 for the list
+    // header
+    item_first: Column<Advice>,
+    item_rem: Column<Advice>,
+    item_index: Column<Advice>,
+    // Whether the item is the child of a branch at the key nibble, the child
+    // of an extension or the value of a leaf, together with the inverse of
+    // the difference of `item_index` to the index of that item
+    is_key_item: Column<Advice>,
+    key_item_inv: Column<Advice>,
+    // Whether the item is the hex prefix encoded path of an extension or leaf,
+    // and whether the path has an odd number of nibbles
+    is_path_item: Column<Advice>,
+    path_odd: Column<Advice>,
+    // Whether the high and low nibbles of the byte are nibbles of the path,
+    // the number of the path nibbles up to the row, and the nibbles of the
+    // key they are matched against
+    has_hi: Column<Advice>,
+    has_lo: Column<Advice>,
+    nibble_count: Column<Advice>,
+    key_nibble_hi: Column<Advice>,
+    key_nibble_lo: Column<Advice>,
+    // Whether the node doesn't match the key up to the row, together with the
+    // inverse of the difference of the nibbles in the row where it doesn't
+    mismatch: Column<Advice>,
+    mismatch_inv: Column<Advice>,
+    // Whether the child node is inlined, whether the byte is part of the hash
+    // or the encoding of the child node, and the RLC of those bytes up to the
+    // row
+    child_inline: Column<Advice>,
+    is_child_byte: Column<Advice>,
+    child_rlc: Column<Advice>,
+    // Whether the byte is part of the fields of the leaf value, which are the
+    // fields of the account or the value of the storage slot, together with
+    // whether it's the first byte of a field, the number of the remaining
+    // bytes of the field and the index of the field, starting at 1
+    is_field: Column<Advice>,
+    sub_first: Column<Advice>,
+    sub_rem: Column<Advice>,
+    sub_index: Column<Advice>,
+    // Whether the field is the one of the proof type, together with the
+    // inverse of the difference of `sub_index` to its index
+    is_target_field: Column<Advice>,
+    target_field_inv: Column<Advice>,
+    // Whether the byte is part of the value, and the value of those bytes up
+    // to the row
+    is_value_byte: Column<Advice>,
+    value_acc: Column<Advice>,
+    // Whether the row is the last byte of the path
+    path_last: Column<Advice>,
+    // Index of the node in its path
+    path_index: Column<Advice>,
+    // Whether the byte is part of the list header of the node
+    is_header: Column<Advice>,
+    // Whether the node is an extension or leaf of another key, where the path
+    // mismatches the key
+    is_other_key: Column<Advice>,
+    // Whether the byte is off the key, and the RLC and number of those bytes
+    // up to the row
+    is_off_key: Column<Advice>,
+    off_key_rlc: Column<Advice>,
+    off_key_len: Column<Advice>,
+    // Whether the node is the leaf of the key which is inserted or deleted by
+    // the update, and so is only in this path
+    is_fresh: Column<Advice>,
+}
+
+impl NodeColumns {
+    fn columns(&self) -> Vec<Column<Advice>> {
+        [
+            self.first,
+            self.last,
+            self.rlc,
+            self.len,
+            self.hash_rlc,
+            self.is_inline,
+        ]
+        .into_iter()
+        .chain(self.node_type)
+        .chain([
+            self.list_rem,
+            self.item_first,
+            self.item_rem,
+            self.item_index,
+            self.is_key_item,
+            self.key_item_inv,
+            self.is_path_item,
+            self.path_odd,
+            self.has_hi,
+            self.has_lo,
+            self.nibble_count,
+            self.key_nibble_hi,
+            self.key_nibble_lo,
+            self.mismatch,
+            self.mismatch_inv,
+            self.child_inline,
+            self.is_child_byte,
+            self.child_rlc,
+            self.is_field,
+            self.sub_first,
+            self.sub_rem,
+            self.sub_index,
+            self.is_target_field,
+            self.target_field_inv,
+            self.is_value_byte,
+            self.value_acc,
+            self.path_last,
+            self.path_index,
+            self.is_header,
+            self.is_other_key,
+            self.is_off_key,
+            self.off_key_rlc,
+            self.off_key_len,
+            self.is_fresh,
+        ])
+        .collect()
+    }
+}
+
+/// Config for MptCircuit
+#[derive(Clone, Debug)]
+pub struct MptCircuitConfig<F> {
+    // Whether the row is one of the `n_rows` rows of the circuit, and whether
+    // it's the first or last of them
+    q_enable: Column<Fixed>,
+    q_first: Column<Fixed>,
+    q_last: Column<Fixed>,
+    // Whether the row is a nibble of a key or a byte of a node, and padding
+    // otherwise
+    is_key: Column<Advice>,
+    is_node: Column<Advice>,
+    // Whether the row is in the storage trie, and in the new path
+    is_storage_trie: Column<Advice>,
+    is_new: Column<Advice>,
+    byte: ByteColumns<Column<Advice>>,
+    // Depth in the key of the nibble of a key row, or of the first nibble of
+    // a node, and the nibble of the key at that depth
+    depth: Column<Advice>,
+    nibble: Column<Advice>,
+    key: KeyColumns,
+    node: NodeColumns,
+    // Values of the update of the row besides its MptTable row: the flags of
+    // its proof type in PROOF_TYPES, and the RLC of the keys of the account,
+    // of the storage slot and of the trie of the row
+    proof_type_flags: [Column<Advice>; PROOF_TYPES.len()],
+    address_hash: Column<Advice>,
+    storage_key_hash: Column<Advice>,
+    key_hash: Column<Advice>,
+    // Old root of the update of the row, or the new root of the last update
+    // in the padding rows
+    root_chain: Column<Advice>,
+    // Old and new state roots
+    state_roots: Column<Instance>,
+    byte_table: ByteColumns<TableColumn>,
+    // Field index, remaining bytes and byte of the fields of the empty
+    // account, as in the NodeColumns
+    empty_account_table: [TableColumn; 3],
+    mpt_table: MptTable,
+    keccak_table: KeccakTable,
+    _marker: PhantomData<F>,
+}
+
+impl<F: Field> MptCircuitConfig<F> {
+    /// Configure MptCircuit
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        mpt_table: MptTable,
+        keccak_table: KeccakTable,
+        randomness: Expression<F>,
+    ) -> Self {
+        let q_enable = meta.fixed_column();
+        let q_first = meta.fixed_column();
+        let q_last = meta.fixed_column();
+        let [is_key, is_node, is_storage_trie, is_new, depth, nibble] =
+            [(); 6].map(|_| meta.advice_column());
+        let byte = ByteColumns::construct(|| meta.advice_column());
+        let key = KeyColumns {
+            first: meta.advice_column(),
+            last: meta.advice_column(),
+            is_odd: meta.advice_column(),
+            acc: meta.advice_column(),
+            is_preimage: meta.advice_column(),
+            preimage_byte: meta.advice_column(),
+            preimage_rlc: meta.advice_column(),
+            preimage_int: meta.advice_column(),
+            preimage_len: meta.advice_column(),
+        };
+        let node = NodeColumns {
+            first: meta.advice_column(),
+            last: meta.advice_column(),
+            rlc: meta.advice_column(),
+            len: meta.advice_column(),
+            hash_rlc: meta.advice_column(),
+            is_inline: meta.advice_column(),
+            node_type: [(); NODE_TYPES].map(|_| meta.advice_column()),
+            list_rem: meta.advice_column(),
+            item_first: meta.advice_column(),
+            item_rem: meta.advice_column(),
+            item_index: meta.advice_column(),
+            is_key_item: meta.advice_column(),
+            key_item_inv: meta.advice_column(),
+            is_path_item: meta.advice_column(),
+            path_odd: meta.advice_column(),
+            has_hi: meta.advice_column(),
+            has_lo: meta.advice_column(),
+            nibble_count: meta.advice_column(),
+            key_nibble_hi: meta.advice_column(),
+            key_nibble_lo: meta.advice_column(),
+            mismatch: meta.advice_column(),
+            mismatch_inv: meta.advice_column(),
+            child_inline: meta.advice_column(),
+            is_child_byte: meta.advice_column(),
+            child_rlc: meta.advice_column(),
+            is_field: meta.advice_column(),
+            sub_first: meta.advice_column(),
+            sub_rem: meta.advice_column(),
+            sub_index: meta.advice_column(),
+            is_target_field: meta.advice_column(),
+            target_field_inv: meta.advice_column(),
+            is_value_byte: meta.advice_column(),
+            value_acc: meta.advice_column(),
+            path_last: meta.advice_column(),
+            path_index: meta.advice_column(),
+            is_header: meta.advice_column(),
+            is_other_key: meta.advice_column(),
+            is_off_key: meta.advice_column(),
+            off_key_rlc: meta.advice_column(),
+            off_key_len: meta.advice_column(),
+            is_fresh: meta.advice_column(),
+        };
+        let proof_type_flags = [(); PROOF_TYPES.len()].map(|_| meta.advice_column());
+        let [address_hash, storage_key_hash, key_hash, root_chain] =
+            [(); 4].map(|_| meta.advice_column());
+        let state_roots = meta.instance_column();
+        let byte_table = ByteColumns::construct(|| meta.lookup_table_column());
+        let empty_account_table = [(); 3].map(|_| meta.lookup_table_column());
+
+        meta.enable_equality(root_chain);
+        meta.enable_equality(state_roots);
+
+        // The MptTable columns are address, storage_key, proof_type, new_root,
+        // old_root, new_value and old_value.
+        let mpt_columns = mpt_table.columns();
+        let (address, storage_key, proof_type) = (mpt_columns[0], mpt_columns[1], mpt_columns[2]);
+        let (new_root, old_root) = (mpt_columns[3], mpt_columns[4]);
+        let (new_value, old_value) = (mpt_columns[5], mpt_columns[6]);
+
+        meta.create_gate("mpt row", |meta| {
+            let mut cb = BaseConstraintBuilder::default();
+
+            let [is_key, is_node, s, key_first, path_last] =
+                [is_key, is_node, is_storage_trie, key.first, node.path_last]
+                    .map(|column| query(meta, column, 0));
+            let is_update = is_key.clone() + is_node.clone();
+            let flags = proof_type_flags.map(|column| query(meta, column, 0));
+
+            for flag in [is_key.clone(), is_node.clone(), s.clone()]
+                .into_iter()
+                .chain(once(query(meta, is_new, 0)))
+                .chain(flags.clone())
+            {
+                cb.require_boolean("row flags are boolean", flag);
+            }
+            cb.require_boolean("row is a key, a node or padding", is_update.clone());
+            cb.condition(meta.query_fixed(q_last, Rotation::cur()), |cb| {
+                cb.require_zero("last row is padding", is_update.clone());
+            });
+            cb.condition(not::expr(is_update.clone()), |cb| {
+                for column in mpt_columns.iter() {
+                    cb.require_zero("padding has no MptTable row", query(meta, *column, 0));
+                }
+            });
+            cb.condition(not::expr(is_node.clone()), |cb| {
+                cb.require_zero("only nodes end a path", path_last);
+            });
+
+            cb.condition(is_update, |cb| {
+                cb.require_equal(
+                    "update has a single proof type",
+                    sum::expr(flags.clone()),
+                    1.expr(),
+                );
+                cb.require_equal(
+                    "proof_type is given by its flags",
+                    query(meta, proof_type, 0),
+                    sum::expr(
+                        flags
+                            .iter()
+                            .zip(PROOF_TYPES)
+                            .map(|(flag, proof_type)| flag.clone() * proof_type.expr()),
+                    ),
+                );
+                cb.require_equal(
+                    "root_chain is the old root of the update",
+                    query(meta, root_chain, 0),
+                    query(meta, old_root, 0),
+                );
+                cb.require_equal(
+                    "key_hash is the key of the trie of the row",
+                    query(meta, key_hash, 0),
+                    select::expr(
+                        s.clone(),
+                        query(meta, storage_key_hash, 0),
+                        query(meta, address_hash, 0),
+                    ),
+                );
+            });
+
+            cb.condition(meta.query_fixed(q_first, Rotation::cur()), |cb| {
+                cb.require_zero("first row is not a node", is_node);
+                cb.require_zero(
+                    "first row is the first nibble of an account key, or padding",
+                    is_key * (not::expr(key_first) + s),
+                );
+            });
+
+            cb.gate(meta.query_fixed(q_enable, Rotation::cur()))
+        });
+
+        meta.create_gate("mpt row transition", |meta| {
+            let mut cb = BaseConstraintBuilder::default();
+
+            let [is_key_next, is_node_next, s_next, is_new_next] =
+                [is_key, is_node, is_storage_trie, is_new].map(|column| query(meta, column, 1));
+            let [is_key, is_node, s, is_new] =
+                [is_key, is_node, is_storage_trie, is_new].map(|column| query(meta, column, 0));
+            let [key_first_next, node_first_next, depth_next, is_inline_next, hash_rlc_next] =
+                [key.first, node.first, depth, node.is_inline, node.hash_rlc]
+                    .map(|column| query(meta, column, 1));
+            let [key_last, node_last, path_last] =
+                [key.last, node.last, node.path_last].map(|column| query(meta, column, 0));
+            let [path_index, path_index_next] =
+                [0, 1].map(|rotation| query(meta, node.path_index, rotation));
+            let [is_branch, is_extension, is_leaf, _] =
+                node.node_type.map(|column| query(meta, column, 0));
+            let is_storage = query(meta, proof_type_flags[4], 0);
+
+            cb.condition(not::expr(is_key.clone() + is_node.clone()), |cb| {
+                cb.require_zero(
+                    "padding is followed by padding",
+                    is_key_next.clone() + is_node_next.clone(),
+                );
+            });
+
+            cb.condition(is_key.clone() * not::expr(key_last.clone()), |cb| {
+                cb.require_equal("key continues", is_key_next.clone(), 1.expr());
+                cb.require_zero("key continues", key_first_next.clone());
+            });
+            let account_key_last = and::expr([is_key.clone(), key_last, not::expr(s.clone())]);
+            cb.condition(account_key_last.clone() * is_storage.clone(), |cb| {
+                for value in [is_key_next.clone(), key_first_next.clone(), s_next.clone()] {
+                    cb.require_equal(
+                        "account key is followed by the storage key",
+                        value,
+                        1.expr(),
+                    );
+                }
+            });
+
+            // The old path follows the keys, and the new path follows the old
+            // path.
+            for (path_start, is_new_path, root) in [
+                (
+                    is_key.clone() * query(meta, key.last, 0) - account_key_last * is_storage,
+                    false,
+                    old_root,
+                ),
+                (
+                    is_node.clone() * path_last.clone() * not::expr(is_new.clone()),
+                    true,
+                    new_root,
+                ),
+            ] {
+                cb.condition(path_start, |cb| {
+                    cb.require_equal("path starts with a node", is_node_next.clone(), 1.expr());
+                    cb.require_equal("path starts with a node", node_first_next.clone(), 1.expr());
+                    cb.require_zero("path starts in the account trie", s_next.clone());
+                    cb.require_equal(
+                        "old path is followed by the new path",
+                        is_new_next.clone(),
+                        is_new_path.expr(),
+                    );
+                    cb.require_zero("path starts at depth 0", depth_next.clone());
+                    cb.require_zero("path starts at the index 0", path_index_next.clone());
+                    cb.require_zero("root node is not inline", is_inline_next.clone());
+                    cb.require_equal(
+                        "root node hashes to the root",
+                        hash_rlc_next.clone(),
+                        query(meta, root, 0),
+                    );
+                });
+            }
+
+            cb.condition(is_node.clone() * not::expr(node_last.clone()), |cb| {
+                cb.require_equal("node continues", is_node_next.clone(), 1.expr());
+                cb.require_zero("node continues", node_first_next.clone());
+                cb.require_equal("node continues", s_next.clone(), s.clone());
+                cb.require_equal("node continues", is_new_next.clone(), is_new.clone());
+                cb.require_equal(
+                    "node continues",
+                    path_index_next.clone(),
+                    path_index.clone(),
+                );
+            });
+
+            cb.condition(
+                and::expr([is_node.clone(), node_last, not::expr(path_last.clone())]),
+                |cb| {
+                    cb.require_equal(
+                        "node is followed by its child",
+                        is_node_next.clone(),
+                        1.expr(),
+                    );
+                    cb.require_equal("node is followed by its child", node_first_next, 1.expr());
+                    cb.require_equal("node is followed by its child", is_new_next, is_new.clone());
+                    cb.require_equal(
+                        "child node is the next node of the path",
+                        path_index_next,
+                        path_index + 1.expr(),
+                    );
+                    cb.require_equal(
+                        "account leaf is followed by the storage trie",
+                        s_next.clone(),
+                        s + is_leaf.clone(),
+                    );
+                    cb.require_equal(
+                        "child node follows the key nibbles of the node",
+                        depth_next,
+                        not::expr(is_leaf) * query(meta, depth, 0)
+                            + is_branch
+                            + is_extension * query(meta, node.nibble_count, 0),
+                    );
+                    cb.require_equal(
+                        "child node hashes to the child of the node",
+                        hash_rlc_next,
+                        query(meta, node.child_rlc, 0),
+                    );
+                    cb.require_equal(
+                        "child node is inline if the child of the node is a list",
+                        is_inline_next,
+                        query(meta, node.child_inline, 0),
+                    );
+                },
+            );
+
+            let update_last = and::expr([is_node, path_last, is_new]);
+            cb.condition(update_last.clone(), |cb| {
+                cb.require_zero(
+                    "update is followed by the account key of the next update, or padding",
+                    is_node_next,
+                );
+                cb.require_zero(
+                    "update is followed by the account key of the next update, or padding",
+                    is_key_next * (not::expr(key_first_next) + s_next),
+                );
+            });
+            cb.condition(not::expr(update_last.clone()), |cb| {
+                for column in mpt_columns
+                    .iter()
+                    .copied()
+                    .chain([address_hash, storage_key_hash])
+                    .chain(proof_type_flags)
+                {
+                    cb.require_equal(
+                        "update values don't change within an update",
+                        query(meta, column, 1),
+                        query(meta, column, 0),
+                    );
+                }
+            });
+            cb.require_equal(
+                "root_chain is the new root of the previous update",
+                query(meta, root_chain, 1),
+                select::expr(
+                    update_last,
+                    query(meta, new_root, 0),
+                    query(meta, root_chain, 0),
+                ),
+            );
+
+            cb.gate(
+                meta.query_fixed(q_enable, Rotation::cur())
+                    * not::expr(meta.query_fixed(q_last, Rotation::cur())),
+            )
+        });
+
+        meta.create_gate("mpt key row", |meta| {
+            let mut cb = BaseConstraintBuilder::default();
+
+            let [first, last, is_odd, is_preimage] =
+                [key.first, key.last, key.is_odd, key.is_preimage]
+                    .map(|column| query(meta, column, 0));
+            for flag in [&first, &last, &is_odd, &is_preimage] {
+                cb.require_boolean("key flags are boolean", flag.clone());
+            }
+            let s = query(meta, is_storage_trie, 0);
+            let current_byte = query(meta, byte.byte, 0);
+            let preimage_byte = query(meta, key.preimage_byte, 0);
+            // Value of an accumulator in the previous row of the key, which is
+            // 0 in the first row
+            let prev = |meta: &mut VirtualCells<F>, column: Column<Advice>| {
+                not::expr(first.clone()) * query(meta, column, -1)
+            };
+
+            cb.condition(first.clone(), |cb| {
+                cb.require_zero("key starts at depth 0", query(meta, depth, 0));
+                cb.require_zero("key starts with a high nibble", is_odd.clone());
+                cb.require_equal(
+                    "key starts with its preimage",
+                    is_preimage.clone(),
+                    1.expr(),
+                );
+            });
+            cb.condition(not::expr(first.clone()), |cb| {
+                cb.require_equal(
+                    "depth increases by 1",
+                    query(meta, depth, 0),
+                    query(meta, depth, -1) + 1.expr(),
+                );
+                cb.require_equal(
+                    "high and low nibbles alternate",
+                    is_odd.clone() + query(meta, key.is_odd, -1),
+                    1.expr(),
+                );
+                cb.require_equal(
+                    "key is in a single trie",
+                    s.clone(),
+                    query(meta, is_storage_trie, -1),
+                );
+                cb.require_zero(
+                    "preimage is a prefix of the key rows",
+                    is_preimage.clone() * not::expr(query(meta, key.is_preimage, -1)),
+                );
+            });
+            cb.condition(last.clone(), |cb| {
+                cb.require_equal(
+                    "key has 64 nibbles",
+                    query(meta, depth, 0),
+                    (KEY_NIBBLES - 1).expr(),
+                );
+            });
+
+            cb.condition(is_odd.clone(), |cb| {
+                cb.require_equal(
+                    "high and low nibbles are of the same byte",
+                    current_byte.clone(),
+                    query(meta, byte.byte, -1),
+                );
+            });
+            cb.require_equal(
+                "nibble is the high or low nibble of the byte",
+                query(meta, nibble, 0),
+                select::expr(
+                    is_odd.clone(),
+                    query(meta, byte.nibble_lo, 0),
+                    query(meta, byte.nibble_hi, 0),
+                ),
+            );
+            let acc_prev = prev(meta, key.acc);
+            cb.require_equal(
+                "acc = acc_prev * randomness + byte for each byte",
+                query(meta, key.acc, 0),
+                select::expr(
+                    is_odd,
+                    acc_prev.clone() * randomness.clone() + current_byte,
+                    acc_prev,
+                ),
+            );
+
+            cb.require_zero(
+                "preimage_byte is 0 out of the preimage",
+                not::expr(is_preimage.clone()) * preimage_byte.clone(),
+            );
+            let [preimage_rlc_prev, preimage_int_prev, preimage_len_prev] =
+                [key.preimage_rlc, key.preimage_int, key.preimage_len]
+                    .map(|column| prev(meta, column));
+            cb.require_equal(
+                "preimage_rlc = preimage_rlc_prev * randomness + preimage_byte",
+                query(meta, key.preimage_rlc, 0),
+                select::expr(
+                    is_preimage.clone(),
+                    preimage_rlc_prev.clone() * randomness.clone() + preimage_byte.clone(),
+                    preimage_rlc_prev,
+                ),
+            );
+            cb.require_equal(
+                "preimage_int = preimage_int_prev * 256 + preimage_byte",
+                query(meta, key.preimage_int, 0),
+                select::expr(
+                    is_preimage.clone(),
+                    preimage_int_prev.clone() * 256.expr() + preimage_byte,
+                    preimage_int_prev,
+                ),
+            );
+            cb.require_equal(
+                "preimage_len = preimage_len_prev + is_preimage",
+                query(meta, key.preimage_len, 0),
+                preimage_len_prev + is_preimage,
+            );
+
+            cb.condition(last, |cb| {
+                cb.require_equal(
+                    "key is the hash of the trie of the row",
+                    query(meta, key.acc, 0),
+                    query(meta, key_hash, 0),
+                );
+                let preimage_len = query(meta, key.preimage_len, 0);
+                cb.require_zero(
+                    "account key is the hash of the address",
+                    not::expr(s.clone()) * (preimage_len.clone() - 20.expr()),
+                );
+                cb.require_zero(
+                    "account key is the hash of the address",
+                    not::expr(s.clone())
+                        * (query(meta, key.preimage_int, 0) - query(meta, address, 0)),
+                );
+                cb.require_zero(
+                    "storage key is the hash of the storage key",
+                    s.clone() * (preimage_len - 32.expr()),
+                );
+                cb.require_zero(
+                    "storage key is the hash of the storage key",
+                    s * (query(meta, key.preimage_rlc, 0) - query(meta, storage_key, 0)),
+                );
+            });
+
+            cb.gate(meta.query_fixed(q_enable, Rotation::cur()) * query(meta, is_key, 0))
+        });
+
+        meta.create_gate("mpt node row", |meta| {
+            let mut cb = BaseConstraintBuilder::default();
+
+            let current_byte = byte.map(|column| query(meta, column, 0));
+            let [k_single, k_string, k_long_string, k_list, k_long_list, k_long_list2] =
+                current_byte.kinds.clone();
+            let rlp_len = current_byte.rlp_len.clone();
+            let [byte_next, byte_next_2, byte_next_3] =
+                [1, 2, 3].map(|rotation| query(meta, byte.byte, rotation));
+            let [first, last, item_first, mismatch, is_field, sub_first] = [
+                node.first,
+                node.last,
+                node.item_first,
+                node.mismatch,
+                node.is_field,
+                node.sub_first,
+            ]
+            .map(|column| query(meta, column, 0));
+            let [item_first_prev, mismatch_prev, is_field_prev] =
+                [node.item_first, node.mismatch, node.is_field]
+                    .map(|column| query(meta, column, -1));
+            let node_type = node.node_type.map(|column| query(meta, column, 0));
+            let [is_branch, is_extension, is_leaf, is_empty] = node_type.clone();
+            let [is_key_item, is_path_item, path_odd, has_hi, has_lo] = [
+                node.is_key_item,
+                node.is_path_item,
+                node.path_odd,
+                node.has_hi,
+                node.has_lo,
+            ]
+            .map(|column| query(meta, column, 0));
+            let [item_index, item_rem, sub_rem, sub_index, nibble_count, node_depth] = [
+                node.item_index,
+                node.item_rem,
+                node.sub_rem,
+                node.sub_index,
+                node.nibble_count,
+                depth,
+            ]
+            .map(|column| query(meta, column, 0));
+            let s = query(meta, is_storage_trie, 0);
+            let [is_nonce, is_balance, is_code_hash, is_not_exist, is_storage] =
+                proof_type_flags.map(|column| query(meta, column, 0));
+            // Value of an accumulator in the previous row of the node, which
+            // is 0 in the first row
+            let prev = |meta: &mut VirtualCells<F>, column: Column<Advice>| {
+                not::expr(first.clone()) * query(meta, column, -1)
+            };
+
+            let [nibble_hi, nibble_lo] = [
+                current_byte.nibble_hi.clone(),
+                current_byte.nibble_lo.clone(),
+            ];
+            let [key_nibble_hi, key_nibble_lo] =
+                [node.key_nibble_hi, node.key_nibble_lo].map(|column| query(meta, column, 0));
+            // Difference of the nibbles of the path in the row to the nibbles of
+            // the key, which is 0 only if both nibbles match
+            let nibble_diff = has_hi.clone() * (nibble_hi.clone() - key_nibble_hi.clone())
+                + 31.expr() * has_lo.clone() * (nibble_lo.clone() - key_nibble_lo.clone());
+
+            for column in [node.first, node.last, node.item_first, node.is_inline]
+                .into_iter()
+                .chain(node.node_type)
+                .chain([
+                    node.is_key_item,
+                    node.is_path_item,
+                    node.path_odd,
+                    node.has_hi,
+                    node.has_lo,
+                    node.mismatch,
+                    node.child_inline,
+                    node.is_child_byte,
+                    node.is_field,
+                    node.sub_first,
+                    node.is_target_field,
+                    node.is_value_byte,
+                    node.path_last,
+                    node.is_header,
+                    node.is_other_key,
+                    node.is_off_key,
+                    node.is_fresh,
+                ])
+            {
+                cb.require_boolean("node flags are boolean", query(meta, column, 0));
+            }
+            cb.require_equal(
+                "node has a single type",
+                sum::expr(node_type.clone()),
+                1.expr(),
+            );
+
+            // Keccak input of the node
+            let [rlc_prev, len_prev] = [node.rlc, node.len].map(|column| prev(meta, column));
+            cb.require_equal(
+                "rlc = rlc_prev * randomness + byte",
+                query(meta, node.rlc, 0),
+                rlc_prev * randomness.clone() + current_byte.byte.clone(),
+            );
+            cb.require_equal(
+                "len = len_prev + 1",
+                query(meta, node.len, 0),
+                len_prev + 1.expr(),
+            );
+
+            // List header and items of the node
+            cb.condition(first.clone(), |cb| {
+                cb.require_equal(
+                    "node starts with a list header, or is the empty trie",
+                    k_list.clone() + k_long_list.clone() + k_long_list2.clone() + is_empty.clone(),
+                    1.expr(),
+                );
+                cb.require_zero(
+                    "empty trie is 0x80",
+                    is_empty.clone() * (current_byte.byte.clone() - 0x80.expr()),
+                );
+                cb.require_equal(
+                    "list_rem is the length of the list",
+                    query(meta, node.list_rem, 0),
+                    k_list.clone() * rlp_len.clone()
+                        + k_long_list.clone() * (1.expr() + byte_next.clone())
+                        + k_long_list2.clone()
+                            * (2.expr() + byte_next.clone() * 256.expr() + byte_next_2.clone()),
+                );
+                cb.require_equal("list header is an item", item_first.clone(), 1.expr());
+                cb.require_zero("list header is the item 0", item_index.clone());
+                cb.require_equal(
+                    "item_rem of the list header",
+                    item_rem.clone(),
+                    k_long_list.clone() + k_long_list2.clone() * 2.expr(),
+                );
+                cb.require_zero(
+                    "extensions and leaves mismatch the key in their path",
+                    not::expr(is_branch.clone()) * (mismatch.clone() - is_empty.clone()),
+                );
+                cb.require_zero(
+                    "only branches and extensions have a child node",
+                    (is_leaf.clone() + is_empty.clone()) * query(meta, node.child_inline, 0),
+                );
+            });
+            cb.condition(not::expr(first.clone()), |cb| {
+                for column in [depth, nibble, node.hash_rlc, node.is_inline]
+                    .into_iter()
+                    .chain(node.node_type)
+                    .chain([
+                        node.path_odd,
+                        node.child_inline,
+                        node.path_index,
+                        node.is_other_key,
+                        node.is_fresh,
+                    ])
+                {
+                    cb.require_equal(
+                        "node values don't change within a node",
+                        query(meta, column, 0),
+                        query(meta, column, -1),
+                    );
+                }
+                cb.require_equal(
+                    "list_rem decreases by 1",
+                    query(meta, node.list_rem, 0),
+                    query(meta, node.list_rem, -1) - 1.expr(),
+                );
+                cb.require_zero(
+                    "node mismatches the key after the first mismatch",
+                    mismatch_prev.clone() * not::expr(mismatch.clone()),
+                );
+                cb.require_zero(
+                    "node mismatches the key where a nibble differs",
+                    (mismatch.clone() - mismatch_prev.clone())
+                        * (nibble_diff.clone() * query(meta, node.mismatch_inv, 0) - 1.expr()),
+                );
+            });
+            cb.condition(not::expr(first.clone()) * item_first.clone(), |cb| {
+                cb.require_zero(
+                    "item starts after the previous item",
+                    query(meta, node.item_rem, -1),
+                );
+                cb.require_equal(
+                    "item_index increases by 1",
+                    item_index.clone(),
+                    query(meta, node.item_index, -1) + 1.expr(),
+                );
+                cb.require_equal(
+                    "item is a string or a short list",
+                    k_single.clone() + k_string.clone() + k_long_string.clone() + k_list.clone(),
+                    1.expr(),
+                );
+                cb.require_equal(
+                    "item_rem is the length of the item",
+                    item_rem.clone(),
+                    (k_string.clone() + k_list.clone()) * rlp_len.clone()
+                        + k_long_string * (1.expr() + byte_next.clone()),
+                );
+            });
+            cb.condition(not::expr(item_first.clone()), |cb| {
+                cb.require_equal(
+                    "item_rem decreases by 1",
+                    item_rem.clone(),
+                    query(meta, node.item_rem, -1) - 1.expr(),
+                );
+                cb.require_equal(
+                    "item continues",
+                    item_index.clone(),
+                    query(meta, node.item_index, -1),
+                );
+                cb.require_zero(
+                    "fields are a suffix of the leaf value",
+                    is_field_prev.clone() * not::expr(is_field.clone()),
+                );
+            });
+
+            // Item of the key, and path of extensions and leaves
+            let target_item = select::expr(
+                is_branch.clone(),
+                query(meta, nibble, 0) + 1.expr(),
+                2.expr(),
+            );
+            let key_item_diff = item_index.clone() - target_item;
+            cb.require_equal(
+                "is_key_item is whether the item is the child or value of the key",
+                is_key_item.clone(),
+                1.expr() - key_item_diff.clone() * query(meta, node.key_item_inv, 0),
+            );
+            cb.require_zero(
+                "is_key_item is whether the item is the child or value of the key",
+                key_item_diff * is_key_item.clone(),
+            );
+            cb.require_equal(
+                "is_path_item is whether the item is the path of an extension or leaf",
+                is_path_item.clone(),
+                (is_extension.clone() + is_leaf.clone())
+                    * item_index.clone()
+                    * (2.expr() - item_index.clone()),
+            );
+
+            cb.condition(is_path_item.clone() * item_first.clone(), |cb| {
+                cb.require_equal(
+                    "path is a string",
+                    k_single.clone() + k_string.clone(),
+                    1.expr(),
+                );
+                cb.require_zero(
+                    "path is not empty",
+                    k_string.clone() * (last.clone() + query(meta, node.item_first, 1)),
+                );
+            });
+            // The first byte of the path is its hex prefix flag
+            let is_flag = is_path_item.clone()
+                * select::expr(
+                    item_first.clone(),
+                    k_single.clone(),
+                    item_first_prev.clone(),
+                );
+            cb.condition(is_flag.clone(), |cb| {
+                cb.require_equal(
+                    "hex prefix flag is the node type and the parity of the path",
+                    nibble_hi.clone(),
+                    2.expr() * is_leaf.clone() + path_odd.clone(),
+                );
+                cb.require_zero(
+                    "hex prefix flag of an even path has no nibble",
+                    not::expr(path_odd.clone()) * nibble_lo.clone(),
+                );
+            });
+            cb.require_equal(
+                "has_hi is whether the byte follows the hex prefix flag",
+                has_hi.clone(),
+                and::expr([
+                    is_path_item.clone(),
+                    not::expr(item_first.clone()),
+                    not::expr(item_first_prev.clone()),
+                ]),
+            );
+            cb.require_equal(
+                "has_lo is whether the byte has a low nibble in the path",
+                has_lo.clone(),
+                has_hi.clone() + is_flag * path_odd,
+            );
+            cb.require_equal(
+                "nibble_count = nibble_count_prev + has_hi + has_lo",
+                nibble_count.clone(),
+                prev(meta, node.nibble_count) + has_hi.clone() + has_lo.clone(),
+            );
+            cb.condition(not::expr(mismatch.clone()), |cb| {
+                cb.require_zero(
+                    "path nibbles match the key up to the first mismatch",
+                    has_hi * (nibble_hi - key_nibble_hi),
+                );
+                cb.require_zero(
+                    "path nibbles match the key up to the first mismatch",
+                    has_lo * (nibble_lo - key_nibble_lo),
+                );
+            });
+
+            // Child of branches and extensions
+            let key_item_first = is_key_item.clone() * item_first.clone();
+            cb.condition(
+                key_item_first.clone() * (is_branch.clone() + is_extension.clone()),
+                |cb| {
+                    cb.require_equal(
+                        "child is a hash or an inline node",
+                        k_string.clone() + k_list.clone(),
+                        1.expr(),
+                    );
+                    cb.require_equal(
+                        "child_inline is whether the child is a list",
+                        query(meta, node.child_inline, 0),
+                        k_list.clone(),
+                    );
+                },
+            );
+            cb.condition(key_item_first.clone() * is_branch.clone(), |cb| {
+                cb.require_zero(
+                    "child of a branch is a hash, or empty where the key is not in the trie",
+                    k_string.clone() * (rlp_len.clone() - 32.expr() + 32.expr() * mismatch.clone()),
+                );
+                cb.require_zero(
+                    "child of a branch is a hash, or empty where the key is not in the trie",
+                    32.expr() * mismatch.clone() - k_string.clone() * (32.expr() - rlp_len.clone()),
+                );
+            });
+            cb.condition(key_item_first.clone() * is_extension.clone(), |cb| {
+                cb.require_zero(
+                    "child of an extension is a hash",
+                    k_string.clone() * (rlp_len.clone() - 32.expr()),
+                );
+            });
+
+            // Fields of the leaf value
+            cb.require_zero(
+                "fields are in the leaf value",
+                is_field.clone() * not::expr(is_leaf.clone() * is_key_item.clone()),
+            );
+            let value_item_first = is_leaf.clone() * key_item_first;
+            cb.condition(value_item_first.clone() * not::expr(s.clone()), |cb| {
+                cb.require_equal(
+                    "account value is a long string",
+                    current_byte.byte.clone(),
+                    0xb8.expr(),
+                );
+                cb.require_equal("account value is a long list", byte_next_2, 0xf8.expr());
+                cb.require_equal(
+                    "account list has the length of the account value",
+                    byte_next_3,
+                    query(meta, node.item_rem, 3),
+                );
+                for rotation in 0..4 {
+                    cb.require_zero(
+                        "account fields start after the headers",
+                        query(meta, node.is_field, rotation),
+                    );
+                }
+                cb.require_equal(
+                    "account fields start after the headers",
+                    query(meta, node.is_field, 4),
+                    1.expr(),
+                );
+            });
+            cb.condition(value_item_first * s.clone(), |cb| {
+                cb.require_equal(
+                    "storage value is a string",
+                    k_single.clone() + k_string.clone(),
+                    1.expr(),
+                );
+                cb.require_equal(
+                    "storage value is a single byte, or follows its string header",
+                    is_field.clone(),
+                    k_single.clone(),
+                );
+            });
+            cb.require_zero(
+                "storage value is a single byte, or follows its string header",
+                and::expr([
+                    s.clone(),
+                    is_field.clone(),
+                    not::expr(item_first.clone()),
+                    not::expr(is_field_prev.clone()),
+                    not::expr(item_first_prev),
+                ]),
+            );
+
+            let field_start = is_field.clone()
+                * select::expr(
+                    item_first.clone(),
+                    1.expr(),
+                    not::expr(is_field_prev.clone()),
+                );
+            cb.condition(field_start, |cb| {
+                cb.require_equal("fields start with a field", sub_first.clone(), 1.expr());
+                cb.require_equal("fields start with the field 1", sub_index.clone(), 1.expr());
+            });
+            let field_continues = and::expr([
+                is_field.clone(),
+                not::expr(item_first.clone()),
+                is_field_prev,
+            ]);
+            cb.condition(field_continues, |cb| {
+                cb.require_zero(
+                    "field starts after the previous field",
+                    sub_first.clone() * query(meta, node.sub_rem, -1),
+                );
+                cb.require_zero(
+                    "sub_rem decreases by 1",
+                    not::expr(sub_first.clone())
+                        * (sub_rem.clone() - query(meta, node.sub_rem, -1) + 1.expr()),
+                );
+                cb.require_equal(
+                    "sub_index increases by 1 at each field",
+                    sub_index.clone(),
+                    query(meta, node.sub_index, -1) + sub_first.clone(),
+                );
+            });
+            cb.condition(is_field.clone() * sub_first.clone(), |cb| {
+                cb.require_equal(
+                    "field is a string",
+                    k_single.clone() + k_string.clone(),
+                    1.expr(),
+                );
+                cb.require_equal(
+                    "sub_rem is the length of the field",
+                    sub_rem.clone(),
+                    k_string.clone() * rlp_len.clone(),
+                );
+            });
+
+            // Field of the proof type, which is the value of the key or the
+            // storage root of the account of a storage update
+            let is_target_field = query(meta, node.is_target_field, 0);
+            let target_field = is_nonce.clone()
+                + 2.expr() * is_balance
+                + 4.expr() * is_code_hash
+                + is_storage.clone() * (3.expr() - 2.expr() * s.clone());
+            let target_field_diff = sub_index.clone() - target_field;
+            cb.require_equal(
+                "is_target_field is whether the field is the one of the proof type",
+                is_target_field.clone(),
+                is_field.clone()
+                    * (1.expr()
+                        - target_field_diff.clone() * query(meta, node.target_field_inv, 0)),
+            );
+            cb.require_zero(
+                "is_target_field is whether the field is the one of the proof type",
+                target_field_diff * is_target_field.clone(),
+            );
+            let is_storage_root = and::expr([
+                is_storage.clone(),
+                not::expr(s.clone()),
+                is_target_field.clone(),
+            ]);
+            cb.condition(is_storage_root.clone() * sub_first.clone(), |cb| {
+                cb.require_equal("storage root is a hash", k_string.clone(), 1.expr());
+                cb.require_equal("storage root is a hash", rlp_len.clone(), 32.expr());
+            });
+            // Whether the node is in the trie of the value of the update
+            let is_final = not::expr(is_storage * not::expr(s.clone()));
+            cb.require_equal(
+                "is_value_byte is whether the byte is part of the value",
+                query(meta, node.is_value_byte, 0),
+                is_target_field
+                    * is_final.clone()
+                    * not::expr(sub_first.clone() * not::expr(k_single)),
+            );
+            cb.require_equal(
+                "is_child_byte is whether the byte is part of the child",
+                query(meta, node.is_child_byte, 0),
+                (is_branch.clone() + is_extension.clone())
+                    * is_key_item
+                    * not::expr(item_first * not::expr(k_list))
+                    + is_storage_root * not::expr(sub_first),
+            );
+            let child_rlc_prev = prev(meta, node.child_rlc);
+            cb.require_equal(
+                "child_rlc = child_rlc_prev * randomness + byte for each child byte",
+                query(meta, node.child_rlc, 0),
+                select::expr(
+                    query(meta, node.is_child_byte, 0),
+                    child_rlc_prev.clone() * randomness.clone() + current_byte.byte.clone(),
+                    child_rlc_prev,
+                ),
+            );
+            // Nonces are integers, and the other values are RLCs of words
+            let value_acc_prev = prev(meta, node.value_acc);
+            cb.require_equal(
+                "value_acc = value_acc_prev * base + byte for each value byte",
+                query(meta, node.value_acc, 0),
+                select::expr(
+                    query(meta, node.is_value_byte, 0),
+                    value_acc_prev.clone() * select::expr(is_nonce, 256.expr(), randomness.clone())
+                        + current_byte.byte,
+                    value_acc_prev,
+                ),
+            );
+
+            // Bytes off the key, which are the siblings of the child of a
+            // branch, the fields of an account leaf besides the one of the
+            // proof type, and the items of an extension or leaf of another key
+            let [is_header, is_other_key, is_off_key, is_fresh] = [
+                node.is_header,
+                node.is_other_key,
+                node.is_off_key,
+                node.is_fresh,
+            ]
+            .map(|column| query(meta, column, 0));
+            cb.require_equal(
+                "is_header is whether the byte is part of the list header",
+                is_header.clone(),
+                select::expr(
+                    first.clone(),
+                    1.expr(),
+                    query(meta, node.is_header, -1) * not::expr(query(meta, node.item_first, 0)),
+                ),
+            );
+            let is_other_field =
+                query(meta, node.is_field, 0) - query(meta, node.is_target_field, 0);
+            cb.require_equal(
+                "is_off_key is whether the byte is off the key",
+                is_off_key.clone(),
+                not::expr(is_header)
+                    * (is_branch.clone() * not::expr(query(meta, node.is_key_item, 0))
+                        + (is_extension.clone() + is_leaf.clone()) * is_other_key.clone())
+                    + is_leaf.clone() * not::expr(is_other_key.clone()) * is_other_field,
+            );
+            let [off_key_rlc_prev, off_key_len_prev] =
+                [node.off_key_rlc, node.off_key_len].map(|column| prev(meta, column));
+            cb.require_equal(
+                "off_key_rlc = off_key_rlc_prev * randomness + byte for each byte off the key",
+                query(meta, node.off_key_rlc, 0),
+                select::expr(
+                    is_off_key.clone(),
+                    off_key_rlc_prev.clone() * randomness.clone() + query(meta, byte.byte, 0),
+                    off_key_rlc_prev,
+                ),
+            );
+            cb.require_equal(
+                "off_key_len = off_key_len_prev + is_off_key",
+                query(meta, node.off_key_len, 0),
+                off_key_len_prev + is_off_key,
+            );
+            cb.condition(last.clone(), |cb| {
+                cb.require_equal(
+                    "is_other_key is whether an extension or leaf mismatches the key",
+                    is_other_key,
+                    (is_extension.clone() + is_leaf.clone()) * mismatch.clone(),
+                );
+                cb.require_zero(
+                    "only the leaf of the key is in a single path",
+                    is_fresh * (not::expr(is_leaf.clone()) + mismatch.clone()),
+                );
+            });
+
+            // End of the node, and of the path
+            let path_last = query(meta, node.path_last, 0);
+            cb.condition(last.clone(), |cb| {
+                cb.require_zero("node ends with its list", query(meta, node.list_rem, 0));
+                cb.require_zero("node ends with its last item", item_rem);
+                cb.require_equal(
+                    "branches have 17 items, and extensions and leaves 2",
+                    item_index,
+                    17.expr() * is_branch + 2.expr() * (is_extension + is_leaf.clone()),
+                );
+                cb.require_zero(
+                    "leaf is at the end of the key",
+                    is_leaf.clone()
+                        * not::expr(mismatch.clone())
+                        * (node_depth + nibble_count - KEY_NIBBLES.expr()),
+                );
+                cb.require_zero(
+                    "leaf value has fields",
+                    is_leaf.clone() * not::expr(is_field.clone()),
+                );
+                cb.require_zero(
+                    "fields end with their last field",
+                    is_field.clone() * sub_rem,
+                );
+                cb.require_zero(
+                    "accounts have 4 fields, and storage values 1",
+                    is_field * (sub_index - 4.expr() + 3.expr() * s),
+                );
+                cb.require_zero(
+                    "inline node is its own reference",
+                    query(meta, node.is_inline, 0)
+                        * (query(meta, node.rlc, 0) - query(meta, node.hash_rlc, 0)),
+                );
+                cb.require_equal(
+                    "path ends at the leaf of the key, or where the key is not in the trie",
+                    path_last.clone(),
+                    mismatch.clone() + not::expr(mismatch.clone()) * is_leaf * is_final,
+                );
+            });
+            cb.condition(not::expr(last), |cb| {
+                cb.require_zero("path ends at the last byte of a node", path_last.clone());
+            });
+            let value = select::expr(
+                query(meta, is_new, 0),
+                query(meta, new_value, 0),
+                query(meta, old_value, 0),
+            );
+            cb.condition(path_last.clone() * not::expr(mismatch.clone()), |cb| {
+                cb.require_equal(
+                    "value of the path is the value of the key",
+                    query(meta, node.value_acc, 0),
+                    value.clone(),
+                );
+            });
+            cb.condition(path_last.clone() * mismatch.clone(), |cb| {
+                cb.require_zero("value of a key not in the trie is 0", value);
+            });
+            cb.condition(path_last * is_not_exist, |cb| {
+                cb.require_equal("account is not in the trie", mismatch, 1.expr());
+            });
+
+            cb.gate(meta.query_fixed(q_enable, Rotation::cur()) * query(meta, is_node, 0))
+        });
+
+        meta.lookup("mpt byte table", |meta| {
+            let q_enable = meta.query_fixed(q_enable, Rotation::cur());
+            byte.columns()
+                .into_iter()
+                .zip(byte_table.columns())
+                .map(|(column, table)| (q_enable.clone() * query(meta, column, 0), table))
+                .collect()
+        });
+        meta.lookup("mpt preimage byte range check", |meta| {
+            let q_enable = meta.query_fixed(q_enable, Rotation::cur());
+            vec![(
+                q_enable * query(meta, key.preimage_byte, 0),
+                byte_table.byte,
+            )]
+        });
+
+        // The nibbles of the path of extensions and leaves, and the nibble of
+        // the child of branches, are the nibbles of the key at their depth.
+        meta.lookup_any("mpt path nibble hi", |meta| {
+            let has_hi = query(meta, node.has_hi, 0);
+            let nibble_depth = query(meta, depth, 0)
+                + not::expr(query(meta, node.first, 0)) * query(meta, node.nibble_count, -1);
+            let input = [
+                query(meta, key_hash, 0),
+                nibble_depth,
+                query(meta, node.key_nibble_hi, 0),
+            ];
+            key_nibble_lookup(meta, [is_key, key_hash, depth, nibble], has_hi, input)
+        });
+        meta.lookup_any("mpt path nibble lo", |meta| {
+            let has_lo = query(meta, node.has_lo, 0);
+            let nibble_depth = query(meta, depth, 0)
+                + not::expr(query(meta, node.first, 0)) * query(meta, node.nibble_count, -1)
+                + query(meta, node.has_hi, 0);
+            let input = [
+                query(meta, key_hash, 0),
+                nibble_depth,
+                query(meta, node.key_nibble_lo, 0),
+            ];
+            key_nibble_lookup(meta, [is_key, key_hash, depth, nibble], has_lo, input)
+        });
+        meta.lookup_any("mpt branch nibble", |meta| {
+            let condition = query(meta, node.first, 0) * query(meta, node.node_type[0], 0);
+            let input = [key_hash, depth, nibble].map(|column| query(meta, column, 0));
+            key_nibble_lookup(meta, [is_key, key_hash, depth, nibble], condition, input)
+        });
+
+        meta.lookup_any("mpt key keccak", |meta| {
+            let condition = query(meta, is_key, 0) * query(meta, key.last, 0);
+            let input = [
+                1.expr(),
+                query(meta, key.preimage_rlc, 0),
+                query(meta, key.preimage_len, 0),
+                query(meta, key_hash, 0),
+            ];
+            keccak_lookup(meta, &keccak_table, condition, input)
+        });
+        meta.lookup_any("mpt node keccak", |meta| {
+            let condition = and::expr([
+                query(meta, is_node, 0),
+                query(meta, node.last, 0),
+                not::expr(query(meta, node.is_inline, 0)),
+            ]);
+            let input = [
+                1.expr(),
+                query(meta, node.rlc, 0),
+                query(meta, node.len, 0),
+                query(meta, node.hash_rlc, 0),
+            ];
+            keccak_lookup(meta, &keccak_table, condition, input)
+        });
+
+        // Each node of a path is in the other path of the update, with the
+        // same bytes off the key, except the empty trie and the leaf of the key
+        // when it's inserted or deleted.
+        for (name, is_new_path) in [("mpt old path node", false), ("mpt new path node", true)] {
+            meta.lookup_any(name, |meta| {
+                let is_new = query(meta, is_new, 0);
+                let (in_path, in_other_path) = if is_new_path {
+                    (is_new.clone(), not::expr(is_new))
+                } else {
+                    (not::expr(is_new.clone()), is_new)
+                };
+                let node_last = query(meta, is_node, 0) * query(meta, node.last, 0);
+                let condition = and::expr([
+                    node_last.clone(),
+                    in_path,
+                    not::expr(query(meta, node.node_type[3], 0)),
+                    not::expr(query(meta, node.is_fresh, 0)),
+                ]);
+                let node_type = sum::expr(
+                    node.node_type
+                        .iter()
+                        .enumerate()
+                        .map(|(index, column)| index.expr() * query(meta, *column, 0)),
+                );
+                [key_hash, old_root, new_root, node.path_index, depth]
+                    .map(|column| query(meta, column, 0))
+                    .into_iter()
+                    .chain(once(node_type))
+                    .chain(
+                        [node.off_key_rlc, node.off_key_len].map(|column| query(meta, column, 0)),
+                    )
+                    .map(|value| {
+                        (
+                            condition.clone() * value.clone(),
+                            node_last.clone() * in_other_path.clone() * value,
+                        )
+                    })
+                    .collect()
+            });
+        }
+        // The other fields of the leaf of an account which is inserted or
+        // deleted are those of the empty account.
+        meta.lookup("mpt empty account field", |meta| {
+            let condition = and::expr([
+                query(meta, is_node, 0),
+                query(meta, node.is_fresh, 0),
+                query(meta, node.is_off_key, 0),
+            ]);
+            [node.sub_index, node.sub_rem, byte.byte]
+                .into_iter()
+                .zip(empty_account_table)
+                .map(|(column, table)| (condition.clone() * query(meta, column, 0), table))
+                .collect()
+        });
+
+        Self {
+            q_enable,
+            q_first,
+            q_last,
+            is_key,
+            is_node,
+            is_storage_trie,
+            is_new,
+            byte,
+            depth,
+            nibble,
+            key,
+            node,
+            proof_type_flags,
+            address_hash,
+            storage_key_hash,
+            key_hash,
+            root_chain,
+            state_roots,
+            byte_table,
+            empty_account_table,
+            mpt_table,
+            keccak_table,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Load the byte table, with the nibbles and RLP prefix of each byte, and
+    /// the table of the fields of the empty account.
+    pub fn load(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_table(
+            || "mpt byte table",
+            |mut table| {
+                // The all-zero row is looked up by the padding rows
+                let rows =
+                    once(ByteColumns::default()).chain((0..=255).map(ByteColumns::from_byte));
+                for (offset, row) in rows.enumerate() {
+                    for (column, value) in self.byte_table.columns().into_iter().zip(row.columns())
+                    {
+                        table.assign_cell(
+                            || format!("mpt byte table row {}", offset),
+                            column,
+                            offset,
+                            || Value::known(F::from(value)),
+                        )?;
+                    }
+                }
+                Ok(())
+            },
+        )?;
+        layouter.assign_table(
+            || "mpt empty account table",
+            |mut table| {
+                // The all-zero row is looked up by the other rows
+                let rows = once([0; 3]).chain(empty_account_fields());
+                for (offset, row) in rows.enumerate() {
+                    for (column, value) in self.empty_account_table.into_iter().zip(row) {
+                        table.assign_cell(
+                            || format!("mpt empty account table row {}", offset),
+                            column,
+                            offset,
+                            || Value::known(F::from(value)),
+                        )?;
+                    }
+                }
+                Ok(())
+            },
+        )
+    }
+
+    /// Make the assignments to the MptCircuit, together with the MptTable.
+    /// The updates are given in the order they are applied to the state trie,
+    /// and are followed by padding up to `n_rows`.
+    pub fn assign(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        updates: &[MptUpdateWitness],
+        n_rows: usize,
+        randomness: F,
+    ) -> Result<(), Error> {
+        let rows = updates
+            .iter()
+            .map(|witness| update_rows(witness, randomness))
+            .collect::<Result<Vec<_>, _>>()?;
+        let num_rows = rows.iter().map(Vec::len).sum::<usize>();
+        if num_rows >= n_rows {
+            error!(
+                "mpt updates take {} rows, which is not below n_rows = {}",
+                num_rows, n_rows
+            );
+            return Err(Error::Synthesis);
+        }
+
+        let root_cells = layouter.assign_region(
+            || "mpt circuit",
+            |mut region| {
+                for offset in 0..n_rows {
+                    for (name, column, value) in [
+                        ("q_enable", self.q_enable, true),
+                        ("q_first", self.q_first, offset == 0),
+                        ("q_last", self.q_last, offset + 1 == n_rows),
+                    ] {
+                        region.assign_fixed(
+                            || name,
+                            column,
+                            offset,
+                            || Value::known(F::from(value as u64)),
+                        )?;
+                    }
+                }
+
+                let mut root_cells = Vec::with_capacity(n_rows);
+                let mut root_chain = F::zero();
+                for (witness, rows) in updates.iter().zip(rows.iter()) {
+                    let update = &witness.update;
+                    let table_row = update.table_assignment(randomness);
+                    let flags = PROOF_TYPES.map(|proof_type| proof_type == update.proof_type());
+                    let address_hash = keccak_rlc(&key_preimage(update, false), randomness);
+                    let storage_key_hash = match update.storage_key() {
+                        Some(_) => keccak_rlc(&key_preimage(update, true), randomness),
+                        None => F::zero(),
+                    };
+                    let (new_root, old_root) = update.root_assignments(randomness);
+                    for row in rows {
+                        let offset = root_cells.len();
+                        self.assign_row(&mut region, offset, row)?;
+                        self.mpt_table.assign(&mut region, offset, &table_row)?;
+                        let key_hash = if row.is_storage_trie {
+                            storage_key_hash
+                        } else {
+                            address_hash
+                        };
+                        root_cells.push(self.assign_update(
+                            &mut region,
+                            offset,
+                            flags,
+                            [address_hash, storage_key_hash, key_hash, old_root],
+                        )?);
+                    }
+                    root_chain = new_root;
+                }
+
+                for offset in root_cells.len()..n_rows {
+                    self.assign_row(&mut region, offset, &MptRow::padding())?;
+                    for column in self.mpt_table.columns() {
+                        region.assign_advice(
+                            || "mpt table padding",
+                            column,
+                            offset,
+                            || Value::known(F::zero()),
+                        )?;
+                    }
+                    root_cells.push(self.assign_update(
+                        &mut region,
+                        offset,
+                        [false; PROOF_TYPES.len()],
+                        [F::zero(), F::zero(), F::zero(), root_chain],
+                    )?);
+                }
+                Ok(root_cells)
+            },
+        )?;
+
+        // The root chain starts at the old state root, and ends at the new
+        // state root in the padding rows.
+        if let (Some(first), Some(last)) = (root_cells.first(), root_cells.last()) {
+            layouter.constrain_instance(first.cell(), self.state_roots, 0)?;
+            layouter.constrain_instance(last.cell(), self.state_roots, 1)?;
+        }
+        Ok(())
+    }
+
+    /// Assign the values of a key, node or padding row.
+    fn assign_row(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        row: &MptRow<F>,
+    ) -> Result<(), Error> {
+        let byte = if row.key.is_some() || row.node.is_some() {
+            ByteColumns::from_byte(row.byte)
+        } else {
+            ByteColumns::default()
+        };
+        let key_values = row
+            .key
+            .as_ref()
+            .map_or_else(|| vec![F::zero(); self.key.columns().len()], KeyRow::values);
+        let node_values = row.node.as_ref().map_or_else(
+            || vec![F::zero(); self.node.columns().len()],
+            NodeRow::values,
+        );
+
+        let columns = [
+            self.is_key,
+            self.is_node,
+            self.is_storage_trie,
+            self.is_new,
+            self.depth,
+            self.nibble,
+        ]
+        .into_iter()
+        .chain(self.byte.columns())
+        .chain(self.key.columns())
+        .chain(self.node.columns());
+        let values = [
+            row.key.is_some(),
+            row.node.is_some(),
+            row.is_storage_trie,
+            row.is_new,
+        ]
+        .into_iter()
+        .map(|flag| flag as u64)
+        .chain([row.depth as u64, row.nibble as u64])
+        .chain(byte.columns())
+        .map(F::from)
+        .chain(key_values)
+        .chain(node_values);
+        for (column, value) in columns.zip_eq(values) {
+            region.assign_advice(|| "mpt row", column, offset, || Value::known(value))?;
+        }
+        Ok(())
+    }
+
+    /// Assign the values of the update of a row besides its MptTable row, and
+    /// the root chain.  Returns the cell of the root chain.
+    fn assign_update(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        flags: [bool; PROOF_TYPES.len()],
+        [address_hash, storage_key_hash, key_hash, root_chain]: [F; 4],
+    ) -> Result<AssignedCell<F, F>, Error> {
+        for (column, flag) in self.proof_type_flags.into_iter().zip(flags) {
+            region.assign_advice(
+                || "proof type flag",
+                column,
+                offset,
+                || Value::known(F::from(flag as u64)),
+            )?;
+        }
+        for (name, column, value) in [
+            ("address_hash", self.address_hash, address_hash),
+            ("storage_key_hash", self.storage_key_hash, storage_key_hash),
+            ("key_hash", self.key_hash, key_hash),
+        ] {
+            region.assign_advice(|| name, column, offset, || Value::known(value))?;
+        }
+        region.assign_advice(
+            || "root_chain",
+            self.root_chain,
+            offset,
+            || Value::known(root_chain),
+        )
+    }
+}
+
+/// Query an advice column at a rotation
+fn query<F: Field>(
+    meta: &mut VirtualCells<'_, F>,
+    column: Column<Advice>,
+    rotation: i32,
+) -> Expression<F> {
+    meta.query_advice(column, Rotation(rotation))
+}
+
+/// Return the lookup of `input` to the nibbles of the keys, which are given
+/// by the `is_key`, `key_hash`, `depth` and `nibble` columns.
+fn key_nibble_lookup<F: Field>(
+    meta: &mut VirtualCells<'_, F>,
+    [is_key, key_hash, depth, nibble]: [Column<Advice>; 4],
+    condition: Expression<F>,
+    input: [Expression<F>; 3],
+) -> Vec<(Expression<F>, Expression<F>)> {
+    let is_key = query(meta, is_key, 0);
+    input
+        .into_iter()
+        .zip([key_hash, depth, nibble])
+        .map(|(input, column)| {
+            (
+                condition.clone() * input,
+                is_key.clone() * query(meta, column, 0),
+            )
+        })
+        .collect()
+}
+
+/// Return the lookup of `input` to the keccak table
+fn keccak_lookup<F: Field>(
+    meta: &mut VirtualCells<'_, F>,
+    keccak_table: &KeccakTable,
+    condition: Expression<F>,
+    input: [Expression<F>; 4],
+) -> Vec<(Expression<F>, Expression<F>)> {
+    input
+        .into_iter()
+        .zip(keccak_table.columns())
+        .map(|(input, column)| {
+            (
+                condition.clone() * input,
+                meta.query_advice(column, Rotation::cur()),
+            )
+        })
+        .collect()
+}
+
+/// Values of a row of the circuit
+#[derive(Clone, Debug)]
+struct MptRow<F> {
+    is_storage_trie: bool,
+    is_new: bool,
+    byte: u8,
+    depth: usize,
+    nibble: u8,
+    key: Option<KeyRow<F>>,
+    node: Option<NodeRow<F>>,
+}
+
+impl<F: Field> MptRow<F> {
+    fn padding() -> Self {
+        Self {
+            is_storage_trie: false,
+            is_new: false,
+            byte: 0,
+            depth: 0,
+            nibble: 0,
+            key: None,
+            node: None,
+        }
+    }
+}
+
+/// Values of the KeyColumns of a row
+#[derive(Clone, Debug)]
+struct KeyRow<F> {
+    first: bool,
+    last: bool,
+    is_odd: bool,
+    acc: F,
+    is_preimage: bool,
+    preimage_byte: u8,
+    preimage_rlc: F,
+    preimage_int: F,
+    preimage_len: usize,
+}
+
+impl<F: Field> KeyRow<F> {
+    fn values(&self) -> Vec<F> {
+        vec![
+            F::from(self.first as u64),
+            F::from(self.last as u64),
+            F::from(self.is_odd as u64),
+            self.acc,
+            F::from(self.is_preimage as u64),
+            F::from(self.preimage_byte as u64),
+            self.preimage_rlc,
+            self.preimage_int,
+            F::from(self.preimage_len as u64),
+        ]
+    }
+}
+
+/// Values of the NodeColumns of a row
+#[derive(Clone, Debug)]
+struct NodeRow<F> {
+    first: bool,
+    last: bool,
+    rlc: F,
+    len: usize,
+    hash_rlc: F,
+    is_inline: bool,
+    node_type: NodeType,
+    list_rem: usize,
+    item_first: bool,
+    item_rem: usize,
+    item_index: usize,
+    is_key_item: bool,
+    key_item_inv: F,
+    is_path_item: bool,
+    path_odd: bool,
+    has_hi: bool,
+    has_lo: bool,
+    nibble_count: usize,
+    key_nibble_hi: u8,
+    key_nibble_lo: u8,
+    mismatch: bool,
+    mismatch_inv: F,
+    child_inline: bool,
+    is_child_byte: bool,
+    child_rlc: F,
+    is_field: bool,
+    sub_first: bool,
+    sub_rem: usize,
+    sub_index: usize,
+    is_target_field: bool,
+    target_field_inv: F,
+    is_value_byte: bool,
+    value_acc: F,
+    path_last: bool,
+    path_index: usize,
+    is_header: bool,
+    is_other_key: bool,
+    is_off_key: bool,
+    off_key_rlc: F,
+    off_key_len: usize,
+    is_fresh: bool,
+}
+
+impl<F: Field> NodeRow<F> {
+    fn values(&self) -> Vec<F> {
+        let flag = |value: bool| F::from(value as u64);
+        let int = |value: usize| F::from(value as u64);
+        [
+            flag(self.first),
+            flag(self.last),
+            self.rlc,
+            int(self.len),
+            self.hash_rlc,
+            flag(self.is_inline),
+        ]
+        .into_iter()
+        .chain((0..NODE_TYPES).map(|node_type| flag(self.node_type as usize == node_type)))
+        .chain([
+            int(self.list_rem),
+            flag(self.item_first),
+            int(self.item_rem),
+            int(self.item_index),
+            flag(self.is_key_item),
+            self.key_item_inv,
+            flag(self.is_path_item),
+            flag(self.path_odd),
+            flag(self.has_hi),
+            flag(self.has_lo),
+            int(self.nibble_count),
+            F::from(self.key_nibble_hi as u64),
+            F::from(self.key_nibble_lo as u64),
+            flag(self.mismatch),
+            self.mismatch_inv,
+            flag(self.child_inline),
+            flag(self.is_child_byte),
+            self.child_rlc,
+            flag(self.is_field),
+            flag(self.sub_first),
+            int(self.sub_rem),
+            int(self.sub_index),
+            flag(self.is_target_field),
+            self.target_field_inv,
+            flag(self.is_value_byte),
+            self.value_acc,
+            flag(self.path_last),
+            int(self.path_index),
+            flag(self.is_header),
+            flag(self.is_other_key),
+            flag(self.is_off_key),
+            self.off_key_rlc,
+            int(self.off_key_len),
+            flag(self.is_fresh),
+        ])
+        .collect()
+    }
+}
+
+/// A node of the path of a key, with its index in the path, the depth in the
+/// key of its first nibble, and whether it's inlined in its parent node and
+/// in the storage trie.
+#[derive(Clone, Debug)]
+struct PathNode {
+    bytes: Vec<u8>,
+    index: usize,
+    depth: usize,
+    is_inline: bool,
+    is_storage_trie: bool,
+}
+
+/// Reference to the next node of a path in its parent node
+enum ChildRef {
+    Hash(Vec<u8>),
+    Inline(Vec<u8>),
+}
+
+/// Return the rows of an update: the nibbles of its keys, followed by the
+/// nodes of its old and new paths.
+fn update_rows<F: Field>(
+    witness: &MptUpdateWitness,
+    randomness: F,
+) -> Result<Vec<MptRow<F>>, Error> {
+    let update = &witness.update;
+    let mut rows = key_rows(update, false, randomness);
+    if update.storage_key().is_some() {
+        rows.extend(key_rows(update, true, randomness));
+    }
+    let paths = [
+        path_nodes(update, &witness.old_proof)?,
+        path_nodes(update, &witness.new_proof)?,
+    ];
+    for (is_new, path) in [false, true].into_iter().zip(paths.iter()) {
+        let other_path = &paths[1 - is_new as usize];
+        for node in path {
+            // The leaf of the key is only in this path when the other path
+            // ends before it, or is the empty trie.
+            let is_fresh = is_key_leaf(update, node)
+                && other_path
+                    .get(node.index)
+                    .map_or(true, |other| other.bytes[..] == EMPTY_NODE);
+            rows.extend(node_rows(update, node, is_new, is_fresh, randomness)?);
+        }
+    }
+    Ok(rows)
+}
+
+/// Return the keccak preimage of the key of an update in the account trie,
+/// which is its address, or in the storage trie, which is its storage key.
+fn key_preimage(update: &MptUpdate, is_storage_trie: bool) -> Vec<u8> {
+    match update.storage_key() {
+        Some(storage_key) if is_storage_trie => storage_key.to_be_bytes().to_vec(),
+        _ => update.address().as_bytes().to_vec(),
+    }
+}
+
+/// Return the rows of the nibbles of the key of an update
+fn key_rows<F: Field>(update: &MptUpdate, is_storage_trie: bool, randomness: F) -> Vec<MptRow<F>> {
+    let preimage = key_preimage(update, is_storage_trie);
+    let key = keccak256(&preimage);
+    let (mut acc, mut preimage_rlc, mut preimage_int) = (F::zero(), F::zero(), F::zero());
+    (0..KEY_NIBBLES)
+        .map(|depth| {
+            let byte = key[depth / 2];
+            let is_odd = depth % 2 == 1;
+            if is_odd {
+                acc = acc * randomness + F::from(byte as u64);
+            }
+            let preimage_byte = preimage.get(depth).copied();
+            if let Some(preimage_byte) = preimage_byte {
+                preimage_rlc = preimage_rlc * randomness + F::from(preimage_byte as u64);
+                preimage_int = preimage_int * F::from(256) + F::from(preimage_byte as u64);
+            }
+            MptRow {
+                is_storage_trie,
+                is_new: false,
+                byte,
+                depth,
+                nibble: if is_odd { byte & 0xf } else { byte >> 4 },
+                key: Some(KeyRow {
+                    first: depth == 0,
+                    last: depth + 1 == KEY_NIBBLES,
+                    is_odd,
+                    acc,
+                    is_preimage: preimage_byte.is_some(),
+                    preimage_byte: preimage_byte.unwrap_or_default(),
+                    preimage_rlc,
+                    preimage_int,
+                    preimage_len: preimage.len().min(depth + 1),
+                }),
+                node: None,
+            }
+        })
+        .collect()
+}
+
+/// Return the nodes of the path of the key of an update in a proof, from the
+/// root to the leaf of the key, or to the node where the key is not in the
+/// trie.  For storage updates, the path continues from the account leaf into
+/// the storage trie.  An empty proof is the empty trie, and the nodes shorter
+/// than 32 bytes are taken from their parent node, since they are not part of
+/// the `eth_getProof` response.
+fn path_nodes(update: &MptUpdate, proof: &MptProof) -> Result<Vec<PathNode>, Error> {
+    let mut proof_nodes = proof_or_empty(&proof.account_proof);
+    let mut node = PathNode {
+        bytes: next_proof_node(&mut proof_nodes)?,
+        index: 0,
+        depth: 0,
+        is_inline: false,
+        is_storage_trie: false,
+    };
+    let mut nodes = vec![];
+    loop {
+        let child = path_child(update, &node)?;
+        let is_storage_trie = node.is_storage_trie;
+        nodes.push(node);
+        let (child, depth, child_is_storage_trie) = match child {
+            Some(child) => child,
+            None => return Ok(nodes),
+        };
+        if child_is_storage_trie && !is_storage_trie {
+            proof_nodes = proof_or_empty(&proof.storage_proof);
+        }
+        let (bytes, is_inline) = match child {
+            ChildRef::Inline(bytes) => (bytes, true),
+            ChildRef::Hash(hash) => {
+                let bytes = next_proof_node(&mut proof_nodes)?;
+                if keccak256(&bytes)[..] != hash[..] {
+                    error!("mpt proof node doesn't hash to its reference in the parent node");
+                    return Err(Error::Synthesis);
+                }
+                (bytes, false)
+            }
+        };
+        node = PathNode {
+            bytes,
+            index: nodes.len(),
+            depth,
+            is_inline,
+            is_storage_trie: child_is_storage_trie,
+        };
+    }
+}
+
+/// Return the reference to the child of a node on the path of the key of an
+/// update, with its depth and whether it's in the storage trie, or None if
+/// the path ends at the node.
+fn path_child(
+    update: &MptUpdate,
+    node: &PathNode,
+) -> Result<Option<(ChildRef, usize, bool)>, Error> {
+    if node.bytes[..] == EMPTY_NODE {
+        return Ok(None);
+    }
+    let key = nibbles(&keccak256(key_preimage(update, node.is_storage_trie)));
+    let items: Vec<Rlp> = Rlp::new(&node.bytes).iter().collect();
+    let (child, depth) = match items.len() {
+        17 => {
+            let nibble = key.get(node.depth).ok_or_else(|| invalid_node(()))?;
+            let child = items[*nibble as usize].clone();
+            if child.as_raw() == EMPTY_NODE {
+                return Ok(None);
+            }
+            (child, node.depth + 1)
+        }
+        2 => {
+            let (is_leaf, path) = hex_prefix_decode(items[0].data().map_err(invalid_node)?)?;
+            if is_leaf {
+                // The account leaf of a storage update is followed by the
+                // storage trie from its storage root.
+                if key.get(node.depth..) != Some(&path[..])
+                    || node.is_storage_trie
+                    || update.storage_key().is_none()
+                {
+                    return Ok(None);
+                }
+                let account = Rlp::new(items[1].data().map_err(invalid_node)?);
+                let storage_root = account.at(2).and_then(|field| field.data());
+                return Ok(Some((
+                    ChildRef::Hash(storage_root.map_err(invalid_node)?.to_vec()),
+                    0,
+                    true,
+                )));
+            }
+            if key.get(node.depth..node.depth + path.len()) != Some(&path[..]) {
+                return Ok(None);
+            }
+            (items[1].clone(), node.depth + path.len())
+        }
+        _ => return Err(invalid_node(())),
+    };
+    let child = if child.is_list() {
+        ChildRef::Inline(child.as_raw().to_vec())
+    } else {
+        ChildRef::Hash(child.data().map_err(invalid_node)?.to_vec())
+    };
+    Ok(Some((child, depth, node.is_storage_trie)))
+}
+
+/// Return whether a node is the leaf of the key of an update
+fn is_key_leaf(update: &MptUpdate, node: &PathNode) -> bool {
+    let key = nibbles(&keccak256(key_preimage(update, node.is_storage_trie)));
+    let items: Vec<Rlp> = Rlp::new(&node.bytes).iter().collect();
+    match items.as_slice() {
+        [path, _] => matches!(
+            path.data().map(hex_prefix_decode),
+            Ok(Ok((true, path))) if key.get(node.depth..) == Some(&path[..])
+        ),
+        _ => false,
+    }
+}
+
+/// Return the rows of the bytes of a node of the path of the key of an
+/// update.  A fresh node is the leaf of the key which is inserted or deleted
+/// by the update.
+fn node_rows<F: Field>(
+    update: &MptUpdate,
+    node: &PathNode,
+    is_new: bool,
+    is_fresh: bool,
+    randomness: F,
+) -> Result<Vec<MptRow<F>>, Error> {
+    let bytes = &node.bytes;
+    let s = node.is_storage_trie;
+    let key = nibbles(&keccak256(key_preimage(update, s)));
+    let key_nibble = |depth: usize| key.get(depth).copied().ok_or_else(|| invalid_node(()));
+    let kind = |index: usize| RlpKind::from_byte(bytes[index]).map(|(kind, _)| kind);
+
+    let (node_type, items, path_odd, is_other_key) = if bytes[..] == EMPTY_NODE {
+        (NodeType::Empty, vec![0..1], false, false)
+    } else {
+        let items = node_items(bytes)?;
+        match items.len() - 1 {
+            17 => (NodeType::Branch, items, false, false),
+            2 => {
+                let path = Rlp::new(&bytes[items[1].clone()]);
+                let (is_leaf, path) = hex_prefix_decode(path.data().map_err(invalid_node)?)?;
+                let node_type = if is_leaf {
+                    NodeType::Leaf
+                } else {
+                    NodeType::Extension
+                };
+                let is_other_key = key.get(node.depth..node.depth + path.len()) != Some(&path[..]);
+                (node_type, items, path.len() % 2 == 1, is_other_key)
+            }
+            _ => return Err(invalid_node(())),
+        }
+    };
+    let is_branch = node_type == NodeType::Branch;
+    let nibble = if is_branch {
+        key_nibble(node.depth)?
+    } else {
+        0
+    };
+    let target_item = if is_branch { nibble as usize + 1 } else { 2 };
+
+    let proof_type = update.proof_type();
+    let is_final = proof_type != ProofType::StorageChanged || s;
+    let target_field = match proof_type {
+        ProofType::NonceChanged => 1,
+        ProofType::BalanceChanged => 2,
+        ProofType::CodeHashExists => 4,
+        ProofType::StorageChanged if s => 1,
+        ProofType::StorageChanged => 3,
+        _ => 0,
+    };
+    let value_base = match proof_type {
+        ProofType::NonceChanged => F::from(256),
+        _ => randomness,
+    };
+    let hash_rlc = if node.is_inline {
+        bytes_rlc(bytes, randomness)
+    } else {
+        keccak_rlc(bytes, randomness)
+    };
+
+    // The child of a branch at the key nibble is empty where the key is not
+    // in the trie.
+    let (child_inline, mut mismatch) = match node_type {
+        NodeType::Branch | NodeType::Extension => {
+            let start = items[target_item].start;
+            (
+                kind(start) == Some(RlpKind::List),
+                is_branch && bytes[start] == EMPTY_NODE[0],
+            )
+        }
+        NodeType::Leaf => (false, false),
+        NodeType::Empty => (false, true),
+    };
+    // The fields of a leaf value start after the string and list headers of
+    // the account, or after the string header of the storage value if any.
+    let field_start = match node_type {
+        NodeType::Leaf => {
+            let start = items[2].start;
+            Some(if !s {
+                start + 4
+            } else if kind(start) == Some(RlpKind::Single) {
+                start
+            } else {
+                start + 1
+            })
+        }
+        _ => None,
+    };
+
+    let mut rows = Vec::with_capacity(bytes.len());
+    let (mut rlc, mut child_rlc, mut value_acc) = (F::zero(), F::zero(), F::zero());
+    let (mut nibble_count, mut sub_rem, mut sub_index) = (0, 0, 0);
+    let (mut off_key_rlc, mut off_key_len) = (F::zero(), 0);
+    let mut item_first_prev = false;
+    for (item_index, item) in items.iter().enumerate() {
+        for index in item.clone() {
+            let byte = bytes[index];
+            let rlp_kind = RlpKind::from_byte(byte);
+            let item_first = index == item.start;
+            rlc = rlc * randomness + F::from(byte as u64);
+
+            // Nibbles of the path of extensions and leaves, after the hex
+            // prefix flag
+            let is_key_item = item_index == target_item;
+            let is_path_item =
+                matches!(node_type, NodeType::Extension | NodeType::Leaf) && item_index == 1;
+            let is_flag = is_path_item
+                && if item_first {
+                    kind(index) == Some(RlpKind::Single)
+                } else {
+                    item_first_prev
+                };
+            let has_hi = is_path_item && !item_first && !item_first_prev;
+            let has_lo = has_hi || (is_flag && path_odd);
+            let key_nibble_hi = if has_hi {
+                key_nibble(node.depth + nibble_count)?
+            } else {
+                0
+            };
+            let key_nibble_lo = if has_lo {
+                key_nibble(node.depth + nibble_count + has_hi as usize)?
+            } else {
+                0
+            };
+            let nibble_diff = F::from(has_hi as u64)
+                * (F::from((byte >> 4) as u64) - F::from(key_nibble_hi as u64))
+                + F::from(31 * has_lo as u64)
+                    * (F::from((byte & 0xf) as u64) - F::from(key_nibble_lo as u64));
+            let mut mismatch_inv = F::zero();
+            if !mismatch && !nibble_diff.is_zero_vartime() {
+                mismatch = true;
+                mismatch_inv = inverse(nibble_diff);
+            }
+            nibble_count += has_hi as usize + has_lo as usize;
+
+            // Fields of the leaf value
+            let is_field = field_start.map_or(false, |start| index >= start);
+            let mut sub_first = false;
+            if is_field {
+                if Some(index) == field_start {
+                    sub_first = true;
+                    sub_index = 1;
+                } else if sub_rem == 0 {
+                    sub_first = true;
+                    sub_index += 1;
+                } else {
+                    sub_rem -= 1;
+                }
+                if sub_first {
+                    sub_rem = match rlp_kind {
+                        Some((RlpKind::String, len)) => len as usize,
+                        _ => 0,
+                    };
+                }
+            }
+            let target_field_diff = F::from(sub_index as u64) - F::from(target_field);
+            let is_target_field = is_field && target_field_diff.is_zero_vartime();
+            let is_value_byte =
+                is_target_field && is_final && (!sub_first || kind(index) == Some(RlpKind::Single));
+            let is_child_byte = (matches!(node_type, NodeType::Branch | NodeType::Extension)
+                && is_key_item
+                && (!item_first || kind(index) == Some(RlpKind::List)))
+                || (!is_final && is_target_field && !sub_first);
+            if is_child_byte {
+                child_rlc = child_rlc * randomness + F::from(byte as u64);
+            }
+            if is_value_byte {
+                value_acc = value_acc * value_base + F::from(byte as u64);
+            }
+
+            // Bytes off the key, which are the same in the old and new paths
+            let is_header = item_index == 0;
+            let is_off_key = (!is_header
+                && match node_type {
+                    NodeType::Branch => !is_key_item,
+                    NodeType::Extension | NodeType::Leaf => is_other_key,
+                    NodeType::Empty => false,
+                })
+                || (node_type == NodeType::Leaf && !is_other_key && is_field && !is_target_field);
+            if is_off_key {
+                off_key_rlc = off_key_rlc * randomness + F::from(byte as u64);
+                off_key_len += 1;
+            }
+
+            let last = index + 1 == bytes.len();
+            rows.push(MptRow {
+                is_storage_trie: s,
+                is_new,
+                byte,
+                depth: node.depth,
+                nibble,
+                key: None,
+                node: Some(NodeRow {
+                    first: index == 0,
+                    last,
+                    rlc,
+                    len: index + 1,
+                    hash_rlc,
+                    is_inline: node.is_inline,
+                    node_type,
+                    list_rem: bytes.len() - 1 - index,
+                    item_first,
+                    item_rem: item.end - 1 - index,
+                    item_index,
+                    is_key_item,
+                    key_item_inv: inverse(F::from(item_index as u64) - F::from(target_item as u64)),
+                    is_path_item,
+                    path_odd,
+                    has_hi,
+                    has_lo,
+                    nibble_count,
+                    key_nibble_hi,
+                    key_nibble_lo,
+                    mismatch,
+                    mismatch_inv,
+                    child_inline,
+                    is_child_byte,
+                    child_rlc,
+                    is_field,
+                    sub_first,
+                    sub_rem,
+                    sub_index,
+                    is_target_field,
+                    target_field_inv: if is_field {
+                        inverse(target_field_diff)
+                    } else {
+                        F::zero()
+                    },
+                    is_value_byte,
+                    value_acc,
+                    path_last: last && (mismatch || (node_type == NodeType::Leaf && is_final)),
+                    path_index: node.index,
+                    is_header,
+                    is_other_key,
+                    is_off_key,
+                    off_key_rlc,
+                    off_key_len,
+                    is_fresh,
+                }),
+            });
+            item_first_prev = item_first;
+        }
+    }
+    Ok(rows)
+}
+
+/// Return the field index, number of remaining bytes and byte of each byte of
+/// the fields of the empty account, as in the NodeColumns: a nonce and
+/// balance of 0, the root of the empty trie and the hash of the empty code.
+fn empty_account_fields() -> Vec<[u64; 3]> {
+    let hash_field = |index: u64, hash: [u8; 32]| {
+        once([index, 32, 0xa0]).chain(
+            hash.into_iter()
+                .enumerate()
+                .map(move |(i, byte)| [index, 31 - i as u64, byte as u64]),
+        )
+    };
+    [[1, 0, 0x80], [2, 0, 0x80]]
+        .into_iter()
+        .chain(hash_field(3, keccak256(EMPTY_NODE)))
+        .chain(hash_field(4, keccak256([])))
+        .collect()
+}
+
+/// Return the ranges of the list header and of the items of a node.
+fn node_items(bytes: &[u8]) -> Result<Vec<Range<usize>>, Error> {
+    let rlp = Rlp::new(bytes);
+    if !rlp.is_list() {
+        return Err(invalid_node(()));
+    }
+    let header_len = rlp.payload_info().map_err(invalid_node)?.header_len;
+    let mut items = vec![0..header_len];
+    for item in rlp.iter() {
+        let start = items[items.len() - 1].end;
+        items.push(start..start + item.as_raw().len());
+    }
+    if items[items.len() - 1].end != bytes.len() {
+        return Err(invalid_node(()));
+    }
+    Ok(items)
+}
+
+/// Decode the hex prefix encoded path of an extension or leaf.  Returns
+/// whether the node is a leaf, and the nibbles of the path.
+fn hex_prefix_decode(path: &[u8]) -> Result<(bool, Vec<u8>), Error> {
+    let flag = path.first().ok_or_else(|| invalid_node(()))? >> 4;
+    if flag > 3 {
+        return Err(invalid_node(()));
+    }
+    Ok((flag >= 2, nibbles(path)[2 - (flag & 1) as usize..].to_vec()))
+}
+
+/// Return the nibbles of some bytes, high nibble first
+fn nibbles(bytes: &[u8]) -> Vec<u8> {
+    bytes
+        .iter()
+        .flat_map(|byte| [byte >> 4, byte & 0xf])
+        .collect()
+}
+
+/// Return the nodes of a proof, or the empty trie for an empty proof
+fn proof_or_empty(proof: &[Vec<u8>]) -> std::vec::IntoIter<Vec<u8>> {
+    if proof.is_empty() {
+        vec![EMPTY_NODE.to_vec()].into_iter()
+    } else {
+        proof.to_vec().into_iter()
+    }
+}
+
+fn next_proof_node(proof_nodes: &mut impl Iterator<Item = Vec<u8>>) -> Result<Vec<u8>, Error> {
+    proof_nodes.next().ok_or_else(|| {
+        error!("mpt proof is missing a node of the path");
+        Error::Synthesis
+    })
+}
+
+fn invalid_node<E>(_: E) -> Error {
+    error!("invalid mpt proof node");
+    Error::Synthesis
+}
+
+/// Return the inverse of a field element, or 0 for 0
+fn inverse<F: Field>(value: F) -> F {
+    value.invert().unwrap_or(F::zero())
+}
+
+/// Return the RLC of some bytes, as the input of the keccak table
+fn bytes_rlc<F: Field>(bytes: &[u8], randomness: F) -> F {
+    bytes.iter().fold(F::zero(), |acc, byte| {
+        acc * randomness + F::from(*byte as u64)
+    })
+}
+
+/// Return the RLC of the keccak hash of some bytes, as the output of the
+/// keccak table
+fn keccak_rlc<F: Field>(bytes: &[u8], randomness: F) -> F {
+    rlc(
+        Word::from_big_endian(&keccak256(bytes)).to_le_bytes(),
+        randomness,
+    )
+}
+
+/// MPT Circuit for proving the MPT updates of the MptTable
+#[derive(Clone, Default, Debug)]
+pub struct MptCircuit<F> {
+    /// The MPT updates with their proofs, in the order they are applied to
+    /// the state trie
+    pub updates: Vec<MptUpdateWitness>,
+    /// Number of rows of the circuit, including the padding after the
+    /// updates
+    pub n_rows: usize,
+    /// Randomness for RLC encoding
+    pub randomness: F,
+}
+
+impl<F: Field> MptCircuit<F> {
+    /// Return a new MptCircuit
+    pub fn new(updates: Vec<MptUpdateWitness>, randomness: F, n_rows: usize) -> Self {
+        Self {
+            updates,
+            n_rows,
+            randomness,
+        }
+    }
+
+    /// Return the keys and the nodes of all the paths which are not inline,
+    /// which are hashed via the keccak table.
+    pub fn keccak_inputs(&self) -> Vec<Vec<u8>> {
+        self.updates
+            .iter()
+            .flat_map(|witness| {
+                let update = &witness.update;
+                let keys = once(key_preimage(update, false))
+                    .chain(update.storage_key().map(|_| key_preimage(update, true)));
+                let nodes = [&witness.old_proof, &witness.new_proof]
+                    .into_iter()
+                    .flat_map(|proof| path_nodes(update, proof).unwrap_or_default())
+                    .filter(|node| !node.is_inline)
+                    .map(|node| node.bytes);
+                keys.chain(nodes).collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    /// Return the number of rows required by the updates, with the padding
+    /// row at the end
+    pub fn get_num_rows_required(&self) -> usize {
+        self.updates
+            .iter()
+            .map(|witness| update_rows(witness, self.randomness).map_or(0, |rows| rows.len()))
+            .sum::<usize>()
+            + 1
+    }
+
+    /// Return the old and new state roots, which are the public inputs of the
+    /// circuit
+    pub fn state_roots(&self) -> Vec<F> {
+        match (self.updates.first(), self.updates.last()) {
+            (Some(first), Some(last)) => vec![
+                first.update.root_assignments(self.randomness).1,
+                last.update.root_assignments(self.randomness).0,
+            ],
+            _ => vec![F::zero(); 2],
+        }
+    }
+
+    /// Return the randomness and the state roots instance columns
+    pub fn instance(&self) -> Vec<Vec<F>> {
+        vec![vec![self.randomness; self.n_rows], self.state_roots()]
+    }
+}
+
+impl<F: Field> Circuit<F> for MptCircuit<F> {
+    type Config = MptCircuitConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let [randomness] = power_of_randomness_from_instance(meta);
+        let mpt_table = MptTable::construct(meta);
+        let keccak_table = KeccakTable::construct(meta);
+        MptCircuitConfig::configure(meta, mpt_table, keccak_table, randomness)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.load(&mut layouter)?;
+        config.keccak_table.dev_load(
+            &mut layouter,
+            &self.keccak_inputs(),
+            &Challenges::mock(Value::known(self.randomness)),
+        )?;
+        config.assign(&mut layouter, &self.updates, self.n_rows, self.randomness)
+    }
+}
+
+#[cfg(test)]
+mod mpt_circuit_test {
+    use super::*;
+
+    use crate::table::AccountFieldTag;
+    use eth_types::Address;
+    use ethers_core::utils::rlp::{self, RlpStream};
+    use halo2_proofs::{
+        dev::{MockProver, VerifyFailure},
+        halo2curves::bn256::Fr,
+    };
+
+    const N_ROWS: usize = (1 << 11) - 64;
+
+    struct Account {
+        nonce: u64,
+        balance: u64,
+        storage_root: [u8; 32],
+    }
+
+    impl Account {
+        fn new(nonce: u64, balance: u64) -> Self {
+            Self {
+                nonce,
+                balance,
+                storage_root: keccak256(EMPTY_NODE),
+            }
+        }
+    }
+
+    fn be_bytes(value: u64) -> Vec<u8> {
+        let bytes = value.to_be_bytes();
+        let start = bytes.iter().position(|byte| *byte != 0).unwrap_or(8);
+        bytes[start..].to_vec()
+    }
+
+    // Hex prefix encoding of the nibbles of the path of an extension or leaf
+    fn hex_prefix(path: &[u8], is_leaf: bool) -> Vec<u8> {
+        let flag = 2 * is_leaf as u8 + (path.len() % 2) as u8;
+        let flag_nibbles = match path.len() % 2 {
+            0 => vec![flag, 0],
+            _ => vec![flag],
+        };
+        [flag_nibbles, path.to_vec()]
+            .concat()
+            .chunks(2)
+            .map(|pair| pair[0] << 4 | pair[1])
+            .collect()
+    }
+
+    fn leaf(path: &[u8], value: &[u8]) -> Vec<u8> {
+        let mut leaf = RlpStream::new_list(2);
+        leaf.append(&hex_prefix(path, true)).append(&value.to_vec());
+        leaf.out().to_vec()
+    }
+
+    fn account_leaf(address: Address, depth: usize, account: &Account) -> Vec<u8> {
+        let mut value = RlpStream::new_list(4);
+        value
+            .append(&be_bytes(account.nonce))
+            .append(&be_bytes(account.balance))
+            .append(&account.storage_root.to_vec())
+            .append(&keccak256([]).to_vec());
+        leaf(&nibbles(&keccak256(address))[depth..], &value.out())
+    }
+
+    fn storage_leaf(key: Word, depth: usize, value: u64) -> Vec<u8> {
+        leaf(
+            &nibbles(&keccak256(key_bytes(key)))[depth..],
+            &rlp::encode(&be_bytes(value)),
+        )
+    }
+
+    // Append the reference to a child node, which is inlined if it's shorter
+    // than 32 bytes.
+    fn append_child(stream: &mut RlpStream, child: &[u8]) {
+        match child.len() {
+            0..=31 => stream.append_raw(child, 1),
+            _ => stream.append(&keccak256(child).to_vec()),
+        };
+    }
+
+    fn branch(children: &[(usize, &[u8])]) -> Vec<u8> {
+        let mut branch = RlpStream::new_list(17);
+        for nibble in 0..16 {
+            match children.iter().find(|(index, _)| *index == nibble) {
+                Some((_, child)) => append_child(&mut branch, child),
+                None => {
+                    branch.append_empty_data();
+                }
+            };
+        }
+        branch.append_empty_data();
+        branch.out().to_vec()
+    }
+
+    fn extension(path: &[u8], child: &[u8]) -> Vec<u8> {
+        let mut extension = RlpStream::new_list(2);
+        extension.append(&hex_prefix(path, false));
+        append_child(&mut extension, child);
+        extension.out().to_vec()
+    }
+
+    fn key_bytes(key: Word) -> [u8; 32] {
+        let mut bytes = [0; 32];
+        key.to_big_endian(&mut bytes);
+        bytes
+    }
+
+    fn first_nibble(address: Address) -> usize {
+        (keccak256(address)[0] >> 4) as usize
+    }
+
+    fn root(node: &[u8]) -> Word {
+        Word::from_big_endian(&keccak256(node))
+    }
+
+    fn proof(account_proof: Vec<Vec<u8>>) -> MptProof {
+        MptProof {
+            account_proof,
+            storage_proof: vec![],
+        }
+    }
+
+    // Return an address whose key is in a different branch child than the
+    // given ones.
+    fn address_in_other_child(addresses: &[Address]) -> Address {
+        (1..=u64::MAX)
+            .map(Address::from_low_u64_be)
+            .find(|address| {
+                addresses
+                    .iter()
+                    .all(|other| first_nibble(*other) != first_nibble(*address))
+            })
+            .unwrap()
+    }
+
+    fn run(updates: Vec<MptUpdateWitness>) -> Result<(), Vec<VerifyFailure>> {
+        let circuit = MptCircuit::new(updates, Fr::from(0x100), N_ROWS);
+        let prover = MockProver::run(11, &circuit, circuit.instance()).unwrap();
+        prover.verify()
+    }
+
+    fn nonce_update(address: Address, old_nonce: u64, new_nonce: u64) -> MptUpdateWitness {
+        let old_leaf = account_leaf(address, 0, &Account::new(old_nonce, 0));
+        let new_leaf = account_leaf(address, 0, &Account::new(new_nonce, 0));
+        MptUpdateWitness {
+            update: MptUpdate::account(
+                address,
+                AccountFieldTag::Nonce,
+                old_nonce.into(),
+                new_nonce.into(),
+                root(&old_leaf),
+                root(&new_leaf),
+            ),
+            old_proof: proof(vec![old_leaf]),
+            new_proof: proof(vec![new_leaf]),
+        }
+    }
+
+    // Return the update of a storage slot of an account, which is the only
+    // account of the trie, from the storage trie of `old_storage_proof` to the
+    // one of `new_storage_proof`.
+    fn storage_update(
+        address: Address,
+        storage_key: Word,
+        [old_value, new_value]: [u64; 2],
+        [old_storage_proof, new_storage_proof]: [Vec<Vec<u8>>; 2],
+    ) -> MptUpdateWitness {
+        let [old, new] = [old_storage_proof, new_storage_proof].map(|storage_proof| {
+            let account = Account {
+                storage_root: keccak256(
+                    storage_proof.first().map_or(&EMPTY_NODE[..], Vec::as_slice),
+                ),
+                ..Account::new(1, 0)
+            };
+            let account_leaf = account_leaf(address, 0, &account);
+            (
+                root(&account_leaf),
+                MptProof {
+                    account_proof: vec![account_leaf],
+                    storage_proof,
+                },
+            )
+        });
+        MptUpdateWitness {
+            update: MptUpdate::storage(
+                1,
+                address,
+                storage_key,
+                old_value.into(),
+                new_value.into(),
+                old.0,
+                new.0,
+            ),
+            old_proof: old.1,
+            new_proof: new.1,
+        }
+    }
+
+    #[test]
+    fn mpt_circuit_nonce() {
+        assert_eq!(
+            run(vec![nonce_update(Address::repeat_byte(0x11), 0, 1)]),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn mpt_circuit_balance_in_branch() {
+        let address = Address::repeat_byte(0x11);
+        let other = address_in_other_child(&[address]);
+        let other_leaf = account_leaf(other, 1, &Account::new(3, 100));
+
+        let [old, new] = [200, 300].map(|balance| {
+            let leaf = account_leaf(address, 1, &Account::new(1, balance));
+            let branch = branch(&[
+                (first_nibble(address), leaf.as_slice()),
+                (first_nibble(other), other_leaf.as_slice()),
+            ]);
+            (root(&branch), vec![branch, leaf])
+        });
+        let update = MptUpdateWitness {
+            update: MptUpdate::account(
+                address,
+                AccountFieldTag::Balance,
+                200.into(),
+                300.into(),
+                old.0,
+                new.0,
+            ),
+            old_proof: proof(old.1),
+            new_proof: proof(new.1),
+        };
+        assert_eq!(run(vec![update]), Ok(()));
+    }
+
+    // Return the update of the balance of an account which is inserted in
+    // an empty child of the branch of two other accounts, with the given
+    // nonce.
+    fn insert_account(nonce: u64) -> MptUpdateWitness {
+        let others = [Address::repeat_byte(0x11), Address::repeat_byte(0x33)];
+        let address = address_in_other_child(&others);
+        let other_leaves = others.map(|other| account_leaf(other, 1, &Account::new(3, 100)));
+        let leaf = account_leaf(address, 1, &Account::new(nonce, 200));
+        let other_children = [0, 1].map(|i| (first_nibble(others[i]), other_leaves[i].as_slice()));
+        let old_branch = branch(&other_children);
+        let new_branch = branch(
+            &[
+                &other_children[..],
+                &[(first_nibble(address), leaf.as_slice())],
+            ]
+            .concat(),
+        );
+        MptUpdateWitness {
+            update: MptUpdate::account(
+                address,
+                AccountFieldTag::Balance,
+                0.into(),
+                200.into(),
+                root(&old_branch),
+                root(&new_branch),
+            ),
+            old_proof: proof(vec![old_branch]),
+            new_proof: proof(vec![new_branch, leaf]),
+        }
+    }
+
+    #[test]
+    fn mpt_circuit_insert_account() {
+        assert_eq!(run(vec![insert_account(0)]), Ok(()));
+    }
+
+    #[test]
+    fn mpt_circuit_insert_account_with_other_fields() {
+        // The inserted account has a nonce, which is not the field of the
+        // update.
+        assert!(run(vec![insert_account(1)]).is_err());
+    }
+
+    #[test]
+    fn mpt_circuit_changed_sibling() {
+        // The balance of the other account of the branch changes too.
+        let address = Address::repeat_byte(0x11);
+        let other = address_in_other_child(&[address]);
+
+        let [old, new] = [(200, 100), (300, 101)].map(|(balance, other_balance)| {
+            let leaf = account_leaf(address, 1, &Account::new(1, balance));
+            let other_leaf = account_leaf(other, 1, &Account::new(3, other_balance));
+            let branch = branch(&[
+                (first_nibble(address), leaf.as_slice()),
+                (first_nibble(other), other_leaf.as_slice()),
+            ]);
+            (root(&branch), vec![branch, leaf])
+        });
+        let update = MptUpdateWitness {
+            update: MptUpdate::account(
+                address,
+                AccountFieldTag::Balance,
+                200.into(),
+                300.into(),
+                old.0,
+                new.0,
+            ),
+            old_proof: proof(old.1),
+            new_proof: proof(new.1),
+        };
+        assert!(run(vec![update]).is_err());
+    }
+
+    #[test]
+    fn mpt_circuit_storage() {
+        let storage_key = Word::from(7);
+        let update = storage_update(
+            Address::repeat_byte(0x22),
+            storage_key,
+            [0x2a, 0x2b],
+            [0x2a, 0x2b].map(|value| vec![storage_leaf(storage_key, 0, value)]),
+        );
+        assert_eq!(run(vec![update]), Ok(()));
+    }
+
+    #[test]
+    fn mpt_circuit_storage_from_empty() {
+        let storage_key = Word::from(7);
+        let update = storage_update(
+            Address::repeat_byte(0x22),
+            storage_key,
+            [0, 0x2a],
+            [vec![], vec![storage_leaf(storage_key, 0, 0x2a)]],
+        );
+        assert_eq!(run(vec![update]), Ok(()));
+    }
+
+    #[test]
+    fn mpt_circuit_inline_nodes() {
+        // The storage trie is an extension to a branch at the last but one
+        // nibble of the key, whose children are leaves of a single nibble.
+        // The branch and the leaves are shorter than 32 bytes, so they are
+        // inlined in their parent node.
+        let storage_key = Word::from(7);
+        let key = nibbles(&keccak256(key_bytes(storage_key)));
+        let other_leaf = leaf(&[5], &[1]);
+        let storage_proofs = [0x10, 0x11].map(|value| {
+            let leaf = leaf(&key[63..], &[value]);
+            let branch = branch(&[
+                (key[62] as usize, leaf.as_slice()),
+                ((key[62] as usize + 1) % 16, other_leaf.as_slice()),
+            ]);
+            assert!(leaf.len() < 32 && branch.len() < 32);
+            vec![extension(&key[..62], &branch)]
+        });
+        let update = storage_update(
+            Address::repeat_byte(0x22),
+            storage_key,
+            [0x10, 0x11],
+            storage_proofs,
+        );
+        assert_eq!(run(vec![update]), Ok(()));
+    }
+
+    #[test]
+    fn mpt_circuit_chained_updates() {
+        let address = Address::repeat_byte(0x11);
+        let nonce_update = nonce_update(address, 0, 1);
+
+        let old_leaf = nonce_update.new_proof.account_proof[0].clone();
+        let new_leaf = account_leaf(address, 0, &Account::new(1, 500));
+        let balance_update = MptUpdateWitness {
+            update: MptUpdate::account(
+                address,
+                AccountFieldTag::Balance,
+                0.into(),
+                500.into(),
+                root(&old_leaf),
+                root(&new_leaf),
+            ),
+            old_proof: proof(vec![old_leaf]),
+            new_proof: proof(vec![new_leaf]),
+        };
+        assert_eq!(run(vec![nonce_update, balance_update]), Ok(()));
+    }
+
+    #[test]
+    fn mpt_circuit_account_does_not_exist() {
+        let addresses = [Address::repeat_byte(0x11), Address::repeat_byte(0x33)];
+        let address = address_in_other_child(&addresses);
+        let leaves = addresses.map(|address| account_leaf(address, 1, &Account::new(1, 0)));
+        let branch = branch(&[
+            (first_nibble(addresses[0]), leaves[0].as_slice()),
+            (first_nibble(addresses[1]), leaves[1].as_slice()),
+        ]);
+        let update = MptUpdateWitness {
+            update: MptUpdate::account_does_not_exist(address, root(&branch)),
+            old_proof: proof(vec![branch.clone()]),
+            new_proof: proof(vec![branch]),
+        };
+        assert_eq!(run(vec![update]), Ok(()));
+    }
+
+    #[test]
+    fn mpt_circuit_account_does_not_exist_in_leaf() {
+        // The key of the address diverges from the key of the only leaf of
+        // the trie.
+        let leaf = account_leaf(Address::repeat_byte(0x11), 0, &Account::new(1, 0));
+        let update = MptUpdateWitness {
+            update: MptUpdate::account_does_not_exist(Address::repeat_byte(0x33), root(&leaf)),
+            old_proof: proof(vec![leaf.clone()]),
+            new_proof: proof(vec![leaf]),
+        };
+        assert_eq!(run(vec![update]), Ok(()));
+    }
+
+    #[test]
+    fn mpt_circuit_account_exists() {
+        let address = Address::repeat_byte(0x11);
+        let leaf = account_leaf(address, 0, &Account::new(1, 0));
+        let update = MptUpdateWitness {
+            update: MptUpdate::account_does_not_exist(address, root(&leaf)),
+            old_proof: proof(vec![leaf.clone()]),
+            new_proof: proof(vec![leaf]),
+        };
+        assert!(run(vec![update]).is_err());
+    }
+
+    #[test]
+    fn mpt_circuit_wrong_value() {
+        let mut update = nonce_update(Address::repeat_byte(0x11), 0, 1);
+        // The new leaf has nonce 1
+        update.update = MptUpdate::account(
+            Address::repeat_byte(0x11),
+            AccountFieldTag::Nonce,
+            0.into(),
+            2.into(),
+            update.update.old_root(),
+            update.update.new_root(),
+        );
+        assert!(run(vec![update]).is_err());
+    }
+
+    #[test]
+    fn mpt_circuit_wrong_root() {
+        let mut update = nonce_update(Address::repeat_byte(0x11), 0, 1);
+        update.new_proof = update.old_proof.clone();
+        assert!(run(vec![update]).is_err());
+    }
+
+    #[test]
+    fn mpt_circuit_wrong_key() {
+        // The proofs are of the leaf of another address, with the same
+        // values and roots.
+        let mut update = nonce_update(Address::repeat_byte(0x44), 0, 1);
+        update.update = MptUpdate::account(
+            Address::repeat_byte(0x11),
+            AccountFieldTag::Nonce,
+            0.into(),
+            1.into(),
+            update.update.old_root(),
+            update.update.new_root(),
+        );
+        assert!(run(vec![update]).is_err());
+    }
+}
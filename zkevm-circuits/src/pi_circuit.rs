@@ -6,16 +6,17 @@ use eth_types::geth_types::BlockConstants;
 use eth_types::geth_types::GethData;
 use eth_types::sign_types::SignData;
 use eth_types::H256;
-use eth_types::{
-    geth_types::Transaction, Address, Field, ToBigEndian, ToLittleEndian, ToScalar, Word,
-};
+use eth_types::{geth_types::Transaction, Address, Field, ToBigEndian, Word};
 use ethers_core::types::Block;
-use halo2_proofs::plonk::Instance;
+use halo2_proofs::plonk::{Expression, Fixed, Instance, TableColumn};
+use keccak256::plain::Keccak;
 
+use crate::table::BlockContextFieldTag;
 use crate::table::BlockTable;
+use crate::table::KeccakTable;
 use crate::table::TxFieldTag;
 use crate::table::TxTable;
-use crate::util::random_linear_combine_word as rlc;
+use crate::util::{power_of_randomness_from_instance, Challenges};
 use halo2_proofs::{
     circuit::{AssignedCell, Layouter, Region, SimpleFloorPlanner, Value},
     plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Selector},
@@ -23,9 +24,17 @@ use halo2_proofs::{
 };
 
 /// Fixed by the spec
-const TX_LEN: usize = 9;
-const BLOCK_LEN: usize = 7 + 256;
-const EXTRA_LEN: usize = 2;
+const TX_LEN: usize = 10;
+const NUM_HISTORY_HASHES: usize = 256;
+const BLOCK_LEN: usize = 9 + NUM_HISTORY_HASHES;
+const EXTRA_LEN: usize = 3;
+
+/// Offset of the block number in the block table
+const BLOCK_NUMBER_OFFSET: usize = 3;
+/// Offset of the first previous block hash in the block table
+const BLOCK_HASHES_OFFSET: usize = 10;
+/// Offset of the chain id in the block table
+const CHAIN_ID_OFFSET: usize = 7;
 
 /// Values of the block table (as in the spec)
 #[derive(Clone, Default, Debug)]
@@ -37,6 +46,8 @@ pub struct BlockValues {
     difficulty: Word,
     base_fee: Word, // NOTE: BaseFee was added by EIP-1559 and is ignored in legacy headers.
     chain_id: u64,
+    num_txs: u64,
+    cum_num_txs: u64,
     history_hashes: Vec<H256>,
 }
 
@@ -44,24 +55,61 @@ pub struct BlockValues {
 #[derive(Default, Debug, Clone)]
 pub struct TxValues {
     nonce: Word,
-    gas: Word, //gas limit
+    gas: u64, //gas limit
     gas_price: Word,
     from_addr: Address,
     to_addr: Address,
     is_create: u64,
     value: Word,
     call_data_len: u64,
+    call_data_gas_cost: u64,
     tx_sign_hash: [u8; 32],
 }
 
 /// Extra values (not contained in block or tx tables)
 #[derive(Default, Debug, Clone)]
 pub struct ExtraValues {
-    // block_hash: H256,
+    block_hash: H256,
     state_root: H256,
     prev_state_root: H256,
 }
 
+/// A raw public input value, given by the big-endian bytes it's built from.
+/// Values which fit in the field are the integer of their bytes, the others
+/// are the RLC of their bytes, as in the block and tx tables.
+#[derive(Clone, Debug)]
+struct RpiField {
+    bytes: Vec<u8>,
+    is_rlc: bool,
+}
+
+impl RpiField {
+    fn int(bytes: &[u8]) -> Self {
+        Self {
+            bytes: bytes.to_vec(),
+            is_rlc: false,
+        }
+    }
+
+    fn rlc(bytes: &[u8]) -> Self {
+        Self {
+            bytes: bytes.to_vec(),
+            is_rlc: true,
+        }
+    }
+
+    fn value<F: Field>(&self, randomness: F) -> F {
+        let base = if self.is_rlc {
+            randomness
+        } else {
+            F::from(256)
+        };
+        self.bytes
+            .iter()
+            .fold(F::zero(), |acc, byte| acc * base + F::from(*byte as u64))
+    }
+}
+
 /// PublicData contains all the values that the PiCircuit recieves as input
 #[derive(Debug, Clone)]
 pub struct PublicData {
@@ -96,13 +144,17 @@ impl Default for PublicData {
 impl PublicData {
     /// Returns struct with values for the block table
     pub fn get_block_table_values(&self) -> BlockValues {
-        let mut history_hashes: Vec<H256> = self
-            .extra
-            .history_hashes
-            .iter()
-            .map(|&hash| H256::from(hash.to_be_bytes()))
-            .collect();
-        history_hashes.extend(vec![H256::zero(); 256 - history_hashes.len()]);
+        // The hashes of the previous blocks are sorted by block number, and
+        // the missing ones are zero.
+        let mut history_hashes = vec![H256::zero(); NUM_HISTORY_HASHES];
+        let num_history_hashes = self.extra.history_hashes.len();
+        assert!(num_history_hashes <= NUM_HISTORY_HASHES);
+        for (history_hash, hash) in history_hashes[NUM_HISTORY_HASHES - num_history_hashes..]
+            .iter_mut()
+            .zip(self.extra.history_hashes.iter())
+        {
+            *history_hash = H256::from(hash.to_be_bytes());
+        }
         BlockValues {
             coinbase: self.block_constants.coinbase,
             gas_limit: self.block_constants.gas_limit.as_u64(),
@@ -111,6 +163,9 @@ impl PublicData {
             difficulty: self.block_constants.difficulty,
             base_fee: self.block_constants.base_fee,
             chain_id: self.extra.chain_id.as_u64(),
+            // The circuit proves a single block
+            num_txs: self.txs.len() as u64,
+            cum_num_txs: self.txs.len() as u64,
             history_hashes,
         }
     }
@@ -132,12 +187,17 @@ impl PublicData {
             tx_vals.push(TxValues {
                 nonce: tx.nonce,
                 gas_price: tx.gas_price,
-                gas: tx.gas_limit,
+                gas: tx.gas_limit.as_u64(),
                 from_addr: tx.from,
                 to_addr: tx.to.unwrap_or_else(Address::zero),
                 is_create: (tx.to.is_none() as u64),
                 value: tx.value,
                 call_data_len: tx.call_data.0.len() as u64,
+                call_data_gas_cost: tx
+                    .call_data
+                    .0
+                    .iter()
+                    .fold(0, |acc, byte| acc + if *byte == 0 { 4 } else { 16 }),
                 tx_sign_hash: msg_hash_le,
             });
         }
@@ -147,19 +207,182 @@ impl PublicData {
     /// Returns struct with the extra values
     pub fn get_extra_values(&self) -> ExtraValues {
         ExtraValues {
-            // block_hash: self.extra.eth_block.hash.unwrap_or_else(H256::zero),
+            block_hash: self.extra.eth_block.hash.unwrap_or_else(H256::zero),
             state_root: self.extra.eth_block.state_root,
             prev_state_root: self.prev_state_root,
         }
     }
+
+    /// Returns the tag and the value of each row of the block table, in the
+    /// same layout as the one used by the EVM Circuit.  The zero row has no
+    /// value.
+    fn block_table_rows(&self) -> Vec<(u64, Option<RpiField>)> {
+        let block = self.get_block_table_values();
+        [
+            (0, None),
+            (
+                BlockContextFieldTag::Coinbase as u64,
+                Some(RpiField::int(block.coinbase.as_bytes())),
+            ),
+            (
+                BlockContextFieldTag::Timestamp as u64,
+                Some(RpiField::int(&block.timestamp.to_be_bytes())),
+            ),
+            (
+                BlockContextFieldTag::Number as u64,
+                Some(RpiField::int(&block.number.to_be_bytes())),
+            ),
+            (
+                BlockContextFieldTag::Difficulty as u64,
+                Some(RpiField::rlc(&block.difficulty.to_be_bytes())),
+            ),
+            (
+                BlockContextFieldTag::GasLimit as u64,
+                Some(RpiField::int(&block.gas_limit.to_be_bytes())),
+            ),
+            (
+                BlockContextFieldTag::BaseFee as u64,
+                Some(RpiField::rlc(&block.base_fee.to_be_bytes())),
+            ),
+            (
+                BlockContextFieldTag::ChainId as u64,
+                Some(RpiField::rlc(&Word::from(block.chain_id).to_be_bytes())),
+            ),
+            (
+                BlockContextFieldTag::NumTxs as u64,
+                Some(RpiField::int(&block.num_txs.to_be_bytes())),
+            ),
+            (
+                BlockContextFieldTag::CumNumTxs as u64,
+                Some(RpiField::int(&block.cum_num_txs.to_be_bytes())),
+            ),
+        ]
+        .into_iter()
+        .chain(block.history_hashes.iter().map(|hash| {
+            (
+                BlockContextFieldTag::BlockHash as u64,
+                Some(RpiField::rlc(hash.as_bytes())),
+            )
+        }))
+        .collect()
+    }
+
+    /// Returns the tag and the `[tx_id, index, value]` of each row of the tx
+    /// table, in the same layout as the one used by the Tx Circuit.  The zero
+    /// row has no values.
+    fn tx_table_rows(
+        &self,
+        max_txs: usize,
+        max_calldata: usize,
+    ) -> Vec<(TxFieldTag, Option<[RpiField; 3]>)> {
+        let txs = self.get_tx_table_values();
+        assert!(txs.len() <= max_txs);
+        let tx_default = TxValues::default();
+        let int = |value: u64| RpiField::int(&value.to_be_bytes());
+
+        let mut rows = vec![(TxFieldTag::Null, None)];
+        for i in 0..max_txs {
+            let tx = if i < txs.len() { &txs[i] } else { &tx_default };
+            let mut tx_sign_hash_be = tx.tx_sign_hash;
+            tx_sign_hash_be.reverse();
+
+            for (tag, value) in [
+                (TxFieldTag::Nonce, RpiField::rlc(&tx.nonce.to_be_bytes())),
+                (TxFieldTag::Gas, int(tx.gas)),
+                (
+                    TxFieldTag::GasPrice,
+                    RpiField::rlc(&tx.gas_price.to_be_bytes()),
+                ),
+                (
+                    TxFieldTag::CallerAddress,
+                    RpiField::int(tx.from_addr.as_bytes()),
+                ),
+                (
+                    TxFieldTag::CalleeAddress,
+                    RpiField::int(tx.to_addr.as_bytes()),
+                ),
+                (TxFieldTag::IsCreate, RpiField::int(&[tx.is_create as u8])),
+                (TxFieldTag::Value, RpiField::rlc(&tx.value.to_be_bytes())),
+                (TxFieldTag::CallDataLength, int(tx.call_data_len)),
+                (TxFieldTag::CallDataGasCost, int(tx.call_data_gas_cost)),
+                (TxFieldTag::TxSignHash, RpiField::rlc(&tx_sign_hash_be)),
+            ] {
+                rows.push((tag, Some([int(i as u64 + 1), int(0), value])));
+            }
+        }
+        // Tx Table CallData
+        let mut calldata_count = 0;
+        for (i, tx) in self.txs.iter().enumerate() {
+            for (index, byte) in tx.call_data.0.iter().enumerate() {
+                assert!(calldata_count < max_calldata);
+                rows.push((
+                    TxFieldTag::CallData,
+                    Some([
+                        int(i as u64 + 1),
+                        int(index as u64),
+                        RpiField::int(&[*byte]),
+                    ]),
+                ));
+                calldata_count += 1;
+            }
+        }
+        for _ in calldata_count..max_calldata {
+            rows.push((
+                TxFieldTag::CallData,
+                Some([int(0), int(0), RpiField::int(&[0])]),
+            ));
+        }
+        rows
+    }
+
+    /// Returns the raw public inputs in the order of the raw_public_inputs
+    /// column: the block table values, the extra values and then the tx table
+    /// tx_id, index and value columns.  The zero rows of the tables have no
+    /// value.
+    fn raw_public_inputs(&self, max_txs: usize, max_calldata: usize) -> Vec<Option<RpiField>> {
+        let extra = self.get_extra_values();
+        let tx_table_rows = self.tx_table_rows(max_txs, max_calldata);
+
+        let mut raw_public_inputs: Vec<Option<RpiField>> = self
+            .block_table_rows()
+            .into_iter()
+            .map(|(_, value)| value)
+            .collect();
+        raw_public_inputs.extend(
+            [extra.block_hash, extra.state_root, extra.prev_state_root]
+                .map(|hash| Some(RpiField::rlc(hash.as_bytes()))),
+        );
+        for column in 0..3 {
+            raw_public_inputs.extend(
+                tx_table_rows
+                    .iter()
+                    .map(|(_, values)| values.as_ref().map(|values| values[column].clone())),
+            );
+        }
+        raw_public_inputs
+    }
+
+    /// Returns the bytes of the raw public inputs, which are hashed with
+    /// keccak to get the public input of the circuit.
+    pub fn rpi_bytes(&self, max_txs: usize, max_calldata: usize) -> Vec<u8> {
+        self.raw_public_inputs(max_txs, max_calldata)
+            .into_iter()
+            .flatten()
+            .flat_map(|field| field.bytes)
+            .collect()
+    }
 }
 
 /// Config for PiCircuit
 #[derive(Clone, Debug)]
 pub struct PiCircuitConfig<F: Field, const MAX_TXS: usize, const MAX_CALLDATA: usize> {
     q_block_table: Selector,
+    q_block_hash_first: Selector,
+    q_block_hash: Selector,
+    block_tag: Column<Fixed>,
     block_table: BlockTable,
     q_tx_table: Selector,
+    tx_tag: Column<Fixed>,
     tx_table: TxTable,
     raw_public_inputs: Column<Advice>,
     rpi_rlc_acc: Column<Advice>,
@@ -167,7 +390,21 @@ pub struct PiCircuitConfig<F: Field, const MAX_TXS: usize, const MAX_CALLDATA: u
     q_not_end: Selector,
     q_end: Selector,
 
-    pi: Column<Instance>, // rpi_rand, rpi_rlc, chain_ID, state_root, prev_state_root
+    // Bytes of the raw public inputs, hashed with keccak
+    q_rpi_byte: Selector,
+    rpi_field_start: Column<Fixed>,
+    rpi_field_is_rlc: Column<Fixed>,
+    rpi_byte: Column<Advice>,
+    rpi_field_acc: Column<Advice>,
+    q_keccak_input_first: Selector,
+    q_keccak_input: Selector,
+    keccak_input_rlc: Column<Advice>,
+    q_keccak: Selector,
+    byte_table: TableColumn,
+    keccak_table: KeccakTable,
+
+    // rpi_rand, rpi_rlc, chain_ID, state_root, prev_state_root, pi_hash_hi, pi_hash_lo
+    pi: Column<Instance>,
 
     _marker: PhantomData<F>,
 }
@@ -175,10 +412,22 @@ pub struct PiCircuitConfig<F: Field, const MAX_TXS: usize, const MAX_CALLDATA: u
 impl<F: Field, const MAX_TXS: usize, const MAX_CALLDATA: usize>
     PiCircuitConfig<F, MAX_TXS, MAX_CALLDATA>
 {
-    fn new(meta: &mut ConstraintSystem<F>, block_table: BlockTable, tx_table: TxTable) -> Self {
+    /// Return a new PiCircuitConfig, which assigns the block table and the tx
+    /// table shared with the other circuits.
+    pub fn new(
+        meta: &mut ConstraintSystem<F>,
+        block_table: BlockTable,
+        tx_table: TxTable,
+        keccak_table: KeccakTable,
+        randomness: Expression<F>,
+    ) -> Self {
         let q_block_table = meta.selector();
+        let q_block_hash_first = meta.selector();
+        let q_block_hash = meta.selector();
+        let block_tag = meta.fixed_column();
 
         let q_tx_table = meta.selector();
+        let tx_tag = meta.fixed_column();
 
         let raw_public_inputs = meta.advice_column();
         let rpi_rlc_acc = meta.advice_column();
@@ -186,11 +435,26 @@ impl<F: Field, const MAX_TXS: usize, const MAX_CALLDATA: usize>
         let q_not_end = meta.selector();
         let q_end = meta.selector();
 
+        let q_rpi_byte = meta.complex_selector();
+        let rpi_field_start = meta.fixed_column();
+        let rpi_field_is_rlc = meta.fixed_column();
+        let rpi_byte = meta.advice_column();
+        let rpi_field_acc = meta.advice_column();
+        let q_keccak_input_first = meta.selector();
+        let q_keccak_input = meta.selector();
+        let keccak_input_rlc = meta.advice_column();
+        let q_keccak = meta.complex_selector();
+        let byte_table = meta.lookup_table_column();
+
         let pi = meta.instance_column();
 
         meta.enable_equality(raw_public_inputs);
         meta.enable_equality(rpi_rlc_acc);
         meta.enable_equality(rand_rpi);
+        meta.enable_equality(rpi_byte);
+        meta.enable_equality(rpi_field_acc);
+        meta.enable_equality(tx_table.value);
+        meta.enable_equality(block_table.index);
         meta.enable_equality(pi);
 
         // 0.0 rpi_rlc_acc[0] == RLC(raw_public_inputs, rand_rpi)
@@ -228,18 +492,56 @@ impl<F: Field, const MAX_TXS: usize, const MAX_CALLDATA: usize>
         });
 
         // 0.2 Block table -> value column match with raw_public_inputs at expected
-        // offset
+        // offset, and tag column match with the fixed layout
         meta.create_gate("block_table[i] = raw_public_inputs[offset + i]", |meta| {
             let q_block_table = meta.query_selector(q_block_table);
             let block_value = meta.query_advice(block_table.value, Rotation::cur());
             let rpi_block_value = meta.query_advice(raw_public_inputs, Rotation::cur());
-            vec![q_block_table * (block_value - rpi_block_value)]
+            let tag = meta.query_advice(block_table.tag, Rotation::cur());
+            let expected_tag = meta.query_fixed(block_tag, Rotation::cur());
+
+            vec![
+                q_block_table.clone() * (block_value - rpi_block_value),
+                q_block_table * (tag - expected_tag),
+            ]
+        });
+
+        // 0.3 Block table -> index column is the block number of the hash for
+        // the previous block hashes.  The index of the block fields is copied
+        // from the raw public input of the block number, and the one of the
+        // zero row from its zero raw public input.
+        meta.create_gate("block_table.index", |meta| {
+            let q_block_hash_first = meta.query_selector(q_block_hash_first);
+            let q_block_hash = meta.query_selector(q_block_hash);
+            let index = meta.query_advice(block_table.index, Rotation::cur());
+            let index_prev = meta.query_advice(block_table.index, Rotation::prev());
+            let number = meta.query_advice(
+                block_table.value,
+                Rotation(BLOCK_NUMBER_OFFSET as i32 - BLOCK_HASHES_OFFSET as i32),
+            );
+
+            vec![
+                q_block_hash_first
+                    * (index.clone() - number
+                        + Expression::Constant(F::from(NUM_HISTORY_HASHES as u64))),
+                q_block_hash * (index - index_prev - Expression::Constant(F::one())),
+            ]
+        });
+
+        // 0.4 Tx table -> {tx_id, index, value} column match with raw_public_inputs
+        // at expected offset, and tag column match with the fixed layout
+        meta.create_gate("tx_table.tag = tx_tag", |meta| {
+            let q_tx_table = meta.query_selector(q_tx_table);
+            let tag = meta.query_advice(tx_table.tag, Rotation::cur());
+            let expected_tag = meta.query_fixed(tx_tag, Rotation::cur());
+
+            vec![q_tx_table * (tag - expected_tag)]
         });
 
         let offset = BLOCK_LEN + 1 + EXTRA_LEN;
         let tx_table_len = MAX_TXS * TX_LEN + 1 + MAX_CALLDATA;
 
-        //  0.3 Tx table -> {tx_id, index, value} column match with raw_public_inputs
+        //  0.4.1 Tx table -> {tx_id, index, value} column match with raw_public_inputs
         // at expected offset
         meta.create_gate(
             "tx_table.tx_id[i] == raw_public_inputs[offset + i]",
@@ -284,16 +586,105 @@ impl<F: Field, const MAX_TXS: usize, const MAX_CALLDATA: usize>
             },
         );
 
+        // 1.0 Each raw public input is built from its bytes, either as an
+        // integer or as a RLC
+        meta.lookup("rpi_byte is a byte", |meta| {
+            let q_rpi_byte = meta.query_selector(q_rpi_byte);
+            let rpi_byte = meta.query_advice(rpi_byte, Rotation::cur());
+
+            vec![(q_rpi_byte * rpi_byte, byte_table)]
+        });
+        meta.create_gate(
+            "rpi_field_acc[i] = rpi_field_acc[i-1] * base + rpi_byte[i]",
+            |meta| {
+                let q_rpi_byte = meta.query_selector(q_rpi_byte);
+                let field_start = meta.query_fixed(rpi_field_start, Rotation::cur());
+                let field_is_rlc = meta.query_fixed(rpi_field_is_rlc, Rotation::cur());
+                let rpi_byte = meta.query_advice(rpi_byte, Rotation::cur());
+                let acc = meta.query_advice(rpi_field_acc, Rotation::cur());
+                let acc_prev = meta.query_advice(rpi_field_acc, Rotation::prev());
+
+                let one = Expression::Constant(F::one());
+                let base = field_is_rlc.clone() * randomness.clone()
+                    + (one.clone() - field_is_rlc) * Expression::Constant(F::from(256));
+
+                vec![q_rpi_byte * (acc - ((one - field_start) * acc_prev * base + rpi_byte))]
+            },
+        );
+
+        // 1.1 keccak_input_rlc[last] = RLC(rpi_bytes), as in the keccak table
+        meta.create_gate("keccak_input_rlc[0] = rpi_byte[0]", |meta| {
+            let q_keccak_input_first = meta.query_selector(q_keccak_input_first);
+            let rpi_byte = meta.query_advice(rpi_byte, Rotation::cur());
+            let input_rlc = meta.query_advice(keccak_input_rlc, Rotation::cur());
+
+            vec![q_keccak_input_first * (input_rlc - rpi_byte)]
+        });
+        meta.create_gate(
+            "keccak_input_rlc[i] = keccak_input_rlc[i-1] * randomness + rpi_byte[i]",
+            |meta| {
+                let q_keccak_input = meta.query_selector(q_keccak_input);
+                let rpi_byte = meta.query_advice(rpi_byte, Rotation::cur());
+                let input_rlc = meta.query_advice(keccak_input_rlc, Rotation::cur());
+                let input_rlc_prev = meta.query_advice(keccak_input_rlc, Rotation::prev());
+
+                vec![
+                    q_keccak_input * (input_rlc - (input_rlc_prev * randomness.clone() + rpi_byte)),
+                ]
+            },
+        );
+
+        // 1.2 The digest of the rpi bytes follows them, as the RLC of its 32
+        // bytes, so that the field accumulated on the last byte of the digest is
+        // the output RLC of the keccak table.
+        meta.lookup_any("pi keccak lookup", |meta| {
+            let q_keccak = meta.query_selector(q_keccak);
+            let input_rlc = meta.query_advice(keccak_input_rlc, Rotation::cur());
+            let input_len = Expression::Constant(F::from(Self::rpi_bytes_len() as u64));
+            let output_rlc = meta.query_advice(rpi_field_acc, Rotation(32));
+
+            let input = [
+                q_keccak.clone(),
+                q_keccak.clone() * input_rlc,
+                q_keccak.clone() * input_len,
+                q_keccak * output_rlc,
+            ];
+            let table = [
+                keccak_table.is_enabled,
+                keccak_table.input_rlc,
+                keccak_table.input_len,
+                keccak_table.output_rlc,
+            ]
+            .map(|column| meta.query_advice(column, Rotation::cur()));
+
+            input.into_iter().zip(table).collect()
+        });
+
         Self {
             q_block_table,
+            q_block_hash_first,
+            q_block_hash,
+            block_tag,
             block_table,
             q_tx_table,
+            tx_tag,
             tx_table,
             raw_public_inputs,
             rpi_rlc_acc,
             rand_rpi,
             q_not_end,
             q_end,
+            q_rpi_byte,
+            rpi_field_start,
+            rpi_field_is_rlc,
+            rpi_byte,
+            rpi_field_acc,
+            q_keccak_input_first,
+            q_keccak_input,
+            keccak_input_rlc,
+            q_keccak,
+            byte_table,
+            keccak_table,
             pi,
             _marker: PhantomData,
         }
@@ -306,298 +697,158 @@ impl<F: Field, const MAX_TXS: usize, const MAX_CALLDATA: usize>
         BLOCK_LEN + 1 + EXTRA_LEN + 3 * (TX_LEN * MAX_TXS + 1 + MAX_CALLDATA)
     }
 
-    /// Assigns a tx_table row and stores the values in a vec for the
-    /// raw_public_inputs column
-    #[allow(clippy::too_many_arguments)]
-    fn assign_tx_row(
-        &self,
-        region: &mut Region<'_, F>,
-        offset: usize,
-        tx_id: usize,
-        tag: TxFieldTag,
-        index: usize,
-        tx_value: F,
-        raw_pi_vals: &mut [F],
-    ) -> Result<(), Error> {
-        let tx_id = F::from(tx_id as u64);
-        let tag = F::from(tag as u64);
-        let index = F::from(index as u64);
-
-        self.q_tx_table.enable(region, offset)?;
-
-        // Assign vals to Tx_table
-        region.assign_advice(
-            || "tx_id",
-            self.tx_table.tx_id,
-            offset,
-            || Value::known(tx_id),
-        )?;
-        region.assign_advice(|| "tag", self.tx_table.tag, offset, || Value::known(tag))?;
-        region.assign_advice(
-            || "index",
-            self.tx_table.index,
-            offset,
-            || Value::known(index),
-        )?;
-        region.assign_advice(
-            || "tx_value",
-            self.tx_table.value,
-            offset,
-            || Value::known(tx_value),
-        )?;
-
-        // Assign vals to raw_public_inputs column
-        let tx_table_len = TX_LEN * MAX_TXS + 1 + MAX_CALLDATA;
-
-        let id_offset = BLOCK_LEN + 1 + EXTRA_LEN;
-        let index_offset = id_offset + tx_table_len;
-        let value_offset = index_offset + tx_table_len;
-
-        region.assign_advice(
-            || "raw_pi.tx_id",
-            self.raw_public_inputs,
-            offset + id_offset,
-            || Value::known(tx_id),
-        )?;
-
-        region.assign_advice(
-            || "raw_pi.tx_index",
-            self.raw_public_inputs,
-            offset + index_offset,
-            || Value::known(index),
-        )?;
-
-        region.assign_advice(
-            || "raw_pi.tx_value",
-            self.raw_public_inputs,
-            offset + value_offset,
-            || Value::known(tx_value),
-        )?;
+    /// Return the number of bytes of the raw public inputs, which doesn't
+    /// depend on their values.
+    fn rpi_bytes_len() -> usize {
+        PublicData::default().rpi_bytes(MAX_TXS, MAX_CALLDATA).len()
+    }
 
-        // Add copy to vec
-        raw_pi_vals[offset + id_offset] = tx_id;
-        raw_pi_vals[offset + index_offset] = index;
-        raw_pi_vals[offset + value_offset] = tx_value;
+    /// Get number of rows required.
+    pub fn get_num_rows_required() -> usize {
+        // The rpi bytes are followed by the 32 bytes of their digest, and the
+        // 16 bytes of its hi and lo halves.
+        Self::circuit_len().max(Self::rpi_bytes_len() + 64)
+    }
 
-        Ok(())
+    /// Load the byte table used to range check the rpi bytes.
+    pub fn load(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_table(
+            || "byte table",
+            |mut table| {
+                for byte in 0..256 {
+                    table.assign_cell(
+                        || "byte",
+                        self.byte_table,
+                        byte,
+                        || Value::known(F::from(byte as u64)),
+                    )?;
+                }
+                Ok(())
+            },
+        )
     }
 
-    /// Assigns the values for block table in the block_table column
-    /// and in the raw_public_inputs column. A copy is also stored in
-    /// a vector for computing RLC(raw_public_inputs)
+    /// Assigns the block table rows, whose values are the raw public inputs
+    /// at the same offsets.
     fn assign_block_table(
         &self,
         region: &mut Region<'_, F>,
-        block_values: BlockValues,
-        randomness: F,
-        raw_pi_vals: &mut [F],
-    ) -> Result<AssignedCell<F, F>, Error> {
-        let mut offset = 0;
-        for i in 0..BLOCK_LEN + 1 {
-            self.q_block_table.enable(region, offset + i)?;
+        rows: &[(u64, Option<RpiField>)],
+        number: u64,
+        raw_pi_vals: &[F],
+        rpi_cells: &[AssignedCell<F, F>],
+    ) -> Result<(), Error> {
+        for (offset, (tag, _)) in rows.iter().enumerate() {
+            self.q_block_table.enable(region, offset)?;
+            let index = if offset == 0 {
+                F::zero()
+            } else if offset < BLOCK_HASHES_OFFSET {
+                F::from(number)
+            } else {
+                if offset == BLOCK_HASHES_OFFSET {
+                    self.q_block_hash_first.enable(region, offset)?;
+                } else {
+                    self.q_block_hash.enable(region, offset)?;
+                }
+                // The block number of the hash, which is negative for the
+                // missing hashes of the first blocks.
+                F::from(number) + F::from((offset - BLOCK_HASHES_OFFSET) as u64)
+                    - F::from(NUM_HISTORY_HASHES as u64)
+            };
+
+            region.assign_fixed(
+                || "block_tag",
+                self.block_tag,
+                offset,
+                || Value::known(F::from(*tag)),
+            )?;
+            for (name, column, value) in [
+                ("tag", self.block_table.tag, F::from(*tag)),
+                ("value", self.block_table.value, raw_pi_vals[offset]),
+            ] {
+                region.assign_advice(|| name, column, offset, || Value::known(value))?;
+            }
+            let index_cell = region.assign_advice(
+                || "index",
+                self.block_table.index,
+                offset,
+                || Value::known(index),
+            )?;
+            if offset == 0 {
+                region.constrain_equal(index_cell.cell(), rpi_cells[0].cell())?;
+            } else if offset < BLOCK_HASHES_OFFSET {
+                region.constrain_equal(index_cell.cell(), rpi_cells[BLOCK_NUMBER_OFFSET].cell())?;
+            }
         }
 
-        // zero row
-        region.assign_advice(
-            || "zero",
-            self.block_table.value,
-            offset,
-            || Value::known(F::zero()),
-        )?;
-        region.assign_advice(
-            || "zero",
-            self.raw_public_inputs,
-            offset,
-            || Value::known(F::zero()),
-        )?;
-        raw_pi_vals[offset] = F::zero();
-        offset += 1;
-
-        // coinbase
-        let coinbase = block_values.coinbase.to_scalar().unwrap();
-        region.assign_advice(
-            || "coinbase",
-            self.block_table.value,
-            offset,
-            || Value::known(coinbase),
-        )?;
-        region.assign_advice(
-            || "coinbase",
-            self.raw_public_inputs,
-            offset,
-            || Value::known(coinbase),
-        )?;
-        raw_pi_vals[offset] = coinbase;
-        offset += 1;
-
-        // gas_limit
-        let gas_limit = F::from(block_values.gas_limit);
-        region.assign_advice(
-            || "gas_limit",
-            self.block_table.value,
-            offset,
-            || Value::known(gas_limit),
-        )?;
-        region.assign_advice(
-            || "gas_limit",
-            self.raw_public_inputs,
-            offset,
-            || Value::known(gas_limit),
-        )?;
-        raw_pi_vals[offset] = gas_limit;
-        offset += 1;
-
-        // number
-        let number = F::from(block_values.number);
-        region.assign_advice(
-            || "number",
-            self.block_table.value,
-            offset,
-            || Value::known(number),
-        )?;
-        region.assign_advice(
-            || "number",
-            self.raw_public_inputs,
-            offset,
-            || Value::known(number),
-        )?;
-        raw_pi_vals[offset] = number;
-        offset += 1;
-
-        // timestamp
-        let timestamp = F::from(block_values.timestamp);
-        region.assign_advice(
-            || "timestamp",
-            self.block_table.value,
-            offset,
-            || Value::known(timestamp),
-        )?;
-        region.assign_advice(
-            || "timestamp",
-            self.raw_public_inputs,
-            offset,
-            || Value::known(timestamp),
-        )?;
-        raw_pi_vals[offset] = timestamp;
-        offset += 1;
+        Ok(())
+    }
 
-        // difficulty
-        let difficulty = rlc(block_values.difficulty.to_le_bytes(), randomness);
-        region.assign_advice(
-            || "difficulty",
-            self.block_table.value,
-            offset,
-            || Value::known(difficulty),
-        )?;
-        region.assign_advice(
-            || "difficulty",
-            self.raw_public_inputs,
-            offset,
-            || Value::known(difficulty),
-        )?;
-        raw_pi_vals[offset] = difficulty;
-        offset += 1;
+    /// Assigns the tx table rows, whose tx_id, index and value columns are
+    /// the raw public inputs at the expected offsets.  Returns the
+    /// `CallerAddress` and `TxSignHash` cells of each tx, which are constrained
+    /// by the Tx Circuit.
+    fn assign_tx_table(
+        &self,
+        region: &mut Region<'_, F>,
+        tags: &[TxFieldTag],
+        raw_pi_vals: &[F],
+    ) -> Result<Vec<[AssignedCell<F, F>; 2]>, Error> {
+        let tx_table_len = TX_LEN * MAX_TXS + 1 + MAX_CALLDATA;
+        assert_eq!(tags.len(), tx_table_len);
 
-        // base_fee
-        let base_fee = rlc(block_values.base_fee.to_le_bytes(), randomness);
-        region.assign_advice(
-            || "base_fee",
-            self.block_table.value,
-            offset,
-            || Value::known(base_fee),
-        )?;
-        region.assign_advice(
-            || "base_fee",
-            self.raw_public_inputs,
-            offset,
-            || Value::known(base_fee),
-        )?;
-        raw_pi_vals[offset] = base_fee;
-        offset += 1;
+        let id_offset = BLOCK_LEN + 1 + EXTRA_LEN;
+        let index_offset = id_offset + tx_table_len;
+        let value_offset = index_offset + tx_table_len;
 
-        // chain_id
-        let chain_id = F::from(block_values.chain_id);
-        region.assign_advice(
-            || "chain_id",
-            self.block_table.value,
-            offset,
-            || Value::known(chain_id),
-        )?;
-        let chain_id_cell = region.assign_advice(
-            || "chain_id",
-            self.raw_public_inputs,
-            offset,
-            || Value::known(chain_id),
-        )?;
-        raw_pi_vals[offset] = chain_id;
-        offset += 1;
+        let mut caller_address_cells = Vec::new();
+        let mut tx_sign_hash_cells = Vec::new();
+        for (offset, tag) in tags.iter().enumerate() {
+            self.q_tx_table.enable(region, offset)?;
 
-        for prev_hash in block_values.history_hashes {
-            let prev_hash = rlc(prev_hash.to_fixed_bytes(), randomness);
+            region.assign_fixed(
+                || "tx_tag",
+                self.tx_tag,
+                offset,
+                || Value::known(F::from(*tag as u64)),
+            )?;
             region.assign_advice(
-                || "prev_hash",
-                self.block_table.value,
+                || "tag",
+                self.tx_table.tag,
                 offset,
-                || Value::known(prev_hash),
+                || Value::known(F::from(*tag as u64)),
             )?;
             region.assign_advice(
-                || "prev_hash",
-                self.raw_public_inputs,
+                || "tx_id",
+                self.tx_table.tx_id,
                 offset,
-                || Value::known(prev_hash),
+                || Value::known(raw_pi_vals[offset + id_offset]),
+            )?;
+            region.assign_advice(
+                || "index",
+                self.tx_table.index,
+                offset,
+                || Value::known(raw_pi_vals[offset + index_offset]),
+            )?;
+            let value_cell = region.assign_advice(
+                || "tx_value",
+                self.tx_table.value,
+                offset,
+                || Value::known(raw_pi_vals[offset + value_offset]),
             )?;
-            raw_pi_vals[offset] = prev_hash;
-            offset += 1;
-        }
-
-        Ok(chain_id_cell)
-    }
 
-    /// Assigns the extra fields (not in block or tx tables):
-    ///   - state root
-    ///   - previous block state root
-    /// to the raw_public_inputs column and stores a copy in a
-    /// vector for computing RLC(raw_public_inputs).
-    fn assign_extra_fields(
-        &self,
-        region: &mut Region<'_, F>,
-        extra: ExtraValues,
-        randomness: F,
-        raw_pi_vals: &mut [F],
-    ) -> Result<[AssignedCell<F, F>; 2], Error> {
-        let mut offset = BLOCK_LEN + 1;
-        // block hash
-        // let block_hash = rlc(extra.block_hash.to_fixed_bytes(), randomness);
-        // region.assign_advice(
-        //     || "block.hash",
-        //     self.raw_public_inputs,
-        //     offset,
-        //     || Ok(block_hash),
-        // )?;
-        // raw_pi_vals[offset] = block_hash;
-        // offset += 1;
-
-        // block state root
-        let state_root = rlc(extra.state_root.to_fixed_bytes(), randomness);
-        let state_root_cell = region.assign_advice(
-            || "state.root",
-            self.raw_public_inputs,
-            offset,
-            || Value::known(state_root),
-        )?;
-        raw_pi_vals[offset] = state_root;
-        offset += 1;
+            match tag {
+                TxFieldTag::CallerAddress => caller_address_cells.push(value_cell),
+                TxFieldTag::TxSignHash => tx_sign_hash_cells.push(value_cell),
+                _ => (),
+            }
+        }
 
-        // previous block state root
-        let prev_state_root = rlc(extra.prev_state_root.to_fixed_bytes(), randomness);
-        let prev_state_root_cell = region.assign_advice(
-            || "parent_block.hash",
-            self.raw_public_inputs,
-            offset,
-            || Value::known(prev_state_root),
-        )?;
-        raw_pi_vals[offset] = prev_state_root;
-        Ok([state_root_cell, prev_state_root_cell])
+        Ok(caller_address_cells
+            .into_iter()
+            .zip(tx_sign_hash_cells)
+            .map(|(caller_address, tx_sign_hash)| [caller_address, tx_sign_hash])
+            .collect())
     }
 
     /// Assign `rpi_rlc_acc` and `rand_rpi` columns
@@ -661,10 +912,146 @@ impl<F: Field, const MAX_TXS: usize, const MAX_CALLDATA: usize>
         self.q_not_end.enable(region, 0)?;
         Ok((rpi_rand, rpi_rlc))
     }
+
+    /// Assigns the bytes of a raw public input starting at `offset`,
+    /// accumulating them into its value.  Returns the cells of the bytes and
+    /// the cell of the value.
+    fn assign_rpi_field(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        field: &RpiField,
+        randomness: F,
+    ) -> Result<(Vec<AssignedCell<F, F>>, AssignedCell<F, F>), Error> {
+        let base = if field.is_rlc {
+            randomness
+        } else {
+            F::from(256)
+        };
+        let mut acc = F::zero();
+        let mut byte_cells = Vec::with_capacity(field.bytes.len());
+        let mut acc_cell = None;
+        for (i, byte) in field.bytes.iter().enumerate() {
+            let offset = offset + i;
+            acc = acc * base + F::from(*byte as u64);
+
+            self.q_rpi_byte.enable(region, offset)?;
+            region.assign_fixed(
+                || "rpi_field_start",
+                self.rpi_field_start,
+                offset,
+                || Value::known(F::from((i == 0) as u64)),
+            )?;
+            region.assign_fixed(
+                || "rpi_field_is_rlc",
+                self.rpi_field_is_rlc,
+                offset,
+                || Value::known(F::from(field.is_rlc as u64)),
+            )?;
+            byte_cells.push(region.assign_advice(
+                || "rpi_byte",
+                self.rpi_byte,
+                offset,
+                || Value::known(F::from(*byte as u64)),
+            )?);
+            acc_cell = Some(region.assign_advice(
+                || "rpi_field_acc",
+                self.rpi_field_acc,
+                offset,
+                || Value::known(acc),
+            )?);
+        }
+        Ok((
+            byte_cells,
+            acc_cell.expect("raw public input without bytes"),
+        ))
+    }
+
+    /// Assigns the bytes of the raw public inputs, constraining each value to
+    /// its raw_public_inputs cell, followed by the bytes of their keccak
+    /// digest and its hi and lo halves.  Returns the cells of the hi and lo
+    /// halves of the digest.
+    fn assign_rpi_bytes(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        rpi_fields: &[(RpiField, AssignedCell<F, F>)],
+        randomness: F,
+    ) -> Result<[AssignedCell<F, F>; 2], Error> {
+        let rpi_bytes: Vec<u8> = rpi_fields
+            .iter()
+            .flat_map(|(field, _)| field.bytes.clone())
+            .collect();
+        assert_eq!(rpi_bytes.len(), Self::rpi_bytes_len());
+        let digest = {
+            let mut keccak = Keccak::default();
+            keccak.update(&rpi_bytes);
+            keccak.digest()
+        };
+
+        layouter.assign_region(
+            || "pi keccak",
+            |mut region| {
+                let mut offset = 0;
+                for (field, rpi_cell) in rpi_fields {
+                    let (_, acc_cell) =
+                        self.assign_rpi_field(&mut region, offset, field, randomness)?;
+                    region.constrain_equal(acc_cell.cell(), rpi_cell.cell())?;
+                    offset += field.bytes.len();
+                }
+
+                // RLC of the keccak input
+                let mut input_rlc = F::zero();
+                for (offset, byte) in rpi_bytes.iter().enumerate() {
+                    if offset == 0 {
+                        self.q_keccak_input_first.enable(&mut region, offset)?;
+                    } else {
+                        self.q_keccak_input.enable(&mut region, offset)?;
+                    }
+                    input_rlc = input_rlc * randomness + F::from(*byte as u64);
+                    region.assign_advice(
+                        || "keccak_input_rlc",
+                        self.keccak_input_rlc,
+                        offset,
+                        || Value::known(input_rlc),
+                    )?;
+                }
+                self.q_keccak.enable(&mut region, offset - 1)?;
+
+                // Digest, and its hi and lo halves
+                let (digest_byte_cells, _) = self.assign_rpi_field(
+                    &mut region,
+                    offset,
+                    &RpiField::rlc(&digest),
+                    randomness,
+                )?;
+                offset += digest.len();
+                let mut digest_halves = Vec::with_capacity(2);
+                for (half, half_digest_byte_cells) in
+                    digest.chunks(16).zip(digest_byte_cells.chunks(16))
+                {
+                    let (byte_cells, half_cell) = self.assign_rpi_field(
+                        &mut region,
+                        offset,
+                        &RpiField::int(half),
+                        randomness,
+                    )?;
+                    for (byte_cell, digest_byte_cell) in
+                        byte_cells.iter().zip(half_digest_byte_cells)
+                    {
+                        region.constrain_equal(byte_cell.cell(), digest_byte_cell.cell())?;
+                    }
+                    offset += half.len();
+                    digest_halves.push(half_cell);
+                }
+
+                Ok(digest_halves.try_into().unwrap())
+            },
+        )
+    }
 }
 
 /// Public Inputs Circuit
-#[derive(Default)]
+#[derive(Default, Debug)]
 pub struct PiCircuit<F: Field, const MAX_TXS: usize, const MAX_CALLDATA: usize> {
     /// Randomness for RLC encdoing
     pub randomness: F,
@@ -676,163 +1063,172 @@ pub struct PiCircuit<F: Field, const MAX_TXS: usize, const MAX_CALLDATA: usize>
     pub public_data: PublicData,
 }
 
-impl<F: Field, const MAX_TXS: usize, const MAX_CALLDATA: usize> Circuit<F>
-    for PiCircuit<F, MAX_TXS, MAX_CALLDATA>
+impl<F: Field, const MAX_TXS: usize, const MAX_CALLDATA: usize>
+    PiCircuit<F, MAX_TXS, MAX_CALLDATA>
 {
-    type Config = PiCircuitConfig<F, MAX_TXS, MAX_CALLDATA>;
-    type FloorPlanner = SimpleFloorPlanner;
+    /// Compute the public inputs of the circuit, which are the values of its
+    /// `pi` instance column.
+    pub fn public_inputs(&self) -> Vec<F> {
+        let raw_public_inputs = self.public_data.raw_public_inputs(MAX_TXS, MAX_CALLDATA);
+        let raw_pi_vals: Vec<F> = raw_public_inputs
+            .iter()
+            .map(|field| {
+                field
+                    .as_ref()
+                    .map(|field| field.value(self.randomness))
+                    .unwrap_or_default()
+            })
+            .collect();
+        let rpi_rlc = raw_pi_vals
+            .iter()
+            .rev()
+            .fold(F::zero(), |acc, val| acc * self.rand_rpi + val);
 
-    fn without_witnesses(&self) -> Self {
-        Self::default()
-    }
+        let digest = {
+            let mut keccak = Keccak::default();
+            keccak.update(&self.public_data.rpi_bytes(MAX_TXS, MAX_CALLDATA));
+            keccak.digest()
+        };
+        let [pi_hash_hi, pi_hash_lo] =
+            [&digest[..16], &digest[16..]].map(|half| RpiField::int(half).value(self.randomness));
 
-    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
-        let block_table = BlockTable::construct(meta);
-        let tx_table = TxTable::construct(meta);
-        PiCircuitConfig::new(meta, block_table, tx_table)
+        vec![
+            self.rand_rpi,
+            rpi_rlc,
+            raw_pi_vals[CHAIN_ID_OFFSET],
+            raw_pi_vals[BLOCK_LEN + 2],
+            raw_pi_vals[BLOCK_LEN + 3],
+            pi_hash_hi,
+            pi_hash_lo,
+        ]
     }
 
-    fn synthesize(
+    /// Make the assignments to the PiCircuit.  Returns the `CallerAddress`
+    /// and `TxSignHash` cells of each tx of the tx table, which are
+    /// constrained by the Tx Circuit.
+    pub fn assign(
         &self,
-        config: Self::Config,
-        mut layouter: impl Layouter<F>,
-    ) -> Result<(), Error> {
-        let pi_cells = layouter.assign_region(
+        config: &PiCircuitConfig<F, MAX_TXS, MAX_CALLDATA>,
+        layouter: &mut impl Layouter<F>,
+    ) -> Result<Vec<[AssignedCell<F, F>; 2]>, Error> {
+        let block_table_rows = self.public_data.block_table_rows();
+        let tx_table_tags: Vec<TxFieldTag> = self
+            .public_data
+            .tx_table_rows(MAX_TXS, MAX_CALLDATA)
+            .into_iter()
+            .map(|(tag, _)| tag)
+            .collect();
+        let raw_public_inputs = self.public_data.raw_public_inputs(MAX_TXS, MAX_CALLDATA);
+        let number = self.public_data.get_block_table_values().number;
+
+        let (pi_cells, rpi_fields, tx_cells) = layouter.assign_region(
             || "region 0",
             |mut region| {
-                let circuit_len = Self::Config::circuit_len();
-                let mut raw_pi_vals = vec![F::zero(); circuit_len];
+                let raw_pi_vals: Vec<F> = raw_public_inputs
+                    .iter()
+                    .map(|field| {
+                        field
+                            .as_ref()
+                            .map(|field| field.value(self.randomness))
+                            .unwrap_or_default()
+                    })
+                    .collect();
+                let rpi_cells = raw_pi_vals
+                    .iter()
+                    .enumerate()
+                    .map(|(offset, value)| {
+                        region.assign_advice(
+                            || "raw_public_inputs",
+                            config.raw_public_inputs,
+                            offset,
+                            || Value::known(*value),
+                        )
+                    })
+                    .collect::<Result<Vec<_>, Error>>()?;
 
                 // Assign block table
-                let block_values = self.public_data.get_block_table_values();
-                let chain_id = config.assign_block_table(
+                config.assign_block_table(
                     &mut region,
-                    block_values,
-                    self.randomness,
-                    &mut raw_pi_vals,
+                    &block_table_rows,
+                    number,
+                    &raw_pi_vals,
+                    &rpi_cells,
                 )?;
 
-                // Assign extra fields
-                let extra_vals = self.public_data.get_extra_values();
-                let [state_root, prev_state_root] = config.assign_extra_fields(
-                    &mut region,
-                    extra_vals,
-                    self.randomness,
-                    &mut raw_pi_vals,
-                )?;
-
-                let mut offset = 0;
                 // Assign Tx table
-                let txs = self.public_data.get_tx_table_values();
-                assert!(txs.len() <= MAX_TXS);
-                let tx_default = TxValues::default();
-
-                // Add empty row
-                config.assign_tx_row(
-                    &mut region,
-                    offset,
-                    0,
-                    TxFieldTag::Null,
-                    0,
-                    F::zero(),
-                    &mut raw_pi_vals,
-                )?;
-                offset += 1;
-
-                for i in 0..MAX_TXS {
-                    let tx = if i < txs.len() { &txs[i] } else { &tx_default };
-
-                    for (tag, value) in &[
-                        (
-                            TxFieldTag::Nonce,
-                            rlc(tx.nonce.to_le_bytes(), self.randomness),
-                        ),
-                        (TxFieldTag::Gas, rlc(tx.gas.to_le_bytes(), self.randomness)),
-                        (
-                            TxFieldTag::GasPrice,
-                            rlc(tx.gas_price.to_le_bytes(), self.randomness),
-                        ),
-                        (
-                            TxFieldTag::CallerAddress,
-                            tx.from_addr.to_scalar().expect("tx.from too big"),
-                        ),
-                        (
-                            TxFieldTag::CalleeAddress,
-                            tx.to_addr.to_scalar().expect("tx.to too big"),
-                        ),
-                        (TxFieldTag::IsCreate, F::from(tx.is_create)),
-                        (
-                            TxFieldTag::Value,
-                            rlc(tx.value.to_le_bytes(), self.randomness),
-                        ),
-                        (TxFieldTag::CallDataLength, F::from(tx.call_data_len)),
-                        (
-                            TxFieldTag::TxSignHash,
-                            rlc(tx.tx_sign_hash, self.randomness),
-                        ),
-                    ] {
-                        config.assign_tx_row(
-                            &mut region,
-                            offset,
-                            i + 1,
-                            *tag,
-                            0,
-                            *value,
-                            &mut raw_pi_vals,
-                        )?;
-                        offset += 1;
-                    }
-                }
-                // Tx Table CallData
-                let mut calldata_count = 0;
-                for (i, tx) in self.public_data.txs.iter().enumerate() {
-                    for (index, byte) in tx.call_data.0.iter().enumerate() {
-                        assert!(calldata_count < MAX_CALLDATA);
-                        config.assign_tx_row(
-                            &mut region,
-                            offset,
-                            i + 1,
-                            TxFieldTag::CallData,
-                            index,
-                            F::from(*byte as u64),
-                            &mut raw_pi_vals,
-                        )?;
-                        offset += 1;
-                        calldata_count += 1;
-                    }
-                }
-                for _ in calldata_count..MAX_CALLDATA {
-                    config.assign_tx_row(
-                        &mut region,
-                        offset,
-                        0, // tx_id
-                        TxFieldTag::CallData,
-                        0,
-                        F::zero(),
-                        &mut raw_pi_vals,
-                    )?;
-                    offset += 1;
-                }
+                let tx_cells = config.assign_tx_table(&mut region, &tx_table_tags, &raw_pi_vals)?;
 
                 // rpi_rlc and rand_rpi cols
                 let (rpi_rand, rpi_rlc) =
                     config.assign_rlc_pi(&mut region, self.rand_rpi, raw_pi_vals)?;
 
-                Ok(vec![
-                    rpi_rand,
-                    rpi_rlc,
-                    chain_id,
-                    state_root,
-                    prev_state_root,
-                ])
+                // The zero rows of the tables are not hashed
+                let rpi_fields: Vec<(RpiField, AssignedCell<F, F>)> = raw_public_inputs
+                    .iter()
+                    .zip(rpi_cells.iter())
+                    .filter_map(|(field, cell)| field.clone().map(|field| (field, cell.clone())))
+                    .collect();
+
+                Ok((
+                    vec![
+                        rpi_rand,
+                        rpi_rlc,
+                        rpi_cells[CHAIN_ID_OFFSET].clone(),
+                        rpi_cells[BLOCK_LEN + 2].clone(),
+                        rpi_cells[BLOCK_LEN + 3].clone(),
+                    ],
+                    rpi_fields,
+                    tx_cells,
+                ))
             },
         )?;
 
+        let [pi_hash_hi, pi_hash_lo] =
+            config.assign_rpi_bytes(layouter, &rpi_fields, self.randomness)?;
+
         // Constrain raw_public_input cells to public inputs
-        for (i, pi_cell) in pi_cells.iter().enumerate() {
+        for (i, pi_cell) in pi_cells
+            .iter()
+            .chain([&pi_hash_hi, &pi_hash_lo])
+            .enumerate()
+        {
             layouter.constrain_instance(pi_cell.cell(), config.pi, i)?;
         }
 
+        Ok(tx_cells)
+    }
+}
+
+impl<F: Field, const MAX_TXS: usize, const MAX_CALLDATA: usize> Circuit<F>
+    for PiCircuit<F, MAX_TXS, MAX_CALLDATA>
+{
+    type Config = PiCircuitConfig<F, MAX_TXS, MAX_CALLDATA>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let [randomness] = power_of_randomness_from_instance(meta);
+        let block_table = BlockTable::construct(meta);
+        let tx_table = TxTable::construct(meta);
+        let keccak_table = KeccakTable::construct(meta);
+        PiCircuitConfig::new(meta, block_table, tx_table, keccak_table, randomness)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.load(&mut layouter)?;
+        config.keccak_table.dev_load(
+            &mut layouter,
+            &[self.public_data.rpi_bytes(MAX_TXS, MAX_CALLDATA)],
+            &Challenges::mock(Value::known(self.randomness)),
+        )?;
+        self.assign(&config, &mut layouter)?;
         Ok(())
     }
 }
@@ -842,6 +1238,8 @@ mod pi_circuit_test {
     use super::*;
 
     use crate::test_util::rand_tx;
+    use crate::util::random_linear_combine_word as rlc;
+    use eth_types::{ToLittleEndian, ToScalar, U64};
     use halo2_proofs::{
         dev::{MockProver, VerifyFailure},
         halo2curves::bn256::Fr,
@@ -850,6 +1248,12 @@ mod pi_circuit_test {
     use rand::SeedableRng;
     use rand_chacha::ChaCha20Rng;
 
+    /// RLC of a 32 bytes big-endian value, as done in the EVM Circuit.
+    fn rlc_be<F: Field>(mut bytes: [u8; 32], randomness: F) -> F {
+        bytes.reverse();
+        rlc(bytes, randomness)
+    }
+
     /// Compute the raw_public_inputs column from the verifier's perspective.
     fn raw_public_inputs_col<F: Field, const MAX_TXS: usize, const MAX_CALLDATA: usize>(
         public_data: &PublicData,
@@ -867,40 +1271,31 @@ mod pi_circuit_test {
         // zero row
         result[offset] = F::zero();
         offset += 1;
-        // coinbase
-        let mut coinbase_bytes = [0u8; 32];
-        coinbase_bytes[12..].clone_from_slice(block.coinbase.as_bytes());
-        result[offset] = rlc(coinbase_bytes, randomness);
-        offset += 1;
-        // gas_limit
-        result[offset] = F::from(block.gas_limit);
-        offset += 1;
-        // number
-        result[offset] = F::from(block.number);
-        offset += 1;
-        // timestamp
-        result[offset] = F::from(block.timestamp);
-        offset += 1;
-        // difficulty
-        result[offset] = rlc(block.difficulty.to_le_bytes(), randomness);
-        offset += 1;
-        // base_fee
-        result[offset] = rlc(block.base_fee.to_le_bytes(), randomness);
-        offset += 1;
-        // chain_id
-        result[offset] = F::from(block.chain_id);
-        offset += 1;
+        for val in [
+            block.coinbase.to_scalar().expect("coinbase too big"),
+            F::from(block.timestamp),
+            F::from(block.number),
+            rlc(block.difficulty.to_le_bytes(), randomness),
+            F::from(block.gas_limit),
+            rlc(block.base_fee.to_le_bytes(), randomness),
+            rlc(Word::from(block.chain_id).to_le_bytes(), randomness),
+            F::from(block.num_txs),
+            F::from(block.cum_num_txs),
+        ] {
+            result[offset] = val;
+            offset += 1;
+        }
         // Previous block hashes
         for prev_hash in block.history_hashes {
-            result[offset] = rlc(prev_hash.to_fixed_bytes(), randomness);
+            result[offset] = rlc_be(prev_hash.to_fixed_bytes(), randomness);
             offset += 1;
         }
 
         // Insert Extra Values
-        // block Root
-        result[BLOCK_LEN] = rlc(extra.state_root.to_fixed_bytes(), randomness);
-        // parent block hash
-        result[BLOCK_LEN + 1] = rlc(extra.prev_state_root.to_fixed_bytes(), randomness);
+        for hash in [extra.block_hash, extra.state_root, extra.prev_state_root] {
+            result[offset] = rlc_be(hash.to_fixed_bytes(), randomness);
+            offset += 1;
+        }
 
         // Insert Tx table
         offset = 0;
@@ -925,13 +1320,14 @@ mod pi_circuit_test {
 
             for val in &[
                 rlc(tx.nonce.to_le_bytes(), randomness),
-                rlc(tx.gas.to_le_bytes(), randomness),
+                F::from(tx.gas),
                 rlc(tx.gas_price.to_le_bytes(), randomness),
                 tx.from_addr.to_scalar().expect("tx.from too big"),
                 tx.to_addr.to_scalar().expect("tx.to too big"),
                 F::from(tx.is_create),
                 rlc(tx.value.to_le_bytes(), randomness),
                 F::from(tx.call_data_len),
+                F::from(tx.call_data_gas_cost),
                 rlc(tx.tx_sign_hash, randomness),
             ] {
                 result[id_offset + offset] = F::from((i + 1) as u64);
@@ -984,22 +1380,30 @@ mod pi_circuit_test {
             .rev()
             .fold(F::zero(), |acc, val| acc * rand_rpi + val);
 
-        // let block_hash = public_data
-        //     .extra
-        //     .eth_block
-        //     .hash
-        //     .unwrap_or_else(H256::zero)
-        //     .to_fixed_bytes();
+        // Keccak digest of the raw public input bytes, split in hi and lo halves
+        let digest = {
+            let mut keccak = Keccak::default();
+            keccak.update(&public_data.rpi_bytes(MAX_TXS, MAX_CALLDATA));
+            keccak.digest()
+        };
+        let pi_hash_hi = Word::from_big_endian(&digest[..16])
+            .to_scalar()
+            .expect("pi_hash_hi too big");
+        let pi_hash_lo = Word::from_big_endian(&digest[16..])
+            .to_scalar()
+            .expect("pi_hash_lo too big");
 
         let public_inputs = vec![
             rand_rpi,
             rlc_rpi,
-            F::from(public_data.extra.chain_id.as_u64()),
-            rlc(
+            rlc(public_data.extra.chain_id.to_le_bytes(), randomness),
+            rlc_be(
                 public_data.extra.eth_block.state_root.to_fixed_bytes(),
                 randomness,
             ),
-            rlc(public_data.prev_state_root.to_fixed_bytes(), randomness),
+            rlc_be(public_data.prev_state_root.to_fixed_bytes(), randomness),
+            pi_hash_hi,
+            pi_hash_lo,
         ];
 
         let circuit = PiCircuit::<F, MAX_TXS, MAX_CALLDATA> {
@@ -1007,8 +1411,11 @@ mod pi_circuit_test {
             rand_rpi,
             public_data,
         };
+        assert_eq!(circuit.public_inputs(), public_inputs);
 
-        let prover = match MockProver::run(k, &circuit, vec![public_inputs]) {
+        let num_rows = PiCircuitConfig::<F, MAX_TXS, MAX_CALLDATA>::get_num_rows_required();
+        let instance = vec![vec![randomness; num_rows], public_inputs];
+        let prover = match MockProver::run(k, &circuit, instance) {
             Ok(prover) => prover,
             Err(e) => panic!("{:#?}", e),
         };
@@ -1021,7 +1428,7 @@ mod pi_circuit_test {
         const MAX_CALLDATA: usize = 8;
         let public_data = PublicData::default();
 
-        let k = 13;
+        let k = 15;
         assert_eq!(run::<Fr, MAX_TXS, MAX_CALLDATA>(k, public_data), Ok(()));
     }
 
@@ -1035,13 +1442,18 @@ mod pi_circuit_test {
         let mut public_data = PublicData::default();
         let chain_id = 1337u64;
         public_data.extra.chain_id = Word::from(chain_id);
+        public_data.extra.history_hashes = vec![Word::from(0xa1), Word::from(0xa2)];
+        public_data.block_constants.number = U64::from(300);
+        public_data.extra.eth_block.hash = Some(H256::from_low_u64_be(0xb0));
+        public_data.extra.eth_block.state_root = H256::from_low_u64_be(0xb1);
+        public_data.prev_state_root = H256::from_low_u64_be(0xb2);
 
         let n_tx = 2;
         for _ in 0..n_tx {
             public_data.txs.push(rand_tx(&mut rng, chain_id));
         }
 
-        let k = 13;
+        let k = 15;
         assert_eq!(run::<Fr, MAX_TXS, MAX_CALLDATA>(k, public_data), Ok(()));
     }
 }
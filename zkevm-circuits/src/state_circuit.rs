@@ -123,18 +123,25 @@ impl<F: Field> StateCircuitConfig<F> {
     pub(crate) fn load(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
         LookupsChip::construct(self.lookups).load(layouter)
     }
-    /// Make the assignments to the StateCircuit
+    /// Make the assignments to the StateCircuit, together with the RwTable.
+    /// Both are assigned in a single region, so other circuits sharing the
+    /// RwTable must not load it again.  The MptTable is assigned by the
+    /// MptCircuit.
     pub fn assign(
         &self,
         layouter: &mut impl Layouter<F>,
         rows: &[Rw],
+        updates: &MptUpdates,
         n_rows: usize,
         randomness: F,
     ) -> Result<(), Error> {
-        let updates = MptUpdates::mock_from(rows);
         layouter.assign_region(
             || "state circuit",
-            |mut region| self.assign_with_region(&mut region, rows, &updates, n_rows, randomness),
+            |mut region| {
+                self.rw_table
+                    .load_with_region(&mut region, rows, n_rows, randomness)?;
+                self.assign_with_region(&mut region, rows, updates, n_rows, randomness)
+            },
         )
     }
 
@@ -152,7 +159,7 @@ impl<F: Field> StateCircuitConfig<F> {
         let rows = rows.into_iter();
         let prev_rows = once(None).chain(rows.clone().map(Some));
 
-        let mut state_root = F::zero();
+        let mut state_root = updates.old_root_assignment(randomness);
 
         for (offset, (row, prev_row)) in rows.zip(prev_rows).enumerate() {
             if offset >= padding_length {
@@ -272,10 +279,21 @@ pub struct StateCircuit<F> {
 }
 
 impl<F: Field> StateCircuit<F> {
-    /// make a new state circuit from an RwMap
+    /// make a new state circuit from an RwMap, with mock MPT updates
     pub fn new(randomness: F, rw_map: RwMap, n_rows: usize) -> Self {
+        let updates = MptUpdates::mock_from(&rw_map.table_assignments());
+        Self::new_with_mpt_updates(randomness, rw_map, updates, n_rows)
+    }
+
+    /// make a new state circuit from an RwMap and the MPT updates of its last
+    /// accesses, as given by `MptUpdates::from_proofs`
+    pub fn new_with_mpt_updates(
+        randomness: F,
+        rw_map: RwMap,
+        updates: MptUpdates,
+        n_rows: usize,
+    ) -> Self {
         let rows = rw_map.table_assignments();
-        let updates = MptUpdates::mock_from(&rows);
         Self {
             randomness,
             rows,
@@ -9,65 +9,73 @@
 //! The current implementation contains the following circuits:
 //!
 //! - [x] EVM Circuit
-//! - [ ] State Circuit
+//! - [x] State Circuit
 //! - [x] Tx Circuit
 //! - [x] Bytecode Circuit
 //! - [x] Copy Circuit
-//! - [ ] Keccak Circuit
-//! - [ ] MPT Circuit
-//! - [ ] PublicInputs Circuit
+//! - [x] Exp Circuit
+//! - [x] Keccak Circuit
+//! - [x] MPT Circuit
+//! - [x] PublicInputs Circuit
 //!
 //! And the following shared tables, with the circuits that use them:
 //!
 //! - [x] Copy Table
 //!   - [x] Copy Circuit
 //!   - [x] EVM Circuit
-//! - [ ] Rw Table
-//!   - [ ] State Circuit
-//!   - [ ] EVM Circuit
-//!   - [ ] Copy Circuit
+//! - [x] Rw Table
+//!   - [x] State Circuit
+//!   - [x] EVM Circuit
+//!   - [x] Copy Circuit
 //! - [x] Tx Table
 //!   - [x] Tx Circuit
 //!   - [x] EVM Circuit
 //!   - [x] Copy Circuit
-//!   - [ ] PublicInputs Circuit
+//!   - [x] PublicInputs Circuit
 //! - [x] Bytecode Table
 //!   - [x] Bytecode Circuit
 //!   - [x] EVM Circuit
 //!   - [x] Copy Circuit
-//! - [ ] Block Table
-//!   - [ ] EVM Circuit
-//!   - [ ] PublicInputs Circuit
-//! - [ ] MPT Table
-//!   - [ ] MPT Circuit
-//!   - [ ] State Circuit
+//! - [x] Block Table
+//!   - [x] EVM Circuit
+//!   - [x] PublicInputs Circuit
+//! - [x] MPT Table
+//!   - [x] MPT Circuit
+//!   - [x] State Circuit
 //! - [x] Keccak Table
-//!   - [ ] Keccak Circuit
-//!   - [ ] EVM Circuit
+//!   - [x] Keccak Circuit
+//!   - [x] EVM Circuit
 //!   - [x] Bytecode Circuit
 //!   - [x] Tx Circuit
-//!   - [ ] MPT Circuit
+//!   - [x] MPT Circuit
+//!   - [x] PublicInputs Circuit
+//! - [x] Exp Table
+//!   - [x] Exp Circuit
+//!   - [x] EVM Circuit
 
 use crate::bytecode_circuit::bytecode_unroller::{
     unroll, Config as BytecodeConfig, UnrolledBytecode,
 };
 use crate::copy_circuit::CopyCircuit;
 use crate::evm_circuit::{table::FixedTableTag, EvmCircuit};
+use crate::exp_circuit::{ExpCircuit, ROWS_PER_STEP};
 use crate::keccak_circuit::keccak_packed_multi::KeccakPackedConfig as KeccakConfig;
+use crate::mpt_circuit::{MptCircuit, MptCircuitConfig};
+use crate::pi_circuit::{PiCircuit, PiCircuitConfig, PublicData};
 use crate::state_circuit::StateCircuitConfig;
-use crate::table::{BlockTable, BytecodeTable, CopyTable, MptTable, RwTable, TxTable};
+use crate::table::{BlockTable, BytecodeTable, CopyTable, ExpTable, MptTable, RwTable, TxTable};
 use crate::tx_circuit::{TxCircuit, TxCircuitConfig};
 use crate::util::Challenges;
-use crate::witness::{block_convert, Block, MptUpdates};
+use crate::witness::{block_convert, Block, MptUpdates, RwMap};
 
 use bus_mapping::mock::BlockData;
-use eth_types::geth_types::{self, GethData};
-use eth_types::Field;
+use eth_types::geth_types::{self, BlockConstants, GethData};
+use eth_types::{Field, ToBigEndian, H256};
 
 use halo2_proofs::arithmetic::CurveAffine;
 use halo2_proofs::halo2curves::{
     bn256::Fr,
-    group::{Curve, Group},
+    group::{ff::Field as GroupField, Curve, Group},
     secp256k1::Secp256k1Affine,
 };
 use halo2_proofs::{
@@ -96,7 +104,10 @@ pub struct SuperCircuitConfig<F: Field, const MAX_TXS: usize, const MAX_CALLDATA
     tx_circuit: TxCircuitConfig<F>,
     bytecode_circuit: BytecodeConfig<F>,
     copy_circuit: CopyCircuit<F>,
+    exp_circuit: ExpCircuit<F>,
     keccak_circuit: KeccakConfig<F>,
+    pi_circuit: PiCircuitConfig<F, MAX_TXS, MAX_CALLDATA>,
+    mpt_circuit: MptCircuitConfig<F>,
 }
 
 /// The Super Circuit contains all the zkEVM circuits
@@ -114,6 +125,12 @@ pub struct SuperCircuit<F: Field, const MAX_TXS: usize, const MAX_CALLDATA: usiz
     // Tx Circuit
     /// The transaction circuit that will be used in the `synthesize` step.
     pub tx_circuit: TxCircuit<F, MAX_TXS, MAX_CALLDATA>,
+    // Public Input Circuit
+    /// The public input circuit, which assigns the block and tx tables.
+    pub pi_circuit: PiCircuit<F, MAX_TXS, MAX_CALLDATA>,
+    // MPT Circuit
+    /// The MPT circuit, which assigns the MPT table.
+    pub mpt_circuit: MptCircuit<F>,
     // Bytecode Circuit
     // bytecodes: Vec<UnrolledBytecode<F>>,
     /// The maximium size for the underlying bytecode circuit.
@@ -131,7 +148,15 @@ impl<F: Field, const MAX_TXS: usize, const MAX_CALLDATA: usize>
             config.evm_circuit.get_num_rows_required(block)
         };
         let num_rows_tx_circuit = TxCircuitConfig::<F>::get_num_rows_required(MAX_TXS);
-        num_rows_evm_circuit.max(num_rows_tx_circuit)
+        // The State Circuit needs one more row than the rws for the leading
+        // `Rw::Start` padding.
+        let num_rows_state_circuit = block.rws.0.values().map(|rws| rws.len()).sum::<usize>() + 1;
+        let num_rows_pi_circuit =
+            PiCircuitConfig::<F, MAX_TXS, MAX_CALLDATA>::get_num_rows_required();
+        num_rows_evm_circuit
+            .max(num_rows_tx_circuit)
+            .max(num_rows_state_circuit)
+            .max(num_rows_pi_circuit)
     }
 }
 
@@ -153,6 +178,7 @@ impl<F: Field, const MAX_TXS: usize, const MAX_CALLDATA: usize> Circuit<F>
         let block_table = BlockTable::construct(meta);
         let q_copy_table = meta.fixed_column();
         let copy_table = CopyTable::construct(meta, q_copy_table);
+        let exp_table = ExpTable::construct(meta);
 
         let power_of_randomness = array::from_fn(|i| {
             Expression::Constant(F::from(MOCK_RANDOMNESS).pow(&[1 + i as u64, 0, 0, 0]))
@@ -170,10 +196,30 @@ impl<F: Field, const MAX_TXS: usize, const MAX_CALLDATA: usize> Circuit<F>
             &block_table,
             &copy_table,
             &keccak_table,
+            &exp_table,
         );
         let state_circuit =
             StateCircuitConfig::configure(meta, power_of_randomness.clone(), &rw_table, &mpt_table);
         let challenges = Challenges::mock(power_of_randomness[0].clone());
+        let tx_circuit = TxCircuitConfig::new(
+            meta,
+            tx_table.clone(),
+            keccak_table.clone(),
+            challenges.clone(),
+        );
+        let pi_circuit = PiCircuitConfig::new(
+            meta,
+            block_table.clone(),
+            tx_table.clone(),
+            keccak_table.clone(),
+            power_of_randomness[0].clone(),
+        );
+        let mpt_circuit = MptCircuitConfig::configure(
+            meta,
+            mpt_table,
+            keccak_table.clone(),
+            power_of_randomness[0].clone(),
+        );
 
         Self::Config {
             tx_table: tx_table.clone(),
@@ -193,12 +239,8 @@ impl<F: Field, const MAX_TXS: usize, const MAX_CALLDATA: usize> Circuit<F>
                 q_copy_table,
                 power_of_randomness[0].clone(),
             ),
-            tx_circuit: TxCircuitConfig::new(
-                meta,
-                tx_table,
-                keccak_table.clone(),
-                challenges.clone(),
-            ),
+            exp_circuit: ExpCircuit::configure(meta, exp_table),
+            tx_circuit,
             bytecode_circuit: BytecodeConfig::configure(
                 meta,
                 bytecode_table,
@@ -206,6 +248,8 @@ impl<F: Field, const MAX_TXS: usize, const MAX_CALLDATA: usize> Circuit<F>
                 challenges,
             ),
             keccak_circuit,
+            pi_circuit,
+            mpt_circuit,
         }
     }
 
@@ -216,44 +260,43 @@ impl<F: Field, const MAX_TXS: usize, const MAX_CALLDATA: usize> Circuit<F>
     ) -> Result<(), Error> {
         let challenges = Challenges::mock(Value::known(self.block.randomness));
 
-        // --- EVM Circuit ---
+        // --- State Circuit ---
+        // The RwTable is assigned once here, together with the State Circuit,
+        // and the EVM and Copy circuits look up the same columns.
+        self.block.rws.check_rw_counter_sanity();
         let rws = self.block.rws.table_assignments();
-        config
-            .evm_circuit
-            .load_fixed_table(&mut layouter, self.fixed_table_tags.clone())?;
-        config.evm_circuit.load_byte_table(&mut layouter)?;
-        config.rw_table.load(
+        config.state_circuit.load(&mut layouter)?;
+        config.state_circuit.assign(
             &mut layouter,
             &rws,
+            &self.block.mpt_updates,
             self.block.state_circuit_pad_to,
             self.block.randomness,
         )?;
-        config.state_circuit.load(&mut layouter)?;
+        // --- EVM Circuit ---
         config
-            .block_table
-            .load(&mut layouter, &self.block.context, self.block.randomness)?;
+            .evm_circuit
+            .load_fixed_table(&mut layouter, self.fixed_table_tags.clone())?;
+        config.evm_circuit.load_byte_table(&mut layouter)?;
         config
             .copy_table
             .load(&mut layouter, &self.block, self.block.randomness)?;
         config
             .evm_circuit
             .assign_block(&mut layouter, &self.block)?;
-        // --- State Circuit ---
-        config.mpt_table.load(
-            &mut layouter,
-            &MptUpdates::mock_from(&rws),
-            self.block.randomness,
-        )?;
-        config.state_circuit.assign(
-            &mut layouter,
-            &rws,
-            self.block.state_circuit_pad_to,
-            self.block.randomness,
-        )?;
+        // --- Public Input Circuit ---
+        // The Block and Tx tables are assigned by the PI Circuit, and the Tx
+        // Circuit only verifies the signatures of the txs of the Tx table.
+        config.pi_circuit.load(&mut layouter)?;
+        let tx_cells = self.pi_circuit.assign(&config.pi_circuit, &mut layouter)?;
         // --- Tx Circuit ---
         config.tx_circuit.load(&mut layouter)?;
-        self.tx_circuit
-            .assign(&config.tx_circuit, &mut layouter, &challenges)?;
+        self.tx_circuit.assign_with_tx_table(
+            &config.tx_circuit,
+            &mut layouter,
+            &challenges,
+            &tx_cells,
+        )?;
         // --- Bytecode Circuit ---
         let bytecodes: Vec<UnrolledBytecode<F>> = self
             .block
@@ -279,6 +322,20 @@ impl<F: Field, const MAX_TXS: usize, const MAX_CALLDATA: usize> Circuit<F>
         config
             .copy_circuit
             .assign_block(&mut layouter, &self.block, self.block.randomness)?;
+        // --- Exp Circuit ---
+        config
+            .exp_circuit
+            .assign_block(&mut layouter, &self.block)?;
+        // --- MPT Circuit ---
+        // The MptTable is assigned by the MPT Circuit, and the State Circuit
+        // looks up the same columns.
+        config.mpt_circuit.load(&mut layouter)?;
+        config.mpt_circuit.assign(
+            &mut layouter,
+            &self.mpt_circuit.updates,
+            self.mpt_circuit.n_rows,
+            self.block.randomness,
+        )?;
         Ok(())
     }
 }
@@ -293,23 +350,47 @@ impl<const MAX_TXS: usize, const MAX_CALLDATA: usize> SuperCircuit<Fr, MAX_TXS,
         geth_data: GethData,
         rng: &mut (impl RngCore + Clone),
     ) -> Result<(u32, Self, Vec<Vec<Fr>>), bus_mapping::Error> {
-        let txs = geth_data
+        let txs: Vec<geth_types::Transaction> = geth_data
             .eth_block
             .transactions
             .iter()
             .map(geth_types::Transaction::from)
             .collect();
-
         let mut builder =
             BlockData::new_from_geth_data(geth_data.clone()).new_circuit_input_builder();
 
         builder
             .handle_block(&geth_data.eth_block, &geth_data.geth_traces)
             .expect("could not handle block tx");
-        let keccak_inputs = builder.keccak_inputs()?;
-        let mut block = block_convert(&builder.block, &builder.code_db);
+        // The mock state isn't queried from geth, so its proofs are computed
+        // from the trie of all its accounts.
+        if builder.block.proofs.is_empty() {
+            builder.block.proofs = MptUpdates::proofs_from_accounts(
+                &geth_data.accounts,
+                &RwMap::from(&builder.block.container).table_assignments(),
+            )
+            .expect("could not compute the mpt proofs");
+        }
+        let mut block =
+            block_convert(&builder.block, &builder.code_db).expect("could not convert block");
+
+        let public_data = PublicData {
+            txs: txs.clone(),
+            extra: geth_data.clone(),
+            block_constants: BlockConstants::try_from(&geth_data.eth_block)?,
+            // The state root before the first MPT update of the block.
+            prev_state_root: H256::from(block.mpt_updates.old_root().to_be_bytes()),
+        };
         block.randomness = Fr::from(MOCK_RANDOMNESS);
-        block.state_circuit_pad_to = 1;
+        let mut mpt_circuit =
+            MptCircuit::new(block.mpt_updates.witnesses().to_vec(), block.randomness, 0);
+        mpt_circuit.n_rows = mpt_circuit.get_num_rows_required();
+        let mut keccak_inputs = builder.keccak_inputs()?;
+        keccak_inputs.push(public_data.rpi_bytes(MAX_TXS, MAX_CALLDATA));
+        keccak_inputs.extend(mpt_circuit.keccak_inputs());
+        // Pad the RwTable with a single `Rw::Start` row, so that its last row is
+        // the last rw of the block as expected by the State Circuit.
+        block.state_circuit_pad_to = block.rws.0.values().map(|rws| rws.len()).sum::<usize>() + 1;
 
         let fixed_table_tags: Vec<FixedTableTag> = FixedTableTag::iter().collect();
         let log2_ceil = |n| u32::BITS - (n as u32).leading_zeros() - (n & (n - 1) == 0) as u32;
@@ -328,17 +409,34 @@ impl<const MAX_TXS: usize, const MAX_CALLDATA: usize> SuperCircuit<Fr, MAX_TXS,
             .map(|(_, bytecode)| bytecode.bytes.len())
             .sum::<usize>();
         let k = k.max(log2_ceil(64 + bytecodes_len));
+        let exp_steps_len = block
+            .exp_events
+            .iter()
+            .map(|exp_event| exp_event.steps.len())
+            .sum::<usize>();
+        let k = k.max(log2_ceil(64 + (exp_steps_len + 1) * ROWS_PER_STEP));
         let k = k.max(log2_ceil(64 + num_rows_required));
+        let k = k.max(log2_ceil(64 + mpt_circuit.n_rows));
+        let k = k.max(log2_ceil(
+            64 + KeccakConfig::<Fr>::get_num_rows_required(&keccak_inputs),
+        ));
         log::debug!("super circuit uses k = {}", k);
 
         let aux_generator = <Secp256k1Affine as CurveAffine>::CurveExt::random(rng).to_affine();
-        let chain_id = block.context.chain_id;
+        let chain_id = builder.block.chain_id;
         let tx_circuit = TxCircuit::new(aux_generator, chain_id.as_u64(), txs);
+        let pi_circuit = PiCircuit {
+            randomness: Fr::from(MOCK_RANDOMNESS),
+            rand_rpi: Fr::random(&mut *rng),
+            public_data,
+        };
 
         let circuit = Self {
             block,
             fixed_table_tags,
             tx_circuit,
+            pi_circuit,
+            mpt_circuit,
             keccak_inputs,
             // Instead of using 1 << k - NUM_BLINDING_ROWS, we use a much smaller number of enabled
             // rows for the Bytecode Circuit because otherwise it penalizes significantly the
@@ -346,8 +444,15 @@ impl<const MAX_TXS: usize, const MAX_CALLDATA: usize> SuperCircuit<Fr, MAX_TXS,
             bytecode_size: bytecodes_len + 64,
         };
 
-        // SignVerifyChip -> ECDSAChip -> MainGate instance column
-        let instances = vec![vec![]];
+        // SignVerifyChip -> ECDSAChip -> MainGate instance column, the PI
+        // Circuit instance column, which contains the keccak of the raw public
+        // inputs as hi and lo halves, and the MPT Circuit instance column, which
+        // contains the old and new state roots.
+        let instances = vec![
+            vec![],
+            circuit.pi_circuit.public_inputs(),
+            circuit.mpt_circuit.state_roots(),
+        ];
         Ok((k, circuit, instances))
     }
 }
@@ -374,6 +479,13 @@ mod super_circuit_tests {
         let chain_id = (*MOCK_CHAIN_ID).as_u64();
 
         let bytecode = bytecode! {
+            PUSH32(Word::MAX)
+            PUSH1(0x00)
+            MSTORE
+            PUSH1(0x20)
+            PUSH1(0x00)
+            SHA3
+            POP
             GAS
             STOP
         };
@@ -1,20 +1,20 @@
 //! Table definitions used cross-circuits
 
 use crate::copy_circuit::number_or_hash_to_field;
-use crate::evm_circuit::util::{rlc, RandomLinearCombination};
+use crate::evm_circuit::util::{rlc, split_u256, RandomLinearCombination};
 use crate::impl_expr;
 use crate::util::build_tx_log_address;
 use crate::util::Challenges;
 use crate::witness::{
-    Block, BlockContext, Bytecode, MptUpdateRow, MptUpdates, Rw, RwMap, RwRow, Transaction,
+    Block, BlockContexts, Bytecode, MptUpdateRow, MptUpdates, Rw, RwMap, RwRow, Transaction,
 };
-use bus_mapping::circuit_input_builder::{CopyDataType, CopyEvent, CopyStep};
+use bus_mapping::circuit_input_builder::{CopyDataType, CopyEvent, CopyStep, ExpEvent};
 use core::iter::once;
 use eth_types::{Field, ToLittleEndian, ToScalar, Word};
 use gadgets::binary_number::{BinaryNumberChip, BinaryNumberConfig};
 use halo2_proofs::{
     arithmetic::FieldExt,
-    circuit::{Region, Value},
+    circuit::{AssignedCell, Region, Value},
     plonk::{Advice, Column, ConstraintSystem, Error},
 };
 use halo2_proofs::{circuit::Layouter, plonk::*, poly::Rotation};
@@ -429,6 +429,7 @@ impl RwTable {
 }
 
 /// The types of proofs in the MPT table
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum ProofType {
     /// Nonce updated
     NonceChanged = AccountFieldTag::Nonce as isize,
@@ -476,16 +477,19 @@ impl MptTable {
         region: &mut Region<'_, F>,
         offset: usize,
         row: &MptUpdateRow<F>,
-    ) -> Result<(), Error> {
-        for (column, value) in self.0.iter().zip_eq(row.values()) {
-            region.assign_advice(
-                || "assign mpt table row value",
-                *column,
-                offset,
-                || Value::known(*value),
-            )?;
-        }
-        Ok(())
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        self.0
+            .iter()
+            .zip_eq(row.values())
+            .map(|(column, value)| {
+                region.assign_advice(
+                    || "assign mpt table row value",
+                    *column,
+                    offset,
+                    || Value::known(*value),
+                )
+            })
+            .collect()
     }
 
     pub(crate) fn load<F: Field>(
@@ -629,6 +633,11 @@ pub enum BlockContextFieldTag {
     /// Chain ID field.  Although this is not a field in the block header, we
     /// add it here for convenience.
     ChainId,
+    /// Number of transactions of the block
+    NumTxs,
+    /// Number of transactions of the block and the previous blocks proven
+    /// together with it
+    CumNumTxs,
 }
 impl_expr!(BlockContextFieldTag);
 
@@ -653,11 +662,11 @@ impl BlockTable {
         }
     }
 
-    /// Assign the `BlockTable` from a `BlockContext`.
+    /// Assign the `BlockTable` from the `BlockContexts` of the blocks.
     pub fn load<F: Field>(
         &self,
         layouter: &mut impl Layouter<F>,
-        block: &BlockContext,
+        block: &BlockContexts,
         randomness: F,
     ) -> Result<(), Error> {
         layouter.assign_region(
@@ -858,7 +867,7 @@ pub struct CopyTable {
 }
 
 type CopyTableRow<F> = [(F, &'static str); 8];
-type CopyCircuitRow<F> = [(F, &'static str); 4];
+type CopyCircuitRow<F> = [(F, &'static str); 5];
 
 impl CopyTable {
     /// Construct a new CopyTable
@@ -883,7 +892,9 @@ impl CopyTable {
     ) -> Vec<(CopyDataType, CopyTableRow<F>, CopyCircuitRow<F>)> {
         let mut assignments = Vec::new();
         // rlc_acc
-        let rlc_acc = if copy_event.dst_type == CopyDataType::RlcAcc {
+        let rlc_acc = if copy_event.src_type == CopyDataType::RlcAcc
+            || copy_event.dst_type == CopyDataType::RlcAcc
+        {
             let values = copy_event
                 .bytes
                 .iter()
@@ -894,6 +905,7 @@ impl CopyTable {
             F::zero()
         };
         let mut value_acc = F::zero();
+        let mut read_value_acc = F::zero();
         for (step_idx, (is_read_step, copy_step)) in copy_event
             .bytes
             .iter()
@@ -974,6 +986,10 @@ impl CopyTable {
             } else {
                 F::from(copy_step.value as u64)
             };
+            // read_value_acc
+            if copy_event.src_type == CopyDataType::RlcAcc && is_read_step {
+                read_value_acc = read_value_acc * randomness + F::from(copy_step.value as u64);
+            }
             // is_pad
             let is_pad = F::from(is_read_step && copy_step_addr >= copy_event.src_addr_end);
 
@@ -1000,6 +1016,7 @@ impl CopyTable {
                     (value, "value"),
                     (is_pad, "is_pad"),
                     (is_code, "is_code"),
+                    (read_value_acc, "read_value_acc"),
                 ],
             ));
         }
@@ -1083,3 +1100,134 @@ impl<F: Field> LookupTable<F> for CopyTable {
         ]
     }
 }
+
+/// Lookup table within the Exponentiation circuit, used to verify the relation
+/// `base ^ exponent == exponentiation (mod 2^256)` of the EXP opcode. Each
+/// multiplication step of an exponentiation by squaring takes one enabled
+/// row, holding the intermediate exponent and exponentiation of that step.
+#[derive(Clone, Copy, Debug)]
+pub struct ExpTable {
+    /// Whether the row holds an exponentiation step.
+    pub is_step: Column<Fixed>,
+    /// Lower 128 bits of the base.
+    pub base_lo: Column<Advice>,
+    /// Higher 128 bits of the base.
+    pub base_hi: Column<Advice>,
+    /// Lower 128 bits of the (intermediate) exponent.
+    pub exponent_lo: Column<Advice>,
+    /// Higher 128 bits of the (intermediate) exponent.
+    pub exponent_hi: Column<Advice>,
+    /// Lower 128 bits of the (intermediate) exponentiation result.
+    pub exponentiation_lo: Column<Advice>,
+    /// Higher 128 bits of the (intermediate) exponentiation result.
+    pub exponentiation_hi: Column<Advice>,
+}
+
+impl ExpTable {
+    /// Construct a new ExpTable
+    pub fn construct<F: Field>(meta: &mut ConstraintSystem<F>) -> Self {
+        Self {
+            is_step: meta.fixed_column(),
+            base_lo: meta.advice_column(),
+            base_hi: meta.advice_column(),
+            exponent_lo: meta.advice_column(),
+            exponent_hi: meta.advice_column(),
+            exponentiation_lo: meta.advice_column(),
+            exponentiation_hi: meta.advice_column(),
+        }
+    }
+
+    /// Generate the exponentiation table assignments from an exponentiation
+    /// event, one row per multiplication step, following the order of the
+    /// table's advice columns.
+    pub fn assignments<F: Field>(exp_event: &ExpEvent) -> Vec<[F; 6]> {
+        let (base_lo, base_hi) = split_u256(&exp_event.base);
+        let mut exponent = exp_event.exponent;
+        exp_event
+            .steps
+            .iter()
+            .map(|step| {
+                let (exponent_lo, exponent_hi) = split_u256(&exponent);
+                let (exponentiation_lo, exponentiation_hi) = split_u256(&step.d);
+                // An odd exponent comes from a multiplication by the base,
+                // an even one from a squaring.
+                exponent = if exponent.bit(0) {
+                    exponent - 1
+                } else {
+                    exponent >> 1
+                };
+                [
+                    base_lo,
+                    base_hi,
+                    exponent_lo,
+                    exponent_hi,
+                    exponentiation_lo,
+                    exponentiation_hi,
+                ]
+                .map(|value| value.to_scalar().unwrap())
+            })
+            .collect()
+    }
+
+    /// Provide this function for the case that we want to consume an
+    /// exponentiation table but without running the full exponentiation
+    /// circuit
+    pub fn dev_load<F: Field>(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        block: &Block<F>,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "exponentiation table",
+            |mut region| {
+                let mut offset = 0;
+                let exp_table_columns = self.columns();
+                for exp_event in block.exp_events.iter() {
+                    for row in Self::assignments::<F>(exp_event) {
+                        region.assign_fixed(
+                            || format!("exponentiation table is_step {}", offset),
+                            self.is_step,
+                            offset,
+                            || Value::known(F::one()),
+                        )?;
+                        for (column, value) in exp_table_columns.iter().zip_eq(row) {
+                            region.assign_advice(
+                                || format!("exponentiation table row {}", offset),
+                                *column,
+                                offset,
+                                || Value::known(value),
+                            )?;
+                        }
+                        offset += 1;
+                    }
+                }
+                Ok(())
+            },
+        )
+    }
+}
+
+impl ExpTable {
+    pub(crate) fn columns(&self) -> Vec<Column<Advice>> {
+        vec![
+            self.base_lo,
+            self.base_hi,
+            self.exponent_lo,
+            self.exponent_hi,
+            self.exponentiation_lo,
+            self.exponentiation_hi,
+        ]
+    }
+}
+
+impl<F: Field> LookupTable<F> for ExpTable {
+    fn table_exprs(&self, meta: &mut VirtualCells<F>) -> Vec<Expression<F>> {
+        std::iter::once(meta.query_fixed(self.is_step, Rotation::cur()))
+            .chain(
+                self.columns()
+                    .iter()
+                    .map(|column| meta.query_advice(*column, Rotation::cur())),
+            )
+            .collect()
+    }
+}
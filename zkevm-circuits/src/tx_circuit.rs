@@ -19,7 +19,7 @@ use halo2_proofs::{
 };
 use itertools::Itertools;
 use log::error;
-use sign_verify::{SignVerifyChip, SignVerifyConfig};
+use sign_verify::{AssignedSignatureVerify, SignVerifyChip, SignVerifyConfig};
 use std::marker::PhantomData;
 
 pub use halo2_proofs::halo2curves::{
@@ -143,13 +143,14 @@ impl<F: Field, const MAX_TXS: usize, const MAX_CALLDATA: usize>
         }
     }
 
-    /// Make the assignments to the TxCircuit
-    pub fn assign(
+    /// Verify the signatures of the txs, returning the assigned address and
+    /// message hash RLC of each of them.
+    fn assign_sign_verify(
         &self,
         config: &TxCircuitConfig<F>,
         layouter: &mut impl Layouter<F>,
         challenges: &Challenges<Value<F>>,
-    ) -> Result<(), Error> {
+    ) -> Result<Vec<AssignedSignatureVerify<F>>, Error> {
         assert!(self.txs.len() <= MAX_TXS);
         let sign_datas: Vec<SignData> = self
             .txs
@@ -162,9 +163,18 @@ impl<F: Field, const MAX_TXS: usize, const MAX_CALLDATA: usize>
             })
             .try_collect()?;
 
-        let assigned_sig_verifs =
-            self.sign_verify
-                .assign(&config.sign_verify, layouter, &sign_datas, challenges)?;
+        self.sign_verify
+            .assign(&config.sign_verify, layouter, &sign_datas, challenges)
+    }
+
+    /// Make the assignments to the TxCircuit
+    pub fn assign(
+        &self,
+        config: &TxCircuitConfig<F>,
+        layouter: &mut impl Layouter<F>,
+        challenges: &Challenges<Value<F>>,
+    ) -> Result<(), Error> {
+        let assigned_sig_verifs = self.assign_sign_verify(config, layouter, challenges)?;
 
         layouter.assign_region(
             || "tx table",
@@ -300,6 +310,42 @@ impl<F: Field, const MAX_TXS: usize, const MAX_CALLDATA: usize>
         )?;
         Ok(())
     }
+
+    /// Make the assignments to the TxCircuit when the tx table is assigned by
+    /// another circuit (the PI Circuit), which provides the `CallerAddress`
+    /// and `TxSignHash` cells of each tx.  Only the signatures are verified,
+    /// and their outputs are constrained to those cells.
+    pub fn assign_with_tx_table(
+        &self,
+        config: &TxCircuitConfig<F>,
+        layouter: &mut impl Layouter<F>,
+        challenges: &Challenges<Value<F>>,
+        tx_cells: &[[AssignedCell<F, F>; 2]],
+    ) -> Result<(), Error> {
+        let assigned_sig_verifs = self.assign_sign_verify(config, layouter, challenges)?;
+        assert_eq!(assigned_sig_verifs.len(), tx_cells.len());
+
+        layouter.assign_region(
+            || "tx table sign verify",
+            |mut region| {
+                // Ref. spec 0. Copy constraints between the tx rows and the
+                // SignVerifyChip
+                for (assigned_sig_verif, [caller_address, tx_sign_hash]) in
+                    assigned_sig_verifs.iter().zip(tx_cells.iter())
+                {
+                    region.constrain_equal(
+                        caller_address.cell(),
+                        assigned_sig_verif.address.cell(),
+                    )?;
+                    region.constrain_equal(
+                        tx_sign_hash.cell(),
+                        assigned_sig_verif.msg_hash_rlc.cell(),
+                    )?;
+                }
+                Ok(())
+            },
+        )
+    }
 }
 
 impl<F: Field, const MAX_TXS: usize, const MAX_CALLDATA: usize> Circuit<F>
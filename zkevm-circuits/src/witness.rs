@@ -3,13 +3,13 @@
 //! used to generate witnesses for circuits.
 
 mod block;
-pub use block::{block_convert, Block, BlockContext};
+pub use block::{block_convert, Block, BlockContext, BlockContexts};
 mod bytecode;
 pub use bytecode::Bytecode;
 mod call;
 pub use call::Call;
 mod mpt;
-pub use mpt::{MptUpdate, MptUpdateRow, MptUpdates};
+pub use mpt::{MptError, MptProof, MptUpdate, MptUpdateRow, MptUpdateWitness, MptUpdates};
 mod rw;
 pub use rw::{Rw, RwMap, RwRow};
 mod step;
@@ -1,13 +1,13 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
-use bus_mapping::circuit_input_builder::{self, CopyEvent};
+use bus_mapping::circuit_input_builder::{self, CopyEvent, ExpEvent};
 use eth_types::{Address, Field, ToLittleEndian, ToScalar, Word};
 use halo2_proofs::halo2curves::bn256::Fr;
 use itertools::Itertools;
 
 use crate::{evm_circuit::util::RandomLinearCombination, table::BlockContextFieldTag};
 
-use super::{tx::tx_convert, Bytecode, RwMap, Transaction};
+use super::{tx::tx_convert, Bytecode, MptError, MptUpdates, RwMap, Transaction};
 
 /// Block is the struct used by all circuits, which constains all the needed
 /// data for witness generation.
@@ -19,12 +19,17 @@ pub struct Block<F> {
     pub txs: Vec<Transaction>,
     /// Read write events in the RwTable
     pub rws: RwMap,
+    /// MPT updates of the last accesses of the account fields and storage
+    /// slots in the RwTable
+    pub mpt_updates: MptUpdates,
     /// Bytecode used in the block
     pub bytecodes: HashMap<Word, Bytecode>,
-    /// The block context
-    pub context: BlockContext,
+    /// The contexts of the blocks
+    pub context: BlockContexts,
     /// Copy events for the EVM circuit's copy table.
     pub copy_events: Vec<CopyEvent>,
+    /// Exponentiation traces for the exponentiation circuit's table.
+    pub exp_events: Vec<ExpEvent>,
     /// Pad evm circuit to make selectors fixed, so vk/pk can be universal.
     pub evm_circuit_pad_to: usize,
     /// Length to rw table rows in state circuit
@@ -52,104 +57,176 @@ pub struct BlockContext {
     pub history_hashes: Vec<Word>,
     /// The chain id
     pub chain_id: Word,
+    /// The number of transactions of the block
+    pub num_txs: usize,
+    /// The number of transactions of the block and of the previous blocks
+    /// proven together with it
+    pub cum_num_txs: usize,
 }
 
 impl BlockContext {
-    /// Assignments for block table
+    /// Assignments for the block table rows of the fields of the block, which
+    /// are indexed by its number
     pub fn table_assignments<F: Field>(&self, randomness: F) -> Vec<[F; 3]> {
-        [
-            vec![
-                [
-                    F::from(BlockContextFieldTag::Coinbase as u64),
-                    F::zero(),
-                    self.coinbase.to_scalar().unwrap(),
-                ],
-                [
-                    F::from(BlockContextFieldTag::Timestamp as u64),
-                    F::zero(),
-                    self.timestamp.to_scalar().unwrap(),
-                ],
-                [
-                    F::from(BlockContextFieldTag::Number as u64),
-                    F::zero(),
-                    self.number.to_scalar().unwrap(),
-                ],
-                [
-                    F::from(BlockContextFieldTag::Difficulty as u64),
-                    F::zero(),
-                    RandomLinearCombination::random_linear_combine(
-                        self.difficulty.to_le_bytes(),
-                        randomness,
-                    ),
-                ],
-                [
-                    F::from(BlockContextFieldTag::GasLimit as u64),
-                    F::zero(),
-                    F::from(self.gas_limit),
-                ],
-                [
-                    F::from(BlockContextFieldTag::BaseFee as u64),
-                    F::zero(),
-                    RandomLinearCombination::random_linear_combine(
-                        self.base_fee.to_le_bytes(),
-                        randomness,
-                    ),
-                ],
-                [
-                    F::from(BlockContextFieldTag::ChainId as u64),
-                    F::zero(),
-                    RandomLinearCombination::random_linear_combine(
-                        self.chain_id.to_le_bytes(),
-                        randomness,
-                    ),
-                ],
+        let number = self.number.to_scalar().unwrap();
+        vec![
+            [
+                F::from(BlockContextFieldTag::Coinbase as u64),
+                number,
+                self.coinbase.to_scalar().unwrap(),
+            ],
+            [
+                F::from(BlockContextFieldTag::Timestamp as u64),
+                number,
+                self.timestamp.to_scalar().unwrap(),
             ],
-            {
-                let len_history = self.history_hashes.len();
-                self.history_hashes
+            [F::from(BlockContextFieldTag::Number as u64), number, number],
+            [
+                F::from(BlockContextFieldTag::Difficulty as u64),
+                number,
+                RandomLinearCombination::random_linear_combine(
+                    self.difficulty.to_le_bytes(),
+                    randomness,
+                ),
+            ],
+            [
+                F::from(BlockContextFieldTag::GasLimit as u64),
+                number,
+                F::from(self.gas_limit),
+            ],
+            [
+                F::from(BlockContextFieldTag::BaseFee as u64),
+                number,
+                RandomLinearCombination::random_linear_combine(
+                    self.base_fee.to_le_bytes(),
+                    randomness,
+                ),
+            ],
+            [
+                F::from(BlockContextFieldTag::ChainId as u64),
+                number,
+                RandomLinearCombination::random_linear_combine(
+                    self.chain_id.to_le_bytes(),
+                    randomness,
+                ),
+            ],
+            [
+                F::from(BlockContextFieldTag::NumTxs as u64),
+                number,
+                F::from(self.num_txs as u64),
+            ],
+            [
+                F::from(BlockContextFieldTag::CumNumTxs as u64),
+                number,
+                F::from(self.cum_num_txs as u64),
+            ],
+        ]
+    }
+}
+
+/// Contexts of the consecutive blocks proven together
+#[derive(Debug, Default, Clone)]
+pub struct BlockContexts {
+    /// The context of each block, by block number
+    pub ctxs: BTreeMap<u64, BlockContext>,
+}
+
+impl BlockContexts {
+    /// Assignments for block table
+    pub fn table_assignments<F: Field>(&self, randomness: F) -> Vec<[F; 3]> {
+        // The history hashes of consecutive blocks overlap, so each hash is
+        // assigned once, indexed by the number of the hashed block.
+        let history_hashes: BTreeMap<Word, Word> = self
+            .ctxs
+            .values()
+            .flat_map(|ctx| {
+                let len_history = ctx.history_hashes.len();
+                ctx.history_hashes
                     .iter()
                     .enumerate()
-                    .map(|(idx, hash)| {
-                        [
-                            F::from(BlockContextFieldTag::BlockHash as u64),
-                            (self.number - len_history + idx).to_scalar().unwrap(),
-                            RandomLinearCombination::random_linear_combine(
-                                hash.to_le_bytes(),
-                                randomness,
-                            ),
-                        ]
-                    })
-                    .collect()
-            },
-        ]
-        .concat()
+                    .map(move |(idx, hash)| (ctx.number - len_history + idx, *hash))
+            })
+            .collect();
+        self.ctxs
+            .values()
+            .flat_map(|ctx| ctx.table_assignments(randomness))
+            .chain(history_hashes.into_iter().map(|(number, hash)| {
+                [
+                    F::from(BlockContextFieldTag::BlockHash as u64),
+                    number.to_scalar().unwrap(),
+                    RandomLinearCombination::random_linear_combine(hash.to_le_bytes(), randomness),
+                ]
+            }))
+            .collect()
     }
 }
 
-impl From<&circuit_input_builder::Block> for BlockContext {
+impl From<&circuit_input_builder::Block> for BlockContexts {
     fn from(block: &circuit_input_builder::Block) -> Self {
+        let mut cum_num_txs = 0;
         Self {
-            coinbase: block.coinbase,
-            gas_limit: block.gas_limit,
-            number: block.number,
-            timestamp: block.timestamp,
-            difficulty: block.difficulty,
-            base_fee: block.base_fee,
-            history_hashes: block.history_hashes.clone(),
-            chain_id: block.chain_id,
+            ctxs: block
+                .headers
+                .iter()
+                .map(|(block_num, header)| {
+                    let num_txs = block
+                        .txs()
+                        .iter()
+                        .filter(|tx| tx.block_num == *block_num)
+                        .count();
+                    cum_num_txs += num_txs;
+                    (
+                        *block_num,
+                        BlockContext {
+                            coinbase: header.coinbase,
+                            gas_limit: header.gas_limit,
+                            number: header.number,
+                            timestamp: header.timestamp,
+                            difficulty: header.difficulty,
+                            base_fee: header.base_fee,
+                            history_hashes: header.history_hashes.clone(),
+                            chain_id: block.chain_id,
+                            num_txs,
+                            cum_num_txs,
+                        },
+                    )
+                })
+                .collect(),
         }
     }
 }
 
-/// Convert a block struct in bus-mapping to a witness block used in circuits
+/// Return the MPT updates of the last accesses of the block.
+#[cfg(not(any(feature = "test", test)))]
+fn mpt_updates(block: &circuit_input_builder::Block, rws: &RwMap) -> Result<MptUpdates, MptError> {
+    MptUpdates::from_proofs(&rws.table_assignments(), &block.proofs, &block.nodes)
+}
+
+/// Return the MPT updates of the last accesses of the block.  In tests, the
+/// state of a mock block isn't queried from geth, so the updates of a block
+/// without proofs are mocked.
+#[cfg(any(feature = "test", test))]
+fn mpt_updates(block: &circuit_input_builder::Block, rws: &RwMap) -> Result<MptUpdates, MptError> {
+    if block.proofs.is_empty() {
+        return Ok(MptUpdates::mock_from(&rws.table_assignments()));
+    }
+    MptUpdates::from_proofs(&rws.table_assignments(), &block.proofs, &block.nodes)
+}
+
+/// Convert a block struct in bus-mapping to a witness block used in circuits.
+/// The MPT updates are computed from the `eth_getProof` responses of the
+/// block.
 pub fn block_convert(
     block: &circuit_input_builder::Block,
     code_db: &bus_mapping::state_db::CodeDB,
-) -> Block<Fr> {
-    Block {
+) -> Result<Block<Fr>, MptError> {
+    let rws = RwMap::from(&block.container);
+    let mpt_updates = mpt_updates(block, &rws)?;
+    Ok(Block {
         randomness: Fr::from(0xcafeu64),
         context: block.into(),
-        rws: RwMap::from(&block.container),
+        mpt_updates,
+        rws,
         txs: block
             .txs()
             .iter()
@@ -171,9 +248,16 @@ pub fn block_convert(
                         (bytecode.hash, bytecode)
                     })
             })
+            // Bytecodes of accounts which are not called might still be
+            // accessed by EXTCODESIZE and EXTCODECOPY.
+            .chain(code_db.0.values().map(|code| {
+                let bytecode = Bytecode::new(code.clone());
+                (bytecode.hash, bytecode)
+            }))
             .collect(),
         copy_events: block.copy_events.clone(),
+        exp_events: block.exp_events.clone(),
         sha3_inputs: block.sha3_inputs.clone(),
         ..Default::default()
-    }
+    })
 }
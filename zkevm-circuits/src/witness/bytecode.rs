@@ -42,25 +42,39 @@ impl Bytecode {
             Value::known(F::from(self.bytes.len() as u64)),
         ]);
 
-        let mut push_data_left = 0;
-        for (idx, byte) in self.bytes.iter().enumerate() {
-            let mut is_code = true;
-            if push_data_left > 0 {
-                is_code = false;
-                push_data_left -= 1;
-            } else if (OpcodeId::PUSH1.as_u8()..=OpcodeId::PUSH32.as_u8()).contains(byte) {
-                push_data_left = *byte as usize - (OpcodeId::PUSH1.as_u8() - 1) as usize;
-            }
+        for (idx, (byte, is_code)) in self.bytes_with_is_code().enumerate() {
             rows.push([
                 hash,
                 Value::known(F::from(BytecodeFieldTag::Byte as u64)),
                 Value::known(F::from(idx as u64)),
                 Value::known(F::from(is_code as u64)),
-                Value::known(F::from(*byte as u64)),
+                Value::known(F::from(byte as u64)),
             ])
         }
         rows
     }
+
+    /// Get the byte at `index`, and whether it's an opcode rather than the
+    /// data of a PUSH* operation
+    pub fn get(&self, index: usize) -> (u8, bool) {
+        self.bytes_with_is_code()
+            .nth(index)
+            .expect("index out of bytecode")
+    }
+
+    fn bytes_with_is_code(&self) -> impl Iterator<Item = (u8, bool)> + '_ {
+        let mut push_data_left = 0;
+        self.bytes.iter().map(move |byte| {
+            let mut is_code = true;
+            if push_data_left > 0 {
+                is_code = false;
+                push_data_left -= 1;
+            } else if (OpcodeId::PUSH1.as_u8()..=OpcodeId::PUSH32.as_u8()).contains(byte) {
+                push_data_left = *byte as usize - (OpcodeId::PUSH1.as_u8() - 1) as usize;
+            }
+            (*byte, is_code)
+        })
+    }
 }
 
 impl From<&eth_types::bytecode::Bytecode> for Bytecode {
@@ -1,8 +1,19 @@
 use crate::evm_circuit::{util::RandomLinearCombination, witness::Rw};
 use crate::table::{AccountFieldTag, ProofType};
-use eth_types::{Address, Field, ToLittleEndian, ToScalar, Word};
+use eth_types::{
+    geth_types::Account, Address, Bytes, EIP1186ProofResponse, Field, StorageProof, ToBigEndian,
+    ToLittleEndian, ToScalar, Word, H256,
+};
+use ethers_core::utils::{keccak256, rlp};
 use itertools::Itertools;
-use std::collections::HashMap;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    error::Error as StdError,
+    fmt::{Display, Formatter, Result as FmtResult},
+};
+use trie::{empty_root, NodeDb, Trie};
+
+mod trie;
 
 /// An MPT update whose validility is proved by the MptCircuit
 #[derive(Debug, Clone, Copy)]
@@ -15,8 +26,88 @@ pub struct MptUpdate {
 }
 
 /// All the MPT updates in the MptCircuit, accessible by their key
-#[derive(Default, Clone)]
-pub struct MptUpdates(HashMap<Key, MptUpdate>);
+#[derive(Debug, Default, Clone)]
+pub struct MptUpdates {
+    updates: HashMap<Key, MptUpdate>,
+    witnesses: Vec<MptUpdateWitness>,
+    old_root: Word,
+    new_root: Word,
+}
+
+/// Error when replaying the MPT updates on the trie of `eth_getProof`
+/// responses
+#[derive(Debug)]
+pub enum MptError {
+    /// A node on the path of an updated key is not in the proofs.  This is
+    /// also the case of the sibling of a deleted key, when its branch is
+    /// collapsed.
+    MissingNode(H256),
+    /// A node of the proofs is not a valid trie node.
+    InvalidNode,
+    /// An updated account has no proof.
+    MissingAccount(Address),
+}
+
+impl Display for MptError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl StdError for MptError {}
+
+/// The rlp-encoded nodes of a Merkle Patricia Trie proof, as returned by
+/// `eth_getProof`: from the state root to the account and, for a storage key,
+/// from the storage root of the account to the storage slot.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MptProof {
+    /// Nodes from the state root to the account
+    pub account_proof: Vec<Vec<u8>>,
+    /// Nodes from the storage root of the account to the storage slot
+    pub storage_proof: Vec<Vec<u8>>,
+}
+
+impl MptProof {
+    /// Take the proof of the account, and the proof of the storage key if
+    /// any, from an `eth_getProof` response.
+    pub fn from_eip1186(proof: &EIP1186ProofResponse, storage_key: Option<Word>) -> Self {
+        let storage_proof = storage_key
+            .and_then(|storage_key| {
+                proof
+                    .storage_proof
+                    .iter()
+                    .find(|storage_proof| storage_proof.key == storage_key)
+            })
+            .map(|storage_proof| {
+                storage_proof
+                    .proof
+                    .iter()
+                    .map(|node| node.to_vec())
+                    .collect()
+            })
+            .unwrap_or_default();
+        Self {
+            account_proof: proof
+                .account_proof
+                .iter()
+                .map(|node| node.to_vec())
+                .collect(),
+            storage_proof,
+        }
+    }
+}
+
+/// An MPT update together with the proofs of its key before and after it,
+/// which is the witness of the MptCircuit.
+#[derive(Debug, Clone)]
+pub struct MptUpdateWitness {
+    /// The MPT update
+    pub update: MptUpdate,
+    /// Proof of the key in the trie of `old_root`
+    pub old_proof: MptProof,
+    /// Proof of the key in the trie of `new_root`
+    pub new_proof: MptProof,
+}
 
 /// The field element encoding of an MPT update, which is used by the MptTable
 #[derive(Debug, Clone, Copy)]
@@ -24,11 +115,137 @@ pub struct MptUpdateRow<F>([F; 7]);
 
 impl MptUpdates {
     pub(crate) fn get(&self, row: &Rw) -> Option<MptUpdate> {
-        key(row).map(|key| *self.0.get(&key).expect("missing key in mpt updates"))
+        key(row).map(|key| *self.updates.get(&key).expect("missing key in mpt updates"))
+    }
+
+    /// The state root before the updates
+    pub fn old_root(&self) -> Word {
+        self.old_root
+    }
+
+    /// The state root after the updates
+    pub fn new_root(&self) -> Word {
+        self.new_root
+    }
+
+    /// The updates together with their proofs, in the order they are applied
+    /// to the state trie, which are empty for mocked updates
+    pub fn witnesses(&self) -> &[MptUpdateWitness] {
+        &self.witnesses
+    }
+
+    pub(crate) fn old_root_assignment<F: Field>(&self, randomness: F) -> F {
+        RandomLinearCombination::random_linear_combine(self.old_root.to_le_bytes(), randomness)
+    }
+
+    /// Return the MPT updates of the last access of each account field and
+    /// storage slot of `rows`, which are sorted as in the RwTable.  The updates
+    /// are applied in this order to the state trie before the block, given by
+    /// the `eth_getProof` responses of all the accessed accounts and storage
+    /// slots, to compute the intermediate state roots and the proofs of each
+    /// update.  The `nodes` off the proofs are the siblings of the deleted
+    /// keys, which their branch is collapsed into.
+    pub fn from_proofs(
+        rows: &[Rw],
+        proofs: &[EIP1186ProofResponse],
+        nodes: &[Bytes],
+    ) -> Result<Self, MptError> {
+        let mut db = NodeDb::new();
+        for node in proofs
+            .iter()
+            .flat_map(|proof| {
+                proof.account_proof.iter().chain(
+                    proof
+                        .storage_proof
+                        .iter()
+                        .flat_map(|storage_proof| storage_proof.proof.iter()),
+                )
+            })
+            .chain(nodes)
+        {
+            db.insert(keccak256(node), node.to_vec());
+        }
+        let mut accounts: HashMap<Address, AccountState> = proofs
+            .iter()
+            .map(|proof| (proof.address, AccountState::from(proof)))
+            .collect();
+
+        let old_root = proofs
+            .iter()
+            .find_map(|proof| proof.account_proof.first())
+            .map(keccak256)
+            .unwrap_or_else(empty_root);
+        let mut trie = Trie::new(old_root);
+        let mut root = Word::from_big_endian(&old_root);
+
+        let mut updates = HashMap::new();
+        let mut witnesses = vec![];
+        for (key, mut key_rows) in &rows.iter().group_by(|row| key(row)) {
+            let key = match key {
+                Some(key) => key,
+                None => continue,
+            };
+            let first = key_rows.next().unwrap();
+            let last = key_rows.last().unwrap_or(first);
+            let (old_value, new_value) = (value_prev(first), value(last));
+            let old_proof = prove(&trie, &accounts, &key, &db)?;
+
+            // The trie is left untouched by reads, so that the accounts which
+            // don't exist aren't created.
+            if new_value != old_value {
+                let address = key.account_address();
+                let account = accounts
+                    .get_mut(&address)
+                    .ok_or(MptError::MissingAccount(address))?;
+                match key {
+                    Key::Account { field_tag, .. } => match field_tag {
+                        AccountFieldTag::Nonce => account.nonce = new_value,
+                        AccountFieldTag::Balance => account.balance = new_value,
+                        AccountFieldTag::CodeHash => {
+                            account.code_hash = H256::from(new_value.to_be_bytes())
+                        }
+                    },
+                    Key::AccountStorage { storage_key, .. } => account.storage.insert(
+                        keccak256(storage_key.to_be_bytes()),
+                        if new_value.is_zero() {
+                            vec![]
+                        } else {
+                            rlp::encode(&new_value).to_vec()
+                        },
+                        &db,
+                    )?,
+                    Key::AccountDoesNotExist { .. } => unreachable!("not an rw key"),
+                }
+                trie.insert(keccak256(address), account.leaf_value(), &db)?;
+            }
+
+            let new_root = Word::from_big_endian(&trie.root());
+            let update = MptUpdate {
+                key,
+                old_value,
+                new_value,
+                old_root: root,
+                new_root,
+            };
+            updates.insert(key, update);
+            witnesses.push(MptUpdateWitness {
+                update,
+                old_proof,
+                new_proof: prove(&trie, &accounts, &key, &db)?,
+            });
+            root = new_root;
+        }
+
+        Ok(Self {
+            updates,
+            witnesses,
+            old_root: Word::from_big_endian(&old_root),
+            new_root: root,
+        })
     }
 
     pub(crate) fn mock_from(rows: &[Rw]) -> Self {
-        let map: HashMap<_, _> = rows
+        let updates: HashMap<_, _> = rows
             .iter()
             .group_by(|row| key(row))
             .into_iter()
@@ -49,30 +266,208 @@ impl MptUpdates {
                 )
             })
             .collect();
-        MptUpdates(map)
+        let new_root = Word::from(updates.len() as u64);
+        MptUpdates {
+            updates,
+            witnesses: vec![],
+            old_root: Word::zero(),
+            new_root,
+        }
+    }
+
+    /// Return the `eth_getProof` responses of the accounts and storage slots
+    /// of `rows` in the state trie of `accounts`, which are all the accounts
+    /// of the state.  This is the state of the mock blocks, which are not
+    /// queried from geth.
+    pub fn proofs_from_accounts(
+        accounts: &[Account],
+        rows: &[Rw],
+    ) -> Result<Vec<EIP1186ProofResponse>, MptError> {
+        // All the nodes of the tries are known, so the db is empty.
+        let db = NodeDb::new();
+        let mut trie = Trie::new(empty_root());
+        let mut states = HashMap::new();
+        for account in accounts {
+            let mut storage = Trie::new(empty_root());
+            for (storage_key, value) in account.storage.iter() {
+                if !value.is_zero() {
+                    storage.insert(
+                        keccak256(storage_key.to_be_bytes()),
+                        rlp::encode(value).to_vec(),
+                        &db,
+                    )?;
+                }
+            }
+            let state = AccountState {
+                nonce: account.nonce,
+                balance: account.balance,
+                code_hash: H256::from(keccak256(&account.code)),
+                storage,
+            };
+            trie.insert(keccak256(account.address), state.leaf_value(), &db)?;
+            states.insert(account.address, (account, state));
+        }
+
+        let mut keys: BTreeMap<Address, BTreeSet<Word>> = BTreeMap::new();
+        for key in rows.iter().filter_map(key) {
+            let storage_keys = keys.entry(key.account_address()).or_default();
+            if let Key::AccountStorage { storage_key, .. } = key {
+                storage_keys.insert(storage_key);
+            }
+        }
+        keys.into_iter()
+            .map(|(address, storage_keys)| {
+                let to_bytes = |proof: Vec<Vec<u8>>| -> Vec<Bytes> {
+                    proof.into_iter().map(Bytes::from).collect()
+                };
+                let account_proof = to_bytes(trie.prove(keccak256(address), &db)?);
+                let (account, state) = match states.get(&address) {
+                    Some((account, state)) => (Some(*account), Some(state)),
+                    None => (None, None),
+                };
+                let storage_proof = storage_keys
+                    .into_iter()
+                    .map(|storage_key| {
+                        Ok::<_, MptError>(StorageProof {
+                            key: storage_key,
+                            value: account
+                                .and_then(|account| account.storage.get(&storage_key))
+                                .copied()
+                                .unwrap_or_default(),
+                            proof: match state {
+                                Some(state) => to_bytes(
+                                    state
+                                        .storage
+                                        .prove(keccak256(storage_key.to_be_bytes()), &db)?,
+                                ),
+                                None => vec![],
+                            },
+                        })
+                    })
+                    .collect::<Result<_, _>>()?;
+                Ok::<_, MptError>(EIP1186ProofResponse {
+                    address,
+                    balance: state.map(|state| state.balance).unwrap_or_default(),
+                    code_hash: state.map(|state| state.code_hash).unwrap_or_default(),
+                    nonce: state.map(|state| state.nonce).unwrap_or_default(),
+                    storage_hash: H256::from(
+                        state.map_or_else(empty_root, |state| state.storage.root()),
+                    ),
+                    account_proof,
+                    storage_proof,
+                })
+            })
+            .collect()
     }
 
     pub(crate) fn table_assignments<F: Field>(&self, randomness: F) -> Vec<MptUpdateRow<F>> {
-        self.0
+        self.updates
             .values()
-            .map(|update| {
-                let (new_root, old_root) = update.root_assignments(randomness);
-                let (new_value, old_value) = update.value_assignments(randomness);
-                MptUpdateRow([
-                    update.key.address(),
-                    update.key.storage_key(randomness),
-                    update.key.proof_type(),
-                    new_root,
-                    old_root,
-                    new_value,
-                    old_value,
-                ])
-            })
+            .map(|update| update.table_assignment(randomness))
             .collect()
     }
 }
 
 impl MptUpdate {
+    /// Return the update of a field of an account from `old_root` to
+    /// `new_root`.
+    pub fn account(
+        address: Address,
+        field_tag: AccountFieldTag,
+        old_value: Word,
+        new_value: Word,
+        old_root: Word,
+        new_root: Word,
+    ) -> Self {
+        Self {
+            key: Key::Account { address, field_tag },
+            old_value,
+            new_value,
+            old_root,
+            new_root,
+        }
+    }
+
+    /// Return the update of a storage slot of an account from `old_root` to
+    /// `new_root`.
+    pub fn storage(
+        tx_id: usize,
+        address: Address,
+        storage_key: Word,
+        old_value: Word,
+        new_value: Word,
+        old_root: Word,
+        new_root: Word,
+    ) -> Self {
+        Self {
+            key: Key::AccountStorage {
+                tx_id,
+                address,
+                storage_key,
+            },
+            old_value,
+            new_value,
+            old_root,
+            new_root,
+        }
+    }
+
+    /// Return the proof that an account doesn't exist in the trie of `root`.
+    pub fn account_does_not_exist(address: Address, root: Word) -> Self {
+        Self {
+            key: Key::AccountDoesNotExist { address },
+            old_value: Word::zero(),
+            new_value: Word::zero(),
+            old_root: root,
+            new_root: root,
+        }
+    }
+
+    pub(crate) fn address(&self) -> Address {
+        self.key.account_address()
+    }
+
+    pub(crate) fn storage_key(&self) -> Option<Word> {
+        match self.key {
+            Key::AccountStorage { storage_key, .. } => Some(storage_key),
+            Key::Account { .. } | Key::AccountDoesNotExist { .. } => None,
+        }
+    }
+
+    pub(crate) fn old_root(&self) -> Word {
+        self.old_root
+    }
+
+    pub(crate) fn new_root(&self) -> Word {
+        self.new_root
+    }
+
+    pub(crate) fn old_value(&self) -> Word {
+        self.old_value
+    }
+
+    pub(crate) fn new_value(&self) -> Word {
+        self.new_value
+    }
+
+    pub(crate) fn proof_type(&self) -> ProofType {
+        self.key.proof_type()
+    }
+
+    pub(crate) fn table_assignment<F: Field>(&self, randomness: F) -> MptUpdateRow<F> {
+        let (new_root, old_root) = self.root_assignments(randomness);
+        let (new_value, old_value) = self.value_assignments(randomness);
+        MptUpdateRow([
+            self.key.address(),
+            self.key.storage_key(randomness),
+            F::from(self.key.proof_type() as u64),
+            new_root,
+            old_root,
+            new_value,
+            old_value,
+        ])
+    }
+
     pub(crate) fn value_assignments<F: Field>(&self, word_randomness: F) -> (F, F) {
         let assign = |x: Word| match self.key {
             Key::Account {
@@ -110,26 +505,32 @@ enum Key {
         address: Address,
         storage_key: Word,
     },
+    AccountDoesNotExist {
+        address: Address,
+    },
 }
 
 impl Key {
-    fn address<F: Field>(&self) -> F {
+    fn account_address(&self) -> Address {
         match self {
-            Self::Account { address, .. } | Self::AccountStorage { address, .. } => {
-                address.to_scalar().unwrap()
-            }
+            Self::Account { address, .. }
+            | Self::AccountStorage { address, .. }
+            | Self::AccountDoesNotExist { address } => *address,
         }
     }
-    fn proof_type<F: Field>(&self) -> F {
-        let proof_type = match self {
+    fn address<F: Field>(&self) -> F {
+        self.account_address().to_scalar().unwrap()
+    }
+    fn proof_type(&self) -> ProofType {
+        match self {
             Self::AccountStorage { .. } => ProofType::StorageChanged,
             Self::Account { field_tag, .. } => (*field_tag).into(),
-        };
-        F::from(proof_type as u64)
+            Self::AccountDoesNotExist { .. } => ProofType::AccountDoesNotExist,
+        }
     }
     fn storage_key<F: Field>(&self, randomness: F) -> F {
         match self {
-            Self::Account { .. } => F::zero(),
+            Self::Account { .. } | Self::AccountDoesNotExist { .. } => F::zero(),
             Self::AccountStorage { storage_key, .. } => {
                 RandomLinearCombination::random_linear_combine(
                     storage_key.to_le_bytes(),
@@ -148,6 +549,74 @@ impl<F: Field> MptUpdateRow<F> {
     }
 }
 
+// The state of an account in the trie, as it's updated
+struct AccountState {
+    nonce: Word,
+    balance: Word,
+    code_hash: H256,
+    storage: Trie,
+}
+
+impl From<&EIP1186ProofResponse> for AccountState {
+    fn from(proof: &EIP1186ProofResponse) -> Self {
+        Self {
+            nonce: proof.nonce,
+            balance: proof.balance,
+            code_hash: proof.code_hash,
+            storage: Trie::new(proof.storage_hash.to_fixed_bytes()),
+        }
+    }
+}
+
+impl AccountState {
+    // Return the rlp encoding of the account, which is empty when the account
+    // is empty and doesn't belong in the trie, as in EIP-161.
+    fn leaf_value(&self) -> Vec<u8> {
+        let code_hash = if self.code_hash.is_zero() {
+            H256::from(keccak256([]))
+        } else {
+            self.code_hash
+        };
+        let storage_root = H256::from(self.storage.root());
+        if self.nonce.is_zero()
+            && self.balance.is_zero()
+            && code_hash == H256::from(keccak256([]))
+            && storage_root == H256::from(empty_root())
+        {
+            return vec![];
+        }
+
+        let mut stream = rlp::RlpStream::new_list(4);
+        stream
+            .append(&self.nonce)
+            .append(&self.balance)
+            .append(&storage_root)
+            .append(&code_hash);
+        stream.out().to_vec()
+    }
+}
+
+// Return the proof of a key in the state trie, and in the storage trie of its
+// account for a storage key.
+fn prove(
+    trie: &Trie,
+    accounts: &HashMap<Address, AccountState>,
+    key: &Key,
+    db: &NodeDb,
+) -> Result<MptProof, MptError> {
+    let address = key.account_address();
+    let storage_proof = match (key, accounts.get(&address)) {
+        (Key::AccountStorage { storage_key, .. }, Some(account)) => account
+            .storage
+            .prove(keccak256(storage_key.to_be_bytes()), db)?,
+        _ => vec![],
+    };
+    Ok(MptProof {
+        account_proof: trie.prove(keccak256(address), db)?,
+        storage_proof,
+    })
+}
+
 fn key(row: &Rw) -> Option<Key> {
     match row {
         Rw::Account {
@@ -187,3 +656,157 @@ fn value_prev(row: &Rw) -> Word {
         _ => unreachable!(),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers_core::utils::rlp::RlpStream;
+
+    fn leaf(key: [u8; 32], value: Vec<u8>) -> Vec<u8> {
+        let mut stream = RlpStream::new_list(2);
+        stream
+            .append(&[vec![0x20], key.to_vec()].concat())
+            .append(&value);
+        stream.out().to_vec()
+    }
+
+    fn account_leaf(address: Address, nonce: u64, balance: u64, storage_root: [u8; 32]) -> Vec<u8> {
+        let mut stream = RlpStream::new_list(4);
+        stream
+            .append(&Word::from(nonce))
+            .append(&Word::from(balance))
+            .append(&H256::from(storage_root))
+            .append(&H256::from(keccak256([])));
+        leaf(keccak256(address), stream.out().to_vec())
+    }
+
+    fn root(node: &[u8]) -> Word {
+        Word::from_big_endian(&keccak256(node))
+    }
+
+    #[test]
+    fn from_proofs() {
+        let address = Address::repeat_byte(0x11);
+        let storage_key = Word::from(3);
+        let old_leaf = account_leaf(address, 0, 10, empty_root());
+        // The proof of an account which is the only one in the trie, with an
+        // empty storage.
+        let proof = EIP1186ProofResponse {
+            address,
+            balance: Word::from(10),
+            code_hash: H256::from(keccak256([])),
+            nonce: Word::zero(),
+            storage_hash: H256::from(empty_root()),
+            account_proof: vec![Bytes::from(old_leaf.clone())],
+            storage_proof: vec![StorageProof {
+                key: storage_key,
+                value: Word::zero(),
+                proof: vec![],
+            }],
+        };
+
+        let rows = [
+            Rw::AccountStorage {
+                rw_counter: 1,
+                is_write: true,
+                account_address: address,
+                storage_key,
+                value: Word::from(5),
+                value_prev: Word::zero(),
+                tx_id: 1,
+                committed_value: Word::zero(),
+            },
+            Rw::Account {
+                rw_counter: 2,
+                is_write: true,
+                account_address: address,
+                field_tag: AccountFieldTag::Nonce,
+                value: Word::one(),
+                value_prev: Word::zero(),
+            },
+            Rw::Account {
+                rw_counter: 3,
+                is_write: false,
+                account_address: address,
+                field_tag: AccountFieldTag::Balance,
+                value: Word::from(10),
+                value_prev: Word::from(10),
+            },
+        ];
+        let updates = MptUpdates::from_proofs(&rows, &[proof], &[]).unwrap();
+
+        let storage_root = keccak256(leaf(
+            keccak256(storage_key.to_be_bytes()),
+            rlp::encode(&Word::from(5)).to_vec(),
+        ));
+        let roots = [
+            root(&old_leaf),
+            root(&account_leaf(address, 0, 10, storage_root)),
+            root(&account_leaf(address, 1, 10, storage_root)),
+        ];
+        assert_eq!(updates.old_root(), roots[0]);
+        assert_eq!(updates.new_root(), roots[2]);
+        for (row, (old_root, new_root)) in rows
+            .iter()
+            .zip([(0, 1), (1, 2), (2, 2)].map(|(i, j)| (roots[i], roots[j])))
+        {
+            let update = updates.get(row).unwrap();
+            assert_eq!((update.old_root(), update.new_root()), (old_root, new_root));
+        }
+        for witness in updates.witnesses() {
+            for (proof, root) in [
+                (&witness.old_proof, witness.update.old_root()),
+                (&witness.new_proof, witness.update.new_root()),
+            ] {
+                assert_eq!(
+                    Word::from_big_endian(&keccak256(&proof.account_proof[0])),
+                    root
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn proofs_from_accounts() {
+        let address = Address::repeat_byte(0x11);
+        let account = Account {
+            address,
+            balance: Word::from(10),
+            ..Default::default()
+        };
+        let row = Rw::Account {
+            rw_counter: 1,
+            is_write: true,
+            account_address: address,
+            field_tag: AccountFieldTag::Nonce,
+            value: Word::one(),
+            value_prev: Word::zero(),
+        };
+        let proofs = MptUpdates::proofs_from_accounts(&[account], &[row]).unwrap();
+        let old_leaf = account_leaf(address, 0, 10, empty_root());
+        assert_eq!(proofs.len(), 1);
+        assert_eq!(proofs[0].account_proof, vec![Bytes::from(old_leaf)]);
+
+        let updates = MptUpdates::from_proofs(&[row], &proofs, &[]).unwrap();
+        assert_eq!(
+            updates.new_root(),
+            root(&account_leaf(address, 1, 10, empty_root()))
+        );
+    }
+
+    #[test]
+    fn from_proofs_missing_account() {
+        let row = Rw::Account {
+            rw_counter: 1,
+            is_write: true,
+            account_address: Address::repeat_byte(0x11),
+            field_tag: AccountFieldTag::Nonce,
+            value: Word::one(),
+            value_prev: Word::zero(),
+        };
+        assert!(matches!(
+            MptUpdates::from_proofs(&[row], &[], &[]),
+            Err(MptError::MissingAccount(_))
+        ));
+    }
+}
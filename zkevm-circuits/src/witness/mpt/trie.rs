@@ -0,0 +1,575 @@
+//! An in-memory Merkle Patricia Trie built from the nodes of `eth_getProof`
+//! responses.  Only the nodes of the proven keys, and the siblings they are
+//! collapsed into when they are deleted, are known.  The rest of the trie is
+//! referred to by hash, which is enough to update the proven keys and compute
+//! the new root.
+
+use super::MptError;
+use eth_types::H256;
+use ethers_core::utils::{
+    keccak256,
+    rlp::{Rlp, RlpStream},
+};
+use std::collections::HashMap;
+
+/// The rlp-encoded nodes of the proofs, by their hash
+pub(crate) type NodeDb = HashMap<[u8; 32], Vec<u8>>;
+
+/// Root of the empty trie, `keccak256(rlp(""))`
+pub(crate) fn empty_root() -> [u8; 32] {
+    keccak256([0x80])
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Empty,
+    Leaf { path: Vec<u8>, value: Vec<u8> },
+    Extension { path: Vec<u8>, child: Box<Node> },
+    Branch { children: Box<[Node; 16]> },
+    Hash([u8; 32]),
+}
+
+impl Default for Node {
+    fn default() -> Self {
+        Node::Empty
+    }
+}
+
+/// A partial Merkle Patricia Trie with 32 byte keys, whose unknown nodes are
+/// resolved from a [`NodeDb`] when they are on the path of an update.
+#[derive(Debug, Clone)]
+pub(crate) struct Trie {
+    root: Node,
+}
+
+impl Trie {
+    /// Return the trie with the given root.
+    pub(crate) fn new(root: [u8; 32]) -> Self {
+        Self {
+            root: if root == empty_root() || root == [0; 32] {
+                Node::Empty
+            } else {
+                Node::Hash(root)
+            },
+        }
+    }
+
+    /// Set the value of a key, removing the key when the value is empty.
+    pub(crate) fn insert(
+        &mut self,
+        key: [u8; 32],
+        value: Vec<u8>,
+        db: &NodeDb,
+    ) -> Result<(), MptError> {
+        let root = std::mem::replace(&mut self.root, Node::Empty);
+        let path = nibbles(&key);
+        self.root = if value.is_empty() {
+            delete(root, &path, db)?
+        } else {
+            insert(root, &path, value, db)?
+        };
+        Ok(())
+    }
+
+    /// Return the proof of a key, as in `eth_getProof`: the nodes on the path
+    /// of the key from the root, except the ones inlined in their parent.
+    pub(crate) fn prove(&self, key: [u8; 32], db: &NodeDb) -> Result<Vec<Vec<u8>>, MptError> {
+        let mut proof = vec![];
+        prove(&self.root, &nibbles(&key), db, &mut proof)?;
+        Ok(proof)
+    }
+
+    /// Return the root hash of the trie.
+    pub(crate) fn root(&self) -> [u8; 32] {
+        match &self.root {
+            Node::Empty => empty_root(),
+            Node::Hash(hash) => *hash,
+            node => keccak256(encode(node)),
+        }
+    }
+}
+
+fn nibbles(bytes: &[u8]) -> Vec<u8> {
+    bytes
+        .iter()
+        .flat_map(|byte| [byte >> 4, byte & 0x0f])
+        .collect()
+}
+
+fn common_prefix_len(a: &[u8], b: &[u8]) -> usize {
+    a.iter().zip(b.iter()).take_while(|(a, b)| a == b).count()
+}
+
+fn resolve(node: Node, db: &NodeDb) -> Result<Node, MptError> {
+    match node {
+        Node::Hash(hash) => {
+            let bytes = db
+                .get(&hash)
+                .ok_or_else(|| MptError::MissingNode(H256::from(hash)))?;
+            decode(&Rlp::new(bytes))
+        }
+        node => Ok(node),
+    }
+}
+
+fn branch(children: impl IntoIterator<Item = (u8, Node)>) -> Node {
+    let mut branch: [Node; 16] = Default::default();
+    for (nibble, child) in children {
+        branch[nibble as usize] = child;
+    }
+    Node::Branch {
+        children: Box::new(branch),
+    }
+}
+
+// Prepend `path` to a node, which is the remainder of the path of a branch
+// child.
+fn with_prefix(path: Vec<u8>, node: Node) -> Node {
+    match node {
+        Node::Leaf {
+            path: node_path,
+            value,
+        } => Node::Leaf {
+            path: [path, node_path].concat(),
+            value,
+        },
+        Node::Extension {
+            path: node_path,
+            child,
+        } => Node::Extension {
+            path: [path, node_path].concat(),
+            child,
+        },
+        node if path.is_empty() => node,
+        node => Node::Extension {
+            path,
+            child: Box::new(node),
+        },
+    }
+}
+
+fn insert(node: Node, path: &[u8], value: Vec<u8>, db: &NodeDb) -> Result<Node, MptError> {
+    Ok(match resolve(node, db)? {
+        Node::Empty => Node::Leaf {
+            path: path.to_vec(),
+            value,
+        },
+        Node::Leaf {
+            path: leaf_path,
+            value: leaf_value,
+        } => {
+            if leaf_path == path {
+                return Ok(Node::Leaf {
+                    path: leaf_path,
+                    value,
+                });
+            }
+            let n = common_prefix_len(&leaf_path, path);
+            with_prefix(
+                path[..n].to_vec(),
+                branch([
+                    (
+                        leaf_path[n],
+                        Node::Leaf {
+                            path: leaf_path[n + 1..].to_vec(),
+                            value: leaf_value,
+                        },
+                    ),
+                    (
+                        path[n],
+                        Node::Leaf {
+                            path: path[n + 1..].to_vec(),
+                            value,
+                        },
+                    ),
+                ]),
+            )
+        }
+        Node::Extension {
+            path: extension_path,
+            child,
+        } => {
+            let n = common_prefix_len(&extension_path, path);
+            if n == extension_path.len() {
+                return Ok(Node::Extension {
+                    child: Box::new(insert(*child, &path[n..], value, db)?),
+                    path: extension_path,
+                });
+            }
+            with_prefix(
+                path[..n].to_vec(),
+                branch([
+                    (
+                        extension_path[n],
+                        with_prefix(extension_path[n + 1..].to_vec(), *child),
+                    ),
+                    (
+                        path[n],
+                        Node::Leaf {
+                            path: path[n + 1..].to_vec(),
+                            value,
+                        },
+                    ),
+                ]),
+            )
+        }
+        Node::Branch { mut children } => {
+            let nibble = path[0] as usize;
+            let child = std::mem::replace(&mut children[nibble], Node::Empty);
+            children[nibble] = insert(child, &path[1..], value, db)?;
+            Node::Branch { children }
+        }
+        Node::Hash(_) => unreachable!("resolved node"),
+    })
+}
+
+fn delete(node: Node, path: &[u8], db: &NodeDb) -> Result<Node, MptError> {
+    Ok(match resolve(node, db)? {
+        Node::Empty => Node::Empty,
+        Node::Leaf {
+            path: leaf_path,
+            value,
+        } => {
+            if leaf_path == path {
+                Node::Empty
+            } else {
+                Node::Leaf {
+                    path: leaf_path,
+                    value,
+                }
+            }
+        }
+        Node::Extension {
+            path: extension_path,
+            child,
+        } => {
+            if !path.starts_with(&extension_path) {
+                return Ok(Node::Extension {
+                    path: extension_path,
+                    child,
+                });
+            }
+            match delete(*child, &path[extension_path.len()..], db)? {
+                Node::Empty => Node::Empty,
+                child => with_prefix(extension_path, child),
+            }
+        }
+        Node::Branch { mut children } => {
+            let nibble = path[0] as usize;
+            let child = std::mem::replace(&mut children[nibble], Node::Empty);
+            children[nibble] = delete(child, &path[1..], db)?;
+
+            let remaining: Vec<usize> = children
+                .iter()
+                .enumerate()
+                .filter(|(_, child)| !matches!(child, Node::Empty))
+                .map(|(nibble, _)| nibble)
+                .collect();
+            match remaining.as_slice() {
+                [] => Node::Empty,
+                // A branch with a single child is replaced by the child, which
+                // must be known to be merged with the nibble of the branch.  It
+                // isn't in the proof of the deleted key, but it's fetched
+                // together with the proofs.
+                [nibble] => {
+                    let child = std::mem::replace(&mut children[*nibble], Node::Empty);
+                    match resolve(child, db)? {
+                        child @ Node::Branch { .. } => Node::Extension {
+                            path: vec![*nibble as u8],
+                            child: Box::new(child),
+                        },
+                        child => with_prefix(vec![*nibble as u8], child),
+                    }
+                }
+                _ => Node::Branch { children },
+            }
+        }
+        Node::Hash(_) => unreachable!("resolved node"),
+    })
+}
+
+fn prove(node: &Node, path: &[u8], db: &NodeDb, proof: &mut Vec<Vec<u8>>) -> Result<(), MptError> {
+    let resolved;
+    let node = match node {
+        Node::Hash(_) => {
+            resolved = resolve(node.clone(), db)?;
+            &resolved
+        }
+        node => node,
+    };
+    if matches!(node, Node::Empty) {
+        return Ok(());
+    }
+    let encoded = encode(node);
+    if proof.is_empty() || encoded.len() >= 32 {
+        proof.push(encoded);
+    }
+    match node {
+        Node::Extension {
+            path: extension_path,
+            child,
+        } if path.starts_with(extension_path) => {
+            prove(child, &path[extension_path.len()..], db, proof)
+        }
+        Node::Branch { children } => prove(&children[path[0] as usize], &path[1..], db, proof),
+        _ => Ok(()),
+    }
+}
+
+fn hex_prefix(path: &[u8], is_leaf: bool) -> Vec<u8> {
+    let flag = if is_leaf { 2 } else { 0 };
+    let (first, rest) = if path.len() % 2 == 1 {
+        (((flag + 1) << 4) | path[0], &path[1..])
+    } else {
+        (flag << 4, path)
+    };
+    std::iter::once(first)
+        .chain(rest.chunks(2).map(|pair| (pair[0] << 4) | pair[1]))
+        .collect()
+}
+
+fn decode(rlp: &Rlp) -> Result<Node, MptError> {
+    let invalid = |_| MptError::InvalidNode;
+    if rlp.is_data() {
+        let data = rlp.data().map_err(invalid)?;
+        return match data.len() {
+            0 => Ok(Node::Empty),
+            32 => Ok(Node::Hash(data.try_into().unwrap())),
+            _ => Err(MptError::InvalidNode),
+        };
+    }
+    match rlp.item_count().map_err(invalid)? {
+        2 => {
+            let encoded_path = rlp.at(0).map_err(invalid)?.data().map_err(invalid)?;
+            let flag = encoded_path.first().ok_or(MptError::InvalidNode)? >> 4;
+            let path: Vec<u8> = nibbles(encoded_path)
+                .into_iter()
+                .skip(if flag & 1 == 1 { 1 } else { 2 })
+                .collect();
+            match flag {
+                0 | 1 => Ok(Node::Extension {
+                    path,
+                    child: Box::new(decode(&rlp.at(1).map_err(invalid)?)?),
+                }),
+                2 | 3 => Ok(Node::Leaf {
+                    path,
+                    value: rlp
+                        .at(1)
+                        .map_err(invalid)?
+                        .data()
+                        .map_err(invalid)?
+                        .to_vec(),
+                }),
+                _ => Err(MptError::InvalidNode),
+            }
+        }
+        17 => {
+            let mut children: [Node; 16] = Default::default();
+            for (i, child) in children.iter_mut().enumerate() {
+                *child = decode(&rlp.at(i).map_err(invalid)?)?;
+            }
+            Ok(Node::Branch {
+                children: Box::new(children),
+            })
+        }
+        _ => Err(MptError::InvalidNode),
+    }
+}
+
+fn encode(node: &Node) -> Vec<u8> {
+    let stream = match node {
+        Node::Leaf { path, value } => {
+            let mut stream = RlpStream::new_list(2);
+            stream.append(&hex_prefix(path, true)).append(value);
+            stream
+        }
+        Node::Extension { path, child } => {
+            let mut stream = RlpStream::new_list(2);
+            stream.append(&hex_prefix(path, false));
+            append_reference(&mut stream, child);
+            stream
+        }
+        Node::Branch { children } => {
+            let mut stream = RlpStream::new_list(17);
+            for child in children.iter() {
+                append_reference(&mut stream, child);
+            }
+            stream.append_empty_data();
+            stream
+        }
+        Node::Empty | Node::Hash(_) => unreachable!("not an encoded node"),
+    };
+    stream.out().to_vec()
+}
+
+// Nodes shorter than 32 bytes are inlined in their parent, and the others are
+// referred to by their hash.
+fn append_reference(stream: &mut RlpStream, node: &Node) {
+    match node {
+        Node::Empty => {
+            stream.append_empty_data();
+        }
+        Node::Hash(hash) => {
+            stream.append(&hash.to_vec());
+        }
+        node => {
+            let encoded = encode(node);
+            if encoded.len() < 32 {
+                stream.append_raw(&encoded, 1);
+            } else {
+                stream.append(&keccak256(&encoded).to_vec());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(i: u64) -> [u8; 32] {
+        keccak256(i.to_be_bytes())
+    }
+
+    // Return all the hashed nodes of a trie whose nodes are all known.
+    fn nodes(node: &Node, db: &mut NodeDb) {
+        match node {
+            Node::Empty | Node::Leaf { .. } => {}
+            Node::Extension { child, .. } => nodes(child, db),
+            Node::Branch { children } => children.iter().for_each(|child| nodes(child, db)),
+            Node::Hash(_) => unreachable!("full trie"),
+        }
+        if !matches!(node, Node::Empty) {
+            let encoded = encode(node);
+            db.insert(keccak256(&encoded), encoded);
+        }
+    }
+
+    fn full_trie(keys: impl IntoIterator<Item = u64>) -> (Trie, NodeDb) {
+        let db = NodeDb::new();
+        let mut trie = Trie::new(empty_root());
+        for i in keys {
+            trie.insert(key(i), vec![i as u8 + 1; 40], &db).unwrap();
+        }
+        let mut db = NodeDb::new();
+        nodes(&trie.root, &mut db);
+        (trie, db)
+    }
+
+    #[test]
+    fn two_leaves_branch() {
+        let leaf = |key: [u8; 32], value: &[u8]| {
+            let mut stream = RlpStream::new_list(2);
+            stream
+                .append(&[vec![0x30 | (key[0] & 0x0f)], key[1..].to_vec()].concat())
+                .append(&value.to_vec());
+            stream.out().to_vec()
+        };
+        // Two keys in different children of the root branch
+        let a = key(1);
+        let j = (2..).find(|j| key(*j)[0] >> 4 != a[0] >> 4).unwrap();
+        let b = key(j);
+        let mut branch = RlpStream::new_list(17);
+        for nibble in 0..16 {
+            if nibble == a[0] >> 4 {
+                branch.append(&keccak256(leaf(a, &[2; 40])).to_vec());
+            } else if nibble == b[0] >> 4 {
+                branch.append(&keccak256(leaf(b, &[j as u8 + 1; 40])).to_vec());
+            } else {
+                branch.append_empty_data();
+            }
+        }
+        branch.append_empty_data();
+
+        let (trie, _) = full_trie([1, j]);
+        assert_eq!(trie.root(), keccak256(branch.out()));
+    }
+
+    #[test]
+    fn insertion_order() {
+        let (trie, _) = full_trie(0..20);
+        let (reversed, _) = full_trie((0..20).rev());
+        assert_eq!(trie.root(), reversed.root());
+    }
+
+    #[test]
+    fn delete() {
+        let (mut trie, db) = full_trie(0..20);
+        for i in 10..20 {
+            trie.insert(key(i), vec![], &db).unwrap();
+        }
+        assert_eq!(trie.root(), full_trie(0..10).0.root());
+
+        for i in 0..10 {
+            trie.insert(key(i), vec![], &db).unwrap();
+        }
+        assert_eq!(trie.root(), empty_root());
+    }
+
+    #[test]
+    fn partial_trie() {
+        let (mut full, db) = full_trie(0..20);
+        let mut partial = Trie::new(full.root());
+        for (i, value) in [(3, vec![0xff; 40]), (25, vec![1; 3]), (7, vec![])] {
+            full.insert(key(i), value.clone(), &db).unwrap();
+            partial.insert(key(i), value, &db).unwrap();
+            assert_eq!(full.root(), partial.root());
+        }
+    }
+
+    #[test]
+    fn prove_keys() {
+        let (trie, db) = full_trie(0..20);
+        let mut partial = Trie::new(trie.root());
+        let mut proof_db = NodeDb::new();
+        for i in [3, 25] {
+            let proof = trie.prove(key(i), &NodeDb::new()).unwrap();
+            assert_eq!(proof, partial.prove(key(i), &db).unwrap());
+            assert_eq!(keccak256(&proof[0]), trie.root());
+            proof_db.extend(proof.into_iter().map(|node| (keccak256(&node), node)));
+        }
+        // The proofs are enough to update their keys.
+        partial.insert(key(3), vec![1; 40], &proof_db).unwrap();
+        partial.insert(key(25), vec![2; 40], &proof_db).unwrap();
+        let (mut full, _) = full_trie(0..20);
+        full.insert(key(3), vec![1; 40], &db).unwrap();
+        full.insert(key(25), vec![2; 40], &db).unwrap();
+        assert_eq!(partial.root(), full.root());
+    }
+
+    #[test]
+    fn delete_collapsed_sibling() {
+        // Two keys in different children of the root branch, which is
+        // collapsed into the other key when one of them is deleted.
+        let j = (2..).find(|j| key(*j)[0] >> 4 != key(1)[0] >> 4).unwrap();
+        let (trie, db) = full_trie([1, j]);
+        let mut proof_db: NodeDb = trie
+            .prove(key(1), &db)
+            .unwrap()
+            .into_iter()
+            .map(|node| (keccak256(&node), node))
+            .collect();
+
+        let mut partial = Trie::new(trie.root());
+        assert!(matches!(
+            partial.insert(key(1), vec![], &proof_db),
+            Err(MptError::MissingNode(_))
+        ));
+
+        let sibling = trie.prove(key(j), &db).unwrap().pop().unwrap();
+        proof_db.insert(keccak256(&sibling), sibling);
+        let mut partial = Trie::new(trie.root());
+        partial.insert(key(1), vec![], &proof_db).unwrap();
+        assert_eq!(partial.root(), full_trie([j]).0.root());
+    }
+
+    #[test]
+    fn partial_trie_missing_node() {
+        let (trie, _) = full_trie(0..20);
+        let mut partial = Trie::new(trie.root());
+        assert!(matches!(
+            partial.insert(key(3), vec![1], &NodeDb::new()),
+            Err(MptError::MissingNode(_))
+        ));
+    }
+}
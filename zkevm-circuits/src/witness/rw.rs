@@ -265,6 +265,17 @@ impl Rw {
         }
     }
 
+    pub(crate) fn account_destructed_value_pair(&self) -> (bool, bool) {
+        match self {
+            Self::AccountDestructed {
+                is_destructed,
+                is_destructed_prev,
+                ..
+            } => (*is_destructed, *is_destructed_prev),
+            _ => unreachable!(),
+        }
+    }
+
     pub(crate) fn aux_pair(&self) -> (usize, Word) {
         match self {
             Self::AccountStorage {
@@ -591,7 +602,7 @@ impl From<&operation::OperationContainer> for RwMap {
                 .iter()
                 .map(|op| Rw::TxAccessListAccount {
                     rw_counter: op.rwc().into(),
-                    is_write: true,
+                    is_write: op.rw().is_write(),
                     tx_id: op.op().tx_id,
                     account_address: op.op().address,
                     is_warm: op.op().is_warm,
@@ -158,6 +158,10 @@ pub(super) fn tx_convert(tx: &circuit_input_builder::Transaction, id: usize) ->
                 is_static: call.is_static,
             })
             .collect(),
-        steps: tx.steps().iter().map(step_convert).collect(),
+        steps: tx
+            .steps()
+            .iter()
+            .map(|step| step_convert(step, tx.block_num))
+            .collect(),
     }
 }